@@ -0,0 +1,50 @@
+#[macro_use]
+extern crate criterion;
+extern crate content_inspector;
+
+use std::env;
+use std::fs;
+
+use content_inspector::{inspect_file, inspect_file_mmap};
+use criterion::Criterion;
+
+fn files_benchmark(c: &mut Criterion) {
+    // A directory of small files, with the fixtures from testdata (repeated)
+    let directory = env::temp_dir().join(format!("content_inspector_bench_{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    let testdata = concat!(env!("CARGO_MANIFEST_DIR"), "/testdata");
+    let mut paths = vec![];
+    let files = fs::read_dir(testdata)
+        .unwrap()
+        .map(|entry| entry.unwrap().path());
+    for file in files.filter(|path| path.is_file()) {
+        let content = fs::read(file).unwrap();
+        for _ in 0..10 {
+            let path = directory.join(paths.len().to_string());
+            fs::write(&path, &content).unwrap();
+            paths.push(path);
+        }
+    }
+
+    let mut group = c.benchmark_group("files");
+    group.bench_function("inspect_file", |b| {
+        b.iter(|| {
+            for path in &paths {
+                inspect_file(path).unwrap();
+            }
+        })
+    });
+    group.bench_function("inspect_file_mmap", |b| {
+        b.iter(|| {
+            for path in &paths {
+                inspect_file_mmap(path).unwrap();
+            }
+        })
+    });
+    group.finish();
+
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+criterion_group!(benches, files_benchmark);
+criterion_main!(benches);
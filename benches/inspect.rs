@@ -0,0 +1,265 @@
+#[macro_use]
+extern crate criterion;
+extern crate content_inspector;
+
+use std::io;
+
+use content_inspector::{
+    contains_binary, copy_and_inspect, inspect, Inspector, InspectorBuilder, Strictness,
+};
+use criterion::{black_box, Criterion, Throughput};
+
+fn inspect_benchmark(c: &mut Criterion) {
+    let text = include_bytes!("../testdata/text_UTF-8.txt");
+    let binary = include_bytes!("../testdata/test.png");
+
+    let default_inspector = Inspector::default();
+    let custom_inspector = (0..40)
+        .fold(Inspector::new(), |builder, i| {
+            builder.magic_number(0, format!("MAGIC{:02}", i).as_bytes())
+        })
+        .detect_control_characters(true)
+        .build()
+        .unwrap();
+    let byte_level_inspector = Inspector::new()
+        .detect_control_characters(true)
+        .min_printable_ratio(0.5)
+        .detect_ascii(true)
+        .build()
+        .unwrap();
+
+    for &(name, buffer) in &[("text", &text[..]), ("binary", &binary[..])] {
+        let mut group = c.benchmark_group(name);
+        group.bench_function("inspect", |b| b.iter(|| inspect(black_box(buffer))));
+        group.bench_function("Inspector::inspect (default)", |b| {
+            b.iter(|| default_inspector.inspect(black_box(buffer)))
+        });
+        group.bench_function("Inspector::inspect (40 magic numbers)", |b| {
+            b.iter(|| custom_inspector.inspect(black_box(buffer)))
+        });
+        group.bench_function("Inspector::inspect (all byte-level heuristics)", |b| {
+            b.iter(|| byte_level_inspector.inspect(black_box(buffer)))
+        });
+        group.finish();
+    }
+}
+
+/// A writer that discards everything, without the specializations of `io::copy` for sinks.
+struct Discard;
+
+impl io::Write for Discard {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        Ok(black_box(buf).len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A reader without the specializations of `io::copy` for slices.
+struct Source<'a>(&'a [u8]);
+
+impl io::Read for Source<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+fn copy_benchmark(c: &mut Criterion) {
+    let text = include_bytes!("../testdata/text_UTF-8.txt");
+    let content: Vec<u8> = text.iter().cycle().take(1 << 20).cloned().collect();
+
+    let mut group = c.benchmark_group("copy");
+    group.throughput(Throughput::Bytes(content.len() as u64));
+    group.bench_function("io::copy", |b| {
+        b.iter(|| io::copy(&mut Source(black_box(&content[..])), &mut Discard).unwrap())
+    });
+    group.bench_function("copy_and_inspect", |b| {
+        b.iter(|| copy_and_inspect(&mut Source(black_box(&content[..])), &mut Discard).unwrap())
+    });
+    group.finish();
+}
+
+type Configure = fn(InspectorBuilder) -> InspectorBuilder;
+
+/// The byte-level heuristics and the UTF-8 validation on large windows, where the scan dominates.
+fn byte_level_benchmark(c: &mut Criterion) {
+    let inputs: &[(&str, &[u8])] = &[
+        ("UTF-8 text", include_bytes!("../testdata/text_UTF-8.txt")),
+        ("ASCII source code", include_bytes!("inspect.rs")),
+    ];
+    let settings: &[(&str, Configure)] = &[
+        ("NULL bytes", |builder| builder),
+        ("control characters", |builder| {
+            builder.detect_control_characters(true)
+        }),
+        ("control characters, printable ratio and ASCII", |builder| {
+            builder
+                .detect_control_characters(true)
+                .min_printable_ratio(0.5)
+                .detect_ascii(true)
+        }),
+        ("strict with printable ratio", |builder| {
+            builder
+                .strictness(Strictness::Strict)
+                .min_printable_ratio(0.5)
+        }),
+    ];
+
+    for &(input, text) in inputs {
+        for &size in &[4 * 1024, 64 * 1024] {
+            let content: Vec<u8> = text.iter().cycle().take(size).cloned().collect();
+            let mut group = c.benchmark_group(format!(
+                "byte-level heuristics ({}, {} KiB)",
+                input,
+                size / 1024
+            ));
+            group.throughput(Throughput::Bytes(size as u64));
+            for &(name, configure) in settings {
+                let inspector = configure(Inspector::new().max_scan_size(size))
+                    .build()
+                    .unwrap();
+                group.bench_function(name, |b| b.iter(|| inspector.inspect(black_box(&content))));
+            }
+            group.finish();
+        }
+    }
+}
+
+/// Scanning a whole stream from memory, which should approach the memory bandwidth.
+fn contains_binary_benchmark(c: &mut Criterion) {
+    let text = include_bytes!("../testdata/text_UTF-8.txt");
+    let content: Vec<u8> = text.iter().cycle().take(64 << 20).cloned().collect();
+
+    let mut group = c.benchmark_group("contains_binary");
+    group.throughput(Throughput::Bytes(content.len() as u64));
+    group.sample_size(20);
+    group.bench_function("NULL bytes", |b| {
+        b.iter(|| contains_binary(black_box(&content[..]), false).unwrap())
+    });
+    group.bench_function("control characters", |b| {
+        b.iter(|| contains_binary(black_box(&content[..]), true).unwrap())
+    });
+    group.finish();
+}
+
+/// The search for NULL bytes in a large window without any, which uses the `memchr` crate (or the
+/// portable fallback, when built with `--no-default-features --features std`).
+fn null_byte_search_benchmark(c: &mut Criterion) {
+    let text = include_bytes!("../testdata/text_UTF-8.txt");
+    let mut group = c.benchmark_group("NULL-byte search");
+    for &size in &[1 << 10, 64 << 10, 1 << 20] {
+        let content: Vec<u8> = text.iter().cycle().take(size).cloned().collect();
+        let inspector = Inspector::new()
+            .detect_bom(false)
+            .detect_magic_numbers(false)
+            .null_scan_limit(size)
+            .build()
+            .unwrap();
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_function(format!("{} KiB", size >> 10), |b| {
+            b.iter(|| inspector.inspect(black_box(&content)))
+        });
+    }
+    group.finish();
+}
+
+/// The same text at the start of buffers of very different sizes, which should take the same time
+/// since only the start of a buffer is examined. With the `bench-internals` feature, it is checked
+/// first that the same number of bytes is read from all of them.
+fn buffer_size_benchmark(c: &mut Criterion) {
+    let text = include_bytes!("../testdata/text_UTF-8.txt");
+    let mut group = c.benchmark_group("buffer size");
+    for &(name, size) in &[
+        ("1 KiB", 1 << 10),
+        ("1 MiB", 1 << 20),
+        ("100 MiB", 100 << 20),
+    ] {
+        let buffer: Vec<u8> = text.iter().cycle().take(size).cloned().collect();
+        #[cfg(feature = "bench-internals")]
+        assert_eq!(
+            Inspector::default().bytes_read(&buffer[..1 << 10]),
+            Inspector::default().bytes_read(&buffer)
+        );
+        group.bench_function(name, |b| b.iter(|| inspect(black_box(&buffer))));
+    }
+    group.finish();
+}
+
+/// The byte order mark check alone (without magic numbers and with a NULL-byte scan of only one
+/// byte), for buffers that start with ASCII letters and with byte order marks.
+fn byte_order_mark_benchmark(c: &mut Criterion) {
+    let inspector = Inspector::new()
+        .detect_magic_numbers(false)
+        .null_scan_limit(1)
+        .build()
+        .unwrap();
+    let ascii: Vec<Vec<u8>> = (0..1000_u32)
+        .map(|i| format!("{}ext {}", (b'a' + (i % 26) as u8) as char, i).into_bytes())
+        .collect();
+    let boms: Vec<Vec<u8>> = (0..1000)
+        .map(|i| {
+            let bom = [&b"\xEF\xBB\xBF"[..], b"\xFF\xFE", b"\xFE\xFF"][i % 3];
+            [bom, b"text"].concat()
+        })
+        .collect();
+
+    let mut group = c.benchmark_group("byte order mark");
+    for (name, buffers) in &[("ASCII", &ascii), ("byte order marks", &boms)] {
+        group.throughput(Throughput::Elements(buffers.len() as u64));
+        group.bench_function(*name, |b| {
+            b.iter(|| {
+                for buffer in buffers.iter() {
+                    black_box(inspector.inspect(black_box(buffer)));
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+/// Many short strings, like the cell values of a table.
+fn short_strings_benchmark(c: &mut Criterion) {
+    let words = [
+        "id",
+        "Zürich",
+        "true",
+        "N/A",
+        "2024-01-31",
+        "foo@example.com",
+        "€",
+    ];
+    let cells: Vec<Vec<u8>> = (0..1000_usize)
+        .map(|i| match i % 4 {
+            0 => i.to_string().into_bytes(),
+            1 => format!("{:.3}", i as f64 / 7.0).into_bytes(),
+            2 => words[i % words.len()].as_bytes().to_vec(),
+            _ => words[i % words.len()].repeat(1 + i % 9).into_bytes(),
+        })
+        .collect();
+
+    let mut group = c.benchmark_group("short strings");
+    group.throughput(Throughput::Elements(cells.len() as u64));
+    group.bench_function("inspect", |b| {
+        b.iter(|| {
+            for cell in &cells {
+                black_box(inspect(black_box(cell)));
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    inspect_benchmark,
+    copy_benchmark,
+    contains_binary_benchmark,
+    null_byte_search_benchmark,
+    byte_level_benchmark,
+    buffer_size_benchmark,
+    byte_order_mark_benchmark,
+    short_strings_benchmark
+);
+criterion_main!(benches);
@@ -0,0 +1,78 @@
+#[macro_use]
+extern crate criterion;
+extern crate content_inspector;
+extern crate rayon;
+
+use std::fs;
+
+use content_inspector::{inspect, inspect_many, Inspector, Strictness};
+use criterion::{BenchmarkId, Criterion, Throughput};
+use rayon::ThreadPoolBuilder;
+
+fn many_benchmark(c: &mut Criterion) {
+    let testdata = concat!(env!("CARGO_MANIFEST_DIR"), "/testdata");
+    let files: Vec<Vec<u8>> = fs::read_dir(testdata)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.is_file())
+        .map(|path| fs::read(path).unwrap())
+        .collect();
+    let buffers: Vec<&[u8]> = (0..100_000).map(|i| &files[i % files.len()][..]).collect();
+
+    let mut group = c.benchmark_group("many");
+    group.bench_function("sequential", |b| {
+        b.iter(|| {
+            buffers
+                .iter()
+                .map(|buffer| inspect(buffer))
+                .collect::<Vec<_>>()
+        })
+    });
+
+    let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let mut threads = 1;
+    while threads <= cores {
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap();
+        group.bench_with_input(
+            BenchmarkId::new("inspect_many", threads),
+            &threads,
+            |b, _| b.iter(|| pool.install(|| inspect_many(buffers.clone()))),
+        );
+        threads *= 2;
+    }
+    group.finish();
+}
+
+/// A single huge buffer with `scan_all`, whose window is scanned in parallel chunks.
+fn scan_all_benchmark(c: &mut Criterion) {
+    let text = include_bytes!("../testdata/text_UTF-8.txt");
+    let buffer: Vec<u8> = text.iter().cycle().take(512 << 20).cloned().collect();
+    let inspector = Inspector::new()
+        .strictness(Strictness::Paranoid)
+        .detect_control_characters(true)
+        .build()
+        .unwrap();
+
+    let mut group = c.benchmark_group("scan_all (512 MiB)");
+    group.throughput(Throughput::Bytes(buffer.len() as u64));
+    group.sample_size(10);
+    let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let mut threads = 1;
+    while threads <= cores {
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap();
+        group.bench_with_input(BenchmarkId::new("threads", threads), &threads, |b, _| {
+            b.iter(|| pool.install(|| inspector.inspect(&buffer)))
+        });
+        threads *= 2;
+    }
+    group.finish();
+}
+
+criterion_group!(benches, many_benchmark, scan_all_benchmark);
+criterion_main!(benches);
@@ -1,36 +1,1978 @@
 extern crate content_inspector;
 
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
 use std::env;
-use std::fs::File;
-use std::io::{Error, Read};
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{self, BufRead, Error, IsTerminal, Read, Write};
 use std::path::Path;
-use std::process::exit;
+use std::process::{self, exit};
+use std::sync::{mpsc, Mutex};
+use std::thread;
 
-const MAX_PEEK_SIZE: usize = 1024;
+use content_inspector::{
+    recommended_peek_size, BomPolicy, ByteClass, ContentType, InspectionReport, Inspector,
+    Mismatch, Preset, Reason, Strictness, Summary,
+};
 
-fn main() -> Result<(), Error> {
-    let mut args = env::args();
+/// The number of bytes that are read and inspected without `--bytes` or `--all`.
+const DEFAULT_PEEK_SIZE: usize = recommended_peek_size();
 
-    if args.len() < 2 {
-        eprintln!("USAGE: inspect FILE [FILE...]");
-        exit(1);
+/// The number of bytes that are read with `--all`, unless `--force` is given.
+const ALL_LIMIT: usize = 64 * 1024 * 1024;
+
+/// The number of paths of every type that are kept for the outliers of `--summary`.
+const OUTLIER_LIMIT: usize = 100;
+
+/// The width of the longest bar of `--histogram`.
+const HISTOGRAM_WIDTH: usize = 40;
+
+const USAGE: &str = r"USAGE: inspect [OPTIONS] [FILE...]
+
+Reads from standard input if there are no files, or if FILE is '-'.
+
+OPTIONS:
+    --json            Print one JSON object per file
+    --pretty          Print a JSON array (with --json)
+    --mime            Detect and print the encodings like 'file --mime-encoding'
+    --check TYPE      Only print the files that are not of the type (like 'UTF-8' or
+                      'us-ascii'), with the reason
+    --allow-bom       With --check UTF-8, also accept a byte order mark
+    --no-bom          With --check UTF-8, do not accept a byte order mark (the default)
+    --convert TYPE    Convert text files (UTF-8, UTF-16, UTF-32 or Latin-1) to the type (like
+                      'UTF-8' or 'UTF-16LE') and write them to standard output; binary files
+                      are skipped
+    --add-bom         With --convert UTF-8, start the output with a byte order mark
+    --in-place        With --convert, replace the files instead of writing to standard output
+    --strip-bom       Remove the UTF-8 byte order mark of the files (in place), files without
+                      one are not written; binary files are refused
+    --transcode       With --strip-bom, convert UTF-16 and UTF-32 files with a byte order mark
+                      to UTF-8 without one
+    --dry-run         With --strip-bom, only print the files that would be changed
+    --verify          Inspect every file twice, in the window and as a whole (scanned in chunks),
+                      and only report the files of which the types differ, with the offset of
+                      the NULL byte after the window (one JSON object per file with --json)
+    --records[=DELIM] Read records from standard input as they arrive, split at DELIM (a newline
+                      by default, '\0' or '\t' or another byte, or a NULL byte with -z), and
+                      print 'SEQ\tTYPE' for every record; only the first 1024 bytes (or the
+                      --bytes) of a record are inspected
+    --textconv        Write the text of the single FILE to standard output as a textconv
+                      helper of git: UTF-16 (even without a byte order mark) and UTF-32 are
+                      converted to UTF-8, other text is unchanged, and binary content is an
+                      error (exit status 1)
+    -0, -z, --print0  Print 'PATH\0TYPE\0' records, with the exact bytes of the paths
+    -q, --quiet       Print no results, only set the exit status
+    --text-only       Only report text files (exit status 0 if there are any)
+    --binary-only     Only report binary files (exit status 0 if there are any)
+    --list-text       Only print the paths of text files, one per line ('PATH\0' with -0)
+    --list-binary     Only print the paths of binary files, one per line ('PATH\0' with -0)
+    --format TEMPLATE Print every file with the template, like '{path}\t{type}\n' (see below)
+    --summary         Print the number of files and bytes of every type instead of the files,
+                      and the text files in another encoding than most (added to --json)
+    --histogram       With --summary, add a bar of the number of files of every type
+    -r, --recursive   Inspect the files in directories (needs the 'walk' feature)
+    --dereference     Follow symbolic links, also in the directories of -r (by default, only
+                      the ones on the command line are followed)
+    --no-dereference  Do not follow symbolic links, not even the ones on the command line
+    --no-ignore       Do not skip files excluded by .gitignore or .ignore, or hidden files
+    -w, --watch       After the files are inspected, watch them (and the directories of -r)
+                      and print 'PATH: TYPE' or 'PATH: removed' for every change (needs the
+                      'watch' feature)
+    --bytes N         Read and inspect the first N bytes of every file (the default is 1024)
+    --all             Read and inspect whole files, up to 64 MiB unless --force is given
+    --force           With --all, read files of any size
+    -v, --verbose     Print the detailed report of every file (more fields with --json), and
+                      note the paths that are skipped on standard error
+    --threads N       Inspect N files at once (the default is the number of CPUs)
+    --unordered       Print the results as soon as they are ready, not in the order of the files
+    --color WHEN      Print an aligned table with colors and a summary line: 'auto' (the
+                      default, on a terminal unless NO_COLOR is set), 'always' or 'never'
+
+TEMPLATES:
+    {path}, {type}, {mime}, {is_text}, {reason}, {binary_format}, {size}, {bom_length},
+    {null_bytes}, {first_null_offset}, {bytes_examined} and {line_endings} are replaced by the
+    values of the file, which are empty if they do not apply (like the size of standard input).
+    '{{' and '}}' are braces, and '\t', '\n', '\0' and '\\' are escapes.
+
+EXIT STATUS:
+    0 if all files are text (or if a file matches --text-only or --binary-only, if all files
+    pass the --check, or if no binary file is skipped by --convert), 1 if not, and 2 if a path
+    is not a regular file that can be read (or converted)";
+
+/// The options on the command line.
+struct Options {
+    json: bool,
+    pretty: bool,
+    print0: bool,
+    quiet: bool,
+    /// Only text files (`Some(true)`) or only binary files (`Some(false)`) are selected.
+    filter: Option<bool>,
+    /// Only the paths of the selected files are printed (`--list-text` and `--list-binary`).
+    list: bool,
+    /// The template of `--format`.
+    format: Option<Template>,
+    /// The files are aggregated per type (`--summary`), with bars if `histogram`.
+    summary: bool,
+    histogram: bool,
+    recursive: bool,
+    no_ignore: bool,
+    watch: bool,
+    /// Symbolic links are followed everywhere (`Some(true)`), nowhere (`Some(false)`), or only on
+    /// the command line (`None`).
+    dereference: Option<bool>,
+    verbose: bool,
+    threads: usize,
+    unordered: bool,
+    /// The results are printed as an aligned table with a summary line, with colors if `color`.
+    table: bool,
+    color: bool,
+    mime: bool,
+    /// The expected type of content with `--check`.
+    check: Option<ContentType>,
+    allow_bom: bool,
+    /// The number of bytes that are read and inspected, or `None` for whole files (`--all`).
+    peek_size: Option<usize>,
+    force: bool,
+    /// The target type with `--convert`.
+    convert: Option<ContentType>,
+    add_bom: bool,
+    in_place: bool,
+    textconv: bool,
+    verify: bool,
+    /// The byte order marks are removed (`--strip-bom`), also by converting UTF-16 and UTF-32
+    /// with `transcode`, or only listed with `dry_run`.
+    strip_bom: bool,
+    transcode: bool,
+    dry_run: bool,
+    /// The delimiter of the records of `--records`.
+    records: Option<u8>,
+    inspector: Inspector,
+}
+
+/// A file to inspect, or a path that could not be walked with the error.
+type Input = Result<OsString, (OsString, String)>;
+
+/// The name of standard input in the output.
+const STDIN: &str = "<stdin>";
+
+fn is_stdin(filename: &OsString) -> bool {
+    filename == "-"
+}
+
+/// The name of the file in the output.
+fn label(filename: &OsString) -> String {
+    if is_stdin(filename) {
+        STDIN.to_string()
+    } else {
+        filename.to_string_lossy().into_owned()
+    }
+}
+
+/// The exact bytes of the path (on Unix, elsewhere it is converted lossily like in `label`).
+fn path_bytes(filename: &OsString) -> Cow<'_, [u8]> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        if !is_stdin(filename) {
+            return Cow::Borrowed(filename.as_bytes());
+        }
+    }
+    Cow::Owned(label(filename).into_bytes())
+}
+
+/// The fields of a JSON object, with values that are already encoded.
+struct Object(Vec<(&'static str, String)>);
+
+impl Object {
+    fn new(path: &OsString) -> Object {
+        let mut object = Object(vec![("path", string(&label(path)))]);
+        if path.to_str().is_none() {
+            object.0.push(("path_is_lossy", "true".to_string()));
+        }
+        object
+    }
+
+    fn field(&mut self, key: &'static str, value: String) {
+        self.0.push((key, value));
+    }
+
+    /// The object on one line, or on one line per field (indented as an element of an array).
+    fn render(&self, pretty: bool) -> String {
+        let fields = self
+            .0
+            .iter()
+            .map(|(key, value)| format!("{}: {}", string(key), value));
+        if pretty {
+            let fields: Vec<_> = fields.map(|field| format!("    {}", field)).collect();
+            format!("  {{\n{}\n  }}", fields.join(",\n"))
+        } else {
+            format!("{{{}}}", fields.collect::<Vec<_>>().join(", "))
+        }
+    }
+}
+
+/// A JSON string.
+fn string(value: &str) -> String {
+    let mut encoded = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => encoded.push_str("\\\""),
+            '\\' => encoded.push_str("\\\\"),
+            '\n' => encoded.push_str("\\n"),
+            '\r' => encoded.push_str("\\r"),
+            '\t' => encoded.push_str("\\t"),
+            c if (c as u32) < 0x20 => encoded.push_str(&format!("\\u{:04x}", c as u32)),
+            c => encoded.push(c),
+        }
+    }
+    encoded.push('"');
+    encoded
+}
+
+/// A JSON number, or `null`.
+fn offset(value: Option<usize>) -> String {
+    value.map_or("null".to_string(), |value| value.to_string())
+}
+
+/// The maximum number of bytes that are read from every file, or `None` if there is none.
+fn peek_limit(options: &Options) -> Option<usize> {
+    match options.peek_size {
+        None if options.force => None,
+        None => Some(ALL_LIMIT),
+        peek_size => peek_size,
+    }
+}
+
+/// Read the start of the file, or of standard input (all of it with `--all`). The rest of the
+/// stream is not read, so that large pipes are not consumed. Without `--force`, a warning is
+/// printed if `--all` stops at the limit.
+fn read_peek(filename: &OsString, options: &Options) -> Result<Vec<u8>, Error> {
+    let mut buffer: Vec<u8> = vec![];
+    let reader: Box<dyn Read> = if is_stdin(filename) {
+        Box::new(io::stdin().lock())
+    } else {
+        Box::new(File::open(filename)?)
+    };
+
+    // One more byte than the limit of `--all` tells whether the file is larger
+    let limit = match (options.peek_size, peek_limit(options)) {
+        (None, Some(limit)) => limit as u64 + 1,
+        (_, limit) => limit.map_or(u64::MAX, |limit| limit as u64),
+    };
+    reader.take(limit).read_to_end(&mut buffer)?;
+    if options.peek_size.is_none() && !options.force && buffer.len() > ALL_LIMIT {
+        buffer.truncate(ALL_LIMIT);
+        eprintln!(
+            "{}: larger than {} MiB, only the start is inspected (use --force to read all of it)",
+            label(filename),
+            ALL_LIMIT >> 20
+        );
+    }
+    Ok(buffer)
+}
+
+/// Statistics of the bytes in the window (the start of the file that is read), and the line
+/// endings of text.
+struct Stats {
+    /// The size of the file (which is not known for standard input).
+    size: Option<u64>,
+    window: usize,
+    null_bytes: usize,
+    first_null_offset: Option<usize>,
+    /// See `ByteClass::of`.
+    control_bytes: usize,
+    high_bit_bytes: usize,
+    /// The number of LF, CRLF and CR line endings, for text.
+    line_endings: Option<[usize; 3]>,
+}
+
+impl Stats {
+    fn new(buffer: &[u8], content_type: ContentType, size: Option<u64>) -> Stats {
+        let count = |class| {
+            buffer
+                .iter()
+                .filter(|&&byte| ByteClass::of(byte) == class)
+                .count()
+        };
+        let line_endings = Some(content_type)
+            .filter(|c| c.is_text())
+            .map(|content_type| {
+                let text = content_inspector::decode_lossy(buffer, content_type);
+                let crlf = text.matches("\r\n").count();
+                [
+                    text.matches('\n').count() - crlf,
+                    crlf,
+                    text.matches('\r').count() - crlf,
+                ]
+            });
+        Stats {
+            size,
+            window: buffer.len(),
+            null_bytes: count(ByteClass::Null),
+            first_null_offset: buffer.iter().position(|&byte| byte == 0),
+            control_bytes: count(ByteClass::Control),
+            high_bit_bytes: buffer.iter().filter(|&&byte| byte >= 0x80).count(),
+            line_endings,
+        }
+    }
+
+    /// The style of the line endings, like `LF` or `mixed`.
+    fn line_ending_style(&self) -> Option<&'static str> {
+        let counts = self.line_endings?;
+        let styles = ["LF", "CRLF", "CR"];
+        let mut used = (0..3).filter(|&i| counts[i] > 0);
+        Some(match (used.next(), used.next()) {
+            (None, _) => "none",
+            (Some(i), None) => styles[i],
+            _ => "mixed",
+        })
+    }
+}
+
+/// Check the type of content of the buffer with `Inspector::check_lenient`, so that ASCII and
+/// empty files (which are UTF-8) pass as UTF-8. With `--allow-bom`, UTF-8 with a byte order mark
+/// passes as UTF-8, too.
+fn check(
+    inspector: &Inspector,
+    buffer: &[u8],
+    expected: ContentType,
+    options: &Options,
+) -> Result<(), Mismatch> {
+    match inspector.check_lenient(buffer, expected) {
+        Err(ref mismatch)
+            if options.allow_bom
+                && expected == ContentType::UTF_8
+                && mismatch.detected() == ContentType::UTF_8_BOM =>
+        {
+            Ok(())
+        }
+        result => result,
+    }
+}
+
+/// The type of content with the given name (like `UTF-8` or `binary`) or `file --mime-encoding`
+/// label (like `us-ascii`), in any case.
+fn parse_type(name: &str) -> Option<ContentType> {
+    use ContentType::*;
+
+    [
+        BINARY, UTF_8, UTF_8_BOM, UTF_16LE, UTF_16BE, UTF_32LE, UTF_32BE, LATIN_1, ASCII,
+    ]
+    .iter()
+    .copied()
+    .find(|&content_type| {
+        name.eq_ignore_ascii_case(&content_type.to_string())
+            || (content_type != UTF_8_BOM
+                && name.eq_ignore_ascii_case(content_type.mime_encoding()))
+    })
+}
+
+/// The name of the type of content, or the label of `file --mime-encoding` with `--mime`.
+fn type_name(content_type: ContentType, options: &Options) -> String {
+    if options.mime {
+        content_type.mime_encoding().to_string()
+    } else {
+        content_type.to_string()
+    }
+}
+
+/// The metadata of the path on the command line, of the link itself with `--no-dereference`.
+fn metadata(filename: &OsString, options: &Options) -> io::Result<fs::Metadata> {
+    if options.dereference == Some(false) {
+        fs::symlink_metadata(filename)
+    } else {
+        fs::metadata(filename)
+    }
+}
+
+/// The message for an error of opening or reading a file, which is shorter than the one of the
+/// error itself for the common kinds.
+fn describe(error: &Error) -> String {
+    match error.kind() {
+        io::ErrorKind::NotFound => "not found".to_string(),
+        io::ErrorKind::PermissionDenied => "permission denied".to_string(),
+        _ => error.to_string(),
+    }
+}
+
+/// Why the path on the command line can not be inspected: it does not exist, or it is not a
+/// regular file. Returns the size of the file otherwise.
+fn check_regular_file(filename: &OsString, options: &Options) -> Result<u64, String> {
+    let metadata = match metadata(filename, options) {
+        Ok(metadata) => metadata,
+        // Unlike a missing file, a dangling link has metadata of its own
+        Err(ref error)
+            if error.kind() == io::ErrorKind::NotFound
+                && fs::symlink_metadata(filename).is_ok() =>
+        {
+            return Err("is a dangling symbolic link".to_string());
+        }
+        Err(error) => return Err(describe(&error)),
+    };
+
+    let file_type = metadata.file_type();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+        if file_type.is_socket() {
+            return Err("is a socket".to_string());
+        } else if file_type.is_fifo() {
+            return Err("is a FIFO".to_string());
+        } else if file_type.is_block_device() || file_type.is_char_device() {
+            return Err("is a device".to_string());
+        }
+    }
+    if file_type.is_file() {
+        Ok(metadata.len())
+    } else if file_type.is_dir() {
+        Err("is a directory (use -r to inspect its files)".to_string())
+    } else if file_type.is_symlink() {
+        Err("is a symbolic link (not followed with --no-dereference)".to_string())
+    } else {
+        Err("is not a regular file".to_string())
+    }
+}
+
+/// The result for one path.
+enum Outcome {
+    /// The report, and the mismatch with `--check`.
+    Inspected(Box<InspectionReport>, Stats, Option<Mismatch>),
+    /// The path is not a regular file, or it could not be read, or the directory could not be
+    /// walked.
+    Failed(String),
+}
+
+fn inspect_input(input: Input, options: &Options) -> (OsString, Outcome) {
+    let inspector = &options.inspector;
+    let filename = match input {
+        Ok(filename) => filename,
+        Err((path, error)) => return (path, Outcome::Failed(error)),
+    };
+    let checked = if is_stdin(&filename) {
+        Ok(None)
+    } else {
+        check_regular_file(&filename, options).map(Some)
+    };
+    let outcome = match checked {
+        Ok(size) => match read_peek(&filename, options) {
+            Ok(buffer) => {
+                let report = inspector.inspect_detailed(&buffer);
+                let stats = Stats::new(&buffer, report.content_type, size);
+                let mismatch = options
+                    .check
+                    .and_then(|expected| check(inspector, &buffer, expected, options).err());
+                Outcome::Inspected(Box::new(report), stats, mismatch)
+            }
+            Err(error) => Outcome::Failed(describe(&error)),
+        },
+        Err(error) => Outcome::Failed(error),
+    };
+    (filename, outcome)
+}
+
+/// The reason of the report, like `NullBytes` or `Heuristic(name)`.
+fn reason_name(reason: Reason) -> String {
+    match reason {
+        Reason::Heuristic(name) => format!("Heuristic({})", name),
+        reason => format!("{:?}", reason),
+    }
+}
+
+/// A value in a `--format` template.
+#[derive(Clone, Copy)]
+enum Field {
+    Path,
+    Type,
+    Mime,
+    IsText,
+    Reason,
+    BinaryFormat,
+    Size,
+    BomLength,
+    NullBytes,
+    FirstNullOffset,
+    BytesExamined,
+    LineEndings,
+}
+
+/// The names of the placeholders.
+const FIELDS: &[(&str, Field)] = &[
+    ("path", Field::Path),
+    ("type", Field::Type),
+    ("mime", Field::Mime),
+    ("is_text", Field::IsText),
+    ("reason", Field::Reason),
+    ("binary_format", Field::BinaryFormat),
+    ("size", Field::Size),
+    ("bom_length", Field::BomLength),
+    ("null_bytes", Field::NullBytes),
+    ("first_null_offset", Field::FirstNullOffset),
+    ("bytes_examined", Field::BytesExamined),
+    ("line_endings", Field::LineEndings),
+];
+
+impl Field {
+    /// The value for the file, which is empty if it does not apply.
+    fn value(
+        self,
+        filename: &OsString,
+        report: &InspectionReport,
+        stats: &Stats,
+        options: &Options,
+    ) -> String {
+        let or_empty = |value: Option<String>| value.unwrap_or_default();
+        match self {
+            Field::Path => label(filename),
+            Field::Type => type_name(report.content_type, options),
+            Field::Mime => report.content_type.mime_encoding().to_string(),
+            Field::IsText => report.content_type.is_text().to_string(),
+            Field::Reason => reason_name(report.reason),
+            Field::BinaryFormat => or_empty(report.binary_format.map(|f| format!("{:?}", f))),
+            Field::Size => or_empty(stats.size.map(|size| size.to_string())),
+            Field::BomLength => report.bom_length.to_string(),
+            Field::NullBytes => report.null_bytes.to_string(),
+            Field::FirstNullOffset => or_empty(report.first_null_offset.map(|o| o.to_string())),
+            Field::BytesExamined => report.bytes_examined.to_string(),
+            Field::LineEndings => or_empty(stats.line_ending_style().map(str::to_string)),
+        }
+    }
+}
+
+/// A part of a template.
+#[derive(Clone)]
+enum Piece {
+    Text(String),
+    Field(Field),
+}
+
+/// The output for every file, like `{path}: {type}\n`: `{name}` placeholders (see `FIELDS`),
+/// `{{` and `}}` for braces, and the escapes `\t`, `\n`, `\0` and `\\`.
+#[derive(Clone)]
+struct Template(Vec<Piece>);
+
+impl Template {
+    fn parse(template: &str) -> Result<Template, String> {
+        let mut pieces = vec![];
+        let mut text = String::new();
+        let mut chars = template.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => text.push(match chars.next() {
+                    Some('t') => '\t',
+                    Some('n') => '\n',
+                    Some('0') => '\0',
+                    Some('\\') => '\\',
+                    Some(c) => return Err(format!("unknown escape '\\{}'", c)),
+                    None => return Err("the template ends with a backslash".to_string()),
+                }),
+                '{' | '}' if chars.as_str().starts_with(c) => {
+                    chars.next();
+                    text.push(c);
+                }
+                '{' => {
+                    let rest = chars.as_str();
+                    let end = rest
+                        .find('}')
+                        .ok_or("unclosed '{' (use '{{' for a brace)")?;
+                    let name = &rest[..end];
+                    let field =
+                        FIELDS
+                            .iter()
+                            .find(|(known, _)| *known == name)
+                            .ok_or_else(|| {
+                                let known: Vec<_> = FIELDS
+                                    .iter()
+                                    .map(|(name, _)| format!("{{{}}}", name))
+                                    .collect();
+                                format!(
+                                    "unknown placeholder '{{{}}}', the placeholders are {}",
+                                    name,
+                                    known.join(", ")
+                                )
+                            })?;
+                    if !text.is_empty() {
+                        pieces.push(Piece::Text(std::mem::take(&mut text)));
+                    }
+                    pieces.push(Piece::Field(field.1));
+                    chars = rest[end + 1..].chars();
+                }
+                '}' => return Err("unmatched '}' (use '}}' for a brace)".to_string()),
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            pieces.push(Piece::Text(text));
+        }
+        Ok(Template(pieces))
+    }
+
+    /// The output for the file. With `-0`, the path is rendered with its exact bytes.
+    fn render(
+        &self,
+        filename: &OsString,
+        report: &InspectionReport,
+        stats: &Stats,
+        options: &Options,
+    ) -> Vec<u8> {
+        let mut output = vec![];
+        for piece in &self.0 {
+            match *piece {
+                Piece::Text(ref text) => output.extend_from_slice(text.as_bytes()),
+                Piece::Field(Field::Path) if options.print0 => {
+                    output.extend_from_slice(&path_bytes(filename))
+                }
+                Piece::Field(field) => output
+                    .extend_from_slice(field.value(filename, report, stats, options).as_bytes()),
+            }
+        }
+        output
+    }
+}
+
+/// The JSON object for a path: the type of its content and the details of the report, or the
+/// error.
+fn json_object(filename: &OsString, outcome: &Outcome, options: &Options) -> Object {
+    let mut object = Object::new(filename);
+    let (report, stats) = match *outcome {
+        Outcome::Inspected(ref report, ref stats, _) => (report, stats),
+        Outcome::Failed(ref error) => {
+            object.field("error", string(error));
+            return object;
+        }
+    };
+
+    let binary_format = report.binary_format.map_or("null".to_string(), |format| {
+        string(&format!("{:?}", format))
+    });
+
+    object.field("type", string(&report.content_type.to_string()));
+    object.field("is_text", report.content_type.is_text().to_string());
+    if options.mime {
+        object.field("mime_encoding", string(report.content_type.mime_encoding()));
+    }
+    object.field("reason", string(&reason_name(report.reason)));
+    object.field("binary_format", binary_format);
+    object.field("null_bytes", report.null_bytes.to_string());
+    object.field("first_null_offset", offset(report.first_null_offset));
+    object.field("bom_length", report.bom_length.to_string());
+    object.field("invalid_utf8_offset", offset(report.invalid_utf8_offset));
+    object.field("bytes_examined", report.bytes_examined.to_string());
+    object.field("peek_size", offset(peek_limit(options)));
+    if options.verbose {
+        let style = stats.line_ending_style();
+        object.field("line_endings", style.map_or("null".to_string(), string));
+        let counts = stats
+            .line_endings
+            .map_or("null".to_string(), |[lf, crlf, cr]| {
+                format!("{{\"lf\": {}, \"crlf\": {}, \"cr\": {}}}", lf, crlf, cr)
+            });
+        object.field("line_ending_counts", counts);
+        object.field("window_bytes", stats.window.to_string());
+        object.field("window_null_bytes", stats.null_bytes.to_string());
+        object.field("window_control_bytes", stats.control_bytes.to_string());
+        object.field("window_high_bit_bytes", stats.high_bit_bytes.to_string());
+    }
+    object
+}
+
+/// The detailed report for a file, as a block of aligned lines.
+fn verbose_block(
+    filename: &OsString,
+    report: &InspectionReport,
+    stats: &Stats,
+    options: &Options,
+) -> String {
+    let reason = match (report.reason, report.binary_format) {
+        (Reason::Heuristic(name), _) => format!("Heuristic ({})", name),
+        (reason, Some(format)) => format!("{:?} ({:?})", reason, format),
+        (reason, None) => format!("{:?}", reason),
+    };
+    let bom = match report.bom_length {
+        0 => "none".to_string(),
+        length => format!("{} bytes", length),
+    };
+    let line_endings = match (stats.line_ending_style(), stats.line_endings) {
+        (Some(style), Some([lf, crlf, cr])) => {
+            format!("{} ({} LF, {} CRLF, {} CR)", style, lf, crlf, cr)
+        }
+        _ => "-".to_string(),
+    };
+    let with_offset = |count: usize, offset: Option<usize>| match offset {
+        Some(offset) => format!("{} (first at {})", count, offset),
+        None => count.to_string(),
+    };
+    let invalid_utf8 = report
+        .invalid_utf8_offset
+        .map_or("-".to_string(), |offset| offset.to_string());
+
+    let fields = [
+        ("type", type_name(report.content_type, options)),
+        ("reason", reason),
+        ("byte order mark", bom),
+        ("line endings", line_endings),
+        ("window", format!("{} bytes", stats.window)),
+        ("bytes examined", report.bytes_examined.to_string()),
+        (
+            "NULL bytes",
+            with_offset(stats.null_bytes, stats.first_null_offset),
+        ),
+        ("control bytes", stats.control_bytes.to_string()),
+        ("high-bit bytes", stats.high_bit_bytes.to_string()),
+        ("invalid UTF-8 at", invalid_utf8),
+    ];
+    let mut block = format!("{}\n", label(filename));
+    for (name, value) in &fields {
+        block.push_str(&format!("  {:<17} {}\n", format!("{}:", name), value));
+    }
+    block
+}
+
+/// The type of content in its color (with `--color`): binary content in red, UTF-8 in green, the
+/// other Unicode types in cyan and the legacy encodings (Latin-1) in yellow.
+fn colored(name: &str, content_type: ContentType, options: &Options) -> String {
+    use ContentType::*;
+
+    let color = match content_type {
+        BINARY => "31",
+        UTF_8 | UTF_8_BOM | ASCII => "32",
+        UTF_16LE | UTF_16BE | UTF_32LE | UTF_32BE => "36",
+        _ => "33",
+    };
+    if options.color {
+        format!("\x1b[{}m{}\x1b[0m", color, name)
+    } else {
+        name.to_string()
+    }
+}
+
+/// The table of the files and their types, with the paths padded to the same width, and the
+/// summary line, like `3 files: 2 UTF-8, 1 binary` (the most common types first).
+fn table(rows: &[(String, ContentType)], options: &Options) -> String {
+    let width = rows
+        .iter()
+        .map(|(path, _)| path.chars().count())
+        .max()
+        .unwrap_or(0);
+    let mut table = String::new();
+    let mut counts = BTreeMap::new();
+    for (path, content_type) in rows {
+        let name = type_name(*content_type, options);
+        let padding = width - path.chars().count();
+        table.push_str(&format!(
+            "{}{}  {}\n",
+            path,
+            " ".repeat(padding),
+            colored(&name, *content_type, options)
+        ));
+        counts.entry(name).or_insert((*content_type, 0)).1 += 1;
+    }
+
+    let mut counts: Vec<_> = counts.into_iter().collect();
+    counts.sort_by(|(_, (_, a)), (_, (_, b))| b.cmp(a));
+    let counts: Vec<_> = counts
+        .iter()
+        .map(|(name, (content_type, count))| {
+            format!("{} {}", count, colored(name, *content_type, options))
+        })
+        .collect();
+    let files = if rows.len() == 1 { "file" } else { "files" };
+    if counts.is_empty() {
+        table.push_str(&format!("{} {}\n", rows.len(), files));
+    } else {
+        table.push_str(&format!(
+            "{} {}: {}\n",
+            rows.len(),
+            files,
+            counts.join(", ")
+        ));
+    }
+    table
+}
+
+/// The table of `--summary`: the number of files and bytes of every type (with a bar with
+/// `--histogram`) and of all files, and the outliers.
+fn summary_table(summary: &Summary, outliers: &[(&str, ContentType)], options: &Options) -> String {
+    let total = summary.total();
+    let types = summary.types();
+    let names: Vec<_> = types
+        .iter()
+        .map(|&(content_type, _)| type_name(content_type, options))
+        .collect();
+    let name_width = names
+        .iter()
+        .map(|name| name.len())
+        .chain([5])
+        .max()
+        .unwrap();
+    let files_width = total.files.to_string().len().max(5);
+    let bytes_width = total.bytes.to_string().len().max(5);
+    let row = |name: &str, padding: usize, files: &str, bytes: &str| {
+        format!(
+            "{}{}  {:>files_width$}  {:>bytes_width$}",
+            name,
+            " ".repeat(padding),
+            files,
+            bytes,
+            files_width = files_width,
+            bytes_width = bytes_width
+        )
+    };
+
+    let mut table = row("type", name_width - 4, "files", "bytes");
+    table.push('\n');
+    let most_files = types.first().map_or(0, |(_, count)| count.files);
+    for ((content_type, count), name) in types.iter().zip(&names) {
+        let padding = name_width - name.len();
+        let name = colored(name, *content_type, options);
+        table.push_str(&row(
+            &name,
+            padding,
+            &count.files.to_string(),
+            &count.bytes.to_string(),
+        ));
+        if options.histogram {
+            // Every type has a bar of at least one character
+            let width = (count.files as usize * HISTOGRAM_WIDTH).div_ceil(most_files as usize);
+            table.push_str(&format!("  {}", "#".repeat(width)));
+        }
+        table.push('\n');
     }
+    table.push_str(&row(
+        "total",
+        name_width - 5,
+        &total.files.to_string(),
+        &total.bytes.to_string(),
+    ));
+    table.push('\n');
 
-    args.next();
+    if let Some(dominant) = summary
+        .dominant_text_type()
+        .filter(|_| !outliers.is_empty())
+    {
+        let count: u64 = types
+            .iter()
+            .filter(|&&(content_type, _)| summary.is_outlier(content_type))
+            .map(|(_, count)| count.files)
+            .sum();
+        table.push_str(&format!(
+            "\n{} text {} not {}:\n",
+            count,
+            if count == 1 { "file is" } else { "files are" },
+            type_name(dominant, options)
+        ));
+        for &(path, content_type) in outliers {
+            let name = type_name(content_type, options);
+            table.push_str(&format!(
+                "  {}: {}\n",
+                path,
+                colored(&name, content_type, options)
+            ));
+        }
+        if count > outliers.len() as u64 {
+            table.push_str(&format!("  and {} more\n", count - outliers.len() as u64));
+        }
+    }
+    table
+}
 
-    for filename in args {
-        if !Path::new(&filename).is_file() {
+/// The JSON object of `--summary`, like `{"summary": {"files": 3, "bytes": 120, "types": {...},
+/// "dominant_text_type": "UTF-8", "outliers": [...]}}`.
+fn summary_object(
+    summary: &Summary,
+    outliers: &[(&str, ContentType)],
+    options: &Options,
+) -> Object {
+    let count = |files: u64, bytes: u64| format!("{{\"files\": {}, \"bytes\": {}}}", files, bytes);
+    let types: Vec<_> = summary
+        .types()
+        .into_iter()
+        .map(|(content_type, c)| {
+            let name = string(&type_name(content_type, options));
+            format!("{}: {}", name, count(c.files, c.bytes))
+        })
+        .collect();
+    let outliers: Vec<_> = outliers
+        .iter()
+        .map(|&(path, content_type)| {
+            let name = string(&type_name(content_type, options));
+            format!("{{\"path\": {}, \"type\": {}}}", string(path), name)
+        })
+        .collect();
+    let dominant = summary
+        .dominant_text_type()
+        .map_or("null".to_string(), |dominant| {
+            string(&type_name(dominant, options))
+        });
+    let total = summary.total();
+    let summary = format!(
+        "{{\"files\": {}, \"bytes\": {}, \"types\": {{{}}}, \"dominant_text_type\": {}, \"outliers\": [{}]}}",
+        total.files,
+        total.bytes,
+        types.join(", "),
+        dominant,
+        outliers.join(", ")
+    );
+    Object(vec![("summary", summary)])
+}
+
+/// Prints the outcomes in the selected format, and determines the exit status from them.
+struct Printer<'a> {
+    options: &'a Options,
+    /// The number of JSON objects in the array with `--pretty`, or of verbose blocks.
+    printed: usize,
+    /// The rows of the table, which is printed when all files are inspected.
+    rows: Vec<(String, ContentType)>,
+    /// The output for every file, or `None` for verbose blocks.
+    template: Option<Template>,
+    /// The types of the files with `--summary`, and the first paths of every type.
+    summary: Summary,
+    paths: HashMap<ContentType, Vec<String>>,
+    selected_text: bool,
+    selected_binary: bool,
+    mismatched: bool,
+    failed: bool,
+}
+
+impl<'a> Printer<'a> {
+    fn new(options: &'a Options) -> Printer<'a> {
+        if options.json && options.pretty && !options.quiet {
+            print!("[");
+        }
+        // The formats of `--format`, `--list-text` and `-0` take precedence over `-v`
+        let format = match options.format {
+            Some(_) => None,
+            None if options.list && options.print0 => Some("{path}\\0"),
+            None if options.list => Some("{path}\\n"),
+            None if options.print0 => Some("{path}\\0{type}\\0"),
+            None if options.verbose => None,
+            None => Some("{path}: {type}\\n"),
+        };
+        let template = match (&options.format, format) {
+            (Some(template), _) => Some(template.clone()),
+            (None, format) => format.map(|format| Template::parse(format).unwrap()),
+        };
+        Printer {
+            options,
+            printed: 0,
+            rows: vec![],
+            template,
+            summary: Summary::new(),
+            paths: HashMap::new(),
+            selected_text: false,
+            selected_binary: false,
+            mismatched: false,
+            failed: false,
+        }
+    }
+
+    fn print(&mut self, filename: &OsString, outcome: &Outcome) {
+        let options = self.options;
+        match *outcome {
+            Outcome::Inspected(_, _, Some(ref mismatch)) if options.check.is_some() => {
+                self.mismatched = true;
+                if !options.quiet {
+                    println!("{}: {}", label(filename), mismatch);
+                }
+                return;
+            }
+            Outcome::Inspected(..) if options.check.is_some() => return,
+            Outcome::Inspected(ref report, ..) => {
+                let is_text = report.content_type.is_text();
+                if options.filter.is_some_and(|text| text != is_text) {
+                    return;
+                }
+                self.selected_text |= is_text;
+                self.selected_binary |= !is_text;
+            }
+            Outcome::Failed(ref error) => {
+                self.failed = true;
+                if options.quiet || !options.json {
+                    eprintln!("{}: {}", label(filename), error);
+                    return;
+                }
+            }
+        }
+        if options.quiet {
+            return;
+        }
+        if let Outcome::Inspected(ref report, ref stats, _) = *outcome {
+            if options.summary {
+                self.add_to_summary(filename, report.content_type, stats);
+                if !options.json {
+                    return;
+                }
+            }
+        }
+
+        if options.json && options.pretty {
+            let separator = if self.printed == 0 { "" } else { "," };
+            print!(
+                "{}\n{}",
+                separator,
+                json_object(filename, outcome, options).render(true)
+            );
+            self.printed += 1;
+        } else if options.json {
+            println!("{}", json_object(filename, outcome, options).render(false));
+        } else if let Outcome::Inspected(ref report, ref stats, _) = *outcome {
+            match self.template {
+                None => {
+                    // Blocks are separated by empty lines
+                    let separator = if self.printed == 0 { "" } else { "\n" };
+                    print!(
+                        "{}{}",
+                        separator,
+                        verbose_block(filename, report, stats, options)
+                    );
+                    self.printed += 1;
+                }
+                Some(_) if options.table => self.rows.push((label(filename), report.content_type)),
+                Some(ref template) => {
+                    // Like with println!, a closed standard output ends the program
+                    io::stdout()
+                        .lock()
+                        .write_all(&template.render(filename, report, stats, options))
+                        .expect("writing to stdout");
+                }
+            }
+        }
+    }
+
+    /// The exit status, like the one of grep: 2 if there were errors, otherwise 0 if all files
+    /// are text (or if a file matches `--text-only` or `--binary-only`, or if all files pass the
+    /// `--check`), and 1 if not.
+    fn add_to_summary(&mut self, filename: &OsString, content_type: ContentType, stats: &Stats) {
+        // The size of standard input is not known, only the part that was read
+        let bytes = stats.size.unwrap_or(stats.window as u64);
+        self.summary.add(content_type, bytes);
+        let paths = self.paths.entry(content_type).or_default();
+        if paths.len() < OUTLIER_LIMIT {
+            paths.push(label(filename));
+        }
+    }
+
+    /// The paths of the outliers of the summary, in the order of the types, which are not all the
+    /// outliers if there are more than `OUTLIER_LIMIT` files of a type.
+    fn outliers(&self) -> Vec<(&str, ContentType)> {
+        let mut outliers = vec![];
+        for (content_type, _) in self.summary.types() {
+            if self.summary.is_outlier(content_type) {
+                for path in &self.paths[&content_type] {
+                    outliers.push((path.as_str(), content_type));
+                }
+            }
+        }
+        outliers
+    }
+
+    fn finish(self) -> i32 {
+        if self.options.summary && self.options.json {
+            let outliers = self.outliers();
+            let object = summary_object(&self.summary, &outliers, self.options);
+            if self.options.pretty {
+                let separator = if self.printed == 0 { "" } else { "," };
+                print!("{}\n{}", separator, object.render(true));
+            } else {
+                println!("{}", object.render(false));
+            }
+        } else if self.options.summary {
+            let outliers = self.outliers();
+            print!("{}", summary_table(&self.summary, &outliers, self.options));
+        }
+        if self.options.json && self.options.pretty && !self.options.quiet {
+            let empty = self.printed == 0 && !self.options.summary;
+            println!("{}]", if empty { "" } else { "\n" });
+        }
+        if self.options.table {
+            print!("{}", table(&self.rows, self.options));
+        }
+        let success = match self.options.filter {
+            _ if self.options.check.is_some() => !self.mismatched,
+            None => !self.selected_binary,
+            Some(true) => self.selected_text,
+            Some(false) => self.selected_binary,
+        };
+        if self.failed {
+            2
+        } else if success {
+            0
+        } else {
+            1
+        }
+    }
+}
+
+/// The files in the directory tree at the given root, in the order of their names. Symbolic links
+/// are only followed with `--dereference` (cycles are reported as errors then), and with
+/// `verbose`, the entries that are not regular files are noted on standard error. The tree is
+/// walked lazily, only one directory is read at a time.
+#[cfg(feature = "walk")]
+fn walk(root: OsString, options: &Options) -> Box<dyn Iterator<Item = Input> + Send> {
+    let verbose = options.verbose;
+    let walker = ignore::WalkBuilder::new(&root)
+        .standard_filters(!options.no_ignore)
+        .require_git(false)
+        .follow_links(options.dereference == Some(true))
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .build();
+    Box::new(walker.filter_map(move |entry| {
+        match entry {
+            Ok(entry)
+                if entry
+                    .file_type()
+                    .is_some_and(|file_type| file_type.is_file()) =>
+            {
+                Some(Ok(entry.into_path().into_os_string()))
+            }
+            Ok(entry) => {
+                if verbose
+                    && !entry
+                        .file_type()
+                        .is_some_and(|file_type| file_type.is_dir())
+                {
+                    eprintln!("{}: skipped, not a regular file", entry.path().display());
+                }
+                None
+            }
+            Err(error) => Some(Err((root.clone(), error.to_string()))),
+        }
+    }))
+}
+
+#[cfg(not(feature = "walk"))]
+fn walk(_: OsString, _: &Options) -> Box<dyn Iterator<Item = Input> + Send> {
+    unreachable!("--recursive is rejected without the walk feature")
+}
+
+/// Watch the files on the command line (and the directories with `-r`, the others were reported
+/// as errors), and print a line for every file that changes until the watcher stops.
+#[cfg(feature = "watch")]
+fn watch(filenames: &[OsString], options: &Options) -> i32 {
+    use content_inspector::{InspectionWatcher, WatchEvent};
+
+    let mut watcher = match InspectionWatcher::new(options.inspector.clone()) {
+        Ok(watcher) => watcher,
+        Err(error) => {
+            eprintln!("--watch: {}", error);
+            return 2;
+        }
+    };
+    for filename in filenames.iter().filter(|filename| !is_stdin(filename)) {
+        let directory = metadata(filename, options).is_ok_and(|m| m.is_dir());
+        if directory && !options.recursive {
             continue;
         }
+        if let Err(error) = watcher.watch(filename, options.recursive) {
+            eprintln!("{}: {}", label(filename), error);
+            return 2;
+        }
+    }
+
+    eprintln!("Watching for changes, press Ctrl-C to stop");
+    for event in watcher {
+        match event {
+            WatchEvent::Changed(path, content_type) => {
+                let name = type_name(content_type, options);
+                println!(
+                    "{}: {}",
+                    path.display(),
+                    colored(&name, content_type, options)
+                );
+            }
+            WatchEvent::Removed(path) => println!("{}: removed", path.display()),
+            WatchEvent::Error(path, error) => eprintln!("{}: {}", path.display(), describe(&error)),
+            WatchEvent::Watch(error) => eprintln!("--watch: {}", error),
+        }
+    }
+    2
+}
+
+#[cfg(not(feature = "watch"))]
+fn watch(_: &[OsString], _: &Options) -> i32 {
+    unreachable!("--watch is rejected without the watch feature")
+}
+
+/// The files to inspect: the arguments, with the directories replaced by their files if the
+/// search is recursive.
+fn inputs(filenames: Vec<OsString>, options: &Options) -> impl Iterator<Item = Input> + Send + '_ {
+    filenames.into_iter().flat_map(move |filename| {
+        if options.recursive && metadata(&filename, options).is_ok_and(|m| m.is_dir()) {
+            walk(filename, options)
+        } else {
+            Box::new(std::iter::once(Ok(filename)))
+        }
+    })
+}
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    exit(2);
+}
+
+/// Inspect the inputs on `options.threads` threads. The results are printed on this thread only
+/// (so that lines are never interleaved), in the order of the inputs unless `options.unordered`
+/// is set: results that are ready early wait for the earlier ones.
+fn inspect_parallel<I>(inputs: I, options: &Options, printer: &mut Printer)
+where
+    I: Iterator<Item = Input> + Send,
+{
+    let inputs = Mutex::new(inputs.enumerate());
+    let (sender, receiver) = mpsc::channel();
+    thread::scope(|scope| {
+        for _ in 0..options.threads {
+            let sender = sender.clone();
+            let inputs = &inputs;
+            scope.spawn(move || loop {
+                // The lock is released before the file is read
+                let next = inputs.lock().unwrap().next();
+                let Some((index, input)) = next else {
+                    break;
+                };
+                if sender.send((index, inspect_input(input, options))).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(sender);
+
+        let mut pending = BTreeMap::new();
+        let mut next_index = 0;
+        for (index, (filename, outcome)) in receiver {
+            if options.unordered {
+                printer.print(&filename, &outcome);
+                continue;
+            }
+            pending.insert(index, (filename, outcome));
+            while let Some((filename, outcome)) = pending.remove(&next_index) {
+                printer.print(&filename, &outcome);
+                next_index += 1;
+            }
+        }
+    });
+}
+
+/// What `--convert` did with a file.
+enum Conversion {
+    /// The content was converted from the given type.
+    Converted(ContentType),
+    /// The content already was of the target type, and was not changed.
+    Unchanged,
+    /// Binary content, which is never converted.
+    Binary,
+}
+
+/// Convert the text in the file (or standard input) to the target type, and write it to standard
+/// output, or over the file with `--in-place`. The whole file is read, but its type is detected in
+/// the window like without `--convert`. Content that is not valid in the detected encoding is an
+/// error, and the file is left as it is.
+fn convert_input(
+    filename: &OsString,
+    target: ContentType,
+    options: &Options,
+) -> Result<Conversion, String> {
+    let mut content = vec![];
+    let read = if is_stdin(filename) {
+        if options.in_place {
+            return Err("standard input can not be converted in place".to_string());
+        }
+        io::stdin().lock().read_to_end(&mut content)
+    } else {
+        check_regular_file(filename, options)?;
+        File::open(filename).and_then(|mut file| file.read_to_end(&mut content))
+    };
+    read.map_err(|error| describe(&error))?;
+
+    let detected = options
+        .inspector
+        .inspect(&content[..content.len().min(options.peek_size.unwrap_or(usize::MAX))]);
+    if !detected.is_text() {
+        return Ok(Conversion::Binary);
+    }
+    let bom = if options.add_bom {
+        BomPolicy::Always
+    } else {
+        BomPolicy::Auto
+    };
+    let converted = content_inspector::convert(&content, detected, target, bom)
+        .map_err(|error| format!("can not convert from {}: {}", detected, error))?;
+
+    let conversion = if converted == content {
+        Conversion::Unchanged
+    } else {
+        Conversion::Converted(detected)
+    };
+    if !options.in_place {
+        io::stdout()
+            .lock()
+            .write_all(&converted)
+            .expect("writing to stdout");
+    } else if let Conversion::Converted(_) = conversion {
+        replace_file(Path::new(filename), &converted).map_err(|error| error.to_string())?;
+    }
+    Ok(conversion)
+}
+
+/// Write the text of the file (or standard input) to standard output with `--textconv`, for git,
+/// which runs it with the path of the file to diff (often a temporary file). UTF-16 and UTF-32
+/// are decoded leniently (invalid sequences are replaced by U+FFFD, so that a diff never fails),
+/// other text is written as it is. Returns the exit status: 1 for binary content, of which
+/// nothing is written, and 2 if the file can not be read.
+fn textconv(filename: &OsString, options: &Options) -> i32 {
+    use ContentType::*;
+
+    let mut content = vec![];
+    let read = if is_stdin(filename) {
+        io::stdin().lock().read_to_end(&mut content)
+    } else {
+        File::open(filename).and_then(|mut file| file.read_to_end(&mut content))
+    };
+    if let Err(error) = read {
+        eprintln!("{}: {}", label(filename), describe(&error));
+        return 2;
+    }
+
+    let content_type = options.inspector.inspect(&content);
+    let text = match content_type {
+        BINARY => {
+            eprintln!("{}: binary content", label(filename));
+            return 1;
+        }
+        UTF_16LE | UTF_16BE | UTF_32LE | UTF_32BE => {
+            content_inspector::decode_lossy(&content, content_type)
+                .into_owned()
+                .into_bytes()
+        }
+        _ => content,
+    };
+    io::stdout()
+        .lock()
+        .write_all(&text)
+        .expect("writing to stdout");
+    0
+}
 
-        let file = File::open(&filename)?;
-        let mut buffer: Vec<u8> = vec![];
+/// Replace the content of the file. The content is written to a temporary file next to it (with
+/// the same permissions), which is then renamed over the file, so that the file is never left
+/// partially written.
+fn replace_file(path: &Path, content: &[u8]) -> io::Result<()> {
+    let mut temporary_name = OsString::from(".");
+    temporary_name.push(path.file_name().unwrap_or_default());
+    temporary_name.push(format!(".inspect-{}", process::id()));
+    let temporary = path.with_file_name(temporary_name);
 
-        file.take(MAX_PEEK_SIZE as u64).read_to_end(&mut buffer)?;
+    let permissions = fs::metadata(path)?.permissions();
+    let result = File::options()
+        .write(true)
+        .create_new(true)
+        .open(&temporary)
+        .and_then(|mut file| {
+            file.write_all(content)?;
+            file.set_permissions(permissions)?;
+            file.sync_all()
+        })
+        .and_then(|()| fs::rename(&temporary, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temporary);
+    }
+    result
+}
 
-        let content_type = content_inspector::inspect(&buffer);
-        println!("{}: {}", filename, content_type);
+/// Convert the inputs with `--convert`, one after the other (so that the output of the files is
+/// not interleaved). Returns the exit status: 2 if a file could not be converted, otherwise 1 if
+/// a binary file was skipped, and 0 if not.
+fn convert_files<I>(inputs: I, target: ContentType, options: &Options) -> i32
+where
+    I: Iterator<Item = Input>,
+{
+    let mut failed = false;
+    let mut skipped = false;
+    for input in inputs {
+        let (filename, result) = match input {
+            Ok(filename) => {
+                let result = convert_input(&filename, target, options);
+                (filename, result)
+            }
+            Err((path, error)) => (path, Err(error)),
+        };
+        match result {
+            Ok(Conversion::Converted(from)) if options.in_place && !options.quiet => {
+                println!(
+                    "{}: converted from {} to {}",
+                    label(&filename),
+                    from,
+                    target
+                );
+            }
+            Ok(Conversion::Converted(_)) | Ok(Conversion::Unchanged) => {}
+            Ok(Conversion::Binary) => {
+                skipped = true;
+                eprintln!(
+                    "{}: skipped, binary content is not converted",
+                    label(&filename)
+                );
+            }
+            Err(error) => {
+                failed = true;
+                eprintln!("{}: {}", label(&filename), error);
+            }
+        }
+    }
+    if failed {
+        2
+    } else if skipped {
+        1
+    } else {
+        0
     }
+}
 
-    Ok(())
+/// Inspect the records of standard input with `--records`, and print the type of every record as
+/// soon as its delimiter was read (or the end of the input, after the last one). Only the start of
+/// a record is kept for the inspection, the rest of it is skipped, so that long records do not use
+/// more memory. Returns the exit status: 2 if standard input could not be read, otherwise 1 if a
+/// record is binary, and 0 if not.
+fn records(delimiter: u8, options: &Options) -> i32 {
+    let limit = options.peek_size.unwrap_or(DEFAULT_PEEK_SIZE);
+    let mut reader = io::stdin().lock();
+    let mut record = vec![];
+    // Whether a record was started, which ends without a delimiter at the end of the input
+    let mut started = false;
+    let mut sequence = 0u64;
+    let mut binary = false;
+    loop {
+        let (length, end) = match reader.fill_buf() {
+            Ok(buffer) => {
+                let end = buffer.iter().position(|&byte| byte == delimiter);
+                let length = end.unwrap_or(buffer.len());
+                let kept = length.min(limit - record.len());
+                record.extend_from_slice(&buffer[..kept]);
+                (length, end)
+            }
+            Err(ref error) if error.kind() == io::ErrorKind::Interrupted => continue,
+            Err(error) => {
+                eprintln!("{}: {}", STDIN, describe(&error));
+                return 2;
+            }
+        };
+        let at_end = length == 0 && end.is_none();
+        if at_end && !started {
+            break;
+        }
+        reader.consume(length + end.map_or(0, |_| 1));
+        if end.is_none() && !at_end {
+            started = true;
+            continue;
+        }
+
+        started = false;
+        sequence += 1;
+        let content_type = options.inspector.inspect(&record);
+        binary |= !content_type.is_text();
+        if !options.quiet {
+            // Standard output flushes every line, so that the types are printed as they arrive
+            println!("{}\t{}", sequence, type_name(content_type, options));
+        }
+        record.clear();
+        if at_end {
+            break;
+        }
+    }
+    if binary {
+        1
+    } else {
+        0
+    }
+}
+
+/// What `--strip-bom` did (or would do, with `--dry-run`) with a file.
+enum BomChange {
+    /// The UTF-8 byte order mark was removed.
+    Stripped,
+    /// The text was converted from the given type to UTF-8 without a byte order mark.
+    Transcoded(ContentType),
+    /// UTF-16 or UTF-32 text, which keeps its byte order mark without `--transcode`.
+    Kept(ContentType),
+    /// There is no byte order mark, and the file was not written.
+    Unchanged,
+    /// Binary content, which is never rewritten.
+    Binary,
+}
+
+/// Remove the byte order mark of the file with `--strip-bom`, by replacing the file (see
+/// `replace_file`). Only files with a byte order mark are written, so that the modification times
+/// of the others do not change.
+fn strip_bom_input(filename: &OsString, options: &Options) -> Result<BomChange, String> {
+    use ContentType::*;
+
+    if is_stdin(filename) {
+        return Err("standard input can not be rewritten".to_string());
+    }
+    check_regular_file(filename, options)?;
+    let mut content = fs::read(filename).map_err(|error| describe(&error))?;
+
+    let report = options.inspector.inspect_detailed(&content);
+    let change = match report.content_type {
+        content_type if !content_type.is_text() => return Ok(BomChange::Binary),
+        _ if report.bom_length == 0 => return Ok(BomChange::Unchanged),
+        UTF_8_BOM => {
+            content_inspector::strip_bom_in_place(&mut content, false);
+            BomChange::Stripped
+        }
+        content_type if !options.transcode => return Ok(BomChange::Kept(content_type)),
+        content_type => {
+            content =
+                content_inspector::convert(&content, content_type, UTF_8, BomPolicy::Never)
+                    .map_err(|error| format!("can not convert from {}: {}", content_type, error))?;
+            BomChange::Transcoded(content_type)
+        }
+    };
+    if !options.dry_run {
+        replace_file(Path::new(filename), &content).map_err(|error| error.to_string())?;
+    }
+    Ok(change)
+}
+
+/// Remove the byte order marks of the inputs with `--strip-bom`. Returns the exit status: 2 if a
+/// file could not be changed, otherwise 1 if a binary file was refused, and 0 if not.
+fn strip_bom_files<I>(inputs: I, options: &Options) -> i32
+where
+    I: Iterator<Item = Input>,
+{
+    let would = if options.dry_run { "would " } else { "" };
+    let mut failed = false;
+    let mut refused = false;
+    for input in inputs {
+        let (filename, result) = match input {
+            Ok(filename) => {
+                let result = strip_bom_input(&filename, options);
+                (filename, result)
+            }
+            Err((path, error)) => (path, Err(error)),
+        };
+        let name = label(&filename);
+        match result {
+            Ok(BomChange::Stripped) if !options.quiet => {
+                let verb = if options.dry_run { "remove" } else { "removed" };
+                println!("{}: {}{} the UTF-8 byte order mark", name, would, verb);
+            }
+            Ok(BomChange::Transcoded(from)) if !options.quiet => {
+                let verb = if options.dry_run {
+                    "convert"
+                } else {
+                    "converted"
+                };
+                println!("{}: {}{} from {} to UTF-8", name, would, verb, from);
+            }
+            Ok(BomChange::Kept(content_type)) => {
+                eprintln!(
+                    "{}: kept, the byte order mark of {} is only removed with --transcode",
+                    name, content_type
+                );
+            }
+            Ok(BomChange::Binary) => {
+                refused = true;
+                eprintln!("{}: refused, binary content is not rewritten", name);
+            }
+            Ok(_) => {}
+            Err(error) => {
+                failed = true;
+                eprintln!("{}: {}", name, error);
+            }
+        }
+    }
+    if failed {
+        2
+    } else if refused {
+        1
+    } else {
+        0
+    }
+}
+
+/// A file of which the whole content has another type than its window, with `--verify`.
+struct Disagreement {
+    window_type: ContentType,
+    full_type: ContentType,
+    /// The offset of the first byte that the window missed, like a NULL byte.
+    offset: u64,
+}
+
+/// Inspect the window of the file (or standard input) like without `--verify`, and then scan the
+/// rest of it for NULL bytes, in chunks (with `content_inspector::contains_binary`), so that the
+/// memory does not grow with the size of the file. Only text without a byte order mark can turn
+/// out to be binary, since the other types are decided in the window.
+fn verify_input(filename: &OsString, options: &Options) -> Result<Option<Disagreement>, String> {
+    let mut reader: Box<dyn Read> = if is_stdin(filename) {
+        Box::new(io::stdin().lock())
+    } else {
+        check_regular_file(filename, options)?;
+        Box::new(File::open(filename).map_err(|error| describe(&error))?)
+    };
+    let peek_size = options.peek_size.unwrap_or(DEFAULT_PEEK_SIZE) as u64;
+    let mut window = vec![];
+    (&mut reader)
+        .take(peek_size)
+        .read_to_end(&mut window)
+        .map_err(|error| describe(&error))?;
+
+    let report = options.inspector.inspect_detailed(&window);
+    if !report.content_type.is_text() || report.bom_length > 0 {
+        return Ok(None);
+    }
+    let offset =
+        content_inspector::contains_binary(reader, false).map_err(|error| describe(&error))?;
+    Ok(offset.map(|offset| Disagreement {
+        window_type: report.content_type,
+        full_type: ContentType::BINARY,
+        offset: window.len() as u64 + offset,
+    }))
+}
+
+/// Verify the inputs with `--verify`, and print the disagreements. Returns the exit status: 2 if
+/// a file could not be read, otherwise 1 if the types of a file differ, and 0 if not.
+fn verify_files<I>(inputs: I, options: &Options) -> i32
+where
+    I: Iterator<Item = Input>,
+{
+    let mut failed = false;
+    let mut disagreed = false;
+    for input in inputs {
+        let (filename, result) = match input {
+            Ok(filename) => {
+                let result = verify_input(&filename, options);
+                (filename, result)
+            }
+            Err((path, error)) => (path, Err(error)),
+        };
+        match result {
+            Ok(None) => {}
+            Ok(Some(disagreement)) => {
+                disagreed = true;
+                if options.quiet {
+                    continue;
+                }
+                if options.json {
+                    let mut object = Object::new(&filename);
+                    let window_type = disagreement.window_type.to_string();
+                    object.field("window_type", string(&window_type));
+                    object.field("full_type", string(&disagreement.full_type.to_string()));
+                    object.field("offset", disagreement.offset.to_string());
+                    object.field("peek_size", offset(peek_limit(options)));
+                    println!("{}", object.render(false));
+                } else {
+                    println!(
+                        "{}: {} in the first {} bytes, {} with a NULL byte at offset {}",
+                        label(&filename),
+                        disagreement.window_type,
+                        options.peek_size.unwrap_or(DEFAULT_PEEK_SIZE),
+                        disagreement.full_type,
+                        disagreement.offset
+                    );
+                }
+            }
+            Err(error) => {
+                failed = true;
+                if options.json && !options.quiet {
+                    let mut object = Object::new(&filename);
+                    object.field("error", string(&error));
+                    println!("{}", object.render(false));
+                } else {
+                    eprintln!("{}: {}", label(&filename), error);
+                }
+            }
+        }
+    }
+    if failed {
+        2
+    } else if disagreed {
+        1
+    } else {
+        0
+    }
+}
+
+fn main() {
+    let mut options = Options {
+        json: false,
+        pretty: false,
+        print0: false,
+        quiet: false,
+        filter: None,
+        list: false,
+        format: None,
+        summary: false,
+        histogram: false,
+        recursive: false,
+        no_ignore: false,
+        watch: false,
+        dereference: None,
+        verbose: false,
+        threads: thread::available_parallelism().map_or(1, |threads| threads.get()),
+        unordered: false,
+        table: false,
+        color: false,
+        mime: false,
+        check: None,
+        allow_bom: false,
+        peek_size: Some(DEFAULT_PEEK_SIZE),
+        force: false,
+        convert: None,
+        add_bom: false,
+        in_place: false,
+        textconv: false,
+        verify: false,
+        strip_bom: false,
+        transcode: false,
+        dry_run: false,
+        records: None,
+        inspector: Inspector::default(),
+    };
+    let mut filenames = vec![];
+    let mut color = OsString::from("auto");
+    let mut args = env::args_os().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.to_str() {
+            Some("--threads") => {
+                options.threads = match args.next().and_then(|n| n.to_str()?.parse().ok()) {
+                    Some(threads) if threads > 0 => threads,
+                    _ => usage(),
+                }
+            }
+            Some("--unordered") => options.unordered = true,
+            Some("--color") => color = args.next().unwrap_or_default(),
+            Some(arg) if arg.starts_with("--color=") => color = OsString::from(&arg[8..]),
+            Some("--mime") => options.mime = true,
+            Some("--check") => {
+                options.check = match args.next().and_then(|name| parse_type(name.to_str()?)) {
+                    Some(content_type) => Some(content_type),
+                    None => usage(),
+                }
+            }
+            Some("--allow-bom") => options.allow_bom = true,
+            Some("--no-bom") => options.allow_bom = false,
+            Some("--convert") => {
+                use ContentType::*;
+                options.convert = match args.next().and_then(|name| parse_type(name.to_str()?)) {
+                    Some(BINARY) | Some(ASCII) | Some(LATIN_1) | None => usage(),
+                    content_type => content_type,
+                }
+            }
+            Some("--add-bom") => options.add_bom = true,
+            Some("--in-place") => options.in_place = true,
+            Some("--textconv") => options.textconv = true,
+            Some("--verify") => options.verify = true,
+            Some("--strip-bom") => options.strip_bom = true,
+            Some("--transcode") => options.transcode = true,
+            Some("--dry-run") => options.dry_run = true,
+            Some("--records") => options.records = Some(b'\n'),
+            Some(arg) if arg.starts_with("--records=") => {
+                options.records = match &arg.as_bytes()[10..] {
+                    b"\\n" => Some(b'\n'),
+                    b"\\t" => Some(b'\t'),
+                    b"\\0" => Some(b'\0'),
+                    &[delimiter] => Some(delimiter),
+                    _ => usage(),
+                }
+            }
+            Some("--json") => options.json = true,
+            Some("--pretty") => options.pretty = true,
+            Some("-0") | Some("-z") | Some("--print0") => options.print0 = true,
+            Some("-q") | Some("--quiet") => options.quiet = true,
+            Some("--text-only") => options.filter = Some(true),
+            Some("--binary-only") => options.filter = Some(false),
+            Some("--list-text") => (options.filter, options.list) = (Some(true), true),
+            Some("--list-binary") => (options.filter, options.list) = (Some(false), true),
+            Some("--format") => {
+                let template = args.next().and_then(|template| template.into_string().ok());
+                options.format = match Template::parse(&template.unwrap_or_else(|| usage())) {
+                    Ok(template) => Some(template),
+                    Err(error) => {
+                        eprintln!("--format: {}", error);
+                        exit(2);
+                    }
+                }
+            }
+            Some("--summary") => options.summary = true,
+            Some("--histogram") => (options.summary, options.histogram) = (true, true),
+            Some("-r") | Some("--recursive") => options.recursive = true,
+            Some("--no-ignore") => options.no_ignore = true,
+            Some("-w") | Some("--watch") => options.watch = true,
+            Some("--dereference") => options.dereference = Some(true),
+            Some("--no-dereference") => options.dereference = Some(false),
+            Some("--bytes") => {
+                options.peek_size = match args.next().and_then(|n| n.to_str()?.parse().ok()) {
+                    Some(bytes) if bytes > 0 => Some(bytes),
+                    _ => usage(),
+                }
+            }
+            Some("--all") => options.peek_size = None,
+            Some("--force") => options.force = true,
+            Some("-v") | Some("--verbose") => options.verbose = true,
+            _ => filenames.push(arg),
+        }
+    }
+
+    if options.recursive && cfg!(not(feature = "walk")) {
+        eprintln!(
+            "--recursive needs the 'walk' feature: cargo run --features walk --example inspect"
+        );
+        exit(2);
+    }
+    if options.watch && cfg!(not(feature = "watch")) {
+        eprintln!(
+            "--watch needs the 'watch' feature: cargo run --features watch --example inspect"
+        );
+        exit(2);
+    }
+
+    let output_options =
+        options.json || options.print0 || options.filter.is_some() || options.format.is_some();
+    if options.check.is_some() && (options.mime || output_options) {
+        usage();
+    }
+    if options.convert.is_some() && (options.mime || options.check.is_some() || output_options)
+        || options.convert.is_none() && (options.add_bom || options.in_place)
+        || options.force && options.peek_size.is_some()
+    {
+        usage();
+    }
+    // The changes are printed as 'PATH: TYPE' lines, and standard input can not be watched
+    let other_mode = options.check.is_some() || options.convert.is_some();
+    if options.watch && (output_options || other_mode || options.verbose || options.quiet) {
+        usage();
+    }
+    // The summary replaces the output of the files, except for the JSON objects
+    let other_output = options.print0 || options.list || options.format.is_some();
+    if options.summary
+        && (other_output || other_mode || options.verbose || options.quiet || options.watch)
+    {
+        usage();
+    }
+    let any_mode = other_output || other_mode || options.summary || options.watch;
+    if options.textconv && (any_mode || options.json || options.filter.is_some() || options.verbose)
+    {
+        usage();
+    }
+    // The whole files are scanned after the window anyway
+    let any_mode = any_mode || options.textconv;
+    if options.verify
+        && (any_mode || options.filter.is_some() || options.pretty || options.peek_size.is_none())
+    {
+        usage();
+    }
+    let any_mode = any_mode || options.verify;
+    if options.strip_bom && (any_mode || options.json || options.filter.is_some())
+        || !options.strip_bom && (options.transcode || options.dry_run)
+    {
+        usage();
+    }
+    // With --records, -z is the delimiter; the records are only read from standard input
+    let any_mode = any_mode && !(options.print0 && !other_mode && options.format.is_none())
+        || options.strip_bom;
+    let whole = options.peek_size.is_none() || options.recursive;
+    if options.records.is_some()
+        && (any_mode || options.json || options.filter.is_some() || options.verbose || whole)
+        || options.records.is_some() && !filenames.iter().all(is_stdin)
+    {
+        usage();
+    }
+    if options.records.is_some() && options.print0 {
+        options.records = Some(b'\0');
+    }
+
+    // Only the plain list of types is printed as a table
+    let terminal = io::stdout().is_terminal();
+    let no_color = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    (options.table, options.color) = match color.to_str() {
+        Some("always") => (true, true),
+        Some("never") => (terminal, false),
+        Some("auto") => (terminal, terminal && !no_color),
+        _ => usage(),
+    };
+    let other_format = options.json || options.print0 || options.list || options.format.is_some();
+    if other_format
+        || options.verbose
+        || options.check.is_some()
+        || options.quiet
+        || options.summary
+    {
+        options.table = false;
+    }
+
+    let builder = if options.mime {
+        // Like file(1), which needs the ASCII detection to tell us-ascii from utf-8
+        Inspector::new().preset(Preset::File).detect_ascii(true)
+    } else if options.check.is_some() {
+        // The encoding is validated in the window, and ASCII is detected for `--check ascii`
+        Inspector::new()
+            .strictness(Strictness::Strict)
+            .detect_ascii(true)
+    } else if options.convert.is_some() {
+        // Text that is not valid UTF-8 (or UTF-16 or UTF-32) is converted from Latin-1
+        Inspector::new().latin1_fallback(true)
+    } else if options.textconv {
+        // Sources in UTF-16 are not always saved with a byte order mark
+        Inspector::new().detect_utf16_without_bom(true)
+    } else {
+        Inspector::new()
+    };
+    // Everything that is read is scanned
+    options.inspector = match options.peek_size {
+        Some(peek_size) => builder.max_scan_size(peek_size),
+        None => builder.scan_all(true),
+    }
+    .build()
+    .unwrap();
+
+    if let Some(delimiter) = options.records {
+        exit(records(delimiter, &options));
+    }
+
+    // Without files, read from standard input, unless that would wait for the terminal
+    if filenames.is_empty() && !io::stdin().is_terminal() {
+        filenames.push(OsString::from("-"));
+    }
+    if filenames.is_empty()
+        || (options.pretty && !options.json)
+        || ((options.print0 || options.list || options.format.is_some()) && options.json)
+        || (options.watch && filenames.iter().all(is_stdin))
+        || (options.textconv && filenames.len() != 1)
+    {
+        usage();
+    }
+
+    if options.textconv {
+        exit(textconv(&filenames[0], &options));
+    }
+    if options.verify {
+        exit(verify_files(inputs(filenames, &options), &options));
+    }
+    if options.strip_bom {
+        exit(strip_bom_files(inputs(filenames, &options), &options));
+    }
+
+    if let Some(target) = options.convert {
+        exit(convert_files(inputs(filenames, &options), target, &options));
+    }
+
+    let watched = if options.watch {
+        filenames.clone()
+    } else {
+        vec![]
+    };
+    let mut printer = Printer::new(&options);
+    if options.threads == 1 {
+        for input in inputs(filenames, &options) {
+            let (filename, outcome) = inspect_input(input, &options);
+            printer.print(&filename, &outcome);
+        }
+    } else {
+        inspect_parallel(inputs(filenames, &options), &options, &mut printer);
+    }
+    let status = printer.finish();
+    if options.watch {
+        exit(watch(&watched, &options));
+    }
+    exit(status);
 }
@@ -0,0 +1,42 @@
+import os
+import shutil
+
+# The seed corpus of the fuzz targets: the samples in testdata/ (cut to twice
+# the window of the default inspector), and edge cases of the byte order marks.
+# Run from this directory.
+
+LIMIT = 2048
+
+boms = {
+    "UTF-8": b"\xEF\xBB\xBF",
+    "UTF-16LE": b"\xFF\xFE",
+    "UTF-16BE": b"\xFE\xFF",
+    "UTF-32LE": b"\xFF\xFE\x00\x00",
+    "UTF-32BE": b"\x00\x00\xFE\xFF",
+}
+
+shutil.rmtree("seeds", ignore_errors=True)
+os.mkdir("seeds")
+
+for name in sorted(os.listdir("../testdata")):
+    path = os.path.join("../testdata", name)
+    if name.endswith((".py", ".md", ".tsv", ".snap")) or not os.path.isfile(path):
+        continue
+    with open(path, "rb") as source:
+        data = source.read(LIMIT)
+    with open(os.path.join("seeds", name), "wb") as target:
+        target.write(data)
+
+for name, bom in boms.items():
+    with open("seeds/bom_{}".format(name), "wb") as target:
+        target.write(bom)
+    with open("seeds/bom_{}_doubled".format(name), "wb") as target:
+        target.write(bom + bom)
+    with open("seeds/bom_{}_truncated".format(name), "wb") as target:
+        target.write(bom[:-1])
+
+with open("seeds/empty", "wb") as target:
+    pass
+
+with open("seeds/crlf", "wb") as target:
+    target.write(b"a\r\nb\rc\n\r")
@@ -0,0 +1,172 @@
+//! The checks of the fuzz targets in `fuzz_targets/`, which are also run without the fuzzer by
+//! `tests/fuzz.rs`. Every check panics if one of the APIs disagrees with another one for the same
+//! bytes, or if a report has an offset or a length outside of the buffer.
+
+extern crate content_inspector;
+
+use content_inspector::{
+    bom_bytes, decode_lossy_with, decode_with, inspect_and_decode, ContentType, InspectionReport,
+    Inspector, NewlinePolicy, Strictness,
+};
+
+/// Every type of content, to decode the buffers with.
+pub const CONTENT_TYPES: [ContentType; 9] = [
+    ContentType::BINARY,
+    ContentType::ASCII,
+    ContentType::LATIN_1,
+    ContentType::UTF_8,
+    ContentType::UTF_8_BOM,
+    ContentType::UTF_16LE,
+    ContentType::UTF_16BE,
+    ContentType::UTF_32LE,
+    ContentType::UTF_32BE,
+];
+
+const NEWLINE_POLICIES: [NewlinePolicy; 3] = [
+    NewlinePolicy::Preserve,
+    NewlinePolicy::NormalizeToLf,
+    NewlinePolicy::NormalizeToCrlf,
+];
+
+/// The inspectors that are checked: the default one, and one for every strictness with UTF-16
+/// without a byte order mark, so that all stages of the detection run.
+pub fn inspectors() -> Vec<Inspector> {
+    let mut inspectors = vec![Inspector::default()];
+    for &strictness in &[
+        Strictness::Fast,
+        Strictness::Standard,
+        Strictness::Strict,
+        Strictness::Paranoid,
+    ] {
+        let inspector = Inspector::new()
+            .strictness(strictness)
+            .detect_utf16_without_bom(true)
+            .build()
+            .unwrap();
+        inspectors.push(inspector);
+    }
+    inspectors
+}
+
+/// `inspect` agrees with `inspect_detailed` and `inspect_reader`.
+pub fn check_inspect(data: &[u8]) {
+    assert_eq!(
+        content_inspector::inspect(data),
+        content_inspector::inspect_detailed(data).content_type
+    );
+    for inspector in inspectors() {
+        let content_type = inspector.inspect(data);
+        assert_eq!(content_type, inspector.inspect_detailed(data).content_type);
+        assert_eq!(content_type, inspector.inspect_reader(data).unwrap());
+    }
+}
+
+/// The offsets and lengths of the reports are within the buffer, and consistent with it.
+pub fn check_inspect_detailed(data: &[u8]) {
+    check_report(data, &content_inspector::inspect_detailed(data));
+    for inspector in inspectors() {
+        check_report(data, &inspector.inspect_detailed(data));
+    }
+}
+
+fn check_report(data: &[u8], report: &InspectionReport) {
+    assert!(report.bom_length <= data.len(), "{:?}", report);
+    if report.bom_length > 0 {
+        assert!(data.starts_with(&bom_bytes(report.detected_type)[..report.bom_length]));
+    }
+    for &length in &[
+        report.scanned_bytes,
+        report.validated_bytes,
+        report.sniffed_bytes,
+        report.bytes_examined,
+    ] {
+        assert!(length <= data.len(), "{:?}", report);
+    }
+    assert!(report.null_bytes <= report.scanned_bytes, "{:?}", report);
+    match report.first_null_offset {
+        Some(offset) => {
+            assert!(offset < data.len(), "{:?}", report);
+            assert_eq!(0, data[offset], "{:?}", report);
+            assert!(report.null_bytes > 0, "{:?}", report);
+        }
+        None => assert_eq!(0, report.null_bytes, "{:?}", report),
+    }
+    if let Some(offset) = report.invalid_utf8_offset {
+        assert!(offset < data.len(), "{:?}", report);
+    }
+}
+
+/// Split the input of the streaming target into the content and the lengths of its chunks: the
+/// first byte is the number of lengths that follow (modulo 16), and the lengths are repeated
+/// until the content is used up. A length of zero pushes an empty chunk (and if all of them are
+/// zero, the content is a single chunk).
+pub fn chunks(data: &[u8]) -> (&[u8], Vec<&[u8]>) {
+    let (count, rest) = match data.split_first() {
+        Some((&count, rest)) => (usize::from(count % 16).min(rest.len()), rest),
+        None => return (data, vec![]),
+    };
+    let (lengths, content) = rest.split_at(count);
+    let mut chunks = vec![];
+    let mut remaining = content;
+    for &length in lengths.iter().cycle() {
+        if remaining.is_empty() || lengths.iter().all(|&length| length == 0) {
+            break;
+        }
+        let (chunk, rest) = remaining.split_at(usize::from(length).min(remaining.len()));
+        chunks.push(chunk);
+        remaining = rest;
+    }
+    if !remaining.is_empty() {
+        chunks.push(remaining);
+    }
+    (content, chunks)
+}
+
+/// The `StreamingInspector` agrees with `inspect` for the same bytes, however they are split into
+/// chunks, and a result that is settled early does not change anymore.
+pub fn check_streaming(data: &[u8]) {
+    let (content, chunks) = chunks(data);
+    for inspector in inspectors() {
+        let expected = inspector.inspect(content);
+        let mut streaming = inspector.streaming();
+        let mut settled = None;
+        for chunk in &chunks {
+            streaming.push(chunk);
+            if settled.is_some() {
+                assert_eq!(settled, streaming.current());
+            }
+            settled = streaming.current();
+            if let Some(content_type) = settled {
+                assert_eq!(expected, content_type, "settled early");
+            }
+        }
+        assert_eq!(expected, streaming.finalize());
+    }
+}
+
+/// The decode helpers never panic, and the strict and the lossy decoding agree on whether (and
+/// where) the content is invalid, for every type of content and newline policy.
+pub fn check_decode(data: &[u8]) {
+    for &content_type in &CONTENT_TYPES {
+        for &policy in &NEWLINE_POLICIES {
+            let lossy = decode_lossy_with(data, content_type, policy);
+            match decode_with(data, content_type, policy) {
+                Ok(text) => {
+                    assert_eq!(text, lossy.text);
+                    assert_eq!(0, lossy.replacements);
+                    assert_eq!(None, lossy.first_error_offset);
+                }
+                Err(error) => {
+                    assert!(error.offset < data.len(), "{:?}", error);
+                    assert!(lossy.replacements > 0);
+                    assert_eq!(Some(error.offset), lossy.first_error_offset);
+                }
+            }
+        }
+    }
+
+    let decoded = inspect_and_decode(data);
+    assert_eq!(content_inspector::inspect(data), decoded.content_type);
+    assert!(decoded.bom_length <= data.len());
+    assert!(data.starts_with(&bom_bytes(decoded.content_type)[..decoded.bom_length]));
+}
@@ -0,0 +1,134 @@
+#ifndef CONTENT_INSPECTOR_H
+#define CONTENT_INSPECTOR_H
+
+/* Generated by cbindgen from src/ffi.rs, do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/*
+ `BINARY`
+ */
+#define CI_BINARY 0
+
+/*
+ `UTF_8`
+ */
+#define CI_UTF_8 1
+
+/*
+ `UTF_8_BOM`
+ */
+#define CI_UTF_8_BOM 2
+
+/*
+ `UTF_16LE`
+ */
+#define CI_UTF_16LE 3
+
+/*
+ `UTF_16BE`
+ */
+#define CI_UTF_16BE 4
+
+/*
+ `UTF_32LE`
+ */
+#define CI_UTF_32LE 5
+
+/*
+ `UTF_32BE`
+ */
+#define CI_UTF_32BE 6
+
+/*
+ `LATIN_1`
+ */
+#define CI_LATIN_1 7
+
+/*
+ `ASCII`
+ */
+#define CI_ASCII 8
+
+/*
+ Returned for invalid arguments, or if the inspection panicked.
+ */
+#define CI_ERROR 255
+
+/*
+ The value of the offsets in a `CiReport` that are not set.
+ */
+#define CI_NONE SIZE_MAX
+
+/*
+ An [`InspectionReport`](../struct.InspectionReport.html), with the types of content as codes,
+ the reason and the binary format as the codes below, and `CI_NONE` for missing offsets.
+
+ Reasons: 0 empty buffer, 1 byte order mark, 2 UTF-16 pattern, 3 NULL bytes, 4 magic number,
+ 5 control characters, 6 printable ratio, 7 invalid UTF-8, 8 invalid encoding, 9 custom
+ heuristic, 10 no binary indicators.
+
+ Binary formats: 0 none, 1 PDF, 2 PNG, 3 Windows shortcut, 4 Windows registry hive, 5 cabinet,
+ 6 Windows prefetch, 7 gzip, 8 Zstandard, 255 custom.
+ */
+typedef struct CiReport {
+  uint8_t content_type;
+  uint8_t detected_type;
+  uint8_t reason;
+  uint8_t binary_format;
+  uintptr_t null_bytes;
+  uintptr_t first_null_offset;
+  uintptr_t bom_length;
+  uintptr_t control_characters;
+  uintptr_t printable_bytes;
+  uintptr_t invalid_utf8_offset;
+  uintptr_t scanned_bytes;
+  uintptr_t validated_bytes;
+  uintptr_t sniffed_bytes;
+  uintptr_t bytes_examined;
+} CiReport;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/*
+ Determine the type of content of the `length` bytes at `data`, like
+ [`inspect`](../fn.inspect.html). Returns `CI_ERROR` if `data` is NULL (and `length` is not
+ zero).
+
+ # Safety
+
+ `data` has to point to `length` readable bytes, unless it is NULL or `length` is zero.
+ */
+uint8_t ci_inspect(const uint8_t *data, uintptr_t length);
+
+/*
+ Like [`ci_inspect`](fn.ci_inspect.html), but fills the report (like
+ [`inspect_detailed`](../fn.inspect_detailed.html)). Returns zero on success, and `-1` if
+ `data` (with a length that is not zero) or `report` is NULL. The report is not changed on
+ errors.
+
+ # Safety
+
+ `data` has to point to `length` readable bytes, unless it is NULL or `length` is zero, and
+ `report` has to be NULL or point to a writable `CiReport`.
+ */
+int ci_inspect_detailed(const uint8_t *data, uintptr_t length, struct CiReport *report);
+
+/*
+ The name of a type of content (like `"UTF-16LE"`, see the `Display` implementation of
+ [`ContentType`](../enum.ContentType.html)), as a static NUL-terminated string. Returns NULL for
+ unknown codes.
+ */
+const char *ci_content_type_name(uint8_t content_type);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* CONTENT_INSPECTOR_H */
@@ -0,0 +1,55 @@
+// Tests of the Node.js module, run with `npm test` (after `npm run build`).
+//
+// The classification of the fixtures by the default inspector is taken from the golden corpus of
+// the Rust tests (`testdata/golden_corpus.tsv`), so that both agree.
+
+import assert from "node:assert/strict";
+import { readFileSync } from "node:fs";
+import { createRequire } from "node:module";
+import { join } from "node:path";
+import { test } from "node:test";
+
+const { inspect, isText, inspectFile } = createRequire(import.meta.url)("../index.js");
+
+const testdata = new URL("../../testdata/", import.meta.url).pathname;
+
+const corpus = readFileSync(join(testdata, "golden_corpus.tsv"), "utf8")
+  .split("\n")
+  .filter((line) => line && !line.startsWith("#"))
+  .map((line) => line.split("\t"))
+  .filter(([inspector, name]) => inspector === "default" && !name.startsWith("<"));
+
+test("the fixtures are classified like in the Rust tests", async () => {
+  assert.ok(corpus.length > 0);
+  for (const [, name, contentType] of corpus) {
+    const path = join(testdata, name);
+    const data = readFileSync(path);
+    assert.equal(inspect(data), contentType, name);
+    assert.equal(isText(data), contentType !== "binary", name);
+    assert.equal(await inspectFile(path), contentType, name);
+  }
+});
+
+test("empty buffers are UTF-8", () => {
+  assert.equal(inspect(Buffer.alloc(0)), "UTF-8");
+  assert.equal(inspect(Buffer.from("text").subarray(4)), "UTF-8");
+  assert.equal(isText(Buffer.alloc(0)), true);
+});
+
+test("slices of buffers", () => {
+  const data = Buffer.from([0x00, 0x00, 0xff, 0xfe, 0x48, 0x00]);
+  assert.equal(inspect(data), "binary");
+  assert.equal(inspect(data.subarray(2)), "UTF-16LE");
+  assert.equal(isText(data.subarray(2)), true);
+});
+
+test("errors of inspectFile", async () => {
+  const missing = join(testdata, "does-not-exist.txt");
+  await assert.rejects(inspectFile(missing), (error) => error.message.startsWith(missing));
+  await assert.rejects(inspectFile(testdata));
+});
+
+test("invalid arguments", () => {
+  assert.throws(() => inspect("text"));
+  assert.throws(() => isText(42));
+});
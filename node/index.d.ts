@@ -0,0 +1,14 @@
+/* tslint:disable */
+/* eslint-disable */
+
+/* auto-generated by NAPI-RS */
+
+/** The type of content of the buffer, like `"UTF-8"` or `"binary"`. */
+export function inspect(data: Buffer): string
+/** Whether the buffer contains text. */
+export function isText(data: Buffer): boolean
+/**
+ * The type of content of the file at the given path. The start of the file is read on a thread of
+ * the libuv pool, not on the JavaScript thread.
+ */
+export function inspectFile(path: string): Promise<string>
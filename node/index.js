@@ -0,0 +1,2 @@
+// The native module is built by `npm run build` (`napi build --release`).
+module.exports = require("./content-inspector.node");
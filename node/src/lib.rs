@@ -0,0 +1,55 @@
+//! Node.js bindings for `content_inspector`, built with [napi-rs](https://napi.rs):
+//!
+//! ```bash
+//! cd node && npm install && npm run build && npm test
+//! ```
+//!
+//! `inspect` and `isText` use the memory of the `Buffer` directly, without copying it. They use
+//! the default inspector, like `inspectFile`, which reads the start of the file on a thread of the
+//! libuv pool.
+
+use napi::bindgen_prelude::{AsyncTask, BufferSlice};
+use napi::{Env, Error, Result, Status, Task};
+use napi_derive::napi;
+
+/// The type of content of the buffer, like `"UTF-8"` or `"binary"`.
+#[napi]
+pub fn inspect(data: BufferSlice) -> String {
+    content_inspector::inspect(&data).to_string()
+}
+
+/// Whether the buffer contains text.
+#[napi]
+pub fn is_text(data: BufferSlice) -> bool {
+    content_inspector::inspect(&data).is_text()
+}
+
+pub struct InspectFile {
+    path: String,
+}
+
+impl Task for InspectFile {
+    type Output = String;
+    type JsValue = String;
+
+    fn compute(&mut self) -> Result<String> {
+        match content_inspector::inspect_file(&self.path) {
+            Ok(content_type) => Ok(content_type.to_string()),
+            Err(error) => Err(Error::new(
+                Status::GenericFailure,
+                format!("{}: {}", self.path, error),
+            )),
+        }
+    }
+
+    fn resolve(&mut self, _: Env, content_type: String) -> Result<String> {
+        Ok(content_type)
+    }
+}
+
+/// The type of content of the file at the given path. The start of the file is read on a thread of
+/// the libuv pool, not on the JavaScript thread.
+#[napi(ts_return_type = "Promise<string>")]
+pub fn inspect_file(path: String) -> AsyncTask<InspectFile> {
+    AsyncTask::new(InspectFile { path })
+}
@@ -0,0 +1,115 @@
+//! Python bindings for `content_inspector`, built with [maturin](https://www.maturin.rs):
+//!
+//! ```bash
+//! cd python && maturin develop && pytest
+//! ```
+//!
+//! The functions take any bytes-like object (`bytes`, `bytearray`, `memoryview`, ...) and use the
+//! default inspector. Contiguous buffers are not copied, and the GIL is released while they are
+//! inspected, so large buffers can be inspected by several threads at once.
+
+use std::slice;
+
+use content_inspector::{InspectionReport, Reason};
+use pyo3::buffer::PyBuffer;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+/// Call `f` with the contents of a bytes-like object, without the GIL. Buffers that are not
+/// contiguous are copied first.
+fn with_bytes<T, F>(py: Python<'_>, data: &Bound<'_, PyAny>, f: F) -> PyResult<T>
+where
+    T: Send,
+    F: FnOnce(&[u8]) -> T + Send,
+{
+    let buffer = PyBuffer::<u8>::get(data)?;
+    if !buffer.is_c_contiguous() {
+        let bytes = buffer.to_vec(py)?;
+        return Ok(py.allow_threads(|| f(&bytes)));
+    }
+    let bytes = if buffer.len_bytes() == 0 {
+        &[]
+    } else {
+        // SAFETY: the memory stays valid (and a `bytearray` can not be resized) until the buffer
+        // is released, which is after `f` returns
+        unsafe { slice::from_raw_parts(buffer.buf_ptr() as *const u8, buffer.len_bytes()) }
+    };
+    Ok(py.allow_threads(|| f(bytes)))
+}
+
+fn reason_name(reason: Reason) -> &'static str {
+    match reason {
+        Reason::EmptyBuffer => "EmptyBuffer",
+        Reason::ByteOrderMark => "ByteOrderMark",
+        Reason::Utf16Pattern => "Utf16Pattern",
+        Reason::NullBytes => "NullBytes",
+        Reason::MagicNumber => "MagicNumber",
+        Reason::ControlCharacters => "ControlCharacters",
+        Reason::PrintableRatio => "PrintableRatio",
+        Reason::InvalidUtf8 => "InvalidUtf8",
+        Reason::InvalidEncoding => "InvalidEncoding",
+        Reason::Heuristic(_) => "Heuristic",
+        Reason::NoBinaryIndicators => "NoBinaryIndicators",
+        // Reasons that are not listed here yet (`Reason` is non-exhaustive)
+        _ => "Other",
+    }
+}
+
+/// The type of content of a bytes-like object, like `"UTF-8"` or `"binary"`.
+#[pyfunction]
+fn inspect(py: Python<'_>, data: &Bound<'_, PyAny>) -> PyResult<String> {
+    with_bytes(py, data, |bytes| {
+        content_inspector::inspect(bytes).to_string()
+    })
+}
+
+/// Whether a bytes-like object contains text.
+#[pyfunction]
+fn is_text(py: Python<'_>, data: &Bound<'_, PyAny>) -> PyResult<bool> {
+    with_bytes(py, data, |bytes| {
+        content_inspector::inspect(bytes).is_text()
+    })
+}
+
+/// The inspection report of a bytes-like object as a `dict` with the fields of the Rust
+/// `InspectionReport`. The types of content are names, the reason and the binary format are the
+/// names of the variants (with the name of a custom heuristic in `heuristic`), and missing offsets
+/// and formats are `None`.
+#[pyfunction]
+fn inspect_detailed<'py>(
+    py: Python<'py>,
+    data: &Bound<'py, PyAny>,
+) -> PyResult<Bound<'py, PyDict>> {
+    let report: InspectionReport = with_bytes(py, data, content_inspector::inspect_detailed)?;
+    let dict = PyDict::new(py);
+    dict.set_item("content_type", report.content_type.to_string())?;
+    dict.set_item("detected_type", report.detected_type.to_string())?;
+    dict.set_item("reason", reason_name(report.reason))?;
+    if let Reason::Heuristic(heuristic) = report.reason {
+        dict.set_item("heuristic", heuristic)?;
+    }
+    dict.set_item("null_bytes", report.null_bytes)?;
+    dict.set_item("first_null_offset", report.first_null_offset)?;
+    dict.set_item("bom_length", report.bom_length)?;
+    dict.set_item("control_characters", report.control_characters)?;
+    dict.set_item("printable_bytes", report.printable_bytes)?;
+    dict.set_item("invalid_utf8_offset", report.invalid_utf8_offset)?;
+    let binary_format = report.binary_format.map(|format| format!("{:?}", format));
+    dict.set_item("binary_format", binary_format)?;
+    dict.set_item("scanned_bytes", report.scanned_bytes)?;
+    dict.set_item("validated_bytes", report.validated_bytes)?;
+    dict.set_item("sniffed_bytes", report.sniffed_bytes)?;
+    dict.set_item("bytes_examined", report.bytes_examined)?;
+    Ok(dict)
+}
+
+#[pymodule]
+#[pyo3(name = "content_inspector")]
+fn python_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(inspect, m)?)?;
+    m.add_function(wrap_pyfunction!(is_text, m)?)?;
+    m.add_function(wrap_pyfunction!(inspect_detailed, m)?)?;
+    m.add("DETECTION_VERSION", content_inspector::detection_version())?;
+    m.add("__version__", env!("CARGO_PKG_VERSION"))?;
+    Ok(())
+}
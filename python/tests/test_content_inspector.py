@@ -0,0 +1,80 @@
+"""Tests of the Python module, run with `maturin develop && pytest`.
+
+The classification of the fixtures by the default inspector is taken from the golden corpus of
+the Rust tests (`testdata/golden_corpus.tsv`), so that both agree.
+"""
+
+from concurrent.futures import ThreadPoolExecutor
+from pathlib import Path
+
+import pytest
+
+import content_inspector
+
+TESTDATA = Path(__file__).resolve().parents[2] / "testdata"
+
+
+def golden_corpus():
+    """The (file name, content type, reason) of the fixtures for the default inspector."""
+    rows = []
+    for line in (TESTDATA / "golden_corpus.tsv").read_text().splitlines():
+        if line.startswith("#"):
+            continue
+        inspector, name, content_type, reason = line.split("\t")
+        if inspector == "default" and not name.startswith("<"):
+            rows.append((name, content_type, reason))
+    return rows
+
+
+@pytest.mark.parametrize("name, content_type, reason", golden_corpus())
+def test_fixture_corpus(name, content_type, reason):
+    data = (TESTDATA / name).read_bytes()
+    assert content_inspector.inspect(data) == content_type
+    assert content_inspector.is_text(data) == (content_type != "binary")
+
+    report = content_inspector.inspect_detailed(data)
+    assert report["content_type"] == content_type
+    assert report["reason"] == reason
+
+
+def test_detection_version():
+    header = (TESTDATA / "golden_corpus.tsv").read_text().splitlines()[0]
+    assert header == "# detection version {}".format(content_inspector.DETECTION_VERSION)
+
+
+def test_report():
+    report = content_inspector.inspect_detailed(b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR")
+    assert report["content_type"] == "binary"
+    assert report["reason"] == "MagicNumber"
+    assert report["binary_format"] == "Png"
+
+    report = content_inspector.inspect_detailed(b"text\x00")
+    assert report["reason"] == "NullBytes"
+    assert report["null_bytes"] == 1
+    assert report["first_null_offset"] == 4
+    assert report["binary_format"] is None
+    assert report["invalid_utf8_offset"] is None
+
+    report = content_inspector.inspect_detailed(b"")
+    assert report["content_type"] == "UTF-8"
+    assert report["reason"] == "EmptyBuffer"
+
+
+def test_bytes_like():
+    data = b"\xff\xfeH\x00i\x00"
+    assert content_inspector.inspect(bytearray(data)) == "UTF-16LE"
+    assert content_inspector.inspect(memoryview(data)) == "UTF-16LE"
+    assert content_inspector.inspect(memoryview(b"xx" + data)[2:]) == "UTF-16LE"
+    # Not contiguous, so it is copied
+    assert content_inspector.inspect(memoryview(b"t\x00e\x00x\x00t\x00")[::2]) == "UTF-8"
+    assert content_inspector.is_text(memoryview(bytearray(b"text")))
+
+    with pytest.raises(TypeError):
+        content_inspector.inspect("text")
+
+
+def test_threads():
+    data = (TESTDATA / "text_UTF-8.txt").read_bytes() * 10000
+    with ThreadPoolExecutor(max_workers=4) as executor:
+        results = list(executor.map(content_inspector.inspect, [data] * 16))
+    assert results == ["UTF-8"] * 16
@@ -0,0 +1,319 @@
+use std::io;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use tokio::io::{AsyncRead, AsyncReadExt, ReadBuf};
+use tokio::sync::watch;
+
+use crate::{default_inspector, ContentType, Inspector, StreamingInspector};
+
+impl Inspector {
+    /// Like [`inspect_reader`](#method.inspect_reader), but reads the content from the given
+    /// asynchronous reader.
+    ///
+    /// If the future is dropped before it completes, the bytes that have been read so far are
+    /// lost, as with [`read_to_end`](https://docs.rs/tokio/1/tokio/io/trait.AsyncReadExt.html#method.read_to_end).
+    pub async fn inspect_async_reader<R: AsyncRead + Unpin + ?Sized>(
+        &self,
+        reader: &mut R,
+    ) -> io::Result<ContentType> {
+        let mut buffer = vec![];
+        match self.read_limit() {
+            Some(limit) => reader.take(limit as u64).read_to_end(&mut buffer).await?,
+            None => reader.read_to_end(&mut buffer).await?,
+        };
+        Ok(self.inspect(&buffer))
+    }
+}
+
+/// Like [`inspect_reader`](fn.inspect_reader.html), but reads the content from the given
+/// asynchronous reader. Only the first 1024 bytes are read.
+///
+/// ```
+/// use content_inspector::{inspect_async_reader, ContentType};
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() -> std::io::Result<()> {
+/// let mut reader = &b"Hello"[..];
+///
+/// assert_eq!(ContentType::UTF_8, inspect_async_reader(&mut reader).await?);
+/// # Ok(())
+/// # }
+/// ```
+pub async fn inspect_async_reader<R: AsyncRead + Unpin + ?Sized>(
+    reader: &mut R,
+) -> io::Result<ContentType> {
+    default_inspector().inspect_async_reader(reader).await
+}
+
+/// An asynchronous reader that passes all content through from the wrapped reader, while
+/// determining its type. This is the asynchronous counterpart of
+/// [`InspectingReader`](struct.InspectingReader.html).
+///
+/// ```
+/// use content_inspector::{ContentType, InspectingAsyncReader};
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() -> std::io::Result<()> {
+/// let mut reader = InspectingAsyncReader::new(&b"\x00\x01\x02"[..]);
+/// let mut verdict = reader.watch();
+///
+/// tokio::io::copy(&mut reader, &mut tokio::io::sink()).await?;
+///
+/// let content_type = *verdict.wait_for(Option::is_some).await.unwrap();
+/// assert_eq!(Some(ContentType::BINARY), content_type);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct InspectingAsyncReader<R> {
+    inner: R,
+    streaming: StreamingInspector,
+    sender: watch::Sender<Option<ContentType>>,
+}
+
+impl<R: AsyncRead + Unpin> InspectingAsyncReader<R> {
+    /// Wraps the given reader, using the settings of [`inspect`](fn.inspect.html).
+    pub fn new(inner: R) -> InspectingAsyncReader<R> {
+        InspectingAsyncReader::with_inspector(default_inspector(), inner)
+    }
+
+    /// Wraps the given reader, using the settings of the given `Inspector`.
+    pub fn with_inspector(inspector: &Inspector, inner: R) -> InspectingAsyncReader<R> {
+        InspectingAsyncReader {
+            inner,
+            streaming: inspector.streaming(),
+            sender: watch::Sender::new(None),
+        }
+    }
+
+    /// The type of content, once enough content has been read to determine it or the end of the
+    /// wrapped reader has been reached.
+    pub fn content_type(&self) -> Option<ContentType> {
+        *self.sender.borrow()
+    }
+
+    /// Returns a receiver that is notified once the type of content has been determined, for
+    /// example with `receiver.wait_for(Option::is_some).await`. Waiting fails if the reader is
+    /// dropped before that.
+    pub fn watch(&self) -> watch::Receiver<Option<ContentType>> {
+        self.sender.subscribe()
+    }
+
+    /// Returns a reference to the wrapped reader.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Returns the wrapped reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for InspectingAsyncReader<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let before = buf.filled().len();
+        ready!(Pin::new(&mut this.inner).poll_read(cx, buf))?;
+
+        if this.content_type().is_none() {
+            let filled = &buf.filled()[before..];
+            let content_type = if filled.is_empty() {
+                // The end of the reader, unless there was no room to read into
+                Some(this.streaming.result()).filter(|_| buf.remaining() > 0)
+            } else {
+                this.streaming.push(filled);
+                this.streaming.current()
+            };
+            if content_type.is_some() {
+                this.sender.send_replace(content_type);
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::Future;
+    use std::io;
+    use std::pin::Pin;
+    use std::task::{Context, Poll, Waker};
+
+    use tokio::io::{AsyncRead, ReadBuf};
+
+    use crate::test_util::text_with_null_at;
+    use crate::{
+        inspect_async_reader, ContentType::*, InspectingAsyncReader, Inspector, Strictness,
+    };
+
+    /// A reader that yields a single byte per read, and is not ready before every byte.
+    struct PendingReader<'a> {
+        data: &'a [u8],
+        ready: bool,
+        polls: usize,
+    }
+
+    impl<'a> AsyncRead for PendingReader<'a> {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            self.polls += 1;
+            self.ready = !self.ready;
+            if !self.ready {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            match self.data.split_first() {
+                Some((&byte, rest)) if buf.remaining() > 0 => {
+                    buf.put_slice(&[byte]);
+                    self.data = rest;
+                }
+                _ => {}
+            }
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    fn pending(data: &[u8]) -> PendingReader<'_> {
+        PendingReader {
+            data,
+            ready: true,
+            polls: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_inspect_async_reader() {
+        let png = include_bytes!("../testdata/test.png");
+        assert_eq!(BINARY, inspect_async_reader(&mut &png[..]).await.unwrap());
+        assert_eq!(UTF_8, inspect_async_reader(&mut &b""[..]).await.unwrap());
+
+        let utf32 = include_bytes!("../testdata/text_UTF-32LE-BOM.txt");
+        let mut reader = pending(utf32);
+        assert_eq!(UTF_32LE, inspect_async_reader(&mut reader).await.unwrap());
+
+        let buffer = text_with_null_at(1023);
+        let mut reader = pending(&buffer);
+        assert_eq!(BINARY, inspect_async_reader(&mut reader).await.unwrap());
+        assert_eq!(3000 - 1024, reader.data.len());
+        assert_eq!(2 * 1024, reader.polls);
+
+        let buffer = text_with_null_at(1024);
+        assert_eq!(
+            UTF_8,
+            inspect_async_reader(&mut pending(&buffer)).await.unwrap()
+        );
+
+        let paranoid = Inspector::new()
+            .strictness(Strictness::Paranoid)
+            .build()
+            .unwrap();
+        let mut reader = pending(&buffer);
+        assert_eq!(
+            BINARY,
+            paranoid.inspect_async_reader(&mut reader).await.unwrap()
+        );
+        assert!(reader.data.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_inspect_async_reader_cancelled() {
+        let buffer = text_with_null_at(10);
+        let mut reader = pending(&buffer);
+        {
+            let future = inspect_async_reader(&mut reader);
+            tokio::pin!(future);
+            let mut cx = Context::from_waker(Waker::noop());
+            for _ in 0..9 {
+                assert!(future.as_mut().poll(&mut cx).is_pending());
+            }
+        }
+        // The dropped future has consumed eight bytes, and the reader can still be used
+        assert_eq!(3000 - 8, reader.data.len());
+        assert_eq!(BINARY, inspect_async_reader(&mut reader).await.unwrap());
+        assert_eq!(3000 - 8 - 1024, reader.data.len());
+    }
+
+    #[tokio::test]
+    async fn test_inspect_async_reader_error() {
+        struct FailingReader;
+
+        impl AsyncRead for FailingReader {
+            fn poll_read(
+                self: Pin<&mut Self>,
+                _cx: &mut Context<'_>,
+                _buf: &mut ReadBuf<'_>,
+            ) -> Poll<io::Result<()>> {
+                Poll::Ready(Err(io::Error::other("connection reset")))
+            }
+        }
+
+        let error = inspect_async_reader(&mut FailingReader).await.unwrap_err();
+        assert_eq!("connection reset", error.to_string());
+    }
+
+    #[tokio::test]
+    async fn test_inspecting_async_reader() {
+        let png = include_bytes!("../testdata/test.png");
+        let mut reader = InspectingAsyncReader::new(pending(png));
+        let mut verdict = reader.watch();
+        let waiting =
+            tokio::spawn(async move { *verdict.wait_for(Option::is_some).await.unwrap() });
+
+        let mut content = vec![];
+        tokio::io::copy(&mut reader, &mut content).await.unwrap();
+        assert_eq!(&png[..], &content[..]);
+        assert_eq!(Some(BINARY), reader.content_type());
+        assert_eq!(Some(BINARY), waiting.await.unwrap());
+
+        let buffer = text_with_null_at(1024);
+        let mut reader = InspectingAsyncReader::new(&buffer[..]);
+        tokio::io::copy(&mut reader, &mut tokio::io::sink())
+            .await
+            .unwrap();
+        assert_eq!(Some(UTF_8), reader.content_type());
+        assert!(reader.into_inner().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_inspecting_async_reader_early_eof() {
+        let mut reader = InspectingAsyncReader::new(pending(b"short"));
+        let mut buffer = [0; 3];
+        let n = tokio::io::AsyncReadExt::read(&mut reader, &mut buffer)
+            .await
+            .unwrap();
+        assert_eq!(1, n);
+        assert_eq!(None, reader.content_type());
+        // Reading into an empty buffer does not signal the end of the reader
+        tokio::io::AsyncReadExt::read(&mut reader, &mut [])
+            .await
+            .unwrap();
+        assert_eq!(None, reader.content_type());
+
+        tokio::io::copy(&mut reader, &mut tokio::io::sink())
+            .await
+            .unwrap();
+        assert_eq!(Some(UTF_8), reader.content_type());
+
+        let inspector = Inspector::new().latin1_fallback(true).build().unwrap();
+        let latin1 = include_bytes!("../testdata/text_Latin-1.txt");
+        let mut reader = InspectingAsyncReader::with_inspector(&inspector, pending(latin1));
+        tokio::io::copy(&mut reader, &mut tokio::io::sink())
+            .await
+            .unwrap();
+        assert_eq!(Some(LATIN_1), reader.content_type());
+
+        let reader = InspectingAsyncReader::new(pending(b""));
+        let mut verdict = reader.watch();
+        drop(reader);
+        assert!(verdict.wait_for(Option::is_some).await.is_err());
+    }
+}
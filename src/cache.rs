@@ -0,0 +1,358 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use crate::{ContentType, Inspector};
+
+#[derive(Clone, Debug)]
+struct Entry {
+    modified: SystemTime,
+    size: u64,
+    fingerprint: u64,
+    content_type: ContentType,
+    last_used: u64,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    entries: HashMap<PathBuf, Entry>,
+    /// The paths of the entries, from the least to the most recently used.
+    order: BTreeMap<u64, PathBuf>,
+    clock: u64,
+}
+
+impl State {
+    fn touch(&mut self, path: &Path) {
+        self.clock += 1;
+        if let Some(entry) = self.entries.get_mut(path) {
+            self.order.remove(&entry.last_used);
+            entry.last_used = self.clock;
+            self.order.insert(self.clock, path.to_path_buf());
+        }
+    }
+}
+
+/// The canonical path of the file, with its modification time and size.
+fn key(path: &Path) -> io::Result<(PathBuf, SystemTime, u64)> {
+    let path = fs::canonicalize(path)?;
+    let metadata = fs::metadata(&path)?;
+    Ok((path, metadata.modified()?, metadata.len()))
+}
+
+/// A cache of the results of [`Inspector::inspect_file`](struct.Inspector.html#method.inspect_file),
+/// for programs that inspect the same files over and over again (like an editor).
+///
+/// The entries are keyed by the canonical path of the file. An entry is only used if the
+/// modification time and the size of the file are unchanged, and it was created by an inspector
+/// with the same [`fingerprint`](struct.Inspector.html#method.fingerprint) (which includes the
+/// [`detection_version`](fn.detection_version.html)). If the cache is full, the least recently
+/// used entry is evicted.
+///
+/// The cache can be shared between threads. Files are inspected without holding its lock.
+///
+/// Note that a change that keeps both the size and the modification time (which may have a
+/// coarse resolution) of a file goes unnoticed.
+///
+/// ```no_run
+/// use content_inspector::{InspectionCache, Inspector};
+///
+/// # fn main() -> std::io::Result<()> {
+/// let cache = InspectionCache::new(1000);
+/// let inspector = Inspector::default();
+///
+/// // Only the first call reads the file
+/// assert!(cache.get_or_inspect("Cargo.toml", &inspector)?.is_text());
+/// assert!(cache.get_or_inspect("Cargo.toml", &inspector)?.is_text());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct InspectionCache {
+    capacity: usize,
+    state: Mutex<State>,
+}
+
+impl InspectionCache {
+    /// Create an empty cache for the results of at most `capacity` files. Nothing is cached if
+    /// the capacity is zero.
+    pub fn new(capacity: usize) -> InspectionCache {
+        InspectionCache {
+            capacity,
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    /// The maximum number of entries.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The number of entries, including those that are out of date.
+    pub fn len(&self) -> usize {
+        self.state().entries.len()
+    }
+
+    /// Whether there are no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Remove all entries.
+    pub fn clear(&self) {
+        *self.state() = State::default();
+    }
+
+    /// Return the cached type of content of the file at the given path, or inspect the file with
+    /// the given `Inspector` (like [`Inspector::inspect_file`](struct.Inspector.html#method.inspect_file))
+    /// if there is no valid entry, and cache the result.
+    pub fn get_or_inspect<P: AsRef<Path>>(
+        &self,
+        path: P,
+        inspector: &Inspector,
+    ) -> io::Result<ContentType> {
+        let (path, modified, size) = key(path.as_ref())?;
+        let fingerprint = inspector.fingerprint();
+        if let Some(content_type) = self.lookup(&path, modified, size, fingerprint) {
+            return Ok(content_type);
+        }
+
+        // The metadata was read before the content, so a change during the inspection leads to
+        // a new inspection next time.
+        let content_type = inspector.inspect_file(&path)?;
+        if self.capacity == 0 {
+            return Ok(content_type);
+        }
+
+        let mut state = self.state();
+        if let Some(entry) = state.entries.remove(&path) {
+            state.order.remove(&entry.last_used);
+        }
+        while state.entries.len() >= self.capacity {
+            let (_, oldest) = match state.order.pop_first() {
+                Some(oldest) => oldest,
+                None => break,
+            };
+            state.entries.remove(&oldest);
+        }
+        state.entries.insert(
+            path.clone(),
+            Entry {
+                modified,
+                size,
+                fingerprint,
+                content_type,
+                last_used: 0,
+            },
+        );
+        state.touch(&path);
+        Ok(content_type)
+    }
+
+    /// Return the cached type of content of the file at the given path if there is a valid entry
+    /// for the given `Inspector`, without reading the file (only its metadata), or `None` if it
+    /// would be inspected by [`get_or_inspect`](#method.get_or_inspect).
+    pub fn get<P: AsRef<Path>>(
+        &self,
+        path: P,
+        inspector: &Inspector,
+    ) -> io::Result<Option<ContentType>> {
+        let (path, modified, size) = key(path.as_ref())?;
+        Ok(self.lookup(&path, modified, size, inspector.fingerprint()))
+    }
+
+    fn lookup(
+        &self,
+        path: &Path,
+        modified: SystemTime,
+        size: u64,
+        fingerprint: u64,
+    ) -> Option<ContentType> {
+        let mut state = self.state();
+        let entry = state.entries.get(path)?;
+        if entry.modified != modified || entry.size != size || entry.fingerprint != fingerprint {
+            return None;
+        }
+        let content_type = entry.content_type;
+        state.touch(path);
+        Some(content_type)
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        // The state is consistent after every operation, so a panic elsewhere does not matter
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs::{self, File};
+    use std::path::{Path, PathBuf};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::{Duration, SystemTime};
+
+    use crate::{ContentType::*, InspectionCache, Inspector};
+
+    /// A temporary file that is removed when dropped.
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str, content: &[u8]) -> TempFile {
+            let path = env::temp_dir().join(format!(
+                "content_inspector_cache_{}_{}",
+                ::std::process::id(),
+                name
+            ));
+            fs::write(&path, content).unwrap();
+            TempFile(path)
+        }
+
+        /// Replace the content, keeping the modification time.
+        fn replace(&self, content: &[u8]) {
+            let modified = fs::metadata(&self.0).unwrap().modified().unwrap();
+            fs::write(&self.0, content).unwrap();
+            set_modified(&self.0, modified);
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    fn set_modified(path: &Path, modified: SystemTime) {
+        let file = File::options().write(true).open(path).unwrap();
+        file.set_modified(modified).unwrap();
+    }
+
+    fn cached(cache: &InspectionCache, file: &TempFile) -> bool {
+        let path = fs::canonicalize(&file.0).unwrap();
+        cache.state().entries.contains_key(&path)
+    }
+
+    #[test]
+    fn test_cache_hit() {
+        let file = TempFile::new("hit", b"text");
+        let cache = InspectionCache::new(10);
+        let inspector = Inspector::default();
+
+        assert_eq!(None, cache.get(&file.0, &inspector).unwrap());
+        assert_eq!(UTF_8, cache.get_or_inspect(&file.0, &inspector).unwrap());
+        assert_eq!(1, cache.len());
+        assert_eq!(Some(UTF_8), cache.get(&file.0, &inspector).unwrap());
+        let strict = Inspector::new()
+            .strictness(crate::Strictness::Strict)
+            .build()
+            .unwrap();
+        assert_eq!(None, cache.get(&file.0, &strict).unwrap());
+
+        // The file is not read again as long as its size and modification time are unchanged
+        file.replace(b"\x00\x00\x00\x00");
+        assert_eq!(UTF_8, cache.get_or_inspect(&file.0, &inspector).unwrap());
+        assert_eq!(BINARY, inspector.inspect_file(&file.0).unwrap());
+
+        // Another path to the same file shares the entry
+        let dir = file.0.parent().unwrap();
+        let other = dir.join(".").join(file.0.file_name().unwrap());
+        assert_eq!(UTF_8, cache.get_or_inspect(&other, &inspector).unwrap());
+        assert_eq!(1, cache.len());
+
+        assert!(cache
+            .get_or_inspect(dir.join("missing"), &inspector)
+            .is_err());
+
+        cache.clear();
+        assert!(cache.is_empty());
+        assert_eq!(BINARY, cache.get_or_inspect(&file.0, &inspector).unwrap());
+    }
+
+    #[test]
+    fn test_cache_invalidation() {
+        let file = TempFile::new("invalidation", b"text");
+        let cache = InspectionCache::new(10);
+        let inspector = Inspector::default();
+        assert_eq!(UTF_8, cache.get_or_inspect(&file.0, &inspector).unwrap());
+
+        // Touching the file
+        file.replace(b"\x00\x00\x00\x00");
+        set_modified(&file.0, SystemTime::now() + Duration::from_secs(10));
+        assert_eq!(BINARY, cache.get_or_inspect(&file.0, &inspector).unwrap());
+
+        // A different size
+        file.replace(b"more text");
+        assert_eq!(UTF_8, cache.get_or_inspect(&file.0, &inspector).unwrap());
+
+        // Another configuration
+        file.replace(b"\xFF\xFEtext\x00\x00");
+        assert_eq!(UTF_16LE, cache.get_or_inspect(&file.0, &inspector).unwrap());
+        let no_bom = Inspector::new().detect_bom(false).build().unwrap();
+        assert_eq!(BINARY, cache.get_or_inspect(&file.0, &no_bom).unwrap());
+        assert_eq!(UTF_16LE, cache.get_or_inspect(&file.0, &inspector).unwrap());
+        assert_eq!(1, cache.len());
+    }
+
+    #[test]
+    fn test_cache_eviction() {
+        let files: Vec<_> = (0..3)
+            .map(|i| TempFile::new(&format!("eviction_{}", i), b"text"))
+            .collect();
+        let cache = InspectionCache::new(2);
+        let inspector = Inspector::default();
+
+        cache.get_or_inspect(&files[0].0, &inspector).unwrap();
+        cache.get_or_inspect(&files[1].0, &inspector).unwrap();
+        // The first file is now used more recently than the second one
+        cache.get_or_inspect(&files[0].0, &inspector).unwrap();
+        cache.get_or_inspect(&files[2].0, &inspector).unwrap();
+
+        assert_eq!(2, cache.len());
+        assert!(cached(&cache, &files[0]));
+        assert!(!cached(&cache, &files[1]));
+        assert!(cached(&cache, &files[2]));
+        let state = cache.state();
+        assert_eq!(state.entries.len(), state.order.len());
+        drop(state);
+
+        let disabled = InspectionCache::new(0);
+        assert_eq!(
+            UTF_8,
+            disabled.get_or_inspect(&files[0].0, &inspector).unwrap()
+        );
+        assert!(disabled.is_empty());
+    }
+
+    #[test]
+    fn test_cache_threads() {
+        let files: Arc<Vec<_>> = Arc::new(
+            (0..8)
+                .map(|i| TempFile::new(&format!("threads_{}", i), b"text"))
+                .collect(),
+        );
+        let cache = Arc::new(InspectionCache::new(4));
+        let threads: Vec<_> = (0..4)
+            .map(|t| {
+                let files = files.clone();
+                let cache = cache.clone();
+                thread::spawn(move || {
+                    let inspector = Inspector::default();
+                    for i in 0..100 {
+                        let file = &files[(i * (t + 1)) % files.len()];
+                        assert_eq!(UTF_8, cache.get_or_inspect(&file.0, &inspector).unwrap());
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!(4, cache.len());
+        assert_eq!(4, cache.state().order.len());
+    }
+}
@@ -0,0 +1,242 @@
+use core::error::Error;
+use core::fmt;
+use core::ops::Range;
+use core::str;
+
+use crate::decode::bom;
+use crate::units::{u16_unit, u32_unit};
+use crate::{ContentType, DecodeError, DecodeErrorKind};
+
+/// An invalid sequence that was found by [`code_points`](fn.code_points.html).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CodePointError {
+    /// The bytes of the invalid sequence in the buffer (including a byte order mark).
+    pub range: Range<usize>,
+
+    /// The kind of problem.
+    pub kind: DecodeErrorKind,
+}
+
+impl fmt::Display for CodePointError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let error = DecodeError {
+            offset: self.range.start,
+            kind: self.kind,
+        };
+        write!(f, "{}", error)
+    }
+}
+
+impl Error for CodePointError {}
+
+/// An iterator over the code points of a buffer, see [`code_points`](fn.code_points.html).
+#[derive(Clone, Debug)]
+pub struct CodePoints<'a> {
+    buffer: &'a [u8],
+    position: usize,
+    content_type: ContentType,
+}
+
+impl<'a> CodePoints<'a> {
+    fn next_utf8(&self) -> (usize, Result<char, DecodeErrorKind>) {
+        // A character is at most four bytes long
+        let rest = &self.buffer[self.position..];
+        let head = &rest[..rest.len().min(4)];
+        let valid = match str::from_utf8(head) {
+            Ok(valid) => valid,
+            Err(error) if error.valid_up_to() > 0 => {
+                str::from_utf8(&head[..error.valid_up_to()]).unwrap_or_default()
+            }
+            Err(error) => {
+                let length = error.error_len().unwrap_or(head.len());
+                return (length, Err(DecodeErrorKind::InvalidUtf8));
+            }
+        };
+        let c = valid.chars().next().unwrap_or_default();
+        (c.len_utf8(), Ok(c))
+    }
+
+    fn next_utf16(&self, big_endian: bool) -> (usize, Result<char, DecodeErrorKind>) {
+        let unit = |i: usize| u16_unit([self.buffer[i], self.buffer[i + 1]], big_endian);
+        let i = self.position;
+        if self.buffer.len() - i < 2 {
+            return (1, Err(DecodeErrorKind::IncompleteCodeUnit));
+        }
+        let first = unit(i);
+        let second = Some(i + 2)
+            .filter(|&j| self.buffer.len() - j >= 2)
+            .map(unit);
+        match char::decode_utf16(Some(first).into_iter().chain(second)).next() {
+            Some(Ok(c)) => (2 * c.len_utf16(), Ok(c)),
+            _ => (2, Err(DecodeErrorKind::UnpairedSurrogate(first))),
+        }
+    }
+
+    fn next_utf32(&self, big_endian: bool) -> (usize, Result<char, DecodeErrorKind>) {
+        let rest = &self.buffer[self.position..];
+        if rest.len() < 4 {
+            return (rest.len(), Err(DecodeErrorKind::IncompleteCodeUnit));
+        }
+        let value = u32_unit([rest[0], rest[1], rest[2], rest[3]], big_endian);
+        (
+            4,
+            char::from_u32(value).ok_or(DecodeErrorKind::InvalidScalar(value)),
+        )
+    }
+}
+
+impl<'a> Iterator for CodePoints<'a> {
+    type Item = Result<(Range<usize>, char), CodePointError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.position == self.buffer.len() {
+            return None;
+        }
+        let (length, result) = match self.content_type {
+            ContentType::UTF_16LE => self.next_utf16(false),
+            ContentType::UTF_16BE => self.next_utf16(true),
+            ContentType::UTF_32LE => self.next_utf32(false),
+            ContentType::UTF_32BE => self.next_utf32(true),
+            ContentType::LATIN_1 => (1, Ok(char::from(self.buffer[self.position]))),
+            _ => self.next_utf8(),
+        };
+        let range = self.position..self.position + length;
+        self.position = range.end;
+        Some(match result {
+            Ok(c) => Ok((range, c)),
+            Err(kind) => Err(CodePointError { range, kind }),
+        })
+    }
+}
+
+/// Returns an iterator over the code points of a buffer of the given type, together with the
+/// bytes that each one occupies in the buffer (including a byte order mark, which is skipped).
+///
+/// Invalid sequences are returned as errors, after which the iteration continues: invalid UTF-8
+/// like in [`decode_lossy`](fn.decode_lossy.html), unpaired UTF-16 surrogates, invalid UTF-32
+/// values and an incomplete code unit at the end. `ASCII` and `BINARY` content is decoded like
+/// UTF-8.
+///
+/// ```
+/// use content_inspector::{code_points, ContentType, DecodeErrorKind};
+///
+/// let buffer = b"\xFF\xFEa\x00\x3D\xD8b\x00";
+/// let mut code_points = code_points(buffer, ContentType::UTF_16LE);
+///
+/// assert_eq!(Some(Ok((2..4, 'a'))), code_points.next());
+/// let error = code_points.next().unwrap().unwrap_err();
+/// assert_eq!(4..6, error.range);
+/// assert_eq!(DecodeErrorKind::UnpairedSurrogate(0xD83D), error.kind);
+/// assert_eq!(Some(Ok((6..8, 'b'))), code_points.next());
+/// assert_eq!(None, code_points.next());
+/// ```
+pub fn code_points(buffer: &[u8], content_type: ContentType) -> CodePoints<'_> {
+    let bom = bom(content_type);
+    CodePoints {
+        buffer,
+        position: if buffer.starts_with(bom) {
+            bom.len()
+        } else {
+            0
+        },
+        content_type,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use crate::{code_points, CodePointError, ContentType::*, DecodeErrorKind::*};
+
+    #[test]
+    fn test_code_points_testdata() {
+        let testdata = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata");
+        let text = fs::read_to_string(testdata.join("text_UTF-8.txt")).unwrap();
+
+        let utf8: Vec<_> = code_points(text.as_bytes(), UTF_8)
+            .map(Result::unwrap)
+            .collect();
+        let expected: Vec<_> = text
+            .char_indices()
+            .map(|(i, c)| (i..i + c.len_utf8(), c))
+            .collect();
+        assert_eq!(expected, utf8);
+
+        // Offsets in UTF-16 are twice the UTF-16 index, after the byte order mark
+        let buffer = fs::read(testdata.join("text_UTF-16LE-BOM.txt")).unwrap();
+        let utf16: Vec<_> = code_points(&buffer, UTF_16LE).map(Result::unwrap).collect();
+        let mut position = 2;
+        let expected: Vec<_> = text
+            .chars()
+            .map(|c| {
+                let start = position;
+                position += 2 * c.len_utf16();
+                (start..position, c)
+            })
+            .collect();
+        assert_eq!(expected, utf16);
+        assert_eq!(buffer.len(), position);
+    }
+
+    #[test]
+    fn test_code_points_invalid() {
+        let collect = |buffer: &[u8], content_type| {
+            code_points(buffer, content_type)
+                .map(|item| item.map_err(|CodePointError { range, kind }| (range, kind)))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            vec![
+                Ok((3..4, 'a')),
+                Err((4..5, InvalidUtf8)),
+                Err((5..7, InvalidUtf8)),
+                Ok((7..10, '€')),
+                Err((10..12, InvalidUtf8)),
+            ],
+            collect(b"\xEF\xBB\xBFa\xFF\xE2\x82\xE2\x82\xAC\xF0\x9F", UTF_8_BOM)
+        );
+        assert_eq!(
+            vec![
+                Err((0..2, UnpairedSurrogate(0xDE00))),
+                Ok((2..6, '😀')),
+                Err((6..8, UnpairedSurrogate(0xD83D))),
+                Ok((8..10, 'a')),
+                Err((10..12, UnpairedSurrogate(0xD83D))),
+                Err((12..13, IncompleteCodeUnit)),
+            ],
+            collect(
+                b"\xDE\x00\xD8\x3D\xDE\x00\xD8\x3D\x00a\xD8\x3D\x00",
+                UTF_16BE
+            )
+        );
+        assert_eq!(
+            vec![
+                Ok((4..8, 'a')),
+                Err((8..12, InvalidScalar(0x110000))),
+                Err((12..15, IncompleteCodeUnit)),
+            ],
+            collect(
+                b"\xFF\xFE\x00\x00a\x00\x00\x00\x00\x00\x11\x00\x00\x00\x00",
+                UTF_32LE
+            )
+        );
+        assert_eq!(
+            vec![Ok((0..1, 'c')), Ok((1..2, 'é'))],
+            collect(b"c\xE9", LATIN_1)
+        );
+        assert!(collect(b"\xFF\xFE", UTF_16LE).is_empty());
+        assert!(collect(b"", BINARY).is_empty());
+
+        let error = code_points(b"a\x00\x3D\xD8", UTF_16LE)
+            .nth(1)
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(
+            "unpaired UTF-16 surrogate 0xD83D at byte 2",
+            error.to_string()
+        );
+    }
+}
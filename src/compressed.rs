@@ -0,0 +1,204 @@
+use std::io::{self, Cursor, Read};
+use std::path::Path;
+
+use crate::reader::open_regular_file;
+use crate::{default_inspector, known_format, BinaryFormat, ContentType, Inspector};
+
+/// The result of [`inspect_compressed`](fn.inspect_compressed.html).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct CompressedInspection {
+    /// The compression format (`Gzip` or `Zstd`), or `None` if the content is not compressed with
+    /// one of the supported formats.
+    pub container: Option<BinaryFormat>,
+
+    /// The type of the decompressed content, or of the content itself if it is not compressed.
+    pub content_type: ContentType,
+}
+
+/// The number of bytes that identify a compression format.
+const MAGIC_LENGTH: usize = 4;
+
+/// Read up to `limit` bytes from the reader, or until the end if there is no limit. Truncated
+/// streams yield the content up to the point where they end.
+fn read_decompressed<R: Read>(mut decoder: R, limit: Option<usize>) -> io::Result<Vec<u8>> {
+    let mut buffer = vec![];
+    let result = match limit {
+        Some(limit) => (&mut decoder).take(limit as u64).read_to_end(&mut buffer),
+        None => decoder.read_to_end(&mut buffer),
+    };
+    match result {
+        Ok(_) => Ok(buffer),
+        Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(buffer),
+        Err(e) => Err(e),
+    }
+}
+
+impl Inspector {
+    /// Like [`inspect_reader`](#method.inspect_reader), but if the content is compressed with
+    /// gzip or Zstandard (with the `flate2` and `zstd` features, respectively), the type of the
+    /// decompressed content is determined. Only the examined part of the content is
+    /// decompressed.
+    ///
+    /// Corrupt compressed data results in an error, while the result for a truncated stream is
+    /// based on the content that could be decompressed.
+    pub fn inspect_compressed<R: Read>(&self, mut reader: R) -> io::Result<CompressedInspection> {
+        let mut magic = vec![];
+        (&mut reader)
+            .take(MAGIC_LENGTH as u64)
+            .read_to_end(&mut magic)?;
+
+        let limit = self.read_limit();
+        let stream = Cursor::new(&magic[..]).chain(reader);
+        // The containers are recognized without the `magic-numbers` feature as well
+        let (container, content) = match known_format(&magic) {
+            #[cfg(feature = "flate2")]
+            Some(BinaryFormat::Gzip) => (
+                Some(BinaryFormat::Gzip),
+                read_decompressed(flate2::read::MultiGzDecoder::new(stream), limit)?,
+            ),
+            #[cfg(feature = "zstd")]
+            Some(BinaryFormat::Zstd) => (
+                Some(BinaryFormat::Zstd),
+                read_decompressed(zstd::stream::read::Decoder::new(stream)?, limit)?,
+            ),
+            _ => (None, read_decompressed(stream, limit)?),
+        };
+
+        Ok(CompressedInspection {
+            container,
+            content_type: self.inspect(&content),
+        })
+    }
+
+    /// Like [`inspect_compressed`](#method.inspect_compressed), but opens the file at the given
+    /// path. Directories and special files are handled like in
+    /// [`inspect_file`](#method.inspect_file).
+    pub fn inspect_compressed_file<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> io::Result<CompressedInspection> {
+        let file = open_regular_file(path.as_ref())?;
+        self.inspect_compressed(file)
+    }
+}
+
+/// Like [`inspect_reader`](fn.inspect_reader.html), but determines the type of the decompressed
+/// content if the content is compressed with gzip or Zstandard. See
+/// [`Inspector::inspect_compressed`](struct.Inspector.html#method.inspect_compressed) for
+/// details.
+///
+/// ```no_run
+/// use std::fs::File;
+/// use content_inspector::{inspect_compressed, BinaryFormat, ContentType};
+///
+/// # fn main() -> std::io::Result<()> {
+/// let result = inspect_compressed(File::open("access.log.gz")?)?;
+///
+/// assert_eq!(Some(BinaryFormat::Gzip), result.container);
+/// assert_eq!(ContentType::UTF_8, result.content_type);
+/// # Ok(())
+/// # }
+/// ```
+pub fn inspect_compressed<R: Read>(reader: R) -> io::Result<CompressedInspection> {
+    default_inspector().inspect_compressed(reader)
+}
+
+/// Like [`inspect_compressed`](fn.inspect_compressed.html), but opens the file at the given path.
+pub fn inspect_compressed_file<P: AsRef<Path>>(path: P) -> io::Result<CompressedInspection> {
+    default_inspector().inspect_compressed_file(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::CompressedInspection;
+    use crate::{
+        inspect_compressed, inspect_compressed_file, BinaryFormat, ContentType, ContentType::*,
+    };
+
+    fn testdata(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("testdata")
+            .join(name)
+    }
+
+    fn inspection(
+        container: Option<BinaryFormat>,
+        content_type: ContentType,
+    ) -> CompressedInspection {
+        CompressedInspection {
+            container,
+            content_type,
+        }
+    }
+
+    #[test]
+    fn test_uncompressed() {
+        let result = inspect_compressed_file(testdata("text_UTF-8.txt")).unwrap();
+        assert_eq!(inspection(None, UTF_8), result);
+        let result = inspect_compressed_file(testdata("test.png")).unwrap();
+        assert_eq!(inspection(None, BINARY), result);
+        assert_eq!(
+            inspection(None, UTF_8),
+            inspect_compressed(&b""[..]).unwrap()
+        );
+        assert_eq!(
+            inspection(None, UTF_8),
+            inspect_compressed(&b"ab"[..]).unwrap()
+        );
+    }
+
+    #[cfg(feature = "flate2")]
+    #[test]
+    fn test_gzip() {
+        use std::io::{self, Write};
+
+        use flate2::write::GzEncoder;
+        use flate2::Compression;
+
+        let gzip = Some(BinaryFormat::Gzip);
+        let result = inspect_compressed_file(testdata("text_UTF-8.txt.gz")).unwrap();
+        assert_eq!(inspection(gzip, UTF_8), result);
+        let result = inspect_compressed_file(testdata("test.png.gz")).unwrap();
+        assert_eq!(inspection(gzip, BINARY), result);
+
+        let result = inspect_compressed_file(testdata("text_truncated.txt.gz")).unwrap();
+        assert_eq!(inspection(gzip, UTF_8), result);
+        let error = inspect_compressed_file(testdata("test_corrupt.gz")).unwrap_err();
+        assert_ne!(io::ErrorKind::UnexpectedEof, error.kind());
+
+        // Only the scanned window is decompressed
+        let mut content = vec![b'a'; 10_000];
+        content[1024] = 0x00;
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+        encoder.write_all(&content).unwrap();
+        let compressed = encoder.finish().unwrap();
+        let result = inspect_compressed(&compressed[..]).unwrap();
+        assert_eq!(inspection(gzip, UTF_8), result);
+        let result = inspect_compressed(&compressed[..compressed.len() / 2]).unwrap();
+        assert_eq!(inspection(gzip, UTF_8), result);
+
+        // A truncated header
+        let result = inspect_compressed(&b"\x1F\x8B"[..]).unwrap();
+        assert_eq!(inspection(gzip, UTF_8), result);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_zstd() {
+        let zstd = Some(BinaryFormat::Zstd);
+        let result = inspect_compressed_file(testdata("text_UTF-16LE-BOM.txt.zst")).unwrap();
+        assert_eq!(inspection(zstd, UTF_16LE), result);
+
+        let compressed = zstd::encode_all(&include_bytes!("../testdata/test.png")[..], 3).unwrap();
+        let result = inspect_compressed(&compressed[..]).unwrap();
+        assert_eq!(inspection(zstd, BINARY), result);
+
+        let mut corrupt = compressed.clone();
+        for byte in &mut corrupt[4..] {
+            *byte = 0xFF;
+        }
+        assert!(inspect_compressed(&corrupt[..]).is_err());
+    }
+}
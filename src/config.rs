@@ -0,0 +1,332 @@
+use crate::{
+    ConfigError, ContentType, EmptyResult, Inspector, InspectorBuilder, Preset, Strictness,
+};
+
+/// The settings of an [`InspectorBuilder`](struct.InspectorBuilder.html) as a deserializable
+/// struct, for example to read them from a configuration file (requires the `serde` feature).
+///
+/// All fields are optional and correspond to the builder methods of the same name. Unknown fields
+/// are an error. The `preset` is applied first and the `strictness` second, so the other fields
+/// can adjust them. Custom byte classifiers and heuristics can only be added through the builder.
+///
+/// ```
+/// # extern crate content_inspector;
+/// # extern crate toml;
+/// use content_inspector::{ContentType, Inspector, InspectorConfig};
+///
+/// # fn main() {
+/// let config: InspectorConfig = toml::from_str(
+///     r#"
+///     max_scan_size = 8192
+///     latin1_fallback = true
+///
+///     [[magic_numbers]]
+///     signature = "4D 59 46 4D 54"
+///     "#,
+/// )
+/// .unwrap();
+/// let inspector = Inspector::from_config(&config).unwrap();
+///
+/// assert_eq!(ContentType::BINARY, inspector.inspect(b"MYFMT data"));
+/// # }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct InspectorConfig {
+    /// See [`InspectorBuilder::preset`](struct.InspectorBuilder.html#method.preset)
+    pub preset: Option<Preset>,
+
+    /// See [`InspectorBuilder::strictness`](struct.InspectorBuilder.html#method.strictness)
+    pub strictness: Option<Strictness>,
+
+    /// See [`InspectorBuilder::max_scan_size`](struct.InspectorBuilder.html#method.max_scan_size)
+    #[serde(alias = "scan_size")]
+    pub max_scan_size: Option<usize>,
+
+    /// See [`InspectorBuilder::scan_all`](struct.InspectorBuilder.html#method.scan_all)
+    pub scan_all: Option<bool>,
+
+    /// See [`InspectorBuilder::null_scan_limit`](struct.InspectorBuilder.html#method.null_scan_limit)
+    pub null_scan_limit: Option<usize>,
+
+    /// See [`InspectorBuilder::validation_limit`](struct.InspectorBuilder.html#method.validation_limit)
+    pub validation_limit: Option<usize>,
+
+    /// See [`InspectorBuilder::sniff_limit`](struct.InspectorBuilder.html#method.sniff_limit)
+    pub sniff_limit: Option<usize>,
+
+    /// See [`InspectorBuilder::max_null_bytes`](struct.InspectorBuilder.html#method.max_null_bytes)
+    pub max_null_bytes: Option<usize>,
+
+    /// See [`InspectorBuilder::detect_bom`](struct.InspectorBuilder.html#method.detect_bom)
+    pub detect_bom: Option<bool>,
+
+    /// See [`InspectorBuilder::corroborate_utf16_bom`](struct.InspectorBuilder.html#method.corroborate_utf16_bom)
+    pub corroborate_utf16_bom: Option<bool>,
+
+    /// See [`InspectorBuilder::detect_magic_numbers`](struct.InspectorBuilder.html#method.detect_magic_numbers)
+    pub detect_magic_numbers: Option<bool>,
+
+    /// See [`InspectorBuilder::detect_utf16_without_bom`](struct.InspectorBuilder.html#method.detect_utf16_without_bom)
+    pub detect_utf16_without_bom: Option<bool>,
+
+    /// See [`InspectorBuilder::detect_control_characters`](struct.InspectorBuilder.html#method.detect_control_characters)
+    pub detect_control_characters: Option<bool>,
+
+    /// See [`InspectorBuilder::max_control_ratio`](struct.InspectorBuilder.html#method.max_control_ratio)
+    pub max_control_ratio: Option<f32>,
+
+    /// See [`InspectorBuilder::min_printable_ratio`](struct.InspectorBuilder.html#method.min_printable_ratio)
+    pub min_printable_ratio: Option<f32>,
+
+    /// See [`InspectorBuilder::latin1_fallback`](struct.InspectorBuilder.html#method.latin1_fallback)
+    pub latin1_fallback: Option<bool>,
+
+    /// See [`InspectorBuilder::detect_ascii`](struct.InspectorBuilder.html#method.detect_ascii)
+    pub detect_ascii: Option<bool>,
+
+    /// See [`InspectorBuilder::empty_buffer_result`](struct.InspectorBuilder.html#method.empty_buffer_result)
+    pub empty_buffer_result: Option<EmptyResult>,
+
+    /// See [`InspectorBuilder::normalize_bom`](struct.InspectorBuilder.html#method.normalize_bom)
+    pub normalize_bom: Option<bool>,
+
+    /// See [`InspectorBuilder::allow_control_chars`](struct.InspectorBuilder.html#method.allow_control_chars)
+    pub allow_control_chars: Vec<u8>,
+
+    /// See [`InspectorBuilder::deny_control_chars`](struct.InspectorBuilder.html#method.deny_control_chars)
+    pub deny_control_chars: Vec<u8>,
+
+    /// See [`InspectorBuilder::magic_number`](struct.InspectorBuilder.html#method.magic_number)
+    pub magic_numbers: Vec<MagicNumberConfig>,
+
+    /// See [`InspectorBuilder::allowed_types`](struct.InspectorBuilder.html#method.allowed_types)
+    pub allowed_types: Option<Vec<ContentType>>,
+
+    /// See [`InspectorBuilder::fallback_type`](struct.InspectorBuilder.html#method.fallback_type)
+    pub fallback_type: Option<ContentType>,
+}
+
+/// A custom magic number in an [`InspectorConfig`](struct.InspectorConfig.html).
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MagicNumberConfig {
+    /// The offset of the signature in the buffer (default: 0).
+    #[serde(default)]
+    pub offset: usize,
+
+    /// The signature as a hex string, like `"89504E47"`. Bytes can be separated by whitespace.
+    pub signature: String,
+}
+
+/// Decode a hex string, ignoring whitespace between bytes.
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    let digits: Vec<u8> = hex.bytes().filter(|b| !b.is_ascii_whitespace()).collect();
+    if digits.len() % 2 != 0 {
+        return None;
+    }
+    digits
+        .chunks(2)
+        .map(|pair| {
+            let pair = ::std::str::from_utf8(pair).ok()?;
+            u8::from_str_radix(pair, 16).ok()
+        })
+        .collect()
+}
+
+impl InspectorConfig {
+    /// The builder with these settings.
+    pub fn to_builder(&self) -> Result<InspectorBuilder, ConfigError> {
+        let mut builder = Inspector::new();
+        if let Some(preset) = self.preset {
+            builder = builder.preset(preset);
+        }
+        if let Some(strictness) = self.strictness {
+            builder = builder.strictness(strictness);
+        }
+
+        macro_rules! apply {
+            ($($setting:ident),*) => {
+                $(
+                    if let Some(value) = self.$setting {
+                        builder = builder.$setting(value);
+                    }
+                )*
+            };
+        }
+        apply!(
+            max_scan_size,
+            scan_all,
+            null_scan_limit,
+            validation_limit,
+            sniff_limit,
+            max_null_bytes,
+            detect_bom,
+            corroborate_utf16_bom,
+            detect_magic_numbers,
+            detect_utf16_without_bom,
+            detect_control_characters,
+            max_control_ratio,
+            min_printable_ratio,
+            latin1_fallback,
+            detect_ascii,
+            empty_buffer_result,
+            normalize_bom,
+            fallback_type
+        );
+
+        builder = builder
+            .allow_control_chars(&self.allow_control_chars)
+            .deny_control_chars(&self.deny_control_chars);
+        for magic in &self.magic_numbers {
+            let signature = decode_hex(&magic.signature)
+                .ok_or_else(|| ConfigError::InvalidMagicNumber(magic.signature.clone()))?;
+            builder = builder.magic_number(magic.offset, &signature);
+        }
+        if let Some(ref types) = self.allowed_types {
+            builder = builder.allowed_types(types);
+        }
+        Ok(builder)
+    }
+}
+
+impl Inspector {
+    /// Create an `Inspector` from the given settings (requires the `serde` feature).
+    ///
+    /// Fails if the configuration is invalid, see
+    /// [`InspectorBuilder::build`](struct.InspectorBuilder.html#method.build).
+    pub fn from_config(config: &InspectorConfig) -> Result<Inspector, ConfigError> {
+        config.to_builder()?.build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate toml;
+
+    use crate::{
+        BinaryFormat, ConfigError, ContentType::*, EmptyResult, Inspector, InspectorConfig,
+        MagicNumberConfig, Preset,
+    };
+
+    fn from_toml(document: &str) -> Result<Inspector, ConfigError> {
+        let config: InspectorConfig = toml::from_str(document).unwrap();
+        Inspector::from_config(&config)
+    }
+
+    #[test]
+    fn test_from_config() {
+        let config: InspectorConfig = toml::from_str(
+            r#"
+            preset = "git"
+            scan_size = 16
+            detect_magic_numbers = true
+            latin1_fallback = true
+            empty_buffer_result = "binary"
+            allowed_types = ["UTF-8", "Latin-1", "binary"]
+
+            [[magic_numbers]]
+            signature = "4d59464d54"
+
+            [[magic_numbers]]
+            offset = 2
+            signature = "AB CD"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(Some(Preset::Git), config.preset);
+        assert_eq!(Some(16), config.max_scan_size);
+        assert_eq!(
+            MagicNumberConfig {
+                offset: 2,
+                signature: "AB CD".to_string()
+            },
+            config.magic_numbers[1]
+        );
+
+        let inspector = Inspector::from_config(&config).unwrap();
+        assert_eq!(BINARY, inspector.inspect(b""));
+        assert_eq!(UTF_8, inspector.inspect(&[b'a'; 100]));
+        let mut buffer = vec![b'a'; 100];
+        buffer[17] = 0x00;
+        assert_eq!(UTF_8, inspector.inspect(&buffer));
+        buffer[15] = 0x00;
+        assert_eq!(BINARY, inspector.inspect(&buffer));
+        assert_eq!(LATIN_1, inspector.inspect(b"K\xF6ln"));
+        assert_eq!(BINARY, inspector.inspect(b"\xFF\xFEa\x00"));
+        assert_eq!(
+            Some(BinaryFormat::Custom(0)),
+            inspector.inspect_detailed(b"MYFMT").binary_format
+        );
+        assert_eq!(
+            Some(BinaryFormat::Custom(1)),
+            inspector.inspect_detailed(b"..\xAB\xCD").binary_format
+        );
+    }
+
+    #[test]
+    fn test_from_config_strictness() {
+        let inspector = from_toml("strictness = \"strict\"\nvalidation_limit = 4").unwrap();
+        assert_eq!(BINARY, inspector.inspect(b"ca\xE9!"));
+        assert_eq!(UTF_8, inspector.inspect(b"cafe\xE9!"));
+
+        let inspector = from_toml(
+            r#"
+            detect_control_characters = true
+            max_control_ratio = 0.0
+            allow_control_chars = [1]
+            deny_control_chars = [0x1B]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(UTF_8, inspector.inspect(b"a\x01"));
+        assert_eq!(BINARY, inspector.inspect(b"a\x1B"));
+
+        assert_eq!(
+            InspectorConfig::default(),
+            toml::from_str::<InspectorConfig>("").unwrap()
+        );
+        assert_eq!(
+            Inspector::default().fingerprint(),
+            from_toml("").unwrap().fingerprint()
+        );
+    }
+
+    #[test]
+    fn test_from_config_errors() {
+        assert!(toml::from_str::<InspectorConfig>("latin1_falback = true").is_err());
+        assert!(toml::from_str::<InspectorConfig>("strictness = \"lax\"").is_err());
+        assert!(toml::from_str::<InspectorConfig>("max_scan_size = -1").is_err());
+        assert!(toml::from_str::<InspectorConfig>(
+            "[[magic_numbers]]\nsignature = \"AB\"\nofset = 1"
+        )
+        .is_err());
+        let config: InspectorConfig = toml::from_str("empty_buffer_result = \"none\"").unwrap();
+        assert_eq!(Some(EmptyResult::None), config.empty_buffer_result);
+
+        assert_eq!(
+            Some(ConfigError::InvalidRatio("max_control_ratio")),
+            from_toml("max_control_ratio = 2.0").err()
+        );
+        assert_eq!(
+            Some(ConfigError::InvalidControlCharacter(0x20)),
+            from_toml("allow_control_chars = [0x20]").err()
+        );
+        assert_eq!(
+            Some(ConfigError::FallbackNotAllowed(BINARY)),
+            from_toml("allowed_types = [\"UTF-8\"]").err()
+        );
+        for signature in &["ABC", "XY", "", "4D 5"] {
+            let document = format!("[[magic_numbers]]\nsignature = \"{}\"", signature);
+            let error = from_toml(&document).err().unwrap();
+            if signature.is_empty() {
+                assert_eq!(ConfigError::EmptyMagicNumber, error);
+            } else {
+                assert_eq!(
+                    ConfigError::InvalidMagicNumber(signature.to_string()),
+                    error
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,398 @@
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt;
+
+use crate::{bom_bytes, decode, ContentType, DecodeError};
+
+/// Whether the output of [`convert`](fn.convert.html) starts with a byte order mark.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum BomPolicy {
+    /// Write a byte order mark for every target type except `UTF_8`, so that the output is
+    /// detected as the target type by [`inspect`](fn.inspect.html) (for UTF-16, if the text does
+    /// not consist of control or private use characters, see
+    /// [`InspectorBuilder::corroborate_utf16_bom`](struct.InspectorBuilder.html#method.corroborate_utf16_bom)).
+    Auto,
+
+    /// Always write a byte order mark (the UTF-8 one for `UTF_8`).
+    Always,
+
+    /// Never write a byte order mark.
+    Never,
+}
+
+/// The error of [`convert`](fn.convert.html).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConvertError {
+    /// The buffer is not valid in the source encoding.
+    Decode(DecodeError),
+
+    /// The given type can not be converted from (`BINARY`), or to (`BINARY`, `ASCII` and
+    /// `LATIN_1`, which can not represent every character).
+    Unsupported(ContentType),
+}
+
+impl fmt::Display for ConvertError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ConvertError::Decode(ref error) => write!(f, "{}", error),
+            ConvertError::Unsupported(content_type) => {
+                write!(f, "can not convert {} content", content_type)
+            }
+        }
+    }
+}
+
+impl Error for ConvertError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            ConvertError::Decode(ref error) => Some(error),
+            ConvertError::Unsupported(_) => None,
+        }
+    }
+}
+
+impl From<DecodeError> for ConvertError {
+    fn from(error: DecodeError) -> ConvertError {
+        ConvertError::Decode(error)
+    }
+}
+
+/// The error of [`swap_endianness_in_place`](fn.swap_endianness_in_place.html).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EndianSwapError {
+    /// The given type has no byte order (only the UTF-16 and UTF-32 types have one).
+    Unsupported(ContentType),
+
+    /// The length of the buffer is not a multiple of the size of a code unit.
+    Length {
+        /// The length of the buffer, in bytes.
+        length: usize,
+
+        /// The size of a code unit of the content type, in bytes.
+        unit: usize,
+    },
+}
+
+impl fmt::Display for EndianSwapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            EndianSwapError::Unsupported(content_type) => {
+                write!(f, "can not swap the byte order of {} content", content_type)
+            }
+            EndianSwapError::Length { length, unit } => write!(
+                f,
+                "length of {} bytes is not a multiple of the {}-byte code unit",
+                length, unit
+            ),
+        }
+    }
+}
+
+impl Error for EndianSwapError {}
+
+/// Convert the buffer from one encoding to another, for example from `UTF_16LE` to `UTF_8`. A
+/// byte order mark at the start of the buffer is removed, and the `BomPolicy` determines whether
+/// the output starts with one.
+///
+/// The buffer is decoded like with [`decode`](fn.decode.html), so invalid content is an error
+/// rather than being replaced. The target type can be any of the UTF-8, UTF-16 and UTF-32 types
+/// (`UTF_8` and `UTF_8_BOM` only differ in the byte order mark of `BomPolicy::Auto`).
+///
+/// ```
+/// use content_inspector::{convert, BomPolicy, ContentType};
+///
+/// let utf16 = b"\xFF\xFEH\x00i\x00";
+/// let utf8 = convert(utf16, ContentType::UTF_16LE, ContentType::UTF_8, BomPolicy::Auto).unwrap();
+/// assert_eq!(b"Hi", &utf8[..]);
+///
+/// let utf32 = convert(&utf8, ContentType::UTF_8, ContentType::UTF_32BE, BomPolicy::Never).unwrap();
+/// assert_eq!(b"\x00\x00\x00H\x00\x00\x00i", &utf32[..]);
+/// ```
+pub fn convert(
+    buffer: &[u8],
+    from: ContentType,
+    to: ContentType,
+    bom: BomPolicy,
+) -> Result<Vec<u8>, ConvertError> {
+    if from == ContentType::BINARY {
+        return Err(ConvertError::Unsupported(from));
+    }
+    let width = match to {
+        ContentType::UTF_8 | ContentType::UTF_8_BOM => 1,
+        ContentType::UTF_16LE | ContentType::UTF_16BE => 2,
+        ContentType::UTF_32LE | ContentType::UTF_32BE => 4,
+        ContentType::BINARY | ContentType::ASCII | ContentType::LATIN_1 => {
+            return Err(ConvertError::Unsupported(to))
+        }
+    };
+    let text = decode(buffer, from)?;
+
+    let bom = match (bom, to) {
+        (BomPolicy::Never, _) | (BomPolicy::Auto, ContentType::UTF_8) => &[][..],
+        (_, ContentType::UTF_8) => bom_bytes(ContentType::UTF_8_BOM),
+        _ => bom_bytes(to),
+    };
+    let mut output = Vec::with_capacity(bom.len() + text.len() * width);
+    output.extend_from_slice(bom);
+    match to {
+        ContentType::UTF_16LE => output.extend(text.encode_utf16().flat_map(u16::to_le_bytes)),
+        ContentType::UTF_16BE => output.extend(text.encode_utf16().flat_map(u16::to_be_bytes)),
+        ContentType::UTF_32LE => {
+            output.extend(text.chars().flat_map(|c| u32::from(c).to_le_bytes()))
+        }
+        ContentType::UTF_32BE => {
+            output.extend(text.chars().flat_map(|c| u32::from(c).to_be_bytes()))
+        }
+        _ => output.extend_from_slice(text.as_bytes()),
+    }
+    Ok(output)
+}
+
+/// Swap the byte order of UTF-16 or UTF-32 content in place, for example to turn `UTF_16BE`
+/// into `UTF_16LE` before passing the buffer on, and return the new type of the content. A byte
+/// order mark is a code unit like any other, so it is swapped as well.
+///
+/// The buffer is not changed if its length is not a multiple of the code unit size, or if the
+/// type is not a UTF-16 or UTF-32 type.
+///
+/// ```
+/// # #[cfg(feature = "utf16")]
+/// # {
+/// use content_inspector::{inspect, swap_endianness_in_place, ContentType};
+///
+/// let mut buffer = *b"\xFE\xFF\x00H\x00i";
+/// let detected = inspect(&buffer);
+/// let content_type = swap_endianness_in_place(&mut buffer, detected).unwrap();
+///
+/// assert_eq!(ContentType::UTF_16LE, content_type);
+/// assert_eq!(b"\xFF\xFEH\x00i\x00", &buffer);
+/// # }
+/// ```
+pub fn swap_endianness_in_place(
+    buffer: &mut [u8],
+    content_type: ContentType,
+) -> Result<ContentType, EndianSwapError> {
+    let (unit, swapped) = match content_type {
+        ContentType::UTF_16LE => (2, ContentType::UTF_16BE),
+        ContentType::UTF_16BE => (2, ContentType::UTF_16LE),
+        ContentType::UTF_32LE => (4, ContentType::UTF_32BE),
+        ContentType::UTF_32BE => (4, ContentType::UTF_32LE),
+        _ => return Err(EndianSwapError::Unsupported(content_type)),
+    };
+    if buffer.len() % unit != 0 {
+        return Err(EndianSwapError::Length {
+            length: buffer.len(),
+            unit,
+        });
+    }
+    if unit == 2 {
+        buffer
+            .chunks_exact_mut(2)
+            .for_each(|chunk| chunk.swap(0, 1));
+    } else {
+        buffer.chunks_exact_mut(4).for_each(<[u8]>::reverse);
+    }
+    Ok(swapped)
+}
+
+#[cfg(test)]
+#[cfg_attr(
+    not(all(feature = "utf16", feature = "utf32", feature = "legacy-encodings")),
+    allow(dead_code, unused_imports)
+)]
+mod tests {
+    use crate::{
+        bom_bytes, convert, swap_endianness_in_place, BomPolicy, ContentType, ContentType::*,
+        ConvertError, DecodeError, DecodeErrorKind, EndianSwapError, Inspector,
+    };
+
+    const UNICODE_TYPES: [ContentType; 6] =
+        [UTF_8, UTF_8_BOM, UTF_16LE, UTF_16BE, UTF_32LE, UTF_32BE];
+
+    /// Pseudo-random strings with characters from every plane, around the surrogates and at the
+    /// end of the Unicode range.
+    fn generated_strings() -> Vec<String> {
+        let mut state: u64 = 0x2545_F491_4F6C_DD1D;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        (0..200)
+            .map(|length| {
+                (0..length)
+                    .map(|_| {
+                        let random = next();
+                        let code_point = match random % 4 {
+                            0 => random >> 32 & 0x7F,
+                            1 => 0xD000 + (random >> 32) % 0x3000,
+                            2 => 0x10FF00 + (random >> 32) % 0x100,
+                            _ => (random >> 32) % 0x110000,
+                        };
+                        // A leading U+FEFF would be taken for a byte order mark
+                        match code_point as u32 {
+                            0xFEFF => '\u{FEFE}',
+                            code_point => char::from_u32(code_point).unwrap_or('\u{FFFD}'),
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    #[cfg(all(feature = "utf16", feature = "utf32"))]
+    fn test_convert_round_trip() {
+        // The generated strings have many control and private use characters
+        let inspector = Inspector::new()
+            .corroborate_utf16_bom(false)
+            .build()
+            .unwrap();
+        for text in generated_strings() {
+            let utf8 = text.as_bytes();
+            for &from in &UNICODE_TYPES {
+                let source = convert(utf8, UTF_8, from, BomPolicy::Auto).unwrap();
+                if from != UTF_8 {
+                    assert_eq!(from, inspector.inspect(&source), "{:?}", text);
+                }
+
+                for &to in &UNICODE_TYPES {
+                    let converted = convert(&source, from, to, BomPolicy::Auto).unwrap();
+                    let back = convert(&converted, to, from, BomPolicy::Auto).unwrap();
+                    assert_eq!(source, back, "{:?} -> {:?}: {:?}", from, to, text);
+
+                    let without_bom = convert(&source, from, to, BomPolicy::Never).unwrap();
+                    assert_eq!(
+                        converted.strip_prefix(bom_bytes(to)).unwrap(),
+                        &without_bom[..]
+                    );
+                    let back = convert(&without_bom, to, from, BomPolicy::Never).unwrap();
+                    assert_eq!(source.strip_prefix(bom_bytes(from)).unwrap(), &back[..]);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_convert_bom() {
+        let convert_text = |to, bom| convert(b"a", UTF_8, to, bom).unwrap();
+        assert_eq!(b"a", &convert_text(UTF_8, BomPolicy::Auto)[..]);
+        assert_eq!(
+            b"\xEF\xBB\xBFa",
+            &convert_text(UTF_8, BomPolicy::Always)[..]
+        );
+        assert_eq!(
+            b"\xEF\xBB\xBFa",
+            &convert_text(UTF_8_BOM, BomPolicy::Auto)[..]
+        );
+        assert_eq!(b"a", &convert_text(UTF_8_BOM, BomPolicy::Never)[..]);
+        assert_eq!(
+            b"\xFE\xFF\x00a",
+            &convert_text(UTF_16BE, BomPolicy::Always)[..]
+        );
+        assert_eq!(
+            b"a\x00\x00\x00",
+            &convert_text(UTF_32LE, BomPolicy::Never)[..]
+        );
+
+        // Only a single byte order mark is removed
+        let converted = convert(b"\xFF\xFE\xFF\xFEa\x00", UTF_16LE, UTF_8, BomPolicy::Auto);
+        assert_eq!("\u{FEFF}a".as_bytes(), &converted.unwrap()[..]);
+        let converted = convert(b"caf\xE9", LATIN_1, UTF_8, BomPolicy::Auto);
+        assert_eq!("café".as_bytes(), &converted.unwrap()[..]);
+    }
+
+    #[test]
+    fn test_convert_errors() {
+        for &(from, to) in &[
+            (BINARY, UTF_8),
+            (UTF_8, BINARY),
+            (UTF_16LE, ASCII),
+            (UTF_8, LATIN_1),
+        ] {
+            let unsupported = if from == BINARY { from } else { to };
+            assert_eq!(
+                Err(ConvertError::Unsupported(unsupported)),
+                convert(b"text", from, to, BomPolicy::Auto)
+            );
+        }
+        assert_eq!(
+            "can not convert binary content",
+            convert(b"", UTF_8, BINARY, BomPolicy::Auto)
+                .unwrap_err()
+                .to_string()
+        );
+
+        let error = convert(b"\x00a\xDC\x00", UTF_16BE, UTF_8, BomPolicy::Never).unwrap_err();
+        assert_eq!(
+            ConvertError::Decode(DecodeError {
+                offset: 2,
+                kind: DecodeErrorKind::UnpairedSurrogate(0xDC00),
+            }),
+            error
+        );
+        assert_eq!(
+            "unpaired UTF-16 surrogate 0xDC00 at byte 2",
+            error.to_string()
+        );
+        assert!(std::error::Error::source(&error).is_some());
+    }
+
+    #[test]
+    #[cfg(all(feature = "utf16", feature = "utf32"))]
+    fn test_swap_endianness_in_place() {
+        let inspector = Inspector::new()
+            .corroborate_utf16_bom(false)
+            .build()
+            .unwrap();
+        for &(from, to) in &[
+            (UTF_16LE, UTF_16BE),
+            (UTF_16BE, UTF_16LE),
+            (UTF_32LE, UTF_32BE),
+            (UTF_32BE, UTF_32LE),
+        ] {
+            for text in generated_strings().iter().step_by(7) {
+                let original = convert(text.as_bytes(), UTF_8, from, BomPolicy::Always).unwrap();
+                let expected = convert(text.as_bytes(), UTF_8, to, BomPolicy::Always).unwrap();
+
+                let mut buffer = original.clone();
+                assert_eq!(Ok(to), swap_endianness_in_place(&mut buffer, from));
+                assert_eq!(expected, buffer);
+                assert!(buffer.starts_with(bom_bytes(to)));
+                assert_eq!(to, inspector.inspect(&buffer));
+
+                // Swapping twice restores the original bytes
+                assert_eq!(Ok(from), swap_endianness_in_place(&mut buffer, to));
+                assert_eq!(original, buffer);
+            }
+        }
+    }
+
+    #[test]
+    fn test_swap_endianness_in_place_errors() {
+        let mut buffer = *b"\xFF\xFEa\x00b";
+        assert_eq!(
+            Err(EndianSwapError::Length { length: 5, unit: 2 }),
+            swap_endianness_in_place(&mut buffer, UTF_16LE)
+        );
+        assert_eq!(b"\xFF\xFEa\x00b", &buffer);
+        let error = swap_endianness_in_place(&mut buffer[..2], UTF_32BE).unwrap_err();
+        assert_eq!(
+            "length of 2 bytes is not a multiple of the 4-byte code unit",
+            error.to_string()
+        );
+
+        for &content_type in &[UTF_8, UTF_8_BOM, ASCII, LATIN_1, BINARY] {
+            assert_eq!(
+                Err(EndianSwapError::Unsupported(content_type)),
+                swap_endianness_in_place(&mut buffer, content_type)
+            );
+        }
+        assert_eq!(
+            "can not swap the byte order of UTF-8 content",
+            EndianSwapError::Unsupported(UTF_8).to_string()
+        );
+        assert_eq!(b"\xFF\xFEa\x00b", &buffer);
+    }
+}
@@ -0,0 +1,1112 @@
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::char::REPLACEMENT_CHARACTER;
+use core::error::Error;
+use core::fmt;
+use core::ops::Deref;
+use core::str;
+#[cfg(feature = "std")]
+use std::fs;
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(feature = "std")]
+use std::path::Path;
+
+use crate::newlines::Newlines;
+use crate::units::{utf16_units, utf32_units};
+use crate::{
+    bom_bytes, default_inspector, ContentType, Inspector, NewlinePolicy, KNOWN_BYTE_ORDER_MARKS,
+};
+
+/// The byte order mark that is removed before decoding content of the given type. `BINARY`
+/// content is decoded like UTF-8.
+pub(crate) fn bom(content_type: ContentType) -> &'static [u8] {
+    match content_type {
+        ContentType::UTF_8 | ContentType::ASCII | ContentType::BINARY => {
+            bom_bytes(ContentType::UTF_8_BOM)
+        }
+        _ => bom_bytes(content_type),
+    }
+}
+
+/// Strip the byte order mark of the given type from the start of the buffer, if it is present.
+fn strip_bom<'a>(buffer: &'a [u8], bom: &[u8]) -> &'a [u8] {
+    buffer.strip_prefix(bom).unwrap_or(buffer)
+}
+
+/// The length of the given byte order mark at the start of the buffer, repeated as often as it
+/// occurs if `all` is set.
+fn repeated_bom_length(buffer: &[u8], bom: &[u8], all: bool) -> usize {
+    let mut length = bom.len();
+    while all && buffer[length..].starts_with(bom) {
+        length += bom.len();
+    }
+    length
+}
+
+/// Remove a byte order mark from the start of the buffer, without allocating. Returns the type of
+/// content that the byte order mark indicates, or `None` if there is none (and the buffer is left
+/// unchanged).
+///
+/// If `all` is set, repetitions of the same byte order mark directly after the first one are
+/// removed as well (e.g. in files that have been concatenated).
+///
+/// ```
+/// use content_inspector::{strip_bom_in_place, ContentType};
+///
+/// let mut buffer = b"\xFF\xFEH\x00i\x00".to_vec();
+/// assert_eq!(Some(ContentType::UTF_16LE), strip_bom_in_place(&mut buffer, false));
+/// assert_eq!(b"H\x00i\x00", &buffer[..]);
+///
+/// assert_eq!(None, strip_bom_in_place(&mut buffer, false));
+/// ```
+pub fn strip_bom_in_place(buffer: &mut Vec<u8>, all: bool) -> Option<ContentType> {
+    let &(bom, content_type) = KNOWN_BYTE_ORDER_MARKS
+        .iter()
+        .find(|&&(bom, _)| buffer.starts_with(bom))?;
+    let length = repeated_bom_length(buffer, bom, all);
+    buffer.drain(..length);
+    Some(content_type)
+}
+
+/// Like [`strip_bom_in_place`](fn.strip_bom_in_place.html), but for a string, which can only
+/// start with a UTF-8 byte order mark (the character U+FEFF). Returns `true` if it was removed.
+///
+/// ```
+/// use content_inspector::strip_bom_in_place_str;
+///
+/// let mut text = String::from("\u{FEFF}\u{FEFF}name,value");
+/// assert!(strip_bom_in_place_str(&mut text, true));
+/// assert_eq!("name,value", text);
+/// ```
+pub fn strip_bom_in_place_str(text: &mut String, all: bool) -> bool {
+    if !text.starts_with('\u{FEFF}') {
+        return false;
+    }
+    let length = repeated_bom_length(text.as_bytes(), "\u{FEFF}".as_bytes(), all);
+    text.drain(..length);
+    true
+}
+
+/// The invalid sequences that were replaced while decoding.
+#[derive(Default)]
+struct Replacements {
+    count: usize,
+    first_offset: Option<usize>,
+}
+
+impl Replacements {
+    fn record(&mut self, offset: usize) -> char {
+        self.count += 1;
+        self.first_offset = self.first_offset.or(Some(offset));
+        REPLACEMENT_CHARACTER
+    }
+}
+
+fn decode_utf8<'a>(
+    buffer: &'a [u8],
+    offset: usize,
+    replacements: &mut Replacements,
+    newlines: &mut Newlines,
+) -> Cow<'a, str> {
+    let mut chunks = buffer.utf8_chunks();
+    let first = match chunks.next() {
+        Some(chunk) if chunk.invalid().is_empty() && newlines.is_normalized(chunk.valid()) => {
+            return Cow::Borrowed(chunk.valid())
+        }
+        Some(chunk) => chunk,
+        None => return Cow::Borrowed(""),
+    };
+    let mut text = String::with_capacity(buffer.len() + 2);
+    let mut position = offset;
+    for chunk in Some(first).into_iter().chain(chunks) {
+        newlines.push_str(&mut text, chunk.valid());
+        position += chunk.valid().len();
+        if !chunk.invalid().is_empty() {
+            newlines.push(&mut text, replacements.record(position));
+            position += chunk.invalid().len();
+        }
+    }
+    newlines.finish(&mut text);
+    Cow::Owned(text)
+}
+
+fn decode_utf16(
+    buffer: &[u8],
+    offset: usize,
+    big_endian: bool,
+    replacements: &mut Replacements,
+    newlines: &mut Newlines,
+) -> String {
+    let units = utf16_units(buffer, big_endian);
+    let mut text = String::with_capacity(buffer.len() / 2);
+    let mut position = offset;
+    for c in char::decode_utf16(units) {
+        match c {
+            Ok(c) => {
+                newlines.push(&mut text, c);
+                position += 2 * c.len_utf16();
+            }
+            Err(_) => {
+                newlines.push(&mut text, replacements.record(position));
+                position += 2;
+            }
+        }
+    }
+    if buffer.len() % 2 != 0 {
+        newlines.push(&mut text, replacements.record(position));
+    }
+    newlines.finish(&mut text);
+    text
+}
+
+fn decode_utf32(
+    buffer: &[u8],
+    offset: usize,
+    big_endian: bool,
+    replacements: &mut Replacements,
+    newlines: &mut Newlines,
+) -> String {
+    let mut text = String::with_capacity(buffer.len() / 4);
+    for (i, code_point) in utf32_units(buffer, big_endian).enumerate() {
+        // Rejects surrogates and values beyond U+10FFFF
+        let c = match char::from_u32(code_point) {
+            Some(c) => c,
+            None => replacements.record(offset + 4 * i),
+        };
+        newlines.push(&mut text, c);
+    }
+    if buffer.len() % 4 != 0 {
+        newlines.push(
+            &mut text,
+            replacements.record(offset + buffer.len() / 4 * 4),
+        );
+    }
+    newlines.finish(&mut text);
+    text
+}
+
+fn decode_latin1<'a>(buffer: &'a [u8], newlines: &mut Newlines) -> Cow<'a, str> {
+    match str::from_utf8(buffer) {
+        // ASCII is valid UTF-8
+        Ok(text) if buffer.is_ascii() && newlines.is_normalized(text) => Cow::Borrowed(text),
+        _ => {
+            let mut text = String::with_capacity(buffer.len());
+            for &byte in buffer {
+                newlines.push(&mut text, char::from(byte));
+            }
+            newlines.finish(&mut text);
+            Cow::Owned(text)
+        }
+    }
+}
+
+/// The kind of a [`DecodeError`](struct.DecodeError.html).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DecodeErrorKind {
+    /// A byte sequence that is not valid UTF-8 (including an incomplete one at the end).
+    InvalidUtf8,
+
+    /// A UTF-16 surrogate without the other half of its pair.
+    UnpairedSurrogate(u16),
+
+    /// A UTF-32 value that is not a Unicode scalar value (a surrogate, or above U+10FFFF).
+    InvalidScalar(u32),
+
+    /// Trailing bytes that do not form a complete UTF-16 or UTF-32 code unit.
+    IncompleteCodeUnit,
+}
+
+/// The error of [`decode`](fn.decode.html).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DecodeError {
+    /// The offset of the invalid content in the buffer (including a byte order mark).
+    pub offset: usize,
+
+    /// The kind of problem.
+    pub kind: DecodeErrorKind,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            DecodeErrorKind::InvalidUtf8 => {
+                write!(f, "invalid UTF-8 sequence at byte {}", self.offset)
+            }
+            DecodeErrorKind::UnpairedSurrogate(unit) => write!(
+                f,
+                "unpaired UTF-16 surrogate 0x{:04X} at byte {}",
+                unit, self.offset
+            ),
+            DecodeErrorKind::InvalidScalar(value) => write!(
+                f,
+                "0x{:X} at byte {} is not a valid Unicode code point",
+                value, self.offset
+            ),
+            DecodeErrorKind::IncompleteCodeUnit => write!(
+                f,
+                "incomplete code unit at byte {} (at the end of the content)",
+                self.offset
+            ),
+        }
+    }
+}
+
+impl Error for DecodeError {}
+
+fn decode_utf8_strict<'a>(
+    buffer: &'a [u8],
+    offset: usize,
+    newlines: &mut Newlines,
+) -> Result<Cow<'a, str>, DecodeError> {
+    match str::from_utf8(buffer) {
+        Ok(text) if newlines.is_normalized(text) => Ok(Cow::Borrowed(text)),
+        Ok(text) => {
+            let mut normalized = String::with_capacity(text.len());
+            newlines.push_str(&mut normalized, text);
+            newlines.finish(&mut normalized);
+            Ok(Cow::Owned(normalized))
+        }
+        Err(error) => Err(DecodeError {
+            offset: offset + error.valid_up_to(),
+            kind: DecodeErrorKind::InvalidUtf8,
+        }),
+    }
+}
+
+fn decode_utf16_strict(
+    buffer: &[u8],
+    offset: usize,
+    big_endian: bool,
+    newlines: &mut Newlines,
+) -> Result<String, DecodeError> {
+    let units = utf16_units(buffer, big_endian);
+    let mut text = String::with_capacity(buffer.len() / 2);
+    let mut position = offset;
+    for c in char::decode_utf16(units) {
+        match c {
+            Ok(c) => {
+                newlines.push(&mut text, c);
+                position += 2 * c.len_utf16();
+            }
+            Err(error) => {
+                return Err(DecodeError {
+                    offset: position,
+                    kind: DecodeErrorKind::UnpairedSurrogate(error.unpaired_surrogate()),
+                })
+            }
+        }
+    }
+    if buffer.len() % 2 != 0 {
+        return Err(DecodeError {
+            offset: position,
+            kind: DecodeErrorKind::IncompleteCodeUnit,
+        });
+    }
+    newlines.finish(&mut text);
+    Ok(text)
+}
+
+fn decode_utf32_strict(
+    buffer: &[u8],
+    offset: usize,
+    big_endian: bool,
+    newlines: &mut Newlines,
+) -> Result<String, DecodeError> {
+    let mut text = String::with_capacity(buffer.len() / 4);
+    for (i, value) in utf32_units(buffer, big_endian).enumerate() {
+        match char::from_u32(value) {
+            Some(c) => newlines.push(&mut text, c),
+            None => {
+                return Err(DecodeError {
+                    offset: offset + 4 * i,
+                    kind: DecodeErrorKind::InvalidScalar(value),
+                })
+            }
+        }
+    }
+    if buffer.len() % 4 != 0 {
+        return Err(DecodeError {
+            offset: offset + buffer.len() / 4 * 4,
+            kind: DecodeErrorKind::IncompleteCodeUnit,
+        });
+    }
+    newlines.finish(&mut text);
+    Ok(text)
+}
+
+/// Like [`decode_lossy`](fn.decode_lossy.html), but fails on the first invalid content, with its
+/// offset in the buffer. A byte order mark at the start is removed (and is never an error).
+///
+/// `BINARY` content is decoded like UTF-8, `LATIN_1` content can always be decoded.
+///
+/// ```
+/// use content_inspector::{decode, ContentType, DecodeErrorKind};
+///
+/// assert_eq!("Hi", decode(b"\xFE\xFF\x00H\x00i", ContentType::UTF_16BE).unwrap());
+///
+/// let error = decode(b"caf\xE9", ContentType::UTF_8).unwrap_err();
+/// assert_eq!(3, error.offset);
+/// assert_eq!(DecodeErrorKind::InvalidUtf8, error.kind);
+/// assert_eq!("invalid UTF-8 sequence at byte 3", error.to_string());
+/// ```
+pub fn decode(buffer: &[u8], content_type: ContentType) -> Result<String, DecodeError> {
+    decode_with(buffer, content_type, NewlinePolicy::Preserve)
+}
+
+/// Like [`decode`](fn.decode.html), but rewrites the line endings according to the given policy
+/// while decoding.
+///
+/// ```
+/// use content_inspector::{decode_with, ContentType, NewlinePolicy};
+///
+/// let text = decode_with(b"a\r\nb\rc", ContentType::UTF_8, NewlinePolicy::NormalizeToLf);
+/// assert_eq!("a\nb\nc", text.unwrap());
+/// ```
+pub fn decode_with(
+    buffer: &[u8],
+    content_type: ContentType,
+    newlines: NewlinePolicy,
+) -> Result<String, DecodeError> {
+    let content = strip_bom(buffer, bom(content_type));
+    let offset = buffer.len() - content.len();
+    decode_strict(content, offset, content_type, newlines).map(Cow::into_owned)
+}
+
+/// Decode content without a byte order mark, which starts at the given offset in the buffer.
+pub(crate) fn decode_strict(
+    content: &[u8],
+    offset: usize,
+    content_type: ContentType,
+    newlines: NewlinePolicy,
+) -> Result<Cow<'_, str>, DecodeError> {
+    let n = &mut Newlines::new(newlines);
+    match content_type {
+        ContentType::UTF_16LE => decode_utf16_strict(content, offset, false, n).map(Cow::Owned),
+        ContentType::UTF_16BE => decode_utf16_strict(content, offset, true, n).map(Cow::Owned),
+        ContentType::UTF_32LE => decode_utf32_strict(content, offset, false, n).map(Cow::Owned),
+        ContentType::UTF_32BE => decode_utf32_strict(content, offset, true, n).map(Cow::Owned),
+        ContentType::LATIN_1 => Ok(decode_latin1(content, n)),
+        _ => decode_utf8_strict(content, offset, n),
+    }
+}
+
+/// Decode the buffer to a string, according to the given type of content (as determined by
+/// [`inspect`](fn.inspect.html), for example). A byte order mark at the start is removed.
+///
+/// Valid UTF-8 content is borrowed, everything else is converted. Invalid sequences of the
+/// respective encoding (including an incomplete one at the end) are replaced by U+FFFD.
+/// `BINARY` content is decoded like UTF-8, so that its printable parts stay readable.
+///
+/// ```
+/// # #[cfg(feature = "utf16")]
+/// # {
+/// use std::borrow::Cow;
+/// use content_inspector::{decode_lossy, inspect};
+///
+/// let buffer = b"\xFF\xFEH\x00i\x00";
+/// assert_eq!("Hi", decode_lossy(buffer, inspect(buffer)));
+///
+/// let buffer = b"\xEF\xBB\xBFHi";
+/// assert_eq!(Cow::Borrowed("Hi"), decode_lossy(buffer, inspect(buffer)));
+/// # }
+/// ```
+pub fn decode_lossy(buffer: &[u8], content_type: ContentType) -> Cow<'_, str> {
+    decode_lossy_counted(buffer, content_type).text
+}
+
+/// The result of [`decode_lossy_counted`](fn.decode_lossy_counted.html).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LossyDecode<'a> {
+    /// The decoded text, without a byte order mark.
+    pub text: Cow<'a, str>,
+
+    /// The number of invalid sequences that were replaced by U+FFFD. This does not include
+    /// U+FFFD characters in the content itself.
+    pub replacements: usize,
+
+    /// The offset of the first invalid sequence in the buffer (including a byte order mark), if
+    /// any.
+    pub first_error_offset: Option<usize>,
+
+    /// The number of line endings that were rewritten, see
+    /// [`decode_lossy_with`](fn.decode_lossy_with.html).
+    pub newlines_changed: usize,
+}
+
+/// Like [`decode_lossy`](fn.decode_lossy.html), but also returns how many invalid sequences
+/// were replaced, for example to warn about them or to rank candidate encodings.
+///
+/// ```
+/// use content_inspector::{decode_lossy_counted, ContentType};
+///
+/// let decoded = decode_lossy_counted(b"\xEF\xBF\xBD caf\xE9 na\xEFve", ContentType::UTF_8);
+///
+/// assert_eq!("\u{FFFD} caf\u{FFFD} na\u{FFFD}ve", decoded.text);
+/// assert_eq!(2, decoded.replacements);
+/// assert_eq!(Some(7), decoded.first_error_offset);
+/// ```
+pub fn decode_lossy_counted(buffer: &[u8], content_type: ContentType) -> LossyDecode<'_> {
+    decode_lossy_with(buffer, content_type, NewlinePolicy::Preserve)
+}
+
+/// Like [`decode_lossy_counted`](fn.decode_lossy_counted.html), but rewrites the line endings
+/// according to the given policy while decoding. A CR that is directly followed by LF is a single
+/// line ending. Content that does not need to be changed is still borrowed.
+///
+/// ```
+/// use content_inspector::{decode_lossy_with, ContentType, NewlinePolicy};
+///
+/// let buffer = b"\xFF\xFEa\x00\r\x00\n\x00b\x00\r\x00";
+/// let decoded = decode_lossy_with(buffer, ContentType::UTF_16LE, NewlinePolicy::NormalizeToLf);
+///
+/// assert_eq!("a\nb\n", decoded.text);
+/// assert_eq!(2, decoded.newlines_changed);
+/// ```
+pub fn decode_lossy_with(
+    buffer: &[u8],
+    content_type: ContentType,
+    newlines: NewlinePolicy,
+) -> LossyDecode<'_> {
+    let content = strip_bom(buffer, bom(content_type));
+    decode_content_counted(
+        content,
+        buffer.len() - content.len(),
+        content_type,
+        newlines,
+    )
+}
+
+/// Decode content without a byte order mark.
+pub(crate) fn decode_content(content: &[u8], content_type: ContentType) -> Cow<'_, str> {
+    decode_content_counted(content, 0, content_type, NewlinePolicy::Preserve).text
+}
+
+/// Decode content without a byte order mark, which starts at the given offset in the buffer.
+fn decode_content_counted(
+    content: &[u8],
+    offset: usize,
+    content_type: ContentType,
+    newlines: NewlinePolicy,
+) -> LossyDecode<'_> {
+    let mut replacements = Replacements::default();
+    let mut newlines = Newlines::new(newlines);
+    let (r, n) = (&mut replacements, &mut newlines);
+    let text = match content_type {
+        ContentType::UTF_8 | ContentType::UTF_8_BOM | ContentType::ASCII | ContentType::BINARY => {
+            decode_utf8(content, offset, r, n)
+        }
+        ContentType::UTF_16LE => Cow::Owned(decode_utf16(content, offset, false, r, n)),
+        ContentType::UTF_16BE => Cow::Owned(decode_utf16(content, offset, true, r, n)),
+        ContentType::UTF_32LE => Cow::Owned(decode_utf32(content, offset, false, r, n)),
+        ContentType::UTF_32BE => Cow::Owned(decode_utf32(content, offset, true, r, n)),
+        ContentType::LATIN_1 => decode_latin1(content, n),
+    };
+    LossyDecode {
+        text,
+        replacements: replacements.count,
+        first_error_offset: replacements.first_offset,
+        newlines_changed: newlines.changed(),
+    }
+}
+
+/// Decoded text, together with the type of content that it was decoded from. This is the result
+/// of [`inspect_and_decode`](fn.inspect_and_decode.html), and dereferences to the text.
+///
+/// With the `serde` feature, it can be serialized and deserialized.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DecodedText<'a> {
+    /// The decoded text, without a byte order mark.
+    pub text: Cow<'a, str>,
+
+    /// The type of content that was detected. `BINARY` content is decoded like UTF-8.
+    pub content_type: ContentType,
+
+    /// The length of the byte order mark that was removed, or zero if there was none.
+    pub bom_length: usize,
+
+    /// The number of invalid sequences that were replaced by U+FFFD, see
+    /// [`decode_lossy_counted`](fn.decode_lossy_counted.html).
+    pub replacements: usize,
+}
+
+impl<'a> DecodedText<'a> {
+    /// Determine the type of content in the given buffer, and decode it. This is the same as
+    /// [`inspect_and_decode`](fn.inspect_and_decode.html).
+    pub fn from_bytes(buffer: &'a [u8]) -> DecodedText<'a> {
+        inspect_and_decode(buffer)
+    }
+
+    /// Read the whole file, determine its type of content and decode it.
+    ///
+    /// ```no_run
+    /// use content_inspector::DecodedText;
+    ///
+    /// # fn main() -> std::io::Result<()> {
+    /// let readme = DecodedText::from_file("README.md")?;
+    /// println!("{} lines of {}", readme.lines().count(), readme.content_type);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<DecodedText<'static>> {
+        let buffer = fs::read(path)?;
+        Ok(DecodedText::from_bytes(&buffer).into_owned())
+    }
+
+    /// Converts borrowed text into owned text.
+    pub fn into_owned(self) -> DecodedText<'static> {
+        DecodedText {
+            text: Cow::Owned(self.text.into_owned()),
+            ..self
+        }
+    }
+}
+
+impl<'a> Deref for DecodedText<'a> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.text
+    }
+}
+
+impl Inspector {
+    /// Like [`inspect_and_decode`](fn.inspect_and_decode.html), but uses the settings of this
+    /// `Inspector`.
+    pub fn inspect_and_decode<'a>(&self, buffer: &'a [u8]) -> DecodedText<'a> {
+        let report = self.inspect_detailed(buffer);
+        let content_type = report.content_type;
+        let content = if content_type == report.detected_type && content_type.is_text() {
+            &buffer[report.bom_length..]
+        } else {
+            // Mapped to a fallback type, so the detected byte order mark does not apply
+            strip_bom(buffer, bom(content_type))
+        };
+        let bom_length = buffer.len() - content.len();
+        let decoded =
+            decode_content_counted(content, bom_length, content_type, NewlinePolicy::Preserve);
+        DecodedText {
+            text: decoded.text,
+            content_type,
+            bom_length,
+            replacements: decoded.replacements,
+        }
+    }
+}
+
+/// Read the whole file, determine its type of content and decode it like
+/// [`DecodedText::from_file`](struct.DecodedText.html#method.from_file), but fail on invalid
+/// content (like [`decode`](fn.decode.html)) instead of replacing it. The errors include the
+/// path of the file:
+///
+/// ```
+/// # use std::fs;
+/// # let path = std::env::temp_dir().join("content_inspector_decode_file.txt");
+/// # fs::write(&path, b"caf\xE9").unwrap();
+/// use content_inspector::{decode_file, Error};
+///
+/// let error = decode_file(&path).unwrap_err();
+/// assert!(matches!(error, Error::Decode { .. }));
+/// let message = format!("{}: invalid UTF-8 sequence at byte 3", path.display());
+/// assert_eq!(message, error.to_string());
+/// ```
+#[cfg(feature = "std")]
+pub fn decode_file<P: AsRef<Path>>(path: P) -> Result<DecodedText<'static>, crate::Error> {
+    let path = path.as_ref();
+    let buffer = fs::read(path).map_err(|error| crate::Error::from(error).with_path(path))?;
+    let content_type = default_inspector().inspect(&buffer);
+    let text =
+        decode(&buffer, content_type).map_err(|error| crate::Error::from(error).with_path(path))?;
+    Ok(DecodedText {
+        text: Cow::Owned(text),
+        content_type,
+        bom_length: buffer.len() - strip_bom(&buffer, bom(content_type)).len(),
+        replacements: 0,
+    })
+}
+
+/// Determine the type of content in the given buffer, and decode it accordingly (like with
+/// [`decode_lossy`](fn.decode_lossy.html)). This avoids passing the type of one buffer to the
+/// decoding of another, and the byte order mark is only looked at once. Like with
+/// `decode_lossy`, UTF-8 (and ASCII) content is borrowed.
+///
+/// ```
+/// # #[cfg(feature = "utf16")]
+/// # {
+/// use content_inspector::{inspect_and_decode, ContentType};
+///
+/// let decoded = inspect_and_decode(b"\xFF\xFEH\x00i\x00");
+/// assert_eq!(ContentType::UTF_16LE, decoded.content_type);
+/// assert_eq!(2, decoded.bom_length);
+/// assert_eq!("Hi", &*decoded);
+///
+/// assert!(inspect_and_decode(b"\x00\x01\x02").content_type.is_binary());
+/// # }
+/// ```
+pub fn inspect_and_decode(buffer: &[u8]) -> DecodedText<'_> {
+    default_inspector().inspect_and_decode(buffer)
+}
+
+#[cfg(test)]
+#[cfg_attr(
+    not(all(feature = "utf16", feature = "utf32", feature = "legacy-encodings")),
+    allow(dead_code, unused_imports)
+)]
+mod tests {
+    use std::borrow::Cow;
+    use std::fs;
+    use std::path::Path;
+
+    use crate::{
+        decode, decode_lossy, decode_lossy_counted, decode_lossy_with, decode_with, inspect,
+        inspect_and_decode, strip_bom_in_place, strip_bom_in_place_str, ContentType,
+        ContentType::*, DecodeError, DecodeErrorKind, DecodedText, Inspector, NewlinePolicy::*,
+    };
+
+    #[test]
+    #[cfg(all(feature = "utf16", feature = "utf32"))]
+    fn test_decode_testdata() {
+        let testdata = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata");
+        let text = fs::read_to_string(testdata.join("text_UTF-8.txt")).unwrap();
+        for name in &[
+            "text_UTF-8.txt",
+            "text_UTF-8-BOM.txt",
+            "text_UTF-16LE-BOM.txt",
+            "text_UTF-16BE-BOM.txt",
+            "text_UTF-32LE-BOM.txt",
+            "text_UTF-32BE-BOM.txt",
+        ] {
+            let buffer = fs::read(testdata.join(name)).unwrap();
+            assert_eq!(text, decode_lossy(&buffer, inspect(&buffer)), "{}", name);
+        }
+
+        let buffer = fs::read(testdata.join("text_UTF-16LE.txt")).unwrap();
+        assert_eq!(text, decode_lossy(&buffer, UTF_16LE));
+        let buffer = fs::read(testdata.join("text_UTF-16BE.txt")).unwrap();
+        assert_eq!(text, decode_lossy(&buffer, UTF_16BE));
+
+        let buffer = fs::read(testdata.join("text_Latin-1.txt")).unwrap();
+        assert_eq!(
+            "Grüße aus Köln, señor, à bientôt!\n",
+            decode_lossy(&buffer, LATIN_1)
+        );
+    }
+
+    #[test]
+    fn test_decode_borrowed() {
+        assert!(matches!(
+            decode_lossy(b"text", UTF_8),
+            Cow::Borrowed("text")
+        ));
+        assert!(matches!(
+            decode_lossy(b"\xEF\xBB\xBFtext", UTF_8_BOM),
+            Cow::Borrowed("text")
+        ));
+        assert!(matches!(
+            decode_lossy(b"text", LATIN_1),
+            Cow::Borrowed("text")
+        ));
+        assert!(matches!(
+            decode_lossy(b"text", ASCII),
+            Cow::Borrowed("text")
+        ));
+        assert!(matches!(decode_lossy(b"", UTF_16LE), Cow::Owned(_)));
+        assert!(matches!(decode_lossy(b"caf\xE9", UTF_8), Cow::Owned(_)));
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        assert_eq!("caf\u{FFFD}", decode_lossy(b"caf\xE9", UTF_8));
+        assert_eq!("PNG\u{FFFD}\u{FFFD}", decode_lossy(b"PNG\x89\xFF", BINARY));
+        assert_eq!("\u{0}\u{1}", decode_lossy(b"\x00\x01", BINARY));
+
+        // An unpaired surrogate, and an incomplete unit at the end
+        assert_eq!("a\u{FFFD}b", decode_lossy(b"a\x00\x00\xD8b\x00", UTF_16LE));
+        assert_eq!("a\u{FFFD}", decode_lossy(b"\x00a\x00", UTF_16BE));
+        assert_eq!("\u{1F600}", decode_lossy(b"\xD8\x3D\xDE\x00", UTF_16BE));
+
+        // Surrogates and values beyond U+10FFFF are not code points
+        assert_eq!(
+            "\u{10FFFF}\u{FFFD}\u{FFFD}a\u{FFFD}",
+            decode_lossy(
+                b"\xFF\xFF\x10\x00\x00\xD8\x00\x00\x00\x00\x11\x00a\x00\x00\x00a",
+                UTF_32LE
+            )
+        );
+        assert_eq!("\u{1F600}", decode_lossy(b"\x00\x01\xF6\x00", UTF_32BE));
+    }
+
+    fn error(offset: usize, kind: DecodeErrorKind) -> Result<String, DecodeError> {
+        Err(DecodeError { offset, kind })
+    }
+
+    #[test]
+    #[cfg(all(feature = "utf16", feature = "utf32"))]
+    fn test_decode() {
+        let testdata = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata");
+        let text = fs::read_to_string(testdata.join("text_UTF-8.txt")).unwrap();
+        for name in &[
+            "text_UTF-8-BOM.txt",
+            "text_UTF-16LE-BOM.txt",
+            "text_UTF-16BE.txt",
+            "text_UTF-32BE-BOM.txt",
+        ] {
+            let buffer = fs::read(testdata.join(name)).unwrap();
+            let content_type = match inspect(&buffer) {
+                BINARY => UTF_16BE,
+                content_type => content_type,
+            };
+            assert_eq!(Ok(text.clone()), decode(&buffer, content_type), "{}", name);
+        }
+
+        assert_eq!(Ok(String::new()), decode(b"\xEF\xBB\xBF", UTF_8_BOM));
+        assert_eq!(Ok(String::new()), decode(b"\xFF\xFE\x00\x00", UTF_32LE));
+        assert_eq!(
+            Ok("Gr\u{FC}\u{DF}e".to_string()),
+            decode(b"Gr\xFC\xDFe", LATIN_1)
+        );
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        use self::DecodeErrorKind::*;
+
+        // A 5-byte sequence (not valid since RFC 3629)
+        assert_eq!(
+            error(2, InvalidUtf8),
+            decode(b"ab\xF8\x88\x80\x80\x80", UTF_8)
+        );
+        assert_eq!(
+            error(5, InvalidUtf8),
+            decode(b"\xEF\xBB\xBFab\xE2\x9C", UTF_8_BOM)
+        );
+        assert_eq!(error(1, InvalidUtf8), decode(b"\x00\xFF", BINARY));
+
+        // A lone high surrogate, followed by a regular character
+        let buffer = b"\xFE\xFF\x00a\xD8\x3D\x00b";
+        assert_eq!(
+            error(4, UnpairedSurrogate(0xD83D)),
+            decode(buffer, UTF_16BE)
+        );
+        let buffer = b"\xD8\x3D\xDE\x00\xDE\x00";
+        assert_eq!(
+            error(4, UnpairedSurrogate(0xDE00)),
+            decode(buffer, UTF_16BE)
+        );
+        assert_eq!(
+            error(4, IncompleteCodeUnit),
+            decode(b"\xFF\xFEa\x00b", UTF_16LE)
+        );
+
+        let buffer = b"\xFF\xFE\x00\x00a\x00\x00\x00\x00\x00\x11\x00";
+        assert_eq!(error(8, InvalidScalar(0x110000)), decode(buffer, UTF_32LE));
+        let buffer = b"\x00\x00\xD8\x00";
+        assert_eq!(error(0, InvalidScalar(0xD800)), decode(buffer, UTF_32BE));
+        assert_eq!(
+            error(4, IncompleteCodeUnit),
+            decode(b"\x00\x00\x00a\x00\x00", UTF_32BE)
+        );
+    }
+
+    #[test]
+    fn test_decode_error_messages() {
+        let message =
+            |buffer: &[u8], content_type| decode(buffer, content_type).unwrap_err().to_string();
+        assert_eq!(
+            "invalid UTF-8 sequence at byte 2",
+            message(b"ab\xFF", UTF_8)
+        );
+        assert_eq!(
+            "unpaired UTF-16 surrogate 0xD83D at byte 0",
+            message(b"\x3D\xD8", UTF_16LE)
+        );
+        assert_eq!(
+            "0x110000 at byte 0 is not a valid Unicode code point",
+            message(b"\x00\x11\x00\x00", UTF_32BE)
+        );
+        assert_eq!(
+            "incomplete code unit at byte 2 (at the end of the content)",
+            message(b"\x00a\x00", UTF_16BE)
+        );
+    }
+
+    #[test]
+    #[cfg(all(feature = "utf16", feature = "utf32"))]
+    fn test_inspect_and_decode_testdata() {
+        let testdata = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata");
+        let reference = fs::read_to_string(testdata.join("text_UTF-8.txt")).unwrap();
+        let paths = fs::read_dir(&testdata)
+            .unwrap()
+            .map(|entry| entry.unwrap().path());
+        for path in paths.filter(|path| path.is_file()) {
+            let name = path.file_name().unwrap().to_str().unwrap();
+            let buffer = fs::read(&path).unwrap();
+            let content_type = inspect(&buffer);
+            let counted = decode_lossy_counted(&buffer, content_type);
+
+            let decoded = inspect_and_decode(&buffer);
+            assert_eq!(content_type, decoded.content_type, "{}", name);
+            assert_eq!(counted.text, decoded.text, "{}", name);
+            assert_eq!(counted.replacements, decoded.replacements, "{}", name);
+            #[cfg(feature = "std")]
+            assert_eq!(DecodedText::from_file(&path).unwrap(), decoded, "{}", name);
+            if content_type.is_text() && name.starts_with("text_UTF-") && name.ends_with(".txt") {
+                assert_eq!(reference, &*decoded, "{}", name);
+                assert_eq!(0, decoded.replacements, "{}", name);
+            }
+        }
+
+        let inspector = Inspector::new()
+            .detect_utf16_without_bom(true)
+            .build()
+            .unwrap();
+        for name in &["text_UTF-16LE.txt", "text_UTF-16BE.txt"] {
+            let buffer = fs::read(testdata.join(name)).unwrap();
+            let decoded = inspector.inspect_and_decode(&buffer);
+            assert!(decoded.content_type.is_text(), "{}", name);
+            assert_eq!(0, decoded.bom_length, "{}", name);
+            assert_eq!(reference, &*decoded, "{}", name);
+        }
+
+        let buffer = fs::read(testdata.join("text_UTF-32BE-BOM.txt")).unwrap();
+        assert_eq!(4, DecodedText::from_bytes(&buffer).bom_length);
+        #[cfg(feature = "std")]
+        {
+            let error = DecodedText::from_file(testdata.join("missing.txt")).unwrap_err();
+            assert_eq!(std::io::ErrorKind::NotFound, error.kind());
+        }
+    }
+
+    #[test]
+    fn test_inspect_and_decode_borrowed() {
+        assert!(matches!(
+            inspect_and_decode(b"text").text,
+            Cow::Borrowed("text")
+        ));
+        let decoded = inspect_and_decode(b"\xEF\xBB\xBFtext");
+        assert!(matches!(decoded.text, Cow::Borrowed("text")));
+        assert_eq!(3, decoded.bom_length);
+        assert!(matches!(inspect_and_decode(b"").text, Cow::Borrowed("")));
+        assert!(matches!(
+            inspect_and_decode(b"text").into_owned().text,
+            Cow::Owned(_)
+        ));
+
+        // A byte order mark of a type that is not allowed is not removed
+        let inspector = Inspector::new()
+            .allowed_types(&[UTF_8, ASCII])
+            .fallback_type(UTF_8)
+            .build()
+            .unwrap();
+        assert_eq!(
+            DecodedText {
+                text: Cow::Borrowed("\u{FFFD}\u{FFFD}a\u{0}"),
+                content_type: ContentType::UTF_8,
+                bom_length: 0,
+                replacements: 2,
+            },
+            inspector.inspect_and_decode(b"\xFF\xFEa\x00")
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_decoded_text_serde() {
+        extern crate toml;
+
+        let decoded = inspect_and_decode(b"\xFE\xFF\x00a\xD8\x00");
+        let serialized = toml::to_string(&decoded).unwrap();
+        assert_eq!(
+            "text = \"a\u{FFFD}\"\ncontent_type = \"UTF-16BE\"\nbom_length = 2\nreplacements = 1\n",
+            serialized
+        );
+        let deserialized: DecodedText = toml::from_str(&serialized).unwrap();
+        assert_eq!(decoded, deserialized);
+    }
+
+    #[test]
+    fn test_strip_bom_in_place() {
+        for &(bom, content_type) in &[
+            (&b"\xEF\xBB\xBF"[..], UTF_8_BOM),
+            (b"\xFF\xFE", UTF_16LE),
+            (b"\xFE\xFF", UTF_16BE),
+            (b"\xFF\xFE\x00\x00", UTF_32LE),
+            (b"\x00\x00\xFE\xFF", UTF_32BE),
+        ] {
+            let mut buffer = [bom, bom, b"text"].concat();
+            assert_eq!(Some(content_type), strip_bom_in_place(&mut buffer, false));
+            assert_eq!([bom, b"text"].concat(), buffer);
+            assert_eq!(Some(content_type), strip_bom_in_place(&mut buffer, true));
+            assert_eq!(b"text", &buffer[..]);
+            assert_eq!(None, strip_bom_in_place(&mut buffer, true));
+            assert_eq!(b"text", &buffer[..]);
+
+            let mut buffer = [bom, bom, bom].concat();
+            assert_eq!(Some(content_type), strip_bom_in_place(&mut buffer, true));
+            assert!(buffer.is_empty());
+            assert_eq!(None, strip_bom_in_place(&mut buffer, true));
+        }
+
+        // Only repetitions of the same byte order mark are removed
+        let mut buffer = b"\xFE\xFF\xEF\xBB\xBFtext".to_vec();
+        assert_eq!(Some(UTF_16BE), strip_bom_in_place(&mut buffer, true));
+        assert_eq!(b"\xEF\xBB\xBFtext", &buffer[..]);
+
+        let mut buffer = b"\xFF\xFE\xFF\xFE\x00\x00".to_vec();
+        assert_eq!(Some(UTF_16LE), strip_bom_in_place(&mut buffer, true));
+        assert_eq!(b"\x00\x00", &buffer[..]);
+
+        let mut buffer = b"\xEF\xBB".to_vec();
+        assert_eq!(None, strip_bom_in_place(&mut buffer, true));
+        assert_eq!(b"\xEF\xBB", &buffer[..]);
+    }
+
+    #[test]
+    fn test_strip_bom_in_place_str() {
+        let mut text = String::from("\u{FEFF}\u{FEFF}text\u{FEFF}");
+        assert!(strip_bom_in_place_str(&mut text, false));
+        assert_eq!("\u{FEFF}text\u{FEFF}", text);
+        assert!(strip_bom_in_place_str(&mut text, true));
+        assert_eq!("text\u{FEFF}", text);
+        assert!(!strip_bom_in_place_str(&mut text, true));
+        assert_eq!("text\u{FEFF}", text);
+
+        let mut text = String::from("\u{FEFF}");
+        assert!(strip_bom_in_place_str(&mut text, false));
+        assert!(text.is_empty());
+        assert!(!strip_bom_in_place_str(&mut text, false));
+    }
+
+    #[test]
+    fn test_decode_lossy_counted() {
+        let counted = |buffer: &[u8], content_type| {
+            let decoded = decode_lossy_counted(buffer, content_type);
+            assert_eq!(decode_lossy(buffer, content_type), decoded.text);
+            (decoded.replacements, decoded.first_error_offset)
+        };
+
+        // U+FFFD in the content is not counted
+        assert_eq!((0, None), counted("a\u{FFFD}b".as_bytes(), UTF_8));
+        assert_eq!(
+            (1, Some(7)),
+            counted(b"\xEF\xBB\xBF\xEF\xBF\xBDa\xFF", UTF_8_BOM)
+        );
+        assert_eq!((3, Some(0)), counted(b"\xE2\x82 \xF8\x88", BINARY));
+        assert_eq!((0, None), counted(b"\xFF\xFE\xFD\xFF", UTF_16LE));
+        assert_eq!(
+            (3, Some(4)),
+            counted(b"\xFF\xFE\xFD\xFF\x3D\xD8a\x00\x00\xDEb", UTF_16LE)
+        );
+        assert_eq!((0, None), counted(b"\x00\x00\xFF\xFD", UTF_32BE));
+        assert_eq!(
+            (3, Some(4)),
+            counted(
+                b"\x00\x00\xFF\xFD\x00\x11\x00\x00\x00\x00\xD8\x00\x00",
+                UTF_32BE
+            )
+        );
+        assert_eq!((0, None), counted(b"caf\xE9", LATIN_1));
+
+        assert!(matches!(
+            decode_lossy_counted("a\u{FFFD}".as_bytes(), UTF_8).text,
+            Cow::Borrowed(_)
+        ));
+        assert!(matches!(
+            decode_lossy_counted(b"", UTF_8).text,
+            Cow::Borrowed("")
+        ));
+    }
+
+    #[test]
+    fn test_decode_newlines() {
+        let normalized = |buffer: &[u8], content_type, policy| {
+            let decoded = decode_lossy_with(buffer, content_type, policy);
+            assert_eq!(
+                decoded.text,
+                decode_with(buffer, content_type, policy).unwrap()
+            );
+            (decoded.text.into_owned(), decoded.newlines_changed)
+        };
+
+        // CRLF
+        let crlf = b"a\r\nb\r\n";
+        assert_eq!(("a\nb\n".into(), 2), normalized(crlf, UTF_8, NormalizeToLf));
+        assert_eq!(
+            ("a\r\nb\r\n".into(), 0),
+            normalized(crlf, UTF_8, NormalizeToCrlf)
+        );
+
+        // CR only, including a CR at the very end
+        let cr = b"a\rb\r";
+        assert_eq!(("a\nb\n".into(), 2), normalized(cr, ASCII, NormalizeToLf));
+        assert_eq!(
+            ("a\r\nb\r\n".into(), 2),
+            normalized(cr, LATIN_1, NormalizeToCrlf)
+        );
+
+        // Mixed, in every encoding
+        let mixed = "a\r\nb\rc\nd\r\r\ne\u{FFFD}\r";
+        for &(content_type, ref buffer) in &[
+            (UTF_8_BOM, [&b"\xEF\xBB\xBF"[..], mixed.as_bytes()].concat()),
+            (
+                UTF_16BE,
+                mixed.encode_utf16().flat_map(u16::to_be_bytes).collect(),
+            ),
+            (
+                UTF_32LE,
+                mixed
+                    .chars()
+                    .flat_map(|c| u32::from(c).to_le_bytes())
+                    .collect(),
+            ),
+        ] {
+            assert_eq!(
+                ("a\nb\nc\nd\n\ne\u{FFFD}\n".into(), 5),
+                normalized(buffer, content_type, NormalizeToLf)
+            );
+            assert_eq!(
+                ("a\r\nb\r\nc\r\nd\r\n\r\ne\u{FFFD}\r\n".into(), 4),
+                normalized(buffer, content_type, NormalizeToCrlf)
+            );
+            assert_eq!(
+                (mixed.into(), 0),
+                normalized(buffer, content_type, Preserve)
+            );
+        }
+
+        // A CR before an invalid sequence is a line ending on its own
+        let decoded = decode_lossy_with(b"a\r\xFF\n", UTF_8, NormalizeToLf);
+        assert_eq!("a\n\u{FFFD}\n", decoded.text);
+        assert_eq!((1, 1), (decoded.replacements, decoded.newlines_changed));
+        assert_eq!(
+            Err(DecodeError {
+                offset: 2,
+                kind: DecodeErrorKind::InvalidUtf8,
+            }),
+            decode_with(b"a\r\xFF\n", UTF_8, NormalizeToLf)
+        );
+
+        // Content that does not change is borrowed
+        for &(buffer, policy) in &[
+            (&b"a\rb\r\nc\n"[..], Preserve),
+            (b"a\nb", NormalizeToLf),
+            (b"a\r\nb", NormalizeToCrlf),
+        ] {
+            assert!(matches!(
+                decode_lossy_with(buffer, UTF_8, policy).text,
+                Cow::Borrowed(_)
+            ));
+            assert!(matches!(
+                decode_lossy_with(buffer, LATIN_1, policy).text,
+                Cow::Borrowed(_)
+            ));
+        }
+        assert!(matches!(
+            decode_lossy_with(b"a\r\nb", UTF_8, NormalizeToLf).text,
+            Cow::Owned(_)
+        ));
+    }
+}
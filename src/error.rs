@@ -0,0 +1,222 @@
+use std::error;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::{ConfigError, ConvertError, DecodeError};
+
+/// An error of any of the functions of this crate that read, decode or convert content, or
+/// configure an [`Inspector`](struct.Inspector.html), so that they can be combined with `?`. The
+/// narrower error types of the individual functions convert into it with `From`. New functions
+/// can bring new kinds of errors, so a `match` needs a wildcard arm.
+///
+/// The message includes the path of the file, if it is known, and the underlying error is
+/// available through `source`:
+///
+/// ```
+/// use std::error::Error as _;
+/// use content_inspector::{decode_file, Error};
+///
+/// let error = decode_file("does/not/exist.txt").unwrap_err();
+/// assert!(error.to_string().starts_with("does/not/exist.txt: "));
+/// assert!(matches!(error, Error::Io { .. }));
+/// assert!(error.source().is_some());
+/// ```
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// An error while reading (or writing) content.
+    Io {
+        /// The path of the file, if known.
+        path: Option<PathBuf>,
+        /// The underlying error.
+        error: io::Error,
+    },
+
+    /// Content that is not valid in its encoding.
+    Decode {
+        /// The path of the file, if known.
+        path: Option<PathBuf>,
+        /// The underlying error, with the offset of the invalid content.
+        error: DecodeError,
+    },
+
+    /// Content that can not be converted, see [`convert`](fn.convert.html).
+    Convert(ConvertError),
+
+    /// An invalid configuration of an [`Inspector`](struct.Inspector.html).
+    Config(ConfigError),
+}
+
+impl Error {
+    /// Attach the path of the file that the error is about, to `Io` and `Decode` errors that do
+    /// not have one yet.
+    pub fn with_path<P: AsRef<Path>>(mut self, file: P) -> Error {
+        match self {
+            Error::Io { ref mut path, .. } | Error::Decode { ref mut path, .. } => {
+                if path.is_none() {
+                    *path = Some(file.as_ref().to_path_buf());
+                }
+            }
+            Error::Convert(_) | Error::Config(_) => {}
+        }
+        self
+    }
+
+    /// The path of the file that the error is about, if known.
+    pub fn path(&self) -> Option<&Path> {
+        match *self {
+            Error::Io { ref path, .. } | Error::Decode { ref path, .. } => path.as_deref(),
+            Error::Convert(_) | Error::Config(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(path) = self.path() {
+            write!(f, "{}: ", path.display())?;
+        }
+        match *self {
+            Error::Io { ref error, .. } => write!(f, "{}", error),
+            Error::Decode { ref error, .. } => write!(f, "{}", error),
+            Error::Convert(ref error) => write!(f, "{}", error),
+            Error::Config(ref error) => write!(f, "invalid configuration: {}", error),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::Io { ref error, .. } => Some(error),
+            Error::Decode { ref error, .. } => Some(error),
+            Error::Convert(ref error) => Some(error),
+            Error::Config(ref error) => Some(error),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Error {
+        Error::Io { path: None, error }
+    }
+}
+
+impl From<DecodeError> for Error {
+    fn from(error: DecodeError) -> Error {
+        Error::Decode { path: None, error }
+    }
+}
+
+impl From<ConvertError> for Error {
+    fn from(error: ConvertError) -> Error {
+        match error {
+            ConvertError::Decode(error) => Error::from(error),
+            error => Error::Convert(error),
+        }
+    }
+}
+
+impl From<ConfigError> for Error {
+    fn from(error: ConfigError) -> Error {
+        Error::Config(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Error;
+    use crate::{
+        convert, decode, decode_file, BomPolicy, ConfigError, ContentType::*, DecodeErrorKind,
+        Inspector,
+    };
+    use std::env;
+    use std::error::Error as _;
+    use std::fs;
+    use std::io;
+    use std::path::Path;
+
+    #[test]
+    fn test_missing_file() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata/does_not_exist.txt");
+        let error = decode_file(&path).unwrap_err();
+        assert_eq!(Some(path.as_path()), error.path());
+        let io_error = io::Error::from(io::ErrorKind::NotFound);
+        let message = fs::read(&path).unwrap_err().to_string();
+        assert_eq!(
+            format!("{}: {}", path.display(), message),
+            error.to_string()
+        );
+
+        // The underlying error is the source
+        let source = error.source().unwrap();
+        let source = source.downcast_ref::<io::Error>().unwrap();
+        assert_eq!(io_error.kind(), source.kind());
+        assert_eq!(message, source.to_string());
+    }
+
+    #[test]
+    fn test_decode_error() {
+        let path = env::temp_dir().join(format!(
+            "content_inspector_error_{}.txt",
+            ::std::process::id()
+        ));
+        fs::write(&path, b"valid text\ncaf\xE9\nmore text").unwrap();
+
+        let error = decode_file(&path).unwrap_err();
+        match error {
+            Error::Decode { ref error, .. } => {
+                assert_eq!(
+                    (14, DecodeErrorKind::InvalidUtf8),
+                    (error.offset, error.kind)
+                );
+            }
+            ref error => panic!("{:?}", error),
+        }
+        assert_eq!(
+            format!("{}: invalid UTF-8 sequence at byte 14", path.display()),
+            error.to_string()
+        );
+        assert!(error.source().unwrap().is::<crate::DecodeError>());
+        fs::remove_file(&path).unwrap();
+
+        // Without a path
+        let error = Error::from(decode(b"ab\xFF", UTF_8).unwrap_err());
+        assert_eq!(None, error.path());
+        assert_eq!("invalid UTF-8 sequence at byte 2", error.to_string());
+        let error = error.with_path("a.txt").with_path("b.txt");
+        assert_eq!("a.txt: invalid UTF-8 sequence at byte 2", error.to_string());
+    }
+
+    #[test]
+    fn test_conversions() {
+        // The decode errors of a conversion are decode errors
+        let error = Error::from(convert(b"\xFF", UTF_8, UTF_16LE, BomPolicy::Auto).unwrap_err());
+        assert!(matches!(error, Error::Decode { .. }));
+        let error = Error::from(convert(b"a", BINARY, UTF_8, BomPolicy::Auto).unwrap_err());
+        assert_eq!("can not convert binary content", error.to_string());
+        assert!(error.path().is_none());
+
+        let error: Error = Inspector::new()
+            .magic_number(0, b"")
+            .build()
+            .unwrap_err()
+            .into();
+        assert_eq!(
+            "invalid configuration: magic numbers must not be empty",
+            error.to_string()
+        );
+        let source = error.source().unwrap().downcast_ref::<ConfigError>();
+        assert_eq!(Some(&ConfigError::EmptyMagicNumber), source);
+
+        // Composes with `?`
+        fn read(path: &Path) -> Result<String, Error> {
+            let inspector = Inspector::new().build()?;
+            let buffer = fs::read(path).map_err(|error| Error::from(error).with_path(path))?;
+            Ok(decode(&buffer, inspector.inspect(&buffer))?)
+        }
+        let readme = Path::new(env!("CARGO_MANIFEST_DIR")).join("README.md");
+        assert!(read(&readme).unwrap().contains("content_inspector"));
+    }
+}
@@ -0,0 +1,297 @@
+//! A C interface to the default inspector (with the `ffi` feature). The declarations are in
+//! `include/content_inspector.h`, which is generated with
+//! `cbindgen --config cbindgen.toml --output include/content_inspector.h`.
+//!
+//! To link it from C, build a static or dynamic library with
+//! `cargo rustc --release --lib --features ffi --crate-type staticlib` (or `cdylib`).
+//!
+//! Types of content are passed as the stable codes `CI_BINARY` (0) to `CI_ASCII` (8). None of the
+//! functions panics across the boundary: invalid arguments and panics are reported as `CI_ERROR`
+//! (or `-1`).
+
+use std::os::raw::{c_char, c_int};
+use std::panic::{self, UnwindSafe};
+use std::ptr;
+use std::slice;
+
+use crate::{inspect, inspect_detailed, BinaryFormat, ContentType, InspectionReport, Reason};
+
+/// `BINARY`
+pub const CI_BINARY: u8 = 0;
+/// `UTF_8`
+pub const CI_UTF_8: u8 = 1;
+/// `UTF_8_BOM`
+pub const CI_UTF_8_BOM: u8 = 2;
+/// `UTF_16LE`
+pub const CI_UTF_16LE: u8 = 3;
+/// `UTF_16BE`
+pub const CI_UTF_16BE: u8 = 4;
+/// `UTF_32LE`
+pub const CI_UTF_32LE: u8 = 5;
+/// `UTF_32BE`
+pub const CI_UTF_32BE: u8 = 6;
+/// `LATIN_1`
+pub const CI_LATIN_1: u8 = 7;
+/// `ASCII`
+pub const CI_ASCII: u8 = 8;
+/// Returned for invalid arguments, or if the inspection panicked.
+pub const CI_ERROR: u8 = 255;
+
+/// The value of the offsets in a `CiReport` that are not set.
+pub const CI_NONE: usize = usize::MAX;
+
+/// An [`InspectionReport`](../struct.InspectionReport.html), with the types of content as codes,
+/// the reason and the binary format as the codes below, and `CI_NONE` for missing offsets.
+///
+/// Reasons: 0 empty buffer, 1 byte order mark, 2 UTF-16 pattern, 3 NULL bytes, 4 magic number,
+/// 5 control characters, 6 printable ratio, 7 invalid UTF-8, 8 invalid encoding, 9 custom
+/// heuristic, 10 no binary indicators.
+///
+/// Binary formats: 0 none, 1 PDF, 2 PNG, 3 Windows shortcut, 4 Windows registry hive, 5 cabinet,
+/// 6 Windows prefetch, 7 gzip, 8 Zstandard, 255 custom.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct CiReport {
+    pub content_type: u8,
+    pub detected_type: u8,
+    pub reason: u8,
+    pub binary_format: u8,
+    pub null_bytes: usize,
+    pub first_null_offset: usize,
+    pub bom_length: usize,
+    pub control_characters: usize,
+    pub printable_bytes: usize,
+    pub invalid_utf8_offset: usize,
+    pub scanned_bytes: usize,
+    pub validated_bytes: usize,
+    pub sniffed_bytes: usize,
+    pub bytes_examined: usize,
+}
+
+fn content_type_code(content_type: ContentType) -> u8 {
+    match content_type {
+        ContentType::BINARY => CI_BINARY,
+        ContentType::UTF_8 => CI_UTF_8,
+        ContentType::UTF_8_BOM => CI_UTF_8_BOM,
+        ContentType::UTF_16LE => CI_UTF_16LE,
+        ContentType::UTF_16BE => CI_UTF_16BE,
+        ContentType::UTF_32LE => CI_UTF_32LE,
+        ContentType::UTF_32BE => CI_UTF_32BE,
+        ContentType::LATIN_1 => CI_LATIN_1,
+        ContentType::ASCII => CI_ASCII,
+    }
+}
+
+fn reason_code(reason: Reason) -> u8 {
+    match reason {
+        Reason::EmptyBuffer => 0,
+        Reason::ByteOrderMark => 1,
+        Reason::Utf16Pattern => 2,
+        Reason::NullBytes => 3,
+        Reason::MagicNumber => 4,
+        Reason::ControlCharacters => 5,
+        Reason::PrintableRatio => 6,
+        Reason::InvalidUtf8 => 7,
+        Reason::InvalidEncoding => 8,
+        Reason::Heuristic(_) => 9,
+        Reason::NoBinaryIndicators => 10,
+    }
+}
+
+fn binary_format_code(format: Option<BinaryFormat>) -> u8 {
+    match format {
+        None => 0,
+        Some(BinaryFormat::Pdf) => 1,
+        Some(BinaryFormat::Png) => 2,
+        Some(BinaryFormat::WindowsShortcut) => 3,
+        Some(BinaryFormat::WindowsRegistryHive) => 4,
+        Some(BinaryFormat::Cabinet) => 5,
+        Some(BinaryFormat::WindowsPrefetch) => 6,
+        Some(BinaryFormat::Gzip) => 7,
+        Some(BinaryFormat::Zstd) => 8,
+        Some(BinaryFormat::Custom(_)) => 255,
+    }
+}
+
+impl From<&InspectionReport> for CiReport {
+    fn from(report: &InspectionReport) -> CiReport {
+        CiReport {
+            content_type: content_type_code(report.content_type),
+            detected_type: content_type_code(report.detected_type),
+            reason: reason_code(report.reason),
+            binary_format: binary_format_code(report.binary_format),
+            null_bytes: report.null_bytes,
+            first_null_offset: report.first_null_offset.unwrap_or(CI_NONE),
+            bom_length: report.bom_length,
+            control_characters: report.control_characters,
+            printable_bytes: report.printable_bytes,
+            invalid_utf8_offset: report.invalid_utf8_offset.unwrap_or(CI_NONE),
+            scanned_bytes: report.scanned_bytes,
+            validated_bytes: report.validated_bytes,
+            sniffed_bytes: report.sniffed_bytes,
+            bytes_examined: report.bytes_examined,
+        }
+    }
+}
+
+/// The buffer of a pointer and a length from C, which may be NULL if the length is zero.
+unsafe fn buffer<'a>(data: *const u8, length: usize) -> Option<&'a [u8]> {
+    if length == 0 {
+        Some(&[])
+    } else if data.is_null() || length > isize::MAX as usize {
+        None
+    } else {
+        // SAFETY: the caller guarantees that the memory is valid
+        Some(unsafe { slice::from_raw_parts(data, length) })
+    }
+}
+
+/// Calls `f`, returning `None` if it panics.
+fn catch<T, F: FnOnce() -> T + UnwindSafe>(f: F) -> Option<T> {
+    panic::catch_unwind(f).ok()
+}
+
+/// Determine the type of content of the `length` bytes at `data`, like
+/// [`inspect`](../fn.inspect.html). Returns `CI_ERROR` if `data` is NULL (and `length` is not
+/// zero).
+///
+/// # Safety
+///
+/// `data` has to point to `length` readable bytes, unless it is NULL or `length` is zero.
+#[no_mangle]
+pub unsafe extern "C" fn ci_inspect(data: *const u8, length: usize) -> u8 {
+    let buffer = match unsafe { buffer(data, length) } {
+        Some(buffer) => buffer,
+        None => return CI_ERROR,
+    };
+    catch(|| content_type_code(inspect(buffer))).unwrap_or(CI_ERROR)
+}
+
+/// Like [`ci_inspect`](fn.ci_inspect.html), but fills the report (like
+/// [`inspect_detailed`](../fn.inspect_detailed.html)). Returns zero on success, and `-1` if
+/// `data` (with a length that is not zero) or `report` is NULL. The report is not changed on
+/// errors.
+///
+/// # Safety
+///
+/// `data` has to point to `length` readable bytes, unless it is NULL or `length` is zero, and
+/// `report` has to be NULL or point to a writable `CiReport`.
+#[no_mangle]
+pub unsafe extern "C" fn ci_inspect_detailed(
+    data: *const u8,
+    length: usize,
+    report: *mut CiReport,
+) -> c_int {
+    let buffer = match unsafe { buffer(data, length) } {
+        Some(buffer) if !report.is_null() => buffer,
+        _ => return -1,
+    };
+    match catch(|| CiReport::from(&inspect_detailed(buffer))) {
+        Some(result) => {
+            // SAFETY: the caller guarantees that the report is writable
+            unsafe { ptr::write(report, result) };
+            0
+        }
+        None => -1,
+    }
+}
+
+/// The name of a type of content (like `"UTF-16LE"`, see the `Display` implementation of
+/// [`ContentType`](../enum.ContentType.html)), as a static NUL-terminated string. Returns NULL for
+/// unknown codes.
+#[no_mangle]
+pub extern "C" fn ci_content_type_name(content_type: u8) -> *const c_char {
+    let name: &'static [u8] = match content_type {
+        CI_BINARY => b"binary\0",
+        CI_UTF_8 => b"UTF-8\0",
+        CI_UTF_8_BOM => b"UTF-8-BOM\0",
+        CI_UTF_16LE => b"UTF-16LE\0",
+        CI_UTF_16BE => b"UTF-16BE\0",
+        CI_UTF_32LE => b"UTF-32LE\0",
+        CI_UTF_32BE => b"UTF-32BE\0",
+        CI_LATIN_1 => b"Latin-1\0",
+        CI_ASCII => b"ASCII\0",
+        _ => return ptr::null(),
+    };
+    name.as_ptr() as *const c_char
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CStr;
+    use std::fs;
+    use std::path::Path;
+    use std::ptr;
+
+    use super::{
+        ci_content_type_name, ci_inspect, ci_inspect_detailed, content_type_code, CiReport,
+        CI_ERROR, CI_NONE,
+    };
+    use crate::{inspect_detailed, ContentType::*};
+
+    #[test]
+    fn test_ffi() {
+        let types = [
+            BINARY, UTF_8, UTF_8_BOM, UTF_16LE, UTF_16BE, UTF_32LE, UTF_32BE, LATIN_1, ASCII,
+        ];
+        for (code, &content_type) in types.iter().enumerate() {
+            assert_eq!(code as u8, content_type_code(content_type));
+            let name = unsafe { CStr::from_ptr(ci_content_type_name(code as u8)) };
+            assert_eq!(content_type.to_string(), name.to_str().unwrap());
+        }
+        assert!(ci_content_type_name(9).is_null());
+        assert!(ci_content_type_name(CI_ERROR).is_null());
+
+        let buffer = b"\xFF\xFEH\x00i\x00";
+        assert_eq!(3, unsafe { ci_inspect(buffer.as_ptr(), buffer.len()) });
+        assert_eq!(1, unsafe { ci_inspect(ptr::null(), 0) });
+        assert_eq!(CI_ERROR, unsafe { ci_inspect(ptr::null(), 1) });
+
+        let mut report = CiReport::default();
+        let buffer = b"text\x00";
+        assert_eq!(0, unsafe {
+            ci_inspect_detailed(buffer.as_ptr(), buffer.len(), &mut report)
+        });
+        assert_eq!(CiReport::from(&inspect_detailed(buffer)), report);
+        assert_eq!(
+            (0, 3, 4),
+            (report.content_type, report.reason, report.first_null_offset)
+        );
+        assert_eq!(CI_NONE, report.invalid_utf8_offset);
+        assert_eq!(-1, unsafe {
+            ci_inspect_detailed(buffer.as_ptr(), buffer.len(), ptr::null_mut())
+        });
+        assert_eq!(-1, unsafe {
+            ci_inspect_detailed(ptr::null(), 1, &mut report)
+        });
+    }
+
+    /// The header has to be regenerated when the interface changes.
+    #[test]
+    fn test_header() {
+        let header = Path::new(env!("CARGO_MANIFEST_DIR")).join("include/content_inspector.h");
+        let header = fs::read_to_string(header).unwrap();
+        let source = include_str!("ffi.rs");
+        for line in source.lines() {
+            if let Some(rest) = line.strip_prefix("pub const ") {
+                let (name, value) = rest.split_once(": ").unwrap();
+                let value = value.split_once(" = ").unwrap().1.trim_end_matches(';');
+                let value = match value {
+                    "usize::MAX" => "SIZE_MAX",
+                    value => value,
+                };
+                let define = format!("#define {} {}", name, value);
+                assert!(header.contains(&define), "{}", define);
+            }
+            if let Some(rest) = line.split("extern \"C\" fn ").nth(1) {
+                let name = rest.split('(').next().unwrap();
+                assert!(header.contains(&format!("{}(", name)), "{}", name);
+            }
+            if let Some(field) = line.strip_prefix("    pub ") {
+                let (name, ty) = field.trim_end_matches(',').split_once(": ").unwrap();
+                let ty = if ty == "u8" { "uint8_t" } else { "uintptr_t" };
+                assert!(header.contains(&format!("  {} {};", ty, name)), "{}", name);
+            }
+        }
+    }
+}
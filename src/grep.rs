@@ -0,0 +1,185 @@
+use std::io::{Seek, SeekFrom};
+use std::path::Path;
+
+use grep_matcher::Matcher;
+use grep_searcher::{BinaryDetection, Encoding, SearcherBuilder, Sink, SinkError};
+
+use crate::reader::{open_regular_file, read_prefix};
+use crate::{default_inspector, ContentType, Inspector, Utf8Reader};
+
+/// How content is searched with [`grep-searcher`](https://crates.io/crates/grep-searcher)
+/// (with the `grep` feature), according to its type. See
+/// [`Inspector::search_plan`](struct.Inspector.html#method.search_plan).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SearchPlan {
+    /// Binary content, which is not searched.
+    Skip,
+
+    /// Text that the searcher transcodes from the given encoding (or searches as UTF-8 if it is
+    /// `None`), without binary detection.
+    Search(Option<Encoding>),
+
+    /// Text in an encoding that the searcher does not support (UTF-32 and Latin-1, which it would
+    /// read as Windows-1252), which is transcoded to UTF-8 with a
+    /// [`Utf8Reader`](struct.Utf8Reader.html) before it is searched.
+    Transcode(ContentType),
+}
+
+impl SearchPlan {
+    /// The plan for content of the given type.
+    pub fn new(content_type: ContentType) -> SearchPlan {
+        use ContentType::*;
+
+        let encoding = |label| Some(Encoding::new(label).unwrap());
+        match content_type {
+            BINARY => SearchPlan::Skip,
+            UTF_8 | UTF_8_BOM | ASCII => SearchPlan::Search(None),
+            UTF_16LE => SearchPlan::Search(encoding("utf-16le")),
+            UTF_16BE => SearchPlan::Search(encoding("utf-16be")),
+            UTF_32LE | UTF_32BE | LATIN_1 => SearchPlan::Transcode(content_type),
+        }
+    }
+
+    /// Whether the content is searched at all.
+    pub fn is_searched(&self) -> bool {
+        *self != SearchPlan::Skip
+    }
+
+    /// Configure the builder of a searcher for this plan. The binary detection of the searcher is
+    /// disabled, since the content was found to be text (a byte order mark takes precedence over
+    /// NULL bytes, other than with `BinaryDetection::quit(0)`). With `Skip`, the NULL byte is set
+    /// as the binary byte to quit at, for callers that search anyway.
+    pub fn configure<'a>(&self, builder: &'a mut SearcherBuilder) -> &'a mut SearcherBuilder {
+        match *self {
+            SearchPlan::Skip => builder.binary_detection(BinaryDetection::quit(0x00)),
+            SearchPlan::Search(ref encoding) => builder
+                .binary_detection(BinaryDetection::none())
+                .encoding(encoding.clone())
+                .bom_sniffing(true),
+            SearchPlan::Transcode(_) => builder
+                .binary_detection(BinaryDetection::none())
+                .encoding(None)
+                .bom_sniffing(false),
+        }
+    }
+}
+
+impl Inspector {
+    /// The [`SearchPlan`](enum.SearchPlan.html) for content that starts with the given buffer, so
+    /// that `grep-searcher` treats it like this inspector: binary content is skipped and all text
+    /// is searched, including UTF-16 (with a byte order mark, or without one if it is detected)
+    /// and UTF-32.
+    pub fn search_plan(&self, buffer: &[u8]) -> SearchPlan {
+        SearchPlan::new(self.inspect(buffer))
+    }
+
+    /// Search the file at the given path with a searcher from the builder, configured by the
+    /// [`search_plan`](#method.search_plan) of the start of the file (only the bytes that can
+    /// influence the result are read, like in [`inspect_reader`](#method.inspect_reader)). Returns
+    /// `false` if the file was skipped as binary content.
+    ///
+    /// ```no_run
+    /// # use grep_regex::RegexMatcher;
+    /// use grep_searcher::{sinks::UTF8, SearcherBuilder};
+    /// use content_inspector::Inspector;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let matcher = RegexMatcher::new("TODO")?;
+    /// let inspector = Inspector::default();
+    /// let searched = inspector.search_path(
+    ///     &SearcherBuilder::new(),
+    ///     &matcher,
+    ///     "notes_utf16.txt",
+    ///     UTF8(|line, text| {
+    ///         print!("{}: {}", line, text);
+    ///         Ok(true)
+    ///     }),
+    /// )?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn search_path<P, M, S>(
+        &self,
+        builder: &SearcherBuilder,
+        matcher: M,
+        path: P,
+        sink: S,
+    ) -> Result<bool, S::Error>
+    where
+        P: AsRef<Path>,
+        M: Matcher,
+        S: Sink,
+    {
+        let mut file = open_regular_file(path.as_ref()).map_err(S::Error::error_io)?;
+        let prefix = read_prefix(self, &mut file).map_err(S::Error::error_io)?;
+        file.seek(SeekFrom::Start(0)).map_err(S::Error::error_io)?;
+
+        let plan = self.search_plan(&prefix);
+        let mut searcher = plan.configure(&mut builder.clone()).build();
+        match plan {
+            SearchPlan::Skip => return Ok(false),
+            SearchPlan::Search(_) => searcher.search_file(matcher, &file, sink)?,
+            SearchPlan::Transcode(content_type) => {
+                searcher.search_reader(matcher, Utf8Reader::new(file, content_type), sink)?
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// The [`SearchPlan`](enum.SearchPlan.html) for content that starts with the given buffer, with
+/// the default inspector.
+pub fn search_plan(buffer: &[u8]) -> SearchPlan {
+    default_inspector().search_plan(buffer)
+}
+
+/// Search the file at the given path with the default inspector. See
+/// [`Inspector::search_path`](struct.Inspector.html#method.search_path).
+pub fn search_path<P, M, S>(
+    builder: &SearcherBuilder,
+    matcher: M,
+    path: P,
+    sink: S,
+) -> Result<bool, S::Error>
+where
+    P: AsRef<Path>,
+    M: Matcher,
+    S: Sink,
+{
+    default_inspector().search_path(builder, matcher, path, sink)
+}
+
+#[cfg(test)]
+mod tests {
+    use grep_searcher::{Encoding, SearcherBuilder};
+
+    use super::SearchPlan;
+    use crate::{ContentType::*, Inspector};
+
+    #[test]
+    fn test_search_plan() {
+        let inspector = Inspector::default();
+        assert_eq!(
+            SearchPlan::Skip,
+            inspector.search_plan(b"\x89PNG\r\n\x1a\n")
+        );
+        assert_eq!(SearchPlan::Search(None), inspector.search_plan(b"text"));
+        assert_eq!(
+            SearchPlan::Search(Some(Encoding::new("utf-16le").unwrap())),
+            inspector.search_plan(b"\xFF\xFEt\x00")
+        );
+        assert_eq!(
+            SearchPlan::Transcode(UTF_32BE),
+            inspector.search_plan(b"\x00\x00\xFE\xFF\x00\x00\x00t")
+        );
+        assert!(!SearchPlan::new(BINARY).is_searched());
+        assert!(SearchPlan::new(LATIN_1).is_searched());
+
+        // NULL bytes do not stop the search of text
+        let mut builder = SearcherBuilder::new();
+        let searcher = SearchPlan::new(UTF_16LE).configure(&mut builder).build();
+        assert_eq!(None, searcher.binary_detection().quit_byte());
+        let searcher = SearchPlan::new(BINARY).configure(&mut builder).build();
+        assert_eq!(Some(0x00), searcher.binary_detection().quit_byte());
+    }
+}
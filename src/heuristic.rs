@@ -0,0 +1,1412 @@
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::any;
+#[cfg(any(test, feature = "bench-internals"))]
+use core::cell::Cell;
+use core::cmp::min;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::str;
+
+#[cfg(feature = "rayon")]
+use crate::parallel;
+use crate::search::{memchr, memchr_iter};
+use crate::simd::{self, BlockMasks};
+#[cfg(feature = "utf32")]
+use crate::units::utf32_units;
+#[cfg(feature = "utf16")]
+use crate::units::{u16_unit, utf16_units};
+use crate::{
+    binary_format, BinaryFormat, ContentType, InspectionReport, Reason, BYTE_ORDER_MARKS,
+    MAGIC_NUMBERS, MAX_BOM_LENGTH,
+};
+
+/// Control characters that commonly appear in text files (tab, line feed, form feed, carriage
+/// return and escape), as a bit set.
+pub(crate) const ALLOWED_CONTROL_CHARACTERS: u32 =
+    1 << 0x09 | 1 << 0x0A | 1 << 0x0C | 1 << 0x0D | 1 << 0x1B;
+
+/// A single stage of the detection pipeline of an [`Inspector`](struct.Inspector.html).
+///
+/// The built-in heuristics (byte order marks, magic numbers, NULL bytes, ...) run in the same
+/// pipeline, but only read the start of the buffer. Custom heuristics can be added with
+/// [`InspectorBuilder::with_heuristic`](struct.InspectorBuilder.html#method.with_heuristic):
+///
+/// ```
+/// use content_inspector::{ContentType, Heuristic, Inspector, Partial, Position};
+///
+/// #[derive(Debug)]
+/// struct MyFormat;
+///
+/// impl Heuristic for MyFormat {
+///     fn examine(&self, buffer: &[u8], _: &mut Partial) -> Option<ContentType> {
+///         if buffer.starts_with(b"MYFMT") {
+///             Some(ContentType::BINARY)
+///         } else {
+///             None
+///         }
+///     }
+/// }
+///
+/// let inspector = Inspector::new()
+///     .with_heuristic(Position::First, Box::new(MyFormat))
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(ContentType::BINARY, inspector.inspect(b"MYFMT data"));
+/// ```
+pub trait Heuristic: fmt::Debug + Send + Sync {
+    /// Examine the buffer. Returning a `ContentType` ends the inspection with this result,
+    /// returning `None` passes the buffer on to the next stage.
+    fn examine(&self, buffer: &[u8], partial: &mut Partial) -> Option<ContentType>;
+
+    /// The reason that is reported if this heuristic determines the type of content. Defaults to
+    /// `Reason::Heuristic` with the name of the implementing type.
+    fn reason(&self) -> Reason {
+        Reason::Heuristic(any::type_name::<Self>())
+    }
+
+    /// Feed the settings that affect the results of this heuristic into the given hasher, for
+    /// [`Inspector::fingerprint`](struct.Inspector.html#method.fingerprint). Defaults to the name
+    /// of the implementing type, which is enough for heuristics without settings. Heuristics that
+    /// are configured by their fields have to hash these as well.
+    fn fingerprint(&self, mut state: &mut dyn Hasher) {
+        any::type_name::<Self>().hash(&mut state);
+    }
+}
+
+/// The position of a custom [`Heuristic`](trait.Heuristic.html) in the detection pipeline.
+///
+/// The built-in stages run in the following order: validation of the content after a byte order
+/// mark, byte order marks, UTF-16 without BOM, magic numbers, NULL bytes, control characters,
+/// printable ratio, Latin-1 fallback, UTF-8 validation. Disabled stages are skipped. If no stage
+/// determines the type of content, it is reported as `UTF_8`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Position {
+    /// Before all built-in stages.
+    First,
+
+    /// Directly before the NULL-byte scan.
+    BeforeNullScan,
+
+    /// Directly after the NULL-byte scan.
+    AfterNullScan,
+
+    /// After all built-in stages, before the content is reported as `UTF_8`.
+    BeforeFallback,
+}
+
+/// The state of an ongoing inspection, as seen by a [`Heuristic`](trait.Heuristic.html).
+#[derive(Debug)]
+pub struct Partial<'a> {
+    window: Bytes<'a>,
+    report: InspectionReport,
+    /// Whether the window is ASCII, if the NULL-byte scan covered all of it.
+    ascii: Option<bool>,
+}
+
+impl<'a> Partial<'a> {
+    pub(crate) fn new(window: Bytes<'a>, report: InspectionReport) -> Partial<'a> {
+        Partial {
+            window,
+            report,
+            ascii: None,
+        }
+    }
+
+    /// The part of the buffer that is subject to the (bounded) scanning heuristics.
+    pub fn window(&self) -> &'a [u8] {
+        self.window.all()
+    }
+
+    /// The findings of the previous stages.
+    pub fn report(&self) -> &InspectionReport {
+        &self.report
+    }
+
+    /// Mutable access to the report, to record findings for the following stages.
+    pub fn report_mut(&mut self) -> &mut InspectionReport {
+        &mut self.report
+    }
+
+    /// Whether the window is ASCII (without a second pass over it, if possible).
+    pub(crate) fn is_ascii(&self) -> bool {
+        self.ascii.unwrap_or_else(|| {
+            let window = self.window.all();
+            #[cfg(feature = "rayon")]
+            if parallel::is_parallel(window) {
+                return parallel::is_ascii(window);
+            }
+            simd::ascii_prefix_length(window) == window.len()
+        })
+    }
+
+    /// Record that a stage examined the first `length` bytes of the buffer.
+    pub(crate) fn examined(&mut self, length: usize) {
+        self.report.bytes_examined = self.report.bytes_examined.max(length);
+    }
+
+    pub(crate) fn into_report(self) -> InspectionReport {
+        self.report
+    }
+}
+
+/// The buffer as seen by the built-in stages, which only read it through the methods that return
+/// its first bytes. In the tests of this crate and with the `bench-internals` feature, these
+/// count how far into the buffer the stages actually read, independently of what they record
+/// with `Partial::examined`.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct Bytes<'a> {
+    buffer: &'a [u8],
+    #[cfg(any(test, feature = "bench-internals"))]
+    read: &'a Cell<usize>,
+}
+
+impl<'a> Bytes<'a> {
+    #[cfg(not(any(test, feature = "bench-internals")))]
+    pub(crate) fn new(buffer: &'a [u8]) -> Bytes<'a> {
+        Bytes { buffer }
+    }
+
+    /// The bytes of the buffer, which count the length of the longest prefix that was read.
+    #[cfg(any(test, feature = "bench-internals"))]
+    pub(crate) fn new(buffer: &'a [u8], read: &'a Cell<usize>) -> Bytes<'a> {
+        Bytes { buffer, read }
+    }
+
+    /// The length of the buffer, which is not a read.
+    pub(crate) fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// The first `limit` bytes of the buffer (all of them for a shorter one).
+    pub(crate) fn prefix(&self, limit: usize) -> &'a [u8] {
+        let prefix = &self.buffer[..min(self.buffer.len(), limit)];
+        #[cfg(any(test, feature = "bench-internals"))]
+        self.read.set(self.read.get().max(prefix.len()));
+        prefix
+    }
+
+    /// The whole buffer.
+    pub(crate) fn all(&self) -> &'a [u8] {
+        self.prefix(self.buffer.len())
+    }
+
+    /// The first `limit` bytes as a buffer of their own, without reading them.
+    pub(crate) fn truncate(mut self, limit: usize) -> Bytes<'a> {
+        self.buffer = &self.buffer[..min(self.buffer.len(), limit)];
+        self
+    }
+}
+
+/// A built-in stage of the detection pipeline. Unlike a custom `Heuristic`, it does not get the
+/// buffer as a slice, so that all of its reads are counted.
+pub(crate) trait Stage: fmt::Debug + Send + Sync {
+    fn examine(&self, buffer: Bytes<'_>, partial: &mut Partial) -> Option<ContentType>;
+
+    fn reason(&self) -> Reason;
+}
+
+/// A stage of the pipeline of an `Inspector`, in the order of execution.
+#[derive(Clone, Debug)]
+pub(crate) enum Step {
+    Builtin(Arc<dyn Stage>),
+    Custom(Arc<dyn Heuristic>),
+}
+
+impl Step {
+    pub(crate) fn examine(&self, buffer: Bytes<'_>, partial: &mut Partial) -> Option<ContentType> {
+        match self {
+            Step::Builtin(stage) => stage.examine(buffer, partial),
+            // A custom heuristic gets the whole buffer, so it counts as reading all of it
+            Step::Custom(heuristic) => heuristic.examine(buffer.all(), partial),
+        }
+    }
+
+    pub(crate) fn reason(&self) -> Reason {
+        match self {
+            Step::Builtin(stage) => stage.reason(),
+            Step::Custom(heuristic) => heuristic.reason(),
+        }
+    }
+}
+
+/// The class of a single byte, as seen by the NULL-byte, control character and printable ratio
+/// heuristics.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ByteClass {
+    /// A byte that counts towards the NULL-byte limit.
+    Null,
+
+    /// A byte that counts towards the control character ratio.
+    Control,
+
+    /// A byte that can appear in text, which counts as printable.
+    Text,
+}
+
+impl ByteClass {
+    /// The built-in classification: `0x00` is `Null`, all other bytes below `0x20` except for
+    /// tab, line feed, carriage return, form feed and escape are `Control`.
+    pub fn of(byte: u8) -> ByteClass {
+        classify(byte, ALLOWED_CONTROL_CHARACTERS)
+    }
+}
+
+fn classify(byte: u8, allowed_control_characters: u32) -> ByteClass {
+    match byte {
+        0x00 => ByteClass::Null,
+        0x01..=0x1F if allowed_control_characters & (1 << byte) == 0 => ByteClass::Control,
+        _ => ByteClass::Text,
+    }
+}
+
+/// A lookup table with the class of each byte.
+pub(crate) type ByteClassTable = [ByteClass; 256];
+
+pub(crate) fn byte_class_table<F: Fn(u8) -> ByteClass>(classify: F) -> ByteClassTable {
+    let mut table = [ByteClass::Text; 256];
+    for (byte, class) in (0..=255).zip(table.iter_mut()) {
+        *class = classify(byte);
+    }
+    table
+}
+
+pub(crate) fn control_character_table(allowed_control_characters: u32) -> ByteClassTable {
+    byte_class_table(|byte| classify(byte, allowed_control_characters))
+}
+
+/// Whether all bytes from `0x20` are `Text`, as in the built-in classification. Words without a
+/// smaller byte can then be counted without looking up each byte.
+pub(crate) fn is_text_above_control(table: &ByteClassTable) -> bool {
+    table[0x20..].iter().all(|&class| class == ByteClass::Text)
+}
+
+/// The findings of all byte-level heuristics (NULL bytes, control characters, printable bytes
+/// and ASCII), which are collected in a single pass over the window.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct ByteScan {
+    pub(crate) null_bytes: usize,
+    pub(crate) first_null_offset: Option<usize>,
+    pub(crate) control_characters: usize,
+    /// Not counting a UTF-8 byte order mark
+    pub(crate) printable_bytes: usize,
+    /// The high bits of all bytes, which are only zero for ASCII.
+    high_bits: u64,
+}
+
+impl ByteScan {
+    const LOW: u64 = 0x0101_0101_0101_0101;
+    const HIGH: u64 = 0x8080_8080_8080_8080;
+
+    pub(crate) fn new(window: &[u8], table: &ByteClassTable, text_above_control: bool) -> Self {
+        #[cfg(feature = "rayon")]
+        let mut scan = if parallel::is_parallel(window) {
+            parallel::byte_scan(window, table, text_above_control)
+        } else {
+            ByteScan::count(window, table, text_above_control)
+        };
+        #[cfg(not(feature = "rayon"))]
+        let mut scan = ByteScan::count(window, table, text_above_control);
+
+        let bom = &window[..utf8_bom_length(window)];
+        scan.printable_bytes -= bom
+            .iter()
+            .filter(|&&byte| table[byte as usize] == ByteClass::Text)
+            .count();
+        scan
+    }
+
+    /// Count the bytes of the window, including those of a UTF-8 byte order mark.
+    pub(crate) fn count(window: &[u8], table: &ByteClassTable, text_above_control: bool) -> Self {
+        let mut scan = ByteScan::default();
+        let vectorized = if text_above_control {
+            simd::for_each_block(window, |offset, masks| {
+                scan.update_block(window, offset, masks, table);
+                true
+            })
+        } else {
+            0
+        };
+
+        let rest = &window[vectorized..];
+        let mut words = rest.chunks_exact(8);
+        for (i, word) in words.by_ref().enumerate() {
+            scan.update(vectorized + 8 * i, word, table, text_above_control);
+        }
+        let offset = window.len() - words.remainder().len();
+        scan.update_bytes(offset, words.remainder(), table);
+        scan
+    }
+
+    /// Combine the counts with those of a part of the window that starts at `offset`.
+    #[cfg(feature = "rayon")]
+    pub(crate) fn merge(mut self, offset: usize, other: ByteScan) -> ByteScan {
+        let other_first = other.first_null_offset.map(|first| offset + first);
+        self.first_null_offset = match (self.first_null_offset, other_first) {
+            (Some(first), Some(other)) => Some(first.min(other)),
+            (first, other) => first.or(other),
+        };
+        self.null_bytes += other.null_bytes;
+        self.control_characters += other.control_characters;
+        self.printable_bytes += other.printable_bytes;
+        self.high_bits |= other.high_bits;
+        self
+    }
+
+    pub(crate) fn is_ascii(&self) -> bool {
+        self.high_bits == 0
+    }
+
+    /// Count the bytes of a vectorized block, where only the bytes below 0x20 are looked up.
+    fn update_block(
+        &mut self,
+        window: &[u8],
+        offset: usize,
+        masks: BlockMasks,
+        table: &ByteClassTable,
+    ) {
+        self.high_bits |= u64::from(masks.high);
+        let mut below_0x20 = masks.below_0x20;
+        self.printable_bytes += simd::BLOCK - below_0x20.count_ones() as usize;
+        while below_0x20 != 0 {
+            let i = offset + below_0x20.trailing_zeros() as usize;
+            self.update_bytes(i, &window[i..=i], table);
+            below_0x20 &= below_0x20 - 1;
+        }
+    }
+
+    /// Count the bytes of an eight byte word that starts at the given offset in the window.
+    fn update(&mut self, offset: usize, word: &[u8], table: &ByteClassTable, text_above: bool) {
+        let value = u64::from_le_bytes([
+            word[0], word[1], word[2], word[3], word[4], word[5], word[6], word[7],
+        ]);
+        // Non-zero if one of the bytes is below 0x20
+        let below_0x20 = value.wrapping_sub(0x20 * Self::LOW) & !value & Self::HIGH;
+        if text_above && below_0x20 == 0 {
+            self.printable_bytes += 8;
+            self.high_bits |= value & Self::HIGH;
+        } else {
+            self.update_bytes(offset, word, table);
+        }
+    }
+
+    fn update_bytes(&mut self, offset: usize, bytes: &[u8], table: &ByteClassTable) {
+        for (i, &byte) in bytes.iter().enumerate() {
+            self.high_bits |= u64::from(byte & 0x80);
+            match table[byte as usize] {
+                ByteClass::Null => {
+                    self.first_null_offset = self.first_null_offset.or(Some(offset + i));
+                    self.null_bytes += 1;
+                }
+                ByteClass::Control => self.control_characters += 1,
+                ByteClass::Text => self.printable_bytes += 1,
+            }
+        }
+    }
+}
+
+/// A set of byte values, as a bitmap.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct ByteSet([u64; 4]);
+
+impl ByteSet {
+    const fn with(mut self, byte: u8) -> ByteSet {
+        self.0[byte as usize / 64] |= 1 << (byte % 64);
+        self
+    }
+
+    pub(crate) fn insert(&mut self, byte: u8) {
+        *self = self.with(byte);
+    }
+
+    pub(crate) fn contains(&self, byte: u8) -> bool {
+        self.0[byte as usize / 64] & 1 << (byte % 64) != 0
+    }
+}
+
+/// The maximum number of byte order marks in `BYTE_ORDER_MARKS`.
+const MAX_BOMS: usize = 16;
+
+/// The byte order marks by their first byte, so that a buffer is only compared with those that
+/// start like it (and most buffers with none at all).
+struct BomTable {
+    /// Indices into `BYTE_ORDER_MARKS`, sorted by the first byte and then by the index, which
+    /// keeps the UTF-32 byte order marks before the UTF-16 ones that they start with
+    indices: [u8; MAX_BOMS],
+    /// The range of entries in `indices` for each first byte
+    ranges: [(u8, u8); 256],
+}
+
+const BOM_TABLE: BomTable = {
+    assert!(BYTE_ORDER_MARKS.len() <= MAX_BOMS);
+    let mut table = BomTable {
+        indices: [0; MAX_BOMS],
+        ranges: [(0, 0); 256],
+    };
+    let mut length = 0;
+    let mut byte = 0;
+    while byte < 256 {
+        table.ranges[byte].0 = length as u8;
+        let mut i = 0;
+        while i < BYTE_ORDER_MARKS.len() {
+            if BYTE_ORDER_MARKS[i].0[0] as usize == byte {
+                table.indices[length] = i as u8;
+                length += 1;
+            }
+            i += 1;
+        }
+        table.ranges[byte].1 = length as u8;
+        byte += 1;
+    }
+    table
+};
+
+/// The byte order marks that start with the given byte, in the order of `BYTE_ORDER_MARKS`.
+fn bom_candidates(first_byte: u8) -> impl Iterator<Item = &'static (&'static [u8], ContentType)> {
+    let (start, end) = BOM_TABLE.ranges[first_byte as usize];
+    BOM_TABLE.indices[start as usize..end as usize]
+        .iter()
+        .map(|&index| &BYTE_ORDER_MARKS[index as usize])
+}
+
+/// The bytes that `find_bom` needs: the first one, and as many as the longest byte order mark if
+/// any starts with it.
+fn bom_prefix<'a>(buffer: Bytes<'a>) -> &'a [u8] {
+    match buffer.prefix(1).first() {
+        Some(&first_byte) => {
+            let (start, end) = BOM_TABLE.ranges[first_byte as usize];
+            if start == end {
+                buffer.prefix(1)
+            } else {
+                buffer.prefix(MAX_BOM_LENGTH)
+            }
+        }
+        None => &[],
+    }
+}
+
+/// The byte order mark at the start of the buffer, if there is one.
+fn find_bom(buffer: &[u8]) -> Option<&'static (&'static [u8], ContentType)> {
+    let &first_byte = buffer.first()?;
+    bom_candidates(first_byte).find(|&&(bom, _)| buffer.starts_with(bom))
+}
+
+/// Buffers up to this size are scanned for NULL bytes with a simple loop, which is faster than
+/// setting up `memchr` for a few bytes.
+const SMALL_BUFFER_SIZE: usize = 16;
+
+/// The part of the buffer that is validated by the encoding validation stages.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum Validation {
+    /// Validate the first bytes of the buffer, up to the validation limit. Incomplete sequences at
+    /// the end of this window are accepted, since they might continue beyond it.
+    Window,
+
+    /// Validate the whole buffer, which is assumed to be complete. In addition, the length after a
+    /// byte order mark has to be a multiple of the code unit size, and U+0000 is not allowed.
+    Buffer,
+}
+
+/// The offset of the first invalid UTF-8 sequence. Incomplete sequences at the end are only
+/// reported if `allow_incomplete` is false.
+fn invalid_utf8_offset(bytes: &[u8], allow_incomplete: bool) -> Option<usize> {
+    #[cfg(feature = "rayon")]
+    if parallel::is_parallel(bytes) {
+        return parallel::invalid_utf8_offset(bytes, allow_incomplete);
+    }
+    invalid_utf8_offset_sequential(bytes, allow_incomplete)
+}
+
+pub(crate) fn invalid_utf8_offset_sequential(
+    bytes: &[u8],
+    allow_incomplete: bool,
+) -> Option<usize> {
+    let ascii = simd::ascii_prefix_length(bytes);
+    match str::from_utf8(&bytes[ascii..]) {
+        Err(error) if error.error_len().is_some() || !allow_incomplete => {
+            Some(ascii + error.valid_up_to())
+        }
+        _ => None,
+    }
+}
+
+#[cfg(feature = "utf16")]
+fn valid_utf16(content: &[u8], big_endian: bool, validation: Validation) -> bool {
+    if validation == Validation::Buffer && content.len() % 2 != 0 {
+        return false;
+    }
+
+    let mut units = utf16_units(content, big_endian);
+    while let Some(unit) = units.next() {
+        match unit {
+            0xD800..=0xDBFF => match units.next() {
+                Some(0xDC00..=0xDFFF) => {}
+                Some(_) => return false,
+                None => return validation == Validation::Window,
+            },
+            0xDC00..=0xDFFF => return false,
+            0x0000 if validation == Validation::Buffer => return false,
+            _ => {}
+        }
+    }
+    true
+}
+
+#[cfg(feature = "utf32")]
+fn valid_utf32(content: &[u8], big_endian: bool, validation: Validation) -> bool {
+    if validation == Validation::Buffer && content.len() % 4 != 0 {
+        return false;
+    }
+
+    utf32_units(content, big_endian).all(|code_point| {
+        char::from_u32(code_point).is_some()
+            && !(validation == Validation::Buffer && code_point == 0)
+    })
+}
+
+/// Classifies content with a byte order mark as binary if the rest of it is not valid in the
+/// encoding that is indicated by the byte order mark.
+#[derive(Debug)]
+pub(crate) struct BomValidation {
+    pub(crate) validation: Validation,
+    pub(crate) limit: usize,
+}
+
+impl Stage for BomValidation {
+    fn examine(&self, buffer: Bytes<'_>, partial: &mut Partial) -> Option<ContentType> {
+        use ContentType::*;
+
+        let start = bom_prefix(buffer);
+        partial.examined(start.len());
+        let &(bom, content_type) = find_bom(start)?;
+        let content = match self.validation {
+            Validation::Window => buffer.prefix(self.limit),
+            Validation::Buffer => buffer.all(),
+        };
+        partial.report.validated_bytes = content.len();
+        partial.examined(content.len());
+        let content = content.get(bom.len()..).unwrap_or(&[]);
+
+        let valid = match content_type {
+            UTF_8_BOM => {
+                let allow_incomplete = self.validation == Validation::Window;
+                let offset = invalid_utf8_offset(content, allow_incomplete);
+                partial.report.invalid_utf8_offset = offset.map(|offset| bom.len() + offset);
+                offset.is_none()
+                    && !(self.validation == Validation::Buffer && memchr(0x00, content).is_some())
+            }
+            #[cfg(feature = "utf16")]
+            UTF_16LE => valid_utf16(content, false, self.validation),
+            #[cfg(feature = "utf16")]
+            UTF_16BE => valid_utf16(content, true, self.validation),
+            #[cfg(feature = "utf32")]
+            UTF_32LE => valid_utf32(content, false, self.validation),
+            #[cfg(feature = "utf32")]
+            UTF_32BE => valid_utf32(content, true, self.validation),
+            _ => true,
+        };
+
+        if valid {
+            None
+        } else {
+            debug_event!(
+                buffer_length = buffer.len(),
+                content_type = %content_type,
+                invalid_utf8_offset = ?partial.report.invalid_utf8_offset,
+                "byte order mark rejected, the content is not valid in its encoding"
+            );
+            Some(BINARY)
+        }
+    }
+
+    fn reason(&self) -> Reason {
+        Reason::InvalidEncoding
+    }
+}
+
+/// Content after a two-byte UTF-16 byte order mark of up to this many code units is not checked
+/// by `plausible_utf16`, so that a byte order mark followed by a few characters stays UTF-16.
+#[cfg(feature = "utf16")]
+pub(crate) const UTF16_BOM_UNCHECKED_UNITS: usize = 8;
+
+/// The number of code units after a two-byte UTF-16 byte order mark that are checked by
+/// `plausible_utf16` (at most).
+pub(crate) const UTF16_BOM_CHECKED_UNITS: usize = 128;
+
+/// Whether the content after a two-byte UTF-16 byte order mark (`FF FE` or `FE FF`) looks like
+/// text: at most one in 32 code units may be an unpaired surrogate, a control character other
+/// than those of `ALLOWED_CONTROL_CHARACTERS` (including U+0000 and DEL), a private use
+/// character or a noncharacter. Two bytes are weak evidence on their own, since many binary
+/// formats start with them by accident. Random bytes fail this check, as about 12% of their code
+/// units are surrogates or private use characters.
+///
+/// Only the content up to the given number of bytes (from the start of the buffer) is checked,
+/// and content of up to `UTF16_BOM_UNCHECKED_UNITS` code units is always plausible. This is a
+/// `const fn` for `inspect_const`.
+#[cfg(feature = "utf16")]
+pub(crate) const fn plausible_utf16(buffer: &[u8], big_endian: bool, limit: usize) -> bool {
+    let end = if buffer.len() < limit {
+        buffer.len()
+    } else {
+        limit
+    };
+    let units = end.saturating_sub(2) / 2;
+    if units <= UTF16_BOM_UNCHECKED_UNITS {
+        return true;
+    }
+
+    let mut implausible = 0;
+    let mut i = 0;
+    while i < units {
+        let current = unit_after_bom(buffer, i, big_endian);
+        let plausible = match current {
+            // A high surrogate at the end of the window might be followed by a low one
+            0xD800..=0xDBFF if i + 1 == units => true,
+            0xD800..=0xDBFF
+                if matches!(unit_after_bom(buffer, i + 1, big_endian), 0xDC00..=0xDFFF) =>
+            {
+                i += 1;
+                true
+            }
+            0xD800..=0xDFFF => false,
+            _ => !implausible_utf16_unit(current),
+        };
+        if !plausible {
+            implausible += 1;
+        }
+        i += 1;
+    }
+    implausible * 32 <= units
+}
+
+/// Whether the UTF-16 code unit (other than a surrogate) is unlikely in text: a control character
+/// other than those of `ALLOWED_CONTROL_CHARACTERS` (including U+0000 and DEL), a C1 control
+/// character, a private use character or a noncharacter.
+#[cfg(feature = "utf16")]
+pub(crate) const fn implausible_utf16_unit(unit: u16) -> bool {
+    match unit {
+        0x0000..=0x001F => ALLOWED_CONTROL_CHARACTERS & (1 << unit) == 0,
+        0x007F..=0x009F | 0xE000..=0xF8FF | 0xFDD0..=0xFDEF | 0xFFFE..=0xFFFF => true,
+        _ => false,
+    }
+}
+
+/// The code unit with the given index after a two-byte byte order mark.
+#[cfg(feature = "utf16")]
+const fn unit_after_bom(buffer: &[u8], index: usize, big_endian: bool) -> u16 {
+    u16_unit([buffer[2 + 2 * index], buffer[3 + 2 * index]], big_endian)
+}
+
+#[derive(Debug)]
+pub(crate) struct ByteOrderMark {
+    pub(crate) normalize: bool,
+    /// The number of bytes that are checked by `plausible_utf16`, if UTF-16 byte order marks are
+    /// corroborated.
+    #[cfg(feature = "utf16")]
+    pub(crate) corroboration_limit: Option<usize>,
+}
+
+impl Stage for ByteOrderMark {
+    fn examine(&self, buffer: Bytes<'_>, partial: &mut Partial) -> Option<ContentType> {
+        let start = bom_prefix(buffer);
+        partial.examined(start.len());
+        let &(bom, content_type) = find_bom(start)?;
+        #[cfg(feature = "utf16")]
+        let utf16 = match content_type {
+            ContentType::UTF_16LE => Some(false),
+            ContentType::UTF_16BE => Some(true),
+            _ => None,
+        };
+        #[cfg(feature = "utf16")]
+        if let (Some(big_endian), Some(limit)) = (utf16, self.corroboration_limit) {
+            let content = buffer.prefix(limit);
+            partial.examined(content.len());
+            if !plausible_utf16(content, big_endian, limit) {
+                debug_event!(
+                    buffer_length = buffer.len(),
+                    content_type = %content_type,
+                    "byte order mark ignored, the content does not look like UTF-16"
+                );
+                return None;
+            }
+        }
+        partial.report.bom_length = bom.len();
+        debug_event!(
+            buffer_length = buffer.len(),
+            content_type = %content_type,
+            bom_length = bom.len(),
+            "byte order mark matched"
+        );
+        Some(
+            if self.normalize && content_type == ContentType::UTF_8_BOM {
+                ContentType::UTF_8
+            } else {
+                content_type
+            },
+        )
+    }
+
+    fn reason(&self) -> Reason {
+        Reason::ByteOrderMark
+    }
+}
+
+/// Detects UTF-16 without a byte order mark by the position of the zero bytes, which are the
+/// high bytes of ASCII and Latin-1 characters. All zero bytes have to be on the same side of the
+/// code units, and at least half of the code units have to contain one.
+#[cfg(feature = "utf16")]
+#[derive(Debug)]
+pub(crate) struct Utf16Pattern {
+    pub(crate) limit: usize,
+}
+
+#[cfg(feature = "utf16")]
+impl Stage for Utf16Pattern {
+    fn examine(&self, buffer: Bytes<'_>, partial: &mut Partial) -> Option<ContentType> {
+        let window = buffer.prefix(self.limit);
+        partial.report.sniffed_bytes = window.len();
+        partial.examined(window.len());
+        let units = window.chunks_exact(2);
+        let num_units = units.len();
+        if num_units < 2 {
+            return None;
+        }
+
+        let (mut low_zero, mut high_zero) = (0, 0);
+        for unit in units {
+            match (unit[0], unit[1]) {
+                (0, 0) => return None,
+                (0, _) => high_zero += 1,
+                (_, 0) => low_zero += 1,
+                _ => {}
+            }
+        }
+
+        if high_zero == 0 && 2 * low_zero >= num_units {
+            Some(ContentType::UTF_16LE)
+        } else if low_zero == 0 && 2 * high_zero >= num_units {
+            Some(ContentType::UTF_16BE)
+        } else {
+            None
+        }
+    }
+
+    fn reason(&self) -> Reason {
+        Reason::Utf16Pattern
+    }
+}
+
+/// The built-in magic numbers and custom ones, which are looked up by their first byte.
+#[derive(Debug)]
+pub(crate) struct MagicNumber {
+    /// Custom magic numbers at offset zero (index, signature), sorted by their first byte
+    leading: Vec<(usize, Vec<u8>)>,
+    /// The range of entries in `leading` for each first byte
+    ranges: [(u32, u32); 256],
+    /// Custom magic numbers at other offsets (index, offset, signature)
+    other: Vec<(usize, usize, Vec<u8>)>,
+    /// The first bytes of all magic numbers at offset zero, built-in and custom
+    first_bytes: ByteSet,
+    /// The minimum length of a buffer that matches a magic number which is not at offset zero
+    min_unanchored_length: usize,
+    /// The number of bytes that are needed to check all magic numbers, built-in and custom
+    extent: usize,
+}
+
+impl MagicNumber {
+    pub(crate) fn new(custom: &[(usize, Vec<u8>)]) -> MagicNumber {
+        let mut leading = vec![];
+        let mut other = vec![];
+        for (index, &(offset, ref signature)) in custom.iter().enumerate() {
+            if offset == 0 {
+                leading.push((index, signature.clone()));
+            } else {
+                other.push((index, offset, signature.clone()));
+            }
+        }
+        leading.sort_by_key(|&(index, ref signature)| (signature[0], index));
+
+        let mut ranges = [(0, 0); 256];
+        let mut first_bytes = ByteSet::default();
+        for (i, (_, signature)) in leading.iter().enumerate() {
+            let range = &mut ranges[signature[0] as usize];
+            if range.0 == range.1 {
+                range.0 = i as u32;
+            }
+            range.1 = i as u32 + 1;
+            first_bytes.insert(signature[0]);
+        }
+
+        let extent = MAGIC_NUMBERS
+            .iter()
+            .flat_map(|magic| magic.signature)
+            .map(|&(offset, bytes)| offset + bytes.len())
+            .chain(
+                custom
+                    .iter()
+                    .map(|&(offset, ref signature)| offset.saturating_add(signature.len())),
+            )
+            .max()
+            .unwrap_or(0);
+        let mut min_unanchored_length = other
+            .iter()
+            .map(|&(_, offset, ref signature)| offset.saturating_add(signature.len()))
+            .min()
+            .unwrap_or(usize::MAX);
+        for magic in MAGIC_NUMBERS {
+            match magic.signature.iter().find(|&&(offset, _)| offset == 0) {
+                Some(&(_, bytes)) => first_bytes.insert(bytes[0]),
+                None => {
+                    let length = magic
+                        .signature
+                        .iter()
+                        .map(|&(offset, bytes)| offset + bytes.len());
+                    min_unanchored_length = min_unanchored_length.min(length.max().unwrap_or(0));
+                }
+            }
+        }
+
+        MagicNumber {
+            leading,
+            ranges,
+            other,
+            first_bytes,
+            min_unanchored_length,
+            extent,
+        }
+    }
+
+    /// Whether any magic number can match the buffer, which rules out most short buffers by
+    /// their first byte alone.
+    fn could_match(&self, buffer: Bytes<'_>) -> bool {
+        buffer.len() >= self.min_unanchored_length
+            || buffer
+                .prefix(1)
+                .first()
+                .is_some_and(|&byte| self.first_bytes.contains(byte))
+    }
+
+    /// Returns the index of the first matching custom magic number.
+    fn custom_format(&self, buffer: &[u8]) -> Option<usize> {
+        let first_byte = *buffer.first()?;
+        let (start, end) = self.ranges[first_byte as usize];
+        let leading = self.leading[start as usize..end as usize]
+            .iter()
+            .filter(|(_, signature)| buffer.starts_with(signature))
+            .map(|&(index, _)| index);
+        let other = self
+            .other
+            .iter()
+            .filter(|&&(_, offset, ref signature)| {
+                buffer
+                    .get(offset..)
+                    .is_some_and(|rest| rest.starts_with(signature))
+            })
+            .map(|&(index, _, _)| index);
+
+        leading.chain(other).min()
+    }
+}
+
+impl Stage for MagicNumber {
+    fn examine(&self, buffer: Bytes<'_>, partial: &mut Partial) -> Option<ContentType> {
+        if !self.could_match(buffer) {
+            partial.examined(min(buffer.len(), 1));
+            return None;
+        }
+        let start = buffer.prefix(self.extent);
+        partial.examined(start.len());
+        partial.report.binary_format =
+            binary_format(start).or_else(|| self.custom_format(start).map(BinaryFormat::Custom));
+        partial.report.binary_format?;
+        debug_event!(
+            buffer_length = buffer.len(),
+            binary_format = ?partial.report.binary_format,
+            "magic number matched"
+        );
+        Some(ContentType::BINARY)
+    }
+
+    fn reason(&self) -> Reason {
+        Reason::MagicNumber
+    }
+}
+
+/// The length of a UTF-8 byte order mark at the start of the window, which is not counted for the
+/// printable ratio.
+fn utf8_bom_length(window: &[u8]) -> usize {
+    if window.starts_with(b"\xEF\xBB\xBF") {
+        3
+    } else {
+        0
+    }
+}
+
+/// Counts the NULL bytes in the scanned window. If control characters or printable bytes are
+/// counted as well, this is done in the same pass (see `ByteScan`), otherwise `memchr` is used.
+#[derive(Debug)]
+pub(crate) struct NullBytes {
+    pub(crate) max_null_bytes: usize,
+    pub(crate) limit: usize,
+    /// The classification of all bytes, if control characters or printable bytes are counted in
+    /// the same pass.
+    pub(crate) table: Option<ByteClassTable>,
+    /// See `is_text_above_control`
+    pub(crate) text_above_control: bool,
+}
+
+impl Stage for NullBytes {
+    fn examine(&self, buffer: Bytes<'_>, partial: &mut Partial) -> Option<ContentType> {
+        let window = buffer.prefix(self.limit);
+        partial.examined(window.len());
+        let report = &mut partial.report;
+        report.scanned_bytes = window.len();
+
+        if let Some(ref table) = self.table {
+            let scan = ByteScan::new(window, table, self.text_above_control);
+            report.null_bytes = scan.null_bytes;
+            report.first_null_offset = scan.first_null_offset;
+            report.control_characters = scan.control_characters;
+            report.printable_bytes = scan.printable_bytes;
+            if window.len() == partial.window.len() {
+                partial.ascii = Some(scan.is_ascii());
+            }
+        } else if window.len() <= SMALL_BUFFER_SIZE {
+            for (offset, _) in window.iter().enumerate().filter(|&(_, &byte)| byte == 0x00) {
+                report.first_null_offset = report.first_null_offset.or(Some(offset));
+                report.null_bytes += 1;
+            }
+        } else {
+            (report.null_bytes, report.first_null_offset) = count_null_bytes(window);
+        }
+        debug_event!(
+            buffer_length = buffer.len(),
+            scanned_bytes = window.len(),
+            null_bytes = report.null_bytes,
+            first_null_offset = ?report.first_null_offset,
+            "NULL bytes counted"
+        );
+
+        if report.null_bytes > self.max_null_bytes {
+            Some(ContentType::BINARY)
+        } else {
+            None
+        }
+    }
+
+    fn reason(&self) -> Reason {
+        Reason::NullBytes
+    }
+}
+
+/// The number of NULL bytes in the window, and the offset of the first one.
+fn count_null_bytes(window: &[u8]) -> (usize, Option<usize>) {
+    #[cfg(feature = "rayon")]
+    if parallel::is_parallel(window) {
+        return parallel::count_null_bytes(window);
+    }
+    count_null_bytes_sequential(window)
+}
+
+pub(crate) fn count_null_bytes_sequential(window: &[u8]) -> (usize, Option<usize>) {
+    let mut null_bytes = memchr_iter(0x00, window);
+    let first_null_offset = null_bytes.next();
+    (
+        first_null_offset.map_or(0, |_| 1 + null_bytes.count()),
+        first_null_offset,
+    )
+}
+
+/// Classifies content as binary if the fraction of control characters (counted by the NULL-byte
+/// scan) in the window is above the maximum.
+#[derive(Debug)]
+pub(crate) struct ControlCharacters {
+    pub(crate) max_ratio: f32,
+    /// The limit of the NULL-byte scan
+    pub(crate) limit: usize,
+}
+
+impl Stage for ControlCharacters {
+    fn examine(&self, buffer: Bytes<'_>, partial: &mut Partial) -> Option<ContentType> {
+        let window = min(buffer.len(), self.limit);
+        if partial.report.control_characters as f32 > self.max_ratio * window as f32 {
+            Some(ContentType::BINARY)
+        } else {
+            None
+        }
+    }
+
+    fn reason(&self) -> Reason {
+        Reason::ControlCharacters
+    }
+}
+
+/// Classifies content as binary if the fraction of printable bytes (counted by the NULL-byte scan)
+/// in the window is below the minimum. A UTF-8 byte order mark is not counted.
+#[derive(Debug)]
+pub(crate) struct PrintableRatio {
+    pub(crate) min_ratio: f32,
+    /// The limit of the NULL-byte scan
+    pub(crate) limit: usize,
+}
+
+impl Stage for PrintableRatio {
+    fn examine(&self, buffer: Bytes<'_>, partial: &mut Partial) -> Option<ContentType> {
+        let window = min(buffer.len(), self.limit);
+        let examined = window - utf8_bom_length(buffer.prefix(min(window, 3)));
+        if (partial.report.printable_bytes as f32) < self.min_ratio * examined as f32 {
+            Some(ContentType::BINARY)
+        } else {
+            None
+        }
+    }
+
+    fn reason(&self) -> Reason {
+        Reason::PrintableRatio
+    }
+}
+
+/// Reports content with invalid UTF-8 sequences as Latin-1. An incomplete sequence at the end of
+/// the window is not considered to be invalid, since it might continue beyond the window.
+#[cfg(feature = "legacy-encodings")]
+#[derive(Debug)]
+pub(crate) struct Latin1Fallback {
+    /// The validation limit
+    pub(crate) limit: usize,
+}
+
+#[cfg(feature = "legacy-encodings")]
+impl Stage for Latin1Fallback {
+    fn examine(&self, buffer: Bytes<'_>, partial: &mut Partial) -> Option<ContentType> {
+        let window = buffer.prefix(self.limit);
+        partial.report.validated_bytes = window.len();
+        partial.examined(window.len());
+        partial.report.invalid_utf8_offset = invalid_utf8_offset(window, true);
+        partial.report.invalid_utf8_offset?;
+        debug_event!(
+            buffer_length = buffer.len(),
+            invalid_utf8_offset = ?partial.report.invalid_utf8_offset,
+            "invalid UTF-8, falling back to Latin-1"
+        );
+        Some(ContentType::LATIN_1)
+    }
+
+    fn reason(&self) -> Reason {
+        Reason::InvalidUtf8
+    }
+}
+
+/// Classifies content with invalid UTF-8 sequences as binary.
+#[derive(Debug)]
+pub(crate) struct Utf8Validation {
+    pub(crate) validation: Validation,
+    pub(crate) limit: usize,
+}
+
+impl Stage for Utf8Validation {
+    fn examine(&self, buffer: Bytes<'_>, partial: &mut Partial) -> Option<ContentType> {
+        let (content, allow_incomplete) = match self.validation {
+            Validation::Window => (buffer.prefix(self.limit), true),
+            Validation::Buffer => (buffer.all(), false),
+        };
+        partial.report.validated_bytes = content.len();
+        partial.examined(content.len());
+        partial.report.invalid_utf8_offset = invalid_utf8_offset(content, allow_incomplete);
+        partial
+            .report
+            .invalid_utf8_offset
+            .map(|_| ContentType::BINARY)
+    }
+
+    fn reason(&self) -> Reason {
+        Reason::InvalidUtf8
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(
+    not(all(feature = "utf16", feature = "utf32", feature = "legacy-encodings")),
+    allow(dead_code, unused_imports)
+)]
+mod tests {
+    use std::cell::Cell;
+    use std::fs;
+    use std::path::Path;
+
+    use crate::search::memchr_iter;
+
+    use super::{
+        bom_candidates, byte_class_table, control_character_table, find_bom, is_text_above_control,
+        utf8_bom_length, ByteClassTable, ByteScan, Bytes, MagicNumber, NullBytes, Partial, Stage,
+        ALLOWED_CONTROL_CHARACTERS, SMALL_BUFFER_SIZE,
+    };
+    use crate::{
+        binary_format, inspect_detailed, BinaryFormat, ByteClass, ContentType, BYTE_ORDER_MARKS,
+        MAGIC_NUMBERS,
+    };
+
+    /// The separate passes of the byte-level heuristics before they were combined.
+    fn separate_passes(window: &[u8], table: &ByteClassTable) -> (ByteScan, bool) {
+        let mut null_bytes = memchr_iter(0x00, window);
+        let first_null_offset = null_bytes.next();
+        let count = |class| {
+            window
+                .iter()
+                .filter(|&&byte| table[byte as usize] == class)
+                .count()
+        };
+        let bom = &window[..utf8_bom_length(window)];
+        let bom_text = bom
+            .iter()
+            .filter(|&&byte| table[byte as usize] == ByteClass::Text)
+            .count();
+        let scan = ByteScan {
+            null_bytes: count(ByteClass::Null),
+            first_null_offset: window
+                .iter()
+                .position(|&byte| table[byte as usize] == ByteClass::Null),
+            control_characters: count(ByteClass::Control),
+            printable_bytes: count(ByteClass::Text) - bom_text,
+            high_bits: 0,
+        };
+        if table[0] == ByteClass::Null && table[1..].iter().all(|&c| c != ByteClass::Null) {
+            assert_eq!(first_null_offset, scan.first_null_offset);
+            assert_eq!(
+                first_null_offset.map_or(0, |_| 1 + null_bytes.count()),
+                scan.null_bytes
+            );
+        }
+        (scan, window.is_ascii())
+    }
+
+    #[test]
+    fn test_byte_scan() {
+        let tables = [
+            control_character_table(ALLOWED_CONTROL_CHARACTERS),
+            control_character_table(0),
+            control_character_table(!0),
+            byte_class_table(|byte| match byte {
+                0x7F | 0xFF => ByteClass::Null,
+                0x80..=0x9F => ByteClass::Control,
+                _ => ByteClass::of(byte),
+            }),
+            byte_class_table(|byte| match byte % 3 {
+                0 => ByteClass::Null,
+                1 => ByteClass::Control,
+                _ => ByteClass::Text,
+            }),
+        ];
+        assert!(is_text_above_control(&tables[0]));
+        assert!(!is_text_above_control(&tables[3]));
+
+        let testdata = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata");
+        let mut buffers: Vec<Vec<u8>> = fs::read_dir(testdata)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.is_file())
+            .map(|path| fs::read(path).unwrap())
+            .collect();
+        let mut state: u64 = 0x2545_F491_4F6C_DD1D;
+        for length in 0..500 {
+            buffers.push(
+                (0..length)
+                    .map(|_| {
+                        state ^= state << 13;
+                        state ^= state >> 7;
+                        state ^= state << 17;
+                        // Mostly printable ASCII, with some control and non-ASCII bytes
+                        match state % 16 {
+                            0 => (state >> 32) as u8,
+                            1 => (state >> 32) as u8 % 0x20,
+                            _ => 0x20 + (state >> 32) as u8 % 0x5F,
+                        }
+                    })
+                    .collect(),
+            );
+        }
+        buffers.push(b"\xEF\xBB\xBFabc".to_vec());
+        buffers.push(b"\xEF\xBB".to_vec());
+
+        for buffer in &buffers {
+            for table in &tables {
+                let (expected, ascii) = separate_passes(buffer, table);
+                let scan = ByteScan::new(buffer, table, is_text_above_control(table));
+                assert_eq!(
+                    (expected.null_bytes, expected.first_null_offset),
+                    (scan.null_bytes, scan.first_null_offset)
+                );
+                assert_eq!(
+                    (expected.control_characters, expected.printable_bytes),
+                    (scan.control_characters, scan.printable_bytes)
+                );
+                assert_eq!(ascii, scan.is_ascii());
+
+                // Without the fast path for words
+                let bytewise = ByteScan::new(buffer, table, false);
+                assert_eq!(
+                    (
+                        scan.null_bytes,
+                        scan.control_characters,
+                        scan.printable_bytes
+                    ),
+                    (
+                        bytewise.null_bytes,
+                        bytewise.control_characters,
+                        bytewise.printable_bytes
+                    )
+                );
+            }
+        }
+    }
+
+    /// All buffers of up to two bytes, and buffers of up to four bytes from the bytes that
+    /// start byte order marks and magic numbers (and some others), as well as random ones.
+    fn small_buffers() -> Vec<Vec<u8>> {
+        let mut buffers: Vec<Vec<u8>> = vec![vec![]];
+        buffers.extend((0..=255).map(|a| vec![a]));
+        buffers.extend((0..=0xFFFF_u32).map(|ab| vec![(ab >> 8) as u8, ab as u8]));
+
+        let mut alphabet: Vec<u8> = b"\x00\x01\x14\x20a%\x7F\x80\xFE\xFF".to_vec();
+        let signatures = MAGIC_NUMBERS.iter().flat_map(|magic| magic.signature);
+        for &(_, bytes) in signatures.chain(&[(0, &b"MAGIC"[..]), (0, b"regf")]) {
+            alphabet.extend(bytes.iter().take(2));
+        }
+        alphabet.extend(
+            BYTE_ORDER_MARKS
+                .iter()
+                .flat_map(|&(bom, _)| bom.iter().take(2)),
+        );
+        alphabet.sort_unstable();
+        alphabet.dedup();
+        for &a in &alphabet {
+            for &b in &alphabet {
+                for &c in &alphabet {
+                    buffers.push(vec![a, b, c]);
+                    buffers.extend(alphabet.iter().map(|&d| vec![a, b, c, d]));
+                }
+            }
+        }
+
+        let mut state: u64 = 0x2545_F491_4F6C_DD1D;
+        for length in 0..20_000 {
+            buffers.push(
+                (0..length % (4 * SMALL_BUFFER_SIZE))
+                    .map(|_| {
+                        state ^= state << 13;
+                        state ^= state >> 7;
+                        state ^= state << 17;
+                        alphabet[(state >> 32) as usize % alphabet.len()]
+                    })
+                    .collect(),
+            );
+        }
+        buffers
+    }
+
+    #[test]
+    #[cfg(all(feature = "utf16", feature = "utf32"))]
+    fn test_bom_table() {
+        let reference = |buffer: &[u8]| {
+            BYTE_ORDER_MARKS
+                .iter()
+                .find(|&&(bom, _)| buffer.starts_with(bom))
+        };
+
+        // All prefixes of all byte order marks, followed by any byte
+        for &(bom, _) in BYTE_ORDER_MARKS {
+            for length in 0..=bom.len() {
+                for next in 0..=255 {
+                    let mut buffer = bom[..length].to_vec();
+                    assert_eq!(reference(&buffer), find_bom(&buffer), "{:?}", buffer);
+                    buffer.push(next);
+                    assert_eq!(reference(&buffer), find_bom(&buffer), "{:?}", buffer);
+                    buffer.extend_from_slice(&[0x00, 0x00]);
+                    assert_eq!(reference(&buffer), find_bom(&buffer), "{:?}", buffer);
+                }
+            }
+        }
+
+        // Every first byte, followed by random bytes
+        let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+        for first_byte in 0..=255 {
+            for _ in 0..64 {
+                let mut buffer = vec![first_byte];
+                buffer.extend((0..(state % 6) as usize).map(|_| {
+                    state ^= state << 13;
+                    state ^= state >> 7;
+                    state ^= state << 17;
+                    [0x00, 0xBB, 0xBF, 0xFE, 0xFF, (state >> 40) as u8][(state >> 32) as usize % 6]
+                }));
+                assert_eq!(reference(&buffer), find_bom(&buffer), "{:?}", buffer);
+            }
+        }
+
+        // UTF-32LE before UTF-16LE, which share the first two bytes
+        let types: Vec<_> = bom_candidates(0xFF)
+            .map(|&(_, content_type)| content_type)
+            .collect();
+        assert_eq!(vec![ContentType::UTF_32LE, ContentType::UTF_16LE], types);
+        assert_eq!(0, bom_candidates(b'a').count());
+    }
+
+    #[test]
+    fn test_small_buffer_fast_paths() {
+        let custom = [
+            (0, b"MAGIC".to_vec()),
+            (2, b"\x80\x80".to_vec()),
+            (0, b"\x7F".to_vec()),
+        ];
+        let magic_numbers = [MagicNumber::new(&[]), MagicNumber::new(&custom)];
+        let null_bytes = NullBytes {
+            max_null_bytes: 1,
+            limit: 1024,
+            table: None,
+            text_above_control: false,
+        };
+        let blank = inspect_detailed(b"");
+
+        for buffer in small_buffers() {
+            // The table is searched without looking at the first byte before
+            let bom = BYTE_ORDER_MARKS
+                .iter()
+                .find(|&&(bom, _)| buffer.starts_with(bom));
+            assert_eq!(bom, find_bom(&buffer), "{:?}", buffer);
+
+            for magic_number in &magic_numbers {
+                let expected = binary_format(&buffer).or_else(|| {
+                    magic_number
+                        .custom_format(&buffer)
+                        .map(BinaryFormat::Custom)
+                });
+                let read = Cell::new(0);
+                let bytes = Bytes::new(&buffer, &read);
+                let mut partial = Partial::new(bytes, blank);
+                let result = magic_number.examine(bytes, &mut partial);
+                assert_eq!(expected, partial.report().binary_format, "{:?}", buffer);
+                assert_eq!(expected.map(|_| crate::ContentType::BINARY), result);
+                assert!(
+                    read.get() <= partial.report().bytes_examined,
+                    "{:?}",
+                    buffer
+                );
+            }
+
+            let mut null_offsets = memchr_iter(0x00, &buffer);
+            let first_null_offset = null_offsets.next();
+            let count = first_null_offset.map_or(0, |_| 1 + null_offsets.count());
+            let read = Cell::new(0);
+            let bytes = Bytes::new(&buffer, &read);
+            let mut partial = Partial::new(bytes, blank);
+            let result = null_bytes.examine(bytes, &mut partial);
+            let report = partial.report();
+            assert_eq!(read.get(), report.bytes_examined);
+            assert_eq!(
+                (count, first_null_offset),
+                (report.null_bytes, report.first_null_offset)
+            );
+            assert_eq!(count > 1, result.is_some());
+        }
+    }
+}
@@ -0,0 +1,2634 @@
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(any(test, feature = "bench-internals"))]
+use core::cell::Cell;
+use core::cmp::min;
+use core::error::Error;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+
+#[cfg(feature = "legacy-encodings")]
+use crate::heuristic::Latin1Fallback;
+#[cfg(feature = "utf16")]
+use crate::heuristic::Utf16Pattern;
+use crate::heuristic::{
+    byte_class_table, control_character_table, is_text_above_control, BomValidation, ByteClass,
+    ByteOrderMark, Bytes, ControlCharacters, MagicNumber, NullBytes, PrintableRatio, Step,
+    Utf8Validation, Validation, ALLOWED_CONTROL_CHARACTERS, UTF16_BOM_CHECKED_UNITS,
+};
+use crate::{
+    ContentType, Heuristic, InspectionReport, Mismatch, Partial, Position, Reason,
+    BYTE_ORDER_MARKS, DETECTION_VERSION, MAGIC_NUMBERS, MAX_SCAN_SIZE,
+};
+
+/// The default fraction of (non-allowed) control characters in the scanned window above which the
+/// content is considered to be binary, if the control character heuristic is enabled.
+const MAX_CONTROL_CHARACTER_RATIO: f32 = 0.1;
+
+/// Settings that mimic the binary detection of other tools, see
+/// [`InspectorBuilder::preset`](struct.InspectorBuilder.html#method.preset).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum Preset {
+    /// Git considers a file to be binary if its first 8000 bytes contain a NULL byte. Byte order
+    /// marks are not taken into account, so UTF-16 and UTF-32 encoded text is binary.
+    Git,
+
+    /// GNU grep considers a file to be binary if its first buffer (32 KiB) contains a NULL byte.
+    /// Encoding errors, which also make grep treat a file as binary in UTF-8 locales, are not
+    /// taken into account (corresponding to `LC_ALL=C`).
+    Grep,
+
+    /// `file --mime-encoding` recognizes byte order marks and considers a file to be binary if
+    /// it contains any control characters other than BEL, BS, HT, LF, VT, FF, CR and ESC. Text
+    /// that is not valid UTF-8 is reported as Latin-1 (ISO-8859-1) and magic numbers are not
+    /// taken into account. The preset scans the first 1 MiB.
+    File,
+}
+
+/// Levels of strictness that trade speed for certainty, see
+/// [`InspectorBuilder::strictness`](struct.InspectorBuilder.html#method.strictness).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum Strictness {
+    /// Only the NULL-byte scan of the window. Byte order marks and magic numbers are not taken
+    /// into account, so UTF-16 and UTF-32 encoded text is reported as binary.
+    Fast,
+
+    /// The behavior of [`inspect`](fn.inspect.html): byte order marks, magic numbers and the
+    /// NULL-byte scan of the window.
+    Standard,
+
+    /// Like `Standard`, but the window also has to be valid in the detected encoding. Invalid
+    /// UTF-8 sequences, as well as unpaired surrogates or invalid code points after a UTF-16 or
+    /// UTF-32 byte order mark, make the content binary. Incomplete sequences at the end of the
+    /// window are accepted, since they might continue beyond it.
+    Strict,
+
+    /// Like `Strict`, but the whole buffer is scanned for NULL bytes and validated, and it is
+    /// assumed to be complete: incomplete sequences at its end make the content binary. After a
+    /// byte order mark, the length has to be a multiple of the code unit size, and U+0000
+    /// characters are not allowed.
+    Paranoid,
+}
+
+/// An error in the configuration of an [`Inspector`](struct.Inspector.html). New settings can
+/// bring new errors, so a `match` needs a wildcard arm.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ConfigError {
+    /// A byte outside of the range `0x01..=0x1F` was passed as a control character.
+    InvalidControlCharacter(u8),
+
+    /// An empty magic number was given.
+    EmptyMagicNumber,
+
+    /// The fallback type is not one of the allowed types.
+    FallbackNotAllowed(ContentType),
+
+    /// A ratio (of the given setting) is not between 0.0 and 1.0.
+    InvalidRatio(&'static str),
+
+    /// A magic number in an [`InspectorConfig`](struct.InspectorConfig.html) is not a valid hex
+    /// string.
+    InvalidMagicNumber(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ConfigError::InvalidControlCharacter(byte) => write!(
+                f,
+                "0x{:02X} is not a control character (expected a byte from 0x01 to 0x1F)",
+                byte
+            ),
+            ConfigError::EmptyMagicNumber => write!(f, "magic numbers must not be empty"),
+            ConfigError::FallbackNotAllowed(content_type) => write!(
+                f,
+                "the fallback type {} is not one of the allowed types",
+                content_type
+            ),
+            ConfigError::InvalidRatio(setting) => {
+                write!(f, "{} has to be between 0.0 and 1.0", setting)
+            }
+            ConfigError::InvalidMagicNumber(ref signature) => {
+                write!(f, "'{}' is not a valid hex string", signature)
+            }
+        }
+    }
+}
+
+impl Error for ConfigError {}
+
+/// The result for empty buffers, see
+/// [`InspectorBuilder::empty_buffer_result`](struct.InspectorBuilder.html#method.empty_buffer_result).
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum EmptyResult {
+    /// Empty buffers are reported as `UTF_8`.
+    Utf8,
+
+    /// Empty buffers are reported as `BINARY`.
+    Binary,
+
+    /// Empty buffers are not classified at all: [`Inspector::try_inspect`] returns `None`.
+    ///
+    /// [`Inspector::try_inspect`]: struct.Inspector.html#method.try_inspect
+    None,
+}
+
+#[derive(Clone, Debug)]
+struct Config {
+    max_scan_size: usize,
+    scan_all: bool,
+    null_scan_limit: Option<usize>,
+    validation_limit: Option<usize>,
+    sniff_limit: Option<usize>,
+    max_null_bytes: usize,
+    detect_bom: bool,
+    corroborate_utf16_bom: bool,
+    detect_magic_numbers: bool,
+    detect_utf16_without_bom: bool,
+    detect_control_characters: bool,
+    max_control_ratio: f32,
+    min_printable_ratio: Option<f32>,
+    latin1_fallback: bool,
+    detect_ascii: bool,
+    empty_buffer_result: EmptyResult,
+    normalize_bom: bool,
+    byte_classifier: Option<fn(u8) -> ByteClass>,
+    allowed_control_characters: u32,
+    magic_numbers: Vec<(usize, Vec<u8>)>,
+    validation: Option<Validation>,
+    allowed_types: u32,
+    fallback_type: ContentType,
+}
+
+impl Hash for Config {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // Destructured, so that new settings cannot be forgotten here
+        let Config {
+            max_scan_size,
+            scan_all,
+            null_scan_limit,
+            validation_limit,
+            sniff_limit,
+            max_null_bytes,
+            detect_bom,
+            corroborate_utf16_bom,
+            detect_magic_numbers,
+            detect_utf16_without_bom,
+            detect_control_characters,
+            max_control_ratio,
+            min_printable_ratio,
+            latin1_fallback,
+            detect_ascii,
+            empty_buffer_result,
+            normalize_bom,
+            byte_classifier,
+            allowed_control_characters,
+            ref magic_numbers,
+            validation,
+            allowed_types,
+            fallback_type,
+        } = *self;
+
+        (max_scan_size, scan_all, null_scan_limit, validation_limit).hash(state);
+        (
+            sniff_limit,
+            max_null_bytes,
+            detect_bom,
+            detect_magic_numbers,
+        )
+            .hash(state);
+        (
+            corroborate_utf16_bom,
+            detect_utf16_without_bom,
+            detect_control_characters,
+        )
+            .hash(state);
+        max_control_ratio.to_bits().hash(state);
+        min_printable_ratio.map(f32::to_bits).hash(state);
+        (
+            latin1_fallback,
+            detect_ascii,
+            empty_buffer_result,
+            normalize_bom,
+        )
+            .hash(state);
+        // The address of the classifier is not stable, its classification of all bytes is
+        byte_classifier.map(byte_class_table).hash(state);
+        (allowed_control_characters, magic_numbers, validation).hash(state);
+        (allowed_types, fallback_type).hash(state);
+    }
+}
+
+/// The 64-bit FNV-1a hash, which (unlike the `DefaultHasher`) is guaranteed to be stable.
+struct Fnv1a(u64);
+
+impl Hasher for Fnv1a {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(0x0100_0000_01B3);
+        }
+    }
+}
+
+impl Default for Inspector {
+    fn default() -> Self {
+        InspectorBuilder::default().build().unwrap()
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            max_scan_size: MAX_SCAN_SIZE,
+            scan_all: false,
+            null_scan_limit: None,
+            validation_limit: None,
+            sniff_limit: None,
+            max_null_bytes: 0,
+            detect_bom: true,
+            corroborate_utf16_bom: true,
+            detect_magic_numbers: true,
+            detect_utf16_without_bom: false,
+            detect_control_characters: false,
+            max_control_ratio: MAX_CONTROL_CHARACTER_RATIO,
+            min_printable_ratio: None,
+            latin1_fallback: false,
+            detect_ascii: false,
+            empty_buffer_result: EmptyResult::Utf8,
+            normalize_bom: false,
+            byte_classifier: None,
+            allowed_control_characters: ALLOWED_CONTROL_CHARACTERS,
+            magic_numbers: vec![],
+            validation: None,
+            allowed_types: !0,
+            fallback_type: ContentType::BINARY,
+        }
+    }
+}
+
+/// A configurable version of [`inspect`](fn.inspect.html).
+///
+/// An `Inspector` is created through an [`InspectorBuilder`](struct.InspectorBuilder.html) and
+/// can be reused for any number of buffers. It is immutable and can be shared between threads;
+/// all lookup tables are computed once when it is built, and inspecting a buffer does not
+/// allocate:
+///
+/// ```
+/// use content_inspector::{ContentType, Inspector};
+///
+/// let inspector = Inspector::new().max_scan_size(8192).build().unwrap();
+///
+/// assert_eq!(ContentType::UTF_8, inspector.inspect(b"Hello"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Inspector {
+    config: Arc<Config>,
+    read_limit: Option<usize>,
+    prefix_length: usize,
+    stages: Vec<Step>,
+}
+
+impl Inspector {
+    /// Returns a builder for a new `Inspector`, initialized with the default settings of
+    /// [`inspect`](fn.inspect.html).
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new() -> InspectorBuilder {
+        InspectorBuilder::default()
+    }
+
+    /// Try to determine the type of content in the given buffer, using the settings of this
+    /// `Inspector`.
+    pub fn inspect(&self, buffer: &[u8]) -> ContentType {
+        self.inspect_detailed(buffer).content_type
+    }
+
+    /// Like [`inspect`](#method.inspect), but returns `None` for empty buffers if the inspector
+    /// was configured with [`EmptyResult::None`](enum.EmptyResult.html#variant.None).
+    pub fn try_inspect(&self, buffer: &[u8]) -> Option<ContentType> {
+        if buffer.is_empty() && self.config.empty_buffer_result == EmptyResult::None {
+            None
+        } else {
+            Some(self.inspect(buffer))
+        }
+    }
+
+    /// Like [`inspect`](#method.inspect), but returns a detailed report on how the type of
+    /// content was determined.
+    pub fn inspect_detailed(&self, buffer: &[u8]) -> InspectionReport {
+        let mut report = self.detect(buffer);
+        report.detected_type = report.content_type;
+        if self.config.allowed_types & type_bit(report.content_type) == 0 {
+            report.content_type = self.config.fallback_type;
+        }
+        debug_event!(
+            buffer_length = buffer.len(),
+            content_type = %report.content_type,
+            detected_type = %report.detected_type,
+            reason = ?report.reason,
+            bytes_examined = report.bytes_examined,
+            "content type determined"
+        );
+        report
+    }
+
+    /// The number of bytes at the start of a buffer that determine the result of this
+    /// `Inspector`: any longer prefix of the buffer (and the whole buffer) is classified the same.
+    /// This takes the scanned window, the limits of the stages and custom magic numbers into
+    /// account, and assumes that custom heuristics only look at the scanned window. Returns `None`
+    /// if the whole buffer is examined (with [`scan_all`](struct.InspectorBuilder.html#method.scan_all)
+    /// or the `Paranoid` strictness), so that no prefix is enough.
+    ///
+    /// ```
+    /// use content_inspector::{recommended_peek_size, Inspector, Strictness};
+    ///
+    /// assert_eq!(
+    ///     Some(recommended_peek_size()),
+    ///     Inspector::default().recommended_peek_size()
+    /// );
+    ///
+    /// let inspector = Inspector::new().max_scan_size(8192).build().unwrap();
+    /// assert_eq!(Some(8192), inspector.recommended_peek_size());
+    ///
+    /// let paranoid = Inspector::new().strictness(Strictness::Paranoid).build().unwrap();
+    /// assert_eq!(None, paranoid.recommended_peek_size());
+    /// ```
+    pub fn recommended_peek_size(&self) -> Option<usize> {
+        self.read_limit
+    }
+
+    /// A fingerprint of the [`detection_version`](fn.detection_version.html), the features of the
+    /// detection that are compiled in, and the configuration of this `Inspector`, including custom
+    /// magic numbers and heuristics (by their positions and
+    /// [`Heuristic::fingerprint`](trait.Heuristic.html#method.fingerprint)). Cached results of an
+    /// inspector can be reused by another one with the same fingerprint.
+    ///
+    /// The fingerprint is stable between processes, but it depends on the platform (for example
+    /// the size of `usize`) and may change with new versions of this crate.
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = Fnv1a(0xCBF2_9CE4_8422_2325);
+        DETECTION_VERSION.hash(&mut hasher);
+        [
+            cfg!(feature = "utf16"),
+            cfg!(feature = "utf32"),
+            cfg!(feature = "legacy-encodings"),
+            cfg!(feature = "magic-archives"),
+            cfg!(feature = "magic-documents"),
+            cfg!(feature = "magic-images"),
+            cfg!(feature = "magic-windows"),
+        ]
+        .hash(&mut hasher);
+        // The built-in stages follow from the configuration
+        self.config.hash(&mut hasher);
+        for (index, stage) in self.stages.iter().enumerate() {
+            if let Step::Custom(heuristic) = stage {
+                index.hash(&mut hasher);
+                heuristic.fingerprint(&mut hasher);
+            }
+        }
+        hasher.finish()
+    }
+
+    /// Check that the buffer contains the expected type of content. If it does not, the
+    /// `Mismatch` describes what was found instead:
+    ///
+    /// ```
+    /// use content_inspector::{ContentType, Inspector};
+    ///
+    /// let inspector = Inspector::default();
+    ///
+    /// assert!(inspector.check(b"Hello", ContentType::UTF_8).is_ok());
+    ///
+    /// let mismatch = inspector
+    ///     .check(b"\xEF\xBB\xBFHello", ContentType::UTF_8)
+    ///     .unwrap_err();
+    /// assert_eq!(ContentType::UTF_8_BOM, mismatch.detected());
+    /// assert_eq!(
+    ///     "expected UTF-8, found UTF-8-BOM (3-byte byte order mark at the start)",
+    ///     mismatch.to_string()
+    /// );
+    /// ```
+    pub fn check(&self, buffer: &[u8], expected: ContentType) -> Result<(), Mismatch> {
+        let report = self.inspect_detailed(buffer);
+        if report.content_type == expected {
+            Ok(())
+        } else {
+            Err(Mismatch {
+                expected,
+                report: Box::new(report),
+            })
+        }
+    }
+
+    /// Like [`check`](#method.check), but `ASCII` content is accepted if `UTF_8` is expected.
+    pub fn check_lenient(&self, buffer: &[u8], expected: ContentType) -> Result<(), Mismatch> {
+        match self.check(buffer, expected) {
+            Err(ref mismatch)
+                if expected == ContentType::UTF_8 && mismatch.detected() == ContentType::ASCII =>
+            {
+                Ok(())
+            }
+            result => result,
+        }
+    }
+
+    #[cfg(not(any(test, feature = "bench-internals")))]
+    fn detect(&self, buffer: &[u8]) -> InspectionReport {
+        self.run_stages(Bytes::new(buffer))
+    }
+
+    #[cfg(any(test, feature = "bench-internals"))]
+    fn detect(&self, buffer: &[u8]) -> InspectionReport {
+        self.detect_counted(buffer).0
+    }
+
+    /// The report, and the length of the longest prefix of the buffer that was read by the stages
+    /// (and the ASCII check).
+    #[cfg(any(test, feature = "bench-internals"))]
+    fn detect_counted(&self, buffer: &[u8]) -> (InspectionReport, usize) {
+        let read = Cell::new(0);
+        let report = self.run_stages(Bytes::new(buffer, &read));
+        (report, read.get())
+    }
+
+    /// The number of bytes at the start of the buffer that an inspection actually reads, counted
+    /// by the accessor through which the built-in stages read the buffer (custom heuristics read
+    /// all of it). For the tests and benchmarks that check the bounds of the work, see
+    /// `bytes_examined` of the report for what the stages record.
+    #[cfg(feature = "bench-internals")]
+    #[doc(hidden)]
+    pub fn bytes_read(&self, buffer: &[u8]) -> usize {
+        self.detect_counted(buffer).1
+    }
+
+    fn run_stages(&self, buffer: Bytes<'_>) -> InspectionReport {
+        use ContentType::*;
+
+        let mut report = InspectionReport {
+            content_type: UTF_8,
+            detected_type: UTF_8,
+            reason: Reason::NoBinaryIndicators,
+            null_bytes: 0,
+            first_null_offset: None,
+            bom_length: 0,
+            control_characters: 0,
+            printable_bytes: 0,
+            invalid_utf8_offset: None,
+            binary_format: None,
+            scanned_bytes: 0,
+            validated_bytes: 0,
+            sniffed_bytes: 0,
+            bytes_examined: 0,
+        };
+
+        if buffer.len() == 0 {
+            if self.config.empty_buffer_result == EmptyResult::Binary {
+                report.content_type = BINARY;
+            }
+            report.reason = Reason::EmptyBuffer;
+            return report;
+        }
+
+        let window = if self.config.scan_all {
+            buffer
+        } else {
+            buffer.truncate(self.config.max_scan_size)
+        };
+        let mut partial = Partial::new(window, report);
+        for stage in &self.stages {
+            if let Some(content_type) = stage.examine(buffer, &mut partial) {
+                let report = partial.report_mut();
+                report.content_type = content_type;
+                report.reason = stage.reason();
+                break;
+            }
+        }
+
+        let is_ascii = self.config.detect_ascii && {
+            let window = partial.window().len();
+            partial.examined(window);
+            partial.is_ascii()
+        };
+        let mut report = partial.into_report();
+        if is_ascii && report.reason == Reason::NoBinaryIndicators {
+            report.content_type = ASCII;
+        }
+        report
+    }
+
+    /// The number of bytes at the start of a buffer that can influence the result, or `None` if
+    /// the whole buffer is examined.
+    pub(crate) fn read_limit(&self) -> Option<usize> {
+        self.read_limit
+    }
+
+    /// The number of bytes that are needed to check all byte order marks and magic numbers.
+    pub(crate) fn prefix_length(&self) -> usize {
+        self.prefix_length
+    }
+
+    /// Whether the result for the given prefix of a buffer holds for every continuation of it,
+    /// assuming that custom heuristics only look at the start of the buffer.
+    pub(crate) fn is_settled(&self, prefix: &[u8], report: &InspectionReport) -> bool {
+        let bom_pending = BYTE_ORDER_MARKS
+            .iter()
+            .any(|&(bom, _)| bom.len() > prefix.len() && bom.starts_with(prefix));
+        if bom_pending {
+            return false;
+        }
+
+        // Whether a UTF-16 byte order mark is taken into account depends on the content after it,
+        // up to the scanned window
+        let utf16_bom = [&b"\xFF\xFE"[..], b"\xFE\xFF"]
+            .iter()
+            .any(|&bom| prefix.starts_with(bom) && !prefix.starts_with(b"\xFF\xFE\x00\x00"));
+        let corroboration_limit = self.config.corroboration_limit();
+        if utf16_bom && corroboration_limit.is_some_and(|limit| prefix.len() < limit) {
+            return false;
+        }
+
+        // A stage that precedes the one which decided, and could still decide with more content.
+        // Magic numbers that could still match are not, as they result in BINARY as well.
+        let utf16_pending = self.config.sniffs_utf16();
+        match report.reason {
+            Reason::ByteOrderMark => self.config.validation.is_none(),
+            // Odd lengths are only rejected at the end of the whole buffer
+            Reason::InvalidEncoding => self.config.validation == Some(Validation::Window),
+            Reason::MagicNumber | Reason::NullBytes => !utf16_pending,
+            // A sequence that is incomplete at the end of the prefix could still be completed
+            Reason::InvalidUtf8 => {
+                let incomplete = report.invalid_utf8_offset.is_some_and(|offset| {
+                    core::str::from_utf8(&prefix[min(offset, prefix.len())..])
+                        .is_err_and(|error| error.error_len().is_none())
+                });
+                report.detected_type == ContentType::BINARY && !utf16_pending && !incomplete
+            }
+            _ => false,
+        }
+    }
+
+    /// The part of the buffer that is subject to the (bounded) scanning heuristics.
+    pub(crate) fn scan_window<'a>(&self, buffer: &'a [u8]) -> &'a [u8] {
+        if self.config.scan_all {
+            buffer
+        } else {
+            &buffer[..min(buffer.len(), self.config.max_scan_size)]
+        }
+    }
+}
+
+impl Config {
+    /// The limit of a stage, which defaults to the size of the scanned window.
+    fn limit(&self, limit: Option<usize>) -> usize {
+        match (limit, self.scan_all) {
+            (Some(limit), _) => limit,
+            (None, false) => self.max_scan_size,
+            (None, true) => usize::MAX,
+        }
+    }
+
+    /// The number of bytes (from the start of the buffer) that corroborate a UTF-16 byte order
+    /// mark, or `None` if they are not checked.
+    fn corroboration_limit(&self) -> Option<usize> {
+        let limit = min(self.limit(None), 2 + 2 * UTF16_BOM_CHECKED_UNITS);
+        let utf16 = cfg!(feature = "utf16");
+        Some(limit).filter(|_| utf16 && self.detect_bom && self.corroborate_utf16_bom)
+    }
+
+    /// Whether UTF-16 without a byte order mark is detected, which needs the `utf16` feature.
+    fn sniffs_utf16(&self) -> bool {
+        cfg!(feature = "utf16") && self.detect_utf16_without_bom
+    }
+
+    /// Whether content with invalid UTF-8 is reported as Latin-1, which needs the
+    /// `legacy-encodings` feature.
+    fn falls_back_to_latin1(&self) -> bool {
+        cfg!(feature = "legacy-encodings") && self.latin1_fallback
+    }
+
+    /// The number of bytes that are needed to check all byte order marks and magic numbers.
+    fn prefix_length(&self) -> usize {
+        let builtin = MAGIC_NUMBERS
+            .iter()
+            .flat_map(|magic| magic.signature)
+            .map(|&(offset, bytes)| offset + bytes.len())
+            .chain(BYTE_ORDER_MARKS.iter().map(|&(bom, _)| bom.len()));
+        let custom = self
+            .magic_numbers
+            .iter()
+            .map(|&(offset, ref bytes)| offset.saturating_add(bytes.len()));
+        builtin.chain(custom).max().unwrap_or(0)
+    }
+
+    fn read_limit(&self) -> Option<usize> {
+        if self.validation == Some(Validation::Buffer) {
+            return None;
+        }
+
+        let validation = self.falls_back_to_latin1() || self.validation.is_some();
+        let limit = Some(self.prefix_length())
+            .into_iter()
+            .chain(Some(self.limit(None)))
+            .chain(Some(self.limit(self.null_scan_limit)))
+            .chain(Some(self.limit(self.validation_limit)).filter(|_| validation))
+            .chain(Some(self.limit(self.sniff_limit)).filter(|_| self.sniffs_utf16()))
+            .max()
+            .unwrap_or(0);
+
+        if limit == usize::MAX {
+            None
+        } else {
+            Some(limit)
+        }
+    }
+}
+
+fn type_bit(content_type: ContentType) -> u32 {
+    1 << content_type as u32
+}
+
+fn control_character_bit(byte: u8) -> Result<u32, ConfigError> {
+    match byte {
+        0x01..=0x1F => Ok(1 << byte),
+        _ => Err(ConfigError::InvalidControlCharacter(byte)),
+    }
+}
+
+/// A builder for an [`Inspector`](struct.Inspector.html).
+///
+/// All heuristics that are not enabled by default can be switched on individually. The default
+/// settings correspond exactly to the behavior of [`inspect`](fn.inspect.html).
+#[derive(Clone, Debug, Default)]
+pub struct InspectorBuilder {
+    config: Config,
+    heuristics: Vec<(Position, Arc<dyn Heuristic>)>,
+    error: Option<ConfigError>,
+}
+
+impl InspectorBuilder {
+    /// Use the settings that mimic the binary detection of another tool. This resets all
+    /// previous settings, but they can be adjusted afterwards.
+    ///
+    /// ```
+    /// use content_inspector::{ContentType, Inspector, Preset};
+    ///
+    /// let git = Inspector::new().preset(Preset::Git).build().unwrap();
+    ///
+    /// assert_eq!(ContentType::BINARY, git.inspect(b"\xFF\xFEa\x00"));
+    /// ```
+    pub fn preset(mut self, preset: Preset) -> Self {
+        self.config = Config::default();
+        self.heuristics.clear();
+        self.error = None;
+
+        match preset {
+            Preset::Git => {
+                self.config.max_scan_size = 8000;
+                self.config.detect_bom = false;
+                self.config.detect_magic_numbers = false;
+            }
+            Preset::Grep => {
+                self.config.max_scan_size = 32 * 1024;
+                self.config.detect_bom = false;
+                self.config.detect_magic_numbers = false;
+            }
+            Preset::File => {
+                self.config.max_scan_size = 1024 * 1024;
+                self.config.detect_magic_numbers = false;
+                self.config.detect_control_characters = true;
+                self.config.max_control_ratio = 0.0;
+                self.config.allowed_control_characters = (0x07..=0x0D)
+                    .chain(Some(0x1B))
+                    .fold(0, |bits, byte| bits | 1 << byte);
+                self.config.latin1_fallback = true;
+            }
+        }
+        self
+    }
+
+    /// Select the built-in heuristics for the given level of strictness. This sets
+    /// [`detect_bom`](#method.detect_bom), [`detect_magic_numbers`](#method.detect_magic_numbers)
+    /// and [`scan_all`](#method.scan_all) as well as the encoding validation, and disables the
+    /// detection of UTF-16 without BOM, control characters and the Latin-1 fallback. All other
+    /// settings are kept.
+    ///
+    /// ```
+    /// use content_inspector::{ContentType, Inspector, Strictness};
+    ///
+    /// let standard = Inspector::new().strictness(Strictness::Standard).build().unwrap();
+    /// let strict = Inspector::new().strictness(Strictness::Strict).build().unwrap();
+    ///
+    /// assert_eq!(ContentType::UTF_8, standard.inspect(b"caf\xE9!"));
+    /// assert_eq!(ContentType::BINARY, strict.inspect(b"caf\xE9!"));
+    /// ```
+    pub fn strictness(mut self, strictness: Strictness) -> Self {
+        let fast = strictness == Strictness::Fast;
+        self.config.detect_bom = !fast;
+        self.config.detect_magic_numbers = !fast;
+        self.config.detect_utf16_without_bom = false;
+        self.config.detect_control_characters = false;
+        self.config.latin1_fallback = false;
+        self.config.scan_all = strictness == Strictness::Paranoid;
+        self.config.validation = match strictness {
+            Strictness::Fast | Strictness::Standard => None,
+            Strictness::Strict => Some(Validation::Window),
+            Strictness::Paranoid => Some(Validation::Buffer),
+        };
+        self
+    }
+
+    /// Set the number of bytes at the start of the buffer that are scanned for NULL bytes
+    /// (default: [`MAX_SCAN_SIZE`](constant.MAX_SCAN_SIZE.html), 1024). This is also the default
+    /// for the limits of the other stages, see [`validation_limit`](#method.validation_limit) and [`sniff_limit`](#method.sniff_limit).
+    pub fn max_scan_size(mut self, max_scan_size: usize) -> Self {
+        self.config.max_scan_size = max_scan_size;
+        self
+    }
+
+    /// Scan the whole buffer instead of only the first `max_scan_size` bytes (default: `false`).
+    ///
+    /// Byte order marks and magic numbers are still only checked at the start of the buffer.
+    /// The NULL-byte scan is `memchr`-based, so this is `O(n)` in the size of the buffer, but
+    /// still fast. If you only have a prefix of the data in memory anyway, there is no need to
+    /// enable this option.
+    ///
+    /// With the `rayon` feature, windows of more than 2 MiB are scanned (for NULL bytes, control
+    /// characters, printable bytes, ASCII and UTF-8 validity) in parallel chunks of 1 MiB, with the
+    /// same results as a sequential scan.
+    pub fn scan_all(mut self, scan_all: bool) -> Self {
+        self.config.scan_all = scan_all;
+        self
+    }
+
+    /// Set the number of bytes that are scanned for NULL bytes, control characters and
+    /// printable bytes, instead of [`max_scan_size`](#method.max_scan_size).
+    ///
+    /// ```
+    /// use content_inspector::{ContentType, Inspector};
+    ///
+    /// let inspector = Inspector::new()
+    ///     .null_scan_limit(64 * 1024)
+    ///     .validation_limit(4 * 1024)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let mut buffer = vec![b'a'; 10000];
+    /// buffer.push(0x00);
+    /// assert_eq!(ContentType::BINARY, inspector.inspect(&buffer));
+    /// ```
+    pub fn null_scan_limit(mut self, limit: usize) -> Self {
+        self.config.null_scan_limit = Some(limit);
+        self
+    }
+
+    /// Set the number of bytes that are validated by the Latin-1 fallback and the encoding
+    /// validation of [`Strictness::Strict`](enum.Strictness.html#variant.Strict), instead of
+    /// [`max_scan_size`](#method.max_scan_size).
+    pub fn validation_limit(mut self, limit: usize) -> Self {
+        self.config.validation_limit = Some(limit);
+        self
+    }
+
+    /// Set the number of bytes that are examined for UTF-16 without a byte order mark, instead of
+    /// [`max_scan_size`](#method.max_scan_size).
+    pub fn sniff_limit(mut self, limit: usize) -> Self {
+        self.config.sniff_limit = Some(limit);
+        self
+    }
+
+    /// Set the number of NULL bytes that are tolerated in the scanned window before the content
+    /// is considered to be binary (default: 0).
+    pub fn max_null_bytes(mut self, max_null_bytes: usize) -> Self {
+        self.config.max_null_bytes = max_null_bytes;
+        self
+    }
+
+    /// Detect byte order marks at the start of the buffer (default: `true`).
+    ///
+    /// If disabled, buffers with a byte order mark are classified by the remaining heuristics.
+    /// UTF-16 or UTF-32 encoded text will usually be reported as `BINARY` in this case, since it
+    /// contains NULL bytes.
+    pub fn detect_bom(mut self, enabled: bool) -> Self {
+        self.config.detect_bom = enabled;
+        self
+    }
+
+    /// Only take a two-byte UTF-16 byte order mark (`FF FE` or `FE FF`) into account if the
+    /// content after it looks like UTF-16 text (default: `true`). Otherwise, the buffer is
+    /// classified by the remaining heuristics, as if there was no byte order mark.
+    ///
+    /// Many binary formats start with these bytes by accident, so the first 128 code units
+    /// after the byte order mark (within the scanned window) are checked: at most one in 32 of
+    /// them may be an unpaired surrogate, a control character other than the allowed ones (see
+    /// [`detect_control_characters`](#method.detect_control_characters)), a private use
+    /// character or a noncharacter. Content of up to 8 code units is not checked, so a byte
+    /// order mark that is followed by a few characters is always UTF-16. The UTF-32 byte order
+    /// marks are long enough on their own. Without the `utf16` feature, there are no UTF-16 byte
+    /// order marks to corroborate, so this has no effect.
+    ///
+    /// ```
+    /// # #[cfg(feature = "utf16")]
+    /// # {
+    /// use content_inspector::{ContentType, Inspector};
+    ///
+    /// let mut blob = b"\xFF\xFE".to_vec();
+    /// blob.extend((1..200_u32).flat_map(|i| i.wrapping_mul(0x9E37_79B9).to_le_bytes()));
+    ///
+    /// assert_eq!(ContentType::BINARY, Inspector::default().inspect(&blob));
+    /// assert_eq!(ContentType::UTF_16LE, Inspector::default().inspect(b"\xFF\xFEH\x00i\x00"));
+    ///
+    /// let trusting = Inspector::new().corroborate_utf16_bom(false).build().unwrap();
+    /// assert_eq!(ContentType::UTF_16LE, trusting.inspect(&blob));
+    /// # }
+    /// ```
+    pub fn corroborate_utf16_bom(mut self, enabled: bool) -> Self {
+        self.config.corroborate_utf16_bom = enabled;
+        self
+    }
+
+    /// Classify buffers that start with the magic number of a known binary format (like PDF or
+    /// PNG) as binary (default: `true`). Only the built-in magic numbers of the enabled families
+    /// are detected, see [`BinaryFormat`](enum.BinaryFormat.html).
+    pub fn detect_magic_numbers(mut self, enabled: bool) -> Self {
+        self.config.detect_magic_numbers = enabled;
+        self
+    }
+
+    /// Detect UTF-16 encoded text without a byte order mark (default: `false`).
+    ///
+    /// This only works for text that mostly consists of ASCII or Latin-1 characters, since the
+    /// detection is based on the position of zero bytes within the code units. This check is
+    /// performed before the NULL-byte scan. It needs the `utf16` feature (otherwise this has no
+    /// effect).
+    pub fn detect_utf16_without_bom(mut self, enabled: bool) -> Self {
+        self.config.detect_utf16_without_bom = enabled;
+        self
+    }
+
+    /// Classify the content as binary if more than 10% (see
+    /// [`max_control_ratio`](#method.max_control_ratio)) of the scanned window consists of
+    /// control characters other than tab, line feed, carriage return, form feed and escape
+    /// (default: `false`).
+    pub fn detect_control_characters(mut self, enabled: bool) -> Self {
+        self.config.detect_control_characters = enabled;
+        self
+    }
+
+    /// Set the fraction of control characters in the scanned window above which the content is
+    /// considered to be binary (default: 0.1). With a ratio of 0, a single control character is
+    /// enough. This has no effect unless the control character heuristic is enabled. Ratios outside
+    /// of 0.0 to 1.0 make [`build`](#method.build) fail with a `ConfigError`.
+    pub fn max_control_ratio(mut self, ratio: f32) -> Self {
+        self.config.max_control_ratio = ratio;
+        self.check_ratio(ratio, "max_control_ratio")
+    }
+
+    /// Classify the content as binary if less than the given fraction of the bytes in the scanned
+    /// window is printable (default: no minimum). A byte is printable if it is neither a NULL byte
+    /// nor a control character, so the allowed control characters (tab, line feed, ...) count as
+    /// printable, and so do all bytes of multi-byte UTF-8 sequences. A UTF-8 byte order mark at the
+    /// start of the window is not counted. Windows without any counted bytes are never binary.
+    /// Ratios outside of 0.0 to 1.0 make [`build`](#method.build) fail with a `ConfigError`.
+    ///
+    /// The printable bytes are counted in the same pass as the NULL bytes and control characters.
+    ///
+    /// ```
+    /// use content_inspector::{ContentType, Inspector};
+    ///
+    /// let inspector = Inspector::new().min_printable_ratio(0.9).build().unwrap();
+    ///
+    /// assert_eq!(ContentType::UTF_8, inspector.inspect(b"Hello, World!\n"));
+    /// assert_eq!(ContentType::BINARY, inspector.inspect(b"\x01\x02\x03 Hello"));
+    /// ```
+    pub fn min_printable_ratio(mut self, ratio: f32) -> Self {
+        self.config.min_printable_ratio = Some(ratio);
+        self.check_ratio(ratio, "min_printable_ratio")
+    }
+
+    fn check_ratio(mut self, ratio: f32, setting: &'static str) -> Self {
+        if !(0.0..=1.0).contains(&ratio) {
+            self.error = self.error.or(Some(ConfigError::InvalidRatio(setting)));
+        }
+        self
+    }
+
+    /// Report content that contains invalid UTF-8 sequences in the scanned window as `LATIN_1`
+    /// instead of `UTF_8` (default: `false`). It needs the `legacy-encodings` feature (otherwise
+    /// this has no effect).
+    pub fn latin1_fallback(mut self, enabled: bool) -> Self {
+        self.config.latin1_fallback = enabled;
+        self
+    }
+
+    /// Report text as `ASCII` instead of `UTF_8` if the scanned window only contains ASCII
+    /// characters (default: `false`).
+    pub fn detect_ascii(mut self, enabled: bool) -> Self {
+        self.config.detect_ascii = enabled;
+        self
+    }
+
+    /// Set the result for empty buffers (default: `EmptyResult::Utf8`).
+    ///
+    /// With `EmptyResult::None`, [`Inspector::try_inspect`] returns `None` for empty buffers,
+    /// while [`Inspector::inspect`] still reports them as `UTF_8`. A buffer that consists of
+    /// nothing but a byte order mark is not empty and is classified according to its BOM.
+    ///
+    /// [`Inspector::try_inspect`]: struct.Inspector.html#method.try_inspect
+    /// [`Inspector::inspect`]: struct.Inspector.html#method.inspect
+    pub fn empty_buffer_result(mut self, result: EmptyResult) -> Self {
+        self.config.empty_buffer_result = result;
+        self
+    }
+
+    /// Report UTF-8 encoded text with a byte order mark as `UTF_8` instead of `UTF_8_BOM`
+    /// (default: `false`).
+    ///
+    /// The [`InspectionReport`](struct.InspectionReport.html) still contains the length of the
+    /// byte order mark.
+    pub fn normalize_bom(mut self, enabled: bool) -> Self {
+        self.config.normalize_bom = enabled;
+        self
+    }
+
+    /// Use a custom classification of bytes for the NULL-byte and control character heuristics,
+    /// instead of the built-in [`ByteClass::of`](enum.ByteClass.html#method.of).
+    ///
+    /// The classifier is called once for each byte value when the `Inspector` is built. Note that
+    /// the (`memchr`-based) NULL-byte scan is faster than the table-based classification that is
+    /// used with a custom classifier.
+    ///
+    /// ```
+    /// use content_inspector::{ByteClass, ContentType, Inspector};
+    ///
+    /// let inspector = Inspector::new()
+    ///     .byte_classifier(|byte| match byte {
+    ///         0x7F => ByteClass::Null,
+    ///         _ => ByteClass::of(byte),
+    ///     })
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(ContentType::BINARY, inspector.inspect(b"\x7F"));
+    /// ```
+    pub fn byte_classifier(mut self, classifier: fn(u8) -> ByteClass) -> Self {
+        self.config.byte_classifier = Some(classifier);
+        self
+    }
+
+    /// Do not count the given bytes towards the control character ratio.
+    ///
+    /// Only bytes from `0x01` to `0x1F` are valid, otherwise [`build`](#method.build) fails with a
+    /// `ConfigError`. This does not affect a custom
+    /// [`byte_classifier`](#method.byte_classifier).
+    pub fn allow_control_chars(mut self, bytes: &[u8]) -> Self {
+        for &byte in bytes {
+            match control_character_bit(byte) {
+                Ok(bit) => self.config.allowed_control_characters |= bit,
+                Err(error) => self.error = self.error.or(Some(error)),
+            }
+        }
+        self
+    }
+
+    /// Count the given bytes towards the control character ratio, even if they are allowed by
+    /// default (like escape or form feed).
+    ///
+    /// Only bytes from `0x01` to `0x1F` are valid, otherwise [`build`](#method.build) fails with a
+    /// `ConfigError`.
+    pub fn deny_control_chars(mut self, bytes: &[u8]) -> Self {
+        for &byte in bytes {
+            match control_character_bit(byte) {
+                Ok(bit) => self.config.allowed_control_characters &= !bit,
+                Err(error) => self.error = self.error.or(Some(error)),
+            }
+        }
+        self
+    }
+
+    /// Only return the given types of content (default: all types). Any other detected type is
+    /// mapped to the [`fallback_type`](#method.fallback_type), which has to be one of the allowed
+    /// types, otherwise [`build`](#method.build) fails with a `ConfigError`. The detailed report
+    /// still contains the type that was actually detected.
+    ///
+    /// ```
+    /// use content_inspector::{ContentType, Inspector};
+    ///
+    /// let inspector = Inspector::new()
+    ///     .allowed_types(&[ContentType::UTF_8, ContentType::BINARY])
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(ContentType::UTF_8, inspector.inspect(b"Hello"));
+    /// assert_eq!(ContentType::BINARY, inspector.inspect(b"\xFF\xFEH\x00i\x00"));
+    /// ```
+    pub fn allowed_types(mut self, types: &[ContentType]) -> Self {
+        self.config.allowed_types = types
+            .iter()
+            .fold(0, |bits, &content_type| bits | type_bit(content_type));
+        self
+    }
+
+    /// The type of content that is returned instead of types that are not allowed (default:
+    /// `BINARY`), see [`allowed_types`](#method.allowed_types).
+    pub fn fallback_type(mut self, content_type: ContentType) -> Self {
+        self.config.fallback_type = content_type;
+        self
+    }
+
+    /// Add a custom magic number: buffers that contain the given signature at the given offset
+    /// are classified as binary, with a `BinaryFormat::Custom` format. The index of the custom
+    /// format corresponds to the order in which the magic numbers were added.
+    ///
+    /// The built-in magic numbers take precedence. The signature must not be empty, otherwise
+    /// [`build`](#method.build) fails with a `ConfigError`.
+    pub fn magic_number(mut self, offset: usize, signature: &[u8]) -> Self {
+        if signature.is_empty() {
+            self.error = self.error.or(Some(ConfigError::EmptyMagicNumber));
+        } else {
+            self.config.magic_numbers.push((offset, signature.to_vec()));
+        }
+        self
+    }
+
+    /// Add a custom heuristic to the detection pipeline at the given position. Heuristics at the
+    /// same position run in the order in which they were added.
+    pub fn with_heuristic(mut self, position: Position, heuristic: Box<dyn Heuristic>) -> Self {
+        self.heuristics.push((position, Arc::from(heuristic)));
+        self
+    }
+
+    /// Create an `Inspector` with the current settings. The builder can be used again afterwards.
+    ///
+    /// Fails if the configuration is invalid.
+    pub fn build(&self) -> Result<Inspector, ConfigError> {
+        if let Some(ref error) = self.error {
+            return Err(error.clone());
+        }
+        if self.config.allowed_types & type_bit(self.config.fallback_type) == 0 {
+            return Err(ConfigError::FallbackNotAllowed(self.config.fallback_type));
+        }
+
+        let config = &self.config;
+        let mut stages = vec![];
+        let insert_custom = |stages: &mut Vec<Step>, position| {
+            for &(p, ref heuristic) in &self.heuristics {
+                if p == position {
+                    stages.push(Step::Custom(heuristic.clone()));
+                }
+            }
+        };
+
+        insert_custom(&mut stages, Position::First);
+        let null_scan_limit = config.limit(config.null_scan_limit);
+        let validation_limit = config.limit(config.validation_limit);
+        if let (true, Some(validation)) = (config.detect_bom, config.validation) {
+            stages.push(Step::Builtin(Arc::new(BomValidation {
+                validation,
+                limit: validation_limit,
+            })));
+        }
+        if config.detect_bom {
+            stages.push(Step::Builtin(Arc::new(ByteOrderMark {
+                normalize: config.normalize_bom,
+                #[cfg(feature = "utf16")]
+                corroboration_limit: config.corroboration_limit(),
+            })));
+        }
+        #[cfg(feature = "utf16")]
+        if config.sniffs_utf16() {
+            stages.push(Step::Builtin(Arc::new(Utf16Pattern {
+                limit: config.limit(config.sniff_limit),
+            })));
+        }
+        let magic_numbers = !MAGIC_NUMBERS.is_empty() || !config.magic_numbers.is_empty();
+        if config.detect_magic_numbers && magic_numbers {
+            stages.push(Step::Builtin(Arc::new(MagicNumber::new(
+                &config.magic_numbers,
+            ))));
+        }
+        insert_custom(&mut stages, Position::BeforeNullScan);
+        let counted = config.min_printable_ratio.is_some() || config.detect_control_characters;
+        let table = match config.byte_classifier {
+            Some(classifier) => Some(byte_class_table(classifier)),
+            None if counted => Some(control_character_table(config.allowed_control_characters)),
+            None => None,
+        };
+        stages.push(Step::Builtin(Arc::new(NullBytes {
+            max_null_bytes: config.max_null_bytes,
+            limit: null_scan_limit,
+            text_above_control: table.as_ref().is_some_and(is_text_above_control),
+            table,
+        })));
+        insert_custom(&mut stages, Position::AfterNullScan);
+        if config.detect_control_characters {
+            stages.push(Step::Builtin(Arc::new(ControlCharacters {
+                max_ratio: config.max_control_ratio,
+                limit: null_scan_limit,
+            })));
+        }
+        if let Some(min_ratio) = config.min_printable_ratio {
+            stages.push(Step::Builtin(Arc::new(PrintableRatio {
+                min_ratio,
+                limit: null_scan_limit,
+            })));
+        }
+        #[cfg(feature = "legacy-encodings")]
+        if config.falls_back_to_latin1() {
+            stages.push(Step::Builtin(Arc::new(Latin1Fallback {
+                limit: validation_limit,
+            })));
+        }
+        if let Some(validation) = config.validation {
+            stages.push(Step::Builtin(Arc::new(Utf8Validation {
+                validation,
+                limit: validation_limit,
+            })));
+        }
+        insert_custom(&mut stages, Position::BeforeFallback);
+
+        Ok(Inspector {
+            config: Arc::new(self.config.clone()),
+            read_limit: self.config.read_limit(),
+            prefix_length: self.config.prefix_length(),
+            stages,
+        })
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(
+    not(all(feature = "utf16", feature = "utf32", feature = "legacy-encodings")),
+    allow(dead_code, unused_imports)
+)]
+mod tests {
+    use crate::test_util::text_with_null_at;
+    use crate::{
+        BinaryFormat, ByteClass, ConfigError, ContentType, ContentType::*, EmptyResult, Heuristic,
+        Inspector, InspectorBuilder, Partial, Position, Preset, Reason, Strictness, MAGIC_NUMBERS,
+        MAX_SCAN_SIZE,
+    };
+    use std::fmt;
+    use std::fs;
+    use std::hash::{Hash, Hasher};
+    use std::path::Path;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn test_default_scan_size() {
+        let buffer = text_with_null_at(2000);
+        assert_eq!(UTF_8, Inspector::default().inspect(&buffer));
+        assert_eq!(BINARY, Inspector::default().inspect(&buffer[1000..]));
+    }
+
+    #[test]
+    fn test_max_scan_size() {
+        let buffer = text_with_null_at(2000);
+
+        let small = Inspector::new().max_scan_size(1024).build().unwrap();
+        let large = Inspector::new().max_scan_size(4096).build().unwrap();
+        assert_eq!(UTF_8, small.inspect(&buffer));
+        assert_eq!(BINARY, large.inspect(&buffer));
+
+        let tiny = Inspector::new().max_scan_size(64).build().unwrap();
+        assert_eq!(UTF_8, tiny.inspect(&text_with_null_at(64)));
+        assert_eq!(BINARY, tiny.inspect(&text_with_null_at(63)));
+    }
+
+    #[test]
+    fn test_builder_reuse() {
+        let builder = Inspector::new().max_scan_size(4096);
+        let first = builder.build().unwrap();
+        let second = builder.max_scan_size(1024).build().unwrap();
+
+        let buffer = text_with_null_at(2000);
+        assert_eq!(BINARY, first.inspect(&buffer));
+        assert_eq!(UTF_8, second.inspect(&buffer));
+    }
+
+    #[test]
+    fn test_scan_all() {
+        let mut buffer = vec![b'a'; 10 * 1024 * 1024];
+        *buffer.last_mut().unwrap() = 0x00;
+
+        assert_eq!(UTF_8, Inspector::default().inspect(&buffer));
+        assert_eq!(
+            UTF_8,
+            Inspector::new()
+                .scan_all(false)
+                .build()
+                .unwrap()
+                .inspect(&buffer)
+        );
+        assert_eq!(
+            BINARY,
+            Inspector::new()
+                .scan_all(true)
+                .build()
+                .unwrap()
+                .inspect(&buffer)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "utf16")]
+    fn test_scan_all_keeps_bom_detection() {
+        let inspector = Inspector::new().scan_all(true).build().unwrap();
+        assert_eq!(UTF_16LE, inspector.inspect(b"\xFF\xFEa\x00b\x00"));
+        if cfg!(feature = "magic-numbers") {
+            assert_eq!(BINARY, inspector.inspect(b"%PDF-1.4"));
+        }
+    }
+
+    #[test]
+    fn test_max_null_bytes() {
+        let mut buffer = vec![b'a'; 1000];
+        for offset in &[10, 300, 900] {
+            buffer[*offset] = 0x00;
+        }
+
+        assert_eq!(BINARY, Inspector::default().inspect(&buffer));
+        assert_eq!(
+            BINARY,
+            Inspector::new()
+                .max_null_bytes(2)
+                .build()
+                .unwrap()
+                .inspect(&buffer)
+        );
+        assert_eq!(
+            UTF_8,
+            Inspector::new()
+                .max_null_bytes(3)
+                .build()
+                .unwrap()
+                .inspect(&buffer)
+        );
+        assert_eq!(
+            UTF_8,
+            Inspector::new()
+                .max_null_bytes(4)
+                .build()
+                .unwrap()
+                .inspect(&buffer)
+        );
+    }
+
+    #[test]
+    fn test_max_null_bytes_clustered() {
+        let mut clustered = vec![b'a'; 1000];
+        let mut scattered = vec![b'a'; 1000];
+        for i in 0..5 {
+            clustered[500 + i] = 0x00;
+            scattered[i * 200] = 0x00;
+        }
+
+        let inspector = Inspector::new().max_null_bytes(4).build().unwrap();
+        assert_eq!(BINARY, inspector.inspect(&clustered));
+        assert_eq!(BINARY, inspector.inspect(&scattered));
+
+        let inspector = Inspector::new().max_null_bytes(5).build().unwrap();
+        assert_eq!(UTF_8, inspector.inspect(&clustered));
+        assert_eq!(UTF_8, inspector.inspect(&scattered));
+    }
+
+    #[test]
+    fn test_report_null_bytes() {
+        let mut buffer = vec![b'a'; 2000];
+        buffer[7] = 0x00;
+        buffer[8] = 0x00;
+        buffer[1500] = 0x00;
+
+        let report = Inspector::default().inspect_detailed(&buffer);
+        assert_eq!(BINARY, report.content_type);
+        assert_eq!(Reason::NullBytes, report.reason);
+        assert_eq!(2, report.null_bytes);
+        assert_eq!(Some(7), report.first_null_offset);
+        assert_eq!(1024, report.scanned_bytes);
+
+        let report = Inspector::new()
+            .max_null_bytes(3)
+            .scan_all(true)
+            .build()
+            .unwrap()
+            .inspect_detailed(&buffer);
+        assert_eq!(UTF_8, report.content_type);
+        assert_eq!(Reason::NoBinaryIndicators, report.reason);
+        assert_eq!(3, report.null_bytes);
+        assert_eq!(2000, report.scanned_bytes);
+    }
+
+    type Toggle = fn(InspectorBuilder, bool) -> InspectorBuilder;
+    type ToggleCase = (
+        &'static str,
+        Toggle,
+        bool,
+        &'static [u8],
+        ContentType,
+        ContentType,
+    );
+
+    /// Each toggle with its default value and a fixture that changes its classification (from
+    /// the first to the second type) only if this particular toggle is flipped.
+    const TOGGLES: &[ToggleCase] = &[
+        (
+            "magic numbers",
+            InspectorBuilder::detect_magic_numbers,
+            true,
+            include_bytes!("../testdata/test.pdf"),
+            BINARY,
+            UTF_8,
+        ),
+        (
+            "UTF-16 BOM corroboration",
+            InspectorBuilder::corroborate_utf16_bom,
+            true,
+            include_bytes!("../testdata/test_utf16_bom.jpg"),
+            BINARY,
+            UTF_16LE,
+        ),
+        (
+            "UTF-16 without BOM",
+            InspectorBuilder::detect_utf16_without_bom,
+            false,
+            include_bytes!("../testdata/text_UTF-16LE.txt"),
+            BINARY,
+            UTF_16LE,
+        ),
+        (
+            "control characters",
+            InspectorBuilder::detect_control_characters,
+            false,
+            include_bytes!("../testdata/test_control_characters.bin"),
+            UTF_8,
+            BINARY,
+        ),
+        (
+            "Latin-1 fallback",
+            InspectorBuilder::latin1_fallback,
+            false,
+            include_bytes!("../testdata/text_Latin-1.txt"),
+            UTF_8,
+            LATIN_1,
+        ),
+    ];
+
+    #[test]
+    fn test_toggles() {
+        for &(name, _, _, fixture, default_type, toggled_type) in TOGGLES {
+            // The toggles of the detection that is not compiled in have no effect
+            let disabled = (name == "magic numbers" && !cfg!(feature = "magic-documents"))
+                || (name.starts_with("UTF-16") && !cfg!(feature = "utf16"))
+                || (name == "Latin-1 fallback" && !cfg!(feature = "legacy-encodings"));
+            if disabled {
+                continue;
+            }
+            assert_eq!(default_type, Inspector::default().inspect(fixture));
+
+            for &(other, toggle, default, _, _, _) in TOGGLES {
+                let inspector = toggle(Inspector::new(), !default).build().unwrap();
+                let expected = if name == other {
+                    toggled_type
+                } else {
+                    default_type
+                };
+                assert_eq!(
+                    expected,
+                    inspector.inspect(fixture),
+                    "fixture for '{}' with '{}' set to {}",
+                    name,
+                    other,
+                    !default
+                );
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "utf16")]
+    fn test_utf16_without_bom() {
+        let inspector = Inspector::new()
+            .detect_utf16_without_bom(true)
+            .build()
+            .unwrap();
+        assert_eq!(
+            UTF_16BE,
+            inspector.inspect(include_bytes!("../testdata/text_UTF-16BE.txt"))
+        );
+        assert_eq!(
+            Reason::Utf16Pattern,
+            inspector
+                .inspect_detailed(include_bytes!("../testdata/text_UTF-16LE.txt"))
+                .reason
+        );
+
+        assert_eq!(
+            BINARY,
+            inspector.inspect(include_bytes!("../testdata/test.png"))
+        );
+        assert_eq!(BINARY, inspector.inspect(b"a\x00\x00\x00b\x00c\x00"));
+        assert_eq!(BINARY, inspector.inspect(b"a\x00"));
+        assert_eq!(UTF_8, inspector.inspect(b"Hello"));
+    }
+
+    #[test]
+    #[cfg(all(feature = "utf16", feature = "utf32"))]
+    fn test_utf16_bom_corroboration() {
+        let inspector = Inspector::default();
+        let trusting = Inspector::new()
+            .corroborate_utf16_bom(false)
+            .build()
+            .unwrap();
+
+        // A JPEG file with a UTF-16LE byte order mark in front of it
+        let blob = include_bytes!("../testdata/test_utf16_bom.jpg");
+        let report = inspector.inspect_detailed(blob);
+        assert_eq!(
+            (BINARY, Reason::NullBytes, 0),
+            (report.content_type, report.reason, report.bom_length)
+        );
+        assert_eq!(BINARY, crate::inspect_const(blob));
+        assert_eq!(UTF_16LE, trusting.inspect(blob));
+
+        // Genuine UTF-16 documents, also with more than the checked code units
+        let document = include_bytes!("../testdata/text_UTF-16LE-BOM.txt");
+        let report = inspector.inspect_detailed(document);
+        assert_eq!(
+            (UTF_16LE, Reason::ByteOrderMark, 2),
+            (report.content_type, report.reason, report.bom_length)
+        );
+        let mut long = document.to_vec();
+        long.extend(document[2..].repeat(20));
+        assert_eq!(UTF_16LE, inspector.inspect(&long));
+        assert_eq!(UTF_16LE, crate::inspect_const(&long));
+        let big_endian = include_bytes!("../testdata/text_UTF-16BE-BOM.txt");
+        assert_eq!(UTF_16BE, inspector.inspect(big_endian));
+
+        // Tiny files are not checked
+        assert_eq!(UTF_16LE, inspector.inspect(b"\xFF\xFEa\x00"));
+        assert_eq!(UTF_16BE, inspector.inspect(b"\xFE\xFF"));
+        let controls = [&b"\xFF\xFE"[..], &[0x01, 0x00].repeat(8)].concat();
+        assert_eq!(UTF_16LE, inspector.inspect(&controls));
+        let controls = [&b"\xFF\xFE"[..], &[0x01, 0x00].repeat(9)].concat();
+        assert_eq!(BINARY, inspector.inspect(&controls));
+
+        // One implausible code unit in 32 is accepted (4 of 128), and a surrogate pair is
+        // plausible, as is a high surrogate at the end of the checked code units
+        let mut units = vec![0x0061_u16; 128];
+        units[10] = 0xE000;
+        units[30] = 0x007F;
+        units[50] = 0x0001;
+        units[70] = 0xD83D;
+        units[71] = 0xDE00;
+        units[127] = 0xD83D;
+        let text: Vec<u8> = units.iter().flat_map(|unit| unit.to_be_bytes()).collect();
+        let buffer = [&b"\xFE\xFF"[..], &text].concat();
+        assert_eq!(UTF_16BE, inspector.inspect(&buffer));
+        units[90] = 0xFFFF;
+        units[100] = 0xDC00;
+        units[110] = 0x0000;
+        let text: Vec<u8> = units.iter().flat_map(|unit| unit.to_be_bytes()).collect();
+        let buffer = [&b"\xFE\xFF"[..], &text].concat();
+        assert_eq!(BINARY, inspector.inspect(&buffer));
+        assert_eq!(UTF_16BE, trusting.inspect(&buffer));
+
+        // A UTF-32LE byte order mark is not affected
+        let utf32 = include_bytes!("../testdata/text_UTF-32LE-BOM.txt");
+        assert_eq!(UTF_32LE, inspector.inspect(utf32));
+    }
+
+    #[test]
+    fn test_control_characters() {
+        let inspector = Inspector::new()
+            .detect_control_characters(true)
+            .build()
+            .unwrap();
+        assert_eq!(
+            UTF_8,
+            inspector.inspect(b"\x1B[1mbold\x1B[0m\r\n\tindented\x0C")
+        );
+        assert_eq!(
+            UTF_8,
+            inspector.inspect(b"\x01 one control character in 40 bytes")
+        );
+        assert_eq!(BINARY, inspector.inspect(b"\x01\x02 two in ten"));
+
+        let report = inspector.inspect_detailed(b"\x01\x02 two in ten");
+        assert_eq!(Reason::ControlCharacters, report.reason);
+        assert_eq!(2, report.control_characters);
+    }
+
+    #[test]
+    #[cfg(feature = "legacy-encodings")]
+    fn test_latin1_fallback() {
+        let inspector = Inspector::new().latin1_fallback(true).build().unwrap();
+        assert_eq!(UTF_8, inspector.inspect("Grüße".as_bytes()));
+
+        let report = inspector.inspect_detailed(b"Gr\xFC\xDFe");
+        assert_eq!(LATIN_1, report.content_type);
+        assert_eq!(Reason::InvalidUtf8, report.reason);
+        assert_eq!(Some(2), report.invalid_utf8_offset);
+
+        // Truncated multi-byte sequences at the end of the window are fine
+        assert_eq!(UTF_8, inspector.inspect(b"Gr\xC3"));
+        let mut buffer = vec![b'a'; 1023];
+        buffer.extend_from_slice("ü".as_bytes());
+        assert_eq!(UTF_8, inspector.inspect(&buffer));
+    }
+
+    #[test]
+    fn test_empty_buffer_result() {
+        let inspector = Inspector::default();
+        assert_eq!(UTF_8, inspector.inspect(b""));
+        assert_eq!(Some(UTF_8), inspector.try_inspect(b""));
+        assert_eq!(Reason::EmptyBuffer, inspector.inspect_detailed(b"").reason);
+
+        let inspector = Inspector::new()
+            .empty_buffer_result(EmptyResult::Binary)
+            .build()
+            .unwrap();
+        assert_eq!(BINARY, inspector.inspect(b""));
+        assert_eq!(Some(BINARY), inspector.try_inspect(b""));
+
+        let inspector = Inspector::new()
+            .empty_buffer_result(EmptyResult::None)
+            .build()
+            .unwrap();
+        assert_eq!(UTF_8, inspector.inspect(b""));
+        assert_eq!(None, inspector.try_inspect(b""));
+        assert_eq!(Some(UTF_8), inspector.try_inspect(b"a"));
+    }
+
+    #[test]
+    #[cfg(feature = "utf16")]
+    fn test_bom_only_buffer_is_not_empty() {
+        for &result in &[EmptyResult::Utf8, EmptyResult::Binary, EmptyResult::None] {
+            let inspector = Inspector::new()
+                .empty_buffer_result(result)
+                .build()
+                .unwrap();
+            assert_eq!(Some(UTF_8_BOM), inspector.try_inspect(b"\xEF\xBB\xBF"));
+            assert_eq!(Some(UTF_16LE), inspector.try_inspect(b"\xFF\xFE"));
+            assert_eq!(
+                Reason::ByteOrderMark,
+                inspector.inspect_detailed(b"\xFE\xFF").reason
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "utf32")]
+    fn test_normalize_bom() {
+        let buffer = include_bytes!("../testdata/text_UTF-8-BOM.txt");
+
+        let report = Inspector::default().inspect_detailed(buffer);
+        assert_eq!(UTF_8_BOM, report.content_type);
+        assert_eq!(3, report.bom_length);
+
+        let inspector = Inspector::new().normalize_bom(true).build().unwrap();
+        let report = inspector.inspect_detailed(buffer);
+        assert_eq!(UTF_8, report.content_type);
+        assert_eq!(Reason::ByteOrderMark, report.reason);
+        assert_eq!(3, report.bom_length);
+
+        let report = inspector.inspect_detailed(include_bytes!("../testdata/text_UTF-8.txt"));
+        assert_eq!(UTF_8, report.content_type);
+        assert_eq!(0, report.bom_length);
+
+        let report =
+            inspector.inspect_detailed(include_bytes!("../testdata/text_UTF-32LE-BOM.txt"));
+        assert_eq!(UTF_32LE, report.content_type);
+        assert_eq!(4, report.bom_length);
+    }
+
+    #[test]
+    fn test_byte_classifier() {
+        let buffer = b"text\x00with\x00NULL\x00bytes";
+        assert_eq!(BINARY, Inspector::default().inspect(buffer));
+
+        let inspector = Inspector::new()
+            .byte_classifier(|byte| match byte {
+                0x00 => ByteClass::Text,
+                _ => ByteClass::of(byte),
+            })
+            .build()
+            .unwrap();
+        let report = inspector.inspect_detailed(buffer);
+        assert_eq!(UTF_8, report.content_type);
+        assert_eq!(0, report.null_bytes);
+
+        let inspector = Inspector::new()
+            .byte_classifier(|byte| match byte {
+                0x01 => ByteClass::Text,
+                0x7F => ByteClass::Control,
+                _ => ByteClass::of(byte),
+            })
+            .detect_control_characters(true)
+            .build()
+            .unwrap();
+        assert_eq!(UTF_8, inspector.inspect(b"\x01frame\x01\x01frame\x01"));
+        let report = inspector.inspect_detailed(b"\x7Fdel\x7F\x7Fdel\x7F");
+        assert_eq!(BINARY, report.content_type);
+        assert_eq!(Reason::ControlCharacters, report.reason);
+        assert_eq!(4, report.control_characters);
+    }
+
+    #[test]
+    fn test_byte_classifier_null_offset() {
+        let inspector = Inspector::new()
+            .byte_classifier(|byte| match byte {
+                b'x' => ByteClass::Null,
+                _ => ByteClass::of(byte),
+            })
+            .max_null_bytes(1)
+            .build()
+            .unwrap();
+        let report = inspector.inspect_detailed(b"abcxdefx");
+        assert_eq!(BINARY, report.content_type);
+        assert_eq!(2, report.null_bytes);
+        assert_eq!(Some(3), report.first_null_offset);
+    }
+
+    #[test]
+    fn test_default_byte_class() {
+        assert_eq!(ByteClass::Null, ByteClass::of(0x00));
+        assert_eq!(ByteClass::Control, ByteClass::of(0x01));
+        assert_eq!(ByteClass::Control, ByteClass::of(0x0B));
+        assert_eq!(ByteClass::Text, ByteClass::of(b'\n'));
+        assert_eq!(ByteClass::Text, ByteClass::of(0x1B));
+        assert_eq!(ByteClass::Text, ByteClass::of(0x7F));
+        assert_eq!(ByteClass::Text, ByteClass::of(0xFF));
+    }
+
+    #[test]
+    fn test_allow_control_chars() {
+        let buffer = b"Subject: \x0Bheader\x0B\x0Bfolding";
+
+        let inspector = Inspector::new()
+            .detect_control_characters(true)
+            .build()
+            .unwrap();
+        assert_eq!(BINARY, inspector.inspect(buffer));
+
+        let inspector = Inspector::new()
+            .detect_control_characters(true)
+            .allow_control_chars(&[0x0B])
+            .build()
+            .unwrap();
+        assert_eq!(UTF_8, inspector.inspect(buffer));
+        assert_eq!(0, inspector.inspect_detailed(buffer).control_characters);
+
+        let inspector = Inspector::new()
+            .detect_control_characters(true)
+            .allow_control_chars(&[0x0B])
+            .deny_control_chars(&[0x0B])
+            .build()
+            .unwrap();
+        assert_eq!(BINARY, inspector.inspect(buffer));
+    }
+
+    #[test]
+    fn test_deny_control_chars() {
+        let buffer = b"\x1B[1m\x1B[31mred\x1B[0m";
+        let inspector = Inspector::new()
+            .detect_control_characters(true)
+            .build()
+            .unwrap();
+        assert_eq!(UTF_8, inspector.inspect(buffer));
+
+        let inspector = Inspector::new()
+            .detect_control_characters(true)
+            .deny_control_chars(&[0x1B])
+            .build()
+            .unwrap();
+        assert_eq!(BINARY, inspector.inspect(buffer));
+    }
+
+    #[test]
+    fn test_invalid_control_chars() {
+        let result = Inspector::new().allow_control_chars(&[0x0B, 0x20]).build();
+        assert_eq!(
+            Some(ConfigError::InvalidControlCharacter(0x20)),
+            result.err()
+        );
+
+        let result = Inspector::new().deny_control_chars(&[0x00, 0x7F]).build();
+        let error = result.unwrap_err();
+        assert_eq!(ConfigError::InvalidControlCharacter(0x00), error);
+        assert_eq!(
+            "0x00 is not a control character (expected a byte from 0x01 to 0x1F)",
+            error.to_string()
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "utf16")]
+    fn test_detect_bom() {
+        let inspector = Inspector::new().detect_bom(false).build().unwrap();
+
+        let report =
+            inspector.inspect_detailed(include_bytes!("../testdata/text_UTF-16LE-BOM.txt"));
+        assert_eq!(BINARY, report.content_type);
+        assert_eq!(Reason::NullBytes, report.reason);
+        assert_eq!(0, report.bom_length);
+
+        assert_eq!(
+            UTF_8,
+            inspector.inspect(include_bytes!("../testdata/text_UTF-8-BOM.txt"))
+        );
+        assert_eq!(BINARY, inspector.inspect(b"\xFF\xFE\x00\x01\x02"));
+        assert_eq!(UTF_8, inspector.inspect(b"\xFF\xFE payload"));
+        assert_eq!(UTF_16LE, Inspector::default().inspect(b"\xFF\xFE payload"));
+    }
+
+    #[test]
+    fn test_max_control_ratio() {
+        let buffer = b"\x01 one control character in 40 bytes";
+        let inspector = Inspector::new()
+            .detect_control_characters(true)
+            .max_control_ratio(0.0)
+            .build()
+            .unwrap();
+        assert_eq!(BINARY, inspector.inspect(buffer));
+        assert_eq!(UTF_8, inspector.inspect(b"no control characters"));
+
+        let inspector = Inspector::new()
+            .detect_control_characters(true)
+            .max_control_ratio(0.5)
+            .build()
+            .unwrap();
+        assert_eq!(UTF_8, inspector.inspect(b"\x01\x02ab"));
+        assert_eq!(BINARY, inspector.inspect(b"\x01\x02\x03a"));
+    }
+
+    /// Compares the presets with the recorded outputs of the actual tools, see
+    /// `testdata/record_tool_outputs.py`.
+    #[test]
+    #[cfg(all(feature = "utf16", feature = "utf32", feature = "legacy-encodings"))]
+    fn test_presets() {
+        let git = Inspector::new().preset(Preset::Git).build().unwrap();
+        let grep = Inspector::new().preset(Preset::Grep).build().unwrap();
+        let file = Inspector::new().preset(Preset::File).build().unwrap();
+
+        let testdata = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata");
+        let outputs = include_str!("../testdata/tool_outputs.tsv");
+
+        for line in outputs.lines().filter(|line| !line.starts_with('#')) {
+            let columns: Vec<&str> = line.split('\t').collect();
+            let (name, git_output, grep_output, file_output) =
+                (columns[0], columns[1], columns[2], columns[3]);
+            let buffer = fs::read(testdata.join(name)).unwrap();
+
+            let git_type = git.inspect(&buffer);
+            assert_eq!(
+                git_output == "binary",
+                git_type.is_binary(),
+                "git: {}",
+                name
+            );
+
+            let grep_type = grep.inspect(&buffer);
+            assert_eq!(
+                grep_output == "binary",
+                grep_type.is_binary(),
+                "grep: {}",
+                name
+            );
+
+            let file_type = match file_output {
+                "us-ascii" | "utf-8" if buffer.starts_with(b"\xEF\xBB\xBF") => UTF_8_BOM,
+                "us-ascii" | "utf-8" => UTF_8,
+                "iso-8859-1" => LATIN_1,
+                "utf-16le" => UTF_16LE,
+                "utf-16be" => UTF_16BE,
+                "utf-32le" => UTF_32LE,
+                "utf-32be" => UTF_32BE,
+                "binary" => BINARY,
+                other => panic!("unexpected output of file(1): {}", other),
+            };
+            assert_eq!(file_type, file.inspect(&buffer), "file: {}", name);
+        }
+    }
+
+    /// Compares `mime_encoding` (with the `File` preset and the ASCII detection) with the
+    /// recorded outputs of `file --mime-encoding`.
+    #[test]
+    #[cfg(all(feature = "utf16", feature = "utf32", feature = "legacy-encodings"))]
+    fn test_mime_encoding() {
+        let file = Inspector::new()
+            .preset(Preset::File)
+            .detect_ascii(true)
+            .build()
+            .unwrap();
+
+        // The intentional differences
+        let differences = [
+            // The Latin-1 fallback does not check for the C1 control characters
+            ("text_Latin-1.txt", "unknown-8bit"),
+        ];
+
+        let testdata = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata");
+        let outputs = include_str!("../testdata/tool_outputs.tsv");
+        for line in outputs.lines().filter(|line| !line.starts_with('#')) {
+            let columns: Vec<&str> = line.split('\t').collect();
+            let (name, file_output) = (columns[0], columns[3]);
+            let buffer = fs::read(testdata.join(name)).unwrap();
+
+            let expected = differences
+                .iter()
+                .find(|&&(difference, _)| difference == name)
+                .map_or(file_output, |&(_, label)| label);
+            assert_eq!(
+                expected,
+                file.inspect(&buffer).mime_encoding(),
+                "file: {}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_preset_resets_settings() {
+        let inspector = Inspector::new()
+            .max_null_bytes(10)
+            .allow_control_chars(&[0x20])
+            .preset(Preset::Git)
+            .build()
+            .unwrap();
+        assert_eq!(BINARY, inspector.inspect(b"a\x00b"));
+
+        let inspector = Inspector::new()
+            .preset(Preset::Git)
+            .max_null_bytes(10)
+            .build()
+            .unwrap();
+        assert_eq!(UTF_8, inspector.inspect(b"a\x00b"));
+    }
+
+    #[derive(Debug)]
+    struct MyFormat(ContentType);
+
+    impl Heuristic for MyFormat {
+        fn examine(&self, buffer: &[u8], _: &mut Partial) -> Option<ContentType> {
+            if buffer.starts_with(b"MYFMT") || buffer.starts_with(b"%PDF") {
+                Some(self.0)
+            } else {
+                None
+            }
+        }
+
+        fn reason(&self) -> Reason {
+            Reason::Heuristic("MYFMT")
+        }
+
+        fn fingerprint(&self, mut state: &mut dyn Hasher) {
+            self.0.hash(&mut state);
+        }
+    }
+
+    fn with_my_format(position: Position, content_type: ContentType) -> Inspector {
+        Inspector::new()
+            .with_heuristic(position, Box::new(MyFormat(content_type)))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_custom_heuristic() {
+        let inspector = with_my_format(Position::BeforeFallback, BINARY);
+        let report = inspector.inspect_detailed(b"MYFMT data");
+        assert_eq!(BINARY, report.content_type);
+        assert_eq!(Reason::Heuristic("MYFMT"), report.reason);
+        assert_eq!(UTF_8, inspector.inspect(b"other data"));
+    }
+
+    #[test]
+    fn test_custom_heuristic_position() {
+        let buffer = b"MYFMT\x00data";
+        let reason = |position| {
+            with_my_format(position, BINARY)
+                .inspect_detailed(buffer)
+                .reason
+        };
+        assert_eq!(Reason::Heuristic("MYFMT"), reason(Position::First));
+        assert_eq!(Reason::Heuristic("MYFMT"), reason(Position::BeforeNullScan));
+        assert_eq!(Reason::NullBytes, reason(Position::AfterNullScan));
+        assert_eq!(Reason::NullBytes, reason(Position::BeforeFallback));
+
+        // A heuristic that claims PDF files to be text only wins before the magic numbers
+        if cfg!(feature = "magic-numbers") {
+            let buffer = include_bytes!("../testdata/test.pdf");
+            let inspect = |position| with_my_format(position, UTF_8).inspect(buffer);
+            assert_eq!(UTF_8, inspect(Position::First));
+            assert_eq!(BINARY, inspect(Position::BeforeNullScan));
+            assert_eq!(BINARY, inspect(Position::BeforeFallback));
+        }
+
+        let buffer = b"\xEF\xBB\xBFMYFMT";
+        let inspect = |position| with_my_format(position, BINARY).inspect(buffer);
+        assert_eq!(UTF_8_BOM, inspect(Position::BeforeNullScan));
+    }
+
+    #[test]
+    fn test_custom_heuristic_partial() {
+        #[derive(Debug)]
+        struct ManyNulls;
+
+        impl Heuristic for ManyNulls {
+            fn examine(&self, _: &[u8], partial: &mut Partial) -> Option<ContentType> {
+                if partial.report().null_bytes * 2 > partial.window().len() {
+                    Some(BINARY)
+                } else {
+                    None
+                }
+            }
+        }
+
+        let inspector = Inspector::new()
+            .max_null_bytes(usize::MAX)
+            .with_heuristic(Position::AfterNullScan, Box::new(ManyNulls))
+            .build()
+            .unwrap();
+        assert_eq!(UTF_8, inspector.inspect(b"a\x00b\x00c"));
+        let report = inspector.inspect_detailed(b"a\x00\x00\x00c");
+        assert_eq!(BINARY, report.content_type);
+        assert!(match report.reason {
+            Reason::Heuristic(name) => name.ends_with("ManyNulls"),
+            _ => false,
+        });
+    }
+
+    #[test]
+    fn test_magic_numbers() {
+        let inspector = Inspector::new()
+            .magic_number(0, b"MYFMT")
+            .magic_number(0, b"MY")
+            .magic_number(0, b"OTHER")
+            .magic_number(4, b"SIG")
+            .build()
+            .unwrap();
+
+        let format = |buffer: &[u8]| inspector.inspect_detailed(buffer).binary_format;
+        assert_eq!(Some(BinaryFormat::Custom(0)), format(b"MYFMT data"));
+        assert_eq!(Some(BinaryFormat::Custom(1)), format(b"MYFORMAT"));
+        assert_eq!(Some(BinaryFormat::Custom(2)), format(b"OTHER"));
+        assert_eq!(Some(BinaryFormat::Custom(3)), format(b"....SIG"));
+        assert_eq!(Some(BinaryFormat::Custom(1)), format(b"MY..SIG"));
+        if cfg!(feature = "magic-numbers") {
+            assert_eq!(Some(BinaryFormat::Pdf), format(b"%PDF-1.4"));
+        }
+        assert_eq!(None, format(b"M"));
+        assert_eq!(None, format(b"...SIG"));
+
+        assert_eq!(BINARY, inspector.inspect(b"OTHER"));
+        assert_eq!(UTF_8, inspector.inspect(b"OTHEr"));
+
+        let result = Inspector::new().magic_number(0, b"").build();
+        assert_eq!(Some(ConfigError::EmptyMagicNumber), result.err());
+    }
+
+    #[test]
+    fn test_many_magic_numbers() {
+        let signatures: Vec<String> = (0..40).map(|i| format!("MAGIC{:02}", i)).collect();
+        let inspector = signatures
+            .iter()
+            .fold(Inspector::new(), |builder, signature| {
+                builder.magic_number(0, signature.as_bytes())
+            })
+            .build()
+            .unwrap();
+
+        for (i, signature) in signatures.iter().enumerate() {
+            assert_eq!(
+                Some(BinaryFormat::Custom(i)),
+                inspector
+                    .inspect_detailed(signature.as_bytes())
+                    .binary_format
+            );
+        }
+        assert_eq!(UTF_8, inspector.inspect(b"MAGIC40"));
+    }
+
+    #[test]
+    fn test_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Inspector>();
+        assert_send_sync::<InspectorBuilder>();
+    }
+
+    #[test]
+    #[cfg(feature = "utf16")]
+    fn test_shared_between_threads() {
+        let inspector = Inspector::new()
+            .magic_number(0, b"MYFMT")
+            .detect_control_characters(true)
+            .build()
+            .unwrap();
+        let fixtures: &[(&[u8], ContentType)] = &[
+            (include_bytes!("../testdata/text_UTF-8.txt"), UTF_8),
+            (
+                include_bytes!("../testdata/text_UTF-16LE-BOM.txt"),
+                UTF_16LE,
+            ),
+            (include_bytes!("../testdata/test.png"), BINARY),
+            (
+                include_bytes!("../testdata/test_control_characters.bin"),
+                BINARY,
+            ),
+            (b"MYFMT", BINARY),
+        ];
+
+        thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for _ in 0..100 {
+                        for &(fixture, expected) in fixtures {
+                            assert_eq!(expected, inspector.inspect(fixture));
+                        }
+                    }
+                });
+            }
+        });
+
+        let shared = Arc::new(inspector);
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let inspector = Arc::clone(&shared);
+                thread::spawn(move || inspector.inspect(b"MYFMT"))
+            })
+            .collect();
+        for handle in handles {
+            assert_eq!(BINARY, handle.join().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_byte_classifier_table() {
+        let inspector = Inspector::new()
+            .byte_classifier(|byte| {
+                if byte >= 0x80 {
+                    ByteClass::Null
+                } else {
+                    ByteClass::Text
+                }
+            })
+            .build()
+            .unwrap();
+        assert_eq!(UTF_8, inspector.inspect(b"\x00\x7F"));
+        assert_eq!(BINARY, inspector.inspect(b"\x80"));
+        assert_eq!(BINARY, inspector.inspect(b"\xFF"));
+    }
+
+    #[test]
+    fn test_strictness() {
+        let buffer = include_bytes!("../testdata/text_invalid_UTF-8.txt");
+        let report = |strictness| {
+            let inspector = Inspector::new().strictness(strictness).build().unwrap();
+            let report = inspector.inspect_detailed(buffer);
+            (report.content_type, report.reason)
+        };
+
+        assert_eq!(
+            (UTF_8, Reason::NoBinaryIndicators),
+            report(Strictness::Fast)
+        );
+        assert_eq!(
+            (UTF_8_BOM, Reason::ByteOrderMark),
+            report(Strictness::Standard)
+        );
+        assert_eq!(
+            (BINARY, Reason::InvalidEncoding),
+            report(Strictness::Strict)
+        );
+        assert_eq!(
+            (BINARY, Reason::InvalidEncoding),
+            report(Strictness::Paranoid)
+        );
+
+        let strict = Inspector::new()
+            .strictness(Strictness::Strict)
+            .build()
+            .unwrap();
+        assert_eq!(
+            Some(900),
+            strict.inspect_detailed(buffer).invalid_utf8_offset
+        );
+        assert_eq!(
+            Some(897),
+            strict.inspect_detailed(&buffer[3..]).invalid_utf8_offset
+        );
+        assert_eq!(
+            Reason::InvalidUtf8,
+            strict.inspect_detailed(&buffer[3..]).reason
+        );
+    }
+
+    #[test]
+    fn test_strictness_fixtures() {
+        let fixtures: &[&[u8]] = &[
+            include_bytes!("../testdata/text_UTF-8.txt"),
+            include_bytes!("../testdata/text_UTF-8-BOM.txt"),
+            include_bytes!("../testdata/text_UTF-16LE-BOM.txt"),
+            include_bytes!("../testdata/text_UTF-16BE-BOM.txt"),
+            include_bytes!("../testdata/text_UTF-32LE-BOM.txt"),
+            include_bytes!("../testdata/text_UTF-32BE-BOM.txt"),
+            include_bytes!("../testdata/test.png"),
+        ];
+        let standard = Inspector::default();
+        for &strictness in &[
+            Strictness::Standard,
+            Strictness::Strict,
+            Strictness::Paranoid,
+        ] {
+            let inspector = Inspector::new().strictness(strictness).build().unwrap();
+            for fixture in fixtures {
+                assert_eq!(standard.inspect(fixture), inspector.inspect(fixture));
+            }
+        }
+
+        let fast = Inspector::new()
+            .strictness(Strictness::Fast)
+            .build()
+            .unwrap();
+        assert_eq!(UTF_8, fast.inspect(fixtures[1]));
+        assert_eq!(BINARY, fast.inspect(fixtures[2]));
+        assert_eq!(BINARY, fast.inspect(fixtures[6]));
+    }
+
+    #[test]
+    #[cfg(all(feature = "utf16", feature = "utf32"))]
+    fn test_strictness_window_and_buffer() {
+        let strict = Inspector::new()
+            .strictness(Strictness::Strict)
+            .build()
+            .unwrap();
+        let paranoid = Inspector::new()
+            .strictness(Strictness::Paranoid)
+            .build()
+            .unwrap();
+
+        // Incomplete sequences at the end
+        for buffer in &[
+            &b"caf\xC3"[..],
+            b"\xEF\xBB\xBFcaf\xC3",
+            b"\xFF\xFEa\x00\x3D",
+            b"\xFF\xFEa\x00\x3D\xD8",
+        ] {
+            assert_ne!(BINARY, strict.inspect(buffer));
+            assert_eq!(BINARY, paranoid.inspect(buffer));
+        }
+
+        // Invalid content after the scanned window
+        let mut buffer = vec![b'a'; 2048];
+        buffer.push(0xFF);
+        assert_eq!(UTF_8, strict.inspect(&buffer));
+        assert_eq!(BINARY, paranoid.inspect(&buffer));
+        buffer.pop();
+        buffer.push(0x00);
+        assert_eq!(UTF_8, strict.inspect(&buffer));
+        assert_eq!(Reason::NullBytes, paranoid.inspect_detailed(&buffer).reason);
+
+        // Unpaired surrogates, invalid code points and U+0000
+        assert_eq!(BINARY, strict.inspect(b"\xFF\xFE\x00\xDCa\x00"));
+        assert_eq!(BINARY, strict.inspect(b"\xFE\xFF\xD8\x3Da\x00"));
+        assert_eq!(BINARY, strict.inspect(b"\xFF\xFE\x00\x00\x00\x00\x11\x00"));
+        assert_eq!(UTF_16LE, strict.inspect(b"\xFF\xFE\x3D\xD8\x96\xDC"));
+        assert_eq!(UTF_16LE, strict.inspect(b"\xFF\xFEa\x00\x00\x00"));
+        assert_eq!(BINARY, paranoid.inspect(b"\xFF\xFEa\x00\x00\x00"));
+        assert_eq!(BINARY, paranoid.inspect(b"\xEF\xBB\xBFa\x00"));
+    }
+
+    #[test]
+    fn test_min_printable_ratio() {
+        let inspector = Inspector::new().min_printable_ratio(0.75).build().unwrap();
+        let report = inspector.inspect_detailed(b"text\x01\x02");
+        assert_eq!(BINARY, report.content_type);
+        assert_eq!(Reason::PrintableRatio, report.reason);
+        assert_eq!(4, report.printable_bytes);
+        assert_eq!(2, report.control_characters);
+
+        // Exactly at the threshold
+        assert_eq!(UTF_8, inspector.inspect(b"abc\x01"));
+        assert_eq!(UTF_8, inspector.inspect(b"a\tb\x01"));
+        assert_eq!(UTF_8, inspector.inspect("\u{1F302}ab\x01\x02".as_bytes()));
+        assert_eq!(BINARY, inspector.inspect(b"ab\x01\x02"));
+
+        // Empty windows
+        assert_eq!(UTF_8, inspector.inspect(b""));
+        let empty_window = Inspector::new()
+            .min_printable_ratio(1.0)
+            .max_scan_size(0)
+            .build()
+            .unwrap();
+        assert_eq!(UTF_8, empty_window.inspect(b"\x01\x02"));
+
+        // The ratio is not checked if NULL bytes or control characters already decide
+        let report = inspector.inspect_detailed(b"\x00\x01\x02");
+        assert_eq!(Reason::NullBytes, report.reason);
+        let inspector = Inspector::new()
+            .min_printable_ratio(0.5)
+            .detect_control_characters(true)
+            .build()
+            .unwrap();
+        let report = inspector.inspect_detailed(b"text\x01\x02");
+        assert_eq!(Reason::ControlCharacters, report.reason);
+    }
+
+    #[test]
+    #[cfg(feature = "utf16")]
+    fn test_min_printable_ratio_bom() {
+        let inspector = Inspector::new().min_printable_ratio(0.75).build().unwrap();
+        assert_eq!(UTF_8_BOM, inspector.inspect(b"\xEF\xBB\xBFa\x01"));
+        assert_eq!(
+            UTF_16LE,
+            inspector.inspect(include_bytes!("../testdata/text_UTF-16LE-BOM.txt"))
+        );
+
+        // Without BOM detection, the bytes of a UTF-8 BOM are exempt from the count
+        let inspector = Inspector::new()
+            .min_printable_ratio(0.75)
+            .detect_bom(false)
+            .build()
+            .unwrap();
+        let report = inspector.inspect_detailed(b"\xEF\xBB\xBFab\x01\x02");
+        assert_eq!(BINARY, report.content_type);
+        assert_eq!(2, report.printable_bytes);
+        assert_eq!(UTF_8, inspector.inspect(b"\xEF\xBB\xBFabc\x01"));
+        assert_eq!(UTF_8, inspector.inspect(b"\xEF\xBB\xBF"));
+    }
+
+    #[test]
+    fn test_min_printable_ratio_with_classifier() {
+        let inspector = Inspector::new()
+            .min_printable_ratio(1.0)
+            .byte_classifier(|byte| match byte {
+                0x7F => ByteClass::Control,
+                _ => ByteClass::of(byte),
+            })
+            .allow_control_chars(&[0x01])
+            .build()
+            .unwrap();
+        assert_eq!(BINARY, inspector.inspect(b"text\x7F"));
+        assert_eq!(BINARY, inspector.inspect(b"text\x01"));
+        assert_eq!(UTF_8, inspector.inspect(b"text\t\r\n"));
+
+        let inspector = Inspector::new()
+            .min_printable_ratio(1.0)
+            .allow_control_chars(&[0x01])
+            .build()
+            .unwrap();
+        assert_eq!(UTF_8, inspector.inspect(b"text\x01"));
+    }
+
+    #[test]
+    #[cfg(feature = "utf16")]
+    fn test_allowed_types() {
+        let inspector = Inspector::new()
+            .allowed_types(&[UTF_8, BINARY])
+            .build()
+            .unwrap();
+
+        let report =
+            inspector.inspect_detailed(include_bytes!("../testdata/text_UTF-16LE-BOM.txt"));
+        assert_eq!(BINARY, report.content_type);
+        assert_eq!(UTF_16LE, report.detected_type);
+        assert_eq!(Reason::ByteOrderMark, report.reason);
+
+        let report = inspector.inspect_detailed(include_bytes!("../testdata/text_UTF-8.txt"));
+        assert_eq!(UTF_8, report.content_type);
+        assert_eq!(UTF_8, report.detected_type);
+
+        // UTF-8 with a BOM is a different type
+        assert_eq!(
+            BINARY,
+            inspector.inspect(include_bytes!("../testdata/text_UTF-8-BOM.txt"))
+        );
+        let inspector = Inspector::new()
+            .allowed_types(&[UTF_8, BINARY])
+            .normalize_bom(true)
+            .build()
+            .unwrap();
+        assert_eq!(
+            UTF_8,
+            inspector.inspect(include_bytes!("../testdata/text_UTF-8-BOM.txt"))
+        );
+
+        let inspector = Inspector::new()
+            .allowed_types(&[UTF_8])
+            .fallback_type(UTF_8)
+            .build()
+            .unwrap();
+        let report = inspector.inspect_detailed(include_bytes!("../testdata/test.png"));
+        assert_eq!((UTF_8, BINARY), (report.content_type, report.detected_type));
+        assert_eq!(UTF_8, inspector.inspect(b""));
+    }
+
+    #[test]
+    fn test_allowed_types_fallback() {
+        let result = Inspector::new().allowed_types(&[UTF_8]).build();
+        assert_eq!(Some(ConfigError::FallbackNotAllowed(BINARY)), result.err());
+
+        let result = Inspector::new().allowed_types(&[]).build();
+        assert_eq!(Some(ConfigError::FallbackNotAllowed(BINARY)), result.err());
+
+        let result = Inspector::new()
+            .allowed_types(&[UTF_8, BINARY])
+            .fallback_type(UTF_16LE)
+            .build();
+        assert_eq!(
+            Some(ConfigError::FallbackNotAllowed(UTF_16LE)),
+            result.err()
+        );
+    }
+
+    #[test]
+    fn test_check() {
+        let inspector = Inspector::default();
+        let utf8 = include_bytes!("../testdata/text_UTF-8.txt");
+        assert_eq!(Ok(()), inspector.check(utf8, UTF_8));
+
+        let mismatch = inspector
+            .check(include_bytes!("../testdata/text_UTF-8-BOM.txt"), UTF_8)
+            .unwrap_err();
+        assert_eq!(UTF_8, mismatch.expected);
+        assert_eq!(UTF_8_BOM, mismatch.detected());
+        assert_eq!(Reason::ByteOrderMark, mismatch.reason());
+        assert_eq!(3, mismatch.report.bom_length);
+
+        let mismatch = inspector
+            .check(include_bytes!("../testdata/text_UTF-16LE.txt"), UTF_8)
+            .unwrap_err();
+        assert_eq!(BINARY, mismatch.detected());
+        assert_eq!(Some(1), mismatch.report.first_null_offset);
+        assert_eq!(
+            "expected UTF-8, found binary (52 NULL byte(s), the first at offset 1)",
+            mismatch.to_string()
+        );
+
+        if cfg!(feature = "magic-numbers") {
+            let mismatch = inspector
+                .check(include_bytes!("../testdata/test.png"), UTF_8)
+                .unwrap_err();
+            assert_eq!(
+                "expected UTF-8, found binary (magic number of format Png)",
+                mismatch.to_string()
+            );
+        }
+    }
+
+    #[test]
+    fn test_detect_ascii() {
+        let inspector = Inspector::new().detect_ascii(true).build().unwrap();
+        assert_eq!(ASCII, inspector.inspect(b"simple text\n"));
+        assert_eq!(
+            UTF_8,
+            inspector.inspect(include_bytes!("../testdata/text_UTF-8.txt"))
+        );
+        assert_eq!(BINARY, inspector.inspect(b"text\x00"));
+        assert_eq!(UTF_8_BOM, inspector.inspect(b"\xEF\xBB\xBFtext"));
+        assert_eq!(UTF_8, Inspector::default().inspect(b"simple text\n"));
+    }
+
+    #[test]
+    fn test_check_lenient() {
+        let inspector = Inspector::new().detect_ascii(true).build().unwrap();
+        let ascii = b"simple text\n";
+        assert_eq!(ASCII, inspector.check(ascii, UTF_8).unwrap_err().detected());
+        assert_eq!(Ok(()), inspector.check_lenient(ascii, UTF_8));
+        assert_eq!(Ok(()), inspector.check_lenient(ascii, ASCII));
+        assert!(inspector.check_lenient(b"\x00", UTF_8).is_err());
+
+        let utf8 = include_bytes!("../testdata/text_UTF-8.txt");
+        assert_eq!(Ok(()), inspector.check_lenient(utf8, UTF_8));
+        assert!(inspector.check_lenient(utf8, ASCII).is_err());
+    }
+
+    #[test]
+    fn test_stage_limits() {
+        // Invalid UTF-8 at offset 2000, NULL byte at offset 3000
+        let buffer = include_bytes!("../testdata/test_stage_limits.txt");
+        let inspector = |null_scan_limit, validation_limit| {
+            Inspector::new()
+                .strictness(Strictness::Strict)
+                .null_scan_limit(null_scan_limit)
+                .validation_limit(validation_limit)
+                .build()
+                .unwrap()
+        };
+
+        let report = inspector(3500, 1024).inspect_detailed(buffer);
+        assert_eq!(
+            (BINARY, Reason::NullBytes),
+            (report.content_type, report.reason)
+        );
+        assert_eq!(Some(3000), report.first_null_offset);
+        assert_eq!(3500, report.scanned_bytes);
+
+        let report = inspector(2500, 3500).inspect_detailed(buffer);
+        assert_eq!(
+            (BINARY, Reason::InvalidUtf8),
+            (report.content_type, report.reason)
+        );
+        assert_eq!(Some(2000), report.invalid_utf8_offset);
+        assert_eq!(2500, report.scanned_bytes);
+        assert_eq!(3500, report.validated_bytes);
+
+        let report = inspector(2500, 1024).inspect_detailed(buffer);
+        assert_eq!(UTF_8, report.content_type);
+        assert_eq!(2500, report.scanned_bytes);
+        assert_eq!(1024, report.validated_bytes);
+    }
+
+    #[test]
+    #[cfg(all(feature = "utf16", feature = "legacy-encodings"))]
+    fn test_stage_limits_default() {
+        let buffer = include_bytes!("../testdata/test_stage_limits.txt");
+        let report = Inspector::new()
+            .max_scan_size(3500)
+            .latin1_fallback(true)
+            .detect_utf16_without_bom(true)
+            .build()
+            .unwrap()
+            .inspect_detailed(buffer);
+        assert_eq!(Reason::NullBytes, report.reason);
+        assert_eq!(3500, report.scanned_bytes);
+        assert_eq!(3500, report.sniffed_bytes);
+
+        let report = Inspector::new()
+            .max_scan_size(3500)
+            .null_scan_limit(2500)
+            .latin1_fallback(true)
+            .detect_utf16_without_bom(true)
+            .sniff_limit(16)
+            .build()
+            .unwrap()
+            .inspect_detailed(buffer);
+        assert_eq!(
+            (LATIN_1, Reason::InvalidUtf8),
+            (report.content_type, report.reason)
+        );
+        assert_eq!(
+            (2500, 3500, 16),
+            (
+                report.scanned_bytes,
+                report.validated_bytes,
+                report.sniffed_bytes
+            )
+        );
+
+        let report = Inspector::new()
+            .scan_all(true)
+            .null_scan_limit(2500)
+            .build()
+            .unwrap()
+            .inspect_detailed(buffer);
+        assert_eq!(UTF_8, report.content_type);
+        assert_eq!(2500, report.scanned_bytes);
+    }
+
+    /// The work of an inspection is bounded by the scanned window, whatever the size of the
+    /// buffer. The bytes that are read are counted by the accessor of the stages, so a stage that
+    /// reads more than it records in `bytes_examined` fails as well.
+    #[test]
+    fn test_bytes_examined() {
+        let longest_magic = MAGIC_NUMBERS
+            .iter()
+            .flat_map(|magic| magic.signature)
+            .map(|&(offset, bytes)| offset + bytes.len())
+            .max()
+            .unwrap_or(0);
+        let mut buffer = vec![b'a'; 100 << 20];
+        let (report, read) = Inspector::default().detect_counted(&buffer);
+        assert!(read <= MAX_SCAN_SIZE + longest_magic);
+        assert_eq!(MAX_SCAN_SIZE, read);
+        assert_eq!(read, report.bytes_examined);
+
+        let bounded = [
+            Inspector::default(),
+            Inspector::new().preset(Preset::Git).build().unwrap(),
+            Inspector::new().preset(Preset::Grep).build().unwrap(),
+            Inspector::new().preset(Preset::File).build().unwrap(),
+            Inspector::new()
+                .strictness(Strictness::Strict)
+                .latin1_fallback(true)
+                .detect_utf16_without_bom(true)
+                .detect_control_characters(true)
+                .min_printable_ratio(0.5)
+                .detect_ascii(true)
+                .magic_number(5000, b"MAGIC")
+                .build()
+                .unwrap(),
+        ];
+        let prefixes: &[&[u8]] = &[b"", b"\xEF\xBB\xBF", b"\xFF\xFE", b"%PDF", b"regf", b"\x00"];
+        for prefix in prefixes {
+            buffer[..prefix.len()].copy_from_slice(prefix);
+            for inspector in &bounded {
+                let (report, read) = inspector.detect_counted(&buffer);
+                let limit = inspector.read_limit().unwrap();
+                assert!(read <= report.bytes_examined, "{} {:?}", read, report);
+                assert!(report.bytes_examined <= limit, "{:?}", report);
+                assert!(report.bytes_examined >= report.scanned_bytes);
+            }
+        }
+
+        let paranoid = Inspector::new()
+            .strictness(Strictness::Paranoid)
+            .build()
+            .unwrap();
+        assert_eq!(None, paranoid.read_limit());
+        let (report, read) = paranoid.detect_counted(&buffer);
+        assert_eq!((buffer.len(), buffer.len()), (report.bytes_examined, read));
+
+        // A custom heuristic gets the whole buffer
+        let custom = Inspector::new()
+            .with_heuristic(Position::First, Box::new(MyFormat(BINARY)))
+            .build()
+            .unwrap();
+        assert_eq!(buffer.len(), custom.detect_counted(&buffer).1);
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        /// Hashed by its type name, and with the same `Debug` representation as `MyFormat`
+        struct Unsure;
+
+        impl fmt::Debug for Unsure {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("MyFormat(BINARY)")
+            }
+        }
+
+        impl Heuristic for Unsure {
+            fn examine(&self, _: &[u8], _: &mut Partial) -> Option<ContentType> {
+                None
+            }
+        }
+
+        let fingerprint = Inspector::default().fingerprint();
+        assert_eq!(fingerprint, Inspector::default().fingerprint());
+        assert_eq!(fingerprint, Inspector::new().build().unwrap().fingerprint());
+
+        let builders = vec![
+            Inspector::new().max_scan_size(2048),
+            Inspector::new().scan_all(true),
+            Inspector::new().null_scan_limit(1024),
+            Inspector::new().latin1_fallback(true),
+            Inspector::new().max_control_ratio(0.2),
+            Inspector::new().magic_number(0, b"MYFMT"),
+            Inspector::new().magic_number(0, b"MYFMT2"),
+            Inspector::new().byte_classifier(ByteClass::of),
+            Inspector::new().strictness(Strictness::Strict),
+            Inspector::new().with_heuristic(Position::First, Box::new(MyFormat(BINARY))),
+            Inspector::new().with_heuristic(Position::BeforeFallback, Box::new(MyFormat(BINARY))),
+            Inspector::new().with_heuristic(Position::BeforeFallback, Box::new(MyFormat(UTF_8))),
+            Inspector::new().with_heuristic(Position::BeforeFallback, Box::new(Unsure)),
+        ];
+        let mut fingerprints: Vec<u64> = builders
+            .iter()
+            .map(|builder| builder.build().unwrap().fingerprint())
+            .collect();
+        fingerprints.push(fingerprint);
+        fingerprints.sort();
+        fingerprints.dedup();
+        assert_eq!(builders.len() + 1, fingerprints.len());
+    }
+
+    #[test]
+    fn test_invalid_ratio() {
+        let result = Inspector::new().max_control_ratio(1.5).build();
+        assert_eq!(
+            Some(ConfigError::InvalidRatio("max_control_ratio")),
+            result.err()
+        );
+        let result = Inspector::new().min_printable_ratio(-0.1).build();
+        assert_eq!(
+            Some(ConfigError::InvalidRatio("min_printable_ratio")),
+            result.err()
+        );
+        let result = Inspector::new().min_printable_ratio(f32::NAN).build();
+        assert!(result.is_err());
+        assert!(Inspector::new().max_control_ratio(1.0).build().is_ok());
+    }
+}
@@ -11,7 +11,12 @@
 //! text can legally contain NULL bytes. Conversely, some particular binary formats (like binary
 //! [PGM](https://en.wikipedia.org/wiki/Netpbm_format)) may not contain NULL bytes. Also, for
 //! performance reasons, only the first 1024 bytes are checked for the NULL-byte (if no BOM was
-//! detected).
+//! detected). The size of this window can be changed with an [`Inspector`](struct.Inspector.html).
+//! The two-byte UTF-16 BOMs (`FF FE` and `FE FF`) are only taken into account if the content
+//! after them looks like UTF-16 text, since binary formats can start with them by accident.
+//! A program that only reads the start of a file can read
+//! [`recommended_peek_size`](fn.recommended_peek_size.html) bytes to get the same result as for
+//! the whole file.
 //!
 //! If this library reports a certain type of encoding (say `UTF_16LE`), there is **no guarantee**
 //! that the binary buffer can *actually* be decoded as UTF-16LE.
@@ -25,51 +30,302 @@
 //!
 //! assert!(inspect(b"Hello").is_text());
 //! ```
+//!
+//! # `no_std`
+//! Without the default `std` feature, the crate is `no_std` and only requires `alloc`. The
+//! classification of buffers ([`inspect`](fn.inspect.html), [`Inspector`](struct.Inspector.html)
+//! and the [`StreamingInspector`](struct.StreamingInspector.html)), decoding and validation are
+//! available, but not the functions that work with readers, writers and files.
+//!
+//! # Features of the detection
+//! The detection of UTF-16 (`utf16`), of UTF-32 (`utf32`), the Latin-1 fallback
+//! (`legacy-encodings`) and the built-in magic numbers (`magic-numbers`, which enables the
+//! families `magic-archives`, `magic-documents`, `magic-images` and `magic-windows`) are default
+//! features. Without one of them, the corresponding types of content and binary formats are never
+//! reported (see [`ContentType`](enum.ContentType.html) and
+//! [`BinaryFormat`](enum.BinaryFormat.html)), but all encodings can still be decoded, validated
+//! and converted.
 
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+#[cfg(feature = "memchr")]
 extern crate memchr;
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
+
+/// Emits a `tracing` event at debug level with the `tracing` feature, and nothing without it. The
+/// fields must only be offsets, lengths and types, never (parts of) the content of buffers.
+macro_rules! debug_event {
+    ($($field:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::debug!($($field)*);
+    };
+}
+
+#[cfg(feature = "tokio")]
+mod async_reader;
+#[cfg(feature = "cache")]
+mod cache;
+mod code_points;
+#[cfg(any(feature = "flate2", feature = "zstd"))]
+mod compressed;
+#[cfg(feature = "serde")]
+mod config;
+mod convert;
+mod decode;
+#[cfg(feature = "std")]
+mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "grep")]
+mod grep;
+mod heuristic;
+mod inspector;
+mod lines;
+#[cfg(feature = "infer")]
+mod mime;
+#[cfg(feature = "mmap")]
+mod mmap;
+mod newlines;
+#[cfg(any(not(feature = "std"), test))]
+mod once;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod prior;
+#[cfg(feature = "std")]
+mod reader;
+mod report;
+#[cfg(feature = "std")]
+mod sampling;
+mod search;
+mod simd;
+mod streaming;
+#[cfg(feature = "std")]
+mod summary;
+#[cfg(test)]
+mod test_util;
+mod transcode;
+mod units;
+mod validate;
+#[cfg(feature = "std")]
+mod vectored;
+#[cfg(feature = "walk")]
+mod walk;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "watch")]
+mod watch;
+#[cfg(feature = "utf16")]
+mod wide;
 
-use memchr::memchr;
-use std::cmp::min;
-use std::fmt;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::io::{self, Write};
+#[cfg(feature = "std")]
+use std::sync::OnceLock;
 
-const MAX_SCAN_SIZE: usize = 1024;
+#[cfg(not(feature = "std"))]
+use once::OnceLock;
 
-/// The type of encoding that was detected (for "text" data) or `BINARY` for "binary" data.
+#[cfg(feature = "tokio")]
+pub use async_reader::{inspect_async_reader, InspectingAsyncReader};
+#[cfg(feature = "cache")]
+pub use cache::InspectionCache;
+pub use code_points::{code_points, CodePointError, CodePoints};
+#[cfg(any(feature = "flate2", feature = "zstd"))]
+pub use compressed::{inspect_compressed, inspect_compressed_file, CompressedInspection};
+#[cfg(feature = "serde")]
+pub use config::{InspectorConfig, MagicNumberConfig};
+pub use convert::{convert, swap_endianness_in_place, BomPolicy, ConvertError, EndianSwapError};
+#[cfg(feature = "std")]
+pub use decode::decode_file;
+pub use decode::{
+    decode, decode_lossy, decode_lossy_counted, decode_lossy_with, decode_with, inspect_and_decode,
+    strip_bom_in_place, strip_bom_in_place_str, DecodeError, DecodeErrorKind, DecodedText,
+    LossyDecode,
+};
+#[cfg(feature = "std")]
+pub use error::Error;
+#[cfg(feature = "grep")]
+pub use grep::{search_path, search_plan, SearchPlan};
+pub use heuristic::{ByteClass, Heuristic, Partial, Position};
+pub use inspector::{ConfigError, EmptyResult, Inspector, InspectorBuilder, Preset, Strictness};
+pub use lines::{first_line, first_line_limited, lines, Lines};
+#[cfg(feature = "infer")]
+pub use mime::{inspect_mime, Detector, MimeGuess};
+#[cfg(feature = "mmap")]
+pub use mmap::inspect_file_mmap;
+pub use newlines::NewlinePolicy;
+#[cfg(feature = "rayon")]
+pub use parallel::inspect_many;
+pub use prior::{inspect_with_prior, Reconciled, Verdict};
+#[cfg(feature = "std")]
+pub use reader::{
+    contains_binary, copy_and_inspect, inspect_buf_read, inspect_file, inspect_reader,
+    inspect_reader_keep, inspect_reader_with_buf, inspect_seek, BomStrippingReader,
+    InspectingReader, InspectingWriter,
+};
+pub use report::{InspectionReport, Mismatch, Reason};
+#[cfg(feature = "std")]
+pub use sampling::inspect_file_sampled;
+#[cfg(feature = "futures")]
+pub use streaming::inspect_stream;
+pub use streaming::StreamingInspector;
+#[cfg(feature = "std")]
+pub use summary::{Summary, TypeCount};
+#[cfg(feature = "std")]
+pub use transcode::{transcode_to_utf8, transcode_to_utf8_with, TranscodeSummary, Utf8Reader};
+pub use transcode::{BinaryPolicy, StreamingDecoder};
+pub use validate::{validate, ValidationError, ValidationReason};
+#[cfg(feature = "std")]
+pub use vectored::inspect_vectored;
+#[cfg(feature = "walk")]
+pub use walk::{walk_and_inspect, walk_and_inspect_with, WalkError};
+#[cfg(feature = "watch")]
+pub use watch::{InspectionWatcher, WatchEvent};
+#[cfg(feature = "utf16")]
+pub use wide::inspect_utf16_units;
+
+/// The number of bytes at the start of a buffer that are searched for NULL bytes (and examined
+/// by the other bounded heuristics) by default. See
+/// [`InspectorBuilder::max_scan_size`](struct.InspectorBuilder.html#method.max_scan_size).
+pub const MAX_SCAN_SIZE: usize = 1024;
+
+/// The length of the longest byte order mark that is detected (the four bytes of UTF-32).
+pub const MAX_BOM_LENGTH: usize = {
+    let mut length = 0;
+    let mut i = 0;
+    while i < BYTE_ORDER_MARKS.len() {
+        if BYTE_ORDER_MARKS[i].0.len() > length {
+            length = BYTE_ORDER_MARKS[i].0.len();
+        }
+        i += 1;
+    }
+    length
+};
+
+/// The end (offset and length) of the built-in magic number that extends furthest into a buffer,
+/// or 0 without the `magic-numbers` feature.
+pub const MAX_MAGIC_NUMBER_END: usize = {
+    let mut end = 0;
+    let mut i = 0;
+    while i < MAGIC_NUMBERS.len() {
+        let signature = MAGIC_NUMBERS[i].signature;
+        let mut j = 0;
+        while j < signature.len() {
+            let (offset, bytes) = signature[j];
+            if offset + bytes.len() > end {
+                end = offset + bytes.len();
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    end
+};
+
+/// The version of the detection heuristics, see [`detection_version`](fn.detection_version.html).
+/// This has to be increased whenever the classification of any buffer changes, which is checked
+/// by the golden corpus test (`testdata/golden_corpus.tsv`).
+const DETECTION_VERSION: u32 = 4;
+
+/// The type of encoding that was detected (for "text" data) or `BINARY` for "binary" data. New
+/// encodings can be added without a major version, so a `match` needs a wildcard arm.
 #[allow(non_camel_case_types)]
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
 pub enum ContentType {
     /// "binary" data
+    #[cfg_attr(feature = "serde", serde(rename = "binary"))]
     BINARY,
 
     /// UTF-8 encoded "text" data
+    #[cfg_attr(feature = "serde", serde(rename = "UTF-8"))]
     UTF_8,
 
     /// UTF-8 encoded "text" data with a byte order mark.
+    #[cfg_attr(feature = "serde", serde(rename = "UTF-8-BOM"))]
     UTF_8_BOM,
 
-    /// UTF-16 encoded "text" data (little endian)
+    /// UTF-16 encoded "text" data (little endian). This is only detected with the `utf16`
+    /// feature.
+    #[cfg_attr(feature = "serde", serde(rename = "UTF-16LE"))]
     UTF_16LE,
 
-    /// UTF-16 encoded "text" data (big endian)
+    /// UTF-16 encoded "text" data (big endian). This is only detected with the `utf16` feature.
+    #[cfg_attr(feature = "serde", serde(rename = "UTF-16BE"))]
     UTF_16BE,
 
-    /// UTF-32 encoded "text" data (little endian)
+    /// UTF-32 encoded "text" data (little endian). This is only detected with the `utf32`
+    /// feature.
+    #[cfg_attr(feature = "serde", serde(rename = "UTF-32LE"))]
     UTF_32LE,
 
-    /// UTF-32 encoded "text" data (big endian)
+    /// UTF-32 encoded "text" data (big endian). This is only detected with the `utf32` feature.
+    #[cfg_attr(feature = "serde", serde(rename = "UTF-32BE"))]
     UTF_32BE,
+
+    /// ISO-8859-1 (Latin-1) encoded "text" data. This is only reported if the Latin-1 fallback of
+    /// an [`Inspector`](struct.Inspector.html) is enabled, with the `legacy-encodings` feature.
+    #[cfg_attr(feature = "serde", serde(rename = "Latin-1"))]
+    LATIN_1,
+
+    /// ASCII encoded "text" data, a subset of `UTF_8`. This is only reported if the ASCII detection
+    /// of an [`Inspector`](struct.Inspector.html) is enabled.
+    #[cfg_attr(feature = "serde", serde(rename = "ASCII"))]
+    ASCII,
 }
 
 impl ContentType {
     /// Returns `true`, if the `ContentType` is `BINARY`.
-    pub fn is_binary(self) -> bool {
-        self == ContentType::BINARY
+    pub const fn is_binary(self) -> bool {
+        matches!(self, ContentType::BINARY)
     }
 
     /// Returns `true`, if the `ContentType` is __not__ `BINARY`.
-    pub fn is_text(self) -> bool {
+    pub const fn is_text(self) -> bool {
         !self.is_binary()
     }
+
+    /// The label of the encoding like in the output of `file --mime-encoding`: `"binary"`,
+    /// `"us-ascii"`, `"utf-8"` (also with a byte order mark), `"utf-16le"`, `"utf-16be"`,
+    /// `"utf-32le"` or `"utf-32be"`.
+    ///
+    /// To tell `"us-ascii"` from `"utf-8"`, the ASCII detection of the
+    /// [`Inspector`](struct.Inspector.html) has to be enabled. `LATIN_1` is `"unknown-8bit"`:
+    /// the Latin-1 fallback accepts all text that is not valid UTF-8, while `file` only reports
+    /// `"iso-8859-1"` for text without the C1 control characters (`0x80` to `0x9F`).
+    ///
+    /// ```
+    /// use content_inspector::{ContentType, Inspector, Preset};
+    ///
+    /// // Like file(1)
+    /// let inspector = Inspector::new()
+    ///     .preset(Preset::File)
+    ///     .detect_ascii(true)
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!("us-ascii", inspector.inspect(b"Hello").mime_encoding());
+    /// assert_eq!("utf-8", inspector.inspect("Grüße".as_bytes()).mime_encoding());
+    /// assert_eq!("binary", inspector.inspect(b"\x00\x01").mime_encoding());
+    /// ```
+    pub const fn mime_encoding(self) -> &'static str {
+        use ContentType::*;
+
+        match self {
+            BINARY => "binary",
+            UTF_8 | UTF_8_BOM => "utf-8",
+            UTF_16LE => "utf-16le",
+            UTF_16BE => "utf-16be",
+            UTF_32LE => "utf-32le",
+            UTF_32BE => "utf-32be",
+            LATIN_1 => "unknown-8bit",
+            ASCII => "us-ascii",
+        }
+    }
 }
 
 impl fmt::Display for ContentType {
@@ -84,56 +340,423 @@ impl fmt::Display for ContentType {
             UTF_16BE => "UTF-16BE",
             UTF_32LE => "UTF-32LE",
             UTF_32BE => "UTF-32BE",
+            LATIN_1 => "Latin-1",
+            ASCII => "ASCII",
         };
         write!(f, "{}", name)
     }
 }
 
+const UTF_8_BOM: (&[u8], ContentType) = (&[0xEF, 0xBB, 0xBF], ContentType::UTF_8_BOM);
+const UTF_32BE_BOM: (&[u8], ContentType) = (&[0x00, 0x00, 0xFE, 0xFF], ContentType::UTF_32BE);
+const UTF_32LE_BOM: (&[u8], ContentType) = (&[0xFF, 0xFE, 0x00, 0x00], ContentType::UTF_32LE);
+const UTF_16BE_BOM: (&[u8], ContentType) = (&[0xFE, 0xFF], ContentType::UTF_16BE);
+const UTF_16LE_BOM: (&[u8], ContentType) = (&[0xFF, 0xFE], ContentType::UTF_16LE);
+
 /// Common byte order marks
-/// (see https://en.wikipedia.org/wiki/Byte_order_mark)
-static BYTE_ORDER_MARKS: &[(&[u8], ContentType)] = &[
-    (&[0xEF, 0xBB, 0xBF], ContentType::UTF_8_BOM),
+/// (see https://en.wikipedia.org/wiki/Byte_order_mark), for decoding, converting and stripping
+/// content of a known type, regardless of the features.
+static KNOWN_BYTE_ORDER_MARKS: &[(&[u8], ContentType)] = &[
+    UTF_8_BOM,
     // UTF-32 needs to be checked before UTF-16 (overlapping BOMs)
-    (&[0x00, 0x00, 0xFE, 0xFF], ContentType::UTF_32BE),
-    (&[0xFF, 0xFE, 0x00, 0x00], ContentType::UTF_32LE),
-    (&[0xFE, 0xFF], ContentType::UTF_16BE),
-    (&[0xFF, 0xFE], ContentType::UTF_16LE),
+    UTF_32BE_BOM,
+    UTF_32LE_BOM,
+    UTF_16BE_BOM,
+    UTF_16LE_BOM,
+];
+
+/// The byte order marks that are detected: those of UTF-16 only with the `utf16` feature and
+/// those of UTF-32 only with the `utf32` feature.
+static BYTE_ORDER_MARKS: &[(&[u8], ContentType)] = &[
+    UTF_8_BOM,
+    #[cfg(feature = "utf32")]
+    UTF_32BE_BOM,
+    #[cfg(feature = "utf32")]
+    UTF_32LE_BOM,
+    #[cfg(feature = "utf16")]
+    UTF_16BE_BOM,
+    #[cfg(feature = "utf16")]
+    UTF_16LE_BOM,
+];
+
+/// Binary file formats that can be recognized by their magic number. The built-in formats are
+/// only recognized with the feature of their family (all of them with the default
+/// `magic-numbers` feature). Formats are added along with their magic numbers, so a `match`
+/// needs a wildcard arm.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum BinaryFormat {
+    /// PDF document (detected with the `magic-documents` feature)
+    Pdf,
+
+    /// PNG image (`magic-images`)
+    Png,
+
+    /// Windows shell link (`.lnk` shortcut, `magic-windows`)
+    WindowsShortcut,
+
+    /// Windows registry hive (`magic-windows`)
+    WindowsRegistryHive,
+
+    /// Microsoft cabinet archive (`.cab`, `magic-archives`)
+    Cabinet,
+
+    /// Windows prefetch file (`.pf`, compressed or not, `magic-windows`)
+    WindowsPrefetch,
+
+    /// gzip-compressed data (`.gz`, `magic-archives`)
+    Gzip,
+
+    /// Zstandard-compressed data (`.zst`, `magic-archives`)
+    Zstd,
+
+    /// A custom magic number of an [`Inspector`](struct.Inspector.html), identified by the order
+    /// in which the magic numbers were added (starting at zero).
+    Custom(usize),
+}
+
+/// A magic number, given as a list of byte sequences that all have to be present at the
+/// respective offsets.
+struct Magic {
+    format: BinaryFormat,
+    signature: &'static [(usize, &'static [u8])],
+}
+
+impl Magic {
+    /// A `const` version (for [`inspect_const`](fn.inspect_const.html)) of checking that all
+    /// parts of the signature are found in the buffer.
+    const fn matches(&self, buffer: &[u8]) -> bool {
+        let mut i = 0;
+        while i < self.signature.len() {
+            let (offset, bytes) = self.signature[i];
+            if !contains_at(buffer, offset, bytes) {
+                return false;
+            }
+            i += 1;
+        }
+        true
+    }
+}
+
+/// Whether the bytes are found at the given offset of the buffer, in a form that can be
+/// evaluated at compile time.
+const fn contains_at(buffer: &[u8], offset: usize, bytes: &[u8]) -> bool {
+    if buffer.len() < offset || buffer.len() - offset < bytes.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < bytes.len() {
+        if buffer[offset + i] != bytes[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// Magic numbers for some filetypes that could otherwise be characterized as text, each one
+/// compiled in with its family (or for the containers of `inspect_compressed`).
+#[cfg(any(feature = "magic-documents", feature = "flate2", feature = "zstd"))]
+const PDF: Magic = Magic {
+    format: BinaryFormat::Pdf,
+    signature: &[(0, b"%PDF")],
+};
+#[cfg(any(feature = "magic-images", feature = "flate2", feature = "zstd"))]
+const PNG: Magic = Magic {
+    format: BinaryFormat::Png,
+    signature: &[(0, b"\x89PNG")],
+};
+// Header size (0x4C) followed by the shell link CLSID
+#[cfg(any(feature = "magic-windows", feature = "flate2", feature = "zstd"))]
+const WINDOWS_SHORTCUT: Magic = Magic {
+    format: BinaryFormat::WindowsShortcut,
+    signature: &[(0, b"\x4C\x00\x00\x00\x01\x14\x02\x00")],
+};
+// "regf" followed by the major format version (always 1) at offset 20
+#[cfg(any(feature = "magic-windows", feature = "flate2", feature = "zstd"))]
+const WINDOWS_REGISTRY_HIVE: Magic = Magic {
+    format: BinaryFormat::WindowsRegistryHive,
+    signature: &[(0, b"regf"), (20, b"\x01\x00\x00\x00")],
+};
+// "MSCF" followed by the first reserved field (always zero)
+#[cfg(any(feature = "magic-archives", feature = "flate2", feature = "zstd"))]
+const CABINET: Magic = Magic {
+    format: BinaryFormat::Cabinet,
+    signature: &[(0, b"MSCF"), (4, b"\x00\x00\x00\x00")],
+};
+// "SCCA" follows the 4-byte format version (up to Windows 7, or uncompressed)
+#[cfg(any(feature = "magic-windows", feature = "flate2", feature = "zstd"))]
+const WINDOWS_PREFETCH: Magic = Magic {
+    format: BinaryFormat::WindowsPrefetch,
+    signature: &[(4, b"SCCA")],
+};
+// The compressed prefetch files of Windows 8 and later ("MAM" and the compression method)
+#[cfg(any(feature = "magic-windows", feature = "flate2", feature = "zstd"))]
+const WINDOWS_PREFETCH_COMPRESSED: Magic = Magic {
+    format: BinaryFormat::WindowsPrefetch,
+    signature: &[(0, b"MAM\x04")],
+};
+#[cfg(any(feature = "magic-archives", feature = "flate2", feature = "zstd"))]
+const GZIP: Magic = Magic {
+    format: BinaryFormat::Gzip,
+    signature: &[(0, b"\x1F\x8B")],
+};
+#[cfg(any(feature = "magic-archives", feature = "flate2", feature = "zstd"))]
+const ZSTD: Magic = Magic {
+    format: BinaryFormat::Zstd,
+    signature: &[(0, b"\x28\xB5\x2F\xFD")],
+};
+
+/// All built-in magic numbers, regardless of the features (for the containers of
+/// `inspect_compressed`).
+#[cfg(any(feature = "flate2", feature = "zstd"))]
+static KNOWN_MAGIC_NUMBERS: &[Magic] = &[
+    PDF,
+    PNG,
+    WINDOWS_SHORTCUT,
+    WINDOWS_REGISTRY_HIVE,
+    CABINET,
+    WINDOWS_PREFETCH,
+    WINDOWS_PREFETCH_COMPRESSED,
+    GZIP,
+    ZSTD,
+];
+
+/// The magic numbers that are detected, those of the enabled families (`magic-documents`,
+/// `magic-images`, `magic-windows` and `magic-archives`, which are all enabled by the
+/// `magic-numbers` feature), so that the other entries are not part of smaller builds.
+static MAGIC_NUMBERS: &[Magic] = &[
+    #[cfg(feature = "magic-documents")]
+    PDF,
+    #[cfg(feature = "magic-images")]
+    PNG,
+    #[cfg(feature = "magic-windows")]
+    WINDOWS_SHORTCUT,
+    #[cfg(feature = "magic-windows")]
+    WINDOWS_REGISTRY_HIVE,
+    #[cfg(feature = "magic-archives")]
+    CABINET,
+    #[cfg(feature = "magic-windows")]
+    WINDOWS_PREFETCH,
+    #[cfg(feature = "magic-windows")]
+    WINDOWS_PREFETCH_COMPRESSED,
+    #[cfg(feature = "magic-archives")]
+    GZIP,
+    #[cfg(feature = "magic-archives")]
+    ZSTD,
 ];
 
-/// Magic numbers for some filetypes that could otherwise be characterized as text.
-static MAGIC_NUMBERS: [&[u8]; 2] = [b"%PDF", b"\x89PNG"];
+/// Try to determine the binary file format of the given buffer by looking at its magic number.
+///
+/// Returns `None` if none of the known magic numbers match, which is always the case without
+/// the `magic-numbers` feature (or for the formats of a family that is not enabled, see
+/// [`BinaryFormat`](enum.BinaryFormat.html)).
+pub fn binary_format(buffer: &[u8]) -> Option<BinaryFormat> {
+    find_magic_number(MAGIC_NUMBERS, buffer)
+}
+
+/// Like [`binary_format`](fn.binary_format.html), but with all built-in magic numbers
+/// regardless of the `magic-numbers` feature.
+#[cfg(any(feature = "flate2", feature = "zstd"))]
+fn known_format(buffer: &[u8]) -> Option<BinaryFormat> {
+    find_magic_number(KNOWN_MAGIC_NUMBERS, buffer)
+}
+
+/// The format of the first matching magic number of the table.
+fn find_magic_number(table: &[Magic], buffer: &[u8]) -> Option<BinaryFormat> {
+    table
+        .iter()
+        .find(|magic| magic.matches(buffer))
+        .map(|magic| magic.format)
+}
+
+/// The byte order mark of the given type of content, as it is recognized by
+/// [`inspect`](fn.inspect.html) (for UTF-16 and UTF-32, with the `utf16` and `utf32` features).
+/// This is empty for types without a byte order mark (`UTF_8`, `ASCII`, `LATIN_1` and `BINARY`).
+///
+/// ```
+/// use content_inspector::{bom_bytes, ContentType};
+///
+/// assert_eq!(b"\xFE\xFF", bom_bytes(ContentType::UTF_16BE));
+/// assert!(bom_bytes(ContentType::UTF_8).is_empty());
+/// ```
+pub fn bom_bytes(content_type: ContentType) -> &'static [u8] {
+    KNOWN_BYTE_ORDER_MARKS
+        .iter()
+        .find(|&&(_, bom_type)| bom_type == content_type)
+        .map_or(&[], |&(bom, _)| bom)
+}
+
+/// Write the byte order mark of the given type of content (see
+/// [`bom_bytes`](fn.bom_bytes.html)) to the writer, for example to write content back in the
+/// encoding that it was read in. Returns the number of bytes that were written.
+#[cfg(feature = "std")]
+pub fn write_bom<W: Write + ?Sized>(
+    writer: &mut W,
+    content_type: ContentType,
+) -> io::Result<usize> {
+    let bom = bom_bytes(content_type);
+    writer.write_all(bom)?;
+    Ok(bom.len())
+}
 
 /// Try to determine the type of content in the given buffer. See the crate documentation for a
 /// usage example and for more details on how this analysis is performed.
 ///
 /// If the buffer is empty, the content type will be reported as `UTF_8`.
+///
+/// This uses the process-wide default `Inspector`, see
+/// [`set_default_inspector`](fn.set_default_inspector.html).
 pub fn inspect(buffer: &[u8]) -> ContentType {
-    use ContentType::*;
+    default_inspector().inspect(buffer)
+}
+
+/// Like [`inspect`](fn.inspect.html), but returns a detailed report on how the type of content
+/// was determined.
+pub fn inspect_detailed(buffer: &[u8]) -> InspectionReport {
+    default_inspector().inspect_detailed(buffer)
+}
+
+/// Like [`inspect`](fn.inspect.html) with the default settings of an
+/// [`Inspector`](struct.Inspector.html), but as a `const fn`, so that embedded data can be
+/// classified at compile time:
+///
+/// ```
+/// use content_inspector::{inspect_const, ContentType};
+///
+/// const TYPE: ContentType = inspect_const(include_bytes!("../testdata/text_UTF-8.txt"));
+/// const _: () = assert!(TYPE.is_text(), "binary data in the templates");
+///
+/// assert_eq!(ContentType::UTF_8, TYPE);
+/// ```
+///
+/// The result is the same as that of `Inspector::default()` for every buffer: byte order marks,
+/// the built-in magic numbers and NULL bytes in the first 1024 bytes are checked (an
+/// [`Inspector`](struct.Inspector.html) that was set with
+/// [`set_default_inspector`](fn.set_default_inspector.html) is not taken into account). At run
+/// time, it is slower than `inspect`, which uses `memchr` to find NULL bytes.
+///
+/// Evaluating it in a `const` context requires Rust 1.83 or later, which allows constants to
+/// read the (`static`) tables of byte order marks and magic numbers.
+pub const fn inspect_const(buffer: &[u8]) -> ContentType {
+    #[cfg(feature = "utf16")]
+    const UTF16_BOM_LIMIT: usize = 2 + 2 * heuristic::UTF16_BOM_CHECKED_UNITS;
 
-    for &(bom, content_type) in BYTE_ORDER_MARKS {
-        if buffer.starts_with(bom) {
+    let mut i = 0;
+    while i < BYTE_ORDER_MARKS.len() {
+        let (bom, content_type) = BYTE_ORDER_MARKS[i];
+        let plausible = match content_type {
+            #[cfg(feature = "utf16")]
+            ContentType::UTF_16LE => heuristic::plausible_utf16(buffer, false, UTF16_BOM_LIMIT),
+            #[cfg(feature = "utf16")]
+            ContentType::UTF_16BE => heuristic::plausible_utf16(buffer, true, UTF16_BOM_LIMIT),
+            _ => true,
+        };
+        if contains_at(buffer, 0, bom) && plausible {
             return content_type;
         }
+        i += 1;
     }
 
-    // Scan the first few bytes for zero-bytes
-    let scan_size = min(buffer.len(), MAX_SCAN_SIZE);
-    let has_zero_bytes = memchr(0x00, &buffer[..scan_size]).is_some();
+    let mut i = 0;
+    while i < MAGIC_NUMBERS.len() {
+        if MAGIC_NUMBERS[i].matches(buffer) {
+            return ContentType::BINARY;
+        }
+        i += 1;
+    }
 
-    if has_zero_bytes {
-        return BINARY;
+    let mut i = 0;
+    while i < buffer.len() && i < MAX_SCAN_SIZE {
+        if buffer[i] == 0x00 {
+            return ContentType::BINARY;
+        }
+        i += 1;
     }
+    ContentType::UTF_8
+}
 
-    if MAGIC_NUMBERS.iter().any(|magic| buffer.starts_with(magic)) {
-        return BINARY;
+/// The number of bytes at the start of a buffer that determine the result of
+/// [`inspect`](fn.inspect.html) with the default settings: any longer prefix of the buffer (and
+/// the whole buffer) is classified the same. This is the largest of
+/// [`MAX_SCAN_SIZE`](constant.MAX_SCAN_SIZE.html),
+/// [`MAX_BOM_LENGTH`](constant.MAX_BOM_LENGTH.html) and
+/// [`MAX_MAGIC_NUMBER_END`](constant.MAX_MAGIC_NUMBER_END.html), so it is the size to peek at
+/// when only the start of a file is read:
+///
+/// ```
+/// use content_inspector::{inspect, recommended_peek_size};
+///
+/// let content = b"text\n".repeat(1000);
+/// let peeked = &content[..recommended_peek_size().min(content.len())];
+/// assert_eq!(inspect(&content), inspect(peeked));
+/// ```
+///
+/// An [`Inspector`](struct.Inspector.html) with other settings (including one that was set with
+/// [`set_default_inspector`](fn.set_default_inspector.html)) may need more bytes, see
+/// [`Inspector::recommended_peek_size`](struct.Inspector.html#method.recommended_peek_size).
+pub const fn recommended_peek_size() -> usize {
+    // (`MAX_MAGIC_NUMBER_END` is zero without magic numbers)
+    const fn max(a: usize, b: usize) -> usize {
+        if a > b {
+            a
+        } else {
+            b
+        }
     }
+    max(max(MAX_SCAN_SIZE, MAX_BOM_LENGTH), MAX_MAGIC_NUMBER_END)
+}
+
+/// The version of the detection heuristics. It changes whenever a new release of this crate
+/// classifies some buffer differently than before, so it can be part of the keys of cached
+/// results. See also [`Inspector::fingerprint`](struct.Inspector.html#method.fingerprint).
+pub fn detection_version() -> u32 {
+    DETECTION_VERSION
+}
+
+static DEFAULT_INSPECTOR: OnceLock<Inspector> = OnceLock::new();
+
+/// Set the `Inspector` that is used by [`inspect`](fn.inspect.html) and
+/// [`inspect_detailed`](fn.inspect_detailed.html) in the whole process. Unless this is called,
+/// they use `Inspector::default()`.
+///
+/// The default inspector can only be set once, before the first call to one of the free
+/// functions. Afterwards, the given `Inspector` is returned as an error.
+///
+/// ```
+/// use content_inspector::{inspect, set_default_inspector, ContentType, Inspector};
+///
+/// let git = Inspector::new().max_scan_size(8000).build().unwrap();
+/// set_default_inspector(git).unwrap();
+///
+/// assert_eq!(ContentType::UTF_8, inspect(b"Hello"));
+/// assert!(set_default_inspector(Inspector::default()).is_err());
+/// ```
+pub fn set_default_inspector(inspector: Inspector) -> Result<(), Inspector> {
+    DEFAULT_INSPECTOR.set(inspector)
+}
 
-    UTF_8
+fn default_inspector() -> &'static Inspector {
+    DEFAULT_INSPECTOR.get_or_init(Inspector::default)
 }
 
 #[cfg(test)]
+#[cfg_attr(
+    not(all(feature = "utf16", feature = "utf32", feature = "legacy-encodings")),
+    allow(dead_code, unused_imports)
+)]
 mod tests {
-    use {inspect, ContentType::*};
+    use crate::{
+        binary_format, inspect, inspect_const, inspect_detailed, BinaryFormat, ContentType::*,
+        Inspector, Preset, Reason, Strictness, BYTE_ORDER_MARKS, MAGIC_NUMBERS,
+    };
+    #[cfg(feature = "std")]
+    use crate::{bom_bytes, write_bom};
+    #[cfg(feature = "magic-numbers")]
+    use std::collections::BTreeMap;
+    use std::env;
+    use std::fs;
+    use std::path::Path;
 
     #[test]
     fn test_empty_buffer_utf_8() {
@@ -159,6 +782,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "utf16")]
     fn test_text_utf16le() {
         assert_eq!(
             UTF_16LE,
@@ -167,6 +791,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "utf16")]
     fn test_text_utf16be() {
         assert_eq!(
             UTF_16BE,
@@ -175,6 +800,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "utf32")]
     fn test_text_utf32le() {
         assert_eq!(
             UTF_32LE,
@@ -183,6 +809,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "utf32")]
     fn test_text_utf32be() {
         assert_eq!(
             UTF_32BE,
@@ -190,6 +817,98 @@ mod tests {
         );
     }
 
+    /// The classification of every strict prefix of every byte order mark, alone and followed by
+    /// an ASCII byte, a NULL byte and 0xFF, by the default inspector and with
+    /// `Strictness::Strict`. A prefix of one byte order mark can be another one (UTF-16LE in
+    /// UTF-32LE), and one more byte can complete it. The table has to cover exactly the buffers
+    /// of `BYTE_ORDER_MARKS`, so that a new or reordered byte order mark changes it.
+    #[test]
+    #[cfg(all(feature = "utf16", feature = "utf32"))]
+    fn test_bom_prefixes() {
+        use crate::{ContentType, Reason::*};
+        use std::collections::BTreeSet;
+        type Verdict = (ContentType, Reason);
+
+        #[rustfmt::skip]
+        let expectations: &[(&[u8], Verdict, Verdict)] = &[
+            // UTF-8
+            (b"\xEF",             (UTF_8, NoBinaryIndicators), (UTF_8, NoBinaryIndicators)),
+            (b"\xEFa",            (UTF_8, NoBinaryIndicators), (BINARY, InvalidUtf8)),
+            (b"\xEF\x00",         (BINARY, NullBytes),         (BINARY, NullBytes)),
+            (b"\xEF\xFF",         (UTF_8, NoBinaryIndicators), (BINARY, InvalidUtf8)),
+            (b"\xEF\xBB",         (UTF_8, NoBinaryIndicators), (UTF_8, NoBinaryIndicators)),
+            (b"\xEF\xBBa",        (UTF_8, NoBinaryIndicators), (BINARY, InvalidUtf8)),
+            (b"\xEF\xBB\x00",     (BINARY, NullBytes),         (BINARY, NullBytes)),
+            (b"\xEF\xBB\xFF",     (UTF_8, NoBinaryIndicators), (BINARY, InvalidUtf8)),
+            // UTF-32BE, which starts with NULL bytes
+            (b"\x00",             (BINARY, NullBytes),         (BINARY, NullBytes)),
+            (b"\x00a",            (BINARY, NullBytes),         (BINARY, NullBytes)),
+            (b"\x00\x00",         (BINARY, NullBytes),         (BINARY, NullBytes)),
+            (b"\x00\xFF",         (BINARY, NullBytes),         (BINARY, NullBytes)),
+            (b"\x00\x00a",        (BINARY, NullBytes),         (BINARY, NullBytes)),
+            (b"\x00\x00\x00",     (BINARY, NullBytes),         (BINARY, NullBytes)),
+            (b"\x00\x00\xFF",     (BINARY, NullBytes),         (BINARY, NullBytes)),
+            (b"\x00\x00\xFE",     (BINARY, NullBytes),         (BINARY, NullBytes)),
+            (b"\x00\x00\xFEa",    (BINARY, NullBytes),         (BINARY, NullBytes)),
+            (b"\x00\x00\xFE\x00", (BINARY, NullBytes),         (BINARY, NullBytes)),
+            (b"\x00\x00\xFE\xFF", (UTF_32BE, ByteOrderMark),   (UTF_32BE, ByteOrderMark)),
+            // UTF-32LE, which starts with the byte order mark of UTF-16LE
+            (b"\xFF",             (UTF_8, NoBinaryIndicators), (BINARY, InvalidUtf8)),
+            (b"\xFFa",            (UTF_8, NoBinaryIndicators), (BINARY, InvalidUtf8)),
+            (b"\xFF\x00",         (BINARY, NullBytes),         (BINARY, NullBytes)),
+            (b"\xFF\xFF",         (UTF_8, NoBinaryIndicators), (BINARY, InvalidUtf8)),
+            (b"\xFF\xFE",         (UTF_16LE, ByteOrderMark),   (UTF_16LE, ByteOrderMark)),
+            (b"\xFF\xFEa",        (UTF_16LE, ByteOrderMark),   (UTF_16LE, ByteOrderMark)),
+            (b"\xFF\xFE\x00",     (UTF_16LE, ByteOrderMark),   (UTF_16LE, ByteOrderMark)),
+            (b"\xFF\xFE\xFF",     (UTF_16LE, ByteOrderMark),   (UTF_16LE, ByteOrderMark)),
+            (b"\xFF\xFE\x00a",    (UTF_16LE, ByteOrderMark),   (UTF_16LE, ByteOrderMark)),
+            (b"\xFF\xFE\x00\x00", (UTF_32LE, ByteOrderMark),   (UTF_32LE, ByteOrderMark)),
+            (b"\xFF\xFE\x00\xFF", (UTF_16LE, ByteOrderMark),   (UTF_16LE, ByteOrderMark)),
+            // UTF-16BE
+            (b"\xFE",             (UTF_8, NoBinaryIndicators), (BINARY, InvalidUtf8)),
+            (b"\xFEa",            (UTF_8, NoBinaryIndicators), (BINARY, InvalidUtf8)),
+            (b"\xFE\x00",         (BINARY, NullBytes),         (BINARY, NullBytes)),
+            (b"\xFE\xFF",         (UTF_16BE, ByteOrderMark),   (UTF_16BE, ByteOrderMark)),
+            // UTF-16LE: all of its prefixes are prefixes of UTF-32LE
+        ];
+
+        let mut buffers = BTreeSet::new();
+        for &(bom, _) in BYTE_ORDER_MARKS {
+            for length in 1..bom.len() {
+                for next in &[&b""[..], b"a", b"\x00", b"\xFF"] {
+                    buffers.insert([&bom[..length], next].concat());
+                }
+            }
+        }
+        let table: BTreeSet<Vec<u8>> = expectations
+            .iter()
+            .map(|(buffer, _, _)| buffer.to_vec())
+            .collect();
+        assert_eq!(buffers, table);
+        assert_eq!(table.len(), expectations.len());
+
+        let strict = Inspector::new()
+            .strictness(crate::Strictness::Strict)
+            .build()
+            .unwrap();
+        for &(buffer, default, strict_result) in expectations {
+            let report = inspect_detailed(buffer);
+            assert_eq!(
+                default,
+                (report.content_type, report.reason),
+                "{:?}",
+                buffer
+            );
+            let report = strict.inspect_detailed(buffer);
+            assert_eq!(
+                strict_result,
+                (report.content_type, report.reason),
+                "{:?}",
+                buffer
+            );
+        }
+    }
+
     #[test]
     fn test_png() {
         assert_eq!(BINARY, inspect(include_bytes!("../testdata/test.png")));
@@ -201,10 +920,181 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "magic-documents")]
     fn test_pdf() {
         assert_eq!(BINARY, inspect(include_bytes!("../testdata/test.pdf")));
     }
 
+    #[test]
+    #[cfg(feature = "magic-windows")]
+    fn test_lnk() {
+        let buffer = include_bytes!("../testdata/test.lnk");
+        assert_eq!(BINARY, inspect(buffer));
+        assert_eq!(Some(BinaryFormat::WindowsShortcut), binary_format(buffer));
+    }
+
+    #[test]
+    #[cfg(feature = "magic-windows")]
+    fn test_registry_hive() {
+        let buffer = include_bytes!("../testdata/test_registry_hive.dat");
+        assert_eq!(BINARY, inspect(buffer));
+        assert_eq!(
+            Some(BinaryFormat::WindowsRegistryHive),
+            binary_format(buffer)
+        );
+    }
+
+    #[test]
+    fn test_registry_hive_requires_version() {
+        let buffer = include_bytes!("../testdata/text_regfile.txt");
+        assert_eq!(UTF_8, inspect(buffer));
+        assert_eq!(None, binary_format(buffer));
+
+        assert_eq!(None, binary_format(b"regf"));
+    }
+
+    #[test]
+    #[cfg(feature = "magic-archives")]
+    fn test_cab() {
+        let buffer = include_bytes!("../testdata/test.cab");
+        assert_eq!(BINARY, inspect(buffer));
+        assert_eq!(Some(BinaryFormat::Cabinet), binary_format(buffer));
+
+        assert_eq!(BINARY, inspect(b"MSCF\x00\x00\x00\x00"));
+        assert_eq!(UTF_8, inspect(b"MSCF"));
+    }
+
+    #[test]
+    fn test_cab_requires_reserved_field() {
+        let buffer = include_bytes!("../testdata/text_cabfile.txt");
+        assert_eq!(UTF_8, inspect(buffer));
+        assert_eq!(None, binary_format(buffer));
+    }
+
+    /// The built-in magic numbers are detected with the features of their families only.
+    #[test]
+    fn test_magic_number_families() {
+        let hive = [&b"regf"[..], &[0; 16], b"\x01\x00\x00\x00"].concat();
+        for &(buffer, format, enabled) in &[
+            (
+                &b"%PDF-1.7\n"[..],
+                BinaryFormat::Pdf,
+                cfg!(feature = "magic-documents"),
+            ),
+            (
+                b"\x89PNG\r\n",
+                BinaryFormat::Png,
+                cfg!(feature = "magic-images"),
+            ),
+            (
+                &hive,
+                BinaryFormat::WindowsRegistryHive,
+                cfg!(feature = "magic-windows"),
+            ),
+            (
+                b"\x11\x00\x00\x00SCCA",
+                BinaryFormat::WindowsPrefetch,
+                cfg!(feature = "magic-windows"),
+            ),
+            (
+                b"MAM\x04",
+                BinaryFormat::WindowsPrefetch,
+                cfg!(feature = "magic-windows"),
+            ),
+            (
+                b"MSCF\x00\x00\x00\x00",
+                BinaryFormat::Cabinet,
+                cfg!(feature = "magic-archives"),
+            ),
+            (
+                b"\x28\xB5\x2F\xFD",
+                BinaryFormat::Zstd,
+                cfg!(feature = "magic-archives"),
+            ),
+        ] {
+            let expected = Some(format).filter(|_| enabled);
+            assert_eq!(expected, binary_format(buffer), "{:?}", format);
+            let reason = inspect_detailed(buffer).reason;
+            assert_eq!(enabled, reason == Reason::MagicNumber, "{:?}", format);
+        }
+    }
+
+    #[test]
+    #[cfg(all(feature = "std", feature = "utf16", feature = "utf32"))]
+    fn test_write_bom() {
+        for &content_type in &[UTF_8_BOM, UTF_16LE, UTF_16BE, UTF_32LE, UTF_32BE] {
+            let mut buffer = vec![];
+            let written = write_bom(&mut buffer, content_type).unwrap();
+            assert_eq!(bom_bytes(content_type).len(), written);
+            assert!(written > 0);
+            assert_eq!(content_type, inspect(&buffer), "{:?}", content_type);
+
+            buffer.extend_from_slice(&bom_bytes(content_type)[..written]);
+            assert_eq!(content_type, inspect(&buffer), "{:?}", content_type);
+        }
+
+        for &content_type in &[UTF_8, ASCII, LATIN_1, BINARY] {
+            let mut buffer = vec![];
+            assert_eq!(0, write_bom(&mut buffer, content_type).unwrap());
+            assert!(buffer.is_empty());
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "magic-archives")]
+    fn test_compressed() {
+        let buffer = include_bytes!("../testdata/text_UTF-8.txt.gz");
+        assert_eq!(BINARY, inspect(buffer));
+        assert_eq!(Some(BinaryFormat::Gzip), binary_format(buffer));
+
+        let buffer = include_bytes!("../testdata/text_UTF-16LE-BOM.txt.zst");
+        assert_eq!(BINARY, inspect(buffer));
+        assert_eq!(Some(BinaryFormat::Zstd), binary_format(buffer));
+
+        assert_eq!(BINARY, inspect(b"\x1F\x8B"));
+        assert_eq!(UTF_8, inspect(b"\x1F"));
+    }
+
+    #[test]
+    #[cfg(feature = "magic-windows")]
+    fn test_prefetch() {
+        let buffer = include_bytes!("../testdata/test.pf");
+        assert_eq!(BINARY, inspect(buffer));
+        assert_eq!(Some(BinaryFormat::WindowsPrefetch), binary_format(buffer));
+
+        assert_eq!(BINARY, inspect(b"\x1E\x1E\x1E\x1ESCCA"));
+        assert_eq!(UTF_8, inspect(b"SCCA"));
+
+        let buffer = include_bytes!("../testdata/test_compressed.pf");
+        assert_eq!(BINARY, inspect(buffer));
+        assert_eq!(Some(BinaryFormat::WindowsPrefetch), binary_format(buffer));
+    }
+
+    #[test]
+    #[cfg(feature = "utf16")]
+    fn test_inspect_detailed() {
+        if cfg!(feature = "magic-numbers") {
+            let report = inspect_detailed(include_bytes!("../testdata/test.png"));
+            assert_eq!(BINARY, report.content_type);
+            assert_eq!(Reason::MagicNumber, report.reason);
+            assert_eq!(Some(BinaryFormat::Png), report.binary_format);
+
+            let report = inspect_detailed(b"%PDF-1.4");
+            assert_eq!(BINARY, report.content_type);
+            assert_eq!(Reason::MagicNumber, report.reason);
+            assert_eq!(Some(BinaryFormat::Pdf), report.binary_format);
+        }
+
+        let report = inspect_detailed(include_bytes!("../testdata/test.jpg"));
+        assert_eq!(BINARY, report.content_type);
+        assert_eq!(Reason::NullBytes, report.reason);
+        assert_eq!(None, report.binary_format);
+
+        let report = inspect_detailed(include_bytes!("../testdata/text_UTF-16LE-BOM.txt"));
+        assert_eq!(UTF_16LE, report.content_type);
+        assert_eq!(Reason::ByteOrderMark, report.reason);
+    }
+
     #[test]
     fn test_is_text() {
         assert!(UTF_8.is_text());
@@ -215,4 +1105,387 @@ mod tests {
     fn test_is_binary() {
         assert!(BINARY.is_binary());
     }
+
+    /// The classification of all fixtures (and a few short buffers) by a range of inspectors, as
+    /// (inspector, buffer) -> "content type, reason". It is recorded with the default features.
+    #[cfg(feature = "magic-numbers")]
+    fn golden_corpus() -> BTreeMap<(String, String), String> {
+        let inspectors = vec![
+            ("default", Inspector::new()),
+            ("git", Inspector::new().preset(Preset::Git)),
+            ("grep", Inspector::new().preset(Preset::Grep)),
+            ("file", Inspector::new().preset(Preset::File)),
+            ("fast", Inspector::new().strictness(Strictness::Fast)),
+            ("strict", Inspector::new().strictness(Strictness::Strict)),
+            (
+                "paranoid",
+                Inspector::new().strictness(Strictness::Paranoid),
+            ),
+            (
+                "all",
+                Inspector::new()
+                    .detect_utf16_without_bom(true)
+                    .detect_control_characters(true)
+                    .min_printable_ratio(0.9)
+                    .latin1_fallback(true)
+                    .detect_ascii(true),
+            ),
+        ];
+
+        let testdata = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata");
+        let mut buffers: Vec<(String, Vec<u8>)> = fs::read_dir(&testdata)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.is_file())
+            .filter(|path| {
+                let extension = path.extension().and_then(|e| e.to_str());
+                !matches!(
+                    extension,
+                    Some("py") | Some("md") | Some("tsv") | Some("snap")
+                )
+            })
+            .map(|path| {
+                let name = path.file_name().unwrap().to_string_lossy().into_owned();
+                (name, fs::read(&path).unwrap())
+            })
+            .collect();
+        for &(name, buffer) in &[
+            ("<empty>", &b""[..]),
+            ("<nul>", b"\x00"),
+            ("<utf-16le bom>", b"\xFF\xFE"),
+            ("<utf-32le bom>", b"\xFF\xFE\x00\x00"),
+            ("<control>", b"\x01\x02\x03"),
+            ("<invalid utf-8>", b"\xC3\x28"),
+            ("<incomplete utf-8>", b"caf\xC3"),
+        ] {
+            buffers.push((name.to_string(), buffer.to_vec()));
+        }
+
+        let mut corpus = BTreeMap::new();
+        for (inspector_name, builder) in inspectors {
+            let inspector = builder.build().unwrap();
+            for (name, buffer) in &buffers {
+                let report = inspector.inspect_detailed(buffer);
+                corpus.insert(
+                    (inspector_name.to_string(), name.clone()),
+                    format!("{}\t{:?}", report.content_type, report.reason),
+                );
+            }
+        }
+        corpus
+    }
+
+    /// Fails if the classification of the golden corpus changes without an increased
+    /// `DETECTION_VERSION`. Run with `UPDATE_GOLDEN_CORPUS=1` to record new results.
+    #[test]
+    #[cfg(all(
+        feature = "magic-numbers",
+        feature = "utf16",
+        feature = "utf32",
+        feature = "legacy-encodings"
+    ))]
+    fn test_golden_corpus() {
+        use crate::DETECTION_VERSION;
+
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata/golden_corpus.tsv");
+        let golden = fs::read_to_string(&path).unwrap_or_default();
+        let mut lines = golden.lines();
+        let golden_version: u32 = lines
+            .next()
+            .and_then(|line| line.strip_prefix("# detection version "))
+            .map_or(0, |version| version.parse().unwrap());
+        let golden: BTreeMap<(String, String), String> = lines
+            .map(|line| {
+                let fields: Vec<&str> = line.splitn(3, '\t').collect();
+                (
+                    (fields[0].to_string(), fields[1].to_string()),
+                    fields[2].to_string(),
+                )
+            })
+            .collect();
+
+        let corpus = golden_corpus();
+        let changed: Vec<_> = golden
+            .iter()
+            .filter(|&(key, result)| corpus.get(key).is_some_and(|current| current != result))
+            .map(|(key, result)| format!("{:?}: {} -> {}", key, result, corpus[key]))
+            .collect();
+        assert!(
+            changed.is_empty() || golden_version < DETECTION_VERSION,
+            "The classification changed, DETECTION_VERSION has to be increased:\n{}",
+            changed.join("\n")
+        );
+
+        if env::var_os("UPDATE_GOLDEN_CORPUS").is_some() {
+            let mut content = format!("# detection version {}\n", DETECTION_VERSION);
+            for ((inspector, buffer), result) in &corpus {
+                content.push_str(&format!("{}\t{}\t{}\n", inspector, buffer, result));
+            }
+            fs::write(&path, content).unwrap();
+        } else {
+            assert!(
+                golden == corpus && golden_version == DETECTION_VERSION,
+                "The golden corpus is outdated, run the tests with UPDATE_GOLDEN_CORPUS=1"
+            );
+        }
+    }
+
+    /// The generated corpus (see `testdata/create_generated_files.py`) against the expected
+    /// classifications of its manifest, by the default inspector and by one that detects UTF-16
+    /// without a byte order mark and falls back to Latin-1.
+    #[test]
+    #[cfg(all(feature = "utf16", feature = "utf32", feature = "legacy-encodings"))]
+    fn test_generated_corpus() {
+        let sniffing = Inspector::new()
+            .detect_utf16_without_bom(true)
+            .latin1_fallback(true)
+            .build()
+            .unwrap();
+
+        let generated = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata/generated");
+        let manifest = fs::read_to_string(generated.join("manifest.tsv")).unwrap();
+        let mut names = vec![];
+        for line in manifest.lines().filter(|line| !line.starts_with('#')) {
+            let fields: Vec<&str> = line.split('\t').collect();
+            let buffer = fs::read(generated.join(fields[0])).unwrap();
+            let default = inspect(&buffer).to_string();
+            assert_eq!(fields[1], default, "{} (default)", fields[0]);
+            if fields[2] != "-" {
+                let sniffed = sniffing.inspect(&buffer).to_string();
+                assert_eq!(fields[2], sniffed, "{} (sniffing)", fields[0]);
+            }
+            names.push(fields[0].to_string());
+        }
+
+        // Every generated file has an expectation
+        let mut files: Vec<String> = fs::read_dir(&generated)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .filter(|name| name != "manifest.tsv")
+            .collect();
+        files.sort();
+        names.sort();
+        assert_eq!(files, names);
+    }
+
+    /// The buffers of the short buffer snapshot: every buffer of up to two bytes, and the buffers
+    /// of three and four bytes over the boundaries of the classes of bytes and the bytes of the
+    /// byte order marks (which covers every prefix of a byte order mark).
+    #[cfg(feature = "magic-numbers")]
+    fn short_buffers() -> Vec<Vec<u8>> {
+        const SAMPLE: [u8; 10] = [0x00, 0x1F, 0x20, 0x7F, 0x80, 0xBB, 0xBF, 0xEF, 0xFE, 0xFF];
+
+        let mut buffers = vec![vec![]];
+        buffers.extend((0..=255).map(|byte| vec![byte]));
+        buffers.extend((0..=0xFFFF_u16).map(|bytes| bytes.to_be_bytes().to_vec()));
+        let mut sample = vec![vec![]];
+        for length in 1..=4 {
+            sample = sample
+                .iter()
+                .flat_map(|prefix| {
+                    SAMPLE
+                        .iter()
+                        .map(move |&byte| [&prefix[..], &[byte]].concat())
+                })
+                .collect();
+            if length >= 3 {
+                buffers.extend(sample.iter().cloned());
+            }
+        }
+        buffers
+    }
+
+    /// The classification of short buffers by `inspect`, where the surprising behaviors live,
+    /// against the snapshot in `testdata/short_buffers.tsv`. Consecutive buffers (in the order of
+    /// `short_buffers`) with the same result share a line, with the first and the last buffer and
+    /// their number. After an intentional change, run with `UPDATE_SHORT_BUFFERS=1`, and review
+    /// the diff of the snapshot before committing it.
+    #[test]
+    #[cfg(all(
+        feature = "magic-numbers",
+        feature = "utf16",
+        feature = "utf32",
+        feature = "legacy-encodings"
+    ))]
+    fn test_short_buffers() {
+        let hex = |buffer: &[u8]| {
+            if buffer.is_empty() {
+                "<empty>".to_string()
+            } else {
+                buffer.iter().map(|byte| format!("{:02X}", byte)).collect()
+            }
+        };
+        let mut snapshot = String::from("# first\tlast\tbuffers\ttype\treason\n");
+        let buffers = short_buffers();
+        let results: Vec<String> = buffers
+            .iter()
+            .map(|buffer| {
+                let report = inspect_detailed(buffer);
+                format!("{}\t{:?}", report.content_type, report.reason)
+            })
+            .collect();
+        let mut start = 0;
+        for index in 0..buffers.len() {
+            let next = index + 1;
+            if next == buffers.len()
+                || buffers[next].len() != buffers[index].len()
+                || results[next] != results[index]
+            {
+                snapshot.push_str(&format!(
+                    "{}\t{}\t{}\t{}\n",
+                    hex(&buffers[start]),
+                    hex(&buffers[index]),
+                    next - start,
+                    results[index]
+                ));
+                start = next;
+            }
+        }
+
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata/short_buffers.tsv");
+        if env::var_os("UPDATE_SHORT_BUFFERS").is_some() {
+            fs::write(&path, snapshot).unwrap();
+        } else {
+            let recorded = fs::read_to_string(&path).unwrap_or_default();
+            let changed: Vec<_> = snapshot
+                .lines()
+                .filter(|line| !recorded.lines().any(|recorded| recorded == *line))
+                .take(20)
+                .collect();
+            assert!(
+                recorded == snapshot,
+                "The classification of short buffers changed, review the diff after running the \
+                 tests with UPDATE_SHORT_BUFFERS=1. New lines:\n{}",
+                changed.join("\n")
+            );
+        }
+    }
+
+    /// Classifying the first `recommended_peek_size` bytes of a buffer gives the same result as
+    /// classifying all of it, for the fixtures, and for the fixtures followed by text, NULL bytes
+    /// or invalid UTF-8.
+    #[test]
+    fn test_recommended_peek_size() {
+        assert_eq!(
+            Some(super::recommended_peek_size()),
+            Inspector::default().recommended_peek_size()
+        );
+        for &size in &[
+            super::MAX_SCAN_SIZE,
+            super::MAX_BOM_LENGTH,
+            super::MAX_MAGIC_NUMBER_END,
+        ] {
+            assert!(super::recommended_peek_size() >= size);
+        }
+
+        let inspectors = [
+            Inspector::default(),
+            Inspector::new().preset(Preset::Git).build().unwrap(),
+            Inspector::new().preset(Preset::Grep).build().unwrap(),
+            Inspector::new().preset(Preset::File).build().unwrap(),
+            Inspector::new()
+                .strictness(Strictness::Fast)
+                .build()
+                .unwrap(),
+            Inspector::new()
+                .strictness(Strictness::Strict)
+                .build()
+                .unwrap(),
+            Inspector::new()
+                .detect_utf16_without_bom(true)
+                .detect_control_characters(true)
+                .min_printable_ratio(0.9)
+                .latin1_fallback(true)
+                .detect_ascii(true)
+                .build()
+                .unwrap(),
+            Inspector::new().max_scan_size(64).build().unwrap(),
+            Inspector::new()
+                .magic_number(3000, b"MAGIC")
+                .build()
+                .unwrap(),
+        ];
+        let testdata = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata");
+        let paths = fs::read_dir(testdata)
+            .unwrap()
+            .map(|entry| entry.unwrap().path());
+        for path in paths.filter(|path| path.is_file()) {
+            let fixture = fs::read(&path).unwrap();
+            let tails: [&[u8]; 4] = [b"", &[b'a'; 4000], &[0x00; 4000], &[0xC3; 4000]];
+            for tail in &tails {
+                let buffer = [&fixture[..], tail].concat();
+                for inspector in &inspectors {
+                    let peek_size = inspector.recommended_peek_size().unwrap();
+                    let peeked = &buffer[..peek_size.min(buffer.len())];
+                    assert_eq!(
+                        inspector.inspect(&buffer),
+                        inspector.inspect(peeked),
+                        "{} (+{} bytes), peek size {}",
+                        path.display(),
+                        tail.len(),
+                        peek_size
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_inspect_const() {
+        let inspector = Inspector::default();
+        let testdata = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata");
+        let paths = fs::read_dir(testdata)
+            .unwrap()
+            .map(|entry| entry.unwrap().path());
+        for path in paths.filter(|path| path.is_file()) {
+            let buffer = fs::read(path).unwrap();
+            assert_eq!(inspector.inspect(&buffer), inspect_const(&buffer));
+        }
+
+        // Random buffers around the size of the scanned window, which start with (parts of) byte
+        // order marks and magic numbers, and contain few NULL bytes
+        let mut prefixes: Vec<Vec<u8>> = BYTE_ORDER_MARKS
+            .iter()
+            .map(|&(bom, _)| bom.to_vec())
+            .collect();
+        for magic in MAGIC_NUMBERS {
+            let mut prefix = vec![];
+            for &(offset, bytes) in magic.signature {
+                prefix.resize(offset.max(prefix.len()), b'a');
+                prefix.extend_from_slice(bytes);
+            }
+            prefixes.push(prefix);
+        }
+        let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        for _ in 0..5000 {
+            let length = match next() % 3 {
+                0 => (next() % 32) as usize,
+                1 => 1000 + (next() % 50) as usize,
+                _ => (next() % 3000) as usize,
+            };
+            let mut buffer: Vec<u8> = (0..length)
+                .map(|_| match next() % 2048 {
+                    0 => 0x00,
+                    _ => (next() >> 32) as u8 | 0x01,
+                })
+                .collect();
+            if next() % 2 == 0 {
+                let prefix = &prefixes[(next() % prefixes.len() as u64) as usize];
+                let take = (next() as usize % (prefix.len() + 1)).min(buffer.len());
+                buffer[..take].copy_from_slice(&prefix[..take]);
+            }
+            assert_eq!(
+                inspector.inspect(&buffer),
+                inspect_const(&buffer),
+                "{:?}",
+                buffer
+            );
+        }
+    }
 }
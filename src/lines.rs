@@ -0,0 +1,341 @@
+use alloc::borrow::Cow;
+
+use crate::decode::{bom, decode_content, decode_strict};
+use crate::search::memchr;
+use crate::units::{u16_unit, u32_unit};
+use crate::{ContentType, DecodeError, NewlinePolicy, MAX_SCAN_SIZE};
+
+/// An iterator over the lines of a buffer, see [`lines`](fn.lines.html).
+#[derive(Clone, Debug)]
+pub struct Lines<'a> {
+    buffer: &'a [u8],
+    position: usize,
+    content_type: ContentType,
+    unicode_separators: bool,
+}
+
+impl<'a> Lines<'a> {
+    /// Also split on the Unicode line separators U+0085 (next line), U+2028 (line separator) and
+    /// U+2029 (paragraph separator) (default: false).
+    pub fn unicode_separators(mut self, enabled: bool) -> Self {
+        self.unicode_separators = enabled;
+        self
+    }
+
+    /// The size of a code unit in bytes.
+    fn width(&self) -> usize {
+        match self.content_type {
+            ContentType::UTF_16LE | ContentType::UTF_16BE => 2,
+            ContentType::UTF_32LE | ContentType::UTF_32BE => 4,
+            _ => 1,
+        }
+    }
+
+    fn unit(&self, i: usize) -> u32 {
+        let b = self.buffer;
+        match self.content_type {
+            ContentType::UTF_16LE => u32::from(u16_unit([b[i], b[i + 1]], false)),
+            ContentType::UTF_16BE => u32::from(u16_unit([b[i], b[i + 1]], true)),
+            ContentType::UTF_32LE => u32_unit([b[i], b[i + 1], b[i + 2], b[i + 3]], false),
+            ContentType::UTF_32BE => u32_unit([b[i], b[i + 1], b[i + 2], b[i + 3]], true),
+            _ => u32::from(b[i]),
+        }
+    }
+
+    /// The length of the Unicode line separator at the given offset, if there is one.
+    fn separator_length(&self, i: usize, unit: u32) -> Option<usize> {
+        match self.content_type {
+            ContentType::LATIN_1 => Some(1).filter(|_| unit == 0x85),
+            _ if self.width() == 1 => [&b"\xC2\x85"[..], b"\xE2\x80\xA8", b"\xE2\x80\xA9"]
+                .iter()
+                .find(|separator| self.buffer[i..].starts_with(separator))
+                .map(|separator| separator.len()),
+            _ => Some(self.width()).filter(|_| [0x85, 0x2028, 0x2029].contains(&unit)),
+        }
+    }
+
+    /// The end of the line that starts at the given offset, and the start of the next line.
+    fn find_end(&self, start: usize) -> (usize, usize) {
+        let width = self.width();
+        let length = self.buffer.len();
+        if width == 1 && !self.unicode_separators {
+            return match memchr(b'\n', &self.buffer[start..]) {
+                Some(i) if i > 0 && self.buffer[start + i - 1] == b'\r' => {
+                    (start + i - 1, start + i + 1)
+                }
+                Some(i) => (start + i, start + i + 1),
+                None => (length, length),
+            };
+        }
+
+        let mut i = start;
+        while i + width <= length {
+            let unit = self.unit(i);
+            if unit == 0x0A {
+                if i > start && self.unit(i - width) == 0x0D {
+                    return (i - width, i + width);
+                }
+                return (i, i + width);
+            }
+            if self.unicode_separators {
+                if let Some(separator_length) = self.separator_length(i, unit) {
+                    return (i, i + separator_length);
+                }
+            }
+            i += width;
+        }
+        (length, length)
+    }
+}
+
+impl<'a> Iterator for Lines<'a> {
+    type Item = Result<Cow<'a, str>, DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.position >= self.buffer.len() {
+            return None;
+        }
+        let start = self.position;
+        let (end, next) = self.find_end(start);
+        self.position = next;
+        Some(decode_strict(
+            &self.buffer[start..end],
+            start,
+            self.content_type,
+            NewlinePolicy::Preserve,
+        ))
+    }
+}
+
+/// Iterate over the lines of the buffer, decoded according to the given type of content (like
+/// with [`decode`](fn.decode.html)). Lines are split on LF and CRLF in the code units of the
+/// encoding, and are only decoded when the iterator gets to them. A byte order mark at the start
+/// is removed, and line terminators are not part of the lines.
+///
+/// A final line without a terminator is yielded as well, but there is no empty line after a
+/// terminator at the end. The offsets of errors refer to the whole buffer.
+///
+/// ```
+/// use content_inspector::{lines, ContentType};
+///
+/// let buffer = b"\xFF\xFEa\x00\r\x00\n\x00b\x00";
+/// let first: Vec<_> = lines(buffer, ContentType::UTF_16LE)
+///     .map(Result::unwrap)
+///     .collect();
+///
+/// assert_eq!(vec!["a", "b"], first);
+/// ```
+pub fn lines(buffer: &[u8], content_type: ContentType) -> Lines<'_> {
+    let content = buffer.strip_prefix(bom(content_type)).unwrap_or(buffer);
+    Lines {
+        buffer,
+        position: buffer.len() - content.len(),
+        content_type,
+        unicode_separators: false,
+    }
+}
+
+/// Decode the first line of the buffer, like [`lines`](fn.lines.html) but lossily (like
+/// [`decode_lossy`](fn.decode_lossy.html)), for example to look for a shebang or an XML
+/// declaration. Only the first line is decoded.
+///
+/// Returns `None` if the buffer is empty (apart from a byte order mark), or if the first line is
+/// longer than the first 1024 bytes of the content (see
+/// [`first_line_limited`](fn.first_line_limited.html)).
+///
+/// ```
+/// use content_inspector::{first_line, ContentType};
+///
+/// let buffer = b"\x00#\x00!\x00/\x00b\x00i\x00n\x00/\x00s\x00h\x00\n\x00e\x00c\x00h\x00o";
+/// assert_eq!("#!/bin/sh", first_line(buffer, ContentType::UTF_16BE).unwrap());
+/// ```
+pub fn first_line(buffer: &[u8], content_type: ContentType) -> Option<Cow<'_, str>> {
+    first_line_limited(buffer, content_type, MAX_SCAN_SIZE)
+}
+
+/// Like [`first_line`](fn.first_line.html), but looks for the end of the first line in the given
+/// number of bytes (not counting a byte order mark), instead of 1024.
+pub fn first_line_limited(
+    buffer: &[u8],
+    content_type: ContentType,
+    limit: usize,
+) -> Option<Cow<'_, str>> {
+    let lines = lines(buffer, content_type);
+    let start = lines.position;
+    if start == buffer.len() {
+        return None;
+    }
+    let limited = Lines {
+        buffer: &buffer[..buffer.len().min(start.saturating_add(limit))],
+        ..lines
+    };
+    let (end, next) = limited.find_end(start);
+    if end == limited.buffer.len() && next == end && end < buffer.len() {
+        // The line continues beyond the limit
+        return None;
+    }
+    Some(decode_content(&buffer[start..end], content_type))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use crate::{
+        first_line, first_line_limited, lines, ContentType, ContentType::*, DecodeError,
+        DecodeErrorKind,
+    };
+
+    fn collect(buffer: &[u8], content_type: ContentType) -> Vec<String> {
+        lines(buffer, content_type)
+            .map(|line| line.unwrap().into_owned())
+            .collect()
+    }
+
+    fn utf16le(text: &str) -> Vec<u8> {
+        text.encode_utf16().flat_map(u16::to_le_bytes).collect()
+    }
+
+    fn utf32be(text: &str) -> Vec<u8> {
+        text.chars()
+            .flat_map(|c| u32::from(c).to_be_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn test_lines_testdata() {
+        let testdata = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata");
+        let reference = fs::read_to_string(testdata.join("text_UTF-8.txt")).unwrap();
+        let expected: Vec<_> = reference.lines().collect();
+        assert_eq!(2, expected.len());
+
+        for &(name, content_type) in &[
+            ("text_UTF-8.txt", UTF_8),
+            ("text_UTF-8-BOM.txt", UTF_8_BOM),
+            ("text_UTF-16LE.txt", UTF_16LE),
+            ("text_UTF-16LE-BOM.txt", UTF_16LE),
+            ("text_UTF-16BE-BOM.txt", UTF_16BE),
+            ("text_UTF-32LE-BOM.txt", UTF_32LE),
+            ("text_UTF-32BE-BOM.txt", UTF_32BE),
+        ] {
+            let buffer = fs::read(testdata.join(name)).unwrap();
+            assert_eq!(expected, collect(&buffer, content_type), "{}", name);
+        }
+
+        // Without a newline at the end
+        let buffer = utf16le(reference.trim_end());
+        assert_eq!(expected, collect(&buffer, UTF_16LE));
+    }
+
+    #[test]
+    fn test_lines_terminators() {
+        assert!(collect(b"", UTF_8).is_empty());
+        assert!(collect(b"\xEF\xBB\xBF", UTF_8_BOM).is_empty());
+        assert_eq!(vec![""], collect(b"\n", UTF_8));
+        assert_eq!(vec!["", "a", "", "b"], collect(b"\r\na\n\nb", UTF_8));
+        assert_eq!(vec!["a\rb", "c\r"], collect(b"a\rb\r\nc\r", ASCII));
+
+        let text = "one\r\ntwo\u{2028}three\u{85}four\n";
+        assert_eq!(
+            vec!["one", "two\u{2028}three\u{85}four"],
+            collect(text.as_bytes(), UTF_8)
+        );
+        assert_eq!(
+            vec!["one", "two\u{2028}three\u{85}four"],
+            collect(&utf32be(text), UTF_32BE)
+        );
+        for (buffer, content_type) in [(text.as_bytes().to_vec(), UTF_8), (utf16le(text), UTF_16LE)]
+        {
+            let split: Vec<_> = lines(&buffer, content_type)
+                .unicode_separators(true)
+                .map(Result::unwrap)
+                .collect();
+            assert_eq!(vec!["one", "two", "three", "four"], split);
+        }
+
+        let split: Vec<_> = lines(b"a\x85b\xE0", LATIN_1)
+            .unicode_separators(true)
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(vec!["a", "b\u{E0}"], split);
+
+        // Bytes that look like terminators, but are part of other code units
+        assert_eq!(
+            vec!["\u{D0A}\u{A0D}"],
+            collect(b"\x0D\x0A\x0A\x0D", UTF_16BE)
+        );
+    }
+
+    #[test]
+    fn test_lines_errors() {
+        let buffer = b"\xFE\xFF\x00a\x00\n\xD8\x3D\x00\n\x00b\x00";
+        let lines: Vec<_> = lines(buffer, UTF_16BE).collect();
+        assert_eq!(3, lines.len());
+        assert_eq!("a", lines[0].as_ref().unwrap());
+        assert_eq!(
+            &Err(DecodeError {
+                offset: 6,
+                kind: DecodeErrorKind::UnpairedSurrogate(0xD83D),
+            }),
+            &lines[1]
+        );
+        assert_eq!(
+            &Err(DecodeError {
+                offset: 12,
+                kind: DecodeErrorKind::IncompleteCodeUnit,
+            }),
+            &lines[2]
+        );
+    }
+
+    #[test]
+    fn test_first_line() {
+        let buffer = [
+            &b"\xFE\xFF"[..],
+            b"\x00<\x00?\x00x\x00m\x00l\x00\r\x00\n\x00<",
+        ]
+        .concat();
+        assert_eq!(Some("<?xml".into()), first_line(&buffer, UTF_16BE));
+
+        let testdata = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata");
+        let reference = fs::read_to_string(testdata.join("text_UTF-8.txt")).unwrap();
+        let buffer = fs::read(testdata.join("text_UTF-32LE-BOM.txt")).unwrap();
+        assert_eq!(
+            reference.lines().next(),
+            first_line(&buffer, UTF_32LE).as_deref()
+        );
+
+        // No newline at all
+        assert_eq!(
+            Some("# vim: set ts=4:".into()),
+            first_line(b"# vim: set ts=4:", UTF_8)
+        );
+        assert_eq!(Some("\u{FFFD}".into()), first_line(b"\x00\xD8", UTF_16LE));
+        assert_eq!(
+            Some("".into()),
+            first_line(b"\xEF\xBB\xBF\r\nsecond", UTF_8_BOM)
+        );
+        assert_eq!(None, first_line(b"", UTF_8));
+        assert_eq!(None, first_line(b"\xFF\xFE\x00\x00", UTF_32LE));
+    }
+
+    #[test]
+    fn test_first_line_limited() {
+        let long = [vec![b'a'; 2000], b"\nb".to_vec()].concat();
+        assert_eq!(None, first_line(&long, UTF_8));
+        assert_eq!(None, first_line(&long[..2000], UTF_8));
+        assert_eq!(2000, first_line_limited(&long, UTF_8, 2001).unwrap().len());
+        assert_eq!(None, first_line_limited(&long, UTF_8, 2000));
+
+        // The limit does not include the byte order mark
+        let buffer = b"\xFF\xFEa\x00\n\x00b\x00";
+        assert_eq!(Some("a".into()), first_line_limited(buffer, UTF_16LE, 4));
+        assert_eq!(None, first_line_limited(buffer, UTF_16LE, 3));
+        assert_eq!(Some("a".into()), first_line_limited(b"a", UTF_8, 1));
+        assert_eq!(
+            Some("a".into()),
+            first_line_limited(b"a", UTF_8, usize::MAX)
+        );
+    }
+}
@@ -0,0 +1,185 @@
+use infer::MatcherType;
+
+use crate::{default_inspector, ContentType, Inspector};
+
+/// The detector that determined the MIME type of a [`MimeGuess`](struct.MimeGuess.html).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Detector {
+    /// The magic numbers of the [`infer`](https://crates.io/crates/infer) crate.
+    Infer,
+
+    /// The detection of text (and binary content) of this crate.
+    ContentInspector,
+}
+
+/// The result of [`inspect_mime`](fn.inspect_mime.html).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MimeGuess {
+    /// The MIME type, like `"image/png"`, `"text/xml"` or `"text/plain"`, or `None` for binary
+    /// content in an unknown format.
+    pub mime_type: Option<&'static str>,
+
+    /// The type of content. This is `BINARY` for all formats that `infer` recognizes, except for
+    /// text markup.
+    pub content_type: ContentType,
+
+    /// The detector that determined the MIME type.
+    pub detector: Detector,
+}
+
+impl Inspector {
+    /// Determine the MIME type of the buffer with the magic numbers of the `infer` crate (with the
+    /// `infer` feature), together with the type of content. The precedence is:
+    ///
+    /// 1. Binary formats that `infer` recognizes (like PNG or ZIP) are `BINARY`, even if the
+    ///    inspector would classify the buffer as text.
+    /// 2. For text markup that `infer` recognizes (HTML, XML and shell scripts), the inspector
+    ///    determines the encoding. If it finds binary content instead, it takes precedence, and
+    ///    the MIME type is unknown.
+    /// 3. Otherwise, text is `text/plain` and binary content has no MIME type.
+    ///
+    /// `infer` has no magic number for SVG, so SVG images with an XML declaration are `text/xml`
+    /// (and text, for example `UTF_8`), and those without one are `text/plain`.
+    pub fn inspect_mime(&self, buffer: &[u8]) -> MimeGuess {
+        let known = infer::get(buffer);
+        if let Some(kind) = known.filter(|kind| kind.matcher_type() != MatcherType::Text) {
+            return MimeGuess {
+                mime_type: Some(kind.mime_type()),
+                content_type: ContentType::BINARY,
+                detector: Detector::Infer,
+            };
+        }
+
+        let content_type = self.inspect(buffer);
+        match known {
+            Some(kind) if content_type.is_text() => MimeGuess {
+                mime_type: Some(kind.mime_type()),
+                content_type,
+                detector: Detector::Infer,
+            },
+            _ => MimeGuess {
+                mime_type: Some("text/plain").filter(|_| content_type.is_text()),
+                content_type,
+                detector: Detector::ContentInspector,
+            },
+        }
+    }
+}
+
+/// Determine the MIME type and the type of content of the buffer with the default inspector. See
+/// [`Inspector::inspect_mime`](struct.Inspector.html#method.inspect_mime) for the precedence of
+/// `infer` and the text detection.
+///
+/// ```
+/// use content_inspector::{inspect_mime, ContentType, Detector};
+///
+/// let guess = inspect_mime(b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR");
+/// assert_eq!(Some("image/png"), guess.mime_type);
+/// assert_eq!(ContentType::BINARY, guess.content_type);
+/// assert_eq!(Detector::Infer, guess.detector);
+///
+/// let guess = inspect_mime("Grüße".as_bytes());
+/// assert_eq!(Some("text/plain"), guess.mime_type);
+/// assert_eq!(ContentType::UTF_8, guess.content_type);
+/// ```
+pub fn inspect_mime(buffer: &[u8]) -> MimeGuess {
+    default_inspector().inspect_mime(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Detector, MimeGuess};
+    use crate::{inspect_mime, ContentType, ContentType::*, Inspector};
+
+    fn guess(
+        mime_type: Option<&'static str>,
+        content_type: ContentType,
+        detector: Detector,
+    ) -> MimeGuess {
+        MimeGuess {
+            mime_type,
+            content_type,
+            detector,
+        }
+    }
+
+    #[test]
+    fn test_fixtures() {
+        let svg = include_bytes!("../testdata/test.svg");
+        assert_eq!(
+            guess(Some("text/xml"), UTF_8, Detector::Infer),
+            inspect_mime(svg)
+        );
+
+        let png = include_bytes!("../testdata/test.png");
+        assert_eq!(
+            guess(Some("image/png"), BINARY, Detector::Infer),
+            inspect_mime(png)
+        );
+
+        let utf16 = include_bytes!("../testdata/text_UTF-16LE-BOM.txt");
+        assert_eq!(
+            guess(Some("text/plain"), UTF_16LE, Detector::ContentInspector),
+            inspect_mime(utf16)
+        );
+
+        // Without a byte order mark, only if UTF-16 is detected without one
+        let utf16 = include_bytes!("../testdata/text_UTF-16LE.txt");
+        assert_eq!(
+            guess(None, BINARY, Detector::ContentInspector),
+            inspect_mime(utf16)
+        );
+        let inspector = Inspector::new()
+            .detect_utf16_without_bom(true)
+            .build()
+            .unwrap();
+        assert_eq!(
+            guess(Some("text/plain"), UTF_16LE, Detector::ContentInspector),
+            inspector.inspect_mime(utf16)
+        );
+    }
+
+    #[test]
+    fn test_precedence() {
+        // A binary format of infer wins, even if the content looks like text
+        let buffer = b"PK\x03\x04text";
+        assert_eq!(UTF_8, crate::inspect(buffer));
+        assert_eq!(
+            guess(Some("application/zip"), BINARY, Detector::Infer),
+            inspect_mime(buffer)
+        );
+
+        // Text markup keeps the MIME type, with the encoding of the inspector
+        let buffer = b"\xEF\xBB\xBF<?xml version=\"1.0\"?><svg/>";
+        assert_eq!(
+            guess(Some("text/xml"), UTF_8_BOM, Detector::Infer),
+            inspect_mime(buffer)
+        );
+        let buffer = b"<html><body>Hello</body></html>";
+        assert_eq!(
+            guess(Some("text/html"), UTF_8, Detector::Infer),
+            inspect_mime(buffer)
+        );
+
+        // Unless the inspector finds binary content
+        let buffer = b"<?xml version=\"1.0\"?>\x00\x00";
+        assert_eq!(
+            guess(None, BINARY, Detector::ContentInspector),
+            inspect_mime(buffer)
+        );
+        let buffer = b"#!/bin/sh\n\x00";
+        assert_eq!(
+            guess(None, BINARY, Detector::ContentInspector),
+            inspect_mime(buffer)
+        );
+
+        assert_eq!(
+            guess(Some("text/plain"), UTF_8, Detector::ContentInspector),
+            inspect_mime(b"")
+        );
+        assert_eq!(
+            guess(None, BINARY, Detector::ContentInspector),
+            inspect_mime(b"\x00\x01")
+        );
+    }
+}
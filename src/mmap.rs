@@ -0,0 +1,134 @@
+use std::convert::TryFrom;
+use std::io;
+use std::path::Path;
+
+use memmap2::MmapOptions;
+
+use crate::reader::open_regular_file;
+use crate::{default_inspector, ContentType, Inspector};
+
+impl Inspector {
+    /// Like [`inspect_file`](#method.inspect_file), but maps the examined part of the file into
+    /// memory instead of reading it.
+    ///
+    /// Empty files are not mapped (this fails on some platforms), and files that have become
+    /// shorter after being mapped are read instead. A file that is truncated *while* it is being
+    /// inspected can still lead to a `SIGBUS` on Unix, as with any memory-mapped file.
+    pub fn inspect_file_mmap<P: AsRef<Path>>(&self, path: P) -> io::Result<ContentType> {
+        let file = open_regular_file(path.as_ref())?;
+        let file_length = file.metadata()?.len();
+        let length = match self.read_limit() {
+            Some(limit) => file_length.min(limit as u64),
+            None => file_length,
+        };
+        if length == 0 {
+            return Ok(self.inspect(b""));
+        }
+        let length = usize::try_from(length)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "file too large to map"))?;
+
+        // SAFETY: the mapping is only read, and it is checked below that the file has not been
+        // truncated in the meantime - the same assumption that any reader of a file makes.
+        let mmap = unsafe { MmapOptions::new().len(length).map(&file)? };
+        if file.metadata()?.len() < length as u64 {
+            drop(mmap);
+            return self.inspect_reader(file);
+        }
+        Ok(self.inspect(&mmap))
+    }
+}
+
+/// Like [`inspect_file`](fn.inspect_file.html), but maps the first 1024 bytes of the file into
+/// memory instead of reading them. See
+/// [`Inspector::inspect_file_mmap`](struct.Inspector.html#method.inspect_file_mmap) for details.
+pub fn inspect_file_mmap<P: AsRef<Path>>(path: P) -> io::Result<ContentType> {
+    default_inspector().inspect_file_mmap(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::io;
+    use std::path::{Path, PathBuf};
+
+    use crate::{inspect_file, inspect_file_mmap, ContentType::*, Inspector, Strictness};
+
+    /// A temporary file that is removed when dropped.
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str, content: &[u8]) -> TempFile {
+            let path = env::temp_dir().join(format!(
+                "content_inspector_mmap_{}_{}",
+                ::std::process::id(),
+                name
+            ));
+            fs::write(&path, content).unwrap();
+            TempFile(path)
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_inspect_file_mmap() {
+        let testdata = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata");
+        let paths = fs::read_dir(testdata)
+            .unwrap()
+            .map(|entry| entry.unwrap().path());
+        for path in paths.filter(|path| path.is_file()) {
+            assert_eq!(
+                inspect_file(&path).unwrap(),
+                inspect_file_mmap(&path).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_inspect_file_mmap_empty() {
+        let file = TempFile::new("empty", b"");
+        assert_eq!(UTF_8, inspect_file_mmap(&file.0).unwrap());
+
+        let paranoid = Inspector::new()
+            .strictness(Strictness::Paranoid)
+            .build()
+            .unwrap();
+        assert_eq!(UTF_8, paranoid.inspect_file_mmap(&file.0).unwrap());
+    }
+
+    #[test]
+    fn test_inspect_file_mmap_window() {
+        let mut content = vec![b'a'; 1024];
+        let file = TempFile::new("text_1024", &content);
+        assert_eq!(UTF_8, inspect_file_mmap(&file.0).unwrap());
+
+        content[1023] = 0x00;
+        let file = TempFile::new("binary_1024", &content);
+        assert_eq!(BINARY, inspect_file_mmap(&file.0).unwrap());
+
+        // Only the scanned window is examined, and the whole file with the paranoid settings
+        content[1023] = b'a';
+        content.push(0x00);
+        let file = TempFile::new("binary_1025", &content);
+        assert_eq!(UTF_8, inspect_file_mmap(&file.0).unwrap());
+        let paranoid = Inspector::new()
+            .strictness(Strictness::Paranoid)
+            .build()
+            .unwrap();
+        assert_eq!(BINARY, paranoid.inspect_file_mmap(&file.0).unwrap());
+    }
+
+    #[test]
+    fn test_inspect_file_mmap_errors() {
+        let error = inspect_file_mmap(env::temp_dir()).unwrap_err();
+        assert_eq!(io::ErrorKind::IsADirectory, error.kind());
+
+        let error = inspect_file_mmap("does-not-exist").unwrap_err();
+        assert_eq!(io::ErrorKind::NotFound, error.kind());
+    }
+}
@@ -0,0 +1,175 @@
+use crate::search::{memchr, memchr2};
+use alloc::string::String;
+
+/// How line endings are written when decoding, see
+/// [`decode_lossy_with`](fn.decode_lossy_with.html).
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum NewlinePolicy {
+    /// Line endings are kept as they are.
+    #[default]
+    Preserve,
+
+    /// CRLF and lone CR line endings are replaced by LF.
+    NormalizeToLf,
+
+    /// LF and lone CR line endings are replaced by CRLF.
+    NormalizeToCrlf,
+}
+
+/// Rewrites the line endings of decoded text while it is being written, which can be split
+/// across several calls (a CR at the end is held back until the next character is known).
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Newlines {
+    policy: NewlinePolicy,
+    pending_cr: bool,
+    changed: usize,
+}
+
+impl Newlines {
+    pub(crate) fn new(policy: NewlinePolicy) -> Newlines {
+        Newlines {
+            policy,
+            pending_cr: false,
+            changed: 0,
+        }
+    }
+
+    /// The number of line endings that were changed.
+    pub(crate) fn changed(&self) -> usize {
+        self.changed
+    }
+
+    /// Whether the text would be written as it is (assuming nothing is pending).
+    pub(crate) fn is_normalized(&self, text: &str) -> bool {
+        let bytes = text.as_bytes();
+        match self.policy {
+            NewlinePolicy::Preserve => true,
+            NewlinePolicy::NormalizeToLf => memchr(b'\r', bytes).is_none(),
+            NewlinePolicy::NormalizeToCrlf => {
+                let mut offset = 0;
+                while let Some(i) = memchr2(b'\r', b'\n', &bytes[offset..]) {
+                    let i = offset + i;
+                    if bytes[i] == b'\n' || bytes.get(i + 1) != Some(&b'\n') {
+                        return false;
+                    }
+                    offset = i + 2;
+                }
+                true
+            }
+        }
+    }
+
+    pub(crate) fn push(&mut self, text: &mut String, c: char) {
+        match (self.policy, c) {
+            (NewlinePolicy::Preserve, _) => text.push(c),
+            (_, '\r') => {
+                self.finish(text);
+                self.pending_cr = true;
+            }
+            (policy, '\n') => {
+                let crlf = self.pending_cr;
+                self.pending_cr = false;
+                if crlf == (policy == NewlinePolicy::NormalizeToLf) {
+                    self.changed += 1;
+                }
+                text.push_str(self.line_ending());
+            }
+            _ => {
+                self.finish(text);
+                text.push(c);
+            }
+        }
+    }
+
+    pub(crate) fn push_str(&mut self, text: &mut String, s: &str) {
+        if self.policy == NewlinePolicy::Preserve {
+            text.push_str(s);
+            return;
+        }
+        let mut rest = s;
+        while let Some(i) = memchr2(b'\r', b'\n', rest.as_bytes()) {
+            if i > 0 {
+                self.finish(text);
+                text.push_str(&rest[..i]);
+            }
+            self.push(text, char::from(rest.as_bytes()[i]));
+            rest = &rest[i + 1..];
+        }
+        if !rest.is_empty() {
+            self.finish(text);
+            text.push_str(rest);
+        }
+    }
+
+    /// Write a held back CR, if it is not followed by LF (at the end of the text).
+    pub(crate) fn finish(&mut self, text: &mut String) {
+        if self.pending_cr {
+            self.pending_cr = false;
+            self.changed += 1;
+            text.push_str(self.line_ending());
+        }
+    }
+
+    fn line_ending(&self) -> &'static str {
+        match self.policy {
+            NewlinePolicy::NormalizeToCrlf => "\r\n",
+            _ => "\n",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Newlines;
+    use crate::NewlinePolicy::{self, *};
+
+    fn normalize(pieces: &[&str], policy: NewlinePolicy) -> (String, usize) {
+        let mut newlines = Newlines::new(policy);
+        let mut text = String::new();
+        for piece in pieces {
+            newlines.push_str(&mut text, piece);
+        }
+        newlines.finish(&mut text);
+        (text, newlines.changed())
+    }
+
+    #[test]
+    fn test_newlines() {
+        let mixed = "a\r\nb\rc\nd\r\r\ne\r";
+        assert_eq!((mixed.to_string(), 0), normalize(&[mixed], Preserve));
+        assert_eq!(
+            ("a\nb\nc\nd\n\ne\n".to_string(), 5),
+            normalize(&[mixed], NormalizeToLf)
+        );
+        assert_eq!(
+            ("a\r\nb\r\nc\r\nd\r\n\r\ne\r\n".to_string(), 4),
+            normalize(&[mixed], NormalizeToCrlf)
+        );
+
+        // Split between CR and LF
+        assert_eq!(
+            ("a\nb".to_string(), 1),
+            normalize(&["a\r", "\nb"], NormalizeToLf)
+        );
+        assert_eq!(
+            ("a\n\nb".to_string(), 1),
+            normalize(&["a\r", "", "\n", "\nb"], NormalizeToLf)
+        );
+        assert_eq!(("\n".to_string(), 1), normalize(&["\r"], NormalizeToLf));
+        assert_eq!(("\r\n".to_string(), 1), normalize(&["\r"], NormalizeToCrlf));
+        assert_eq!(("".to_string(), 0), normalize(&[""], NormalizeToCrlf));
+    }
+
+    #[test]
+    fn test_is_normalized() {
+        let newlines = |policy| Newlines::new(policy);
+        assert!(newlines(Preserve).is_normalized("a\rb\n"));
+        assert!(newlines(NormalizeToLf).is_normalized("a\nb\n"));
+        assert!(!newlines(NormalizeToLf).is_normalized("a\r\nb"));
+        assert!(newlines(NormalizeToCrlf).is_normalized("a\r\nb\r\n"));
+        assert!(!newlines(NormalizeToCrlf).is_normalized("a\r\nb\n"));
+        assert!(!newlines(NormalizeToCrlf).is_normalized("a\r"));
+        assert!(!newlines(NormalizeToCrlf).is_normalized("\r\r\n"));
+        assert!(newlines(NormalizeToCrlf).is_normalized(""));
+    }
+}
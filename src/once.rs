@@ -0,0 +1,110 @@
+//! A minimal replacement of `std::sync::OnceLock` for builds without `std`, for the process-wide
+//! default `Inspector`. Threads that find the value being initialized spin until it is ready.
+
+use core::cell::UnsafeCell;
+use core::hint;
+use core::mem::MaybeUninit;
+use core::sync::atomic::{AtomicU8, Ordering};
+
+const EMPTY: u8 = 0;
+const INITIALIZING: u8 = 1;
+const READY: u8 = 2;
+
+pub(crate) struct OnceLock<T> {
+    state: AtomicU8,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+// SAFETY: the value is only written once, by the thread that moved the state from `EMPTY` to
+// `INITIALIZING`, and only read after the state is `READY`
+unsafe impl<T: Send + Sync> Sync for OnceLock<T> {}
+unsafe impl<T: Send> Send for OnceLock<T> {}
+
+impl<T> OnceLock<T> {
+    pub(crate) const fn new() -> OnceLock<T> {
+        OnceLock {
+            state: AtomicU8::new(EMPTY),
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+
+    /// Set the value, or return it as an error if the cell was already set (or is being set).
+    pub(crate) fn set(&self, value: T) -> Result<(), T> {
+        match self.try_initialize(|| value) {
+            Ok(()) => Ok(()),
+            Err(initialize) => Err(initialize()),
+        }
+    }
+
+    pub(crate) fn get_or_init<F: FnOnce() -> T>(&self, initialize: F) -> &T {
+        if self.try_initialize(initialize).is_err() {
+            while self.state.load(Ordering::Acquire) != READY {
+                hint::spin_loop();
+            }
+        }
+        // SAFETY: the state is `READY`, so the value was written
+        unsafe { (*self.value.get()).assume_init_ref() }
+    }
+
+    /// Initialize the value if the cell is empty, or give back the initialization otherwise.
+    fn try_initialize<F: FnOnce() -> T>(&self, initialize: F) -> Result<(), F> {
+        if self
+            .state
+            .compare_exchange(EMPTY, INITIALIZING, Ordering::Acquire, Ordering::Acquire)
+            .is_err()
+        {
+            return Err(initialize);
+        }
+        // SAFETY: only this thread moved the state away from `EMPTY`
+        unsafe { (*self.value.get()).write(initialize()) };
+        self.state.store(READY, Ordering::Release);
+        Ok(())
+    }
+}
+
+impl<T> Drop for OnceLock<T> {
+    fn drop(&mut self) {
+        if *self.state.get_mut() == READY {
+            // SAFETY: the state is `READY`, so the value was written
+            unsafe { self.value.get_mut().assume_init_drop() };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::thread;
+
+    use super::OnceLock;
+
+    #[test]
+    fn test_once_lock() {
+        let once = OnceLock::new();
+        assert_eq!(&1, once.get_or_init(|| 1));
+        assert_eq!(&1, once.get_or_init(|| 2));
+        assert_eq!(Err(3), once.set(3));
+
+        let once = OnceLock::new();
+        assert_eq!(Ok(()), once.set(4));
+        assert_eq!(&4, once.get_or_init(|| 5));
+
+        // One initialization, which all threads see
+        let once = Arc::new(OnceLock::new());
+        let threads: Vec<_> = (0..8)
+            .map(|i| {
+                let once = once.clone();
+                thread::spawn(move || *once.get_or_init(|| i))
+            })
+            .collect();
+        let values: Vec<_> = threads.into_iter().map(|t| t.join().unwrap()).collect();
+        assert!(values.iter().all(|&value| value == values[0]));
+
+        // The value is dropped with the cell
+        let value = Arc::new(());
+        let once = OnceLock::new();
+        once.set(value.clone()).unwrap();
+        drop(once);
+        assert_eq!(1, Arc::strong_count(&value));
+    }
+}
@@ -0,0 +1,317 @@
+use rayon::iter::{IndexedParallelIterator, IntoParallelIterator, ParallelIterator};
+use rayon::slice::ParallelSlice;
+
+use crate::heuristic::{
+    count_null_bytes_sequential, invalid_utf8_offset_sequential, ByteClassTable, ByteScan,
+};
+use crate::simd;
+use crate::{default_inspector, ContentType, Inspector};
+
+/// The size of the chunks of a large window (see [`scan_all`]) that are scanned in parallel.
+///
+/// [`scan_all`]: struct.InspectorBuilder.html#method.scan_all
+const CHUNK_SIZE: usize = 1 << 20;
+
+/// Whether the window is large enough to be scanned in parallel.
+pub(crate) fn is_parallel(window: &[u8]) -> bool {
+    window.len() > 2 * CHUNK_SIZE
+}
+
+/// Like `count_null_bytes_sequential`.
+pub(crate) fn count_null_bytes(window: &[u8]) -> (usize, Option<usize>) {
+    count_null_bytes_in_chunks(window, CHUNK_SIZE)
+}
+
+fn count_null_bytes_in_chunks(window: &[u8], chunk_size: usize) -> (usize, Option<usize>) {
+    let chunks: Vec<_> = window
+        .par_chunks(chunk_size)
+        .map(count_null_bytes_sequential)
+        .collect();
+    let first_null_offset = chunks
+        .iter()
+        .enumerate()
+        .find_map(|(i, &(_, first))| first.map(|first| i * chunk_size + first));
+    (
+        chunks.iter().map(|&(count, _)| count).sum(),
+        first_null_offset,
+    )
+}
+
+/// Like `ByteScan::count`.
+pub(crate) fn byte_scan(window: &[u8], table: &ByteClassTable, text_above: bool) -> ByteScan {
+    byte_scan_in_chunks(window, table, text_above, CHUNK_SIZE)
+}
+
+fn byte_scan_in_chunks(
+    window: &[u8],
+    table: &ByteClassTable,
+    text_above: bool,
+    chunk_size: usize,
+) -> ByteScan {
+    window
+        .par_chunks(chunk_size)
+        .enumerate()
+        .map(|(i, chunk)| {
+            ByteScan::default().merge(i * chunk_size, ByteScan::count(chunk, table, text_above))
+        })
+        .reduce(ByteScan::default, |scan, other| scan.merge(0, other))
+}
+
+/// Whether all bytes are ASCII.
+pub(crate) fn is_ascii(window: &[u8]) -> bool {
+    window
+        .par_chunks(CHUNK_SIZE)
+        .all(|chunk| simd::ascii_prefix_length(chunk) == chunk.len())
+}
+
+/// Like `invalid_utf8_offset_sequential`.
+pub(crate) fn invalid_utf8_offset(bytes: &[u8], allow_incomplete: bool) -> Option<usize> {
+    invalid_utf8_offset_in_chunks(bytes, allow_incomplete, CHUNK_SIZE)
+}
+
+/// Validates the chunks separately. They are split in front of a byte that is not a continuation
+/// byte (as long as there are at most three in a row), so that the validation of each chunk starts
+/// where a sequential validation would start a new character, unless it found an error before.
+/// An incomplete sequence at the end of a chunk is invalid, since no continuation byte follows.
+fn invalid_utf8_offset_in_chunks(
+    bytes: &[u8],
+    allow_incomplete: bool,
+    chunk_size: usize,
+) -> Option<usize> {
+    let is_continuation =
+        |offset: usize| bytes.get(offset).is_some_and(|&byte| byte & 0xC0 == 0x80);
+    let mut boundaries: Vec<usize> = (0..bytes.len())
+        .step_by(chunk_size)
+        .map(|start| match start {
+            0 => 0,
+            _ => start + (0..3).take_while(|&i| is_continuation(start + i)).count(),
+        })
+        .collect();
+    boundaries.push(bytes.len());
+    boundaries.dedup();
+
+    boundaries
+        .par_windows(2)
+        .map(|range| {
+            let last = range[1] == bytes.len();
+            let chunk = &bytes[range[0]..range[1]];
+            invalid_utf8_offset_sequential(chunk, allow_incomplete && last)
+                .map(|offset| range[0] + offset)
+        })
+        .find_first(Option::is_some)
+        .flatten()
+}
+
+impl Inspector {
+    /// Determine the type of content of many buffers in parallel, using the settings of this
+    /// `Inspector`. The results are in the same order as the buffers.
+    pub fn inspect_many<'a, I>(&self, buffers: I) -> Vec<ContentType>
+    where
+        I: IntoParallelIterator<Item = &'a [u8]>,
+    {
+        buffers
+            .into_par_iter()
+            .map(|buffer| self.inspect(buffer))
+            .collect()
+    }
+}
+
+/// Like [`inspect`](fn.inspect.html), but for many buffers, which are inspected in parallel. The
+/// results are in the same order as the buffers.
+///
+/// ```
+/// use content_inspector::{inspect_many, ContentType};
+///
+/// let buffers: Vec<&[u8]> = vec![b"Hello", b"\x00\x01", b"\xFF\xFEH\x00"];
+///
+/// assert_eq!(
+///     vec![ContentType::UTF_8, ContentType::BINARY, ContentType::UTF_16LE],
+///     inspect_many(buffers)
+/// );
+/// ```
+pub fn inspect_many<'a, I>(buffers: I) -> Vec<ContentType>
+where
+    I: IntoParallelIterator<Item = &'a [u8]>,
+{
+    default_inspector().inspect_many(buffers)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+
+    use super::{
+        byte_scan_in_chunks, count_null_bytes_in_chunks, invalid_utf8_offset_in_chunks, CHUNK_SIZE,
+    };
+    use crate::heuristic::{
+        byte_class_table, control_character_table, count_null_bytes_sequential,
+        invalid_utf8_offset_sequential, is_text_above_control, ByteScan,
+    };
+    use crate::{inspect, inspect_many, ByteClass, ContentType, Inspector, Strictness};
+
+    /// Pseudo-random buffers of UTF-8 characters of all lengths, with invalid sequences, NULL
+    /// bytes and control characters in between.
+    fn random_buffers() -> Vec<Vec<u8>> {
+        let pieces: &[&[u8]] = &[
+            b"a",
+            b"text ",
+            "é".as_bytes(),
+            "€".as_bytes(),
+            "😀".as_bytes(),
+            b"\x80",
+            b"\x80\x80\x80\x80\x80",
+            b"\xE2\x82",
+            b"\xF0\x9F",
+            b"\xFF",
+            b"\x00",
+            b"\x01",
+            b"\xEF\xBB\xBF",
+        ];
+        let mut state: u64 = 0x2545_F491_4F6C_DD1D;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        (0..2000)
+            .map(|i| {
+                // Mostly valid text, so that errors late in the buffer are found as well
+                let rare = i % 2 == 0;
+                let mut buffer = vec![];
+                while buffer.len() < i % 200 {
+                    let piece = match next() % if rare { 100 } else { 13 } {
+                        n if n < 13 => pieces[n as usize],
+                        n => pieces[n as usize % 5],
+                    };
+                    buffer.extend_from_slice(piece);
+                }
+                buffer
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_chunks_match_sequential_scan() {
+        let tables = [
+            byte_class_table(ByteClass::of),
+            control_character_table(0),
+            byte_class_table(|byte| match byte {
+                0x00 => ByteClass::Null,
+                0x80..=0xFF => ByteClass::Control,
+                _ => ByteClass::Text,
+            }),
+        ];
+        for buffer in random_buffers() {
+            for chunk_size in (1..=9).chain(Some(64)) {
+                assert_eq!(
+                    count_null_bytes_sequential(&buffer),
+                    count_null_bytes_in_chunks(&buffer, chunk_size),
+                    "{:?}",
+                    buffer
+                );
+                for table in &tables {
+                    for &text_above in &[false, is_text_above_control(table)] {
+                        // The high bits of the bytes only tell whether they are all ASCII
+                        let counts = |scan: ByteScan| {
+                            (
+                                scan.null_bytes,
+                                scan.first_null_offset,
+                                scan.control_characters,
+                                scan.printable_bytes,
+                                scan.is_ascii(),
+                            )
+                        };
+                        assert_eq!(
+                            counts(ByteScan::count(&buffer, table, text_above)),
+                            counts(byte_scan_in_chunks(&buffer, table, text_above, chunk_size)),
+                            "{:?}",
+                            buffer
+                        );
+                    }
+                }
+                for &allow_incomplete in &[false, true] {
+                    assert_eq!(
+                        invalid_utf8_offset_sequential(&buffer, allow_incomplete),
+                        invalid_utf8_offset_in_chunks(&buffer, allow_incomplete, chunk_size),
+                        "{:?} {}",
+                        buffer,
+                        chunk_size
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_parallel_scan_all() {
+        // Valid UTF-8 with a multi-byte character across each chunk boundary
+        let text = "Text with a € sign, ".repeat(5 * CHUNK_SIZE / 22);
+        let inspector = Inspector::new()
+            .strictness(Strictness::Paranoid)
+            .detect_control_characters(true)
+            .min_printable_ratio(0.5)
+            .detect_ascii(true)
+            .build()
+            .unwrap();
+        let report = inspector.inspect_detailed(text.as_bytes());
+        assert_eq!(ContentType::UTF_8, report.content_type);
+        assert_eq!(text.len(), report.validated_bytes);
+        assert_eq!(None, report.invalid_utf8_offset);
+
+        let mut buffer = text.into_bytes();
+        let boundary = 3 * CHUNK_SIZE;
+        buffer[boundary + 1] = 0xFF;
+        assert_eq!(
+            Some(boundary + 1),
+            inspector.inspect_detailed(&buffer).invalid_utf8_offset
+        );
+        buffer[boundary - 1] = 0x00;
+        let last = buffer.len() - 1;
+        buffer[last] = 0x00;
+        let report = inspector.inspect_detailed(&buffer);
+        assert_eq!(ContentType::BINARY, report.content_type);
+        assert_eq!(2, report.null_bytes);
+        assert_eq!(Some(boundary - 1), report.first_null_offset);
+
+        let ascii = vec![b'a'; 3 * CHUNK_SIZE];
+        assert_eq!(ContentType::ASCII, inspector.inspect(&ascii));
+    }
+
+    #[test]
+    fn test_inspect_many() {
+        let testdata = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata");
+        let files: Vec<Vec<u8>> = fs::read_dir(testdata)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.is_file())
+            .map(|path| fs::read(path).unwrap())
+            .collect();
+        // Many buffers of different lengths, to be split across threads
+        let buffers: Vec<&[u8]> = (0..10_000)
+            .map(|i| {
+                let file = &files[i % files.len()];
+                &file[..file.len().min(i)]
+            })
+            .collect();
+
+        let expected: Vec<_> = buffers.iter().map(|buffer| inspect(buffer)).collect();
+        assert_eq!(expected, inspect_many(buffers.clone()));
+        assert_eq!(expected, inspect_many(buffers.par_iter().cloned()));
+
+        let inspector = Inspector::new()
+            .strictness(Strictness::Paranoid)
+            .build()
+            .unwrap();
+        let expected: Vec<_> = buffers
+            .iter()
+            .map(|buffer| inspector.inspect(buffer))
+            .collect();
+        assert_eq!(expected, inspector.inspect_many(buffers.clone()));
+
+        assert!(inspect_many(Vec::<&[u8]>::new()).is_empty());
+    }
+}
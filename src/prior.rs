@@ -0,0 +1,472 @@
+//! The reconciliation of the detected type of content with a type that was claimed by someone
+//! else, like the charset of an HTTP `Content-Type` header or the metadata of an archive.
+
+use core::str;
+
+use crate::{default_inspector, validate, ContentType, InspectionReport, Inspector, Reason};
+
+/// Whether the content agrees with the claimed type of content, see
+/// [`inspect_with_prior`](fn.inspect_with_prior.html).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Verdict {
+    /// The content is consistent with the claimed type of content.
+    Confirmed,
+
+    /// The content contradicts the claimed type of content, so the detected type is used instead.
+    Overridden,
+
+    /// The content does not tell whether the claimed type of content is right, so it is kept.
+    Unknown,
+}
+
+/// The result of [`inspect_with_prior`](fn.inspect_with_prior.html).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Reconciled {
+    /// The type of content to use: the claimed one, unless the content contradicts it (or shows
+    /// whether claimed UTF-8 has a byte order mark).
+    pub content_type: ContentType,
+
+    /// The claimed type of content.
+    pub claimed: ContentType,
+
+    /// Whether the content confirmed or overrode the claimed type of content.
+    pub verdict: Verdict,
+
+    /// The evidence for the verdict: the reason of the detection, `InvalidUtf8` or
+    /// `InvalidEncoding` if the content is not valid in the claimed encoding, `Utf16Pattern` for
+    /// the zero bytes of claimed UTF-16, and `NoBinaryIndicators` for valid claimed UTF-32.
+    pub reason: Reason,
+
+    /// The report of the detection, which does not take the claimed type into account.
+    pub report: InspectionReport,
+}
+
+/// Whether the detected type of content agrees with the claimed one: the same type, or UTF-8
+/// with and without a byte order mark.
+fn agrees(claimed: ContentType, detected: ContentType) -> bool {
+    use ContentType::*;
+    claimed == detected || matches!((claimed, detected), (UTF_8, UTF_8_BOM) | (UTF_8_BOM, UTF_8))
+}
+
+/// The complete code units at the start of the window, without a high surrogate at its end, if
+/// the window is shorter than the buffer (so that the rest may follow beyond it).
+fn complete_units(window: &[u8], unit_size: usize, big_endian: bool, truncated: bool) -> &[u8] {
+    if !truncated {
+        return window;
+    }
+    let units = &window[..window.len() - window.len() % unit_size];
+    match units.len().checked_sub(2) {
+        Some(last) if unit_size == 2 => {
+            let high = if big_endian {
+                units[last]
+            } else {
+                units[last + 1]
+            };
+            if (0xD8..=0xDB).contains(&high) {
+                &units[..last]
+            } else {
+                units
+            }
+        }
+        _ => units,
+    }
+}
+
+impl Inspector {
+    /// Reconcile the type of content of the buffer with the type that was claimed for it, for
+    /// example by the charset of an HTTP `Content-Type` header. The `Reconciled` result has the
+    /// type of content to use, and whether the content confirmed the claimed type, overrode it,
+    /// or does not tell. The rules, in the order of their precedence:
+    ///
+    /// 1. An empty buffer does not tell (`Unknown`, with the claimed type).
+    /// 2. A byte order mark (also one with invalid content after it) or a magic number that was
+    ///    detected decides: the claimed type is confirmed if the detected type agrees with it,
+    ///    and overridden with the detected type otherwise. `UTF_8` and `UTF_8_BOM` agree with
+    ///    each other, the result tells whether there is a byte order mark.
+    /// 3. Otherwise, the claimed type is checked against the scanned window:
+    ///    * `UTF_16LE` and `UTF_16BE` are confirmed by valid UTF-16 without U+0000 with more zero
+    ///      bytes in the high bytes of the code units than in the low ones (like ASCII
+    ///      characters), and valid UTF-16 without any zero bytes does not tell (like CJK text, but
+    ///      also like ASCII text). Anything else is overridden with the detected type.
+    ///    * `UTF_32LE` and `UTF_32BE` are confirmed by valid UTF-32 without U+0000, and overridden
+    ///      with the detected type otherwise.
+    ///    * `BINARY` is confirmed if the detected type is binary. Text does not tell, since many
+    ///      binary formats may contain text only at the start.
+    ///    * `UTF_8`, `UTF_8_BOM`, `ASCII` and `LATIN_1` are overridden with the detected type if
+    ///      it is binary (apart from invalid UTF-8 for `LATIN_1`, see
+    ///      [`Strictness`](enum.Strictness.html)), or UTF-16 by the pattern of its zero bytes.
+    ///      Otherwise, valid UTF-8 confirms `UTF_8` and `UTF_8_BOM` (as `UTF_8`), and ASCII
+    ///      content confirms `ASCII` and `LATIN_1`, as does invalid UTF-8 for `LATIN_1`. Other
+    ///      valid UTF-8 overrides the claim with `UTF_8`, and other invalid UTF-8 with `LATIN_1`
+    ///      (or `BINARY` without the `legacy-encodings` feature).
+    ///
+    /// An incomplete UTF-8 sequence, code unit or surrogate pair at the end of the scanned window
+    /// is not held against the claimed type if the buffer continues beyond it.
+    pub fn inspect_with_prior(&self, buffer: &[u8], claimed: ContentType) -> Reconciled {
+        use ContentType::*;
+
+        let report = self.inspect_detailed(buffer);
+        let detected = report.content_type;
+        let reconciled = |content_type, verdict, reason| Reconciled {
+            content_type,
+            claimed,
+            verdict,
+            reason,
+            report,
+        };
+
+        match report.reason {
+            Reason::EmptyBuffer => return reconciled(claimed, Verdict::Unknown, report.reason),
+            Reason::ByteOrderMark | Reason::InvalidEncoding | Reason::MagicNumber => {
+                let verdict = if agrees(claimed, detected) {
+                    Verdict::Confirmed
+                } else {
+                    Verdict::Overridden
+                };
+                return reconciled(detected, verdict, report.reason);
+            }
+            _ => {}
+        }
+
+        let window = self.scan_window(buffer);
+        let truncated = window.len() < buffer.len();
+        match claimed {
+            UTF_16LE | UTF_16BE => {
+                let big_endian = claimed == UTF_16BE;
+                let units = complete_units(window, 2, big_endian, truncated);
+                if validate(units, claimed).is_err() {
+                    return reconciled(detected, Verdict::Overridden, Reason::InvalidEncoding);
+                }
+                let (mut high_zeros, mut low_zeros, mut nul) = (0, 0, false);
+                for unit in units.chunks_exact(2) {
+                    let (high, low) = if big_endian {
+                        (unit[0], unit[1])
+                    } else {
+                        (unit[1], unit[0])
+                    };
+                    high_zeros += usize::from(high == 0);
+                    low_zeros += usize::from(low == 0);
+                    nul |= high == 0 && low == 0;
+                }
+                if !nul && high_zeros > low_zeros {
+                    reconciled(claimed, Verdict::Confirmed, Reason::Utf16Pattern)
+                } else if high_zeros == 0 && low_zeros == 0 {
+                    reconciled(claimed, Verdict::Unknown, report.reason)
+                } else {
+                    reconciled(detected, Verdict::Overridden, report.reason)
+                }
+            }
+            UTF_32LE | UTF_32BE => {
+                let units = complete_units(window, 4, claimed == UTF_32BE, truncated);
+                if validate(units, claimed).is_err() {
+                    reconciled(detected, Verdict::Overridden, Reason::InvalidEncoding)
+                } else if units.chunks_exact(4).any(|unit| unit == [0; 4]) {
+                    reconciled(detected, Verdict::Overridden, report.reason)
+                } else {
+                    reconciled(claimed, Verdict::Confirmed, Reason::NoBinaryIndicators)
+                }
+            }
+            BINARY if detected == BINARY => reconciled(claimed, Verdict::Confirmed, report.reason),
+            BINARY => reconciled(claimed, Verdict::Unknown, report.reason),
+            LATIN_1 if detected == BINARY && report.reason == Reason::InvalidUtf8 => {
+                reconciled(claimed, Verdict::Confirmed, report.reason)
+            }
+            _ if detected == BINARY || report.reason == Reason::Utf16Pattern => {
+                reconciled(detected, Verdict::Overridden, report.reason)
+            }
+            UTF_8 | UTF_8_BOM | ASCII | LATIN_1 => {
+                let utf8 = match str::from_utf8(window) {
+                    Ok(_) => true,
+                    Err(error) => truncated && error.error_len().is_none(),
+                };
+                let ascii = window.is_ascii();
+                match claimed {
+                    LATIN_1 if !utf8 => {
+                        reconciled(claimed, Verdict::Confirmed, Reason::InvalidUtf8)
+                    }
+                    LATIN_1 | ASCII if ascii => {
+                        reconciled(claimed, Verdict::Confirmed, report.reason)
+                    }
+                    UTF_8 | UTF_8_BOM if utf8 => {
+                        reconciled(UTF_8, Verdict::Confirmed, report.reason)
+                    }
+                    _ if utf8 => reconciled(UTF_8, Verdict::Overridden, report.reason),
+                    _ => {
+                        let fallback = if cfg!(feature = "legacy-encodings") {
+                            LATIN_1
+                        } else {
+                            BINARY
+                        };
+                        reconciled(fallback, Verdict::Overridden, Reason::InvalidUtf8)
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Reconcile the type of content of the buffer with the type that was claimed for it, like the
+/// charset of an HTTP `Content-Type` header or the encoding in the metadata of an archive. This
+/// uses the process-wide default `Inspector`, see
+/// [`Inspector::inspect_with_prior`](struct.Inspector.html#method.inspect_with_prior) for the
+/// rules.
+///
+/// ```
+/// # #[cfg(feature = "utf16")]
+/// # {
+/// use content_inspector::{inspect_with_prior, ContentType, Reason, Verdict};
+///
+/// // A byte order mark overrides the claim
+/// let reconciled = inspect_with_prior(b"\xFF\xFEH\x00i\x00", ContentType::UTF_8);
+/// assert_eq!(ContentType::UTF_16LE, reconciled.content_type);
+/// assert_eq!(Verdict::Overridden, reconciled.verdict);
+///
+/// // The zero bytes of UTF-16 without a byte order mark confirm it
+/// let reconciled = inspect_with_prior(b"H\x00e\x00l\x00l\x00o\x00", ContentType::UTF_16LE);
+/// assert_eq!(ContentType::UTF_16LE, reconciled.content_type);
+/// assert_eq!(Verdict::Confirmed, reconciled.verdict);
+///
+/// // Binary content is binary, whatever the claim
+/// let reconciled = inspect_with_prior(b"\x7FELF\x02\x01\x01\x00", ContentType::UTF_8);
+/// assert_eq!(ContentType::BINARY, reconciled.content_type);
+/// assert_eq!(
+///     (Verdict::Overridden, Reason::NullBytes),
+///     (reconciled.verdict, reconciled.reason)
+/// );
+/// # }
+/// ```
+pub fn inspect_with_prior(buffer: &[u8], claimed: ContentType) -> Reconciled {
+    default_inspector().inspect_with_prior(buffer, claimed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{inspect_with_prior, Verdict};
+    use crate::{ContentType, ContentType::*, Inspector, Reason, Strictness};
+
+    fn check(
+        inspector: &Inspector,
+        buffer: &[u8],
+        claimed: ContentType,
+        expected: (ContentType, Verdict, Reason),
+    ) {
+        let reconciled = inspector.inspect_with_prior(buffer, claimed);
+        assert_eq!(
+            expected,
+            (
+                reconciled.content_type,
+                reconciled.verdict,
+                reconciled.reason
+            ),
+            "{:?} claimed as {}",
+            buffer,
+            claimed
+        );
+        assert_eq!(claimed, reconciled.claimed);
+        assert_eq!(inspector.inspect_detailed(buffer), reconciled.report);
+    }
+
+    fn utf16(text: &str, big_endian: bool) -> Vec<u8> {
+        text.encode_utf16()
+            .flat_map(|unit| {
+                if big_endian {
+                    unit.to_be_bytes()
+                } else {
+                    unit.to_le_bytes()
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_empty_and_evidence() {
+        let inspector = Inspector::default();
+        for &claimed in &[UTF_8, UTF_16LE, BINARY] {
+            check(
+                &inspector,
+                b"",
+                claimed,
+                (claimed, Verdict::Unknown, Reason::EmptyBuffer),
+            );
+        }
+
+        // UTF-8 with and without a byte order mark agree
+        let bom = (UTF_8_BOM, Verdict::Confirmed, Reason::ByteOrderMark);
+        check(&inspector, b"\xEF\xBB\xBFHi", UTF_8, bom);
+        check(&inspector, b"\xEF\xBB\xBFHi", UTF_8_BOM, bom);
+        let bom = (UTF_8_BOM, Verdict::Overridden, Reason::ByteOrderMark);
+        check(&inspector, b"\xEF\xBB\xBFHi", LATIN_1, bom);
+        check(&inspector, b"\xEF\xBB\xBFHi", BINARY, bom);
+
+        if cfg!(feature = "utf16") {
+            let bom = (UTF_16LE, Verdict::Overridden, Reason::ByteOrderMark);
+            check(&inspector, b"\xFF\xFEH\x00i\x00", UTF_8, bom);
+            check(&inspector, b"\xFF\xFEH\x00i\x00", UTF_16BE, bom);
+            let bom = (UTF_16LE, Verdict::Confirmed, Reason::ByteOrderMark);
+            check(&inspector, b"\xFF\xFEH\x00i\x00", UTF_16LE, bom);
+        }
+        if cfg!(feature = "magic-documents") {
+            let magic = (BINARY, Verdict::Overridden, Reason::MagicNumber);
+            check(&inspector, b"%PDF-1.7\n", UTF_8, magic);
+            let magic = (BINARY, Verdict::Confirmed, Reason::MagicNumber);
+            check(&inspector, b"%PDF-1.7\n", BINARY, magic);
+        }
+    }
+
+    #[test]
+    fn test_utf16_and_utf32() {
+        let inspector = Inspector::default();
+        let le = utf16("plain text\r\n", false);
+        let be = utf16("plain text\r\n", true);
+        let pattern = (UTF_16LE, Verdict::Confirmed, Reason::Utf16Pattern);
+        check(&inspector, &le, UTF_16LE, pattern);
+        let pattern = (UTF_16BE, Verdict::Confirmed, Reason::Utf16Pattern);
+        check(&inspector, &be, UTF_16BE, pattern);
+
+        // The zero bytes are on the wrong side, or a U+0000 code unit
+        let wrong = (BINARY, Verdict::Overridden, Reason::NullBytes);
+        check(&inspector, &le, UTF_16BE, wrong);
+        check(&inspector, b"a\x00\x00\x00b\x00", UTF_16LE, wrong);
+
+        // No zero bytes at all, and invalid UTF-16
+        let cjk = utf16("世界和平", false);
+        let unknown = (UTF_16LE, Verdict::Unknown, Reason::NoBinaryIndicators);
+        check(&inspector, &cjk, UTF_16LE, unknown);
+        let invalid = (UTF_8, Verdict::Overridden, Reason::InvalidEncoding);
+        check(&inspector, b"odd", UTF_16LE, invalid);
+        let invalid = (BINARY, Verdict::Overridden, Reason::InvalidEncoding);
+        check(&inspector, b"a\x00\x00\xDC", UTF_16LE, invalid);
+
+        // A surrogate pair and a code unit that continue beyond the scanned window
+        let mut long = utf16(&"a".repeat(511), false);
+        long.extend(utf16("💖 text", false));
+        let pattern = (UTF_16LE, Verdict::Confirmed, Reason::Utf16Pattern);
+        check(&inspector, &long, UTF_16LE, pattern);
+        let invalid = (BINARY, Verdict::Overridden, Reason::InvalidEncoding);
+        check(&inspector, &long[..1024], UTF_16LE, invalid);
+
+        let utf32: Vec<u8> = "text €"
+            .chars()
+            .flat_map(|c| (c as u32).to_le_bytes())
+            .collect();
+        let valid = (UTF_32LE, Verdict::Confirmed, Reason::NoBinaryIndicators);
+        check(&inspector, &utf32, UTF_32LE, valid);
+        let invalid = (BINARY, Verdict::Overridden, Reason::InvalidEncoding);
+        check(&inspector, &utf32, UTF_32BE, invalid);
+        check(&inspector, &le, UTF_32LE, invalid);
+        let nul = (BINARY, Verdict::Overridden, Reason::NullBytes);
+        check(&inspector, &[0; 8], UTF_32LE, nul);
+    }
+
+    #[test]
+    fn test_binary() {
+        let inspector = Inspector::default();
+        let binary = b"\x7FELF\x02\x01\x01\x00";
+        let nul = (BINARY, Verdict::Confirmed, Reason::NullBytes);
+        check(&inspector, binary, BINARY, nul);
+        let nul = (BINARY, Verdict::Overridden, Reason::NullBytes);
+        for &claimed in &[UTF_8, UTF_8_BOM, ASCII, LATIN_1] {
+            check(&inspector, binary, claimed, nul);
+        }
+
+        // Text does not contradict a claim of binary content
+        let text = (BINARY, Verdict::Unknown, Reason::NoBinaryIndicators);
+        check(&inspector, b"Hello", BINARY, text);
+
+        let controls = Inspector::new()
+            .detect_control_characters(true)
+            .build()
+            .unwrap();
+        let reason = (BINARY, Verdict::Overridden, Reason::ControlCharacters);
+        check(&controls, b"\x01\x02\x03\x04\x05text", UTF_8, reason);
+    }
+
+    #[test]
+    fn test_byte_encodings() {
+        let inspector = Inspector::default();
+        let none = Reason::NoBinaryIndicators;
+        check(
+            &inspector,
+            b"Hello",
+            UTF_8,
+            (UTF_8, Verdict::Confirmed, none),
+        );
+        check(
+            &inspector,
+            b"Hello",
+            UTF_8_BOM,
+            (UTF_8, Verdict::Confirmed, none),
+        );
+        check(
+            &inspector,
+            b"Hello",
+            ASCII,
+            (ASCII, Verdict::Confirmed, none),
+        );
+        check(
+            &inspector,
+            b"Hello",
+            LATIN_1,
+            (LATIN_1, Verdict::Confirmed, none),
+        );
+
+        let utf8 = "Grüße".as_bytes();
+        check(&inspector, utf8, UTF_8, (UTF_8, Verdict::Confirmed, none));
+        check(&inspector, utf8, ASCII, (UTF_8, Verdict::Overridden, none));
+        check(
+            &inspector,
+            utf8,
+            LATIN_1,
+            (UTF_8, Verdict::Overridden, none),
+        );
+
+        let latin1 = b"Gr\xFC\xDFe";
+        let fallback = if cfg!(feature = "legacy-encodings") {
+            LATIN_1
+        } else {
+            BINARY
+        };
+        let invalid = (fallback, Verdict::Overridden, Reason::InvalidUtf8);
+        check(&inspector, latin1, UTF_8, invalid);
+        check(&inspector, latin1, ASCII, invalid);
+        let invalid = (LATIN_1, Verdict::Confirmed, Reason::InvalidUtf8);
+        check(&inspector, latin1, LATIN_1, invalid);
+
+        // Also if strict validation reports invalid UTF-8 as binary
+        let strict = Inspector::new()
+            .strictness(Strictness::Strict)
+            .build()
+            .unwrap();
+        check(&strict, latin1, LATIN_1, invalid);
+        let binary = (BINARY, Verdict::Overridden, Reason::InvalidUtf8);
+        check(&strict, latin1, UTF_8, binary);
+
+        // An incomplete sequence at the end of the window, but not at the end of the buffer
+        let mut long = vec![b'a'; 1023];
+        long.extend("ü".as_bytes());
+        check(&inspector, &long, UTF_8, (UTF_8, Verdict::Confirmed, none));
+        let invalid = (fallback, Verdict::Overridden, Reason::InvalidUtf8);
+        check(&inspector, &long[..1024], UTF_8, invalid);
+
+        if cfg!(feature = "utf16") {
+            let sniffing = Inspector::new()
+                .detect_utf16_without_bom(true)
+                .build()
+                .unwrap();
+            let le = utf16("plain text", false);
+            let pattern = (UTF_16LE, Verdict::Overridden, Reason::Utf16Pattern);
+            check(&sniffing, &le, UTF_8, pattern);
+            let pattern = (UTF_16LE, Verdict::Confirmed, Reason::Utf16Pattern);
+            check(&sniffing, &le, UTF_16LE, pattern);
+        }
+    }
+
+    #[test]
+    fn test_default_inspector() {
+        let reconciled = inspect_with_prior(b"Hello", UTF_8);
+        assert_eq!(
+            Inspector::default().inspect_with_prior(b"Hello", UTF_8),
+            reconciled
+        );
+    }
+}
@@ -0,0 +1,1306 @@
+use std::fs::{self, File};
+use std::io::{self, BufRead, IoSlice, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use crate::search::memchr;
+use crate::simd;
+use crate::{
+    default_inspector, ContentType, Inspector, StreamingInspector, KNOWN_BYTE_ORDER_MARKS,
+};
+
+/// The size of the buffer that is used to scan through whole readers.
+const CHUNK_SIZE: usize = 8192;
+
+/// The size of the reads of [`contains_binary`](fn.contains_binary.html), which scans whole
+/// streams and should not be limited by the overhead per read.
+const SCAN_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Read the part of the reader that is examined by the inspector: up to its read limit, or until
+/// the end if it examines the whole buffer.
+pub(crate) fn read_prefix<R: Read>(inspector: &Inspector, mut reader: R) -> io::Result<Vec<u8>> {
+    let mut buffer = vec![];
+    match inspector.read_limit() {
+        Some(limit) => reader.take(limit as u64).read_to_end(&mut buffer)?,
+        None => reader.read_to_end(&mut buffer)?,
+    };
+    Ok(buffer)
+}
+
+impl Inspector {
+    /// Like [`inspect`](#method.inspect), but reads the content from the given reader. Only the
+    /// bytes that are examined by this `Inspector` (the scanned window, in most cases) are read,
+    /// until the end of the reader at most.
+    pub fn inspect_reader<R: Read>(&self, reader: R) -> io::Result<ContentType> {
+        Ok(self.inspect(&read_prefix(self, reader)?))
+    }
+
+    /// Like [`inspect_reader`](#method.inspect_reader), but seeks back to the current position of
+    /// the reader afterwards. The content is inspected from the current position on.
+    pub fn inspect_seek<R: Read + Seek + ?Sized>(&self, reader: &mut R) -> io::Result<ContentType> {
+        let position = reader.stream_position()?;
+        let result = read_prefix(self, &mut *reader);
+        reader.seek(SeekFrom::Start(position))?;
+        Ok(self.inspect(&result?))
+    }
+
+    /// Like [`inspect_reader`](#method.inspect_reader), but also returns the bytes that have been
+    /// read, and the reader itself. The whole content can be read with
+    /// `Cursor::new(prefix).chain(reader)` afterwards.
+    pub fn inspect_reader_keep<R: Read>(
+        &self,
+        mut reader: R,
+    ) -> io::Result<(ContentType, Vec<u8>, R)> {
+        let prefix = read_prefix(self, &mut reader)?;
+        Ok((self.inspect(&prefix), prefix, reader))
+    }
+
+    /// Like [`inspect_reader`](#method.inspect_reader), but reads the content into the given
+    /// buffer instead of allocating one. Up to `scratch.len()` bytes are read (or up to the bytes
+    /// that are examined by this `Inspector`, if that is less), so a small buffer results in a
+    /// less accurate result.
+    pub fn inspect_reader_with_buf<R: Read>(
+        &self,
+        mut reader: R,
+        scratch: &mut [u8],
+    ) -> io::Result<ContentType> {
+        let limit = self
+            .read_limit()
+            .map_or(scratch.len(), |limit| limit.min(scratch.len()));
+        let mut filled = 0;
+        while filled < limit {
+            match reader.read(&mut scratch[filled..limit]) {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(self.inspect(&scratch[..filled]))
+    }
+
+    /// Like [`inspect_reader`](#method.inspect_reader), but only looks at the content that is
+    /// currently buffered by the given reader, without consuming it. The reader can still be read
+    /// from the start afterwards, even if it can not seek.
+    ///
+    /// The result is based on at most one buffer's worth of content (as returned by a single
+    /// call to `fill_buf`), which can be less than the scanned window if the buffer is small or
+    /// the underlying reader returns short reads.
+    pub fn inspect_buf_read<R: BufRead + ?Sized>(&self, reader: &mut R) -> io::Result<ContentType> {
+        Ok(self.inspect(reader.fill_buf()?))
+    }
+
+    /// Copy the whole content of the reader to the writer, like `io::copy`, while determining its
+    /// type with the settings of this `Inspector`. Returns the number of bytes that were copied,
+    /// and the type of content.
+    ///
+    /// Only the examined part of the content is inspected, the rest is just copied.
+    pub fn copy_and_inspect<R: Read + ?Sized, W: Write + ?Sized>(
+        &self,
+        reader: &mut R,
+        writer: &mut W,
+    ) -> io::Result<(u64, ContentType)> {
+        let mut streaming = self.streaming();
+        let mut buffer = [0; CHUNK_SIZE];
+        let mut copied = 0;
+        loop {
+            let chunk = match reader.read(&mut buffer) {
+                Ok(0) => return Ok((copied, streaming.finalize())),
+                Ok(n) => &buffer[..n],
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            streaming.push(chunk);
+            writer.write_all(chunk)?;
+            copied += chunk.len() as u64;
+        }
+    }
+
+    /// Like [`inspect_reader`](#method.inspect_reader), but opens the file at the given path.
+    ///
+    /// Directories and other files that are not regular files (like FIFOs or devices) are not
+    /// opened, which could block or never end. They result in an error of kind `IsADirectory` or
+    /// `InvalidInput`, respectively.
+    pub fn inspect_file<P: AsRef<Path>>(&self, path: P) -> io::Result<ContentType> {
+        self.inspect_reader(open_regular_file(path.as_ref())?)
+    }
+}
+
+/// Open the file at the given path, if it is a regular file.
+pub(crate) fn open_regular_file(path: &Path) -> io::Result<File> {
+    let metadata = fs::metadata(path)?;
+    if metadata.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::IsADirectory,
+            format!("'{}' is a directory", path.display()),
+        ));
+    }
+    if !metadata.is_file() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("'{}' is not a regular file", path.display()),
+        ));
+    }
+    File::open(path)
+}
+
+/// Like [`inspect`](fn.inspect.html), but reads the content from the given reader. The reader
+/// does not need to be seekable, only the first 1024 bytes are read.
+///
+/// ```
+/// use content_inspector::{inspect_reader, ContentType};
+///
+/// let reader = &b"Hello"[..];
+///
+/// assert_eq!(ContentType::UTF_8, inspect_reader(reader).unwrap());
+/// ```
+pub fn inspect_reader<R: Read>(reader: R) -> io::Result<ContentType> {
+    default_inspector().inspect_reader(reader)
+}
+
+/// Like [`inspect_reader`](fn.inspect_reader.html), but seeks back to the current position of the
+/// reader afterwards. The content is inspected from the current position on.
+///
+/// ```
+/// use std::io::{Cursor, Seek, SeekFrom};
+/// use content_inspector::{inspect_seek, ContentType};
+///
+/// # fn main() -> std::io::Result<()> {
+/// let mut cursor = Cursor::new(b"\x00\x00Hello");
+/// cursor.seek(SeekFrom::Start(2))?;
+///
+/// assert_eq!(ContentType::UTF_8, inspect_seek(&mut cursor)?);
+/// assert_eq!(2, cursor.position());
+/// # Ok(())
+/// # }
+/// ```
+pub fn inspect_seek<R: Read + Seek + ?Sized>(reader: &mut R) -> io::Result<ContentType> {
+    default_inspector().inspect_seek(reader)
+}
+
+/// Like [`inspect_reader`](fn.inspect_reader.html), but also returns the bytes that have been
+/// read, and the reader itself, so that the whole content can still be read.
+///
+/// ```
+/// use std::io::{Cursor, Read};
+/// use content_inspector::{inspect_reader_keep, ContentType};
+///
+/// # fn main() -> std::io::Result<()> {
+/// let (content_type, prefix, reader) = inspect_reader_keep(&b"Hello"[..])?;
+/// assert_eq!(ContentType::UTF_8, content_type);
+///
+/// let mut content = String::new();
+/// Cursor::new(prefix).chain(reader).read_to_string(&mut content)?;
+/// assert_eq!("Hello", content);
+/// # Ok(())
+/// # }
+/// ```
+pub fn inspect_reader_keep<R: Read>(reader: R) -> io::Result<(ContentType, Vec<u8>, R)> {
+    default_inspector().inspect_reader_keep(reader)
+}
+
+/// Like [`inspect_reader`](fn.inspect_reader.html), but reads the content into the given buffer
+/// instead of allocating one. See
+/// [`Inspector::inspect_reader_with_buf`](struct.Inspector.html#method.inspect_reader_with_buf)
+/// for details.
+///
+/// This does not allocate, apart from initializing the default settings when they are used for
+/// the first time.
+///
+/// ```
+/// use content_inspector::{inspect_reader_with_buf, ContentType};
+///
+/// let mut scratch = [0; 1024];
+/// let result = inspect_reader_with_buf(&b"Hello"[..], &mut scratch).unwrap();
+///
+/// assert_eq!(ContentType::UTF_8, result);
+/// ```
+pub fn inspect_reader_with_buf<R: Read>(reader: R, scratch: &mut [u8]) -> io::Result<ContentType> {
+    default_inspector().inspect_reader_with_buf(reader, scratch)
+}
+
+/// Like [`inspect`](fn.inspect.html), but peeks at the content that is buffered by the given
+/// reader, without consuming it. See
+/// [`Inspector::inspect_buf_read`](struct.Inspector.html#method.inspect_buf_read) for details.
+///
+/// ```
+/// use std::io::{BufRead, BufReader, Read};
+/// use content_inspector::{inspect_buf_read, ContentType};
+///
+/// # fn main() -> std::io::Result<()> {
+/// let mut reader = BufReader::new(&b"Hello"[..]);
+/// assert_eq!(ContentType::UTF_8, inspect_buf_read(&mut reader)?);
+///
+/// let mut content = String::new();
+/// reader.read_to_string(&mut content)?;
+/// assert_eq!("Hello", content);
+/// # Ok(())
+/// # }
+/// ```
+pub fn inspect_buf_read<R: BufRead + ?Sized>(reader: &mut R) -> io::Result<ContentType> {
+    default_inspector().inspect_buf_read(reader)
+}
+
+/// A reader that removes a UTF-8 byte order mark from the start of the wrapped reader, and passes
+/// everything else through unchanged.
+///
+/// UTF-16 and UTF-32 byte order marks are only removed if
+/// [`strip_all`](#method.strip_all) is enabled, as the content after them is not UTF-8. In any
+/// case, the type of the byte order mark that was found is available from [`bom`](#method.bom).
+///
+/// ```
+/// use std::io::Read;
+/// use content_inspector::{BomStrippingReader, ContentType};
+///
+/// # fn main() -> std::io::Result<()> {
+/// let mut reader = BomStrippingReader::new(&b"\xEF\xBB\xBFname,value"[..]);
+/// let mut content = String::new();
+/// reader.read_to_string(&mut content)?;
+///
+/// assert_eq!("name,value", content);
+/// assert_eq!(Some(ContentType::UTF_8_BOM), reader.bom());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct BomStrippingReader<R> {
+    inner: R,
+    strip_all: bool,
+    head: [u8; 4],
+    head_length: usize,
+    head_position: usize,
+    peeked: bool,
+    bom: Option<ContentType>,
+}
+
+impl<R: Read> BomStrippingReader<R> {
+    /// Wraps the given reader.
+    pub fn new(inner: R) -> BomStrippingReader<R> {
+        BomStrippingReader {
+            inner,
+            strip_all: false,
+            head: [0; 4],
+            head_length: 0,
+            head_position: 0,
+            peeked: false,
+            bom: None,
+        }
+    }
+
+    /// Also remove UTF-16 and UTF-32 byte order marks (default: false).
+    pub fn strip_all(mut self, enabled: bool) -> Self {
+        self.strip_all = enabled;
+        self
+    }
+
+    /// The type of the byte order mark at the start of the content, once the first read has
+    /// returned. `None` if the content does not start with a byte order mark.
+    pub fn bom(&self) -> Option<ContentType> {
+        self.bom
+    }
+
+    /// Returns the wrapped reader. Bytes that have been read from it to look for a byte order
+    /// mark, but have not been returned yet, are lost.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Read from the wrapped reader until the start of the content either is or can not be a
+    /// byte order mark anymore.
+    fn peek(&mut self) -> io::Result<()> {
+        loop {
+            let head = &self.head[..self.head_length];
+            let pending = KNOWN_BYTE_ORDER_MARKS
+                .iter()
+                .any(|&(bom, _)| bom.len() > head.len() && bom.starts_with(head));
+            if !pending {
+                break;
+            }
+            match self.inner.read(&mut self.head[self.head_length..]) {
+                Ok(0) => break,
+                Ok(n) => self.head_length += n,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        let head = &self.head[..self.head_length];
+        if let Some(&(bom, content_type)) = KNOWN_BYTE_ORDER_MARKS
+            .iter()
+            .find(|&&(bom, _)| head.starts_with(bom))
+        {
+            self.bom = Some(content_type);
+            if content_type == ContentType::UTF_8_BOM || self.strip_all {
+                self.head_position = bom.len();
+            }
+        }
+        self.peeked = true;
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for BomStrippingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if !self.peeked {
+            self.peek()?;
+        }
+        if self.head_position < self.head_length {
+            let held_back = &self.head[self.head_position..self.head_length];
+            let n = held_back.len().min(buf.len());
+            buf[..n].copy_from_slice(&held_back[..n]);
+            self.head_position += n;
+            return Ok(n);
+        }
+        self.inner.read(buf)
+    }
+}
+
+/// Copy the whole content of the reader to the writer, like `io::copy`, while determining its
+/// type. Returns the number of bytes that were copied, and the type of content.
+///
+/// ```
+/// use content_inspector::{copy_and_inspect, ContentType};
+///
+/// # fn main() -> std::io::Result<()> {
+/// let mut output = vec![];
+/// let (copied, content_type) = copy_and_inspect(&mut &b"Hello"[..], &mut output)?;
+///
+/// assert_eq!(5, copied);
+/// assert_eq!(ContentType::UTF_8, content_type);
+/// # Ok(())
+/// # }
+/// ```
+pub fn copy_and_inspect<R: Read + ?Sized, W: Write + ?Sized>(
+    reader: &mut R,
+    writer: &mut W,
+) -> io::Result<(u64, ContentType)> {
+    default_inspector().copy_and_inspect(reader, writer)
+}
+
+/// Scan the whole content of the given reader for a NULL byte, and return its offset. If
+/// `control_characters` is set, the offset of the first control character that does not commonly
+/// appear in text (see [`ByteClass::of`](enum.ByteClass.html#method.of)) is returned as well.
+///
+/// Unlike [`inspect_reader`](fn.inspect_reader.html), this reads until the end of the reader (or
+/// the first binary byte), in chunks of 64 KiB. NULL bytes are searched with `memchr` and control
+/// characters eight bytes at a time, so that the scan is mostly limited by the reader.
+///
+/// ```
+/// use content_inspector::contains_binary;
+///
+/// assert_eq!(None, contains_binary(&b"Hello\n"[..], false).unwrap());
+/// assert_eq!(Some(5), contains_binary(&b"Hello\x00"[..], false).unwrap());
+/// assert_eq!(Some(5), contains_binary(&b"Hello\x07\x00"[..], true).unwrap());
+/// ```
+pub fn contains_binary<R: Read>(
+    mut reader: R,
+    control_characters: bool,
+) -> io::Result<Option<u64>> {
+    let mut buffer = vec![0; SCAN_CHUNK_SIZE];
+    let mut offset = 0;
+    loop {
+        let chunk = match reader.read(&mut buffer) {
+            Ok(0) => return Ok(None),
+            Ok(n) => &buffer[..n],
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        let position = if control_characters {
+            simd::first_non_text_byte(chunk)
+        } else {
+            memchr(0x00, chunk)
+        };
+        if let Some(position) = position {
+            return Ok(Some(offset + position as u64));
+        }
+        offset += chunk.len() as u64;
+    }
+}
+
+/// Like [`inspect`](fn.inspect.html), but reads the content from the file at the given path. See
+/// [`Inspector::inspect_file`](struct.Inspector.html#method.inspect_file) for how directories
+/// and special files are handled.
+///
+/// ```no_run
+/// use content_inspector::inspect_file;
+///
+/// # fn main() -> std::io::Result<()> {
+/// assert!(inspect_file("Cargo.toml")?.is_text());
+/// # Ok(())
+/// # }
+/// ```
+pub fn inspect_file<P: AsRef<Path>>(path: P) -> io::Result<ContentType> {
+    default_inspector().inspect_file(path)
+}
+
+/// A reader that passes all content through from the wrapped reader, while determining its type.
+///
+/// Only the bytes that are examined by the `Inspector` are kept for the inspection, the content
+/// is not read ahead.
+///
+/// ```
+/// use std::io::{self, Read};
+/// use content_inspector::{ContentType, InspectingReader};
+///
+/// # fn main() -> io::Result<()> {
+/// let mut reader = InspectingReader::new(&b"Hello"[..]);
+/// let mut content = vec![];
+/// reader.read_to_end(&mut content)?;
+///
+/// assert_eq!(b"Hello", &content[..]);
+/// assert_eq!(Some(ContentType::UTF_8), reader.content_type());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct InspectingReader<R> {
+    inner: R,
+    streaming: StreamingInspector,
+    content_type: Option<ContentType>,
+}
+
+impl<R: Read> InspectingReader<R> {
+    /// Wraps the given reader, using the settings of [`inspect`](fn.inspect.html).
+    pub fn new(inner: R) -> InspectingReader<R> {
+        InspectingReader::with_inspector(default_inspector(), inner)
+    }
+
+    /// Wraps the given reader, using the settings of the given `Inspector`.
+    pub fn with_inspector(inspector: &Inspector, inner: R) -> InspectingReader<R> {
+        InspectingReader {
+            inner,
+            streaming: inspector.streaming(),
+            content_type: None,
+        }
+    }
+
+    /// The type of content, once enough content has been read to determine it or the end of the
+    /// wrapped reader has been reached.
+    pub fn content_type(&self) -> Option<ContentType> {
+        self.content_type
+    }
+
+    /// Returns a reference to the wrapped reader.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Returns the wrapped reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read> Read for InspectingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        if self.content_type.is_none() {
+            if n == 0 && !buf.is_empty() {
+                self.content_type = Some(self.streaming.result());
+            } else {
+                self.streaming.push(&buf[..n]);
+                self.content_type = self.streaming.current();
+            }
+        }
+        Ok(n)
+    }
+}
+
+/// A writer that passes all content through to the wrapped writer, while determining its type.
+///
+/// Only the bytes that were accepted by the wrapped writer are inspected, so short writes are
+/// handled like with any other writer.
+///
+/// ```
+/// use std::io::{self, Write};
+/// use content_inspector::{ContentType, InspectingWriter};
+///
+/// # fn main() -> io::Result<()> {
+/// let mut writer = InspectingWriter::new(vec![]);
+/// writer.write_all(b"\xFE\xFF\x00H")?;
+///
+/// assert_eq!(ContentType::UTF_16BE, writer.content_type());
+/// assert_eq!(b"\xFE\xFF\x00H", &writer.into_inner()[..]);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct InspectingWriter<W> {
+    inner: W,
+    streaming: StreamingInspector,
+}
+
+impl<W: Write> InspectingWriter<W> {
+    /// Wraps the given writer, using the settings of [`inspect`](fn.inspect.html).
+    pub fn new(inner: W) -> InspectingWriter<W> {
+        InspectingWriter::with_inspector(default_inspector(), inner)
+    }
+
+    /// Wraps the given writer, using the settings of the given `Inspector`.
+    pub fn with_inspector(inspector: &Inspector, inner: W) -> InspectingWriter<W> {
+        InspectingWriter {
+            inner,
+            streaming: inspector.streaming(),
+        }
+    }
+
+    /// The type of the content that has been written so far.
+    pub fn content_type(&self) -> ContentType {
+        self.streaming.result()
+    }
+
+    /// Returns a reference to the wrapped writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Returns the wrapped writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write> Write for InspectingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.streaming.push(&buf[..n]);
+        Ok(n)
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        let mut remaining = self.inner.write_vectored(bufs)?;
+        let n = remaining;
+        for buf in bufs {
+            if remaining == 0 {
+                break;
+            }
+            let len = buf.len().min(remaining);
+            self.streaming.push(&buf[..len]);
+            remaining -= len;
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+#[cfg_attr(
+    not(all(feature = "utf16", feature = "utf32", feature = "legacy-encodings")),
+    allow(dead_code, unused_imports)
+)]
+mod tests {
+    use super::{CHUNK_SIZE, SCAN_CHUNK_SIZE};
+    use crate::test_util::text_with_null_at;
+    use crate::{
+        contains_binary, copy_and_inspect, inspect_buf_read, inspect_file, inspect_reader,
+        inspect_reader_keep, inspect_reader_with_buf, inspect_seek, BomStrippingReader,
+        ContentType, ContentType::*, InspectingReader, InspectingWriter, Inspector, Strictness,
+    };
+    use std::env;
+    use std::fs::{self, File};
+    use std::io::{self, BufReader, Cursor, IoSlice, Read, Seek, SeekFrom, Write};
+    use std::path::Path;
+
+    /// A reader that yields at most `chunk_size` bytes per call.
+    struct ChunkedReader<'a> {
+        data: &'a [u8],
+        chunk_size: usize,
+        reads: usize,
+    }
+
+    impl<'a> Read for ChunkedReader<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.reads += 1;
+            let n = self.chunk_size.min(buf.len()).min(self.data.len());
+            buf[..n].copy_from_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+            Ok(n)
+        }
+    }
+
+    fn chunked(data: &[u8], chunk_size: usize) -> ChunkedReader<'_> {
+        ChunkedReader {
+            data,
+            chunk_size,
+            reads: 0,
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "utf16")]
+    fn test_inspect_reader_cursor() {
+        let png = include_bytes!("../testdata/test.png");
+        assert_eq!(BINARY, inspect_reader(Cursor::new(&png[..])).unwrap());
+        let utf16 = include_bytes!("../testdata/text_UTF-16LE-BOM.txt");
+        assert_eq!(UTF_16LE, inspect_reader(Cursor::new(&utf16[..])).unwrap());
+        assert_eq!(UTF_8, inspect_reader(Cursor::new(b"")).unwrap());
+
+        assert_eq!(
+            BINARY,
+            inspect_reader(&text_with_null_at(1023)[..]).unwrap()
+        );
+        assert_eq!(UTF_8, inspect_reader(&text_with_null_at(1024)[..]).unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "utf32")]
+    fn test_inspect_reader_chunked() {
+        let buffer = text_with_null_at(1023);
+        let mut reader = chunked(&buffer, 1);
+        assert_eq!(BINARY, inspect_reader(&mut reader).unwrap());
+        assert_eq!(1024, reader.reads);
+        assert_eq!(3000 - 1024, reader.data.len());
+
+        let utf32 = include_bytes!("../testdata/text_UTF-32BE-BOM.txt");
+        assert_eq!(UTF_32BE, inspect_reader(chunked(utf32, 3)).unwrap());
+
+        let buffer = text_with_null_at(1024);
+        assert_eq!(UTF_8, inspect_reader(chunked(&buffer, 100)).unwrap());
+    }
+
+    #[test]
+    fn test_inspect_reader_limits() {
+        let buffer = text_with_null_at(2500);
+        let inspector = Inspector::new().null_scan_limit(4096).build().unwrap();
+        assert_eq!(
+            BINARY,
+            inspector.inspect_reader(chunked(&buffer, 7)).unwrap()
+        );
+
+        let mut buffer = vec![b'a'; 100_000];
+        buffer.push(0xFF);
+        let paranoid = Inspector::new()
+            .strictness(Strictness::Paranoid)
+            .build()
+            .unwrap();
+        assert_eq!(
+            BINARY,
+            paranoid.inspect_reader(chunked(&buffer, 999)).unwrap()
+        );
+
+        let magic = Inspector::new()
+            .max_scan_size(4)
+            .magic_number(2000, b"MAGIC")
+            .build()
+            .unwrap();
+        let mut buffer = vec![b'a'; 2000];
+        buffer.extend_from_slice(b"MAGIC");
+        assert_eq!(BINARY, magic.inspect_reader(&buffer[..]).unwrap());
+    }
+
+    #[test]
+    fn test_inspect_reader_error() {
+        struct FailingReader(usize);
+
+        impl Read for FailingReader {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                match self.0 {
+                    0 => Err(io::Error::other("broken pipe")),
+                    _ => {
+                        self.0 -= 1;
+                        buf[0] = b'a';
+                        Ok(1)
+                    }
+                }
+            }
+        }
+
+        let error = inspect_reader(FailingReader(10)).unwrap_err();
+        assert_eq!("broken pipe", error.to_string());
+
+        struct InterruptedReader(bool);
+
+        impl Read for InterruptedReader {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                self.0 = !self.0;
+                if self.0 {
+                    Err(io::ErrorKind::Interrupted.into())
+                } else {
+                    buf.get_mut(0).map_or(Ok(0), |byte| {
+                        *byte = 0x00;
+                        Ok(1)
+                    })
+                }
+            }
+        }
+
+        assert_eq!(BINARY, inspect_reader(InterruptedReader(false)).unwrap());
+    }
+
+    fn strip_bom<R: Read>(mut reader: BomStrippingReader<R>) -> (Vec<u8>, Option<ContentType>) {
+        let mut content = vec![];
+        reader.read_to_end(&mut content).unwrap();
+        (content, reader.bom())
+    }
+
+    #[test]
+    fn test_bom_stripping_reader() {
+        let utf8 = include_bytes!("../testdata/text_UTF-8-BOM.txt");
+        for &chunk_size in &[1, 2, 3, 1000] {
+            let reader = BomStrippingReader::new(chunked(utf8, chunk_size));
+            assert_eq!((utf8[3..].to_vec(), Some(UTF_8_BOM)), strip_bom(reader));
+        }
+
+        let text = include_bytes!("../testdata/text_UTF-8.txt");
+        for &chunk_size in &[1, 1000] {
+            let reader = BomStrippingReader::new(chunked(text, chunk_size));
+            assert_eq!((text.to_vec(), None), strip_bom(reader));
+        }
+
+        // Shorter than the BOM
+        for content in &[&b""[..], b"\xEF", b"\xEF\xBB", b"\xEF\xBBa", b"\xFF"] {
+            let reader = BomStrippingReader::new(chunked(content, 1));
+            assert_eq!((content.to_vec(), None), strip_bom(reader));
+        }
+        let reader = BomStrippingReader::new(chunked(b"\xEF\xBB\xBF", 1));
+        assert_eq!((vec![], Some(UTF_8_BOM)), strip_bom(reader));
+
+        // The held-back bytes are returned into small buffers as well
+        let mut reader = BomStrippingReader::new(&b"\x00\x00\xFEa"[..]);
+        let mut byte = [0];
+        let mut content = vec![];
+        while reader.read(&mut byte).unwrap() == 1 {
+            content.push(byte[0]);
+        }
+        assert_eq!(b"\x00\x00\xFEa", &content[..]);
+    }
+
+    #[test]
+    fn test_bom_stripping_reader_utf16() {
+        let utf16 = include_bytes!("../testdata/text_UTF-16LE-BOM.txt");
+        let reader = BomStrippingReader::new(chunked(utf16, 1));
+        assert_eq!((utf16.to_vec(), Some(UTF_16LE)), strip_bom(reader));
+        let reader = BomStrippingReader::new(chunked(utf16, 3)).strip_all(true);
+        assert_eq!((utf16[2..].to_vec(), Some(UTF_16LE)), strip_bom(reader));
+
+        let utf32 = include_bytes!("../testdata/text_UTF-32LE-BOM.txt");
+        let reader = BomStrippingReader::new(chunked(utf32, 1)).strip_all(true);
+        assert_eq!((utf32[4..].to_vec(), Some(UTF_32LE)), strip_bom(reader));
+        let reader = BomStrippingReader::new(chunked(b"\xFF\xFE\x00", 1)).strip_all(true);
+        assert_eq!((vec![0x00], Some(UTF_16LE)), strip_bom(reader));
+    }
+
+    #[test]
+    #[cfg(feature = "utf16")]
+    fn test_copy_and_inspect() {
+        let mut output = vec![];
+        assert_eq!(
+            (0, UTF_8),
+            copy_and_inspect(&mut &b""[..], &mut output).unwrap()
+        );
+        assert!(output.is_empty());
+
+        let mut short = short_writer(3);
+        let result = copy_and_inspect(&mut chunked(b"\xFF\xFEH\x00", 1), &mut short).unwrap();
+        assert_eq!((4, UTF_16LE), result);
+        assert_eq!(b"\xFF\xFEH\x00", &short.data[..]);
+
+        for name in &[
+            "test.png",
+            "text_UTF-8.txt",
+            "text_UTF-32BE-BOM.txt",
+            "test_stage_limits.txt",
+        ] {
+            let content = fs::read(testdata(name)).unwrap();
+            let mut output = vec![];
+            let result = copy_and_inspect(&mut chunked(&content, 100), &mut output).unwrap();
+            assert_eq!(
+                (content.len() as u64, inspect_file(testdata(name)).unwrap()),
+                result
+            );
+            assert_eq!(content, output);
+        }
+
+        // A NULL byte beyond the scanned window
+        let buffer = text_with_null_at(1024);
+        let mut output = vec![];
+        let result = copy_and_inspect(&mut &buffer[..], &mut output).unwrap();
+        assert_eq!((3000, UTF_8), result);
+    }
+
+    #[test]
+    fn test_copy_and_inspect_errors() {
+        struct FailingWriter(usize);
+
+        impl Write for FailingWriter {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                if self.0 == 0 {
+                    return Err(io::Error::other("disk full"));
+                }
+                let n = self.0.min(buf.len());
+                self.0 -= n;
+                Ok(n)
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let buffer = vec![b'a'; 3 * CHUNK_SIZE];
+        let error = copy_and_inspect(&mut &buffer[..], &mut FailingWriter(10_000)).unwrap_err();
+        assert_eq!("disk full", error.to_string());
+
+        struct BrokenReader;
+
+        impl Read for BrokenReader {
+            fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::other("broken pipe"))
+            }
+        }
+
+        let mut output = vec![];
+        let error = copy_and_inspect(&mut (&b"abc"[..]).chain(BrokenReader), &mut output);
+        assert_eq!("broken pipe", error.unwrap_err().to_string());
+        assert_eq!(b"abc", &output[..]);
+    }
+
+    #[test]
+    fn test_contains_binary() {
+        let mut buffer = vec![b'a'; 5 * CHUNK_SIZE];
+        assert_eq!(None, contains_binary(&buffer[..], false).unwrap());
+        assert_eq!(None, contains_binary(&b""[..], false).unwrap());
+
+        for &offset in &[
+            0,
+            CHUNK_SIZE - 1,
+            CHUNK_SIZE,
+            3 * CHUNK_SIZE + 1,
+            5 * CHUNK_SIZE - 1,
+        ] {
+            buffer[offset] = 0x00;
+            for &chunk_size in &[1, 7, CHUNK_SIZE, 2 * CHUNK_SIZE] {
+                let result = contains_binary(chunked(&buffer, chunk_size), false).unwrap();
+                assert_eq!(Some(offset as u64), result);
+            }
+            buffer[offset] = b'a';
+        }
+
+        buffer[CHUNK_SIZE] = 0x00;
+        buffer[2] = 0x07;
+        buffer[1] = b'\t';
+        assert_eq!(
+            Some(CHUNK_SIZE as u64),
+            contains_binary(&buffer[..], false).unwrap()
+        );
+        assert_eq!(Some(2), contains_binary(&buffer[..], true).unwrap());
+
+        let png = include_bytes!("../testdata/test.png");
+        assert_eq!(Some(8), contains_binary(&png[..], false).unwrap());
+        let latin1 = include_bytes!("../testdata/text_Latin-1.txt");
+        assert_eq!(None, contains_binary(&latin1[..], true).unwrap());
+    }
+
+    #[test]
+    fn test_contains_binary_chunk_boundaries() {
+        let mut buffer = vec![b'a'; 3 * SCAN_CHUNK_SIZE];
+        for &offset in &[
+            SCAN_CHUNK_SIZE - 1,
+            SCAN_CHUNK_SIZE,
+            SCAN_CHUNK_SIZE + 1,
+            2 * SCAN_CHUNK_SIZE + 7,
+        ] {
+            for &byte in &[0x00, 0x07] {
+                buffer[offset] = byte;
+                for &chunk_size in &[SCAN_CHUNK_SIZE, 1000, 3 * SCAN_CHUNK_SIZE] {
+                    let expected = Some(offset as u64);
+                    let result = contains_binary(chunked(&buffer, chunk_size), true).unwrap();
+                    assert_eq!(expected, result);
+                    let result = contains_binary(chunked(&buffer, chunk_size), false).unwrap();
+                    assert_eq!(expected.filter(|_| byte == 0x00), result);
+                }
+            }
+            buffer[offset] = b'a';
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "utf16")]
+    fn test_inspect_seek() {
+        let mut buffer = text_with_null_at(100);
+        buffer[2500] = 0x00;
+        let mut cursor = Cursor::new(&buffer[..]);
+        assert_eq!(BINARY, inspect_seek(&mut cursor).unwrap());
+        assert_eq!(0, cursor.position());
+
+        // From the current position on
+        cursor.set_position(101);
+        assert_eq!(UTF_8, inspect_seek(&mut cursor).unwrap());
+        assert_eq!(101, cursor.position());
+        cursor.set_position(2000);
+        assert_eq!(BINARY, inspect_seek(&mut cursor).unwrap());
+        assert_eq!(2000, cursor.position());
+
+        // Less than the scanned window is left
+        cursor.set_position(2999);
+        assert_eq!(UTF_8, inspect_seek(&mut cursor).unwrap());
+        assert_eq!(2999, cursor.position());
+        cursor.set_position(5000);
+        assert_eq!(UTF_8, inspect_seek(&mut cursor).unwrap());
+        assert_eq!(5000, cursor.position());
+
+        let mut file = fs::File::open(testdata("text_UTF-16LE-BOM.txt")).unwrap();
+        assert_eq!(UTF_16LE, inspect_seek(&mut file).unwrap());
+        assert_eq!(0, file.stream_position().unwrap());
+        file.seek(SeekFrom::Start(2)).unwrap();
+        assert_eq!(BINARY, inspect_seek(&mut file).unwrap());
+        assert_eq!(2, file.stream_position().unwrap());
+    }
+
+    #[test]
+    fn test_inspect_seek_errors() {
+        /// A reader that fails to seek to the given position (and to read, if it is zero).
+        struct Unseekable(u64);
+
+        impl Read for Unseekable {
+            fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+                match self.0 {
+                    0 => Err(io::Error::other("read failed")),
+                    _ => Ok(0),
+                }
+            }
+        }
+
+        impl Seek for Unseekable {
+            fn seek(&mut self, position: SeekFrom) -> io::Result<u64> {
+                match position {
+                    SeekFrom::Current(0) => Ok(7),
+                    SeekFrom::Start(position) if position != self.0 => Ok(position),
+                    _ => Err(io::Error::other("seek failed")),
+                }
+            }
+        }
+
+        let error = inspect_seek(&mut Unseekable(7)).unwrap_err();
+        assert_eq!("seek failed", error.to_string());
+        let error = inspect_seek(&mut Unseekable(0)).unwrap_err();
+        assert_eq!("read failed", error.to_string());
+        assert_eq!(UTF_8, inspect_seek(&mut Unseekable(1)).unwrap());
+    }
+
+    #[test]
+    fn test_inspect_reader_keep() {
+        for name in &[
+            "test.png",
+            "text_UTF-8.txt",
+            "text_UTF-16LE-BOM.txt",
+            "test.pdf",
+        ] {
+            let content = fs::read(testdata(name)).unwrap();
+            for &chunk_size in &[1, 100, 100_000] {
+                let (content_type, prefix, reader) =
+                    inspect_reader_keep(chunked(&content, chunk_size)).unwrap();
+                assert_eq!(inspect_file(testdata(name)).unwrap(), content_type);
+                assert_eq!(content.len().min(1024), prefix.len());
+
+                let mut reconstructed = vec![];
+                Cursor::new(prefix)
+                    .chain(reader)
+                    .read_to_end(&mut reconstructed)
+                    .unwrap();
+                assert_eq!(content, reconstructed);
+            }
+        }
+
+        let (content_type, prefix, reader) = inspect_reader_keep(chunked(b"", 1)).unwrap();
+        assert_eq!((UTF_8, 0, 1), (content_type, prefix.len(), reader.reads));
+
+        let paranoid = Inspector::new()
+            .strictness(Strictness::Paranoid)
+            .build()
+            .unwrap();
+        let buffer = text_with_null_at(2999);
+        let (content_type, prefix, _) = paranoid.inspect_reader_keep(&buffer[..]).unwrap();
+        assert_eq!((BINARY, buffer), (content_type, prefix));
+    }
+
+    #[test]
+    #[cfg(all(feature = "utf16", feature = "utf32"))]
+    fn test_inspect_reader_with_buf() {
+        let mut scratch = [0; 1024];
+        let buffer = text_with_null_at(1023);
+        let mut reader = chunked(&buffer, 10);
+        assert_eq!(
+            BINARY,
+            inspect_reader_with_buf(&mut reader, &mut scratch).unwrap()
+        );
+        assert_eq!(3000 - 1024, reader.data.len());
+
+        let utf16 = include_bytes!("../testdata/text_UTF-16BE-BOM.txt");
+        let result = inspect_reader_with_buf(chunked(utf16, 1), &mut scratch).unwrap();
+        assert_eq!(UTF_16BE, result);
+        assert_eq!(
+            UTF_8,
+            inspect_reader_with_buf(&b""[..], &mut scratch).unwrap()
+        );
+
+        // Tiny buffers only see the start of the content
+        let mut tiny = [0; 4];
+        let utf32 = include_bytes!("../testdata/text_UTF-32LE-BOM.txt");
+        let result = inspect_reader_with_buf(chunked(utf32, 1), &mut tiny).unwrap();
+        assert_eq!(UTF_32LE, result);
+        let buffer = text_with_null_at(4);
+        let mut reader = chunked(&buffer, 3);
+        assert_eq!(
+            UTF_8,
+            inspect_reader_with_buf(&mut reader, &mut tiny).unwrap()
+        );
+        assert_eq!(3000 - 4, reader.data.len());
+        assert_eq!(UTF_8, inspect_reader_with_buf(&b"a"[..], &mut []).unwrap());
+
+        // Larger buffers are only filled up to the examined window
+        let mut large = [0; 4096];
+        let buffer = text_with_null_at(2000);
+        let mut reader = chunked(&buffer, 1000);
+        assert_eq!(
+            UTF_8,
+            inspect_reader_with_buf(&mut reader, &mut large).unwrap()
+        );
+        assert_eq!(3000 - 1024, reader.data.len());
+        let inspector = Inspector::new().max_scan_size(4096).build().unwrap();
+        let result = inspector.inspect_reader_with_buf(&buffer[..], &mut large);
+        assert_eq!(BINARY, result.unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "utf16")]
+    fn test_inspect_buf_read() {
+        for &(name, expected) in &[
+            ("test.png", BINARY),
+            ("text_UTF-16BE-BOM.txt", UTF_16BE),
+            ("text_UTF-8.txt", UTF_8),
+        ] {
+            let content = fs::read(testdata(name)).unwrap();
+            let mut reader = BufReader::new(chunked(&content, 100_000));
+            assert_eq!(expected, inspect_buf_read(&mut reader).unwrap());
+            assert_eq!(expected, inspect_buf_read(&mut reader).unwrap());
+
+            let mut read = vec![];
+            reader.read_to_end(&mut read).unwrap();
+            assert_eq!(content, read);
+        }
+
+        // Only the first chunk is buffered
+        let buffer = text_with_null_at(500);
+        let mut reader = BufReader::new(chunked(&buffer, 100));
+        assert_eq!(UTF_8, inspect_buf_read(&mut reader).unwrap());
+        let mut reader = BufReader::with_capacity(10, &buffer[495..]);
+        assert_eq!(BINARY, inspect_buf_read(&mut reader).unwrap());
+        let mut read = vec![];
+        reader.read_to_end(&mut read).unwrap();
+        assert_eq!(&buffer[495..], &read[..]);
+
+        let mut reader = Cursor::new(b"");
+        assert_eq!(UTF_8, inspect_buf_read(&mut reader).unwrap());
+    }
+
+    fn testdata(name: &str) -> ::std::path::PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("testdata")
+            .join(name)
+    }
+
+    #[test]
+    #[cfg(all(feature = "utf16", feature = "legacy-encodings"))]
+    fn test_inspect_file() {
+        assert_eq!(BINARY, inspect_file(testdata("test.png")).unwrap());
+        assert_eq!(UTF_8, inspect_file(testdata("text_UTF-8.txt")).unwrap());
+        assert_eq!(
+            UTF_16BE,
+            inspect_file(testdata("text_UTF-16BE-BOM.txt")).unwrap()
+        );
+
+        let inspector = Inspector::new().latin1_fallback(true).build().unwrap();
+        assert_eq!(
+            LATIN_1,
+            inspector
+                .inspect_file(testdata("text_Latin-1.txt"))
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_inspect_file_errors() {
+        let error = inspect_file(testdata("")).unwrap_err();
+        assert_eq!(io::ErrorKind::IsADirectory, error.kind());
+        assert!(error.to_string().ends_with("is a directory"));
+
+        let error = inspect_file(testdata("does-not-exist")).unwrap_err();
+        assert_eq!(io::ErrorKind::NotFound, error.kind());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_inspect_file_fifo() {
+        use std::process::Command;
+
+        let path = env::temp_dir().join(format!("content_inspector_fifo_{}", ::std::process::id()));
+        let created = Command::new("mkfifo")
+            .arg(&path)
+            .status()
+            .is_ok_and(|status| status.success());
+        if !created {
+            // mkfifo is not available
+            return;
+        }
+
+        let result = inspect_file(&path);
+        fs::remove_file(&path).unwrap();
+        let error = result.unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidInput, error.kind());
+        assert!(error.to_string().ends_with("is not a regular file"));
+    }
+
+    #[test]
+    fn test_inspecting_reader() {
+        let png = include_bytes!("../testdata/test.png");
+        let mut reader = InspectingReader::new(chunked(png, 1));
+        let mut content = vec![];
+        let mut byte = [0];
+        while reader.content_type().is_none() {
+            assert_eq!(1, reader.read(&mut byte).unwrap());
+            content.push(byte[0]);
+        }
+        assert_eq!(Some(BINARY), reader.content_type());
+        assert!(content.len() < 100);
+
+        io::copy(&mut reader, &mut content).unwrap();
+        assert_eq!(&png[..], &content[..]);
+        assert_eq!(Some(BINARY), reader.content_type());
+        assert!(reader.into_inner().data.is_empty());
+
+        let buffer = text_with_null_at(1024);
+        let mut reader = InspectingReader::new(chunked(&buffer, 7));
+        let mut content = vec![];
+        reader.read_to_end(&mut content).unwrap();
+        assert_eq!(buffer, content);
+        assert_eq!(Some(UTF_8), reader.content_type());
+        assert_eq!(1024, reader.streaming.buffer_len());
+    }
+
+    #[test]
+    #[cfg(all(feature = "utf16", feature = "legacy-encodings"))]
+    fn test_inspecting_reader_early_eof() {
+        let mut reader = InspectingReader::new(chunked(b"short text", 3));
+        let mut buffer = [0; 4];
+        assert_eq!(3, reader.read(&mut buffer).unwrap());
+        assert_eq!(None, reader.content_type());
+        // Reading into an empty buffer does not signal the end of the reader
+        assert_eq!(0, reader.read(&mut []).unwrap());
+        assert_eq!(None, reader.content_type());
+
+        let mut content = vec![];
+        reader.read_to_end(&mut content).unwrap();
+        assert_eq!(b"rt text", &content[..]);
+        assert_eq!(Some(UTF_8), reader.content_type());
+
+        let utf16 = include_bytes!("../testdata/text_UTF-16LE-BOM.txt");
+        let mut reader = InspectingReader::new(chunked(&utf16[..2], 1));
+        io::copy(&mut reader, &mut io::sink()).unwrap();
+        assert_eq!(Some(UTF_16LE), reader.content_type());
+
+        let mut reader = InspectingReader::new(chunked(b"", 1));
+        assert_eq!(0, reader.read(&mut buffer).unwrap());
+        assert_eq!(Some(UTF_8), reader.content_type());
+
+        let inspector = Inspector::new().latin1_fallback(true).build().unwrap();
+        let latin1 = include_bytes!("../testdata/text_Latin-1.txt");
+        let mut reader = InspectingReader::with_inspector(&inspector, &latin1[..]);
+        io::copy(&mut reader, &mut io::sink()).unwrap();
+        assert_eq!(Some(LATIN_1), reader.content_type());
+    }
+
+    /// A writer that accepts at most `chunk_size` bytes per call.
+    #[derive(Default)]
+    struct ShortWriter {
+        data: Vec<u8>,
+        chunk_size: usize,
+        flushes: usize,
+    }
+
+    impl Write for ShortWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            let n = self.chunk_size.min(buf.len());
+            self.data.extend_from_slice(&buf[..n]);
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.flushes += 1;
+            Ok(())
+        }
+    }
+
+    fn short_writer(chunk_size: usize) -> ShortWriter {
+        ShortWriter {
+            chunk_size,
+            ..ShortWriter::default()
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "utf32")]
+    fn test_inspecting_writer_copy() {
+        let paths = fs::read_dir(testdata(""))
+            .unwrap()
+            .map(|entry| entry.unwrap().path());
+        for path in paths.filter(|path| path.is_file()) {
+            let expected = fs::read(&path).unwrap();
+
+            let mut writer = InspectingWriter::new(vec![]);
+            io::copy(&mut File::open(&path).unwrap(), &mut writer).unwrap();
+            assert_eq!(inspect_file(&path).unwrap(), writer.content_type());
+            assert_eq!(expected, writer.into_inner());
+
+            let mut writer = InspectingWriter::new(short_writer(5));
+            io::copy(&mut chunked(&expected, 7), &mut writer).unwrap();
+            assert_eq!(inspect_file(&path).unwrap(), writer.content_type());
+            assert_eq!(expected, writer.into_inner().data);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "utf32")]
+    fn test_inspecting_writer_short_writes() {
+        let utf32 = include_bytes!("../testdata/text_UTF-32BE-BOM.txt");
+        let mut writer = InspectingWriter::new(short_writer(1));
+        assert_eq!(1, writer.write(utf32).unwrap());
+        assert_eq!(1, writer.write(&utf32[1..]).unwrap());
+        writer.flush().unwrap();
+        // Only "\x00\x00" has been accepted so far
+        assert_eq!(BINARY, writer.content_type());
+
+        writer.write_all(&utf32[2..]).unwrap();
+        writer.flush().unwrap();
+        assert_eq!(UTF_32BE, writer.content_type());
+        let inner = writer.into_inner();
+        assert_eq!(2, inner.flushes);
+        assert_eq!(&utf32[..], &inner.data[..]);
+    }
+
+    #[test]
+    #[cfg(all(feature = "utf16", feature = "utf32"))]
+    fn test_inspecting_writer_vectored() {
+        let slices = [
+            IoSlice::new(b"\xFF"),
+            IoSlice::new(b""),
+            IoSlice::new(b"\xFE\x00"),
+            IoSlice::new(b"\x00H\x00\x00\x00"),
+        ];
+        let mut writer = InspectingWriter::new(vec![]);
+        assert_eq!(8, writer.write_vectored(&slices).unwrap());
+        assert_eq!(UTF_32LE, writer.content_type());
+
+        // The default implementation only writes the first non-empty slice
+        let mut writer = InspectingWriter::new(short_writer(10));
+        assert_eq!(1, writer.write_vectored(&slices).unwrap());
+        assert_eq!(UTF_8, writer.content_type());
+        assert_eq!(2, writer.write_vectored(&slices[2..]).unwrap());
+        assert_eq!(UTF_16LE, writer.content_type());
+        assert_eq!(5, writer.write_vectored(&slices[3..]).unwrap());
+        assert_eq!(UTF_32LE, writer.content_type());
+        assert_eq!(b"\xFF\xFE\x00\x00H\x00\x00\x00", &writer.get_ref().data[..]);
+    }
+}
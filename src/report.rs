@@ -0,0 +1,181 @@
+use alloc::boxed::Box;
+use core::error::Error;
+use core::fmt;
+use core::mem;
+
+use crate::{BinaryFormat, ContentType};
+
+/// The reason for the verdict in an [`InspectionReport`](struct.InspectionReport.html). New
+/// stages of the detection add reasons, so a `match` needs a wildcard arm.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Reason {
+    /// The buffer is empty.
+    EmptyBuffer,
+
+    /// A byte order mark was found at the start of the buffer.
+    ByteOrderMark,
+
+    /// The zero bytes in the scanned window follow the pattern of UTF-16 encoded text.
+    Utf16Pattern,
+
+    /// More NULL bytes than allowed were found in the scanned window.
+    NullBytes,
+
+    /// The buffer starts with the magic number of a known binary format.
+    MagicNumber,
+
+    /// Too many control characters were found in the scanned window.
+    ControlCharacters,
+
+    /// The fraction of printable bytes in the scanned window is below the minimum.
+    PrintableRatio,
+
+    /// The scanned window (or buffer) contains invalid UTF-8 sequences.
+    InvalidUtf8,
+
+    /// The content after the byte order mark is not valid in the indicated encoding.
+    InvalidEncoding,
+
+    /// A custom [`Heuristic`](trait.Heuristic.html) determined the type of content.
+    Heuristic(&'static str),
+
+    /// No indications of binary content were found.
+    NoBinaryIndicators,
+}
+
+/// Detailed information about the content of a buffer, see
+/// [`inspect_detailed`](fn.inspect_detailed.html).
+///
+/// The report is a plain `Copy` value that refers to nothing but `'static` data, so creating one
+/// does not allocate and it can be logged for every inspected buffer.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct InspectionReport {
+    /// The type of content that was detected.
+    pub content_type: ContentType,
+
+    /// The type of content that was detected before it was mapped to the fallback type, see
+    /// [`InspectorBuilder::allowed_types`](struct.InspectorBuilder.html#method.allowed_types).
+    /// This is equal to `content_type` if the detected type is allowed.
+    pub detected_type: ContentType,
+
+    /// The reason for the detected type of content.
+    pub reason: Reason,
+
+    /// The number of NULL bytes in the scanned window. This is zero if a byte order mark was
+    /// found, since the scan is skipped in that case.
+    pub null_bytes: usize,
+
+    /// The offset of the first NULL byte in the scanned window, if any.
+    pub first_null_offset: Option<usize>,
+
+    /// The length of the byte order mark at the start of the buffer, or zero if there is none.
+    pub bom_length: usize,
+
+    /// The number of control characters in the scanned window, if the control character
+    /// heuristic is enabled or a minimum printable ratio is set.
+    pub control_characters: usize,
+
+    /// The number of printable bytes (neither NULL bytes nor control characters) in the scanned
+    /// window, not counting a UTF-8 byte order mark, if a minimum printable ratio is set or the
+    /// control character heuristic is enabled.
+    pub printable_bytes: usize,
+
+    /// The offset of the first invalid UTF-8 sequence in the scanned window, if the Latin-1
+    /// fallback or UTF-8 validation is enabled (see
+    /// [`Strictness`](enum.Strictness.html)).
+    pub invalid_utf8_offset: Option<usize>,
+
+    /// The binary format, if the magic number of a known format was found.
+    pub binary_format: Option<BinaryFormat>,
+
+    /// The number of bytes that were scanned for NULL bytes (and control characters).
+    pub scanned_bytes: usize,
+
+    /// The number of bytes that were validated as UTF-8 (or in the encoding of a byte order mark),
+    /// if the Latin-1 fallback or the encoding validation is enabled.
+    pub validated_bytes: usize,
+
+    /// The number of bytes that were examined for UTF-16 without a byte order mark, if enabled.
+    pub sniffed_bytes: usize,
+
+    /// The number of bytes at the start of the buffer that were examined by any of the built-in
+    /// stages, from the byte order mark and magic number checks to the scans and validations
+    /// above. Custom [`Heuristic`](trait.Heuristic.html)s are not taken into account.
+    pub bytes_examined: usize,
+}
+
+// The report is returned by value, so it should stay small
+const _: () = assert!(mem::size_of::<InspectionReport>() <= 24 * mem::size_of::<usize>());
+
+/// The error of [`Inspector::check`](struct.Inspector.html#method.check) if the type of content
+/// is not the expected one. The `Display` implementation gives a human-readable description.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mismatch {
+    /// The expected type of content.
+    pub expected: ContentType,
+
+    /// The report for the inspected buffer.
+    pub report: Box<InspectionReport>,
+}
+
+impl Mismatch {
+    /// The type of content that was detected instead.
+    pub fn detected(&self) -> ContentType {
+        self.report.content_type
+    }
+
+    /// The reason for the detected type of content.
+    pub fn reason(&self) -> Reason {
+        self.report.reason
+    }
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let report = &self.report;
+        write!(
+            f,
+            "expected {}, found {} (",
+            self.expected, report.content_type
+        )?;
+        match report.reason {
+            Reason::EmptyBuffer => write!(f, "empty buffer"),
+            Reason::ByteOrderMark => {
+                write!(f, "{}-byte byte order mark at the start", report.bom_length)
+            }
+            Reason::Utf16Pattern => write!(f, "zero bytes follow the pattern of UTF-16"),
+            Reason::NullBytes => match report.first_null_offset {
+                Some(offset) => write!(
+                    f,
+                    "{} NULL byte(s), the first at offset {}",
+                    report.null_bytes, offset
+                ),
+                None => write!(f, "{} NULL byte(s)", report.null_bytes),
+            },
+            Reason::MagicNumber => match report.binary_format {
+                Some(format) => write!(f, "magic number of format {:?}", format),
+                None => write!(f, "magic number"),
+            },
+            Reason::ControlCharacters => write!(
+                f,
+                "{} control character(s) in the first {} bytes",
+                report.control_characters, report.scanned_bytes
+            ),
+            Reason::PrintableRatio => write!(
+                f,
+                "only {} printable byte(s) in the first {} bytes",
+                report.printable_bytes, report.scanned_bytes
+            ),
+            Reason::InvalidUtf8 | Reason::InvalidEncoding => match report.invalid_utf8_offset {
+                Some(offset) => write!(f, "invalid UTF-8 at offset {}", offset),
+                None => write!(f, "invalid {}", report.detected_type),
+            },
+            Reason::Heuristic(name) => write!(f, "determined by {}", name),
+            Reason::NoBinaryIndicators => write!(f, "no indications of binary content"),
+        }?;
+        write!(f, ")")
+    }
+}
+
+impl Error for Mismatch {}
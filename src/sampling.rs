@@ -0,0 +1,274 @@
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use crate::reader::open_regular_file;
+use crate::{default_inspector, ContentType, Inspector};
+
+/// The offsets of `windows` evenly spaced windows of `window_size` bytes in content of the given
+/// length, including the first and the last window. Windows that would start at the same offset
+/// are only included once.
+fn window_offsets(length: u64, windows: usize, window_size: usize) -> Vec<u64> {
+    let last = length.saturating_sub(window_size as u64);
+    let windows = windows.max(1) as u128;
+    let mut offsets: Vec<u64> = (0..windows)
+        .map(|i| {
+            if windows == 1 {
+                0
+            } else {
+                (u128::from(last) * i / (windows - 1)) as u64
+            }
+        })
+        .collect();
+    offsets.dedup();
+    offsets
+}
+
+/// Group the windows at the given (sorted) offsets into ranges of content that can be read at
+/// once, such that no byte is read twice. Returns the start and the end of each range.
+fn read_ranges(offsets: &[u64], window_size: usize, length: u64) -> Vec<(u64, u64)> {
+    let mut ranges: Vec<(u64, u64)> = vec![];
+    for &offset in offsets {
+        let end = length.min(offset.saturating_add(window_size as u64));
+        match ranges.last_mut() {
+            Some(range) if offset <= range.1 => range.1 = end,
+            _ => ranges.push((offset, end)),
+        }
+    }
+    ranges
+}
+
+/// Skip the continuation bytes of a UTF-8 sequence at the start of a window that does not start
+/// at the beginning of the content.
+fn skip_partial_character(window: &[u8]) -> &[u8] {
+    let partial = window
+        .iter()
+        .take(3)
+        .take_while(|&&byte| byte & 0xC0 == 0x80)
+        .count();
+    &window[partial..]
+}
+
+impl Inspector {
+    /// Like [`inspect_file`](#method.inspect_file), but examines `windows` evenly spaced windows
+    /// of `window_size` bytes throughout the file (always including the first and the last one)
+    /// instead of only its start. This finds binary content that has been appended to a large
+    /// text file, for example.
+    ///
+    /// Each window is classified like a buffer that is passed to [`inspect`](#method.inspect),
+    /// so only the scanned window of it is examined. If any window is binary, the file is
+    /// `BINARY`, otherwise it has the type of the first window. UTF-16 and UTF-32 files can only
+    /// be recognized by the first window, so the other windows are not examined for them.
+    ///
+    /// Files that are smaller than the sampling plan are read once, overlapping windows are not
+    /// read twice. A `window_size` of zero results in an error of kind `InvalidInput`.
+    pub fn inspect_file_sampled<P: AsRef<Path>>(
+        &self,
+        path: P,
+        windows: usize,
+        window_size: usize,
+    ) -> io::Result<ContentType> {
+        let mut file = open_regular_file(path.as_ref())?;
+        let length = file.metadata()?.len();
+        self.inspect_sampled(&mut file, length, windows, window_size)
+    }
+
+    fn inspect_sampled<R: Read + Seek>(
+        &self,
+        reader: &mut R,
+        length: u64,
+        windows: usize,
+        window_size: usize,
+    ) -> io::Result<ContentType> {
+        if window_size == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the size of the sampled windows must not be zero",
+            ));
+        }
+
+        let offsets = window_offsets(length, windows, window_size);
+        let mut remaining = offsets.iter().peekable();
+        let mut first_type = None;
+        let mut buffer = vec![];
+        for (start, end) in read_ranges(&offsets, window_size, length) {
+            reader.seek(SeekFrom::Start(start))?;
+            buffer.clear();
+            // The file could have become shorter in the meantime
+            reader.take(end - start).read_to_end(&mut buffer)?;
+
+            while let Some(&offset) = remaining.next_if(|&&offset| offset < end) {
+                let begin = ((offset - start) as usize).min(buffer.len());
+                let window = &buffer[begin..buffer.len().min(begin + window_size)];
+                let content_type = match first_type {
+                    None => self.inspect(window),
+                    Some(_) => self.inspect(skip_partial_character(window)),
+                };
+                match (first_type, content_type) {
+                    (_, ContentType::BINARY) => return Ok(ContentType::BINARY),
+                    (
+                        None,
+                        ContentType::UTF_16LE
+                        | ContentType::UTF_16BE
+                        | ContentType::UTF_32LE
+                        | ContentType::UTF_32BE,
+                    ) => return Ok(content_type),
+                    (None, _) => first_type = Some(content_type),
+                    _ => {}
+                }
+            }
+        }
+        Ok(first_type.unwrap_or_else(|| self.inspect(b"")))
+    }
+}
+
+/// Like [`inspect_file`](fn.inspect_file.html), but examines `windows` evenly spaced windows of
+/// `window_size` bytes throughout the file. See
+/// [`Inspector::inspect_file_sampled`](struct.Inspector.html#method.inspect_file_sampled) for
+/// details.
+pub fn inspect_file_sampled<P: AsRef<Path>>(
+    path: P,
+    windows: usize,
+    window_size: usize,
+) -> io::Result<ContentType> {
+    default_inspector().inspect_file_sampled(path, windows, window_size)
+}
+
+#[cfg(test)]
+#[cfg_attr(
+    not(all(feature = "utf16", feature = "utf32", feature = "legacy-encodings")),
+    allow(dead_code, unused_imports)
+)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::io::{self, Cursor, Read, Seek, SeekFrom};
+    use std::path::Path;
+
+    use super::{read_ranges, window_offsets};
+    use crate::{inspect_file, inspect_file_sampled, ContentType::*, Inspector, Strictness};
+
+    #[test]
+    fn test_sampling_plan() {
+        assert_eq!(vec![0, 250, 500, 750, 1000], window_offsets(1100, 5, 100));
+        assert_eq!(vec![0], window_offsets(1100, 1, 100));
+        assert_eq!(vec![0], window_offsets(1100, 0, 100));
+        assert_eq!(vec![0], window_offsets(50, 5, 100));
+        assert_eq!(vec![0, 1, 2], window_offsets(102, 5, 100));
+        assert_eq!(vec![0, 1 << 40], window_offsets((1 << 40) + 10, 2, 10));
+
+        assert_eq!(
+            vec![(0, 100), (250, 350), (500, 600)],
+            read_ranges(&[0, 250, 500], 100, 600)
+        );
+        assert_eq!(vec![(0, 102)], read_ranges(&[0, 1, 2], 100, 102));
+        assert_eq!(vec![(0, 50)], read_ranges(&[0], 100, 50));
+        assert_eq!(
+            vec![(0, 150), (200, 250)],
+            read_ranges(&[0, 50, 200], 100, 250)
+        );
+    }
+
+    /// A reader that counts the bytes that are read.
+    struct CountingReader {
+        inner: Cursor<Vec<u8>>,
+        bytes_read: usize,
+    }
+
+    impl Read for CountingReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = self.inner.read(buf)?;
+            self.bytes_read += n;
+            Ok(n)
+        }
+    }
+
+    impl Seek for CountingReader {
+        fn seek(&mut self, position: SeekFrom) -> io::Result<u64> {
+            self.inner.seek(position)
+        }
+    }
+
+    fn inspect_counting(
+        content: Vec<u8>,
+        windows: usize,
+        window_size: usize,
+    ) -> (crate::ContentType, usize) {
+        let length = content.len() as u64;
+        let mut reader = CountingReader {
+            inner: Cursor::new(content),
+            bytes_read: 0,
+        };
+        let content_type = Inspector::default()
+            .inspect_sampled(&mut reader, length, windows, window_size)
+            .unwrap();
+        (content_type, reader.bytes_read)
+    }
+
+    #[test]
+    #[cfg(feature = "utf16")]
+    fn test_inspect_sampled() {
+        // Overlapping windows are only read once
+        let mut content = vec![b'a'; 3000];
+        assert_eq!((UTF_8, 3000), inspect_counting(content.clone(), 10, 1024));
+        assert_eq!(
+            (UTF_8, 100),
+            inspect_counting(content[..100].to_vec(), 3, 1024)
+        );
+        assert_eq!((UTF_8, 0), inspect_counting(vec![], 3, 1024));
+        assert_eq!((UTF_8, 3 * 500), inspect_counting(content.clone(), 3, 500));
+
+        content[2999] = 0x00;
+        assert_eq!((BINARY, 3 * 500), inspect_counting(content.clone(), 3, 500));
+        assert_eq!((UTF_8, 500), inspect_counting(content.clone(), 1, 500));
+
+        // No further windows are read once the result is clear
+        content[0] = 0x00;
+        assert_eq!((BINARY, 500), inspect_counting(content.clone(), 3, 500));
+        let utf16 = include_bytes!("../testdata/text_UTF-16LE-BOM.txt").to_vec();
+        assert_eq!((UTF_16LE, 10), inspect_counting(utf16, 3, 10));
+
+        // A window in the middle of a UTF-8 character
+        let strict = Inspector::new()
+            .strictness(Strictness::Strict)
+            .build()
+            .unwrap();
+        let content = "\u{2713}".repeat(1000).into_bytes();
+        let length = content.len() as u64;
+        let content_type = strict
+            .inspect_sampled(&mut Cursor::new(content), length, 5, 100)
+            .unwrap();
+        assert_eq!(UTF_8, content_type);
+    }
+
+    #[test]
+    fn test_inspect_file_sampled() {
+        let path = env::temp_dir().join(format!(
+            "content_inspector_sampled_{}",
+            ::std::process::id()
+        ));
+        let mut content = b"Some text\n".repeat(500_000);
+        content.extend((0..5_000_000u32).map(|i| (i % 251) as u8));
+        fs::write(&path, &content).unwrap();
+        let results = (
+            inspect_file(&path).unwrap(),
+            inspect_file_sampled(&path, 5, 1024).unwrap(),
+            inspect_file_sampled(&path, 1, 1024).unwrap(),
+        );
+        fs::remove_file(&path).unwrap();
+        assert_eq!((UTF_8, BINARY, UTF_8), results);
+
+        let testdata = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata");
+        for name in &["test.png", "text_UTF-8.txt", "text_UTF-32BE-BOM.txt"] {
+            let path = testdata.join(name);
+            assert_eq!(
+                inspect_file(&path).unwrap(),
+                inspect_file_sampled(&path, 4, 1024).unwrap()
+            );
+        }
+
+        let error = inspect_file_sampled(testdata.join("test.png"), 4, 0).unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidInput, error.kind());
+        let error = inspect_file_sampled(&testdata, 4, 1024).unwrap_err();
+        assert_eq!(io::ErrorKind::IsADirectory, error.kind());
+    }
+}
@@ -0,0 +1,168 @@
+//! The search for bytes, with the `memchr` crate (the default `memchr` feature) or with a portable
+//! fallback without dependencies that classifies eight bytes at once (SWAR). Both have the same
+//! results.
+
+#[cfg(feature = "memchr")]
+pub(crate) use memchr::{memchr, memchr2, memchr_iter};
+
+#[cfg(not(feature = "memchr"))]
+pub(crate) use self::fallback::{memchr, memchr2, memchr_iter};
+
+#[cfg_attr(all(feature = "memchr", not(test)), allow(dead_code))]
+pub(crate) mod fallback {
+    use core::convert::TryInto;
+
+    const ONES: u64 = 0x0101_0101_0101_0101;
+    const HIGH: u64 = 0x8080_8080_8080_8080;
+
+    /// The high bits of the bytes of the word that are `0x00` (the classic `haszero` trick). A
+    /// borrow can set the bits above a matching byte, but the lowest bit is always exact.
+    fn zero_bytes(word: u64) -> u64 {
+        word.wrapping_sub(ONES) & !word & HIGH
+    }
+
+    /// The offset of the first byte of the haystack for which `byte_matches` is true. The
+    /// middle part is read as aligned words, for which `word_matches` has to return a mask of the
+    /// matching bytes (exact for the lowest set bit).
+    #[inline(always)]
+    fn find<W, B>(haystack: &[u8], word_matches: W, byte_matches: B) -> Option<usize>
+    where
+        W: Fn(u64) -> u64,
+        B: Fn(u8) -> bool,
+    {
+        let head = haystack.as_ptr().align_offset(8).min(haystack.len());
+        if let Some(position) = haystack[..head].iter().position(|&byte| byte_matches(byte)) {
+            return Some(position);
+        }
+
+        // Two words at a time, which are rarely both checked
+        let mut pairs = haystack[head..].chunks_exact(16);
+        for (i, pair) in pairs.by_ref().enumerate() {
+            let low = word_matches(u64::from_le_bytes(pair[..8].try_into().unwrap_or_default()));
+            let high = word_matches(u64::from_le_bytes(pair[8..].try_into().unwrap_or_default()));
+            if low | high != 0 {
+                let offset = if low != 0 {
+                    low.trailing_zeros() as usize / 8
+                } else {
+                    8 + high.trailing_zeros() as usize / 8
+                };
+                return Some(head + 16 * i + offset);
+            }
+        }
+
+        let tail = pairs.remainder();
+        tail.iter()
+            .position(|&byte| byte_matches(byte))
+            .map(|position| haystack.len() - tail.len() + position)
+    }
+
+    /// The offset of the first occurrence of `needle`, like `memchr::memchr`.
+    pub(crate) fn memchr(needle: u8, haystack: &[u8]) -> Option<usize> {
+        let pattern = ONES * u64::from(needle);
+        find(
+            haystack,
+            |word| zero_bytes(word ^ pattern),
+            |byte| byte == needle,
+        )
+    }
+
+    /// The offset of the first occurrence of either needle, like `memchr::memchr2`.
+    pub(crate) fn memchr2(needle1: u8, needle2: u8, haystack: &[u8]) -> Option<usize> {
+        let (pattern1, pattern2) = (ONES * u64::from(needle1), ONES * u64::from(needle2));
+        find(
+            haystack,
+            |word| zero_bytes(word ^ pattern1) | zero_bytes(word ^ pattern2),
+            |byte| byte == needle1 || byte == needle2,
+        )
+    }
+
+    /// An iterator over the offsets of all occurrences of `needle`, like `memchr::memchr_iter`.
+    pub(crate) fn memchr_iter(needle: u8, haystack: &[u8]) -> Memchr<'_> {
+        Memchr {
+            needle,
+            haystack,
+            offset: 0,
+        }
+    }
+
+    pub(crate) struct Memchr<'a> {
+        needle: u8,
+        haystack: &'a [u8],
+        offset: usize,
+    }
+
+    impl Iterator for Memchr<'_> {
+        type Item = usize;
+
+        fn next(&mut self) -> Option<usize> {
+            let position = self.offset + memchr(self.needle, &self.haystack[self.offset..])?;
+            self.offset = position + 1;
+            Some(position)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fallback;
+
+    #[test]
+    fn test_fallback_every_position() {
+        // All lengths and alignments, with the needle at every position (and none)
+        let background = vec![b'a'; 128 + 8];
+        for start in 0..8 {
+            for length in 0..128 {
+                for position in (0..length).map(Some).chain(Some(None)) {
+                    let mut buffer = background.clone();
+                    if let Some(position) = position {
+                        buffer[start + position] = 0x00;
+                        // A second occurrence later in the same word, and bytes that a borrow
+                        // would turn into false positives
+                        if start + position + 2 < buffer.len() {
+                            buffer[start + position + 1] = 0x01;
+                            buffer[start + position + 2] = 0x00;
+                        }
+                    }
+                    let haystack = &buffer[start..start + length];
+                    let expected = haystack.iter().position(|&byte| byte == 0x00);
+                    assert_eq!(expected, fallback::memchr(0x00, haystack), "{:?}", haystack);
+                    assert_eq!(
+                        expected,
+                        fallback::memchr2(0x00, b'\n', haystack),
+                        "{:?}",
+                        haystack
+                    );
+                    assert_eq!(
+                        haystack
+                            .iter()
+                            .enumerate()
+                            .filter(|&(_, &byte)| byte == 0x00)
+                            .map(|(i, _)| i)
+                            .collect::<Vec<_>>(),
+                        fallback::memchr_iter(0x00, haystack).collect::<Vec<_>>()
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_fallback_every_byte() {
+        let buffer: Vec<u8> = (0..=255).chain((0..=255).rev()).collect();
+        for needle in 0..=255 {
+            for start in 0..8 {
+                let haystack = &buffer[start..];
+                let expected = |needle| haystack.iter().position(|&byte| byte == needle);
+                assert_eq!(expected(needle), fallback::memchr(needle, haystack));
+                assert_eq!(
+                    expected(needle).min(expected(b'\r')),
+                    fallback::memchr2(needle, b'\r', haystack)
+                );
+                assert_eq!(
+                    haystack.iter().filter(|&&byte| byte == needle).count(),
+                    fallback::memchr_iter(needle, haystack).count()
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,337 @@
+//! Vectorized classification of bytes for the hot loops of the byte-level heuristics and UTF-8
+//! validation. On x86-64, AVX2 is used if it is available at runtime (SSE2 otherwise). On other
+//! platforms, no blocks are processed here and the callers fall back to their scalar code.
+//!
+//! The search for binary bytes in streams works on 8-byte words instead (SWAR), which is
+//! portable.
+
+#[cfg(feature = "std")]
+use core::convert::TryInto;
+
+#[cfg(feature = "std")]
+use crate::heuristic::ALLOWED_CONTROL_CHARACTERS;
+
+/// The number of bytes that are classified at once.
+pub(crate) const BLOCK: usize = 32;
+
+/// The classes of the bytes of a block, as bit masks (bit `i` is byte `i` of the block).
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct BlockMasks {
+    /// `0x00`
+    pub(crate) null: u32,
+    /// Below `0x20`, including `0x00`
+    pub(crate) below_0x20: u32,
+    /// From `0x80`, which is not ASCII
+    pub(crate) high: u32,
+}
+
+/// The scalar version of the classification, which the vectorized ones are tested against.
+#[cfg_attr(not(test), allow(dead_code))]
+pub(crate) fn block_masks_scalar(block: &[u8]) -> BlockMasks {
+    let mut masks = BlockMasks::default();
+    for (i, &byte) in block.iter().enumerate().take(BLOCK) {
+        let bit = 1 << i;
+        if byte == 0x00 {
+            masks.null |= bit;
+        }
+        if byte < 0x20 {
+            masks.below_0x20 |= bit;
+        }
+        if byte >= 0x80 {
+            masks.high |= bit;
+        }
+    }
+    masks
+}
+
+/// Whether AVX2 is available, which is detected at runtime with `std` (and only known at compile
+/// time otherwise).
+#[cfg(target_arch = "x86_64")]
+fn has_avx2() -> bool {
+    #[cfg(feature = "std")]
+    let available = is_x86_feature_detected!("avx2");
+    #[cfg(not(feature = "std"))]
+    let available = cfg!(target_feature = "avx2");
+    available
+}
+
+/// Calls `f` with the offset and the classes of each complete block of the bytes, until it
+/// returns `false`. Returns the offset of the block at which it was stopped, or the end of the
+/// last complete block otherwise (zero if vectorization is not available).
+#[inline]
+pub(crate) fn for_each_block<F: FnMut(usize, BlockMasks) -> bool>(bytes: &[u8], f: F) -> usize {
+    #[cfg(target_arch = "x86_64")]
+    let end = if has_avx2() {
+        // SAFETY: AVX2 is available on this CPU
+        unsafe { x86::for_each_block_avx2(bytes, f) }
+    } else {
+        // SAFETY: SSE2 is part of x86-64
+        unsafe { x86::for_each_block_sse2(bytes, f) }
+    };
+    #[cfg(not(target_arch = "x86_64"))]
+    let end = {
+        let _ = (bytes, f);
+        0
+    };
+    end
+}
+
+/// The length of the ASCII prefix of the bytes.
+pub(crate) fn ascii_prefix_length(bytes: &[u8]) -> usize {
+    let mut first_high = None;
+    let end = for_each_block(bytes, |offset, masks| {
+        if masks.high != 0 {
+            first_high = Some(offset + masks.high.trailing_zeros() as usize);
+        }
+        first_high.is_none()
+    });
+    first_high.unwrap_or_else(|| {
+        let rest = &bytes[end..];
+        end + rest
+            .iter()
+            .position(|byte| !byte.is_ascii())
+            .unwrap_or(rest.len())
+    })
+}
+
+// The search for binary bytes in streams is only used by the readers
+#[cfg(feature = "std")]
+const ONES: u64 = 0x0101_0101_0101_0101;
+#[cfg(feature = "std")]
+const HIGH: u64 = 0x8080_8080_8080_8080;
+
+/// The high bits of the bytes of the word that are not text, according to
+/// [`ByteClass::of`](../enum.ByteClass.html#method.of): the bytes below `0x20`, except for the
+/// allowed control characters.
+#[cfg(feature = "std")]
+fn non_text_bytes(word: u64) -> u64 {
+    // Unlike the classic `hasless` trick, this has no false positives above a matching byte,
+    // since no carry crosses a byte
+    let below_0x20 = !(((word & !HIGH) + ONES * (0x80 - 0x20)) | word) & HIGH;
+    if below_0x20 == 0 {
+        return 0;
+    }
+    // Usually a single line break, so the bytes are checked one by one
+    let mut mask = below_0x20;
+    let mut remaining = below_0x20;
+    while remaining != 0 {
+        let bit = remaining & remaining.wrapping_neg();
+        let byte = (word >> (bit.trailing_zeros() - 7)) as u8;
+        if ALLOWED_CONTROL_CHARACTERS & 1 << byte != 0 {
+            mask &= !bit;
+        }
+        remaining &= !bit;
+    }
+    mask
+}
+
+/// The offset of the first byte that is not text (a NULL byte or a control character, see
+/// [`ByteClass::of`](../enum.ByteClass.html#method.of)), classifying eight bytes at once.
+#[cfg(feature = "std")]
+pub(crate) fn first_non_text_byte(bytes: &[u8]) -> Option<usize> {
+    let mut words = bytes.chunks_exact(8);
+    for (i, word) in words.by_ref().enumerate() {
+        let word = u64::from_le_bytes(word.try_into().unwrap_or_default());
+        let mask = non_text_bytes(word);
+        if mask != 0 {
+            return Some(8 * i + mask.trailing_zeros() as usize / 8);
+        }
+    }
+    let rest = words.remainder();
+    rest.iter()
+        .position(|&byte| byte < 0x20 && ALLOWED_CONTROL_CHARACTERS & 1 << byte == 0)
+        .map(|position| bytes.len() - rest.len() + position)
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use core::arch::x86_64::*;
+
+    use super::{BlockMasks, BLOCK};
+
+    #[target_feature(enable = "sse2")]
+    pub(super) unsafe fn for_each_block_sse2<F: FnMut(usize, BlockMasks) -> bool>(
+        bytes: &[u8],
+        mut f: F,
+    ) -> usize {
+        let below_0x20 = _mm_set1_epi8(0x1F);
+        let zero = _mm_setzero_si128();
+        for (i, block) in bytes.chunks_exact(BLOCK).enumerate() {
+            // SAFETY: the block is `BLOCK` (32) bytes long
+            let (first, second) = unsafe {
+                let pointer = block.as_ptr() as *const __m128i;
+                (_mm_loadu_si128(pointer), _mm_loadu_si128(pointer.add(1)))
+            };
+            let low = |v| _mm_movemask_epi8(_mm_cmpeq_epi8(_mm_min_epu8(v, below_0x20), v));
+            let null = |v| _mm_movemask_epi8(_mm_cmpeq_epi8(v, zero));
+            let masks = BlockMasks {
+                null: null(first) as u32 | (null(second) as u32) << 16,
+                below_0x20: low(first) as u32 | (low(second) as u32) << 16,
+                high: _mm_movemask_epi8(first) as u32 | (_mm_movemask_epi8(second) as u32) << 16,
+            };
+            if !f(i * BLOCK, masks) {
+                return i * BLOCK;
+            }
+        }
+        bytes.len() - bytes.len() % BLOCK
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn for_each_block_avx2<F: FnMut(usize, BlockMasks) -> bool>(
+        bytes: &[u8],
+        mut f: F,
+    ) -> usize {
+        let below_0x20 = _mm256_set1_epi8(0x1F);
+        let zero = _mm256_setzero_si256();
+        for (i, block) in bytes.chunks_exact(BLOCK).enumerate() {
+            // SAFETY: the block is `BLOCK` (32) bytes long
+            let v = unsafe { _mm256_loadu_si256(block.as_ptr() as *const __m256i) };
+            let low = _mm256_cmpeq_epi8(_mm256_min_epu8(v, below_0x20), v);
+            let masks = BlockMasks {
+                null: _mm256_movemask_epi8(_mm256_cmpeq_epi8(v, zero)) as u32,
+                below_0x20: _mm256_movemask_epi8(low) as u32,
+                high: _mm256_movemask_epi8(v) as u32,
+            };
+            if !f(i * BLOCK, masks) {
+                return i * BLOCK;
+            }
+        }
+        bytes.len() - bytes.len() % BLOCK
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::{for_each_block_avx2, for_each_block_sse2};
+        use crate::simd::tests::{check_blocks, random_buffers};
+
+        #[test]
+        fn test_for_each_block_x86() {
+            for buffer in random_buffers() {
+                check_blocks(&buffer, |f| unsafe { for_each_block_sse2(&buffer, f) });
+                if is_x86_feature_detected!("avx2") {
+                    check_blocks(&buffer, |f| unsafe { for_each_block_avx2(&buffer, f) });
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "std")]
+    use super::first_non_text_byte;
+    use super::{ascii_prefix_length, block_masks_scalar, for_each_block, BlockMasks, BLOCK};
+    #[cfg(feature = "std")]
+    use crate::ByteClass;
+
+    /// Pseudo-random buffers of every length up to 512 (96 under Miri, which is much slower),
+    /// with bytes around the class boundaries.
+    pub(crate) fn random_buffers() -> Vec<Vec<u8>> {
+        let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        let lengths = if cfg!(miri) { 96 } else { 512 };
+        (0..lengths)
+            .map(|length| {
+                // Buffers with fewer special bytes, to reach the end of the ASCII prefix later
+                let rare = next() % 4 == 0;
+                (0..length)
+                    .map(|_| match next() % if rare { 256 } else { 8 } {
+                        0 => 0x00,
+                        1 => [0x1F, 0x20, 0x7F, 0x80, 0xFF][(next() % 5) as usize],
+                        2 => (next() >> 32) as u8,
+                        _ => 0x20 + (next() >> 32) as u8 % 0x5F,
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Compares the blocks of a vectorized implementation against the scalar version, also when
+    /// it is stopped early.
+    pub(crate) fn check_blocks<F>(buffer: &[u8], for_each: F)
+    where
+        F: Fn(&mut dyn FnMut(usize, BlockMasks) -> bool) -> usize,
+    {
+        let mut blocks = vec![];
+        let end = for_each(&mut |offset, masks| {
+            blocks.push((offset, masks));
+            true
+        });
+        let expected: Vec<_> = buffer
+            .chunks_exact(BLOCK)
+            .enumerate()
+            .map(|(i, block)| (i * BLOCK, block_masks_scalar(block)))
+            .collect();
+        assert_eq!(expected, blocks, "{:?}", buffer);
+        assert_eq!(buffer.len() / BLOCK * BLOCK, end);
+
+        let stop = expected.len() / 2;
+        let end = for_each(&mut |offset, _| offset < stop * BLOCK);
+        assert_eq!(stop * BLOCK, end);
+    }
+
+    #[test]
+    fn test_for_each_block() {
+        for buffer in random_buffers() {
+            let mut blocks = 0;
+            let end = for_each_block(&buffer, |offset, masks| {
+                let block = &buffer[offset..offset + BLOCK];
+                assert_eq!(block_masks_scalar(block), masks);
+                blocks += 1;
+                true
+            });
+            assert_eq!(blocks * BLOCK, end);
+        }
+    }
+
+    #[test]
+    fn test_ascii_prefix_length() {
+        for buffer in random_buffers() {
+            let expected = buffer
+                .iter()
+                .position(|byte| !byte.is_ascii())
+                .unwrap_or(buffer.len());
+            assert_eq!(expected, ascii_prefix_length(&buffer));
+            let ascii: Vec<u8> = buffer.iter().map(|byte| byte & 0x7F).collect();
+            assert_eq!(ascii.len(), ascii_prefix_length(&ascii));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_first_non_text_byte() {
+        let expected = |bytes: &[u8]| {
+            bytes
+                .iter()
+                .position(|&byte| ByteClass::of(byte) != ByteClass::Text)
+        };
+
+        // Every byte value (every 15th under Miri) at every position of all alignments
+        let background = b"Text\n with\tallowed\r\nand \x1B[1m\x0C control characters, \xC3\xA9";
+        for byte in (0..=255).step_by(if cfg!(miri) { 15 } else { 1 }) {
+            for position in 0..24 {
+                let mut buffer = background.to_vec();
+                buffer[position] = byte;
+                for start in 0..8 {
+                    let bytes = &buffer[start..];
+                    assert_eq!(expected(bytes), first_non_text_byte(bytes), "{:?}", bytes);
+                }
+            }
+        }
+        assert_eq!(None, first_non_text_byte(background));
+
+        for buffer in random_buffers() {
+            assert_eq!(
+                expected(&buffer),
+                first_non_text_byte(&buffer),
+                "{:?}",
+                buffer
+            );
+        }
+    }
+}
@@ -0,0 +1,429 @@
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(feature = "futures")]
+use std::future::poll_fn;
+#[cfg(feature = "futures")]
+use std::pin::pin;
+
+#[cfg(feature = "futures")]
+use futures_core::Stream;
+
+use crate::search::memchr;
+use crate::{default_inspector, ContentType, Inspector};
+
+/// Determines the type of content that is pushed in chunks, for example while it is being
+/// downloaded.
+///
+/// Only the bytes that are examined by the `Inspector` are kept, everything that is pushed beyond
+/// that is ignored. A result is available from [`current`](#method.current) as soon as no further
+/// content could change it, which can be long before the end of the content (for a NULL byte or
+/// a byte order mark) or only once enough content has been seen to fill the scanned window. A
+/// UTF-16 byte order mark is settled by the first 128 code units after it, see
+/// [`InspectorBuilder::corroborate_utf16_bom`](struct.InspectorBuilder.html#method.corroborate_utf16_bom).
+///
+/// ```
+/// # #[cfg(feature = "utf32")]
+/// # {
+/// use content_inspector::{ContentType, StreamingInspector};
+///
+/// let mut streaming = StreamingInspector::new();
+/// streaming.push(b"\xFF");
+/// assert_eq!(None, streaming.current());
+/// streaming.push(b"\xFE\x00\x00H\x00\x00\x00");
+/// assert_eq!(Some(ContentType::UTF_32LE), streaming.current());
+/// assert_eq!(ContentType::UTF_32LE, streaming.finalize());
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct StreamingInspector {
+    inspector: Inspector,
+    limit: Option<usize>,
+    buffer: Vec<u8>,
+    next_check: usize,
+    settled: Option<ContentType>,
+}
+
+impl StreamingInspector {
+    /// Returns a `StreamingInspector` with the settings of [`inspect`](fn.inspect.html).
+    pub fn new() -> StreamingInspector {
+        default_inspector().streaming()
+    }
+
+    fn with_inspector(inspector: Inspector) -> StreamingInspector {
+        StreamingInspector {
+            limit: inspector.read_limit(),
+            next_check: 2 * inspector.prefix_length(),
+            inspector,
+            buffer: vec![],
+            settled: None,
+        }
+    }
+
+    /// Feed the next chunk of content.
+    pub fn push(&mut self, chunk: &[u8]) {
+        if self.settled.is_some() {
+            return;
+        }
+
+        let remaining = self
+            .limit
+            .map_or(chunk.len(), |limit| limit - self.buffer.len());
+        let chunk = &chunk[..chunk.len().min(remaining)];
+        self.buffer.extend_from_slice(chunk);
+
+        if self.limit == Some(self.buffer.len()) {
+            self.settled = Some(self.inspector.inspect(&self.buffer));
+            return;
+        }
+
+        // Re-examining the whole buffer after every (small) chunk would be quadratic, so this is
+        // only done for the prefix with the byte order marks and magic numbers, when a NULL byte
+        // arrives, and after the buffer has doubled in size.
+        let length = self.buffer.len();
+        let due = length <= self.inspector.prefix_length()
+            || length >= self.next_check
+            || memchr(0x00, chunk).is_some();
+        if !due {
+            return;
+        }
+        if length >= self.next_check {
+            self.next_check = 2 * length;
+        }
+
+        let report = self.inspector.inspect_detailed(&self.buffer);
+        if self.inspector.is_settled(&self.buffer, &report) {
+            self.settled = Some(report.content_type);
+        }
+    }
+
+    /// The type of content, if it can not be changed by the content that follows anymore.
+    pub fn current(&self) -> Option<ContentType> {
+        self.settled
+    }
+
+    /// Determine the type of content at the end of the content.
+    pub fn finalize(self) -> ContentType {
+        self.result()
+    }
+
+    #[cfg(all(test, feature = "std"))]
+    pub(crate) fn buffer_len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub(crate) fn result(&self) -> ContentType {
+        match self.settled {
+            Some(content_type) => content_type,
+            None => self.inspector.inspect(&self.buffer),
+        }
+    }
+}
+
+impl Default for StreamingInspector {
+    fn default() -> StreamingInspector {
+        StreamingInspector::new()
+    }
+}
+
+impl Inspector {
+    /// Returns a [`StreamingInspector`](struct.StreamingInspector.html) with the settings of this
+    /// `Inspector`.
+    pub fn streaming(&self) -> StreamingInspector {
+        StreamingInspector::with_inspector(self.clone())
+    }
+
+    /// Determine the type of content of a stream of chunks, like the body of an HTTP request,
+    /// using the settings of this `Inspector`.
+    ///
+    /// The stream is polled until the type of content can not change anymore (see
+    /// [`StreamingInspector::current`](struct.StreamingInspector.html#method.current)), which is
+    /// right after a NULL byte or a byte order mark in many cases, or until it ends. The first
+    /// error of the stream is returned.
+    #[cfg(feature = "futures")]
+    pub async fn inspect_stream<S, B, E>(&self, stream: S) -> Result<ContentType, E>
+    where
+        S: Stream<Item = Result<B, E>>,
+        B: AsRef<[u8]>,
+    {
+        let mut stream = pin!(stream);
+        let mut streaming = self.streaming();
+        while let Some(chunk) = poll_fn(|cx| stream.as_mut().poll_next(cx)).await {
+            streaming.push(chunk?.as_ref());
+            if let Some(content_type) = streaming.current() {
+                return Ok(content_type);
+            }
+        }
+        Ok(streaming.finalize())
+    }
+}
+
+/// Determine the type of content of a stream of chunks, using the settings of
+/// [`inspect`](fn.inspect.html). See
+/// [`Inspector::inspect_stream`](struct.Inspector.html#method.inspect_stream) for details.
+#[cfg(feature = "futures")]
+pub async fn inspect_stream<S, B, E>(stream: S) -> Result<ContentType, E>
+where
+    S: Stream<Item = Result<B, E>>,
+    B: AsRef<[u8]>,
+{
+    default_inspector().inspect_stream(stream).await
+}
+
+#[cfg(test)]
+#[cfg_attr(
+    not(all(feature = "utf16", feature = "utf32", feature = "legacy-encodings")),
+    allow(dead_code, unused_imports)
+)]
+mod tests {
+    use crate::{ContentType::*, Inspector, StreamingInspector, Strictness};
+    use std::fs;
+    use std::path::Path;
+
+    #[test]
+    #[cfg(all(feature = "utf16", feature = "utf32"))]
+    fn test_bom_one_byte_at_a_time() {
+        let utf32 = include_bytes!("../testdata/text_UTF-32LE-BOM.txt");
+        let mut streaming = StreamingInspector::new();
+        for &byte in &utf32[..3] {
+            streaming.push(&[byte]);
+            assert_eq!(None, streaming.current());
+        }
+        streaming.push(&utf32[3..4]);
+        assert_eq!(Some(UTF_32LE), streaming.current());
+        assert_eq!(4, streaming.buffer.len());
+
+        streaming.push(&utf32[4..]);
+        assert_eq!(4, streaming.buffer.len());
+        assert_eq!(UTF_32LE, streaming.finalize());
+
+        // A UTF-16 BOM at the end of the content, which could still have been a UTF-32 BOM
+        let mut streaming = StreamingInspector::new();
+        streaming.push(b"\xFF");
+        streaming.push(b"\xFE");
+        assert_eq!(None, streaming.current());
+        assert_eq!(UTF_16LE, streaming.finalize());
+
+        // A UTF-16 BOM is only settled by the content after it, up to 128 code units
+        let mut streaming = StreamingInspector::new();
+        streaming.push(b"\xFE");
+        streaming.push(b"\xFF");
+        assert_eq!(None, streaming.current());
+        for _ in 0..127 {
+            streaming.push(b"\x00a");
+            assert_eq!(None, streaming.current());
+        }
+        streaming.push(b"\x00a");
+        assert_eq!(Some(UTF_16BE), streaming.current());
+    }
+
+    #[test]
+    fn test_magic_number_across_chunks() {
+        let png = include_bytes!("../testdata/test.png");
+        let mut streaming = StreamingInspector::new();
+        streaming.push(&png[..2]);
+        assert_eq!(None, streaming.current());
+        streaming.push(&png[2..100]);
+        assert_eq!(Some(BINARY), streaming.current());
+
+        let inspector = Inspector::new().magic_number(30, b"MAGIC").build().unwrap();
+        let mut streaming = inspector.streaming();
+        streaming.push(&[b'a'; 32]);
+        assert_eq!(None, streaming.current());
+        streaming.push(b"GIC");
+        streaming.push(b"more text");
+        assert_eq!(None, streaming.current());
+        assert_eq!(UTF_8, streaming.finalize());
+
+        let mut streaming = inspector.streaming();
+        streaming.push(&[b'a'; 30]);
+        streaming.push(b"MA");
+        assert_eq!(None, streaming.current());
+        streaming.push(b"GIC");
+        assert_eq!(Some(BINARY), streaming.current());
+    }
+
+    #[test]
+    fn test_scan_budget() {
+        let chunk = vec![b'a'; 8192];
+        let mut streaming = StreamingInspector::new();
+        streaming.push(&chunk[..1000]);
+        assert_eq!(None, streaming.current());
+        streaming.push(&chunk);
+        assert_eq!(Some(UTF_8), streaming.current());
+        assert_eq!(1024, streaming.buffer.len());
+
+        // Content beyond the scanned window is not examined
+        streaming.push(b"\x00");
+        for _ in 0..8 {
+            streaming.push(&chunk);
+        }
+        assert_eq!(1024, streaming.buffer.len());
+        assert_eq!(UTF_8, streaming.finalize());
+
+        let inspector = Inspector::new().max_scan_size(8).build().unwrap();
+        let mut streaming = inspector.streaming();
+        for _ in 0..100 {
+            streaming.push(b"a");
+        }
+        assert_eq!(Some(UTF_8), streaming.current());
+        assert!(streaming.buffer.len() < 100);
+    }
+
+    #[test]
+    #[cfg(all(feature = "utf16", feature = "utf32"))]
+    fn test_early_results() {
+        let mut streaming = StreamingInspector::new();
+        streaming.push(b"some text");
+        assert_eq!(None, streaming.current());
+        streaming.push(b" and a NULL byte \x00");
+        assert_eq!(Some(BINARY), streaming.current());
+
+        // The content after a BOM still needs to be validated
+        let strict = Inspector::new()
+            .strictness(Strictness::Strict)
+            .build()
+            .unwrap();
+        let mut streaming = strict.streaming();
+        streaming.push(b"\xEF\xBB\xBFtext");
+        assert_eq!(None, streaming.current());
+        streaming.push(b" \xFF");
+        assert_eq!(Some(BINARY), streaming.current());
+
+        // A NULL byte could still be a part of UTF-16 text
+        let utf16 = Inspector::new()
+            .detect_utf16_without_bom(true)
+            .build()
+            .unwrap();
+        let mut streaming = utf16.streaming();
+        streaming.push(&include_bytes!("../testdata/text_UTF-16LE.txt")[..100]);
+        assert_eq!(None, streaming.current());
+
+        assert_eq!(UTF_8, StreamingInspector::new().finalize());
+    }
+
+    #[test]
+    #[cfg(feature = "utf32")]
+    fn test_consistent_with_inspect() {
+        let inspectors = vec![
+            Inspector::default(),
+            Inspector::new()
+                .strictness(Strictness::Paranoid)
+                .build()
+                .unwrap(),
+            Inspector::new()
+                .detect_utf16_without_bom(true)
+                .latin1_fallback(true)
+                .build()
+                .unwrap(),
+        ];
+
+        let testdata = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata");
+        let paths = fs::read_dir(testdata)
+            .unwrap()
+            .map(|entry| entry.unwrap().path());
+        for path in paths.filter(|path| path.is_file()) {
+            let buffer = fs::read(path).unwrap();
+            for inspector in &inspectors {
+                let expected = inspector.inspect(&buffer);
+                for &chunk_size in &[1, 3, 100, 8192] {
+                    let mut streaming = inspector.streaming();
+                    for chunk in buffer.chunks(chunk_size) {
+                        streaming.push(chunk);
+                        if let Some(content_type) = streaming.current() {
+                            assert_eq!(expected, content_type);
+                        }
+                    }
+                    assert_eq!(expected, streaming.finalize());
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "futures")]
+    mod stream {
+        use std::pin::Pin;
+        use std::task::{Context, Poll};
+
+        use futures_core::Stream;
+
+        use crate::{inspect_stream, ContentType::*, Inspector, Strictness};
+
+        /// A stream of chunks, that is only ready every other poll, and counts the chunks that
+        /// have been taken.
+        struct ChunkStream<'a> {
+            chunks: Vec<Result<&'a [u8], &'static str>>,
+            taken: usize,
+            ready: bool,
+        }
+
+        impl<'a> Stream for ChunkStream<'a> {
+            type Item = Result<&'a [u8], &'static str>;
+
+            fn poll_next(
+                mut self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+            ) -> Poll<Option<Self::Item>> {
+                self.ready = !self.ready;
+                if !self.ready {
+                    cx.waker().wake_by_ref();
+                    return Poll::Pending;
+                }
+                let chunk = self.chunks.get(self.taken).cloned();
+                self.taken += 1;
+                Poll::Ready(chunk)
+            }
+        }
+
+        fn chunk_stream<'a>(chunks: Vec<Result<&'a [u8], &'static str>>) -> ChunkStream<'a> {
+            ChunkStream {
+                chunks,
+                taken: 0,
+                ready: false,
+            }
+        }
+
+        #[tokio::test]
+        async fn test_inspect_stream() {
+            let text = [b'a'; 100];
+            let mut stream = chunk_stream(vec![Ok(b"some text \x00"), Ok(&text), Ok(&text)]);
+            assert_eq!(Ok(BINARY), inspect_stream(&mut stream).await);
+            assert_eq!(1, stream.taken);
+
+            let utf32 = include_bytes!("../testdata/text_UTF-32BE-BOM.txt");
+            let chunks = utf32.chunks(1).map(Ok).collect();
+            let mut stream = chunk_stream(chunks);
+            assert_eq!(Ok(UTF_32BE), inspect_stream(&mut stream).await);
+            assert_eq!(4, stream.taken);
+
+            let chunks = vec![Ok(&text[..]); 20];
+            let mut stream = chunk_stream(chunks);
+            assert_eq!(Ok(UTF_8), inspect_stream(&mut stream).await);
+            assert_eq!(11, stream.taken);
+
+            let mut stream = chunk_stream(vec![Ok(&text[..]); 3]);
+            assert_eq!(Ok(UTF_8), inspect_stream(&mut stream).await);
+            assert_eq!(4, stream.taken);
+
+            let empty: ChunkStream<'_> = chunk_stream(vec![]);
+            assert_eq!(Ok(UTF_8), inspect_stream(empty).await);
+        }
+
+        #[tokio::test]
+        async fn test_inspect_stream_errors() {
+            let mut stream = chunk_stream(vec![Ok(b"text"), Err("connection reset"), Ok(b"\x00")]);
+            assert_eq!(Err("connection reset"), inspect_stream(&mut stream).await);
+            assert_eq!(2, stream.taken);
+
+            // The error is not reached
+            let stream = chunk_stream(vec![Ok(b"a\x00"), Err("connection reset")]);
+            assert_eq!(Ok(BINARY), inspect_stream(stream).await);
+
+            let paranoid = Inspector::new()
+                .strictness(Strictness::Paranoid)
+                .build()
+                .unwrap();
+            let stream = chunk_stream(vec![Ok(b"text"), Ok(b"\xFF")]);
+            assert_eq!(Ok(BINARY), paranoid.inspect_stream(stream).await);
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+use std::iter::FromIterator;
+
+use crate::ContentType;
+
+/// The number of files of one type of content and their total size, see
+/// [`Summary`](struct.Summary.html).
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct TypeCount {
+    /// The number of files (or buffers).
+    pub files: u64,
+    /// The total size of the files in bytes.
+    pub bytes: u64,
+}
+
+/// The results of many inspections, aggregated per type of content, for example to answer
+/// "which encodings are used in this tree?". A summary is collected from the type and the size of
+/// every file:
+///
+/// ```
+/// use content_inspector::{ContentType::*, Summary, TypeCount};
+///
+/// let summary: Summary = vec![(UTF_8, 120), (UTF_8, 80), (UTF_16LE, 64), (BINARY, 4096)]
+///     .into_iter()
+///     .collect();
+/// assert_eq!(TypeCount { files: 2, bytes: 200 }, summary.get(UTF_8));
+/// assert_eq!(TypeCount { files: 4, bytes: 4360 }, summary.total());
+/// assert_eq!(Some(UTF_8), summary.dominant_text_type());
+/// assert!(summary.is_outlier(UTF_16LE));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Summary {
+    counts: HashMap<ContentType, TypeCount>,
+}
+
+impl Summary {
+    /// An empty summary.
+    pub fn new() -> Summary {
+        Summary::default()
+    }
+
+    /// Add a file of the given type and size.
+    pub fn add(&mut self, content_type: ContentType, bytes: u64) {
+        let count = self.counts.entry(content_type).or_default();
+        count.files += 1;
+        count.bytes += bytes;
+    }
+
+    /// The files of the given type (none if there were no files of the type).
+    pub fn get(&self, content_type: ContentType) -> TypeCount {
+        self.counts.get(&content_type).copied().unwrap_or_default()
+    }
+
+    /// The map of the types to their files, which only has the types that were added.
+    pub fn counts(&self) -> &HashMap<ContentType, TypeCount> {
+        &self.counts
+    }
+
+    /// All files of all types.
+    pub fn total(&self) -> TypeCount {
+        self.counts
+            .values()
+            .fold(TypeCount::default(), |total, count| TypeCount {
+                files: total.files + count.files,
+                bytes: total.bytes + count.bytes,
+            })
+    }
+
+    /// The types that were added, the one with the most files first (then the one with the most
+    /// bytes, then by name).
+    pub fn types(&self) -> Vec<(ContentType, TypeCount)> {
+        let mut types: Vec<_> = self
+            .counts
+            .iter()
+            .map(|(&content_type, &count)| (content_type, count))
+            .collect();
+        types.sort_by(|(a, a_count), (b, b_count)| {
+            (b_count.files, b_count.bytes)
+                .cmp(&(a_count.files, a_count.bytes))
+                .then_with(|| a.to_string().cmp(&b.to_string()))
+        });
+        types
+    }
+
+    /// The text type with the most files, or `None` if there is no text.
+    pub fn dominant_text_type(&self) -> Option<ContentType> {
+        self.types()
+            .into_iter()
+            .map(|(content_type, _)| content_type)
+            .find(|content_type| content_type.is_text())
+    }
+
+    /// Whether the files of the type are the outliers of an encoding audit: text in another
+    /// encoding than the [`dominant_text_type`](#method.dominant_text_type), like the UTF-16 files
+    /// in a tree of UTF-8 files. ASCII and UTF-8 (without a byte order mark) are compatible, and
+    /// binary content is never an outlier.
+    pub fn is_outlier(&self, content_type: ContentType) -> bool {
+        use ContentType::*;
+
+        let dominant = match self.dominant_text_type() {
+            Some(dominant) => dominant,
+            None => return false,
+        };
+        match (dominant, content_type) {
+            (_, BINARY) => false,
+            (UTF_8, ASCII) | (ASCII, UTF_8) => false,
+            (dominant, content_type) => dominant != content_type,
+        }
+    }
+}
+
+impl Extend<(ContentType, u64)> for Summary {
+    fn extend<I: IntoIterator<Item = (ContentType, u64)>>(&mut self, results: I) {
+        for (content_type, bytes) in results {
+            self.add(content_type, bytes);
+        }
+    }
+}
+
+impl FromIterator<(ContentType, u64)> for Summary {
+    fn from_iter<I: IntoIterator<Item = (ContentType, u64)>>(results: I) -> Summary {
+        let mut summary = Summary::new();
+        summary.extend(results);
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Summary, TypeCount};
+    use crate::ContentType::*;
+
+    fn count(files: u64, bytes: u64) -> TypeCount {
+        TypeCount { files, bytes }
+    }
+
+    #[test]
+    fn test_summary() {
+        let summary = Summary::new();
+        assert_eq!(TypeCount::default(), summary.total());
+        assert_eq!(None, summary.dominant_text_type());
+        assert!(!summary.is_outlier(UTF_16LE));
+
+        let mut summary: Summary = vec![(BINARY, 10), (BINARY, 20), (BINARY, 30), (UTF_8, 5)]
+            .into_iter()
+            .collect();
+        summary.extend(vec![(UTF_16LE, 8), (ASCII, 3), (UTF_8, 7)]);
+        assert_eq!(count(3, 60), summary.get(BINARY));
+        assert_eq!(count(2, 12), summary.get(UTF_8));
+        assert_eq!(TypeCount::default(), summary.get(LATIN_1));
+        assert_eq!(count(7, 83), summary.total());
+        assert_eq!(4, summary.counts().len());
+
+        // Ties are broken by the bytes, then by the name
+        let types: Vec<_> = summary.types().into_iter().map(|(t, _)| t).collect();
+        assert_eq!(vec![BINARY, UTF_8, UTF_16LE, ASCII], types);
+
+        // Binary content is more common, but the audit is about the encodings of text
+        assert_eq!(Some(UTF_8), summary.dominant_text_type());
+        assert!(summary.is_outlier(UTF_16LE));
+        assert!(!summary.is_outlier(UTF_8));
+        assert!(!summary.is_outlier(ASCII));
+        assert!(!summary.is_outlier(BINARY));
+        assert!(summary.is_outlier(UTF_8_BOM));
+    }
+}
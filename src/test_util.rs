@@ -0,0 +1,9 @@
+//! Fixtures shared by the unit tests of several modules.
+
+/// 3000 bytes of ASCII text with a single NUL byte at `offset`, for tests of how far into a
+/// buffer (or a stream) the binary check looks.
+pub(crate) fn text_with_null_at(offset: usize) -> Vec<u8> {
+    let mut buffer = vec![b'a'; 3000];
+    buffer[offset] = 0x00;
+    buffer
+}
@@ -0,0 +1,869 @@
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::char::REPLACEMENT_CHARACTER;
+use core::str;
+#[cfg(feature = "std")]
+use std::io::{self, Read, Write};
+
+use crate::decode::bom;
+use crate::newlines::Newlines;
+use crate::units::{u16_unit, utf32_units};
+#[cfg(feature = "std")]
+use crate::NewlinePolicy;
+use crate::{default_inspector, ContentType, ConvertError, Inspector, StreamingInspector};
+
+/// The size of the chunks that are read from the wrapped reader.
+#[cfg(feature = "std")]
+const CHUNK_SIZE: usize = 8192;
+
+/// The converted content, and what had to be replaced.
+#[derive(Clone, Debug, Default)]
+struct Output {
+    text: String,
+    replacements: u64,
+    truncated: bool,
+    newlines: Newlines,
+}
+
+impl Output {
+    fn push(&mut self, c: char) {
+        self.newlines.push(&mut self.text, c);
+    }
+
+    fn push_str(&mut self, s: &str) {
+        self.newlines.push_str(&mut self.text, s);
+    }
+
+    fn replace(&mut self) {
+        self.replacements += 1;
+        self.push(REPLACEMENT_CHARACTER);
+    }
+
+    /// Replace an incomplete character at the end of the content.
+    fn replace_truncated(&mut self) {
+        self.truncated = true;
+        self.replace();
+    }
+}
+
+/// Decode the input, except for an incomplete character at the end (unless it is the end of the
+/// content). Returns the number of decoded bytes.
+fn decode_utf8(input: &[u8], eof: bool, output: &mut Output) -> usize {
+    let mut decoded = 0;
+    for chunk in input.utf8_chunks() {
+        output.push_str(chunk.valid());
+        decoded += chunk.valid().len();
+        let invalid = chunk.invalid();
+        if invalid.is_empty() {
+            continue;
+        }
+        let incomplete = decoded + invalid.len() == input.len()
+            && str::from_utf8(invalid).is_err_and(|error| error.error_len().is_none());
+        if !incomplete {
+            output.replace();
+        } else if eof {
+            output.replace_truncated();
+        } else {
+            // The rest of the character may follow in the next chunk
+            return decoded;
+        }
+        decoded += invalid.len();
+    }
+    decoded
+}
+
+fn decode_utf16(input: &[u8], big_endian: bool, eof: bool, output: &mut Output) -> usize {
+    let unit = |i: usize| u16_unit([input[i], input[i + 1]], big_endian);
+    let mut i = 0;
+    while i + 2 <= input.len() {
+        let first = unit(i);
+        let mut length = 2;
+        let c = if (0xD800..0xDC00).contains(&first) {
+            if i + 4 > input.len() {
+                if !eof {
+                    // The other half of the pair may follow in the next chunk
+                    break;
+                }
+                output.truncated = true;
+                None
+            } else {
+                let second = unit(i + 2);
+                if (0xDC00..0xE000).contains(&second) {
+                    length = 4;
+                    let high = u32::from(first - 0xD800);
+                    let low = u32::from(second - 0xDC00);
+                    char::from_u32(0x10000 + (high << 10) + low)
+                } else {
+                    None
+                }
+            }
+        } else {
+            // Rejects an unpaired low surrogate
+            char::from_u32(u32::from(first))
+        };
+        match c {
+            Some(c) => output.push(c),
+            None => output.replace(),
+        }
+        i += length;
+    }
+    if eof && i < input.len() {
+        output.replace_truncated();
+        i = input.len();
+    }
+    i
+}
+
+fn decode_utf32(input: &[u8], big_endian: bool, eof: bool, output: &mut Output) -> usize {
+    for code_point in utf32_units(input, big_endian) {
+        match char::from_u32(code_point) {
+            Some(c) => output.push(c),
+            None => output.replace(),
+        }
+    }
+    let remainder = input.len() % 4;
+    if eof && remainder != 0 {
+        output.replace_truncated();
+        return input.len();
+    }
+    input.len() - remainder
+}
+
+/// A reader that converts the content of the wrapped reader from the given encoding to UTF-8,
+/// for example to index text files without regard to their encoding.
+///
+/// The output is the same as that of [`decode_lossy`](fn.decode_lossy.html) for the whole
+/// content: a byte order mark at the start is removed, and invalid sequences (including invalid
+/// UTF-8) are replaced by U+FFFD, so the output is always valid UTF-8. Characters that are split
+/// across reads from the wrapped reader are decoded correctly. `BINARY` content is decoded like
+/// UTF-8.
+///
+/// ```
+/// use std::io::Read;
+/// use content_inspector::{ContentType, Utf8Reader};
+///
+/// # fn main() -> std::io::Result<()> {
+/// let content = b"\xFF\xFEG\x00r\x00\xFC\x00\xDF\x00e\x00";
+/// let mut reader = Utf8Reader::new(&content[..], ContentType::UTF_16LE);
+/// let mut text = String::new();
+/// reader.read_to_string(&mut text)?;
+///
+/// assert_eq!("Grüße", text);
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct Utf8Reader<R> {
+    inner: R,
+    content_type: ContentType,
+    input: Vec<u8>,
+    output: Output,
+    position: usize,
+    bytes_read: u64,
+    bom_checked: bool,
+    eof: bool,
+}
+
+#[cfg(feature = "std")]
+impl<R: Read> Utf8Reader<R> {
+    /// Wraps the given reader, whose content is of the given type (for example, as determined
+    /// by [`inspect`](fn.inspect.html)).
+    pub fn new(inner: R, content_type: ContentType) -> Utf8Reader<R> {
+        Utf8Reader {
+            inner,
+            content_type,
+            input: Vec::with_capacity(CHUNK_SIZE),
+            output: Output::default(),
+            position: 0,
+            bytes_read: 0,
+            bom_checked: false,
+            eof: false,
+        }
+    }
+
+    /// Rewrite the line endings according to the given policy, like
+    /// [`decode_lossy_with`](fn.decode_lossy_with.html) (default: `NewlinePolicy::Preserve`).
+    pub fn newlines(mut self, policy: NewlinePolicy) -> Self {
+        self.output.newlines = Newlines::new(policy);
+        self
+    }
+
+    /// The type of content that is converted.
+    pub fn content_type(&self) -> ContentType {
+        self.content_type
+    }
+
+    /// Returns a reference to the wrapped reader.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Returns the wrapped reader. Bytes that have been read from it, but have not been returned
+    /// yet, are lost.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Read the next chunk from the wrapped reader, and convert as much of it as possible.
+    fn fill(&mut self) -> io::Result<()> {
+        self.output.text.clear();
+        self.position = 0;
+
+        let start = self.input.len();
+        self.input.resize(start + CHUNK_SIZE, 0);
+        let n = match self.inner.read(&mut self.input[start..]) {
+            Ok(n) => n,
+            Err(e) => {
+                self.input.truncate(start);
+                return Err(e);
+            }
+        };
+        self.input.truncate(start + n);
+        self.bytes_read += n as u64;
+        self.eof = n == 0;
+
+        if !self.bom_checked {
+            let bom = bom(self.content_type);
+            if self.input.len() < bom.len() && bom.starts_with(&self.input) && !self.eof {
+                return Ok(());
+            }
+            if self.input.starts_with(bom) {
+                self.input.drain(..bom.len());
+            }
+            self.bom_checked = true;
+        }
+
+        let decoded = decode_chunk(self.content_type, &self.input, self.eof, &mut self.output);
+        self.input.drain(..decoded);
+        Ok(())
+    }
+}
+
+/// Decode as much of the input as possible, like `decode_utf8`.
+fn decode_chunk(content_type: ContentType, input: &[u8], eof: bool, output: &mut Output) -> usize {
+    let decoded = match content_type {
+        ContentType::UTF_16LE => decode_utf16(input, false, eof, output),
+        ContentType::UTF_16BE => decode_utf16(input, true, eof, output),
+        ContentType::UTF_32LE => decode_utf32(input, false, eof, output),
+        ContentType::UTF_32BE => decode_utf32(input, true, eof, output),
+        ContentType::LATIN_1 => {
+            input.iter().for_each(|&byte| output.push(char::from(byte)));
+            input.len()
+        }
+        _ => decode_utf8(input, eof, output),
+    };
+    if eof {
+        output.newlines.finish(&mut output.text);
+    }
+    decoded
+}
+
+#[cfg(feature = "std")]
+impl<R: Read> Read for Utf8Reader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        while self.position == self.output.text.len() {
+            if self.eof {
+                return Ok(0);
+            }
+            self.fill()?;
+        }
+        let available = &self.output.text.as_bytes()[self.position..];
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.position += n;
+        Ok(n)
+    }
+}
+
+/// A summary of [`transcode_to_utf8`](fn.transcode_to_utf8.html).
+#[cfg(feature = "std")]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TranscodeSummary {
+    /// The number of bytes that were read from the reader.
+    pub bytes_read: u64,
+
+    /// The number of bytes of UTF-8 that were written to the writer.
+    pub bytes_written: u64,
+
+    /// The number of invalid sequences that were replaced by U+FFFD.
+    pub replacements: u64,
+
+    /// Whether the content ended in the middle of a character (which is one of the
+    /// replacements).
+    pub truncated: bool,
+
+    /// The number of line endings that were rewritten, see
+    /// [`transcode_to_utf8_with`](fn.transcode_to_utf8_with.html).
+    pub newlines_changed: u64,
+}
+
+/// Convert the whole content of the reader from the given encoding to UTF-8, and write it to the
+/// writer, like [`Utf8Reader`](struct.Utf8Reader.html) but without an intermediate copy. The
+/// content is converted in chunks, so only a small buffer is used, regardless of its size.
+///
+/// ```
+/// use content_inspector::{transcode_to_utf8, ContentType};
+///
+/// # fn main() -> std::io::Result<()> {
+/// let mut output = vec![];
+/// let summary = transcode_to_utf8(&b"\x00\x00\x00H\x00\x00\x00i\x00"[..], &mut output, ContentType::UTF_32BE)?;
+///
+/// assert_eq!(b"Hi\xEF\xBF\xBD", &output[..]);
+/// assert_eq!(1, summary.replacements);
+/// assert!(summary.truncated);
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "std")]
+pub fn transcode_to_utf8<R: Read, W: Write>(
+    reader: R,
+    writer: W,
+    content_type: ContentType,
+) -> io::Result<TranscodeSummary> {
+    transcode_to_utf8_with(reader, writer, content_type, NewlinePolicy::Preserve)
+}
+
+/// Like [`transcode_to_utf8`](fn.transcode_to_utf8.html), but rewrites the line endings according
+/// to the given policy, also when a CRLF is split between two chunks.
+///
+/// ```
+/// use content_inspector::{transcode_to_utf8_with, ContentType, NewlinePolicy};
+///
+/// # fn main() -> std::io::Result<()> {
+/// let mut output = vec![];
+/// let summary = transcode_to_utf8_with(
+///     &b"a\r\nb\r"[..],
+///     &mut output,
+///     ContentType::ASCII,
+///     NewlinePolicy::NormalizeToLf,
+/// )?;
+///
+/// assert_eq!(b"a\nb\n", &output[..]);
+/// assert_eq!(2, summary.newlines_changed);
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "std")]
+pub fn transcode_to_utf8_with<R: Read, W: Write>(
+    reader: R,
+    mut writer: W,
+    content_type: ContentType,
+    newlines: NewlinePolicy,
+) -> io::Result<TranscodeSummary> {
+    let mut reader = Utf8Reader::new(reader, content_type).newlines(newlines);
+    let mut bytes_written = 0;
+    while !reader.eof {
+        match reader.fill() {
+            Ok(()) => {}
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+        writer.write_all(reader.output.text.as_bytes())?;
+        bytes_written += reader.output.text.len() as u64;
+    }
+    Ok(TranscodeSummary {
+        bytes_read: reader.bytes_read,
+        bytes_written,
+        replacements: reader.output.replacements,
+        truncated: reader.output.truncated,
+        newlines_changed: reader.output.newlines.changed() as u64,
+    })
+}
+
+/// What [`StreamingDecoder`](struct.StreamingDecoder.html) does with binary content.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum BinaryPolicy {
+    /// Pushing binary content fails with `ConvertError::Unsupported(ContentType::BINARY)`.
+    Reject,
+
+    /// Binary content is decoded like UTF-8, like with [`decode_lossy`](fn.decode_lossy.html).
+    Lossy,
+}
+
+/// Determines the type of content that is pushed in chunks, like
+/// [`StreamingInspector`](struct.StreamingInspector.html), and decodes it as soon as the type is
+/// known. Until then, the content is buffered (for content without a byte order mark, this is
+/// usually the whole scanned window, and for UTF-16 with a byte order mark the code units that
+/// corroborate it).
+///
+/// Characters that are split across chunks are decoded once they are complete, and invalid
+/// sequences are replaced by U+FFFD.
+///
+/// ```
+/// use content_inspector::{ContentType, StreamingDecoder};
+///
+/// # #[cfg(all(feature = "utf16", feature = "utf32"))]
+/// # fn main() -> Result<(), content_inspector::ConvertError> {
+/// let mut decoder = StreamingDecoder::new();
+/// assert_eq!("", decoder.push(b"\xFF\xFE\x00\x00H")?);
+/// assert_eq!(Some(ContentType::UTF_32LE), decoder.content_type());
+/// assert_eq!("H", decoder.push(b"\x00\x00\x00i\x00")?);
+/// assert_eq!("i", decoder.push(b"\x00\x00")?);
+/// assert_eq!("", decoder.finish()?);
+///
+/// // A short UTF-16 text is only decoded at the end
+/// let mut decoder = StreamingDecoder::new();
+/// assert_eq!("", decoder.push(b"\xFF\xFEH\x00i\x00")?);
+/// assert_eq!(None, decoder.content_type());
+/// assert_eq!("Hi", decoder.finish()?);
+/// # Ok(())
+/// # }
+/// # #[cfg(not(all(feature = "utf16", feature = "utf32")))]
+/// # fn main() {}
+/// ```
+#[derive(Clone, Debug)]
+pub struct StreamingDecoder {
+    streaming: StreamingInspector,
+    content_type: Option<ContentType>,
+    binary_policy: BinaryPolicy,
+    input: Vec<u8>,
+    output: Output,
+}
+
+impl StreamingDecoder {
+    /// Returns a `StreamingDecoder` with the settings of [`inspect`](fn.inspect.html).
+    pub fn new() -> StreamingDecoder {
+        default_inspector().streaming_decoder()
+    }
+
+    /// What to do with binary content (default: `BinaryPolicy::Reject`).
+    pub fn binary_policy(mut self, policy: BinaryPolicy) -> Self {
+        self.binary_policy = policy;
+        self
+    }
+
+    /// The type of content, once it is known.
+    pub fn content_type(&self) -> Option<ContentType> {
+        self.content_type
+    }
+
+    /// Feed the next chunk of content, and return the text that can be decoded so far (which is
+    /// empty while the type of content is not known yet).
+    pub fn push(&mut self, chunk: &[u8]) -> Result<&str, ConvertError> {
+        self.output.text.clear();
+        if let Some(error) = self.rejection() {
+            return Err(error);
+        }
+        self.input.extend_from_slice(chunk);
+        if self.content_type.is_none() {
+            self.streaming.push(chunk);
+            match self.streaming.current() {
+                Some(content_type) => self.start(content_type),
+                None => return Ok(""),
+            }
+        }
+        self.decode(false)
+    }
+
+    /// Decode the rest of the content, at its end.
+    pub fn finish(mut self) -> Result<String, ConvertError> {
+        self.output.text.clear();
+        if self.content_type.is_none() {
+            let content_type = self.streaming.result();
+            self.start(content_type);
+        }
+        self.decode(true)?;
+        Ok(self.output.text)
+    }
+
+    fn start(&mut self, content_type: ContentType) {
+        self.content_type = Some(content_type);
+        if self.input.starts_with(bom(content_type)) {
+            self.input.drain(..bom(content_type).len());
+        }
+    }
+
+    /// The error for content that is known to be binary, if it is rejected.
+    fn rejection(&self) -> Option<ConvertError> {
+        match self.content_type {
+            Some(content_type)
+                if content_type.is_binary() && self.binary_policy == BinaryPolicy::Reject =>
+            {
+                Some(ConvertError::Unsupported(content_type))
+            }
+            _ => None,
+        }
+    }
+
+    fn decode(&mut self, eof: bool) -> Result<&str, ConvertError> {
+        if let Some(error) = self.rejection() {
+            // Rejected content is never decoded, so it is not kept either
+            self.input = Vec::new();
+            return Err(error);
+        }
+        let content_type = self.content_type.unwrap_or(ContentType::BINARY);
+        let decoded = decode_chunk(content_type, &self.input, eof, &mut self.output);
+        self.input.drain(..decoded);
+        Ok(&self.output.text)
+    }
+}
+
+impl Default for StreamingDecoder {
+    fn default() -> StreamingDecoder {
+        StreamingDecoder::new()
+    }
+}
+
+impl Inspector {
+    /// Returns a [`StreamingDecoder`](struct.StreamingDecoder.html) with the settings of this
+    /// `Inspector`.
+    pub fn streaming_decoder(&self) -> StreamingDecoder {
+        StreamingDecoder {
+            streaming: self.streaming(),
+            content_type: None,
+            binary_policy: BinaryPolicy::Reject,
+            input: vec![],
+            output: Output::default(),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+#[cfg_attr(
+    not(all(feature = "utf16", feature = "utf32", feature = "legacy-encodings")),
+    allow(dead_code, unused_imports)
+)]
+mod tests {
+    use std::fs;
+    use std::io::{self, Read};
+    use std::path::Path;
+
+    use crate::{
+        decode_lossy, decode_lossy_with, transcode_to_utf8, transcode_to_utf8_with, BinaryPolicy,
+        ContentType, ContentType::*, ConvertError, Inspector, NewlinePolicy::*, StreamingDecoder,
+        TranscodeSummary, Utf8Reader,
+    };
+
+    /// A reader that yields a single byte per read.
+    struct ByteReader<'a>(&'a [u8]);
+
+    impl<'a> Read for ByteReader<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.0.split_first() {
+                Some((&byte, rest)) if !buf.is_empty() => {
+                    buf[0] = byte;
+                    self.0 = rest;
+                    Ok(1)
+                }
+                _ => Ok(0),
+            }
+        }
+    }
+
+    /// Convert the content in single bytes, and read the output in single bytes as well.
+    fn transcode_bytewise(content: &[u8], content_type: ContentType) -> String {
+        let mut reader = Utf8Reader::new(ByteReader(content), content_type);
+        let mut output = vec![];
+        let mut byte = [0];
+        while reader.read(&mut byte).unwrap() == 1 {
+            output.push(byte[0]);
+        }
+        String::from_utf8(output).unwrap()
+    }
+
+    fn transcode(content: &[u8], content_type: ContentType) -> String {
+        let mut text = String::new();
+        Utf8Reader::new(content, content_type)
+            .read_to_string(&mut text)
+            .unwrap();
+        text
+    }
+
+    #[test]
+    fn test_utf8_reader_testdata() {
+        let testdata = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata");
+        let reference = fs::read_to_string(testdata.join("text_UTF-8.txt")).unwrap();
+        for &(name, content_type) in &[
+            ("text_UTF-8.txt", UTF_8),
+            ("text_UTF-8-BOM.txt", UTF_8_BOM),
+            ("text_UTF-16LE.txt", UTF_16LE),
+            ("text_UTF-16BE.txt", UTF_16BE),
+            ("text_UTF-16LE-BOM.txt", UTF_16LE),
+            ("text_UTF-16BE-BOM.txt", UTF_16BE),
+            ("text_UTF-32LE-BOM.txt", UTF_32LE),
+            ("text_UTF-32BE-BOM.txt", UTF_32BE),
+        ] {
+            let content = fs::read(testdata.join(name)).unwrap();
+            assert_eq!(
+                reference,
+                transcode_bytewise(&content, content_type),
+                "{}",
+                name
+            );
+            assert_eq!(reference, transcode(&content, content_type), "{}", name);
+        }
+
+        let content = fs::read(testdata.join("text_Latin-1.txt")).unwrap();
+        assert_eq!(
+            "Grüße aus Köln, señor, à bientôt!\n",
+            transcode_bytewise(&content, LATIN_1)
+        );
+    }
+
+    #[test]
+    fn test_utf8_reader_invalid() {
+        for &(content, content_type) in &[
+            (&b"caf\xE9"[..], UTF_8),
+            (b"\xF0\x9F\x98", UTF_8),
+            (b"\xF0\x9FA\xF8\x88\x80\x80\x80b", UTF_8),
+            (b"\xEF\xBB", UTF_8_BOM),
+            (b"\xEF\xBB\xBF\xEF\xBB\xBF", UTF_8_BOM),
+            (b"PNG\x89\x00\x01", BINARY),
+            (b"a\x00\x00\xD8b\x00", UTF_16LE),
+            (b"\xD8\x3D\xDE\x00\xDE\x00\xD8\x3D", UTF_16BE),
+            (b"\xFF\xFE\x3D\xD8\x00", UTF_16LE),
+            (b"\xFE", UTF_16BE),
+            (
+                b"\x00\x00\xFE\xFF\x00\x11\x00\x00\x00\x00\xD8\x00\x00",
+                UTF_32BE,
+            ),
+            (b"\xFF\xFE\x00", UTF_32LE),
+            (b"", UTF_16LE),
+        ] {
+            let expected = decode_lossy(content, content_type);
+            assert_eq!(
+                expected,
+                transcode_bytewise(content, content_type),
+                "{:?}",
+                content
+            );
+            assert_eq!(expected, transcode(content, content_type), "{:?}", content);
+        }
+    }
+
+    #[test]
+    fn test_utf8_reader_error() {
+        struct FailingReader(bool);
+
+        impl Read for FailingReader {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                self.0 = !self.0;
+                if self.0 {
+                    Err(io::Error::new(io::ErrorKind::Interrupted, "interrupted"))
+                } else {
+                    buf[..2].copy_from_slice(b"\xC3\xA9");
+                    Ok(2)
+                }
+            }
+        }
+
+        let mut reader = Utf8Reader::new(FailingReader(true), UTF_8);
+        let mut buffer = [0; 3];
+        assert_eq!(2, reader.read(&mut buffer).unwrap());
+        assert_eq!(
+            io::ErrorKind::Interrupted,
+            reader.read(&mut buffer).unwrap_err().kind()
+        );
+        assert_eq!(2, reader.read(&mut buffer).unwrap());
+        assert_eq!("é", std::str::from_utf8(&buffer[..2]).unwrap());
+    }
+
+    /// Generated text of the given number of characters, with characters of every UTF-8 length.
+    fn generated_text(length: usize) -> String {
+        "ab\u{E9} \u{20AC}\n\u{1F308}\r\n\u{10FFFF}\u{FEFF}"
+            .chars()
+            .cycle()
+            .take(length)
+            .collect()
+    }
+
+    /// A reader that yields at most 7777 bytes per read.
+    struct OddReader<'a>(&'a [u8]);
+
+    impl<'a> Read for OddReader<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = buf.len().min(self.0.len()).min(7777);
+            buf[..n].copy_from_slice(&self.0[..n]);
+            self.0 = &self.0[n..];
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn test_transcode_to_utf8() {
+        let text = generated_text(1 << 20);
+        let utf16: Vec<u8> = b"\xFE\xFF"
+            .iter()
+            .copied()
+            .chain(text.encode_utf16().flat_map(u16::to_be_bytes))
+            .collect();
+        let utf32: Vec<u8> = text
+            .chars()
+            .flat_map(|c| u32::from(c).to_le_bytes())
+            .collect();
+        assert!(utf16.len() > 2_000_000);
+
+        for &(content, content_type) in &[
+            (&utf16[..], UTF_16BE),
+            (&utf32[..], UTF_32LE),
+            (text.as_bytes(), UTF_8),
+        ] {
+            let mut output = vec![];
+            let summary = transcode_to_utf8(OddReader(content), &mut output, content_type).unwrap();
+            assert!(output == text.as_bytes(), "{:?}", content_type);
+            assert_eq!(
+                TranscodeSummary {
+                    bytes_read: content.len() as u64,
+                    bytes_written: text.len() as u64,
+                    replacements: 0,
+                    truncated: false,
+                    newlines_changed: 0,
+                },
+                summary
+            );
+        }
+    }
+
+    #[test]
+    fn test_transcode_to_utf8_invalid() {
+        let mut content: Vec<u8> = generated_text(100_000)
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        // An unpaired surrogate in the middle, and an incomplete code unit at the end
+        content.splice(8190..8190, vec![0x3D, 0xD8]);
+        content.push(b'a');
+        let mut output = vec![];
+        let summary = transcode_to_utf8(&content[..], &mut output, UTF_16LE).unwrap();
+        assert_eq!(decode_lossy(&content, UTF_16LE).as_bytes(), &output[..]);
+        assert_eq!(2, summary.replacements);
+        assert!(summary.truncated);
+        assert_eq!(output.len() as u64, summary.bytes_written);
+
+        let summary = transcode_to_utf8(&b"\xE2\x82"[..], io::sink(), UTF_8).unwrap();
+        assert_eq!((1, true), (summary.replacements, summary.truncated));
+        let summary = transcode_to_utf8(&b"\xE2\x82a"[..], io::sink(), UTF_8).unwrap();
+        assert_eq!((1, false), (summary.replacements, summary.truncated));
+        let summary = transcode_to_utf8(&b"\x3D\xD8"[..], io::sink(), UTF_16LE).unwrap();
+        assert_eq!((1, true), (summary.replacements, summary.truncated));
+    }
+
+    #[test]
+    fn test_transcode_to_utf8_newlines() {
+        let text = "a\r\nb\rc\nd\r\r\ne\r".repeat(1000);
+        let content: Vec<u8> = text.encode_utf16().flat_map(u16::to_be_bytes).collect();
+        for &policy in &[Preserve, NormalizeToLf, NormalizeToCrlf] {
+            let expected = decode_lossy_with(&content, UTF_16BE, policy);
+
+            // CRLF is split between reads
+            let mut output = String::new();
+            Utf8Reader::new(ByteReader(&content), UTF_16BE)
+                .newlines(policy)
+                .read_to_string(&mut output)
+                .unwrap();
+            assert_eq!(expected.text, output);
+
+            let mut output = vec![];
+            let summary = transcode_to_utf8_with(&content[..], &mut output, UTF_16BE, policy);
+            let summary = summary.unwrap();
+            assert_eq!(expected.text.as_bytes(), &output[..]);
+            assert_eq!(expected.newlines_changed as u64, summary.newlines_changed);
+            assert_eq!(output.len() as u64, summary.bytes_written);
+        }
+    }
+
+    #[test]
+    fn test_transcode_to_utf8_errors() {
+        struct FailingWriter;
+
+        impl io::Write for FailingWriter {
+            fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+                Err(io::Error::other("disk full"))
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let error = transcode_to_utf8(&b"text"[..], FailingWriter, UTF_8).unwrap_err();
+        assert_eq!("disk full", error.to_string());
+
+        let summary = transcode_to_utf8(&b""[..], FailingWriter, UTF_16LE).unwrap();
+        assert_eq!(0, summary.bytes_written);
+    }
+
+    /// Push the content in chunks of the given size, and collect the decoded text.
+    fn decode_chunked(
+        mut decoder: StreamingDecoder,
+        content: &[u8],
+        chunk_size: usize,
+    ) -> Result<String, ConvertError> {
+        let mut text = String::new();
+        for chunk in content.chunks(chunk_size) {
+            text.push_str(decoder.push(chunk)?);
+        }
+        text.push_str(&decoder.finish()?);
+        Ok(text)
+    }
+
+    #[test]
+    #[cfg(all(feature = "utf16", feature = "utf32", feature = "legacy-encodings"))]
+    fn test_streaming_decoder() {
+        let testdata = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata");
+        let reference = fs::read_to_string(testdata.join("text_UTF-8.txt")).unwrap();
+        for name in &[
+            "text_UTF-16LE-BOM.txt",
+            "text_UTF-16BE-BOM.txt",
+            "text_UTF-32LE-BOM.txt",
+            "text_UTF-8-BOM.txt",
+            "text_UTF-8.txt",
+        ] {
+            let content = fs::read(testdata.join(name)).unwrap();
+            for &chunk_size in &[1, 3, 5, 1000] {
+                let text = decode_chunked(StreamingDecoder::new(), &content, chunk_size);
+                assert_eq!(reference, text.unwrap(), "{} in {}", name, chunk_size);
+            }
+        }
+
+        // Decoding starts as soon as the type is known, which is after the content that
+        // corroborates a UTF-16 byte order mark
+        let content = fs::read(testdata.join("text_UTF-32LE-BOM.txt")).unwrap();
+        let mut decoder = StreamingDecoder::new();
+        assert_eq!("", decoder.push(&content[..5]).unwrap());
+        assert_eq!(Some(UTF_32LE), decoder.content_type());
+        assert_eq!(&reference[..2], decoder.push(&content[5..12]).unwrap());
+        let content = fs::read(testdata.join("text_UTF-16LE-BOM.txt")).unwrap();
+        let mut decoder = StreamingDecoder::new();
+        assert_eq!("", decoder.push(&content[..6]).unwrap());
+        assert_eq!(None, decoder.content_type());
+
+        let latin1 = fs::read(testdata.join("text_Latin-1.txt")).unwrap();
+        let inspector = Inspector::new().latin1_fallback(true).build().unwrap();
+        let text = decode_chunked(inspector.streaming_decoder(), &latin1, 3).unwrap();
+        assert_eq!("Grüße aus Köln, señor, à bientôt!\n", text);
+
+        assert_eq!("", StreamingDecoder::new().finish().unwrap());
+        let text = decode_chunked(StreamingDecoder::new(), b"\xFF\xFEa\x00\x3D", 1);
+        assert_eq!("a\u{FFFD}", text.unwrap());
+    }
+
+    #[test]
+    fn test_streaming_decoder_binary() {
+        let mut decoder = StreamingDecoder::new();
+        assert_eq!("", decoder.push(b"PN").unwrap());
+        assert_eq!(
+            Err(ConvertError::Unsupported(BINARY)),
+            decoder.push(b"G\x00\xFF")
+        );
+        assert_eq!(Some(BINARY), decoder.content_type());
+        assert!(decoder.push(b"more").is_err());
+
+        // The rejected content is not buffered
+        for _ in 0..100 {
+            let chunk = [0x00; 4096];
+            assert_eq!(Err(ConvertError::Unsupported(BINARY)), decoder.push(&chunk));
+            assert_eq!(0, decoder.input.capacity());
+        }
+        assert!(decoder.finish().is_err());
+
+        let decoder = StreamingDecoder::new().binary_policy(BinaryPolicy::Lossy);
+        let text = decode_chunked(decoder, b"PNG\x00\xFF\xE2\x82\xAC\xE2", 2).unwrap();
+        assert_eq!("PNG\u{0}\u{FFFD}\u{20AC}\u{FFFD}", text);
+    }
+}
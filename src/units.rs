@@ -0,0 +1,211 @@
+//! Reading the code units of UTF-16 and UTF-32 from bytes. The byte order is always explicit
+//! (`from_le_bytes` and `from_be_bytes`, never a cast of a pointer), so the results do not depend
+//! on the byte order of the host.
+
+/// The UTF-16 code unit of the two bytes.
+#[inline]
+pub(crate) const fn u16_unit(bytes: [u8; 2], big_endian: bool) -> u16 {
+    if big_endian {
+        u16::from_be_bytes(bytes)
+    } else {
+        u16::from_le_bytes(bytes)
+    }
+}
+
+/// The UTF-32 code unit of the four bytes.
+#[inline]
+pub(crate) fn u32_unit(bytes: [u8; 4], big_endian: bool) -> u32 {
+    if big_endian {
+        u32::from_be_bytes(bytes)
+    } else {
+        u32::from_le_bytes(bytes)
+    }
+}
+
+/// The UTF-16 code units of the bytes, without an incomplete one at the end.
+pub(crate) fn utf16_units(bytes: &[u8], big_endian: bool) -> impl Iterator<Item = u16> + '_ {
+    bytes
+        .chunks_exact(2)
+        .map(move |unit| u16_unit([unit[0], unit[1]], big_endian))
+}
+
+/// The UTF-32 code units of the bytes, without an incomplete one at the end.
+pub(crate) fn utf32_units(bytes: &[u8], big_endian: bool) -> impl Iterator<Item = u32> + '_ {
+    bytes
+        .chunks_exact(4)
+        .map(move |unit| u32_unit([unit[0], unit[1], unit[2], unit[3]], big_endian))
+}
+
+/// Hand-computed code units and text in both byte orders, for the helpers above and for the
+/// decoders, validators and heuristics that read code units. These tests do not access files,
+/// so that they also run under Miri, including for a big-endian target (together with the tests
+/// of the vectorized code against its scalar version in `simd`):
+/// `cargo +nightly miri test --target s390x-unknown-linux-gnu --lib -- units simd`.
+#[cfg(test)]
+#[cfg_attr(
+    not(all(feature = "utf16", feature = "utf32", feature = "legacy-encodings")),
+    allow(dead_code, unused_imports)
+)]
+mod tests {
+    use super::{u16_unit, u32_unit, utf16_units, utf32_units};
+    use crate::{
+        code_points, decode, decode_lossy, lines, swap_endianness_in_place, validate, ContentType,
+        ContentType::*, DecodeErrorKind, Inspector, Reason, StreamingDecoder, Strictness,
+    };
+
+    /// "A€💖\n" (U+0041, U+20AC, U+1F496 as the surrogate pair D83D DC96, U+000A).
+    const TEXT: &str = "A\u{20AC}\u{1F496}\n";
+    const UTF_16LE_BYTES: &[u8] = b"\x41\x00\xAC\x20\x3D\xD8\x96\xDC\x0A\x00";
+    const UTF_16BE_BYTES: &[u8] = b"\x00\x41\x20\xAC\xD8\x3D\xDC\x96\x00\x0A";
+    const UTF_32LE_BYTES: &[u8] =
+        b"\x41\x00\x00\x00\xAC\x20\x00\x00\x96\xF4\x01\x00\x0A\x00\x00\x00";
+    const UTF_32BE_BYTES: &[u8] =
+        b"\x00\x00\x00\x41\x00\x00\x20\xAC\x00\x01\xF4\x96\x00\x00\x00\x0A";
+
+    fn samples() -> [(ContentType, &'static [u8]); 4] {
+        [
+            (UTF_16LE, UTF_16LE_BYTES),
+            (UTF_16BE, UTF_16BE_BYTES),
+            (UTF_32LE, UTF_32LE_BYTES),
+            (UTF_32BE, UTF_32BE_BYTES),
+        ]
+    }
+
+    #[test]
+    fn test_units() {
+        assert_eq!(0x3412, u16_unit([0x12, 0x34], false));
+        assert_eq!(0x1234, u16_unit([0x12, 0x34], true));
+        assert_eq!(0x0403_0201, u32_unit([0x01, 0x02, 0x03, 0x04], false));
+        assert_eq!(0x0102_0304, u32_unit([0x01, 0x02, 0x03, 0x04], true));
+
+        // The incomplete unit at the end is left out
+        let units: Vec<u16> = utf16_units(&UTF_16LE_BYTES[..9], false).collect();
+        assert_eq!(vec![0x0041, 0x20AC, 0xD83D, 0xDC96], units);
+        let units: Vec<u16> = utf16_units(UTF_16BE_BYTES, true).collect();
+        assert_eq!(vec![0x0041, 0x20AC, 0xD83D, 0xDC96, 0x000A], units);
+        let units: Vec<u32> = utf32_units(&UTF_32LE_BYTES[..15], false).collect();
+        assert_eq!(vec![0x0041, 0x20AC, 0x0001_F496], units);
+        let units: Vec<u32> = utf32_units(UTF_32BE_BYTES, true).collect();
+        assert_eq!(vec![0x0041, 0x20AC, 0x0001_F496, 0x000A], units);
+
+        // The byte order of the host makes no difference
+        let big_endian = cfg!(target_endian = "big");
+        assert_eq!(
+            u16::from_ne_bytes([0x12, 0x34]),
+            u16_unit([0x12, 0x34], big_endian)
+        );
+        let bytes = [0x01, 0x02, 0x03, 0x04];
+        assert_eq!(u32::from_ne_bytes(bytes), u32_unit(bytes, big_endian));
+    }
+
+    #[test]
+    #[cfg(all(feature = "utf16", feature = "utf32"))]
+    fn test_decoders() {
+        for &(content_type, bytes) in &samples() {
+            assert_eq!(
+                TEXT,
+                decode(bytes, content_type).unwrap(),
+                "{}",
+                content_type
+            );
+            assert_eq!(TEXT, decode_lossy(bytes, content_type), "{}", content_type);
+            assert_eq!(Ok(()), validate(bytes, content_type), "{}", content_type);
+
+            let chars: String = code_points(bytes, content_type)
+                .map(|result| result.unwrap().1)
+                .collect();
+            assert_eq!(TEXT, chars, "{}", content_type);
+            let lines: Vec<_> = lines(bytes, content_type)
+                .map(|line| line.unwrap().into_owned())
+                .collect();
+            // The line break is not part of the line
+            assert_eq!(vec!["A\u{20AC}\u{1F496}"], lines, "{}", content_type);
+
+            // One byte at a time, with the byte order mark
+            let mut decoder = StreamingDecoder::new();
+            let mut text = String::new();
+            let bom = crate::bom_bytes(content_type);
+            for byte in bom.iter().chain(bytes) {
+                text.push_str(decoder.push(&[*byte]).unwrap());
+            }
+            text.push_str(&decoder.finish().unwrap());
+            assert_eq!(TEXT, text, "{}", content_type);
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        // A high surrogate followed by "A", and the code point after U+10FFFF
+        let surrogate = DecodeErrorKind::UnpairedSurrogate(0xD83D);
+        for &(content_type, bytes) in &[
+            (UTF_16LE, &b"\x3D\xD8\x41\x00"[..]),
+            (UTF_16BE, b"\xD8\x3D\x00\x41"),
+        ] {
+            let error = decode(bytes, content_type).unwrap_err();
+            assert_eq!(
+                (0, surrogate),
+                (error.offset, error.kind),
+                "{}",
+                content_type
+            );
+            assert_eq!("\u{FFFD}A", decode_lossy(bytes, content_type));
+        }
+        let scalar = DecodeErrorKind::InvalidScalar(0x0011_0000);
+        for &(content_type, bytes) in &[
+            (UTF_32LE, &b"\x41\x00\x00\x00\x00\x00\x11\x00"[..]),
+            (UTF_32BE, b"\x00\x00\x00\x41\x00\x11\x00\x00"),
+        ] {
+            let error = decode(bytes, content_type).unwrap_err();
+            assert_eq!((4, scalar), (error.offset, error.kind), "{}", content_type);
+            let error = validate(bytes, content_type).unwrap_err();
+            assert_eq!(4, error.offset, "{}", content_type);
+        }
+    }
+
+    #[test]
+    #[cfg(all(feature = "utf16", feature = "utf32"))]
+    fn test_heuristics() {
+        // The validation of the content after a byte order mark
+        let strict = Inspector::new()
+            .strictness(Strictness::Strict)
+            .build()
+            .unwrap();
+        for &(content_type, bytes) in &samples() {
+            let buffer = [crate::bom_bytes(content_type), bytes].concat();
+            assert_eq!(content_type, strict.inspect(&buffer));
+        }
+        assert_eq!(BINARY, strict.inspect(b"\xFF\xFE\x00\xDC"));
+        assert_eq!(BINARY, strict.inspect(b"\xFE\xFF\xDC\x00"));
+        assert_eq!(UTF_16LE, strict.inspect(b"\xFF\xFE\xDC\x00"));
+
+        // UTF-16 without a byte order mark, from the positions of the zero bytes
+        let sniffing = Inspector::new()
+            .detect_utf16_without_bom(true)
+            .build()
+            .unwrap();
+        let le: Vec<u8> = "plain text".bytes().flat_map(|b| [b, 0x00]).collect();
+        let be: Vec<u8> = "plain text".bytes().flat_map(|b| [0x00, b]).collect();
+        let report = sniffing.inspect_detailed(&le);
+        assert_eq!(
+            (UTF_16LE, Reason::Utf16Pattern),
+            (report.content_type, report.reason)
+        );
+        let report = sniffing.inspect_detailed(&be);
+        assert_eq!(
+            (UTF_16BE, Reason::Utf16Pattern),
+            (report.content_type, report.reason)
+        );
+    }
+
+    #[test]
+    fn test_swap_endianness() {
+        for &(content_type, bytes, swapped) in &[
+            (UTF_16LE, UTF_16LE_BYTES, UTF_16BE_BYTES),
+            (UTF_32BE, UTF_32BE_BYTES, UTF_32LE_BYTES),
+        ] {
+            let mut buffer = bytes.to_vec();
+            swap_endianness_in_place(&mut buffer, content_type).unwrap();
+            assert_eq!(swapped, &buffer[..]);
+        }
+    }
+}
@@ -0,0 +1,324 @@
+use core::error::Error;
+use core::fmt;
+use core::str;
+
+use crate::decode::bom;
+use crate::units::{utf16_units, utf32_units};
+use crate::{ContentType, DecodeError, DecodeErrorKind, KNOWN_BYTE_ORDER_MARKS};
+
+/// Why a buffer is not valid content of the claimed type, see
+/// [`validate`](fn.validate.html).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ValidationReason {
+    /// The content can not be decoded, like with [`decode`](fn.decode.html).
+    Invalid(DecodeErrorKind),
+
+    /// A byte that is not ASCII in `ASCII` content.
+    NotAscii(u8),
+
+    /// The buffer starts with the byte order mark of the given type, or does not start with one
+    /// (`None`) although the claimed type requires it.
+    BomMismatch(Option<ContentType>),
+}
+
+/// The error of [`validate`](fn.validate.html).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ValidationError {
+    /// The offset of the offending bytes in the buffer (including a byte order mark).
+    pub offset: usize,
+
+    /// Why the content is not valid.
+    pub reason: ValidationReason,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.reason {
+            ValidationReason::Invalid(kind) => {
+                let error = DecodeError {
+                    offset: self.offset,
+                    kind,
+                };
+                write!(f, "{}", error)
+            }
+            ValidationReason::NotAscii(byte) => {
+                write!(f, "non-ASCII byte 0x{:02X} at byte {}", byte, self.offset)
+            }
+            ValidationReason::BomMismatch(Some(content_type)) => {
+                write!(f, "unexpected {} byte order mark", content_type)
+            }
+            ValidationReason::BomMismatch(None) => write!(f, "missing byte order mark"),
+        }
+    }
+}
+
+impl Error for ValidationError {}
+
+impl From<DecodeError> for ValidationError {
+    fn from(error: DecodeError) -> ValidationError {
+        ValidationError {
+            offset: error.offset,
+            reason: ValidationReason::Invalid(error.kind),
+        }
+    }
+}
+
+/// Check that the buffer is valid content of the given type, for example to verify an encoding
+/// that was declared by someone else, without decoding it into a string.
+///
+/// The rules are those of [`decode`](fn.decode.html): the buffer may start with the byte order
+/// mark of the type (the UTF-8 one for `UTF_8` and `ASCII`, and `UTF_8_BOM` requires it), but
+/// not with the byte order mark of another type. `ASCII` content must not contain bytes above
+/// 0x7F, while every buffer is valid `LATIN_1` and `BINARY` content.
+///
+/// ```
+/// use content_inspector::{validate, ContentType, DecodeErrorKind, ValidationReason};
+///
+/// assert!(validate(b"\xFF\xFEH\x00i\x00", ContentType::UTF_16LE).is_ok());
+///
+/// let error = validate(b"H\x00i\x00\x3D\xD8", ContentType::UTF_16LE).unwrap_err();
+/// assert_eq!(4, error.offset);
+/// assert_eq!(
+///     ValidationReason::Invalid(DecodeErrorKind::UnpairedSurrogate(0xD83D)),
+///     error.reason
+/// );
+///
+/// let error = validate(b"\xFF\xFEH\x00i\x00", ContentType::UTF_16BE).unwrap_err();
+/// assert_eq!(
+///     ValidationReason::BomMismatch(Some(ContentType::UTF_16LE)),
+///     error.reason
+/// );
+/// ```
+pub fn validate(buffer: &[u8], content_type: ContentType) -> Result<(), ValidationError> {
+    if content_type == ContentType::BINARY || content_type == ContentType::LATIN_1 {
+        return Ok(());
+    }
+
+    let bom = bom(content_type);
+    let offset = if buffer.starts_with(bom) {
+        bom.len()
+    } else if let Some(&(_, bom_type)) = KNOWN_BYTE_ORDER_MARKS
+        .iter()
+        .find(|&&(bom, _)| buffer.starts_with(bom))
+    {
+        return Err(ValidationError {
+            offset: 0,
+            reason: ValidationReason::BomMismatch(Some(bom_type)),
+        });
+    } else if content_type == ContentType::UTF_8_BOM {
+        return Err(ValidationError {
+            offset: 0,
+            reason: ValidationReason::BomMismatch(None),
+        });
+    } else {
+        0
+    };
+    let content = &buffer[offset..];
+
+    match content_type {
+        ContentType::UTF_16LE => validate_utf16(content, offset, false),
+        ContentType::UTF_16BE => validate_utf16(content, offset, true),
+        ContentType::UTF_32LE => validate_utf32(content, offset, false),
+        ContentType::UTF_32BE => validate_utf32(content, offset, true),
+        ContentType::ASCII => match content.iter().position(|byte| !byte.is_ascii()) {
+            Some(i) => Err(ValidationError {
+                offset: offset + i,
+                reason: ValidationReason::NotAscii(content[i]),
+            }),
+            None => Ok(()),
+        },
+        _ => match str::from_utf8(content) {
+            Ok(_) => Ok(()),
+            Err(error) => Err(ValidationError {
+                offset: offset + error.valid_up_to(),
+                reason: ValidationReason::Invalid(DecodeErrorKind::InvalidUtf8),
+            }),
+        },
+    }
+}
+
+fn validate_utf16(buffer: &[u8], offset: usize, big_endian: bool) -> Result<(), ValidationError> {
+    let units = utf16_units(buffer, big_endian);
+    let mut position = offset;
+    for c in char::decode_utf16(units) {
+        match c {
+            Ok(c) => position += 2 * c.len_utf16(),
+            Err(error) => {
+                return Err(ValidationError {
+                    offset: position,
+                    reason: ValidationReason::Invalid(DecodeErrorKind::UnpairedSurrogate(
+                        error.unpaired_surrogate(),
+                    )),
+                })
+            }
+        }
+    }
+    if buffer.len() % 2 != 0 {
+        return Err(ValidationError {
+            offset: position,
+            reason: ValidationReason::Invalid(DecodeErrorKind::IncompleteCodeUnit),
+        });
+    }
+    Ok(())
+}
+
+fn validate_utf32(buffer: &[u8], offset: usize, big_endian: bool) -> Result<(), ValidationError> {
+    for (i, value) in utf32_units(buffer, big_endian).enumerate() {
+        if char::from_u32(value).is_none() {
+            return Err(ValidationError {
+                offset: offset + 4 * i,
+                reason: ValidationReason::Invalid(DecodeErrorKind::InvalidScalar(value)),
+            });
+        }
+    }
+    if buffer.len() % 4 != 0 {
+        return Err(ValidationError {
+            offset: offset + buffer.len() - buffer.len() % 4,
+            reason: ValidationReason::Invalid(DecodeErrorKind::IncompleteCodeUnit),
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use crate::{
+        decode, inspect, validate, ContentType,
+        ContentType::*,
+        DecodeErrorKind::*,
+        ValidationError,
+        ValidationReason::{self, *},
+    };
+
+    fn reason(buffer: &[u8], content_type: ContentType) -> (usize, ValidationReason) {
+        let ValidationError { offset, reason } = validate(buffer, content_type).unwrap_err();
+        (offset, reason)
+    }
+
+    #[test]
+    fn test_validate_testdata() {
+        let testdata = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata");
+        let paths = fs::read_dir(testdata)
+            .unwrap()
+            .map(|entry| entry.unwrap().path());
+        for path in paths.filter(|path| path.is_file()) {
+            let buffer = fs::read(path).unwrap();
+            let content_type = inspect(&buffer);
+            if content_type.is_text() {
+                // Invalid UTF-8 can still be detected as UTF-8
+                assert_eq!(
+                    decode(&buffer, content_type).map(drop).map_err(Into::into),
+                    validate(&buffer, content_type)
+                );
+            } else {
+                assert_eq!(Ok(()), validate(&buffer, content_type));
+            }
+        }
+    }
+
+    #[test]
+    fn test_validate_errors() {
+        assert_eq!(
+            (4, Invalid(InvalidUtf8)),
+            reason(b"\xEF\xBB\xBFa\xFF", UTF_8_BOM)
+        );
+        assert_eq!((1, Invalid(InvalidUtf8)), reason(b"a\xE2\x82", UTF_8));
+        assert_eq!((2, NotAscii(0xE9)), reason(b"ca\xE9", ASCII));
+        assert_eq!(
+            (4, Invalid(UnpairedSurrogate(0xDE00))),
+            reason(b"\xFE\xFF\x00a\xDE\x00\x00b", UTF_16BE)
+        );
+        assert_eq!(
+            (2, Invalid(IncompleteCodeUnit)),
+            reason(b"a\x00b", UTF_16LE)
+        );
+        assert_eq!(
+            (4, Invalid(InvalidScalar(0xD800))),
+            reason(b"a\x00\x00\x00\x00\xD8\x00\x00", UTF_32LE)
+        );
+        assert_eq!(
+            (8, Invalid(IncompleteCodeUnit)),
+            reason(b"\x00\x00\xFE\xFF\x00\x00\x00a\x00\x00", UTF_32BE)
+        );
+
+        // Byte order marks
+        assert_eq!(
+            (0, BomMismatch(Some(UTF_16LE))),
+            reason(b"\xFF\xFEa\x00", UTF_8)
+        );
+        assert_eq!(
+            (0, BomMismatch(Some(UTF_32LE))),
+            reason(b"\xFF\xFE\x00\x00", UTF_16BE)
+        );
+        assert_eq!(
+            (0, BomMismatch(Some(UTF_8_BOM))),
+            reason(b"\xEF\xBB\xBFa\x00", UTF_16LE)
+        );
+        assert_eq!((0, BomMismatch(None)), reason(b"a", UTF_8_BOM));
+        // A UTF-16LE byte order mark followed by U+0000 is not a UTF-32LE one
+        assert_eq!(Ok(()), validate(b"\xFF\xFE\x00\x00", UTF_16LE));
+        assert_eq!(Ok(()), validate(b"\xEF\xBB\xBFa", UTF_8));
+
+        assert_eq!(Ok(()), validate(b"\xFF\xFE\x00\xD8\xFF", BINARY));
+        assert_eq!(Ok(()), validate(b"\xFF\xFE\x00\xD8\xFF", LATIN_1));
+        assert_eq!(Ok(()), validate(b"", UTF_32LE));
+
+        assert_eq!(
+            "non-ASCII byte 0xE9 at byte 2",
+            validate(b"ca\xE9", ASCII).unwrap_err().to_string()
+        );
+        assert_eq!(
+            "unexpected UTF-16LE byte order mark",
+            validate(b"\xFF\xFE", UTF_16BE).unwrap_err().to_string()
+        );
+        assert_eq!(
+            "incomplete code unit at byte 2 (at the end of the content)",
+            validate(b"a\x00b", UTF_16LE).unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    fn test_validate_random() {
+        let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        for length in 0..2000 {
+            // Mostly small values, so that there is some valid content
+            let buffer: Vec<u8> = (0..length % 64)
+                .map(|_| match next() % 8 {
+                    0 => (next() >> 32) as u8,
+                    1 => 0xD8 | (next() & 0x07) as u8,
+                    _ => (next() & 0x7F) as u8,
+                })
+                .collect();
+            for &content_type in &[
+                UTF_8, UTF_8_BOM, UTF_16LE, UTF_16BE, UTF_32LE, UTF_32BE, ASCII, LATIN_1, BINARY,
+            ] {
+                let result = validate(&buffer, content_type);
+                let rejected = matches!(
+                    result,
+                    Err(ValidationError {
+                        reason: BomMismatch(_) | NotAscii(_),
+                        ..
+                    })
+                );
+                if !rejected && content_type != BINARY {
+                    assert_eq!(
+                        decode(&buffer, content_type).map(drop).map_err(Into::into),
+                        result,
+                        "{:?} {:?}",
+                        content_type,
+                        buffer
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use std::io::IoSlice;
+
+use crate::{default_inspector, ContentType, Inspector, MAX_SCAN_SIZE};
+
+impl Inspector {
+    /// Like [`inspect`](#method.inspect), but for content that is split into several segments,
+    /// like a chain of `bytes::Bytes` (see `Buf::chunks_vectored`).
+    ///
+    /// If the first segment contains all of the examined content, it is inspected as it is.
+    /// Otherwise, the examined part of the segments is copied into a buffer on the stack (or on
+    /// the heap, if this `Inspector` examines more than the default 1024 bytes).
+    pub fn inspect_vectored(&self, segments: &[IoSlice<'_>]) -> ContentType {
+        let limit = self.read_limit().unwrap_or(usize::MAX);
+        let mut segments = segments
+            .iter()
+            .map(|segment| &segment[..])
+            .filter(|s| !s.is_empty());
+        let first = match segments.next() {
+            Some(first) => first,
+            None => return self.inspect(b""),
+        };
+        let mut rest = segments.peekable();
+        if first.len() >= limit || rest.peek().is_none() {
+            return self.inspect(first);
+        }
+
+        let mut stack = [0; MAX_SCAN_SIZE];
+        let mut heap = vec![];
+        let mut length = 0;
+        for segment in Some(first).into_iter().chain(rest) {
+            let segment = &segment[..segment.len().min(limit - length)];
+            if heap.is_empty() && length + segment.len() <= stack.len() {
+                stack[length..length + segment.len()].copy_from_slice(segment);
+            } else {
+                if heap.is_empty() {
+                    heap.extend_from_slice(&stack[..length]);
+                }
+                heap.extend_from_slice(segment);
+            }
+            length += segment.len();
+            if length == limit {
+                break;
+            }
+        }
+
+        if heap.is_empty() {
+            self.inspect(&stack[..length])
+        } else {
+            self.inspect(&heap)
+        }
+    }
+}
+
+/// Like [`inspect`](fn.inspect.html), but for content that is split into several segments. See
+/// [`Inspector::inspect_vectored`](struct.Inspector.html#method.inspect_vectored) for details.
+///
+/// ```
+/// use std::io::IoSlice;
+/// use content_inspector::{inspect_vectored, ContentType};
+///
+/// let segments = [IoSlice::new(b"\xFF"), IoSlice::new(b"\xFEH\x00")];
+///
+/// assert_eq!(ContentType::UTF_16LE, inspect_vectored(&segments));
+/// ```
+pub fn inspect_vectored(segments: &[IoSlice<'_>]) -> ContentType {
+    default_inspector().inspect_vectored(segments)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io::IoSlice;
+    use std::path::Path;
+
+    use crate::{inspect, inspect_vectored, ContentType::*, Inspector, Strictness};
+
+    fn segments(buffer: &[u8], size: usize) -> Vec<IoSlice<'_>> {
+        buffer.chunks(size).map(IoSlice::new).collect()
+    }
+
+    #[test]
+    #[cfg(feature = "utf32")]
+    fn test_inspect_vectored_bom() {
+        let utf32 = include_bytes!("../testdata/text_UTF-32LE-BOM.txt");
+        let split = |at: usize| {
+            let (head, tail) = utf32.split_at(at);
+            inspect_vectored(&[IoSlice::new(head), IoSlice::new(tail)])
+        };
+        assert_eq!(UTF_32LE, split(1));
+        assert_eq!(UTF_32LE, split(3));
+        assert_eq!(
+            UTF_32LE,
+            inspect_vectored(&[
+                IoSlice::new(&utf32[..1]),
+                IoSlice::new(b""),
+                IoSlice::new(&utf32[1..4]),
+                IoSlice::new(&utf32[4..]),
+            ])
+        );
+        assert_eq!(UTF_32LE, inspect_vectored(&segments(utf32, 1)));
+
+        if cfg!(feature = "magic-numbers") {
+            let png = include_bytes!("../testdata/test.png");
+            assert_eq!(BINARY, inspect_vectored(&segments(&png[..6], 2)));
+        }
+    }
+
+    #[test]
+    fn test_inspect_vectored_null_bytes() {
+        // The first byte of the fifth segment
+        let mut buffer = vec![b'a'; 50];
+        buffer[40] = 0x00;
+        assert_eq!(BINARY, inspect_vectored(&segments(&buffer, 10)));
+        buffer[40] = b'a';
+        assert_eq!(UTF_8, inspect_vectored(&segments(&buffer, 10)));
+
+        let mut buffer = vec![b'a'; 3000];
+        buffer[1023] = 0x00;
+        assert_eq!(BINARY, inspect_vectored(&segments(&buffer, 100)));
+        buffer[1023] = b'a';
+        buffer[1024] = 0x00;
+        assert_eq!(UTF_8, inspect_vectored(&segments(&buffer, 100)));
+
+        // Larger than the buffer on the stack
+        let inspector = Inspector::new().max_scan_size(2048).build().unwrap();
+        assert_eq!(BINARY, inspector.inspect_vectored(&segments(&buffer, 100)));
+        let paranoid = Inspector::new()
+            .strictness(Strictness::Paranoid)
+            .build()
+            .unwrap();
+        buffer[1024] = b'a';
+        buffer[2999] = 0xFF;
+        assert_eq!(BINARY, paranoid.inspect_vectored(&segments(&buffer, 7)));
+
+        assert_eq!(UTF_8, inspect_vectored(&[]));
+        assert_eq!(UTF_8, inspect_vectored(&[IoSlice::new(b"")]));
+    }
+
+    #[test]
+    fn test_inspect_vectored_testdata() {
+        let testdata = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata");
+        let paths = fs::read_dir(testdata)
+            .unwrap()
+            .map(|entry| entry.unwrap().path());
+        for path in paths.filter(|path| path.is_file()) {
+            let buffer = fs::read(path).unwrap();
+            for &size in &[1, 3, 1000, 4096] {
+                assert_eq!(inspect(&buffer), inspect_vectored(&segments(&buffer, size)));
+            }
+        }
+    }
+}
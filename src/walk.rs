@@ -0,0 +1,228 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use ignore::WalkBuilder;
+
+use crate::{ContentType, Inspector};
+
+/// An error while walking a directory tree, see [`walk_and_inspect`](fn.walk_and_inspect.html).
+#[derive(Debug)]
+pub enum WalkError {
+    /// The directory tree could not be walked, for example because of an unreadable directory
+    /// or a symlink loop.
+    Walk(ignore::Error),
+
+    /// A file could not be inspected.
+    Io {
+        /// The path of the file.
+        path: PathBuf,
+        /// The error while reading the file.
+        error: io::Error,
+    },
+}
+
+impl fmt::Display for WalkError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            WalkError::Walk(ref error) => write!(f, "{}", error),
+            WalkError::Io {
+                ref path,
+                ref error,
+            } => write!(f, "{}: {}", path.display(), error),
+        }
+    }
+}
+
+impl Error for WalkError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            WalkError::Walk(ref error) => Some(error),
+            WalkError::Io { ref error, .. } => Some(error),
+        }
+    }
+}
+
+/// Walk the directory tree at the given root and determine the type of content of every file,
+/// using the settings of the given `Inspector`. Only the examined part of each file is read.
+///
+/// Files that are excluded by `.gitignore` and `.ignore` files are skipped, as are hidden files,
+/// and symbolic links are not followed (see [`walk_and_inspect_with`](fn.walk_and_inspect_with.html)
+/// for other options). Errors are returned for the affected entries, the walk continues after
+/// them.
+///
+/// ```no_run
+/// use std::path::Path;
+/// use content_inspector::{walk_and_inspect, Inspector};
+///
+/// for result in walk_and_inspect(Path::new("."), &Inspector::default()) {
+///     match result {
+///         Ok((path, content_type)) => println!("{}: {}", path.display(), content_type),
+///         Err(error) => eprintln!("{}", error),
+///     }
+/// }
+/// ```
+pub fn walk_and_inspect<'a>(
+    root: &Path,
+    inspector: &'a Inspector,
+) -> impl Iterator<Item = Result<(PathBuf, ContentType), WalkError>> + 'a {
+    walk_and_inspect_with(&WalkBuilder::new(root), inspector)
+}
+
+/// Like [`walk_and_inspect`](fn.walk_and_inspect.html), but walks the directory tree with the
+/// given builder, which controls the ignore files, hidden files, symbolic links and so on.
+pub fn walk_and_inspect_with<'a>(
+    builder: &WalkBuilder,
+    inspector: &'a Inspector,
+) -> impl Iterator<Item = Result<(PathBuf, ContentType), WalkError>> + 'a {
+    builder.build().filter_map(move |entry| {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(error) => return Some(Err(WalkError::Walk(error))),
+        };
+        if !entry
+            .file_type()
+            .is_some_and(|file_type| file_type.is_file())
+        {
+            return None;
+        }
+        let path = entry.into_path();
+        Some(match inspector.inspect_file(&path) {
+            Ok(content_type) => Ok((path, content_type)),
+            Err(error) => Err(WalkError::Io { path, error }),
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::env;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    use ignore::WalkBuilder;
+
+    use super::WalkError;
+    use crate::{walk_and_inspect, walk_and_inspect_with, ContentType, ContentType::*, Inspector};
+
+    /// A temporary directory that is removed when dropped.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> TempDir {
+            let path = env::temp_dir().join(format!(
+                "content_inspector_walk_{}_{}",
+                ::std::process::id(),
+                name
+            ));
+            fs::create_dir_all(&path).unwrap();
+            TempDir(path)
+        }
+
+        fn write(&self, name: &str, content: &[u8]) {
+            let path = self.0.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn collect(
+        root: &Path,
+        results: Vec<Result<(PathBuf, ContentType), WalkError>>,
+    ) -> (BTreeMap<String, ContentType>, Vec<WalkError>) {
+        let mut types = BTreeMap::new();
+        let mut errors = vec![];
+        for result in results {
+            match result {
+                Ok((path, content_type)) => {
+                    let name = path.strip_prefix(root).unwrap().to_string_lossy();
+                    types.insert(name.replace('\\', "/"), content_type);
+                }
+                Err(error) => errors.push(error),
+            }
+        }
+        (types, errors)
+    }
+
+    #[test]
+    fn test_walk_and_inspect() {
+        let dir = TempDir::new("tree");
+        dir.write("text.txt", b"Hello");
+        dir.write("sub/binary.bin", b"\x00\x01\x02");
+        dir.write("sub/utf16.txt", b"\xFF\xFEH\x00");
+        dir.write("ignored.txt", b"ignored");
+        dir.write(".ignore", b"ignored.txt\n");
+        dir.write(".hidden", b"hidden");
+
+        let inspector = Inspector::default();
+        let results = walk_and_inspect(&dir.0, &inspector).collect();
+        let (types, errors) = collect(&dir.0, results);
+        assert!(errors.is_empty());
+        let expected: BTreeMap<String, ContentType> = vec![
+            ("text.txt".to_string(), UTF_8),
+            ("sub/binary.bin".to_string(), BINARY),
+            ("sub/utf16.txt".to_string(), UTF_16LE),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(expected, types);
+
+        let mut builder = WalkBuilder::new(&dir.0);
+        builder.hidden(false).ignore(false);
+        let results = walk_and_inspect_with(&builder, &inspector).collect();
+        let (types, _) = collect(&dir.0, results);
+        assert_eq!(6, types.len());
+        assert_eq!(Some(&UTF_8), types.get("ignored.txt"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_walk_and_inspect_errors() {
+        use std::os::unix::fs::{symlink, PermissionsExt};
+
+        let dir = TempDir::new("errors");
+        dir.write("text.txt", b"Hello");
+        dir.write("loop/binary.bin", b"\x00");
+        symlink(&dir.0, dir.0.join("loop/back")).unwrap();
+        symlink(dir.0.join("does-not-exist"), dir.0.join("dangling")).unwrap();
+
+        let inspector = Inspector::default();
+        let mut builder = WalkBuilder::new(&dir.0);
+        builder.follow_links(true);
+        let results = walk_and_inspect_with(&builder, &inspector).collect();
+        let (types, errors) = collect(&dir.0, results);
+        assert_eq!(Some(&UTF_8), types.get("text.txt"));
+        assert_eq!(Some(&BINARY), types.get("loop/binary.bin"));
+        assert_eq!(2, errors.len());
+        assert!(errors
+            .iter()
+            .all(|error| matches!(*error, WalkError::Walk(_))));
+
+        // Skipped, as symbolic links are not followed by default
+        let results = walk_and_inspect(&dir.0, &inspector).collect();
+        let (types, errors) = collect(&dir.0, results);
+        assert_eq!(2, types.len());
+        assert!(errors.is_empty());
+
+        let unreadable = dir.0.join("unreadable");
+        dir.write("unreadable/file.txt", b"Hello");
+        fs::set_permissions(&unreadable, fs::Permissions::from_mode(0o000)).unwrap();
+        let readable = fs::read_dir(&unreadable).is_ok();
+        let results = walk_and_inspect(&dir.0, &inspector).collect();
+        fs::set_permissions(&unreadable, fs::Permissions::from_mode(0o755)).unwrap();
+        if !readable {
+            let (types, errors) = collect(&dir.0, results);
+            assert_eq!(2, types.len());
+            assert_eq!(1, errors.len());
+            assert!(!errors[0].to_string().is_empty());
+        }
+    }
+}
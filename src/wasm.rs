@@ -0,0 +1,100 @@
+//! Bindings for JavaScript with `wasm-bindgen` (with the `wasm` feature), exported as `inspect`,
+//! `isText` and `inspectDetailed`. They use the default inspector and take a `Uint8Array`:
+//!
+//! ```js
+//! import { inspect, isText, inspectDetailed } from "content_inspector";
+//!
+//! const data = new Uint8Array(await file.slice(0, 4096).arrayBuffer());
+//! isText(data);                        // true
+//! inspect(data);                       // "UTF-8"
+//! inspectDetailed(data).reason;        // "NoBinaryIndicators"
+//! ```
+//!
+//! `wasm-bindgen` copies the array into the memory of the module (once, without further copies
+//! here), so it is best to pass only the start of large files. The `wasm` feature does not need
+//! `std`: build with `default-features = false` and
+//! `features = ["wasm", "magic-numbers", "utf16", "utf32"]` to leave out the readers, writers and
+//! files (and the Latin-1 fallback, which `inspect` does not use).
+
+use alloc::format;
+use alloc::string::ToString;
+
+use js_sys::{Object, Reflect};
+use wasm_bindgen::prelude::*;
+
+use crate::{ContentType, Reason};
+
+fn name(content_type: ContentType) -> JsValue {
+    JsValue::from_str(&content_type.to_string())
+}
+
+fn number(value: usize) -> JsValue {
+    JsValue::from_f64(value as f64)
+}
+
+fn offset(offset: Option<usize>) -> JsValue {
+    offset.map_or(JsValue::UNDEFINED, number)
+}
+
+fn reason_name(reason: Reason) -> &'static str {
+    match reason {
+        Reason::EmptyBuffer => "EmptyBuffer",
+        Reason::ByteOrderMark => "ByteOrderMark",
+        Reason::Utf16Pattern => "Utf16Pattern",
+        Reason::NullBytes => "NullBytes",
+        Reason::MagicNumber => "MagicNumber",
+        Reason::ControlCharacters => "ControlCharacters",
+        Reason::PrintableRatio => "PrintableRatio",
+        Reason::InvalidUtf8 => "InvalidUtf8",
+        Reason::InvalidEncoding => "InvalidEncoding",
+        Reason::Heuristic(_) => "Heuristic",
+        Reason::NoBinaryIndicators => "NoBinaryIndicators",
+    }
+}
+
+/// The name of the type of content (like `"UTF-8"` or `"binary"`, see the `Display`
+/// implementation of [`ContentType`](../enum.ContentType.html)).
+#[wasm_bindgen]
+pub fn inspect(data: &[u8]) -> JsValue {
+    name(crate::inspect(data))
+}
+
+/// Whether the content is text, see [`ContentType::is_text`](../enum.ContentType.html#method.is_text).
+#[wasm_bindgen(js_name = isText)]
+pub fn is_text(data: &[u8]) -> bool {
+    crate::inspect(data).is_text()
+}
+
+/// The [`InspectionReport`](../struct.InspectionReport.html) as an object with the fields in
+/// camel case. The types of content are names, the reason and the binary format are the names of
+/// the variants (with the name of a custom heuristic in `heuristic`), and missing offsets and
+/// formats are `undefined`.
+#[wasm_bindgen(js_name = inspectDetailed)]
+pub fn inspect_detailed(data: &[u8]) -> Object {
+    let report = crate::inspect_detailed(data);
+    let object = Object::new();
+    let set = |key: &str, value: JsValue| {
+        Reflect::set(&object, &JsValue::from_str(key), &value).unwrap_throw();
+    };
+    set("contentType", name(report.content_type));
+    set("detectedType", name(report.detected_type));
+    set("reason", JsValue::from_str(reason_name(report.reason)));
+    if let Reason::Heuristic(heuristic) = report.reason {
+        set("heuristic", JsValue::from_str(heuristic));
+    }
+    set("nullBytes", number(report.null_bytes));
+    set("firstNullOffset", offset(report.first_null_offset));
+    set("bomLength", number(report.bom_length));
+    set("controlCharacters", number(report.control_characters));
+    set("printableBytes", number(report.printable_bytes));
+    set("invalidUtf8Offset", offset(report.invalid_utf8_offset));
+    let binary_format = report.binary_format.map_or(JsValue::UNDEFINED, |format| {
+        JsValue::from_str(&format!("{:?}", format))
+    });
+    set("binaryFormat", binary_format);
+    set("scannedBytes", number(report.scanned_bytes));
+    set("validatedBytes", number(report.validated_bytes));
+    set("sniffedBytes", number(report.sniffed_bytes));
+    set("bytesExamined", number(report.bytes_examined));
+    object
+}
@@ -0,0 +1,330 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+use notify::event::{CreateKind, RemoveKind};
+use notify::{Event, EventKind, PollWatcher, RecursiveMode, Watcher};
+
+use crate::{ContentType, InspectionCache, Inspector};
+
+/// The number of files of which the results are cached by an
+/// [`InspectionWatcher`](struct.InspectionWatcher.html).
+const CACHE_CAPACITY: usize = 10_000;
+
+/// A change of a watched file, see [`InspectionWatcher`](struct.InspectionWatcher.html).
+#[derive(Debug)]
+pub enum WatchEvent {
+    /// The file was created or modified, with its type of content.
+    Changed(PathBuf, ContentType),
+
+    /// The file was removed, or renamed to another path.
+    Removed(PathBuf),
+
+    /// The file could not be inspected.
+    Error(PathBuf, io::Error),
+
+    /// The watcher failed, for example because the limit of watches was reached.
+    Watch(notify::Error),
+}
+
+/// What happened to a path within one batch of events.
+#[derive(Default)]
+struct Changes {
+    created: bool,
+    directory: bool,
+}
+
+/// Watches files and directories (with the `watch` feature, which uses the
+/// [`notify`](https://crates.io/crates/notify) crate), and inspects the files that change.
+///
+/// Events that arrive in quick succession (like the ones of an editor that saves a file) are
+/// debounced: the files are inspected once the paths are quiet for the debounce time (100 ms by
+/// default), and every file is reported at most once per batch. The results are kept in an
+/// [`InspectionCache`](struct.InspectionCache.html), so events that do not change the
+/// modification time or the size of a file (like reading it) are not reported, and the file is
+/// not read again.
+///
+/// ```no_run
+/// use content_inspector::{InspectionWatcher, Inspector, WatchEvent};
+///
+/// # fn main() -> Result<(), notify::Error> {
+/// let mut watcher = InspectionWatcher::new(Inspector::default())?;
+/// watcher.watch("src", true)?;
+/// for event in watcher {
+///     match event {
+///         WatchEvent::Changed(path, content_type) => println!("{}: {}", path.display(), content_type),
+///         WatchEvent::Removed(path) => println!("{}: removed", path.display()),
+///         WatchEvent::Error(path, error) => eprintln!("{}: {}", path.display(), error),
+///         WatchEvent::Watch(error) => eprintln!("{}", error),
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct InspectionWatcher {
+    watcher: Box<dyn Watcher + Send>,
+    events: Receiver<notify::Result<Event>>,
+    inspector: Inspector,
+    cache: InspectionCache,
+    debounce: Duration,
+    /// The events of the last batch that were not returned yet.
+    pending: VecDeque<WatchEvent>,
+}
+
+impl InspectionWatcher {
+    /// Create a watcher with the native file system notifications of the platform (like inotify
+    /// on Linux), which inspects the files with the given `Inspector`.
+    pub fn new(inspector: Inspector) -> notify::Result<InspectionWatcher> {
+        let (sender, events) = mpsc::channel();
+        let watcher = notify::recommended_watcher(sender)?;
+        Ok(InspectionWatcher::with_watcher(
+            Box::new(watcher),
+            events,
+            inspector,
+        ))
+    }
+
+    /// Create a watcher that polls the modification times of the watched files in the given
+    /// interval, which also works where there are no native notifications (like on network file
+    /// systems).
+    pub fn polling(inspector: Inspector, interval: Duration) -> notify::Result<InspectionWatcher> {
+        let (sender, events) = mpsc::channel();
+        let config = notify::Config::default().with_poll_interval(interval);
+        let watcher = PollWatcher::new(sender, config)?;
+        Ok(InspectionWatcher::with_watcher(
+            Box::new(watcher),
+            events,
+            inspector,
+        ))
+    }
+
+    fn with_watcher(
+        watcher: Box<dyn Watcher + Send>,
+        events: Receiver<notify::Result<Event>>,
+        inspector: Inspector,
+    ) -> InspectionWatcher {
+        InspectionWatcher {
+            watcher,
+            events,
+            inspector,
+            cache: InspectionCache::new(CACHE_CAPACITY),
+            debounce: Duration::from_millis(100),
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Set the time for which the paths have to be quiet before the changed files are inspected
+    /// (default: 100 ms).
+    pub fn debounce(mut self, debounce: Duration) -> InspectionWatcher {
+        self.debounce = debounce;
+        self
+    }
+
+    /// Watch the file, or the files in the directory (and in its subdirectories, if `recursive`).
+    pub fn watch<P: AsRef<Path>>(&mut self, path: P, recursive: bool) -> notify::Result<()> {
+        let mode = if recursive {
+            RecursiveMode::Recursive
+        } else {
+            RecursiveMode::NonRecursive
+        };
+        self.watcher.watch(path.as_ref(), mode)
+    }
+
+    /// Wait for the next change for at most the given time, and return `None` if there was
+    /// none. The time of the debouncing is not included.
+    pub fn next_timeout(&mut self, timeout: Duration) -> Option<WatchEvent> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Some(event);
+            }
+            let remaining = deadline.checked_duration_since(Instant::now())?;
+            match self.events.recv_timeout(remaining) {
+                Ok(first) => self.batch(first),
+                Err(_) => return None,
+            }
+        }
+    }
+
+    /// Collect the events until the paths are quiet, then inspect the changed files.
+    fn batch(&mut self, first: notify::Result<Event>) {
+        let mut changes = BTreeMap::new();
+        let mut next = Ok(first);
+        loop {
+            match next {
+                Ok(Ok(event)) => collect(&mut changes, event),
+                Ok(Err(error)) => self.pending.push_back(WatchEvent::Watch(error)),
+                Err(RecvTimeoutError::Timeout) | Err(RecvTimeoutError::Disconnected) => break,
+            }
+            next = self.events.recv_timeout(self.debounce);
+        }
+
+        for (path, changes) in changes {
+            if let Some(event) = self.event_for(path, changes) {
+                self.pending.push_back(event);
+            }
+        }
+    }
+
+    /// The event for a path that changed, if it is (or was) a file and its metadata changed.
+    fn event_for(&self, path: PathBuf, changes: Changes) -> Option<WatchEvent> {
+        match fs::metadata(&path) {
+            Ok(metadata) if metadata.is_file() => {}
+            Ok(_) => return None,
+            // A file that was created and removed in the same batch (like a temporary file of an
+            // editor) was never seen
+            Err(ref error) if error.kind() == io::ErrorKind::NotFound => {
+                return Some(WatchEvent::Removed(path))
+                    .filter(|_| !changes.created && !changes.directory);
+            }
+            Err(error) => return Some(WatchEvent::Error(path, error)),
+        }
+
+        match self.cache.get(&path, &self.inspector) {
+            Ok(Some(_)) => return None,
+            Ok(None) => {}
+            Err(error) => return Some(WatchEvent::Error(path, error)),
+        }
+        Some(match self.cache.get_or_inspect(&path, &self.inspector) {
+            Ok(content_type) => WatchEvent::Changed(path, content_type),
+            Err(error) => WatchEvent::Error(path, error),
+        })
+    }
+}
+
+/// Add the paths of the event to the changes of the batch. Accesses (like the ones of the
+/// inspection itself) do not change anything.
+fn collect(changes: &mut BTreeMap<PathBuf, Changes>, event: Event) {
+    let (created, directory) = match event.kind {
+        EventKind::Access(_) => return,
+        EventKind::Create(kind) => (true, kind == CreateKind::Folder),
+        EventKind::Remove(kind) => (false, kind == RemoveKind::Folder),
+        _ => (false, false),
+    };
+    for path in event.paths {
+        let entry = changes.entry(path).or_default();
+        entry.created |= created;
+        entry.directory |= directory;
+    }
+}
+
+impl Iterator for InspectionWatcher {
+    type Item = WatchEvent;
+
+    /// Wait for the next change. Returns `None` only if the watcher stopped.
+    fn next(&mut self) -> Option<WatchEvent> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Some(event);
+            }
+            let first = self.events.recv().ok()?;
+            self.batch(first);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs::{self, File};
+    use std::path::{Path, PathBuf};
+    use std::time::{Duration, SystemTime};
+
+    use super::{InspectionWatcher, WatchEvent};
+    use crate::Inspector;
+
+    /// A temporary directory that is removed when dropped.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> TempDir {
+            let path = env::temp_dir().join(format!(
+                "content_inspector_watch_{}_{}",
+                ::std::process::id(),
+                name
+            ));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            TempDir(fs::canonicalize(path).unwrap())
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    /// Write the file, with a modification time in the future (the poll watcher has a resolution
+    /// of a second).
+    fn write(path: &Path, content: &[u8], seconds: u64) {
+        fs::write(path, content).unwrap();
+        touch(path, seconds);
+    }
+
+    fn touch(path: &Path, seconds: u64) {
+        File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(seconds))
+            .unwrap();
+    }
+
+    /// The next events, until the watcher is quiet for half a second.
+    fn events(watcher: &mut InspectionWatcher) -> Vec<String> {
+        let mut events = vec![];
+        while let Some(event) = watcher.next_timeout(Duration::from_millis(500)) {
+            events.push(match event {
+                WatchEvent::Changed(path, content_type) => {
+                    format!(
+                        "{}: {}",
+                        path.file_name().unwrap().to_string_lossy(),
+                        content_type
+                    )
+                }
+                WatchEvent::Removed(path) => {
+                    format!("{}: removed", path.file_name().unwrap().to_string_lossy())
+                }
+                event => panic!("unexpected event {:?}", event),
+            });
+        }
+        events
+    }
+
+    #[test]
+    fn test_watch_directory() {
+        let dir = TempDir::new("directory");
+        let path = dir.0.join("file.txt");
+        fs::write(&path, b"text").unwrap();
+
+        let mut watcher =
+            InspectionWatcher::polling(Inspector::default(), Duration::from_millis(20))
+                .unwrap()
+                .debounce(Duration::from_millis(50));
+        watcher.watch(&dir.0, false).unwrap();
+        assert!(events(&mut watcher).is_empty());
+
+        // Touching the file re-inspects it, reading it does not
+        touch(&path, 10);
+        assert_eq!(vec!["file.txt: UTF-8"], events(&mut watcher));
+        fs::read(&path).unwrap();
+        assert!(events(&mut watcher).is_empty());
+
+        write(&path, b"\x00\x01\x02", 20);
+        assert_eq!(vec!["file.txt: binary"], events(&mut watcher));
+
+        // Removed and created again
+        fs::remove_file(&path).unwrap();
+        assert_eq!(vec!["file.txt: removed"], events(&mut watcher));
+        write(&path, b"\xFF\xFEt\x00", 30);
+        assert_eq!(vec!["file.txt: UTF-16LE"], events(&mut watcher));
+
+        // A new file in the directory
+        write(&dir.0.join("other.txt"), b"text", 40);
+        assert_eq!(vec!["other.txt: UTF-8"], events(&mut watcher));
+    }
+}
@@ -0,0 +1,206 @@
+//! The classification of UTF-16 code units that are already in memory as `u16`, like the wide
+//! strings of the Windows API.
+
+use crate::heuristic::{implausible_utf16_unit, UTF16_BOM_UNCHECKED_UNITS};
+use crate::{ContentType, MAX_SCAN_SIZE};
+
+/// The number of code units that are examined, the same number of bytes as for a buffer of
+/// bytes.
+const WINDOW_UNITS: usize = MAX_SCAN_SIZE / 2;
+
+/// Determine whether the UTF-16 code units (for example from `OsStr::encode_wide` or from the
+/// wide-string functions of the Windows API) are text, without converting them to bytes first.
+///
+/// The result is the type of content of the bytes of the slice in memory, so it depends on the
+/// byte order of the platform: `UTF_16LE` on little-endian platforms (like x86 and ARM) and
+/// `UTF_16BE` on big-endian ones. Units that were read with the other byte order are reported as
+/// the other type, if they start with U+FFFE (a swapped byte order mark), or if all of them look
+/// like byte-swapped ASCII and Latin-1 characters (with a zero low byte). A leading U+FEFF is the
+/// byte order mark of the platform's byte order. `BINARY` is returned if the first 512 code units
+/// (after a byte order mark) contain
+///
+/// * a U+0000 unit, apart from a single one at the end (the terminator of a C string), so units
+///   that are all zero are binary,
+/// * an unpaired surrogate (a high surrogate at the end of the window may be followed by a low
+///   one beyond it), or
+/// * more than one control character (except for tab, line feed, form feed, carriage return and
+///   escape), private use character or noncharacter in 32 units, if there are more than eight.
+///
+/// An empty slice is text like an empty buffer of bytes (of the platform's byte order).
+///
+/// ```
+/// use content_inspector::{inspect_utf16_units, ContentType};
+///
+/// let units: Vec<u16> = "Grüße, 世界\r\n".encode_utf16().collect();
+/// assert!(inspect_utf16_units(&units).is_text());
+///
+/// let swapped: Vec<u16> = units.iter().map(|unit| unit.swap_bytes()).collect();
+/// assert_ne!(inspect_utf16_units(&units), inspect_utf16_units(&swapped));
+///
+/// assert_eq!(ContentType::BINARY, inspect_utf16_units(&[0xD800, 0x0041]));
+/// assert_eq!(ContentType::BINARY, inspect_utf16_units(&[0; 16]));
+/// ```
+pub fn inspect_utf16_units(units: &[u16]) -> ContentType {
+    let (native, swapped) = if cfg!(target_endian = "big") {
+        (ContentType::UTF_16BE, ContentType::UTF_16LE)
+    } else {
+        (ContentType::UTF_16LE, ContentType::UTF_16BE)
+    };
+    let units = match units.split_last() {
+        Some((0, rest)) if !rest.is_empty() => rest,
+        _ => units,
+    };
+
+    let (content, swap) = match units.split_first() {
+        Some((0xFEFF, rest)) => (rest, false),
+        Some((0xFFFE, rest)) => (rest, true),
+        _ => {
+            let window = &units[..units.len().min(WINDOW_UNITS)];
+            // Byte-swapped ASCII and Latin-1 characters, like the zero bytes of UTF-16 without a
+            // byte order mark
+            let swap = window.len() > UTF16_BOM_UNCHECKED_UNITS
+                && window.iter().all(|&unit| unit != 0 && unit & 0x00FF == 0);
+            (units, swap)
+        }
+    };
+    let content_type = if swap { swapped } else { native };
+    let unit = |index: usize| {
+        if swap {
+            content[index].swap_bytes()
+        } else {
+            content[index]
+        }
+    };
+
+    let window = content.len().min(WINDOW_UNITS);
+    let mut implausible = 0;
+    let mut i = 0;
+    while i < window {
+        match unit(i) {
+            0x0000 => return ContentType::BINARY,
+            0xD800..=0xDBFF if i + 1 == window && window < content.len() => {}
+            0xD800..=0xDBFF if i + 1 < window && matches!(unit(i + 1), 0xDC00..=0xDFFF) => {
+                i += 1;
+            }
+            0xD800..=0xDFFF => return ContentType::BINARY,
+            current if implausible_utf16_unit(current) => implausible += 1,
+            _ => {}
+        }
+        i += 1;
+    }
+    if window > UTF16_BOM_UNCHECKED_UNITS && implausible * 32 > window {
+        ContentType::BINARY
+    } else {
+        content_type
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::inspect_utf16_units;
+    use crate::{bom_bytes, ContentType, ContentType::*, Inspector};
+    use std::fs;
+    use std::path::Path;
+
+    fn native() -> (ContentType, ContentType) {
+        if cfg!(target_endian = "big") {
+            (UTF_16BE, UTF_16LE)
+        } else {
+            (UTF_16LE, UTF_16BE)
+        }
+    }
+
+    /// The bytes of the units in memory.
+    fn bytes(units: &[u16]) -> Vec<u8> {
+        units.iter().flat_map(|unit| unit.to_ne_bytes()).collect()
+    }
+
+    #[test]
+    fn test_text() {
+        let (native, swapped) = native();
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata/text_UTF-8.txt");
+        let text = fs::read_to_string(path).unwrap();
+        let long = text.repeat(20);
+        for text in &[&text[..], "Hello, World!\n", "Grüße, 世界\r\n\t💖", &long] {
+            let units: Vec<u16> = text.encode_utf16().collect();
+            assert_eq!(native, inspect_utf16_units(&units), "{:?}", text);
+
+            // With a byte order mark and a terminator, which agrees with the bytes
+            let mut terminated = vec![0xFEFF];
+            terminated.extend(&units);
+            terminated.push(0x0000);
+            assert_eq!(native, inspect_utf16_units(&terminated));
+            let buffer = [bom_bytes(native), &bytes(&units)].concat();
+            assert_eq!(native, Inspector::default().inspect(&buffer));
+        }
+
+        // A surrogate pair beyond the window
+        let mut units = vec![u16::from(b'a'); 511];
+        units.extend("💖".encode_utf16());
+        assert_eq!(native, inspect_utf16_units(&units));
+        assert_eq!(swapped, inspect_utf16_units(&[0xFFFE]));
+    }
+
+    #[test]
+    fn test_byte_swapped() {
+        let (native, swapped) = native();
+        let text = "Plain text, with a few more characters\r\n";
+        let units: Vec<u16> = text.encode_utf16().map(u16::swap_bytes).collect();
+        assert_eq!(swapped, inspect_utf16_units(&units));
+
+        // With the (swapped) byte order mark, also for text other than ASCII
+        let text = "Grüße, 世界";
+        let mut units = vec![0xFEFF_u16.swap_bytes()];
+        units.extend(text.encode_utf16().map(u16::swap_bytes));
+        assert_eq!(swapped, inspect_utf16_units(&units));
+        assert_eq!(
+            swapped,
+            Inspector::default().inspect(&bytes(&units)),
+            "like the bytes"
+        );
+
+        // Too few characters to tell
+        let units: Vec<u16> = "Short".encode_utf16().map(u16::swap_bytes).collect();
+        assert_eq!(native, inspect_utf16_units(&units));
+    }
+
+    #[test]
+    fn test_binary() {
+        let (native, _) = native();
+        assert_eq!(native, inspect_utf16_units(&[]));
+        assert_eq!(BINARY, inspect_utf16_units(&[0x0000]));
+        assert_eq!(BINARY, inspect_utf16_units(&[0x0000; 2]));
+        assert_eq!(BINARY, inspect_utf16_units(&[0x0000; 1000]));
+        assert_eq!(BINARY, inspect_utf16_units(&[0x0041, 0x0000, 0x0041]));
+        assert_eq!(native, inspect_utf16_units(&[0x0041, 0x0000]));
+
+        // Unpaired surrogates
+        assert_eq!(BINARY, inspect_utf16_units(&[0xDC00, 0x0041]));
+        assert_eq!(BINARY, inspect_utf16_units(&[0x0041, 0xD83D]));
+        assert_eq!(BINARY, inspect_utf16_units(&[0xD83D, 0xD83D, 0xDC96]));
+        assert_eq!(native, inspect_utf16_units(&[0xD83D, 0xDC96]));
+
+        // Control characters: one in 32 units is allowed
+        let mut units = vec![u16::from(b'a'); 64];
+        units[10] = 0x0001;
+        units[20] = 0x0085;
+        assert_eq!(native, inspect_utf16_units(&units));
+        units[30] = 0xE000;
+        assert_eq!(BINARY, inspect_utf16_units(&units));
+        assert_eq!(native, inspect_utf16_units(&[0x0001; 8]));
+        assert_eq!(BINARY, inspect_utf16_units(&[0x0001; 9]));
+
+        // Random units are binary (most of them have unpaired surrogates)
+        let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        for length in (100..2000).step_by(7) {
+            let units: Vec<u16> = (0..length).map(|_| (next() >> 32) as u16).collect();
+            assert_eq!(BINARY, inspect_utf16_units(&units), "{:04X?}", units);
+        }
+    }
+}
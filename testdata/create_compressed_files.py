@@ -0,0 +1,21 @@
+import gzip
+import subprocess
+
+for source in ["text_UTF-8.txt", "test.png"]:
+    with open(source, "rb") as f:
+        data = f.read()
+    with open(source + ".gz", "wb") as target:
+        target.write(gzip.compress(data, mtime=0))
+
+# A truncated member (best-effort result) and a corrupt stream (error)
+with open("text_UTF-8.txt.gz", "rb") as f:
+    data = f.read()
+with open("text_truncated.txt.gz", "wb") as target:
+    target.write(data[: len(data) // 2])
+with open("test_corrupt.gz", "wb") as target:
+    target.write(data[:10] + b"\xff" * 100)
+
+subprocess.run(
+    ["zstd", "-q", "-f", "-19", "text_UTF-16LE-BOM.txt", "-o", "text_UTF-16LE-BOM.txt.zst"],
+    check=True,
+)
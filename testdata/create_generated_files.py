@@ -0,0 +1,85 @@
+import codecs
+import os
+import shutil
+
+# The generated corpus: the reference text (text_UTF-8.txt) in every supported
+# encoding, with and without a byte order mark, and the edge cases of the byte
+# order marks (truncations of the files with a BOM at every length up to 8, the
+# BOM alone, and the BOM twice). The expected classifications are derived from
+# the rules below (not from the crate) and written to generated/manifest.tsv,
+# which is checked by the test suite. Run from this directory.
+#
+# The manifest has the expected type for the default inspector, and for one
+# that detects UTF-16 without a BOM and falls back to Latin-1 ("sniffing"), or
+# "-" where the sniffing verdict is a guess that is not pinned down.
+
+# In the order of the detection: the UTF-32LE BOM starts with the UTF-16LE one
+encodings = {
+    "UTF-8": ("utf-8", codecs.BOM_UTF8, "UTF-8-BOM"),
+    "UTF-32LE": ("utf_32_le", codecs.BOM_UTF32_LE, "UTF-32LE"),
+    "UTF-32BE": ("utf_32_be", codecs.BOM_UTF32_BE, "UTF-32BE"),
+    "UTF-16LE": ("utf_16_le", codecs.BOM_UTF16_LE, "UTF-16LE"),
+    "UTF-16BE": ("utf_16_be", codecs.BOM_UTF16_BE, "UTF-16BE"),
+}
+
+TRUNCATIONS = 8
+
+
+def bom_type(data):
+    for _, bom, content_type in encodings.values():
+        if data.startswith(bom):
+            return content_type
+    return None
+
+
+def is_utf8(data):
+    try:
+        codecs.getincrementaldecoder("utf-8")().decode(data, final=False)
+        return True
+    except UnicodeDecodeError:
+        return False
+
+
+def expected(data, utf16=None):
+    """The expected types for the default and the sniffing inspector. `utf16`
+    is the type of UTF-16 text without a BOM."""
+    content_type = bom_type(data)
+    if content_type is not None:
+        return content_type, content_type
+    if utf16 is not None:
+        return "binary", utf16
+    if b"\x00" in data:
+        return "binary", "-"
+    return "UTF-8", "UTF-8" if is_utf8(data) else "Latin-1"
+
+
+with open("text_UTF-8.txt", "rb") as source:
+    text = source.read().decode("utf-8")
+
+shutil.rmtree("generated", ignore_errors=True)
+os.mkdir("generated")
+manifest = []
+
+
+def write(name, data, utf16=None):
+    with open(os.path.join("generated", name), "wb") as target:
+        target.write(data)
+    manifest.append((name,) + expected(data, utf16))
+
+
+for name, (encoding, bom, _) in encodings.items():
+    content = text.encode(encoding)
+    utf16 = name if name.startswith("UTF-16") else None
+    write("{}.txt".format(name), content, utf16)
+    write("{}-BOM.txt".format(name), bom + content)
+    write("{}-BOM-only".format(name), bom)
+    write("{}-BOM-doubled.txt".format(name), bom + bom + content)
+    for length in range(1, TRUNCATIONS + 1):
+        write("{}-BOM.txt.{}".format(name, length), (bom + content)[:length])
+
+write("Latin-1.txt", text.encode("latin-1", errors="replace"))
+
+with open("generated/manifest.tsv", "w") as target:
+    target.write("# file\tdefault\tsniffing\n")
+    for line in sorted(manifest):
+        target.write("\t".join(line) + "\n")
@@ -16,3 +16,31 @@ with open("text_UTF-8.txt", "rb") as source:
         with open("text_{}-BOM.txt".format(name), "wb") as target:
             target.write(bom)
             target.write(text.encode(encoding))
+
+    for name in ["UTF-16BE", "UTF-16LE"]:
+        encoding, _ = encodings[name]
+        with open("text_{}.txt".format(name), "wb") as target:
+            target.write(text.encode(encoding))
+
+with open("text_Latin-1.txt", "wb") as target:
+    target.write("Grüße aus Köln, señor, à bientôt!\n".encode("latin-1"))
+
+with open("test_control_characters.bin", "wb") as target:
+    for i in range(8):
+        target.write(b"\x01\x02record %d\x1c\x1d\x1e\x1f\n" % i)
+
+with open("text_invalid_UTF-8.txt", "wb") as target:
+    content = codecs.BOM_UTF8
+    line = 0
+    while len(content) < 900:
+        line += 1
+        content += "line {}: simple text\n".format(line).encode("utf-8")
+    target.write(content[:900] + b"\xff" + content[900:1200])
+
+with open("test_stage_limits.txt", "wb") as target:
+    content = b"".join(b"line %04d: simple text\n" % i for i in range(200))[:4000]
+    target.write(content[:2000] + b"\xff" + content[2001:3000] + b"\x00" + content[3001:])
+
+# A JPEG file that starts with the UTF-16LE byte order mark by accident
+with open("test.jpg", "rb") as source, open("test_utf16_bom.jpg", "wb") as target:
+    target.write(codecs.BOM_UTF16_LE + source.read())
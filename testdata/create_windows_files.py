@@ -0,0 +1,53 @@
+import struct
+
+# Header-only samples of Windows file formats, built from the documented
+# on-disk layouts. Only the first few hundred bytes matter for inspection.
+# These are synthesized, not captured on Windows: they agree with the magic
+# numbers by construction, so a misreading of a layout would go unnoticed.
+
+# Shell link (.lnk): header size + LinkCLSID 00021401-0000-0000-C000-000000000046
+lnk = struct.pack("<I", 0x4C)
+lnk += bytes.fromhex("0114020000000000C000000000000046")
+lnk += struct.pack("<II", 0x0000009B, 0x00000020)
+lnk += b"\x00" * (0x4C - len(lnk))
+lnk += "C:\\Windows\\notepad.exe".encode("utf-16-le")
+
+# Registry hive: "regf", sequence numbers, timestamp, version 1.5
+regf = b"regf"
+regf += struct.pack("<II", 0x2A, 0x2A)
+regf += struct.pack("<Q", 0x01D4_5A2B_3C4D_5E6F)
+regf += struct.pack("<IIII", 1, 5, 0, 1)
+regf += struct.pack("<III", 0x20, 0x1000, 1)
+regf += "\\??\\C:\\Users\\Default\\NTUSER.DAT".encode("utf-16-le")
+regf += b"\x00" * (512 - len(regf))
+
+# Cabinet: "MSCF", reserved, total size, reserved, first file offset, version 1.3
+cab = b"MSCF"
+cab += struct.pack("<IIII", 0, 0x1234, 0, 0x2C)
+cab += struct.pack("<BBHHHHH", 3, 1, 1, 1, 0, 0x1234, 0)
+cab += struct.pack("<IHH", 0x44, 1, 0)
+cab += b"notepad.exe\x00"
+
+# Prefetch (Windows 10 layout, uncompressed): version 30, "SCCA", file size, name
+pf = struct.pack("<I", 30)
+pf += b"SCCA"
+pf += struct.pack("<II", 0x11, 0x4A2C)
+pf += "NOTEPAD.EXE".encode("utf-16-le").ljust(60, b"\x00")
+pf += struct.pack("<I", 0x8B6B5C1E)
+
+# Compressed prefetch (Windows 8 and later): "MAM", compression method 4 (XPRESS Huffman),
+# uncompressed size, then the Huffman table (the 4-bit code lengths of 512 symbols)
+pf_compressed = b"MAM\x04"
+pf_compressed += struct.pack("<I", len(pf))
+pf_compressed += bytes(0x55 if i % 3 == 0 else 0 for i in range(256))
+pf_compressed += bytes(range(0x80, 0x100)) * 2
+
+for name, data in [
+    ("test.lnk", lnk),
+    ("test_registry_hive.dat", regf),
+    ("test.cab", cab),
+    ("test.pf", pf),
+    ("test_compressed.pf", pf_compressed),
+]:
+    with open(name, "wb") as target:
+        target.write(data)
@@ -0,0 +1,61 @@
+# Downloads the public test corpora of two encoding detectors into
+# testdata/public (which is ignored by git), for the test in
+# tests/public_corpora.rs. The test runs this script if the directory does
+# not exist yet. Run from any directory, needs git and network access.
+#
+# - uchardet: the files of its test suite, as public/uchardet/<language>/
+#   <charset>.txt (the label is the name of the file)
+# - char-dataset (the corpus of charset-normalizer): the files as
+#   public/char-dataset/<encoding>/<file> (the label is the directory)
+#
+# The revisions are pinned, so that the thresholds of the test stay
+# meaningful. The downloaded revisions are written to public/REVISIONS.
+
+import os
+import shutil
+import subprocess
+import sys
+import tempfile
+
+CORPORA = [
+    # name, repository, revision, subdirectory with the files
+    ("uchardet", "https://gitlab.freedesktop.org/uchardet/uchardet.git", "v0.0.8", "test"),
+    ("char-dataset", "https://github.com/Ousret/char-dataset.git", "master", "."),
+]
+
+testdata = os.path.dirname(os.path.abspath(__file__))
+public = os.path.join(testdata, "public")
+partial = public + ".partial"
+
+shutil.rmtree(partial, ignore_errors=True)
+os.mkdir(partial)
+revisions = []
+
+with tempfile.TemporaryDirectory() as checkouts:
+    for name, repository, revision, subdirectory in CORPORA:
+        checkout = os.path.join(checkouts, name)
+        subprocess.run(
+            ["git", "clone", "--quiet", "--depth", "1", "--branch", revision, repository, checkout],
+            check=True,
+        )
+        commit = subprocess.run(
+            ["git", "rev-parse", "HEAD"], cwd=checkout, capture_output=True, text=True, check=True
+        ).stdout.strip()
+        revisions.append("{}\t{}\t{}\t{}".format(name, repository, revision, commit))
+
+        source = os.path.join(checkout, subdirectory)
+        target = os.path.join(partial, name)
+        os.mkdir(target)
+        # Only the directories with files, not the sources of the test suites
+        for entry in sorted(os.listdir(source)):
+            path = os.path.join(source, entry)
+            if os.path.isdir(path) and not entry.startswith("."):
+                shutil.copytree(path, os.path.join(target, entry))
+
+with open(os.path.join(partial, "REVISIONS"), "w") as target:
+    target.write("\n".join(revisions) + "\n")
+
+# Only a complete download is used by the test
+shutil.rmtree(public, ignore_errors=True)
+os.rename(partial, public)
+print("Downloaded {} into {}".format(", ".join(name for name, *_ in CORPORA), public), file=sys.stderr)
@@ -0,0 +1,49 @@
+# Records how git, grep and file(1) classify the files in this directory.
+# The output (tool_outputs.tsv) is used by the tests of the `Preset`s.
+#
+# - git: binary if `git diff --numstat` reports "-" for the number of lines
+# - grep: binary if `grep -I` skips the file (run with LC_ALL=C)
+# - file: the output of `file --mime-encoding` (via libmagic)
+
+import ctypes
+import os
+import subprocess
+
+MAGIC_MIME_ENCODING = 0x400
+
+libmagic = ctypes.CDLL("libmagic.so.1")
+libmagic.magic_open.restype = ctypes.c_void_p
+libmagic.magic_load.argtypes = [ctypes.c_void_p, ctypes.c_char_p]
+libmagic.magic_file.restype = ctypes.c_char_p
+libmagic.magic_file.argtypes = [ctypes.c_void_p, ctypes.c_char_p]
+
+cookie = libmagic.magic_open(MAGIC_MIME_ENCODING)
+libmagic.magic_load(cookie, None)
+
+env = dict(os.environ, LC_ALL="C")
+
+
+def git(path):
+    numstat = subprocess.run(
+        ["git", "diff", "--no-index", "--numstat", "/dev/null", path],
+        capture_output=True,
+        text=True,
+    ).stdout
+    return "binary" if numstat.startswith("-\t-") else "text"
+
+
+def grep(path):
+    status = subprocess.run(["grep", "-I", "-q", "", path], env=env).returncode
+    return "text" if status == 0 else "binary"
+
+
+def mime_encoding(path):
+    return libmagic.magic_file(cookie, path.encode()).decode()
+
+
+with open("tool_outputs.tsv", "w") as target:
+    target.write("# file\tgit\tgrep\tfile --mime-encoding\n")
+    for name in sorted(os.listdir(".")):
+        if name == "tool_outputs.tsv" or not os.path.isfile(name):
+            continue
+        target.write("\t".join([name, git(name), grep(name), mime_encoding(name)]) + "\n")
@@ -0,0 +1,20 @@
+//! The default inspector is process-wide, so these tests are kept in their own test binary (and
+//! in a single test function, since the test harness runs tests in parallel).
+
+extern crate content_inspector;
+
+use content_inspector::{inspect, inspect_detailed, set_default_inspector, ContentType, Inspector};
+
+#[test]
+fn set_before_first_use() {
+    let inspector = Inspector::new().max_null_bytes(1).build().unwrap();
+    assert!(set_default_inspector(inspector).is_ok());
+
+    assert_eq!(ContentType::UTF_8, inspect(b"one\x00NULL byte"));
+    assert_eq!(ContentType::BINARY, inspect(b"two\x00NULL\x00bytes"));
+    assert_eq!(1, inspect_detailed(b"one\x00NULL byte").null_bytes);
+
+    let inspector = Inspector::default();
+    assert!(set_default_inspector(inspector).is_err());
+    assert_eq!(ContentType::UTF_8, inspect(b"one\x00NULL byte"));
+}
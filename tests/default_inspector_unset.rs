@@ -0,0 +1,15 @@
+//! See `default_inspector.rs`: this runs in a separate process, in which the free functions are
+//! used before the default inspector is set.
+
+extern crate content_inspector;
+
+use content_inspector::{inspect, set_default_inspector, ContentType, Inspector};
+
+#[test]
+fn set_after_first_use() {
+    assert_eq!(ContentType::BINARY, inspect(b"one\x00NULL byte"));
+
+    let inspector = Inspector::new().max_null_bytes(1).build().unwrap();
+    assert!(set_default_inspector(inspector).is_err());
+    assert_eq!(ContentType::BINARY, inspect(b"one\x00NULL byte"));
+}
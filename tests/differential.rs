@@ -0,0 +1,228 @@
+//! A differential test against `file --mime-encoding` and the binary detection of git, which
+//! classifies every file of a corpus with the tools that are installed and with the matching
+//! presets of this crate. It is ignored by default, and needs the default features:
+//!
+//! ```text
+//! cargo test --test differential -- --ignored
+//! DIFFERENTIAL_CORPUS=/path/to/files cargo test --test differential -- --ignored
+//! ```
+//!
+//! The corpus is `testdata` by default. All divergences are written to `differential.tsv` in the
+//! temporary directory of the target (the path is printed), one per line, as file, tool, output
+//! of the tool, output of this crate, and whether the divergence is expected. The expected ones
+//! are listed in `testdata/differential_expectations.tsv` (or the file in
+//! `DIFFERENTIAL_EXPECTATIONS`), and the test fails if a divergence is not listed there, or if a
+//! listed one does not occur anymore. For another corpus without a file of expectations, the
+//! report is only written.
+//!
+//! - `file`: the output of `file --brief --mime-encoding`, compared with `mime_encoding` with
+//!   `Preset::File` and the ASCII detection.
+//! - `git`: "binary" if `git diff --no-index --numstat` reports `-` for the numbers of lines of
+//!   the file (against an empty file), compared with `is_binary` with `Preset::Git`.
+
+use std::collections::BTreeSet;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use content_inspector::{Inspector, Preset};
+
+/// A file for which a tool and this crate disagree.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Divergence {
+    /// The path relative to the corpus, with `/` as the separator.
+    file: String,
+    tool: String,
+    tool_output: String,
+    crate_output: String,
+}
+
+impl Divergence {
+    fn parse(line: &str) -> Divergence {
+        let fields: Vec<&str> = line.split('\t').collect();
+        assert!(fields.len() >= 4, "invalid expectation: {:?}", line);
+        Divergence {
+            file: fields[0].to_string(),
+            tool: fields[1].to_string(),
+            tool_output: fields[2].to_string(),
+            crate_output: fields[3].to_string(),
+        }
+    }
+
+    fn line(&self) -> String {
+        format!(
+            "{}\t{}\t{}\t{}",
+            self.file, self.tool, self.tool_output, self.crate_output
+        )
+    }
+}
+
+/// The files of the corpus, relative to it, in a stable order.
+fn corpus_files(corpus: &Path) -> Vec<String> {
+    let mut files = vec![];
+    let mut directories = vec![corpus.to_path_buf()];
+    while let Some(directory) = directories.pop() {
+        for entry in fs::read_dir(&directory).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                directories.push(path);
+            } else if path.is_file() {
+                let relative = path.strip_prefix(corpus).unwrap();
+                let components: Vec<_> = relative
+                    .components()
+                    .map(|component| component.as_os_str().to_string_lossy())
+                    .collect();
+                files.push(components.join("/"));
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+/// Run the command, or return `None` if it is not installed.
+fn run(command: &mut Command) -> Option<std::process::Output> {
+    match command.output() {
+        Ok(output) => Some(output),
+        Err(ref error) if error.kind() == io::ErrorKind::NotFound => None,
+        Err(error) => panic!("{:?}: {}", command, error),
+    }
+}
+
+/// The outputs of `file --mime-encoding` for the files (in a single run), or `None` if it is not
+/// installed.
+fn file_outputs(corpus: &Path, files: &[String]) -> Option<Vec<String>> {
+    let output = run(Command::new("file")
+        .args(["--brief", "--mime-encoding", "--"])
+        .args(files)
+        .current_dir(corpus))?;
+    assert!(output.status.success(), "file failed: {:?}", output);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let outputs: Vec<String> = stdout.lines().map(str::to_string).collect();
+    assert_eq!(files.len(), outputs.len(), "unexpected output of file");
+    Some(outputs)
+}
+
+/// Whether git classifies the file as binary, or `None` if it is not installed.
+fn git_binary(corpus: &Path, file: &str, empty: &Path) -> Option<bool> {
+    let output = run(Command::new("git")
+        .args([
+            "-c",
+            "core.autocrlf=false",
+            "diff",
+            "--no-index",
+            "--numstat",
+            "--",
+        ])
+        .arg(empty)
+        .arg(file)
+        .current_dir(corpus))?;
+    // The exit code is 1 if there are differences
+    assert!(output.status.code() == Some(1), "git failed: {:?}", output);
+    Some(output.stdout.starts_with(b"-\t-\t"))
+}
+
+fn divergences(corpus: &Path, files: &[String], tools: &mut BTreeSet<String>) -> Vec<Divergence> {
+    let file_preset = Inspector::new()
+        .preset(Preset::File)
+        .detect_ascii(true)
+        .build()
+        .unwrap();
+    let git_preset = Inspector::new().preset(Preset::Git).build().unwrap();
+    let label = |binary: bool| if binary { "binary" } else { "text" }.to_string();
+
+    let empty = Path::new(env!("CARGO_TARGET_TMPDIR")).join("differential_empty");
+    fs::write(&empty, b"").unwrap();
+
+    let file_outputs = file_outputs(corpus, files);
+    let mut divergences = vec![];
+    for (index, file) in files.iter().enumerate() {
+        let buffer = fs::read(corpus.join(file)).unwrap();
+        let mut compare = |tool: &str, tool_output: String, crate_output: String| {
+            tools.insert(tool.to_string());
+            if tool_output != crate_output {
+                divergences.push(Divergence {
+                    file: file.clone(),
+                    tool: tool.to_string(),
+                    tool_output,
+                    crate_output,
+                });
+            }
+        };
+
+        if let Some(outputs) = &file_outputs {
+            let mime_encoding = file_preset.inspect(&buffer).mime_encoding();
+            compare("file", outputs[index].clone(), mime_encoding.to_string());
+        }
+        if let Some(binary) = git_binary(corpus, file, &empty) {
+            let crate_binary = git_preset.inspect(&buffer).is_binary();
+            compare("git", label(binary), label(crate_binary));
+        }
+    }
+    divergences
+}
+
+#[test]
+#[ignore]
+fn differential() {
+    let testdata = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata");
+    let corpus = env::var_os("DIFFERENTIAL_CORPUS").map_or(testdata.clone(), PathBuf::from);
+    let expectations_path = match env::var_os("DIFFERENTIAL_EXPECTATIONS") {
+        Some(path) => Some(PathBuf::from(path)),
+        None if corpus == testdata => Some(testdata.join("differential_expectations.tsv")),
+        None => None,
+    };
+
+    let files = corpus_files(&corpus);
+    let mut tools = BTreeSet::new();
+    let divergences = divergences(&corpus, &files, &mut tools);
+    if tools.is_empty() {
+        eprintln!("Neither file nor git is installed, nothing to compare");
+        return;
+    }
+
+    // Only the expectations of the tools that were run can be checked
+    let expected: BTreeSet<Divergence> = match &expectations_path {
+        Some(path) => fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(Divergence::parse)
+            .filter(|divergence| tools.contains(&divergence.tool))
+            .collect(),
+        None => BTreeSet::new(),
+    };
+
+    let mut report = String::from("# file\ttool\ttool output\tcontent_inspector\texpected\n");
+    for divergence in &divergences {
+        let status = if expected.contains(divergence) {
+            "yes"
+        } else {
+            "no"
+        };
+        report.push_str(&format!("{}\t{}\n", divergence.line(), status));
+    }
+    let report_path = Path::new(env!("CARGO_TARGET_TMPDIR")).join("differential.tsv");
+    fs::write(&report_path, report).unwrap();
+    eprintln!(
+        "{} divergences in {} files ({}), see {}",
+        divergences.len(),
+        files.len(),
+        tools.into_iter().collect::<Vec<_>>().join(", "),
+        report_path.display()
+    );
+
+    if expectations_path.is_some() {
+        let found: BTreeSet<Divergence> = divergences.into_iter().collect();
+        let unexpected: Vec<String> = found.difference(&expected).map(Divergence::line).collect();
+        let missing: Vec<String> = expected.difference(&found).map(Divergence::line).collect();
+        assert!(
+            unexpected.is_empty() && missing.is_empty(),
+            "Unexpected divergences:\n{}\nExpected divergences that did not occur:\n{}",
+            unexpected.join("\n"),
+            missing.join("\n")
+        );
+    }
+}
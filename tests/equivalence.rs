@@ -0,0 +1,315 @@
+//! The entry points of the detection agree with each other: for every file in `testdata` (and
+//! its subdirectories) and for buffers generated by `proptest`, and for a range of inspectors,
+//! the slice functions, the readers, the streaming inspector and the asynchronous reader (with
+//! the `tokio` feature, under a single-threaded runtime) return the same type of content. The
+//! reason of the report for the bytes that a reader consumed is the same as the reason for the
+//! whole buffer.
+//!
+//! The intentional differences are listed in `excluded`.
+
+extern crate content_inspector;
+
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use proptest::prelude::*;
+
+use content_inspector::{
+    ContentType, InspectingReader, Inspector, InspectorBuilder, Preset, Strictness,
+};
+
+/// The number of bytes at the start of a buffer that are split into chunks in every way.
+const CHUNKED_PREFIX: usize = 32;
+
+/// The inspectors to compare the entry points for, by name.
+fn inspectors() -> Vec<(&'static str, Inspector)> {
+    let builders: Vec<(&str, InspectorBuilder)> = vec![
+        ("fast", Inspector::new().strictness(Strictness::Fast)),
+        (
+            "standard",
+            Inspector::new().strictness(Strictness::Standard),
+        ),
+        ("strict", Inspector::new().strictness(Strictness::Strict)),
+        (
+            "paranoid",
+            Inspector::new().strictness(Strictness::Paranoid),
+        ),
+        (
+            "sniffing",
+            Inspector::new()
+                .detect_utf16_without_bom(true)
+                .latin1_fallback(true),
+        ),
+        ("git", Inspector::new().preset(Preset::Git)),
+        ("grep", Inspector::new().preset(Preset::Grep)),
+        ("file", Inspector::new().preset(Preset::File)),
+    ];
+    let mut inspectors = vec![("default", Inspector::default())];
+    inspectors.extend(
+        builders
+            .into_iter()
+            .map(|(name, builder)| (name, builder.build().unwrap())),
+    );
+    inspectors
+}
+
+/// The entry points whose results are compared with `Inspector::inspect`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum EntryPoint {
+    /// The free functions (`inspect`, `inspect_reader` and `inspect_const`), with the default
+    /// inspector only.
+    Free,
+    InspectDetailed,
+    /// `inspect_reader`, with a reader that returns one byte per call.
+    ReaderByteByByte,
+    /// `inspect_buf_read`, with all of the content buffered.
+    BufRead,
+    /// `inspect_reader_with_buf`, with a buffer for all of the content.
+    ReaderWithBuf,
+    InspectingReader,
+    /// `StreamingInspector`, with the first bytes split into chunks in every way.
+    Streaming,
+    #[cfg(feature = "tokio")]
+    AsyncReader,
+    #[cfg(feature = "tokio")]
+    InspectingAsyncReader,
+}
+
+/// The intentional differences of the entry points, which are not compared: none so far, apart
+/// from those that are inherent to the arguments, and are avoided by the arguments above
+/// (`inspect_buf_read` only looks at what is buffered, and `inspect_reader_with_buf` only reads
+/// as much as fits into the buffer). An entry point that returns a different result for some
+/// inspector by design has to be listed here, with the reason.
+fn excluded(_inspector: &str, _entry_point: EntryPoint) -> bool {
+    false
+}
+
+/// A reader that returns a single byte per call, like a slow pipe.
+struct ByteByByte<'a>(&'a [u8]);
+
+impl Read for ByteByByte<'_> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        match (self.0.split_first(), buffer.first_mut()) {
+            (Some((&byte, rest)), Some(target)) => {
+                *target = byte;
+                self.0 = rest;
+                Ok(1)
+            }
+            _ => Ok(0),
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl tokio::io::AsyncRead for ByteByByte<'_> {
+    fn poll_read(
+        mut self: std::pin::Pin<&mut Self>,
+        _context: &mut std::task::Context<'_>,
+        buffer: &mut tokio::io::ReadBuf<'_>,
+    ) -> std::task::Poll<io::Result<()>> {
+        if let Some((&byte, rest)) = self.0.split_first() {
+            if buffer.remaining() > 0 {
+                buffer.put_slice(&[byte]);
+                self.0 = rest;
+            }
+        }
+        std::task::Poll::Ready(Ok(()))
+    }
+}
+
+/// The chunks of the buffer: the first bytes (up to `CHUNKED_PREFIX`) split into chunks of every
+/// length, at every single position, and in every way for the first eight bytes, each followed
+/// by the rest of the buffer in one chunk.
+fn chunkings(buffer: &[u8]) -> Vec<Vec<&[u8]>> {
+    let prefix_length = buffer.len().min(CHUNKED_PREFIX);
+    let (prefix, rest) = buffer.split_at(prefix_length);
+    let mut chunkings = vec![];
+    for length in 1..=prefix_length {
+        chunkings.push(prefix.chunks(length).collect::<Vec<_>>());
+    }
+    for position in 0..=prefix_length {
+        let (first, second) = prefix.split_at(position);
+        chunkings.push(vec![first, second]);
+    }
+    // The positions of the splits of the first bytes are the bits of `splits`
+    let exhaustive = prefix_length.min(8);
+    for splits in 0..1_u32 << exhaustive.saturating_sub(1) {
+        let mut chunks = vec![];
+        let mut start = 0;
+        for end in 1..exhaustive {
+            if splits & (1 << (end - 1)) != 0 {
+                chunks.push(&prefix[start..end]);
+                start = end;
+            }
+        }
+        chunks.push(&prefix[start..]);
+        chunkings.push(chunks);
+    }
+    for chunks in &mut chunkings {
+        chunks.push(rest);
+    }
+    chunkings
+}
+
+/// The results of the entry points that disagree with `Inspector::inspect`.
+fn disagreements(name: &str, inspector: &Inspector, buffer: &[u8]) -> Vec<String> {
+    let expected = inspector.inspect(buffer);
+    let mut disagreements = vec![];
+    let mut check = |entry_point: EntryPoint, content_type: ContentType| {
+        if content_type != expected && !excluded(name, entry_point) {
+            disagreements.push(format!(
+                "{}: {:?} returned {}, not {}",
+                name, entry_point, content_type, expected
+            ));
+        }
+    };
+
+    if name == "default" {
+        check(EntryPoint::Free, content_inspector::inspect(buffer));
+        check(EntryPoint::Free, content_inspector::inspect_const(buffer));
+        let content_type = content_inspector::inspect_reader(buffer).unwrap();
+        check(EntryPoint::Free, content_type);
+    }
+    let report = inspector.inspect_detailed(buffer);
+    check(EntryPoint::InspectDetailed, report.content_type);
+
+    check(
+        EntryPoint::ReaderByteByByte,
+        inspector.inspect_reader(ByteByByte(buffer)).unwrap(),
+    );
+    check(
+        EntryPoint::BufRead,
+        inspector.inspect_buf_read(&mut &buffer[..]).unwrap(),
+    );
+    let mut scratch = vec![0; buffer.len()];
+    let content_type = inspector
+        .inspect_reader_with_buf(ByteByByte(buffer), &mut scratch)
+        .unwrap();
+    check(EntryPoint::ReaderWithBuf, content_type);
+    let mut reader = InspectingReader::with_inspector(inspector, ByteByByte(buffer));
+    io::copy(&mut reader, &mut io::sink()).unwrap();
+    check(EntryPoint::InspectingReader, reader.content_type().unwrap());
+
+    #[cfg(feature = "tokio")]
+    {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let content_type = runtime
+            .block_on(inspector.inspect_async_reader(&mut ByteByByte(buffer)))
+            .unwrap();
+        check(EntryPoint::AsyncReader, content_type);
+        if name == "default" {
+            let content_type = runtime
+                .block_on(content_inspector::inspect_async_reader(&mut ByteByByte(
+                    buffer,
+                )))
+                .unwrap();
+            check(EntryPoint::Free, content_type);
+        }
+        let mut reader =
+            content_inspector::InspectingAsyncReader::with_inspector(inspector, ByteByByte(buffer));
+        runtime
+            .block_on(tokio::io::copy(&mut reader, &mut tokio::io::sink()))
+            .unwrap();
+        check(
+            EntryPoint::InspectingAsyncReader,
+            reader.content_type().unwrap(),
+        );
+    }
+
+    // The reason for the bytes that were read
+    let (_, consumed, _) = inspector.inspect_reader_keep(ByteByByte(buffer)).unwrap();
+    let consumed_reason = inspector.inspect_detailed(&consumed).reason;
+    if consumed_reason != report.reason && !excluded(name, EntryPoint::ReaderByteByByte) {
+        disagreements.push(format!(
+            "{}: the reason for the bytes that were read is {:?}, not {:?}",
+            name, consumed_reason, report.reason
+        ));
+    }
+
+    for chunks in chunkings(buffer) {
+        let mut streaming = inspector.streaming();
+        for chunk in &chunks {
+            streaming.push(chunk);
+        }
+        let content_type = streaming.finalize();
+        if content_type != expected && !excluded(name, EntryPoint::Streaming) {
+            let lengths: Vec<usize> = chunks.iter().map(|chunk| chunk.len()).collect();
+            disagreements.push(format!(
+                "{}: Streaming with chunks of {:?} returned {}, not {}",
+                name, lengths, content_type, expected
+            ));
+            break;
+        }
+    }
+
+    disagreements
+}
+
+fn testdata_files() -> Vec<PathBuf> {
+    let mut files = vec![];
+    let mut directories = vec![Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata")];
+    while let Some(directory) = directories.pop() {
+        for entry in fs::read_dir(&directory).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                directories.push(path);
+            } else if path.is_file() {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+#[test]
+fn fixtures() {
+    let inspectors = inspectors();
+    let mut disagreements = vec![];
+    for path in testdata_files() {
+        let buffer = fs::read(&path).unwrap();
+        for (name, inspector) in &inspectors {
+            for disagreement in self::disagreements(name, inspector, &buffer) {
+                disagreements.push(format!("{}: {}", path.display(), disagreement));
+            }
+        }
+    }
+    assert!(disagreements.is_empty(), "{}", disagreements.join("\n"));
+}
+
+/// Short buffers from the bytes that matter to the detection (byte order marks, NULL bytes,
+/// control characters and the bytes of UTF-8 sequences).
+fn buffer() -> impl Strategy<Value = Vec<u8>> {
+    let byte = prop_oneof![
+        Just(0x00_u8),
+        Just(0x01),
+        Just(b'\n'),
+        Just(b'a'),
+        Just(0x80),
+        Just(0xBB),
+        Just(0xBF),
+        Just(0xC3),
+        Just(0xEF),
+        Just(0xFE),
+        Just(0xFF),
+        any::<u8>(),
+    ];
+    prop::collection::vec(byte, 0..80)
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(200))]
+
+    #[test]
+    fn generated(buffer in buffer()) {
+        let inspectors = inspectors();
+        let mut disagreements = vec![];
+        for (name, inspector) in &inspectors {
+            disagreements.extend(self::disagreements(name, inspector, &buffer));
+        }
+        prop_assert!(disagreements.is_empty(), "{}", disagreements.join("\n"));
+    }
+}
@@ -0,0 +1,109 @@
+//! Checks that the crate compiles with every feature on its own (without the default features),
+//! that `cargo test --no-default-features` builds and passes (examples and integration tests
+//! included), and that the core classification tests pass with each of the encodings and
+//! families of magic numbers on its own, and with all of them, and that the library builds with
+//! the minimum supported Rust version. These builds take a while, so the tests are ignored by
+//! default: run them with `cargo test -- --ignored`.
+
+use std::env;
+use std::path::Path;
+use std::process::Command;
+
+const FEATURES: &[&str] = &[
+    "std",
+    "utf16",
+    "utf32",
+    "legacy-encodings",
+    "magic-numbers",
+    "magic-archives",
+    "magic-documents",
+    "magic-images",
+    "magic-windows",
+    "memchr",
+    "bench-internals",
+    "cache",
+    "ffi",
+    "infer",
+    "flate2",
+    "futures",
+    "grep",
+    "mmap",
+    "rayon",
+    "serde",
+    "tokio",
+    "tracing",
+    "walk",
+    "watch",
+    "wasm",
+    "zstd",
+];
+
+/// The configurations (other than no features at all) of `minimal_configuration_tests`.
+const MINIMAL_CONFIGURATIONS: &[&str] = &[
+    "utf16",
+    "utf32",
+    "legacy-encodings",
+    "magic-archives",
+    "magic-documents",
+    "magic-images",
+    "magic-windows",
+    "utf16,utf32,legacy-encodings,magic-numbers",
+];
+
+/// Run cargo in the crate directory, with a separate target directory (so that the builds do not
+/// wait for the lock of the one running this test).
+fn cargo(args: &[&str]) {
+    let program = env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    run(&program, args, "target/features");
+}
+
+fn run(program: &str, args: &[&str], target_dir: &str) {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let status = Command::new(program)
+        .args(args)
+        .current_dir(manifest_dir)
+        .env("CARGO_TARGET_DIR", manifest_dir.join(target_dir))
+        .status()
+        .unwrap();
+    assert!(status.success(), "cargo {} failed", args.join(" "));
+}
+
+#[test]
+#[ignore]
+fn feature_combinations_compile() {
+    cargo(&["check", "--no-default-features", "--all-targets"]);
+    for feature in FEATURES {
+        cargo(&["check", "--no-default-features", "--features", feature]);
+    }
+    cargo(&["check", "--all-features"]);
+}
+
+#[test]
+#[ignore]
+fn minimal_configuration_tests() {
+    cargo(&["test", "--no-default-features"]);
+    for features in MINIMAL_CONFIGURATIONS {
+        cargo(&[
+            "test",
+            "--no-default-features",
+            "--features",
+            features,
+            "--lib",
+        ]);
+    }
+}
+
+/// The library builds with the `rust-version` of the manifest, installed with rustup (only the
+/// default features: some of the optional dependencies need a newer toolchain).
+#[test]
+#[ignore]
+fn minimum_supported_rust_version() {
+    let toolchain = format!("+{}", env!("CARGO_PKG_RUST_VERSION"));
+    // The rustup proxy selects the toolchain, not the cargo that runs this test
+    run("cargo", &[&toolchain, "check", "--lib"], "target/msrv");
+    run(
+        "cargo",
+        &[&toolchain, "check", "--lib", "--no-default-features"],
+        "target/msrv",
+    );
+}
@@ -0,0 +1,52 @@
+//! Builds the library as a `staticlib`, and compiles and runs the C program in `tests/ffi/test.c`
+//! against it and `include/content_inspector.h`. Needs a C compiler (`cc`, or the one in `CC`).
+
+use std::env;
+use std::path::Path;
+use std::process::Command;
+
+#[test]
+fn c_program() {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let target_dir = manifest_dir.join("target/ffi");
+
+    // A separate target directory, so that the build does not wait for the lock of this one
+    let output = Command::new(env::var("CARGO").unwrap_or_else(|_| "cargo".to_string()))
+        .args([
+            "rustc",
+            "--lib",
+            "--features",
+            "ffi",
+            "--crate-type",
+            "staticlib",
+        ])
+        .args(["--", "--print", "native-static-libs"])
+        .current_dir(manifest_dir)
+        .env("CARGO_TARGET_DIR", &target_dir)
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{}", stderr);
+    // The system libraries that the static library needs, like `-lpthread`
+    let native_libs: Vec<&str> = stderr
+        .lines()
+        .find_map(|line| line.split("native-static-libs: ").nth(1))
+        .map(|libs| libs.split_whitespace().collect())
+        .unwrap_or_default();
+
+    let program = target_dir.join("test_c");
+    let status = Command::new(env::var("CC").unwrap_or_else(|_| "cc".to_string()))
+        .args(["-std=c99", "-Wall", "-Werror", "-I"])
+        .arg(manifest_dir.join("include"))
+        .arg(manifest_dir.join("tests/ffi/test.c"))
+        .arg(target_dir.join("debug/libcontent_inspector.a"))
+        .args(&native_libs)
+        .arg("-o")
+        .arg(&program)
+        .status()
+        .unwrap();
+    assert!(status.success(), "compiling the C program failed");
+
+    let status = Command::new(&program).status().unwrap();
+    assert!(status.success(), "the C program failed");
+}
@@ -0,0 +1,65 @@
+/* Checks the C interface, see tests/ffi.rs. Exits with the line of the first failed check. */
+
+#include <stddef.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <string.h>
+
+#include "content_inspector.h"
+
+#define CHECK(condition)                                                       \
+    if (!(condition)) {                                                        \
+        fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__,       \
+                #condition);                                                   \
+        return 1;                                                              \
+    }
+
+int main(void) {
+    static const uint8_t utf16[] = {0xFF, 0xFE, 'H', 0x00, 'i', 0x00};
+    static const uint8_t binary[] = {'%', 'P', 'D', 'F', '-', 0x00, 0x01};
+    static const uint8_t nul[] = {'a', 'b', 0x00, 0x01};
+    static const uint8_t text[] = "Gr\xC3\xBC\xC3\x9F" "e";
+    CiReport report;
+
+    CHECK(ci_inspect(utf16, sizeof(utf16)) == CI_UTF_16LE);
+    CHECK(ci_inspect(binary, sizeof(binary)) == CI_BINARY);
+    CHECK(ci_inspect(text, sizeof(text) - 1) == CI_UTF_8);
+    CHECK(ci_inspect(NULL, 0) == CI_UTF_8);
+    CHECK(ci_inspect(NULL, 1) == CI_ERROR);
+
+    CHECK(strcmp(ci_content_type_name(CI_BINARY), "binary") == 0);
+    CHECK(strcmp(ci_content_type_name(CI_UTF_16LE), "UTF-16LE") == 0);
+    CHECK(strcmp(ci_content_type_name(CI_ASCII), "ASCII") == 0);
+    CHECK(ci_content_type_name(CI_ERROR) == NULL);
+
+    memset(&report, 0xAA, sizeof(report));
+    CHECK(ci_inspect_detailed(binary, sizeof(binary), &report) == 0);
+    CHECK(report.content_type == CI_BINARY);
+    CHECK(report.reason == 4);
+    CHECK(report.binary_format == 1);
+
+    memset(&report, 0xAA, sizeof(report));
+    CHECK(ci_inspect_detailed(nul, sizeof(nul), &report) == 0);
+    CHECK(report.content_type == CI_BINARY);
+    CHECK(report.detected_type == CI_BINARY);
+    CHECK(report.reason == 3);
+    CHECK(report.binary_format == 0);
+    CHECK(report.null_bytes == 1);
+    CHECK(report.first_null_offset == 2);
+    CHECK(report.bom_length == 0);
+    CHECK(report.invalid_utf8_offset == CI_NONE);
+    CHECK(report.scanned_bytes == sizeof(nul));
+
+    CHECK(ci_inspect_detailed(utf16, sizeof(utf16), &report) == 0);
+    CHECK(report.content_type == CI_UTF_16LE);
+    CHECK(report.reason == 1);
+    CHECK(report.bom_length == 2);
+    CHECK(report.first_null_offset == CI_NONE);
+
+    CHECK(ci_inspect_detailed(NULL, 0, &report) == 0);
+    CHECK(report.reason == 0);
+    CHECK(ci_inspect_detailed(NULL, 1, &report) == -1);
+    CHECK(ci_inspect_detailed(text, sizeof(text) - 1, NULL) == -1);
+
+    return 0;
+}
@@ -0,0 +1,107 @@
+//! Runs the checks of the fuzz targets (see `fuzz/`) without the fuzzer: on the seed corpus, and
+//! on a bounded number of inputs that are derived from it with a simple pseudo-random generator.
+//! This keeps the targets compiling, and catches the shallow failures on every `cargo test`.
+
+#[path = "../fuzz/src/lib.rs"]
+mod harness;
+
+use std::fs;
+use std::path::Path;
+
+/// The number of derived inputs per target.
+const ITERATIONS: usize = 1000;
+
+/// A xorshift generator, so that the derived inputs are the same in every run.
+struct Random(u64);
+
+impl Random {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound as u64) as usize
+    }
+}
+
+fn seeds() -> Vec<Vec<u8>> {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("fuzz/seeds");
+    let mut paths: Vec<_> = fs::read_dir(directory)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    paths.sort();
+    paths.iter().map(|path| fs::read(path).unwrap()).collect()
+}
+
+/// A seed with some bytes replaced, truncated, or with bytes of another seed inserted, like the
+/// mutations of the fuzzer.
+fn derive(random: &mut Random, seeds: &[Vec<u8>]) -> Vec<u8> {
+    let mut input = seeds[random.below(seeds.len())].clone();
+    for _ in 0..random.below(4) + 1 {
+        match random.below(4) {
+            0 if !input.is_empty() => {
+                let index = random.below(input.len());
+                input[index] = random.next() as u8;
+            }
+            1 => input.truncate(random.below(input.len() + 1)),
+            2 => {
+                let other = &seeds[random.below(seeds.len())];
+                let start = random.below(other.len() + 1);
+                let end = start + random.below(other.len() - start + 1).min(64);
+                let index = random.below(input.len() + 1);
+                input.splice(index..index, other[start..end].iter().copied());
+            }
+            _ => {
+                let byte = [0x00, 0xFE, 0xFF, 0xEF, 0xBB, 0xBF, b'\r', b'\n'][random.below(8)];
+                input.insert(random.below(input.len() + 1), byte);
+            }
+        }
+    }
+    input
+}
+
+fn run(check: fn(&[u8]), seed: u64) {
+    let seeds = seeds();
+    assert!(!seeds.is_empty());
+    for input in &seeds {
+        check(input);
+    }
+    let mut random = Random(seed);
+    for _ in 0..ITERATIONS {
+        check(&derive(&mut random, &seeds));
+    }
+}
+
+#[test]
+fn inspect() {
+    run(harness::check_inspect, 1);
+}
+
+#[test]
+fn inspect_detailed() {
+    run(harness::check_inspect_detailed, 2);
+}
+
+#[test]
+fn streaming() {
+    run(harness::check_streaming, 3);
+}
+
+#[test]
+fn decode() {
+    run(harness::check_decode, 4);
+}
+
+#[test]
+fn chunks() {
+    let (content, chunks) = harness::chunks(b"\x02\x01\x00abcd");
+    assert_eq!(b"abcd", content);
+    assert_eq!(vec![&b"a"[..], b"", b"b", b"", b"c", b"", b"d"], chunks);
+
+    let (content, chunks) = harness::chunks(b"");
+    assert!(content.is_empty() && chunks.is_empty());
+}
@@ -0,0 +1,159 @@
+//! A search with `grep-searcher` (with the `grep` feature) over a directory of fixtures, which
+//! searches UTF-16 and UTF-32 text instead of skipping it as binary content.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use grep_regex::RegexMatcher;
+use grep_searcher::sinks::UTF8;
+use grep_searcher::{BinaryDetection, SearcherBuilder};
+
+use content_inspector::{search_path, Inspector};
+
+const FIXTURES: &[&str] = &[
+    "test.png",
+    "text_UTF-8.txt",
+    "text_UTF-16LE-BOM.txt",
+    "text_UTF-16LE.txt",
+    "text_UTF-32LE-BOM.txt",
+];
+
+/// A temporary directory with copies of the fixtures, removed when it is dropped.
+struct Fixtures(PathBuf);
+
+impl Fixtures {
+    fn new(name: &str) -> Fixtures {
+        let directory =
+            env::temp_dir().join(format!("content_inspector_grep_{}_{}", name, process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        for fixture in FIXTURES {
+            let source = Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("testdata")
+                .join(fixture);
+            fs::copy(source, directory.join(fixture)).unwrap();
+        }
+        Fixtures(directory)
+    }
+
+    /// The matching lines of every file in the directory, or `None` for skipped files.
+    fn search<F>(&self, search: F) -> Vec<(String, Option<Vec<String>>)>
+    where
+        F: Fn(&Path, &RegexMatcher, &mut Vec<String>) -> bool,
+    {
+        let matcher = RegexMatcher::new("characters like").unwrap();
+        let mut paths: Vec<_> = fs::read_dir(&self.0)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        paths.sort();
+
+        paths
+            .iter()
+            .map(|path| {
+                let mut lines = vec![];
+                let searched = search(path, &matcher, &mut lines);
+                let name = path.file_name().unwrap().to_string_lossy().into_owned();
+                (name, Some(lines).filter(|_| searched))
+            })
+            .collect()
+    }
+}
+
+impl Drop for Fixtures {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+fn line() -> Option<Vec<String>> {
+    Some(vec![
+        "some characters like 🌂, 💖, ä, 𝄞, € and ∰\n".to_string()
+    ])
+}
+
+#[test]
+fn search_directory() {
+    let fixtures = Fixtures::new("default");
+    let results = fixtures.search(|path, matcher, lines| {
+        search_path(
+            &SearcherBuilder::new(),
+            matcher,
+            path,
+            UTF8(|_, line| {
+                lines.push(line.to_string());
+                Ok(true)
+            }),
+        )
+        .unwrap()
+    });
+
+    // UTF-16 without a byte order mark is binary content for the default inspector
+    assert_eq!(
+        vec![
+            ("test.png".to_string(), None),
+            ("text_UTF-16LE-BOM.txt".to_string(), line()),
+            ("text_UTF-16LE.txt".to_string(), None),
+            ("text_UTF-32LE-BOM.txt".to_string(), line()),
+            ("text_UTF-8.txt".to_string(), line()),
+        ],
+        results
+    );
+}
+
+#[test]
+fn search_utf16_without_bom() {
+    let fixtures = Fixtures::new("utf16");
+    let inspector = Inspector::new()
+        .detect_utf16_without_bom(true)
+        .build()
+        .unwrap();
+    let results = fixtures.search(|path, matcher, lines| {
+        inspector
+            .search_path(
+                &SearcherBuilder::new(),
+                matcher,
+                path,
+                UTF8(|_, line| {
+                    lines.push(line.to_string());
+                    Ok(true)
+                }),
+            )
+            .unwrap()
+    });
+    assert_eq!(("text_UTF-16LE.txt".to_string(), line()), results[2]);
+}
+
+#[test]
+fn nul_based_detection() {
+    // ripgrep's default stops at the first NULL byte, which every UTF-16 file contains
+    let fixtures = Fixtures::new("quit");
+    let results = fixtures.search(|path, matcher, lines| {
+        let mut searcher = SearcherBuilder::new()
+            .binary_detection(BinaryDetection::quit(0x00))
+            .bom_sniffing(false)
+            .build();
+        searcher
+            .search_path(
+                matcher,
+                path,
+                UTF8(|_, line| {
+                    lines.push(line.to_string());
+                    Ok(true)
+                }),
+            )
+            .unwrap();
+        true
+    });
+    assert_eq!(
+        vec![
+            ("test.png".to_string(), Some(vec![])),
+            ("text_UTF-16LE-BOM.txt".to_string(), Some(vec![])),
+            ("text_UTF-16LE.txt".to_string(), Some(vec![])),
+            ("text_UTF-32LE-BOM.txt".to_string(), Some(vec![])),
+            ("text_UTF-8.txt".to_string(), line()),
+        ],
+        results
+    );
+}
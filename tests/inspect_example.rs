@@ -0,0 +1,1210 @@
+//! Builds `examples/inspect.rs` (with the `walk` and `watch` features) and runs it with content piped to its
+//! standard input, or over a directory tree.
+
+use std::env;
+use std::ffi::OsStr;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Output, Stdio};
+use std::sync::OnceLock;
+use std::thread;
+
+/// The path of the example, built once for all tests.
+fn example() -> &'static Path {
+    static EXAMPLE: OnceLock<PathBuf> = OnceLock::new();
+    EXAMPLE.get_or_init(|| {
+        let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let target_dir = manifest_dir.join("target/example");
+
+        // A separate target directory, so that the build does not wait for the lock of this one
+        let status = Command::new(env::var("CARGO").unwrap_or_else(|_| "cargo".to_string()))
+            .args(["build", "--example", "inspect", "--features", "walk,watch"])
+            .current_dir(manifest_dir)
+            .env("CARGO_TARGET_DIR", &target_dir)
+            .status()
+            .unwrap();
+        assert!(status.success(), "building the example failed");
+        target_dir.join("debug/examples/inspect")
+    })
+}
+
+/// The output of the example with the arguments, and the content on its standard input.
+fn output<S: AsRef<OsStr>>(args: &[S], stdin: &'static [u8]) -> Output {
+    let mut child = Command::new(example())
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    // The example stops reading after the first window, so the rest cannot always be written
+    let mut pipe = child.stdin.take().unwrap();
+    let writer = thread::spawn(move || {
+        let _ = pipe.write_all(stdin);
+    });
+    let output = child.wait_with_output().unwrap();
+    writer.join().unwrap();
+    output
+}
+
+/// The exit status and output of the example.
+fn run_bytes<S: AsRef<OsStr>>(args: &[S], stdin: &'static [u8]) -> (i32, Vec<u8>) {
+    let output = output(args, stdin);
+    (output.status.code().unwrap(), output.stdout)
+}
+
+/// The exit status and the output of the example on standard error.
+fn run_errors<S: AsRef<OsStr>>(args: &[S]) -> (i32, String) {
+    let output = output(args, b"");
+    (
+        output.status.code().unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+    )
+}
+
+fn run_status<S: AsRef<OsStr>>(args: &[S], stdin: &'static [u8]) -> (i32, String) {
+    let (status, stdout) = run_bytes(args, stdin);
+    (status, String::from_utf8(stdout).unwrap())
+}
+
+fn run<S: AsRef<OsStr>>(args: &[S], stdin: &'static [u8]) -> String {
+    run_status(args, stdin).1
+}
+
+/// The exit status of the example in quiet mode, which prints nothing.
+fn quiet(args: &[&str], stdin: &'static [u8]) -> i32 {
+    let (status, stdout) = run_status(&[&["-q"], args].concat(), stdin);
+    assert_eq!("", stdout);
+    status
+}
+
+#[test]
+fn text_input() {
+    let text = include_bytes!("../testdata/text_UTF-8.txt");
+    assert_eq!("<stdin>: UTF-8\n", run::<&str>(&[], text));
+    assert_eq!("<stdin>: UTF-8\n", run(&["-"], text));
+
+    let utf16 = include_bytes!("../testdata/text_UTF-16LE-BOM.txt");
+    assert_eq!("<stdin>: UTF-16LE\n", run(&["-"], utf16));
+}
+
+#[test]
+fn binary_input() {
+    let png = include_bytes!("../testdata/test.png");
+    assert_eq!("<stdin>: binary\n", run::<&str>(&[], png));
+
+    let output = run(&["--json", "-"], png);
+    assert!(output.starts_with("{\"path\": \"<stdin>\", \"type\": \"binary\""));
+    assert!(output.contains("\"binary_format\": \"Png\""));
+}
+
+#[test]
+fn large_input() {
+    // Only the first window is read: NULL bytes after it do not matter
+    static LARGE: OnceLock<Vec<u8>> = OnceLock::new();
+    let large = LARGE.get_or_init(|| {
+        let mut large = vec![b'a'; 1024];
+        large.resize(16 * 1024 * 1024, 0);
+        large
+    });
+    assert_eq!("<stdin>: UTF-8\n", run::<&str>(&[], large));
+}
+
+#[test]
+fn files_and_stdin() {
+    let png = include_bytes!("../testdata/test.png");
+    let output = run(&["testdata/text_UTF-8.txt", "-"], png);
+    assert_eq!("testdata/text_UTF-8.txt: UTF-8\n<stdin>: binary\n", output);
+}
+
+/// A temporary directory tree, removed when it is dropped.
+struct Tree(PathBuf);
+
+impl Tree {
+    fn empty(name: &str) -> Tree {
+        let root = env::temp_dir().join(format!(
+            "content_inspector_example_{}_{}",
+            name,
+            process::id()
+        ));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        Tree(root)
+    }
+
+    /// A tree with ignored, hidden and binary files.
+    fn new(name: &str) -> Tree {
+        let tree = Tree::empty(name);
+        tree.write(".gitignore", b"*.log\nbuild/\n");
+        tree.write("README.md", b"text\n");
+        tree.write("debug.log", b"ignored\n");
+        tree.write("build/output.bin", b"\x00\x01");
+        tree.write("src/.hidden", b"hidden\n");
+        tree.write("src/main.rs", b"fn main() {}\n");
+        tree.write(
+            "src/nested/image.png",
+            include_bytes!("../testdata/test.png"),
+        );
+        tree.write("src/nested/.gitignore", b"generated.txt\n");
+        tree.write("src/nested/generated.txt", b"ignored\n");
+        // A symbolic link back to the root, which must not be followed
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(&tree.0, tree.0.join("src/nested/loop")).unwrap();
+        tree
+    }
+
+    fn write(&self, name: &str, content: &[u8]) {
+        let path = self.0.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    /// The output of the example over the tree, with the paths relative to its root.
+    fn inspect(&self, options: &[&str]) -> Vec<String> {
+        let mut args: Vec<_> = options.iter().map(PathBuf::from).collect();
+        args.push(self.0.clone());
+        let prefix = format!("{}/", self.0.display());
+        run(&args, b"")
+            .lines()
+            .map(|line| line.strip_prefix(&prefix).unwrap().to_string())
+            .collect()
+    }
+}
+
+impl Drop for Tree {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+#[test]
+fn recursive() {
+    let tree = Tree::new("recursive");
+    assert_eq!(
+        vec![
+            "README.md: UTF-8",
+            "src/main.rs: UTF-8",
+            "src/nested/image.png: binary",
+        ],
+        tree.inspect(&["-r"])
+    );
+
+    assert_eq!(
+        vec![
+            ".gitignore: UTF-8",
+            "README.md: UTF-8",
+            "build/output.bin: binary",
+            "debug.log: UTF-8",
+            "src/.hidden: UTF-8",
+            "src/main.rs: UTF-8",
+            "src/nested/.gitignore: UTF-8",
+            "src/nested/generated.txt: UTF-8",
+            "src/nested/image.png: binary",
+        ],
+        tree.inspect(&["--recursive", "--no-ignore"])
+    );
+
+    // Without -r, directories are errors
+    assert!(tree.inspect(&[]).is_empty());
+    assert_eq!(2, quiet(&[tree.0.to_str().unwrap()], b""));
+
+    // The link back to the root is only followed with --dereference, and the cycle is an error
+    #[cfg(unix)]
+    assert_eq!(
+        2,
+        quiet(&["-r", "--dereference", tree.0.to_str().unwrap()], b"")
+    );
+}
+
+#[test]
+fn exit_status() {
+    let text = "testdata/text_UTF-8.txt";
+    let utf16 = "testdata/text_UTF-16LE-BOM.txt";
+    let png = "testdata/test.png";
+    let missing = "testdata/missing.txt";
+
+    assert_eq!(0, quiet(&[text], b""));
+    assert_eq!(0, quiet(&[text, utf16], b""));
+    assert_eq!(1, quiet(&[text, png], b""));
+    assert_eq!(2, quiet(&[missing], b""));
+    // Errors take precedence, like with grep
+    assert_eq!(2, quiet(&[text, missing, utf16], b""));
+    assert_eq!(2, quiet(&[png, missing], b""));
+    // Directories are errors, too
+    assert_eq!(2, quiet(&[text, "testdata"], b""));
+
+    // Without arguments, standard input is inspected
+    assert_eq!(0, quiet(&[], b"text"));
+    assert_eq!(1, quiet(&[], b"\x00\x01\x02"));
+    assert_eq!(0, quiet(&[], b""));
+
+    // The filters select the files that count, and succeed if there are any
+    assert_eq!(0, quiet(&["--binary-only", text, png], b""));
+    assert_eq!(1, quiet(&["--binary-only", text, utf16], b""));
+    assert_eq!(0, quiet(&["--text-only", text, png], b""));
+    assert_eq!(1, quiet(&["--text-only", png], b""));
+
+    // Without -q, the exit status is the same
+    let (status, stdout) = run_status(&["--binary-only", text, png], b"");
+    assert_eq!((0, "testdata/test.png: binary\n"), (status, &stdout[..]));
+    let (status, stdout) = run_status(&[text, missing], b"");
+    assert_eq!(
+        (2, "testdata/text_UTF-8.txt: UTF-8\n"),
+        (status, &stdout[..])
+    );
+
+    // Usage errors
+    assert_eq!(2, quiet(&["--pretty", text], b""));
+}
+
+#[test]
+fn threads() {
+    let tree = Tree::empty("threads");
+    for i in 0..400 {
+        let name = format!("{}/{}/file_{}", i % 7, i % 3, i);
+        match i % 4 {
+            0 => tree.write(&name, b"\x00\x01\x02"),
+            1 => tree.write(&name, "Grüße".repeat(i).as_bytes()),
+            2 => tree.write(&name, b"\xFF\xFEt\x00e\x00x\x00t\x00"),
+            _ => tree.write(&name, &vec![b'a'; i * 10]),
+        }
+    }
+
+    let serial = tree.inspect(&["-r", "--threads", "1"]);
+    assert_eq!(400, serial.len());
+    assert_eq!(serial, tree.inspect(&["-r", "--threads", "8"]));
+
+    let mut unordered = tree.inspect(&["-r", "--threads", "8", "--unordered"]);
+    let mut sorted = serial.clone();
+    sorted.sort();
+    unordered.sort();
+    assert_eq!(sorted, unordered);
+
+    // Errors keep their place in the output
+    let args = [
+        "--json",
+        "--threads",
+        "8",
+        "testdata/text_UTF-8.txt",
+        "testdata/missing.txt",
+        "testdata/test.png",
+    ];
+    let (status, stdout) = run_status(&args, b"");
+    assert_eq!(2, status);
+    let paths: Vec<_> = stdout
+        .lines()
+        .map(|line| line.split('"').nth(3).unwrap())
+        .collect();
+    assert_eq!(&args[3..], &paths[..]);
+    assert!(stdout.lines().nth(1).unwrap().contains("\"error\""));
+}
+
+#[test]
+fn print0() {
+    let tree = Tree::empty("print0");
+    tree.write("line\nbreak: and colon.txt", b"text");
+    tree.write("image.png", include_bytes!("../testdata/test.png"));
+    let mut expected = vec![
+        (tree.0.join("line\nbreak: and colon.txt"), "UTF-8"),
+        (tree.0.join("image.png"), "binary"),
+    ];
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        let path = tree.0.join(OsStr::from_bytes(b"invalid \xFF\nUTF-8"));
+        fs::write(&path, b"\xFF\xFEt\x00").unwrap();
+        expected.push((path, "UTF-16LE"));
+    }
+
+    // The error for the missing file is not written to standard output
+    let mut args = vec![PathBuf::from("-0"), tree.0.join("missing")];
+    args.extend(expected.iter().map(|(path, _)| path.clone()));
+    let (status, stdout) = run_bytes(&args, b"");
+    assert_eq!(2, status);
+
+    let mut fields: Vec<&[u8]> = stdout.split(|&byte| byte == 0).collect();
+    assert_eq!(Some(&[][..]), fields.pop());
+    let records: Vec<_> = fields
+        .chunks(2)
+        .map(|record| (record[0], record[1]))
+        .collect();
+    let expected: Vec<_> = expected
+        .iter()
+        .map(|(path, content_type)| (path_bytes(path), content_type.as_bytes()))
+        .collect();
+    assert_eq!(expected, records);
+}
+
+#[test]
+fn list() {
+    let tree = Tree::empty("list");
+    tree.write("README.md", b"text\n");
+    tree.write("src/main.rs", b"fn main() {}\n");
+    tree.write("src/utf16.txt", b"\xFF\xFEt\x00");
+    tree.write("assets/image.png", include_bytes!("../testdata/test.png"));
+    tree.write("assets/data.bin", b"\x00\x01\x02");
+    tree.write("assets/new\nline.bin", b"\x00");
+
+    let list = |options: &[&str], root: &Path| {
+        let mut args: Vec<_> = options.iter().map(PathBuf::from).collect();
+        args.push(root.to_path_buf());
+        run_bytes(&args, b"")
+    };
+    let paths = |names: &[&str], terminator: &str| -> Vec<u8> {
+        names
+            .iter()
+            .map(|name| format!("{}/{}{}", tree.0.display(), name, terminator))
+            .collect::<String>()
+            .into_bytes()
+    };
+    let binary = [
+        "assets/data.bin",
+        "assets/image.png",
+        "assets/new\nline.bin",
+    ];
+    let text = ["README.md", "src/main.rs", "src/utf16.txt"];
+
+    // Only the paths, one per line
+    assert_eq!(
+        (0, paths(&binary, "\n")),
+        list(&["-r", "--list-binary"], &tree.0)
+    );
+    assert_eq!(
+        (0, paths(&text, "\n")),
+        list(&["-r", "--list-text"], &tree.0)
+    );
+
+    // With -0 (or -z), the paths are followed by NULL bytes
+    assert_eq!(
+        (0, paths(&binary, "\0")),
+        list(&["-r", "--list-binary", "-0"], &tree.0)
+    );
+    assert_eq!(
+        (0, paths(&binary, "\0")),
+        list(&["-r", "--print0", "--list-binary"], &tree.0)
+    );
+    assert_eq!(
+        (0, paths(&text, "\0")),
+        list(&["-r", "-z", "--list-text"], &tree.0)
+    );
+
+    // Like with --binary-only, the exit status is 1 if no file matches
+    assert_eq!(
+        (1, vec![]),
+        list(&["-r", "-z", "--list-binary"], &tree.0.join("src"))
+    );
+    assert_eq!(2, run_status(&["--list-text", "--json", "-"], b"").0);
+}
+
+#[cfg(unix)]
+fn path_bytes(path: &Path) -> &[u8] {
+    use std::os::unix::ffi::OsStrExt;
+    path.as_os_str().as_bytes()
+}
+
+#[cfg(not(unix))]
+fn path_bytes(path: &Path) -> &[u8] {
+    path.to_str().unwrap().as_bytes()
+}
+
+/// Fails if the output for the fixtures with the option changes. Run with
+/// `UPDATE_INSPECT_SNAPSHOT=1` to record the new output.
+fn snapshot(option: &str, name: &str) {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let mut fixtures: Vec<_> = fs::read_dir(manifest_dir.join("testdata"))
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .filter(|name| name.starts_with("test") || name.starts_with("text_"))
+        .map(|name| format!("testdata/{}", name))
+        .collect();
+    fixtures.sort();
+    fixtures.insert(0, option.to_string());
+
+    let output = run(&fixtures, b"");
+    let path = manifest_dir.join("testdata").join(name);
+    if env::var_os("UPDATE_INSPECT_SNAPSHOT").is_some() {
+        fs::write(&path, &output).unwrap();
+    } else {
+        let snapshot = fs::read_to_string(&path).unwrap_or_default();
+        assert!(
+            snapshot == output,
+            "The output of {} changed, run the tests with UPDATE_INSPECT_SNAPSHOT=1:\n{}",
+            option,
+            output
+        );
+    }
+}
+
+#[test]
+fn verbose_snapshot() {
+    snapshot("-v", "inspect_verbose.snap");
+}
+
+#[test]
+fn color_snapshot() {
+    snapshot("--color=always", "inspect_color.snap");
+}
+
+#[test]
+fn color() {
+    let args = ["testdata/text_UTF-8.txt", "testdata/test.png"];
+    let plain = "testdata/text_UTF-8.txt: UTF-8\ntestdata/test.png: binary\n";
+
+    // Standard output is not a terminal
+    assert_eq!(plain, run(&args, b""));
+    assert_eq!(plain, run(&[&["--color=auto"], &args[..]].concat(), b""));
+    assert_eq!(
+        plain,
+        run(&[&["--color", "never"], &args[..]].concat(), b"")
+    );
+
+    let table = "testdata/text_UTF-8.txt  \x1b[32mUTF-8\x1b[0m\n\
+                 testdata/test.png        \x1b[31mbinary\x1b[0m\n\
+                 2 files: 1 \x1b[32mUTF-8\x1b[0m, 1 \x1b[31mbinary\x1b[0m\n";
+    assert_eq!(table, run(&[&["--color=always"], &args[..]].concat(), b""));
+
+    // Forced colors take precedence over NO_COLOR
+    let output = Command::new(example())
+        .args(["--color=always", "testdata/test.png"])
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    assert_eq!(
+        "testdata/test.png  \x1b[31mbinary\x1b[0m\n1 file: 1 \x1b[31mbinary\x1b[0m\n",
+        String::from_utf8(output.stdout).unwrap()
+    );
+
+    // Other formats have no colors
+    assert!(!run(&["--color=always", "--json", args[1]], b"").contains('\x1b'));
+    assert_eq!(2, run_status(&["--color=sometimes", args[0]], b"").0);
+}
+
+#[test]
+fn mime() {
+    let output = run(
+        &[
+            "--mime",
+            "testdata/text_regfile.txt",
+            "testdata/text_UTF-8-BOM.txt",
+            "testdata/text_UTF-16LE-BOM.txt",
+            "testdata/text_Latin-1.txt",
+            "testdata/test.png",
+        ],
+        b"",
+    );
+    assert_eq!(
+        "testdata/text_regfile.txt: us-ascii\n\
+         testdata/text_UTF-8-BOM.txt: utf-8\n\
+         testdata/text_UTF-16LE-BOM.txt: utf-16le\n\
+         testdata/text_Latin-1.txt: unknown-8bit\n\
+         testdata/test.png: binary\n",
+        output
+    );
+}
+
+#[test]
+fn check() {
+    let tree = Tree::empty("check");
+    tree.write("main.rs", "fn main() { println!(\"Grüße\"); }\n".as_bytes());
+    tree.write("ascii.rs", b"fn ascii() {}\n");
+    tree.write("empty.rs", b"");
+    tree.write("bom.rs", b"\xEF\xBB\xBFfn bom() {}\n");
+    tree.write("utf16.rs", b"\xFF\xFEf\x00n\x00");
+    tree.write("invalid.rs", b"fn \xFF() {}\n");
+    let path = |name: &str| tree.0.join(name).into_os_string().into_string().unwrap();
+    let check = |args: &[&str], names: &[&str]| {
+        let mut args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        args.extend(names.iter().map(|name| path(name)));
+        run_status(&args, b"")
+    };
+
+    let passing = ["main.rs", "ascii.rs", "empty.rs"];
+    assert_eq!((0, String::new()), check(&["--check", "utf-8"], &passing));
+    assert_eq!(
+        (0, String::new()),
+        check(&["--check", "UTF-8", "--no-bom"], &passing)
+    );
+
+    // One line per offending file, with the reason
+    let (status, output) = check(&["--check", "utf-8"], &["main.rs", "bom.rs", "utf16.rs"]);
+    assert_eq!(1, status);
+    assert_eq!(
+        format!(
+            "{}: expected UTF-8, found UTF-8-BOM (3-byte byte order mark at the start)\n\
+             {}: expected UTF-8, found UTF-16LE (2-byte byte order mark at the start)\n",
+            path("bom.rs"),
+            path("utf16.rs")
+        ),
+        output
+    );
+    assert_eq!(
+        (0, String::new()),
+        check(&["--check", "utf-8", "--allow-bom"], &["main.rs", "bom.rs"])
+    );
+    assert_eq!(
+        1,
+        check(&["--check", "utf-8", "--allow-bom"], &["utf16.rs"]).0
+    );
+
+    // The encoding is validated, with the offset of the error
+    let (status, output) = check(&["--check", "utf-8"], &["invalid.rs"]);
+    assert_eq!(1, status);
+    assert_eq!(
+        format!(
+            "{}: expected UTF-8, found binary (invalid UTF-8 at offset 3)\n",
+            path("invalid.rs")
+        ),
+        output
+    );
+
+    assert_eq!(0, check(&["--check", "us-ascii"], &["ascii.rs"]).0);
+    assert_eq!(1, check(&["--check", "ascii"], &["main.rs"]).0);
+    assert_eq!(0, check(&["--check", "utf-16le"], &["utf16.rs"]).0);
+    assert_eq!(
+        2,
+        check(&["--check", "utf-8"], &["main.rs", "missing.rs"]).0
+    );
+    assert_eq!(2, check(&["--check", "unknown"], &["main.rs"]).0);
+}
+
+#[test]
+fn convert() {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let fixture = |name: &str| fs::read(manifest_dir.join("testdata").join(name)).unwrap();
+    let utf8 = fixture("text_UTF-8.txt");
+
+    // To standard output, without a byte order mark
+    for name in [
+        "text_UTF-16LE-BOM.txt",
+        "text_UTF-32BE-BOM.txt",
+        "text_UTF-8-BOM.txt",
+    ] {
+        let path = manifest_dir.join("testdata").join(name);
+        let (status, output) = run_bytes(
+            &["--convert".as_ref(), "utf-8".as_ref(), path.as_os_str()],
+            b"",
+        );
+        assert_eq!((0, &utf8), (status, &output), "{}", name);
+    }
+    let (_, output) = run_bytes(&["--convert", "UTF-8", "--add-bom"], b"\xFF\xFEh\x00i\x00");
+    assert_eq!(b"\xEF\xBB\xBFhi", &output[..]);
+    let (_, output) = run_bytes(&["--convert", "utf-16le"], b"hi");
+    assert_eq!(b"\xFF\xFEh\x00i\x00", &output[..]);
+    let (_, output) = run_bytes(&["--convert", "utf-8"], b"caf\xE9 au lait\n");
+    assert_eq!("café au lait\n".as_bytes(), &output[..]);
+
+    // In place, binary files are skipped and left as they are
+    let tree = Tree::empty("convert");
+    tree.write("utf16.txt", &fixture("text_UTF-16LE-BOM.txt"));
+    tree.write("utf8.txt", &utf8);
+    tree.write("image.png", &fixture("test.png"));
+    let path = |name: &str| tree.0.join(name).into_os_string().into_string().unwrap();
+    let (status, output) = run_status(
+        &[
+            "--convert".to_string(),
+            "utf-8".to_string(),
+            "--in-place".to_string(),
+            path("image.png"),
+            path("utf16.txt"),
+            path("utf8.txt"),
+        ],
+        b"",
+    );
+    assert_eq!(1, status);
+    assert_eq!(
+        format!("{}: converted from UTF-16LE to UTF-8\n", path("utf16.txt")),
+        output
+    );
+    assert_eq!(utf8, fs::read(path("utf16.txt")).unwrap());
+    assert_eq!(utf8, fs::read(path("utf8.txt")).unwrap());
+    assert_eq!(fixture("test.png"), fs::read(path("image.png")).unwrap());
+    assert_eq!(
+        3,
+        fs::read_dir(&tree.0).unwrap().count(),
+        "no temporary files are left"
+    );
+
+    assert_eq!(2, run_status(&["--convert", "latin-1"], b"hi").0);
+    assert_eq!(2, run_status(&["--in-place"], b"hi").0);
+    assert_eq!(
+        2,
+        run_status(&["--convert", "utf-8", "--in-place"], b"hi").0
+    );
+}
+
+#[test]
+fn peek_size() {
+    // A NULL byte after the default window
+    let tree = Tree::empty("peek_size");
+    let mut content = vec![b'a'; 1500];
+    content.push(0x00);
+    content.extend_from_slice(b"text after the NULL byte\n");
+    tree.write("late_null.txt", &content);
+    let path = tree.0.join("late_null.txt");
+    let inspect = |args: &[&str]| {
+        let mut args: Vec<_> = args.iter().map(OsStr::new).collect();
+        args.push(path.as_os_str());
+        run(&args, b"")
+    };
+    let result = |content_type| format!("{}: {}\n", path.display(), content_type);
+
+    assert_eq!(result("UTF-8"), inspect(&[]));
+    assert_eq!(result("UTF-8"), inspect(&["--bytes", "1024"]));
+    assert_eq!(result("binary"), inspect(&["--bytes", "4096"]));
+    assert_eq!(result("binary"), inspect(&["--all"]));
+    assert_eq!(result("binary"), inspect(&["--all", "--force"]));
+
+    // The limit is part of the JSON output
+    let json = inspect(&["--json", "--bytes", "4096"]);
+    assert!(json.contains("\"first_null_offset\": 1500"));
+    assert!(json.contains("\"peek_size\": 4096"));
+    assert!(inspect(&["--json"]).contains("\"peek_size\": 1024"));
+    assert!(inspect(&["--json", "--all"]).contains("\"peek_size\": 67108864"));
+    assert!(inspect(&["--json", "--all", "--force"]).contains("\"peek_size\": null"));
+
+    assert_eq!(2, run_status(&["--bytes", "0"], b"").0);
+    assert_eq!(2, run_status(&["--force"], b"").0);
+}
+
+#[test]
+fn not_regular_files() {
+    let tree = Tree::empty("not_regular");
+    tree.write("text.txt", b"text\n");
+    tree.write("directory/file.txt", b"text\n");
+    let path = |name: &str| tree.0.join(name).into_os_string().into_string().unwrap();
+
+    // Every path is reported, and the other files are still inspected
+    let (status, errors) = run_errors(&[path("missing.txt"), path("directory"), path("text.txt")]);
+    assert_eq!(2, status);
+    assert_eq!(
+        format!(
+            "{}: not found\n{}: is a directory (use -r to inspect its files)\n",
+            path("missing.txt"),
+            path("directory")
+        ),
+        errors
+    );
+    let (status, output) = run_status(&[path("directory"), path("text.txt")], b"");
+    assert_eq!(
+        (2, format!("{}: UTF-8\n", path("text.txt"))),
+        (status, output)
+    );
+    assert!(run(&["--json", &path("directory")], b"")
+        .contains("\"error\": \"is a directory (use -r to inspect its files)\""));
+}
+
+#[cfg(unix)]
+#[test]
+fn not_regular_files_unix() {
+    use std::os::unix::fs::{symlink, PermissionsExt};
+
+    let tree = Tree::empty("not_regular_unix");
+    tree.write("text.txt", b"text\n");
+    tree.write("locked.txt", b"text\n");
+    symlink(tree.0.join("missing.txt"), tree.0.join("dangling")).unwrap();
+    symlink(tree.0.join("text.txt"), tree.0.join("link")).unwrap();
+    let path = |name: &str| tree.0.join(name).into_os_string().into_string().unwrap();
+
+    assert_eq!(
+        (
+            2,
+            format!("{}: is a dangling symbolic link\n", path("dangling"))
+        ),
+        run_errors(&[path("dangling")])
+    );
+
+    // Links on the command line are followed, unless --no-dereference is given
+    assert_eq!(
+        (0, format!("{}: UTF-8\n", path("link"))),
+        run_status(&[path("link")], b"")
+    );
+    assert_eq!(
+        (0, format!("{}: UTF-8\n", path("link"))),
+        run_status(&["--dereference".to_string(), path("link")], b"")
+    );
+    assert_eq!(
+        (
+            2,
+            format!(
+                "{}: is a symbolic link (not followed with --no-dereference)\n",
+                path("link")
+            )
+        ),
+        run_errors(&["--no-dereference".to_string(), path("link")])
+    );
+
+    // Root can read files without permissions
+    let locked = tree.0.join("locked.txt");
+    fs::set_permissions(&locked, fs::Permissions::from_mode(0o000)).unwrap();
+    if fs::read(&locked).is_err() {
+        assert_eq!(
+            (2, format!("{}: permission denied\n", path("locked.txt"))),
+            run_errors(&[path("locked.txt")])
+        );
+    }
+    fs::set_permissions(&locked, fs::Permissions::from_mode(0o644)).unwrap();
+}
+
+#[test]
+fn format() {
+    let args = |template: &str| {
+        vec![
+            "--format".to_string(),
+            template.to_string(),
+            "testdata/text_UTF-8.txt".to_string(),
+            "testdata/text_UTF-16LE-BOM.txt".to_string(),
+            "testdata/test.png".to_string(),
+        ]
+    };
+    assert_eq!(
+        "testdata/text_UTF-8.txt\tUTF-8\tutf-8\ttrue\t71\n\
+         testdata/text_UTF-16LE-BOM.txt\tUTF-16LE\tutf-16le\ttrue\t122\n\
+         testdata/test.png\tbinary\tbinary\tfalse\t251\n",
+        run(
+            &args("{path}\\t{type}\\t{mime}\\t{is_text}\\t{size}\\n"),
+            b""
+        )
+    );
+    assert_eq!(
+        "NoBinaryIndicators 0 LF|ByteOrderMark 2 LF|MagicNumber 0 |",
+        run(&args("{reason} {bom_length} {line_endings}|"), b"")
+    );
+
+    // The default output is the same as the one of its template
+    assert_eq!(
+        run(&args("{path}: {type}\\n")[2..], b""),
+        run(&args("{path}: {type}\\n"), b"")
+    );
+
+    // Values that do not apply are empty, like the size of standard input
+    assert_eq!(
+        "{<stdin>}  \\ Png\n",
+        run(
+            &["--format", "{{{path}}} {size} \\\\ {binary_format}\\n"],
+            include_bytes!("../testdata/test.png")
+        )
+    );
+    assert_eq!(
+        "3\n",
+        run(&["--format", "{first_null_offset}\\n"], b"abc\0")
+    );
+    assert_eq!("\n", run(&["--format", "{first_null_offset}\\n"], b"abc"));
+
+    for template in ["{size", "{unknown}", "}", "\\x"] {
+        assert_eq!(
+            2,
+            run_status(&["--format", template], b"").0,
+            "{}",
+            template
+        );
+    }
+    let (status, errors) = run_errors(&["--format", "{paht}"]);
+    assert_eq!(2, status);
+    assert!(errors.starts_with(
+        "--format: unknown placeholder '{paht}', the placeholders are {path}, {type}"
+    ));
+    assert_eq!(2, run_status(&["--format", "{path}", "--json", "-"], b"").0);
+}
+
+#[test]
+fn watch() {
+    use std::io::{BufRead, BufReader};
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    let tree = Tree::empty("watch");
+    tree.write("file.txt", b"text\n");
+    let path = tree.0.join("file.txt");
+    let mut child = Command::new(example())
+        .args([OsStr::new("--watch"), path.as_os_str()])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let (sender, lines) = mpsc::channel();
+    let stdout = BufReader::new(child.stdout.take().unwrap());
+    thread::spawn(move || {
+        for line in stdout.lines() {
+            sender.send(line.unwrap()).unwrap();
+        }
+    });
+    let timeout = Duration::from_secs(10);
+    let name = path.display();
+    assert_eq!(
+        format!("{}: UTF-8", name),
+        lines.recv_timeout(timeout).unwrap()
+    );
+
+    // The changes are only noticed once the watcher is ready
+    let mut errors = BufReader::new(child.stderr.take().unwrap());
+    let mut note = String::new();
+    errors.read_line(&mut note).unwrap();
+    assert!(note.starts_with("Watching for changes"), "{}", note);
+
+    fs::write(&path, b"\x00\x01\x02").unwrap();
+    assert_eq!(
+        format!("{}: binary", name),
+        lines.recv_timeout(timeout).unwrap()
+    );
+    assert!(lines.recv_timeout(Duration::from_millis(500)).is_err());
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    // Standard input can not be watched
+    assert_eq!(2, run_status(&["--watch"], b"").0);
+    assert_eq!(2, run_status(&["--watch", "--json", "-"], b"").0);
+}
+
+#[test]
+fn summary() {
+    let tree = Tree::empty("summary");
+    tree.write("README.md", b"text\n");
+    tree.write("src/main.rs", b"fn main() {}\n");
+    tree.write("src/lib.rs", b"pub fn f() {}\n");
+    tree.write("src/utf16.txt", b"\xFF\xFEt\x00e\x00x\x00t\x00");
+    tree.write("src/legacy.txt", b"\xEF\xBB\xBFtext");
+    tree.write("image.png", include_bytes!("../testdata/test.png"));
+    let root = tree.0.to_str().unwrap();
+    let path = |name: &str| tree.0.join(name).display().to_string();
+
+    let (status, stdout) = run_status(&["-r", "--summary", root], b"");
+    assert_eq!(1, status);
+    assert_eq!(
+        format!(
+            "type       files  bytes\n\
+             UTF-8          3     32\n\
+             binary         1    251\n\
+             UTF-16LE       1     10\n\
+             UTF-8-BOM      1      7\n\
+             total          6    300\n\
+             \n\
+             2 text files are not UTF-8:\n  \
+             {}: UTF-16LE\n  \
+             {}: UTF-8-BOM\n",
+            path("src/utf16.txt"),
+            path("src/legacy.txt")
+        ),
+        stdout
+    );
+
+    // The bars are scaled to the type with the most files
+    let stdout = run(&["-r", "--histogram", root], b"");
+    let bars: Vec<_> = stdout
+        .lines()
+        .skip(1)
+        .take(4)
+        .map(|line| line.rsplit(' ').next().unwrap().len())
+        .collect();
+    assert_eq!(vec![40, 14, 14, 14], bars);
+
+    // The summary is the last JSON object
+    let stdout = run(&["-r", "--summary", "--json", root], b"");
+    let lines: Vec<_> = stdout.lines().collect();
+    assert_eq!(7, lines.len());
+    assert_eq!(
+        format!(
+            "{{\"summary\": {{\"files\": 6, \"bytes\": 300, \"types\": {{\
+             \"UTF-8\": {{\"files\": 3, \"bytes\": 32}}, \
+             \"binary\": {{\"files\": 1, \"bytes\": 251}}, \
+             \"UTF-16LE\": {{\"files\": 1, \"bytes\": 10}}, \
+             \"UTF-8-BOM\": {{\"files\": 1, \"bytes\": 7}}}}, \
+             \"dominant_text_type\": \"UTF-8\", \"outliers\": [\
+             {{\"path\": \"{}\", \"type\": \"UTF-16LE\"}}, \
+             {{\"path\": \"{}\", \"type\": \"UTF-8-BOM\"}}]}}}}",
+            path("src/utf16.txt"),
+            path("src/legacy.txt")
+        ),
+        lines[6]
+    );
+
+    // Without text, there are no outliers, and standard input counts the bytes that were read
+    assert_eq!(
+        "type    files  bytes\nbinary      1      3\ntotal       1      3\n",
+        run(&["--summary"], b"\x00\x01\x02")
+    );
+    assert_eq!(2, run_status(&["--summary", "-0", "-"], b"").0);
+    assert_eq!(
+        2,
+        run_status(&["--summary", "--check", "utf-8", "-"], b"").0
+    );
+}
+
+#[test]
+fn textconv() {
+    let reference = fs::read("testdata/text_UTF-8.txt").unwrap();
+    for name in [
+        "text_UTF-16LE-BOM.txt",
+        "text_UTF-16BE-BOM.txt",
+        "text_UTF-16LE.txt",
+        "text_UTF-16BE.txt",
+        "text_UTF-32LE-BOM.txt",
+        "text_UTF-32BE-BOM.txt",
+    ] {
+        let path = format!("testdata/{}", name);
+        assert_eq!(
+            (0, reference.clone()),
+            run_bytes(&["--textconv", &path], b""),
+            "{}",
+            name
+        );
+    }
+
+    // Git passes temporary files, which can be empty
+    let tree = Tree::empty("textconv");
+    tree.write("2Hx8Ua_main.rc", b"");
+    tree.write("latin1.txt", b"caf\xE9\n");
+    let path = |name: &str| tree.0.join(name);
+    assert_eq!(
+        (0, vec![]),
+        run_bytes(
+            &[OsStr::new("--textconv"), path("2Hx8Ua_main.rc").as_os_str()],
+            b""
+        )
+    );
+
+    // Other text is unchanged, binary content is an error without output
+    assert_eq!(
+        (0, b"caf\xE9\n".to_vec()),
+        run_bytes(
+            &[OsStr::new("--textconv"), path("latin1.txt").as_os_str()],
+            b""
+        )
+    );
+    assert_eq!(
+        (0, b"\xEF\xBB\xBFtext".to_vec()),
+        run_bytes(&["--textconv", "-"], b"\xEF\xBB\xBFtext")
+    );
+    assert_eq!(
+        (1, vec![]),
+        run_bytes(&["--textconv", "testdata/test.png"], b"")
+    );
+    assert_eq!(
+        2,
+        run_status(&["--textconv", "testdata/missing.txt"], b"").0
+    );
+    assert_eq!(2, run_status(&["--textconv", "-", "-"], b"").0);
+    assert_eq!(2, run_status(&["--textconv", "--json", "-"], b"").0);
+}
+
+#[test]
+fn verify() {
+    let tree = Tree::empty("verify");
+    let mut late_binary = b"text\n".repeat(400);
+    late_binary.extend_from_slice(b"\x00\x01\x02");
+    tree.write("late_binary.log", &late_binary);
+    tree.write("text.txt", &b"text\n".repeat(400));
+    tree.write("utf16.txt", b"\xFF\xFEt\x00e\x00x\x00t\x00");
+    tree.write("image.png", include_bytes!("../testdata/test.png"));
+    let root = tree.0.to_str().unwrap();
+    let path = tree.0.join("late_binary.log").display().to_string();
+
+    // Only the file with the late NULL byte is reported
+    let (status, stdout) = run_status(&["-r", "--verify", root], b"");
+    assert_eq!(1, status);
+    assert_eq!(
+        format!(
+            "{}: UTF-8 in the first 1024 bytes, binary with a NULL byte at offset 2000\n",
+            path
+        ),
+        stdout
+    );
+    assert_eq!(
+        format!(
+            "{{\"path\": \"{}\", \"window_type\": \"UTF-8\", \"full_type\": \"binary\", \
+             \"offset\": 2000, \"peek_size\": 1024}}\n",
+            path
+        ),
+        run(&["-r", "--verify", "--json", root], b"")
+    );
+
+    // A larger window sees the NULL byte
+    assert_eq!(
+        (0, String::new()),
+        run_status(&["-r", "--verify", "--bytes", "4096", root], b"")
+    );
+
+    // Standard input is scanned after the window, too
+    assert_eq!(
+        "<stdin>: UTF-8 in the first 8 bytes, binary with a NULL byte at offset 10\n",
+        run(&["--verify", "--bytes", "8"], b"0123456789\x00")
+    );
+    assert_eq!(0, run_status(&["--verify", "-"], b"text").0);
+    assert_eq!(2, run_status(&["--verify", "testdata/missing.txt"], b"").0);
+    assert_eq!(2, run_status(&["--verify", "--all", "-"], b"").0);
+}
+
+#[test]
+fn strip_bom() {
+    use std::time::{Duration, SystemTime};
+
+    let tree = Tree::empty("strip_bom");
+    tree.write("bom.txt", b"\xEF\xBB\xBFtext\n");
+    tree.write("plain.txt", b"text\n");
+    tree.write("utf16.txt", b"\xFF\xFEt\x00e\x00x\x00t\x00\n\x00");
+    tree.write("image.png", include_bytes!("../testdata/test.png"));
+    let path = |name: &str| tree.0.join(name);
+    let read = |name: &str| fs::read(path(name)).unwrap();
+    let args = |options: &[&str], names: &[&str]| -> Vec<std::ffi::OsString> {
+        let options = options.iter().map(|option| option.into());
+        options
+            .chain(names.iter().map(|name| path(name).into_os_string()))
+            .collect()
+    };
+
+    // The modification time of a file without a byte order mark must not change
+    let modified = SystemTime::now() - Duration::from_secs(3600);
+    fs::File::options()
+        .write(true)
+        .open(path("plain.txt"))
+        .unwrap()
+        .set_modified(modified)
+        .unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path("bom.txt"), fs::Permissions::from_mode(0o640)).unwrap();
+    }
+
+    let texts = ["bom.txt", "plain.txt", "utf16.txt"];
+    let (status, stdout) = run_status(&args(&["--strip-bom", "--dry-run"], &texts), b"");
+    assert_eq!(0, status);
+    assert_eq!(
+        format!(
+            "{}: would remove the UTF-8 byte order mark\n",
+            path("bom.txt").display()
+        ),
+        stdout
+    );
+    assert_eq!(b"\xEF\xBB\xBFtext\n", &read("bom.txt")[..]);
+
+    let (status, stdout) = run_status(
+        &args(&["--strip-bom", "--dry-run", "--transcode"], &texts),
+        b"",
+    );
+    assert_eq!(0, status);
+    assert_eq!(
+        format!(
+            "{}: would remove the UTF-8 byte order mark\n\
+             {}: would convert from UTF-16LE to UTF-8\n",
+            path("bom.txt").display(),
+            path("utf16.txt").display()
+        ),
+        stdout
+    );
+    assert_eq!(
+        b"\xFF\xFEt\x00e\x00x\x00t\x00\n\x00",
+        &read("utf16.txt")[..]
+    );
+
+    // Without --transcode, UTF-16 keeps its byte order mark
+    let (status, errors) = run_errors(&args(&["--strip-bom"], &texts));
+    assert_eq!(0, status);
+    assert!(
+        errors.contains("kept, the byte order mark of UTF-16LE is only removed with --transcode")
+    );
+    assert_eq!(b"text\n", &read("bom.txt")[..]);
+    assert_eq!(
+        b"\xFF\xFEt\x00e\x00x\x00t\x00\n\x00",
+        &read("utf16.txt")[..]
+    );
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(path("bom.txt")).unwrap().permissions().mode();
+        assert_eq!(0o640, mode & 0o777);
+    }
+
+    let (status, stdout) = run_status(&args(&["--strip-bom", "--transcode"], &texts), b"");
+    assert_eq!(0, status);
+    assert_eq!(
+        format!(
+            "{}: converted from UTF-16LE to UTF-8\n",
+            path("utf16.txt").display()
+        ),
+        stdout
+    );
+    assert_eq!(b"text\n", &read("utf16.txt")[..]);
+    assert_eq!(b"text\n", &read("plain.txt")[..]);
+    let metadata = fs::metadata(path("plain.txt")).unwrap();
+    assert_eq!(modified, metadata.modified().unwrap());
+
+    // A PNG that was passed by accident is refused, and left as it is
+    let (status, errors) = run_errors(&args(&["--strip-bom", "--transcode"], &["image.png"]));
+    assert_eq!(1, status);
+    assert!(errors.ends_with("image.png: refused, binary content is not rewritten\n"));
+    assert_eq!(
+        &include_bytes!("../testdata/test.png")[..],
+        &read("image.png")[..]
+    );
+    assert_eq!(2, run_status(&["--strip-bom", "-"], b"\xEF\xBB\xBFtext").0);
+    assert_eq!(2, run_status(&["--dry-run", "-"], b"").0);
+}
+
+#[test]
+fn records() {
+    use std::io::{BufRead, BufReader};
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    // The PNG is split into records at its newlines, like any other content. The example is
+    // built with the default features, whatever the features of this test, so the expected types
+    // are spelled out: the PNG signature is recognized in the third record (up to its `\r\n`)
+    let png = include_bytes!("../testdata/test.png");
+    let mut stream = b"first line\nsecond line\n".to_vec();
+    stream.extend_from_slice(png);
+    stream.extend_from_slice(b"\nlast line");
+    let expected = [
+        "1\tUTF-8",
+        "2\tUTF-8",
+        "3\tbinary",
+        "4\tUTF-8",
+        "5\tbinary",
+        "6\tUTF-8",
+    ];
+    let stream: &'static [u8] = stream.leak();
+    let (status, stdout) = run_status(&["--records"], stream);
+    assert_eq!(1, status);
+    assert_eq!(&expected[..], stdout.lines().collect::<Vec<_>>());
+
+    // Only the start of a long record is inspected, but it is still one record
+    assert_eq!(
+        "1\tUTF-8\n2\tbinary\n3\tUTF-8\n",
+        run(&["--records", "--bytes", "4"], b"text\x00\n\x00\nend\n")
+    );
+    assert_eq!(
+        "1\tUTF-8\n2\tUTF-8\n3\tbinary\n",
+        run(&["--records", "-z"], b"a\nb\0c\0\x89PNG\r\n\x1a\n\0")
+    );
+    assert_eq!("1\tUTF-8\n2\tUTF-8\n", run(&["--records=,"], b"a,b"));
+    assert_eq!("", run(&["--records"], b""));
+    assert_eq!(2, run_status(&["--records", "README.md"], b"").0);
+
+    // The types are printed as the records arrive, before the end of the input
+    let mut child = Command::new(example())
+        .arg("--records")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    let (sender, lines) = mpsc::channel();
+    let stdout = BufReader::new(child.stdout.take().unwrap());
+    thread::spawn(move || {
+        for line in stdout.lines() {
+            sender.send(line.unwrap()).unwrap();
+        }
+    });
+    stdin.write_all(b"text\n").unwrap();
+    stdin.flush().unwrap();
+    let timeout = Duration::from_secs(10);
+    assert_eq!("1\tUTF-8", lines.recv_timeout(timeout).unwrap());
+    stdin.write_all(b"\x00\x01\n").unwrap();
+    assert_eq!("2\tbinary", lines.recv_timeout(timeout).unwrap());
+    drop(stdin);
+    assert_eq!(1, child.wait().unwrap().code().unwrap());
+}
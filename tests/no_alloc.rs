@@ -0,0 +1,131 @@
+//! Checks that `inspect_reader_with_buf` and `inspect_detailed` do not allocate, with an
+//! allocator that counts the allocations of the current thread.
+
+extern crate content_inspector;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use content_inspector::{inspect_detailed, Inspector, Reason, Strictness};
+#[cfg(feature = "std")]
+use content_inspector::{inspect_reader_with_buf, ContentType};
+
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|allocations| allocations.set(allocations.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocations<T, F: FnOnce() -> T>(f: F) -> (T, usize) {
+    let before = ALLOCATIONS.with(Cell::get);
+    let result = f();
+    (result, ALLOCATIONS.with(Cell::get) - before)
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn inspect_reader_with_buf_does_not_allocate() {
+    let png = include_bytes!("../testdata/test.png");
+    let text = include_bytes!("../testdata/text_UTF-8.txt");
+    let utf16 = include_bytes!("../testdata/text_UTF-16LE-BOM.txt");
+
+    let counted = allocations(|| {
+        std::hint::black_box(Box::new(1));
+        ContentType::UTF_8
+    });
+    assert_eq!(1, counted.1);
+
+    let mut scratch = [0; 1024];
+    // Initializes the default settings
+    inspect_reader_with_buf(&b""[..], &mut scratch).unwrap();
+
+    let result = allocations(|| inspect_reader_with_buf(&png[..], &mut scratch).unwrap());
+    assert_eq!((ContentType::BINARY, 0), result);
+    let result = allocations(|| inspect_reader_with_buf(&text[..], &mut scratch).unwrap());
+    assert_eq!((ContentType::UTF_8, 0), result);
+    let mut tiny = [0; 4];
+    let result = allocations(|| inspect_reader_with_buf(&utf16[..], &mut tiny).unwrap());
+    let utf16_type = if cfg!(feature = "utf16") {
+        ContentType::UTF_16LE
+    } else {
+        ContentType::BINARY
+    };
+    assert_eq!((utf16_type, 0), result);
+
+    let inspector = Inspector::new()
+        .detect_control_characters(true)
+        .latin1_fallback(true)
+        .build()
+        .unwrap();
+    let result = allocations(|| {
+        inspector
+            .inspect_reader_with_buf(&text[..], &mut scratch)
+            .unwrap()
+    });
+    assert_eq!((ContentType::UTF_8, 0), result);
+}
+
+#[test]
+fn inspect_detailed_does_not_allocate() {
+    let png = include_bytes!("../testdata/test.png");
+    let text = include_bytes!("../testdata/text_UTF-8.txt");
+    let utf16 = include_bytes!("../testdata/text_UTF-16LE-BOM.txt");
+
+    // Initializes the default settings
+    inspect_detailed(b"");
+
+    let png_reason = if cfg!(feature = "magic-images") {
+        Reason::MagicNumber
+    } else {
+        Reason::NullBytes
+    };
+    let utf16_reason = if cfg!(feature = "utf16") {
+        Reason::ByteOrderMark
+    } else {
+        Reason::NullBytes
+    };
+    for &(buffer, reason) in &[
+        (&png[..], png_reason),
+        (&text[..], Reason::NoBinaryIndicators),
+        (&utf16[..], utf16_reason),
+        (&b"\x00\x01"[..], Reason::NullBytes),
+        (&b""[..], Reason::EmptyBuffer),
+    ] {
+        let (report, count) = allocations(|| inspect_detailed(buffer));
+        assert_eq!((reason, 0), (report.reason, count));
+    }
+
+    let inspector = Inspector::new()
+        .strictness(Strictness::Paranoid)
+        .detect_control_characters(true)
+        .min_printable_ratio(0.5)
+        .detect_utf16_without_bom(true)
+        .detect_ascii(true)
+        .magic_number(0, b"MAGIC")
+        .build()
+        .unwrap();
+    for &buffer in &[
+        &png[..],
+        &text[..],
+        &utf16[..],
+        &b"MAGIC"[..],
+        &b"a\xFF"[..],
+    ] {
+        let (report, count) = allocations(|| inspector.inspect_detailed(buffer));
+        assert_eq!(0, count, "{:?}", report);
+    }
+}
@@ -0,0 +1,60 @@
+//! Uses the API that is available without `std` from a `no_std` crate. With
+//! `cargo test --no-default-features`, the library itself is built without `std` for this test.
+
+#![no_std]
+
+extern crate alloc;
+extern crate content_inspector;
+
+use alloc::format;
+use alloc::vec::Vec;
+
+use content_inspector::{
+    bom_bytes, decode_lossy, inspect, inspect_const, inspect_detailed, set_default_inspector,
+    validate, ContentType, Inspector, Reason,
+};
+
+const TEXT: &[u8] = include_bytes!("../testdata/text_UTF-8.txt");
+const TEXT_TYPE: ContentType = inspect_const(TEXT);
+const _: () = assert!(TEXT_TYPE.is_text());
+
+#[test]
+fn inspect_without_std() {
+    assert_eq!(ContentType::UTF_8, inspect(b"Hello"));
+    assert_eq!(ContentType::UTF_8, TEXT_TYPE);
+    assert_eq!(TEXT_TYPE, inspect(TEXT));
+    assert_eq!(ContentType::BINARY, inspect(b"\x00\x01"));
+    if cfg!(feature = "utf16") {
+        assert_eq!(ContentType::UTF_16LE, inspect(b"\xFF\xFEH\x00"));
+    }
+
+    let report = inspect_detailed(b"\xEF\xBB\xBFHello");
+    assert_eq!(ContentType::UTF_8_BOM, report.content_type);
+    assert_eq!(Reason::ByteOrderMark, report.reason);
+    assert!(set_default_inspector(Inspector::default()).is_err());
+
+    assert!(ContentType::BINARY.is_binary());
+    assert!(ContentType::UTF_32BE.is_text());
+    assert_eq!("UTF-16BE", format!("{}", ContentType::UTF_16BE));
+    assert_eq!(b"\xFE\xFF", bom_bytes(ContentType::UTF_16BE));
+
+    let inspector = Inspector::new()
+        .detect_control_characters(true)
+        .latin1_fallback(true)
+        .build()
+        .unwrap();
+    if cfg!(feature = "legacy-encodings") {
+        assert_eq!(ContentType::LATIN_1, inspector.inspect(b"Gr\xFC\xDFe"));
+    }
+    let mut streaming = inspector.streaming();
+    for chunk in TEXT.chunks(7) {
+        streaming.push(chunk);
+    }
+    assert_eq!(ContentType::UTF_8, streaming.finalize());
+
+    let text: Vec<u8> = decode_lossy(b"\xFF\xFEH\x00i\x00", ContentType::UTF_16LE)
+        .bytes()
+        .collect();
+    assert_eq!(b"Hi", &text[..]);
+    assert!(validate(TEXT, ContentType::UTF_8).is_ok());
+}
@@ -0,0 +1,226 @@
+//! Property-based tests of the documented invariants of the verdicts, against the UTF-8
+//! validation of the standard library and the UTF-16 decoders of `encoding_rs`. The buffers are
+//! arbitrary bytes, and valid text (in every encoding, with or without a byte order mark) that is
+//! mutated: bytes are flipped, it is truncated (also in the middle of sequences), and byte order
+//! marks are prepended. Failures are shrunk to minimal counterexamples by `proptest`.
+//!
+//! The invariants, for the first 1024 bytes (the window of the default inspector):
+//!
+//! 1. A `UTF_8` or `UTF_8_BOM` verdict with `Strictness::Strict` means that the window (after the
+//!    byte order mark) is valid UTF-8, except for an incomplete sequence at its end, and with
+//!    `Strictness::Paranoid` that the whole buffer is valid UTF-8.
+//! 2. A `UTF_16LE` or `UTF_16BE` verdict for a byte order mark with `Strictness::Strict` means
+//!    that `encoding_rs` decodes the window after it without errors, except for an incomplete
+//!    code unit or surrogate pair at its end, and with `Strictness::Paranoid` that it decodes the
+//!    whole buffer. UTF-16 without a byte order mark (`detect_utf16_without_bom`) is a guess from
+//!    the positions of the zero bytes, which is not validated in any strictness.
+//! 3. A verdict for a byte order mark (`Reason::ByteOrderMark`) means that the buffer starts with
+//!    the byte order mark of the type, and the `bom_length` is its length, for every strictness.
+//! 4. A `BINARY` verdict of the default inspector means that there is a NULL byte in the window
+//!    (at the `first_null_offset`, which the report has), or the magic number of a binary format
+//!    at the start (the report has the `binary_format`).
+
+extern crate content_inspector;
+
+use std::str;
+
+use encoding_rs::{DecoderResult, Encoding, UTF_16BE, UTF_16LE};
+use proptest::prelude::*;
+
+use content_inspector::{bom_bytes, ContentType, Inspector, Reason, Strictness};
+
+const WINDOW: usize = 1024;
+
+/// The byte order marks that are prepended to buffers.
+const BOMS: &[&[u8]] = &[
+    b"\xEF\xBB\xBF",
+    b"\xFF\xFE",
+    b"\xFE\xFF",
+    b"\xFF\xFE\x00\x00",
+    b"\x00\x00\xFE\xFF",
+];
+
+/// A change of valid text.
+#[derive(Clone, Debug)]
+enum Mutation {
+    /// Flip the bits of the mask in the byte at the index (modulo the length).
+    Flip(usize, u8),
+    /// Truncate at the index (modulo the length), which can be in the middle of a sequence.
+    Truncate(usize),
+    /// Prepend one of the `BOMS`.
+    PrependBom(usize),
+}
+
+fn mutation() -> impl Strategy<Value = Mutation> {
+    prop_oneof![
+        (any::<usize>(), 1..=255u8).prop_map(|(index, mask)| Mutation::Flip(index, mask)),
+        any::<usize>().prop_map(Mutation::Truncate),
+        (0..BOMS.len()).prop_map(Mutation::PrependBom),
+    ]
+}
+
+/// The text in one of the encodings (0: UTF-8, 1 and 2: UTF-16LE and BE, 3 and 4: UTF-32LE and
+/// BE), with its byte order mark if `bom`.
+fn encode(text: &str, encoding: usize, bom: bool) -> Vec<u8> {
+    let mut buffer = vec![];
+    if bom {
+        buffer.extend_from_slice(BOMS[encoding]);
+    }
+    match encoding {
+        0 => buffer.extend_from_slice(text.as_bytes()),
+        1 | 2 => {
+            for unit in text.encode_utf16() {
+                let bytes = if encoding == 1 {
+                    unit.to_le_bytes()
+                } else {
+                    unit.to_be_bytes()
+                };
+                buffer.extend_from_slice(&bytes);
+            }
+        }
+        _ => {
+            for c in text.chars() {
+                let bytes = if encoding == 3 {
+                    (c as u32).to_le_bytes()
+                } else {
+                    (c as u32).to_be_bytes()
+                };
+                buffer.extend_from_slice(&bytes);
+            }
+        }
+    }
+    buffer
+}
+
+fn mutate(mut buffer: Vec<u8>, mutations: &[Mutation]) -> Vec<u8> {
+    for mutation in mutations {
+        match *mutation {
+            Mutation::Flip(index, mask) if !buffer.is_empty() => {
+                let index = index % buffer.len();
+                buffer[index] ^= mask;
+            }
+            Mutation::Flip(..) => {}
+            Mutation::Truncate(index) => buffer.truncate(index % (buffer.len() + 1)),
+            Mutation::PrependBom(bom) => {
+                buffer.splice(0..0, BOMS[bom].iter().copied());
+            }
+        }
+    }
+    buffer
+}
+
+/// Arbitrary bytes, or mutated valid text, which may be longer than the window.
+fn buffer() -> impl Strategy<Value = Vec<u8>> {
+    let text = (
+        "\\PC{0,400}",
+        0..5usize,
+        any::<bool>(),
+        prop::collection::vec(mutation(), 0..4),
+    )
+        .prop_map(|(text, encoding, bom, mutations)| {
+            mutate(encode(&text, encoding, bom), &mutations)
+        });
+    prop_oneof![prop::collection::vec(any::<u8>(), 0..2048), text]
+}
+
+fn window(buffer: &[u8]) -> &[u8] {
+    &buffer[..buffer.len().min(WINDOW)]
+}
+
+/// Whether the bytes are valid UTF-8, except for an incomplete sequence at the end if `complete`
+/// is not set.
+fn is_utf8(bytes: &[u8], complete: bool) -> bool {
+    match str::from_utf8(bytes) {
+        Ok(_) => true,
+        // An error without a length is an incomplete sequence at the end
+        Err(error) => !complete && error.error_len().is_none(),
+    }
+}
+
+/// Whether `encoding_rs` decodes the bytes without errors, with an incomplete sequence at the end
+/// being an error only if `complete` is set.
+fn decodes(encoding: &'static Encoding, bytes: &[u8], complete: bool) -> bool {
+    let mut decoder = encoding.new_decoder_without_bom_handling();
+    let mut output = String::with_capacity(decoder.max_utf8_buffer_length(bytes.len()).unwrap());
+    let (result, _) = decoder.decode_to_string_without_replacement(bytes, &mut output, complete);
+    result == DecoderResult::InputEmpty
+}
+
+fn inspector(strictness: Strictness) -> Inspector {
+    Inspector::new()
+        .strictness(strictness)
+        .detect_utf16_without_bom(true)
+        .build()
+        .unwrap()
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(1024))]
+
+    /// Invariant 1.
+    #[test]
+    fn utf8_verdicts_validate(buffer in buffer()) {
+        for &(strictness, complete) in &[(Strictness::Strict, false), (Strictness::Paranoid, true)] {
+            let report = inspector(strictness).inspect_detailed(&buffer);
+            let content = if complete { &buffer[..] } else { window(&buffer) };
+            match report.content_type {
+                ContentType::UTF_8 => prop_assert!(is_utf8(content, complete), "{:?}", report),
+                ContentType::UTF_8_BOM => {
+                    prop_assert!(is_utf8(&content[3..], complete), "{:?}", report)
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Invariant 2.
+    #[test]
+    fn utf16_verdicts_decode(buffer in buffer()) {
+        for &(strictness, complete) in &[(Strictness::Strict, false), (Strictness::Paranoid, true)] {
+            let report = inspector(strictness).inspect_detailed(&buffer);
+            let content = if complete { &buffer[..] } else { window(&buffer) };
+            let encoding = match report.content_type {
+                _ if report.reason != Reason::ByteOrderMark => continue,
+                ContentType::UTF_16LE => UTF_16LE,
+                ContentType::UTF_16BE => UTF_16BE,
+                _ => continue,
+            };
+            let content = &content[report.bom_length..];
+            prop_assert!(decodes(encoding, content, complete), "{:?}", report);
+        }
+    }
+
+    /// Invariant 3.
+    #[test]
+    fn bom_verdicts_start_with_bom(buffer in buffer()) {
+        for &strictness in &[
+            Strictness::Fast,
+            Strictness::Standard,
+            Strictness::Strict,
+            Strictness::Paranoid,
+        ] {
+            let report = inspector(strictness).inspect_detailed(&buffer);
+            if report.reason == Reason::ByteOrderMark {
+                let bom = bom_bytes(report.content_type);
+                prop_assert!(!bom.is_empty(), "{:?}", report);
+                prop_assert!(buffer.starts_with(bom), "{:?}", report);
+                prop_assert_eq!(bom.len(), report.bom_length);
+            }
+        }
+    }
+
+    /// Invariant 4.
+    #[test]
+    fn binary_verdicts_have_evidence(buffer in buffer()) {
+        let report = Inspector::default().inspect_detailed(&buffer);
+        if report.content_type == ContentType::BINARY {
+            match report.first_null_offset {
+                Some(offset) => {
+                    prop_assert!(offset < WINDOW, "{:?}", report);
+                    prop_assert_eq!(0, buffer[offset]);
+                }
+                None => prop_assert!(report.binary_format.is_some(), "{:?}", report),
+            }
+        }
+    }
+}
@@ -0,0 +1,319 @@
+//! The classification of the public test corpora of uchardet and charset-normalizer, which have
+//! small files of real text in many encodings with the encoding as the label. It needs the
+//! `public-corpora` feature, and downloads the corpora with `testdata/download_public_corpora.py`
+//! into `testdata/public` (ignored by git) on the first run, or uses the directory in
+//! `PUBLIC_CORPORA` with the same layout:
+//!
+//! ```text
+//! cargo test --features public-corpora --test public_corpora
+//! ```
+//!
+//! The labels are mapped onto the coarser categories of this crate (see `Category`), and the
+//! files are classified by a strict inspector that also detects UTF-16 without a byte order mark
+//! and falls back to Latin-1. The test fails if the accuracy of a category is below its
+//! threshold, or if a file of `testdata/public_corpora_overrides.tsv` is not classified as listed
+//! there (the files of that list are pinned to their result, and not counted). A report of every
+//! file is written to `public_corpora.tsv` in the temporary directory of the target.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use content_inspector::{ContentType, Inspector, Strictness};
+
+/// The corpora in the directory, with the way their files are labeled.
+const CORPORA: [(&str, Labels); 2] = [
+    ("uchardet", Labels::FileName),
+    ("char-dataset", Labels::Directory),
+];
+
+#[derive(Clone, Copy)]
+enum Labels {
+    /// `<language>/<label>.txt`
+    FileName,
+    /// `<label>/<file>`
+    Directory,
+}
+
+/// What the label of a file says about its classification by this crate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Category {
+    /// UTF-8 (and ASCII, which is a subset): ASCII, UTF-8 or UTF-8 with a BOM.
+    Utf8,
+    /// UTF-16 in either byte order, with or without a BOM: UTF-16LE or UTF-16BE.
+    Utf16,
+    /// UTF-32 in either byte order: UTF-32LE or UTF-32BE.
+    Utf32,
+    /// A legacy encoding with bytes above 0x7F (single-byte or multi-byte, like Shift_JIS): not
+    /// UTF-8, and not binary. Files with only ASCII bytes are skipped, as they are valid UTF-8.
+    Legacy,
+    /// An encoding with 7-bit bytes only (like ISO-2022-JP or UTF-7): text.
+    SevenBit,
+}
+
+/// The minimum share of the files of a category that have to be classified as expected.
+const THRESHOLDS: [(Category, f64); 5] = [
+    (Category::Utf8, 0.98),
+    (Category::Utf16, 0.90),
+    (Category::Utf32, 0.90),
+    (Category::Legacy, 0.95),
+    (Category::SevenBit, 0.90),
+];
+
+/// The minimum share of all files (of all categories) that have to be classified as expected.
+const TOTAL_THRESHOLD: f64 = 0.95;
+
+/// The category of a label of the corpora, or `None` if it is unknown (those files are only
+/// reported). The labels are lowercase, with `-` instead of `_`.
+fn category(label: &str) -> Option<Category> {
+    const LEGACY: [&str; 15] = [
+        "iso-8859",
+        "iso8859",
+        "windows-",
+        "cp",
+        "koi8",
+        "mac",
+        "ibm",
+        "latin",
+        "tis-620",
+        "viscii",
+        "shift-jis",
+        "euc-",
+        "big5",
+        "gb",
+        "johab",
+    ];
+    match label {
+        "ascii" | "us-ascii" | "utf-8" | "utf8" | "utf-8-sig" => Some(Category::Utf8),
+        "utf-16" | "utf-16le" | "utf-16be" => Some(Category::Utf16),
+        "utf-32" | "utf-32le" | "utf-32be" => Some(Category::Utf32),
+        "utf-7" | "hz" | "hz-gb-2312" => Some(Category::SevenBit),
+        _ if label.starts_with("iso-2022") || label.starts_with("iso2022") => {
+            Some(Category::SevenBit)
+        }
+        _ if LEGACY.iter().any(|prefix| label.starts_with(prefix)) => Some(Category::Legacy),
+        _ => None,
+    }
+}
+
+fn expected(category: Category, content_type: ContentType) -> bool {
+    use ContentType::*;
+    match category {
+        Category::Utf8 => [ASCII, UTF_8, UTF_8_BOM].contains(&content_type),
+        Category::Utf16 => [UTF_16LE, UTF_16BE].contains(&content_type),
+        Category::Utf32 => [UTF_32LE, UTF_32BE].contains(&content_type),
+        Category::Legacy => content_type.is_text() && ![UTF_8, UTF_8_BOM].contains(&content_type),
+        Category::SevenBit => content_type.is_text(),
+    }
+}
+
+/// A file of a corpus, with its path relative to the directory of the corpora.
+struct File {
+    path: String,
+    label: String,
+}
+
+fn files(directory: &Path) -> Vec<File> {
+    let mut files = vec![];
+    for &(corpus, labels) in &CORPORA {
+        let root = directory.join(corpus);
+        let mut groups: Vec<PathBuf> = fs::read_dir(&root)
+            .unwrap_or_else(|error| panic!("{}: {}", root.display(), error))
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.is_dir())
+            .collect();
+        groups.sort();
+        for group in groups {
+            let mut paths: Vec<PathBuf> = fs::read_dir(&group)
+                .unwrap()
+                .map(|entry| entry.unwrap().path())
+                .filter(|path| path.is_file())
+                .collect();
+            paths.sort();
+            for path in paths {
+                let label = match labels {
+                    Labels::FileName => path.file_name().unwrap().to_string_lossy(),
+                    Labels::Directory => group.file_name().unwrap().to_string_lossy(),
+                };
+                // "iso-8859-1.txt" or "windows-1252.2.txt"
+                let label = label.split('.').next().unwrap().to_lowercase();
+                let relative = path.strip_prefix(directory).unwrap();
+                let components: Vec<_> = relative
+                    .components()
+                    .map(|component| component.as_os_str().to_string_lossy())
+                    .collect();
+                files.push(File {
+                    path: components.join("/"),
+                    label: label.replace('_', "-"),
+                });
+            }
+        }
+    }
+    files
+}
+
+/// The directory of the corpora, downloaded if necessary.
+fn corpora() -> PathBuf {
+    if let Some(directory) = env::var_os("PUBLIC_CORPORA") {
+        return PathBuf::from(directory);
+    }
+    let testdata = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata");
+    let directory = testdata.join("public");
+    if !directory.is_dir() {
+        let status = Command::new("python3")
+            .arg(testdata.join("download_public_corpora.py"))
+            .status()
+            .expect("python3 is needed to download the corpora");
+        assert!(
+            status.success(),
+            "The download of the corpora failed (it needs git and network access), set \
+             PUBLIC_CORPORA to a directory with the corpora instead"
+        );
+    }
+    directory
+}
+
+/// The classifications of `testdata/public_corpora_overrides.tsv`, by path.
+fn overrides() -> BTreeMap<String, String> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata/public_corpora_overrides.tsv");
+    fs::read_to_string(path)
+        .unwrap()
+        .lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let fields: Vec<&str> = line.split('\t').collect();
+            assert!(fields.len() >= 2, "invalid override: {:?}", line);
+            (fields[0].to_string(), fields[1].to_string())
+        })
+        .collect()
+}
+
+#[derive(Clone, Copy, Default)]
+struct Counts {
+    expected: usize,
+    total: usize,
+}
+
+impl Counts {
+    fn accuracy(&self) -> f64 {
+        if self.total == 0 {
+            1.0
+        } else {
+            self.expected as f64 / self.total as f64
+        }
+    }
+}
+
+#[test]
+fn public_corpora() {
+    let inspector = Inspector::new()
+        .strictness(Strictness::Strict)
+        .detect_utf16_without_bom(true)
+        .latin1_fallback(true)
+        .detect_ascii(true)
+        .build()
+        .unwrap();
+    let directory = corpora();
+    let files = files(&directory);
+    assert!(!files.is_empty(), "No files in {}", directory.display());
+    let mut overrides = overrides();
+
+    let mut counts: BTreeMap<Category, Counts> = BTreeMap::new();
+    let mut skipped = BTreeMap::new();
+    let mut wrong_overrides = vec![];
+    let mut report = String::from("# file\tlabel\tcategory\ttype\treason\tresult\n");
+    for file in &files {
+        let buffer = fs::read(directory.join(&file.path)).unwrap();
+        let inspection = inspector.inspect_detailed(&buffer);
+        let content_type = inspection.content_type;
+        let category = category(&file.label);
+        let result = if let Some(pinned) = overrides.remove(&file.path) {
+            if pinned != content_type.to_string() {
+                wrong_overrides.push(format!("{}: {} -> {}", file.path, pinned, content_type));
+            }
+            "pinned"
+        } else {
+            match category {
+                None => {
+                    *skipped.entry("unknown label").or_insert(0) += 1;
+                    "unknown label"
+                }
+                Some(Category::Legacy) if buffer.is_ascii() => {
+                    *skipped
+                        .entry("legacy encoding with ASCII only")
+                        .or_insert(0) += 1;
+                    "ASCII only"
+                }
+                Some(category) => {
+                    let counts = counts.entry(category).or_default();
+                    counts.total += 1;
+                    if expected(category, content_type) {
+                        counts.expected += 1;
+                        "expected"
+                    } else {
+                        "unexpected"
+                    }
+                }
+            }
+        };
+        report.push_str(&format!(
+            "{}\t{}\t{}\t{}\t{:?}\t{}\n",
+            file.path,
+            file.label,
+            category.map_or("-".to_string(), |category| format!("{:?}", category)),
+            content_type,
+            inspection.reason,
+            result
+        ));
+    }
+
+    let report_path = Path::new(env!("CARGO_TARGET_TMPDIR")).join("public_corpora.tsv");
+    fs::write(&report_path, report).unwrap();
+    let mut total = Counts::default();
+    let mut below = vec![];
+    for &(category, threshold) in &THRESHOLDS {
+        let counts = counts.get(&category).copied().unwrap_or_default();
+        eprintln!(
+            "{:?}: {} of {} files ({:.1} %, threshold {:.1} %)",
+            category,
+            counts.expected,
+            counts.total,
+            100.0 * counts.accuracy(),
+            100.0 * threshold
+        );
+        if counts.accuracy() < threshold {
+            below.push(format!("{:?}", category));
+        }
+        total.expected += counts.expected;
+        total.total += counts.total;
+    }
+    for (reason, count) in &skipped {
+        eprintln!("Skipped ({}): {} files", reason, count);
+    }
+    eprintln!(
+        "All files: {:.1} %, see {}",
+        100.0 * total.accuracy(),
+        report_path.display()
+    );
+    if total.accuracy() < TOTAL_THRESHOLD {
+        below.push("all files".to_string());
+    }
+
+    assert!(
+        wrong_overrides.is_empty(),
+        "Files not classified as in the overrides:\n{}",
+        wrong_overrides.join("\n")
+    );
+    assert!(
+        overrides.is_empty(),
+        "Overrides of files that are not in the corpora:\n{}",
+        overrides.keys().cloned().collect::<Vec<_>>().join("\n")
+    );
+    assert!(
+        below.is_empty(),
+        "Accuracy below the threshold: {}",
+        below.join(", ")
+    );
+}
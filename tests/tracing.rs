@@ -0,0 +1,157 @@
+//! The debug events of the detection (with the `tracing` feature), recorded by a
+//! `tracing_subscriber` layer.
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::Registry;
+
+use content_inspector::{inspect, Inspector, Strictness};
+
+#[derive(Debug, Default)]
+struct Recorded {
+    message: String,
+    fields: Vec<(&'static str, String)>,
+}
+
+impl Recorded {
+    fn field(&self, name: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|&&(field, _)| field == name)
+            .map(|(_, value)| &value[..])
+    }
+}
+
+impl Visit for Recorded {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.message = format!("{:?}", value);
+        } else {
+            self.fields.push((field.name(), format!("{:?}", value)));
+        }
+    }
+}
+
+struct Recorder(Arc<Mutex<Vec<Recorded>>>);
+
+impl<S: Subscriber> Layer<S> for Recorder {
+    fn on_event(&self, event: &Event<'_>, _: Context<'_, S>) {
+        assert_eq!(Level::DEBUG, *event.metadata().level());
+        let mut recorded = Recorded::default();
+        event.record(&mut recorded);
+        self.0.lock().unwrap().push(recorded);
+    }
+}
+
+/// The events of `f`, which all have to carry the length of the buffer.
+fn events<F: FnOnce()>(buffer_length: usize, f: F) -> Vec<Recorded> {
+    let events = Arc::new(Mutex::new(vec![]));
+    let subscriber = Registry::default().with(Recorder(events.clone()));
+    tracing::subscriber::with_default(subscriber, f);
+
+    let events = Arc::try_unwrap(events).unwrap().into_inner().unwrap();
+    let length = buffer_length.to_string();
+    for event in &events {
+        assert_eq!(
+            Some(&length[..]),
+            event.field("buffer_length"),
+            "{:?}",
+            event
+        );
+    }
+    events
+}
+
+fn find<'a>(events: &'a [Recorded], message: &str) -> &'a Recorded {
+    events
+        .iter()
+        .find(|event| event.message == message)
+        .unwrap_or_else(|| panic!("no event {:?} in {:?}", message, events))
+}
+
+#[test]
+fn png_fixture() {
+    let buffer = include_bytes!("../testdata/test.png");
+    let events = events(buffer.len(), || {
+        inspect(buffer);
+    });
+
+    let magic = find(&events, "magic number matched");
+    assert_eq!(Some("Some(Png)"), magic.field("binary_format"));
+
+    let result = find(&events, "content type determined");
+    assert_eq!(Some("binary"), result.field("content_type"));
+    assert_eq!(Some("MagicNumber"), result.field("reason"));
+    assert!(result.field("bytes_examined").is_some());
+    assert!(events
+        .iter()
+        .all(|event| event.message != "NULL bytes counted"));
+}
+
+#[test]
+fn utf8_fixture() {
+    let buffer = include_bytes!("../testdata/text_UTF-8.txt");
+    let events = events(buffer.len(), || {
+        inspect(buffer);
+    });
+
+    // The fixture is smaller than the scanned window
+    let length = buffer.len().to_string();
+    let nulls = find(&events, "NULL bytes counted");
+    assert_eq!(Some("0"), nulls.field("null_bytes"));
+    assert_eq!(Some("None"), nulls.field("first_null_offset"));
+    assert_eq!(Some(&length[..]), nulls.field("scanned_bytes"));
+
+    let result = find(&events, "content type determined");
+    assert_eq!(Some("UTF-8"), result.field("content_type"));
+    assert_eq!(Some("NoBinaryIndicators"), result.field("reason"));
+    assert_eq!(Some(&length[..]), result.field("bytes_examined"));
+}
+
+#[test]
+fn other_decisions() {
+    let buffer = b"text\x00";
+    let recorded = events(buffer.len(), || {
+        inspect(buffer);
+    });
+    let nulls = find(&recorded, "NULL bytes counted");
+    assert_eq!(Some("1"), nulls.field("null_bytes"));
+    assert_eq!(Some("Some(4)"), nulls.field("first_null_offset"));
+
+    let buffer = b"\xFF\xFEH\x00";
+    let recorded = events(buffer.len(), || {
+        inspect(buffer);
+    });
+    let bom = find(&recorded, "byte order mark matched");
+    assert_eq!(Some("UTF-16LE"), bom.field("content_type"));
+    assert_eq!(Some("2"), bom.field("bom_length"));
+
+    let strict = Inspector::new()
+        .strictness(Strictness::Strict)
+        .build()
+        .unwrap();
+    let buffer = b"\xEF\xBB\xBFGr\xFC\xDFe";
+    let recorded = events(buffer.len(), || {
+        strict.inspect(buffer);
+    });
+    let rejected = find(
+        &recorded,
+        "byte order mark rejected, the content is not valid in its encoding",
+    );
+    assert_eq!(Some("UTF-8-BOM"), rejected.field("content_type"));
+    assert_eq!(Some("Some(5)"), rejected.field("invalid_utf8_offset"));
+
+    let latin1 = Inspector::new().latin1_fallback(true).build().unwrap();
+    let buffer = b"Gr\xFC\xDFe";
+    let recorded = events(buffer.len(), || {
+        latin1.inspect(buffer);
+    });
+    let fallback = find(&recorded, "invalid UTF-8, falling back to Latin-1");
+    assert_eq!(Some("Some(2)"), fallback.field("invalid_utf8_offset"));
+    let result = find(&recorded, "content type determined");
+    assert_eq!(Some("Latin-1"), result.field("content_type"));
+}
@@ -0,0 +1,67 @@
+//! Tests of the JavaScript bindings in `src/wasm.rs`, which only run on `wasm32` (with
+//! `wasm-bindgen-test-runner` as the runner, or `wasm-pack test --node -- --features wasm`):
+//!
+//! ```text
+//! CARGO_TARGET_WASM32_UNKNOWN_UNKNOWN_RUNNER=wasm-bindgen-test-runner \
+//!     cargo test --target wasm32-unknown-unknown --features wasm --test wasm
+//! ```
+//!
+//! They run in Node.js, or in a headless browser with `WASM_BINDGEN_USE_BROWSER=1`.
+
+#![cfg(target_arch = "wasm32")]
+
+use js_sys::Reflect;
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+use content_inspector::wasm::{inspect, inspect_detailed, is_text};
+
+fn field(object: &JsValue, key: &str) -> JsValue {
+    Reflect::get(object, &JsValue::from_str(key)).unwrap()
+}
+
+#[wasm_bindgen_test]
+fn utf8_string() {
+    let data = "Grüße, 世界".as_bytes();
+    assert_eq!(JsValue::from_str("UTF-8"), inspect(data));
+    assert!(is_text(data));
+
+    let report = inspect_detailed(data).into();
+    assert_eq!(JsValue::from_str("UTF-8"), field(&report, "contentType"));
+    assert_eq!(
+        JsValue::from_str("NoBinaryIndicators"),
+        field(&report, "reason")
+    );
+    assert_eq!(JsValue::from_f64(0.0), field(&report, "nullBytes"));
+    assert!(field(&report, "firstNullOffset").is_undefined());
+    assert!(field(&report, "binaryFormat").is_undefined());
+}
+
+#[wasm_bindgen_test]
+#[cfg(feature = "utf16")]
+fn utf16le_sample() {
+    let data = b"\xFF\xFEH\x00i\x00";
+    assert_eq!(JsValue::from_str("UTF-16LE"), inspect(data));
+    assert!(is_text(data));
+
+    let report = inspect_detailed(data).into();
+    assert_eq!(JsValue::from_str("ByteOrderMark"), field(&report, "reason"));
+    assert_eq!(JsValue::from_f64(2.0), field(&report, "bomLength"));
+}
+
+#[wasm_bindgen_test]
+fn png_header() {
+    let data = b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR";
+    assert_eq!(JsValue::from_str("binary"), inspect(data));
+    assert!(!is_text(data));
+
+    let report = inspect_detailed(data).into();
+    assert_eq!(JsValue::from_str("binary"), field(&report, "detectedType"));
+    if cfg!(feature = "magic-images") {
+        assert_eq!(JsValue::from_str("MagicNumber"), field(&report, "reason"));
+        assert_eq!(JsValue::from_str("Png"), field(&report, "binaryFormat"));
+    } else {
+        assert_eq!(JsValue::from_str("NullBytes"), field(&report, "reason"));
+        assert_eq!(JsValue::from_f64(8.0), field(&report, "firstNullOffset"));
+    }
+}